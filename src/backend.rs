@@ -1,16 +1,22 @@
-use crate::cache::Cache;
-use crate::config::{RetentionPolicyConfig, SpamConfig};
-use crate::jmap::client::JmapClient;
-use crate::jmap::types::{Email, Mailbox};
+use crate::cache::{Cache, SeenAddress, SessionState};
+use crate::compose;
+use crate::config::{AccountConfig, RetentionPolicyConfig, SpamConfig};
+use crate::jmap::client::{HttpOptions, JmapClient, JmapError};
+use crate::jmap::types::{Email, Mailbox, OutgoingAttachment, Quota, VacationResponse};
+use crate::mute::{self, MutedThreads};
+use crate::offline_search::SearchIndex;
 use crate::rules::{self, CompiledRule};
+use crate::snooze;
 use crate::spam::{self, SpamModel};
+use crate::tui::graphics::{self, EncodedImage, ImageProtocol};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::{mpsc, Arc};
+use std::os::unix::fs::DirBuilderExt;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant};
 
 /// Commands sent from the UI thread to the backend thread.
 pub enum BackendCommand {
@@ -33,9 +39,21 @@ pub enum BackendCommand {
         received_after: Option<String>,
         received_before: Option<String>,
     },
+    SearchAllEmails {
+        page_size: u32,
+        position: u32,
+        search_query: Option<String>,
+        received_after: Option<String>,
+        received_before: Option<String>,
+    },
     GetEmail {
         id: String,
     },
+    /// Fetch several emails with bodies; cache misses go to the server in
+    /// `Email/get` chunks.
+    GetEmails {
+        ids: Vec<String>,
+    },
     GetEmailForReply {
         id: String,
     },
@@ -52,11 +70,36 @@ pub enum BackendCommand {
         id: String,
         flagged: bool,
     },
+    /// Set or clear any keyword, e.g. `$label1` or a custom one.
+    SetKeyword {
+        op_id: u64,
+        id: String,
+        keyword: String,
+        value: bool,
+    },
     MoveEmail {
         op_id: u64,
         id: String,
         to_mailbox_id: String,
     },
+    /// Report emails as spam (or not spam): set the `spam_report_keywords`
+    /// and move them to `to_mailbox_id` as one change with one undo entry.
+    /// Answered with an `EmailMutation` for the first id.
+    ReportSpam {
+        op_id: u64,
+        ids: Vec<String>,
+        spam: bool,
+        to_mailbox_id: String,
+    },
+    /// Move an email to the snooze folder (`[mail].snooze_folder`, created
+    /// when missing) and record when it should return to the inbox (seconds
+    /// since the Unix epoch).
+    SnoozeEmail {
+        op_id: u64,
+        id: String,
+        folder: String,
+        wake_at: u64,
+    },
     MoveThread {
         op_id: u64,
         thread_id: String,
@@ -81,6 +124,31 @@ pub enum BackendCommand {
         mailbox_id: String,
         mailbox_name: String,
     },
+    /// List what emptying a mailbox would destroy, fresh from the server,
+    /// for the confirmation prompt.
+    PreviewEmptyMailbox {
+        mailbox_id: String,
+        mailbox_name: String,
+    },
+    /// Permanently destroy the messages `PreviewEmptyMailbox` listed in a
+    /// mailbox (Trash/Junk); mail that arrived since is left alone.
+    EmptyMailbox {
+        mailbox_id: String,
+        mailbox_name: String,
+        ids: Vec<String>,
+    },
+    /// Read back the mailbox list position saved by `SaveSession`.
+    LoadSession,
+    SaveSession {
+        state: SessionState,
+    },
+    /// Read back the email-list searches recorded by `RecordSearch`.
+    LoadSearchHistory,
+    RecordSearch {
+        query: String,
+    },
+    /// Addresses counted from cached mail, for completion.
+    GetSeenAddresses,
     GetEmailRawHeaders {
         id: String,
     },
@@ -89,10 +157,46 @@ pub enum BackendCommand {
     GetEmailRaw {
         id: String,
     },
+    /// Save an attachment into `dir` (see [`download_dir`]).
     DownloadAttachment {
         blob_id: String,
         name: String,
         content_type: String,
+        dir: std::path::PathBuf,
+    },
+    /// Download an attachment into a private temporary directory so it can be
+    /// opened; it is removed when tmc exits. The plain download path keeps
+    /// its copy.
+    OpenAttachment {
+        blob_id: String,
+        name: String,
+        content_type: String,
+    },
+    /// Save several attachments (from `attachment_downloads`) into `dir`.
+    DownloadAttachments {
+        attachments: Vec<AttachmentDownload>,
+        dir: std::path::PathBuf,
+    },
+    /// Fetch an inline image and encode it for `protocol`, so the UI thread
+    /// never waits on the download or on `img2sixel`.
+    FetchInlineImage {
+        blob_id: String,
+        content_type: String,
+        protocol: ImageProtocol,
+    },
+    /// Submit an edited draft via JMAP EmailSubmission. Without attachments
+    /// it is sent as a plain RFC 5322 message; otherwise each file is uploaded
+    /// and referenced from an `Email/set` creation.
+    SendEmail {
+        draft: compose::ParsedDraft,
+        attachments: Vec<OutgoingAttachment>,
+    },
+    /// Store an unsent draft in the `folder` mailbox (`[mail].drafts_folder`)
+    /// with the `$draft` keyword.
+    SaveDraft {
+        draft: compose::ParsedDraft,
+        attachments: Vec<OutgoingAttachment>,
+        folder: String,
     },
     PreviewRetentionExpiry {
         policies: Vec<RetentionPolicyConfig>,
@@ -100,15 +204,27 @@ pub enum BackendCommand {
     ExecuteRetentionExpiry {
         policies: Vec<RetentionPolicyConfig>,
     },
+    /// Destroy exactly these previewed candidates, e.g. the ones a user
+    /// confirmed, without re-running the policies.
+    ExpireRetentionCandidates {
+        candidates: Vec<RetentionCandidate>,
+    },
+    /// With `progress`, `Progress` responses precede the result.
     PreviewRulesForMailbox {
         origin: String,
         mailbox_id: String,
         mailbox_name: String,
+        progress: bool,
     },
     RunRulesForMailbox {
         origin: String,
         mailbox_id: String,
         mailbox_name: String,
+        progress: bool,
+    },
+    /// Evaluate the rules against one email without acting on it.
+    TestRulesForEmail {
+        id: String,
     },
     /// Train the spam classifier on a message (spam=true) or as ham (spam=false).
     TrainMessage {
@@ -134,6 +250,36 @@ pub enum BackendCommand {
         origin: String,
         id: String,
     },
+    /// Mute (true) or unmute (false) a thread; `None` toggles. New messages
+    /// in a muted thread are archived as they arrive.
+    SetThreadMuted {
+        thread_id: String,
+        muted: Option<bool>,
+    },
+    /// Reply `WatchStarted` once the baseline counts are recorded, then report
+    /// mailbox count changes as `StateChanged` until shutdown. Uses the
+    /// server's EventSource when available, otherwise polls every
+    /// `interval_secs`.
+    Watch {
+        interval_secs: u64,
+    },
+    /// Reverse the most recent user mutation recorded in the undo log. With
+    /// `move_op` (op_id, email id), reverse that command's move of the email
+    /// instead, wherever it is in the log.
+    Undo {
+        move_op: Option<(u64, String)>,
+    },
+    GetVacation,
+    SetVacation {
+        vacation: VacationResponse,
+    },
+    GetQuota,
+    /// Swap in rules re-read from `rules.toml` (SIGHUP); applies to mail
+    /// processed from now on.
+    ReloadRules {
+        rules: Arc<Vec<CompiledRule>>,
+        custom_headers: Arc<Vec<String>>,
+    },
     Shutdown,
 }
 
@@ -156,6 +302,11 @@ pub enum BackendResponse {
         loaded: u32,
         thread_counts: HashMap<String, (usize, usize)>,
     },
+    SearchResults {
+        emails: Result<Vec<Email>, String>,
+        total: Option<u32>,
+        position: u32,
+    },
     ThreadEmails {
         thread_id: String,
         emails: Result<Vec<Email>, String>,
@@ -164,6 +315,10 @@ pub enum BackendResponse {
         id: String,
         result: Box<Result<Email, String>>,
     },
+    /// Found emails in request order; ids the server or cache lacks are left out.
+    EmailBodies {
+        result: Result<Vec<Email>, String>,
+    },
     EmailForReply {
         id: String,
         result: Box<Result<Email, String>>,
@@ -186,18 +341,65 @@ pub enum BackendResponse {
         updated: usize,
         result: Result<(), String>,
     },
+    EmptyMailboxPreview {
+        mailbox_id: String,
+        mailbox_name: String,
+        result: Result<Vec<String>, String>,
+    },
+    /// `destroyed` counts the messages the server confirmed; the rest stay
+    /// queued for replay.
+    MailboxEmptied {
+        mailbox_id: String,
+        mailbox_name: String,
+        destroyed: usize,
+        result: Result<(), String>,
+    },
+    Session(Option<SessionState>),
+    SearchHistory(Vec<String>),
+    SeenAddresses(Vec<SeenAddress>),
+    /// Inbox unread count for another account, from `spawn_account_summary`.
+    AccountUnread {
+        account: String,
+        result: Result<u32, String>,
+    },
     EmailRawHeaders {
         id: String,
         result: Result<String, String>,
     },
+    /// The message source byte for byte; it need not be valid UTF-8.
     EmailRaw {
         id: String,
-        result: Result<String, String>,
+        result: Result<Vec<u8>, String>,
     },
     AttachmentDownloaded {
         name: String,
         result: Result<std::path::PathBuf, String>,
     },
+    /// Reply to `DownloadAttachments`: saved paths plus "name: error" for
+    /// each attachment that failed.
+    AttachmentsDownloaded {
+        saved: Vec<std::path::PathBuf>,
+        failed: Vec<String>,
+    },
+    /// Ok(None) when the image cannot be drawn (too large, not PNG for
+    /// kitty, no `img2sixel`).
+    InlineImage {
+        blob_id: String,
+        result: Result<Option<EncodedImage>, String>,
+    },
+    /// Ok(path of the temporary copy), kept until tmc exits.
+    AttachmentReadyToOpen {
+        name: String,
+        result: Result<std::path::PathBuf, String>,
+    },
+    /// Ok(new email id) once the message has been submitted.
+    EmailSent {
+        result: Result<String, String>,
+    },
+    /// Ok(new email id) once a draft is stored in the Drafts mailbox.
+    DraftSaved {
+        result: Result<String, String>,
+    },
     RetentionPreview {
         result: Result<RetentionPreviewResult, String>,
     },
@@ -214,6 +416,16 @@ pub enum BackendResponse {
         mailbox_name: String,
         result: Result<RulesRunResult, String>,
     },
+    /// Emails fetched so far out of `total` during a full-mailbox scan
+    /// requested with `progress`.
+    Progress {
+        scanned: usize,
+        total: usize,
+    },
+    RulesTest {
+        id: String,
+        result: Result<RulesTestResult, String>,
+    },
     MessageTrained {
         spam: bool,
         result: Result<(), String>,
@@ -231,6 +443,81 @@ pub enum BackendResponse {
         /// Ok((score, verdict)) or an error.
         result: Result<(f64, String), String>,
     },
+    /// Ok(whether the thread is now muted).
+    ThreadMuted {
+        thread_id: String,
+        result: Result<bool, String>,
+    },
+    /// Mailboxes whose total or unread count changed since the last report.
+    StateChanged {
+        mailboxes: Vec<Mailbox>,
+    },
+    /// The watcher is running; `push` when it uses the EventSource.
+    WatchStarted {
+        push: bool,
+    },
+    /// Ok(description of what was undone) or why nothing could be undone.
+    Undone {
+        result: Result<String, String>,
+    },
+    /// Current vacation response, after the update for `SetVacation`.
+    Vacation {
+        result: Result<VacationResponse, String>,
+    },
+    /// Account quotas; `None` when the server has no quota support.
+    Quota {
+        result: Result<Option<Vec<Quota>>, String>,
+    },
+}
+
+impl BackendResponse {
+    /// The variant name, for logs.
+    pub fn name(&self) -> &'static str {
+        match self {
+            BackendResponse::Mailboxes(_) => "Mailboxes",
+            BackendResponse::MailboxCreated { .. } => "MailboxCreated",
+            BackendResponse::MailboxDeleted { .. } => "MailboxDeleted",
+            BackendResponse::Emails { .. } => "Emails",
+            BackendResponse::SearchResults { .. } => "SearchResults",
+            BackendResponse::ThreadEmails { .. } => "ThreadEmails",
+            BackendResponse::EmailBody { .. } => "EmailBody",
+            BackendResponse::EmailBodies { .. } => "EmailBodies",
+            BackendResponse::EmailForReply { .. } => "EmailForReply",
+            BackendResponse::EmailMutation { .. } => "EmailMutation",
+            BackendResponse::ThreadMarkedRead { .. } => "ThreadMarkedRead",
+            BackendResponse::MailboxMarkedRead { .. } => "MailboxMarkedRead",
+            BackendResponse::EmptyMailboxPreview { .. } => "EmptyMailboxPreview",
+            BackendResponse::MailboxEmptied { .. } => "MailboxEmptied",
+            BackendResponse::Session(_) => "Session",
+            BackendResponse::SearchHistory(_) => "SearchHistory",
+            BackendResponse::SeenAddresses(_) => "SeenAddresses",
+            BackendResponse::AccountUnread { .. } => "AccountUnread",
+            BackendResponse::EmailRawHeaders { .. } => "EmailRawHeaders",
+            BackendResponse::EmailRaw { .. } => "EmailRaw",
+            BackendResponse::AttachmentDownloaded { .. } => "AttachmentDownloaded",
+            BackendResponse::AttachmentsDownloaded { .. } => "AttachmentsDownloaded",
+            BackendResponse::InlineImage { .. } => "InlineImage",
+            BackendResponse::AttachmentReadyToOpen { .. } => "AttachmentReadyToOpen",
+            BackendResponse::EmailSent { .. } => "EmailSent",
+            BackendResponse::DraftSaved { .. } => "DraftSaved",
+            BackendResponse::RetentionPreview { .. } => "RetentionPreview",
+            BackendResponse::RetentionExecuted { .. } => "RetentionExecuted",
+            BackendResponse::RulesDryRun { .. } => "RulesDryRun",
+            BackendResponse::RulesRun { .. } => "RulesRun",
+            BackendResponse::Progress { .. } => "Progress",
+            BackendResponse::RulesTest { .. } => "RulesTest",
+            BackendResponse::MessageTrained { .. } => "MessageTrained",
+            BackendResponse::MailboxTrained { .. } => "MailboxTrained",
+            BackendResponse::MailboxClassified { .. } => "MailboxClassified",
+            BackendResponse::MessageClassified { .. } => "MessageClassified",
+            BackendResponse::ThreadMuted { .. } => "ThreadMuted",
+            BackendResponse::StateChanged { .. } => "StateChanged",
+            BackendResponse::WatchStarted { .. } => "WatchStarted",
+            BackendResponse::Undone { .. } => "Undone",
+            BackendResponse::Vacation { .. } => "Vacation",
+            BackendResponse::Quota { .. } => "Quota",
+        }
+    }
 }
 
 /// One message's read-only classification result, used for validation.
@@ -273,6 +560,7 @@ pub struct RulesRunResult {
 
 #[derive(Clone, Debug)]
 pub struct RulesDryRunEntry {
+    pub id: String,
     pub received_at: String,
     pub from: String,
     pub subject: String,
@@ -286,6 +574,51 @@ pub struct RulesDryRunResult {
     pub matched_rules: usize,
     pub actions: usize,
     pub entries: Vec<RulesDryRunEntry>,
+    /// Every scanned email in scan order, so previews can show what falls
+    /// through.
+    pub scanned_emails: Vec<RulesScannedEmail>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RulesScannedEmail {
+    pub id: String,
+    pub received_at: String,
+    pub from: String,
+    pub subject: String,
+    /// Some rule's condition matched, even if it left nothing to do.
+    pub matched: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct RulesTestResult {
+    pub subject: String,
+    pub from: String,
+    /// (rule name, actions) that `run_rules` would apply, in order.
+    pub matches: Vec<(String, Vec<String>)>,
+    pub traces: Vec<rules::RuleTrace>,
+}
+
+/// Keyword changes that report a message as spam (`$junk`) or not spam
+/// (`$notjunk`), clearing the other, for server-side filters that train on
+/// them.
+pub fn spam_report_keywords(is_spam: bool) -> [(&'static str, bool); 2] {
+    [("$junk", is_spam), ("$notjunk", !is_spam)]
+}
+
+/// Whether a mailbox may be emptied wholesale: only Trash and Junk, found by
+/// role or as the configured deleted/spam folder.
+pub fn is_emptiable_mailbox(
+    mailbox_id: &str,
+    mailboxes: &[Mailbox],
+    deleted_folder: &str,
+    spam_folder: &str,
+) -> bool {
+    mailboxes
+        .iter()
+        .any(|m| m.id == mailbox_id && matches!(m.role.as_deref(), Some("trash" | "junk")))
+        || [deleted_folder, spam_folder]
+            .iter()
+            .any(|f| rules::resolve_mailbox_id(f, mailboxes).as_deref() == Some(mailbox_id))
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -293,6 +626,7 @@ pub enum EmailMutationAction {
     MarkRead,
     MarkUnread,
     SetFlagged(bool),
+    SetKeyword(bool),
     Move,
     Destroy,
 }
@@ -313,6 +647,12 @@ enum QueuedMutation {
         id: String,
         flagged: bool,
     },
+    SetKeyword {
+        op_id: u64,
+        id: String,
+        keyword: String,
+        value: bool,
+    },
     MoveEmail {
         op_id: u64,
         id: String,
@@ -323,6 +663,19 @@ enum QueuedMutation {
         thread_id: String,
         to_mailbox_id: String,
     },
+    /// `spam_report_keywords` plus a move, applied together.
+    ReportSpam {
+        op_id: u64,
+        ids: Vec<String>,
+        spam: bool,
+        to_mailbox_id: String,
+    },
+    /// Put an email back in exactly these mailboxes (undo of a move).
+    SetMailboxes {
+        op_id: u64,
+        id: String,
+        mailbox_ids: Vec<String>,
+    },
     DestroyEmail {
         op_id: u64,
         id: String,
@@ -331,6 +684,12 @@ enum QueuedMutation {
         op_id: u64,
         thread_id: String,
     },
+    /// Inline forward sent by a `forward_to` rule.
+    Forward {
+        op_id: u64,
+        id: String,
+        to: String,
+    },
     MarkThreadRead {
         thread_id: String,
         email_ids: Vec<String>,
@@ -349,11 +708,40 @@ enum QueuedMutation {
 
 static GENERATED_OP_ID: AtomicU64 = AtomicU64::new(1);
 
+impl QueuedMutation {
+    /// The id of the command that asked for this mutation, if it had one.
+    fn op_id(&self) -> Option<u64> {
+        match self {
+            QueuedMutation::MarkRead { op_id, .. }
+            | QueuedMutation::MarkUnread { op_id, .. }
+            | QueuedMutation::SetFlagged { op_id, .. }
+            | QueuedMutation::SetKeyword { op_id, .. }
+            | QueuedMutation::MoveEmail { op_id, .. }
+            | QueuedMutation::MoveThread { op_id, .. }
+            | QueuedMutation::ReportSpam { op_id, .. }
+            | QueuedMutation::SetMailboxes { op_id, .. }
+            | QueuedMutation::DestroyEmail { op_id, .. }
+            | QueuedMutation::DestroyThread { op_id, .. }
+            | QueuedMutation::Forward { op_id, .. } => Some(*op_id),
+            QueuedMutation::MarkThreadRead { .. }
+            | QueuedMutation::MarkMailboxRead { .. }
+            | QueuedMutation::RunRulesForMailbox { .. }
+            | QueuedMutation::ExecuteRetentionExpiry { .. } => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 struct RetentionPolicySnapshot {
     name: String,
     folder: String,
-    days: u32,
+    days: Option<u32>,
+    #[serde(default)]
+    keep_newest: Option<u32>,
+    #[serde(default)]
+    only_read: bool,
+    #[serde(default)]
+    only_unflagged: bool,
 }
 
 impl From<&RetentionPolicyConfig> for RetentionPolicySnapshot {
@@ -362,6 +750,9 @@ impl From<&RetentionPolicyConfig> for RetentionPolicySnapshot {
             name: value.name.clone(),
             folder: value.folder.clone(),
             days: value.days,
+            keep_newest: value.keep_newest,
+            only_read: value.only_read,
+            only_unflagged: value.only_unflagged,
         }
     }
 }
@@ -372,6 +763,9 @@ impl From<&RetentionPolicySnapshot> for RetentionPolicyConfig {
             name: value.name.clone(),
             folder: value.folder.clone(),
             days: value.days,
+            keep_newest: value.keep_newest,
+            only_read: value.only_read,
+            only_unflagged: value.only_unflagged,
         }
     }
 }
@@ -396,6 +790,11 @@ fn apply_local_mutation(cache: Option<&Cache>, op: &QueuedMutation) {
         QueuedMutation::SetFlagged { id, flagged, .. } => {
             let _ = cache.apply_set_flagged(id, *flagged);
         }
+        QueuedMutation::SetKeyword {
+            id, keyword, value, ..
+        } => {
+            let _ = cache.apply_set_keyword(id, keyword, *value);
+        }
         QueuedMutation::MoveEmail {
             id, to_mailbox_id, ..
         } => {
@@ -410,6 +809,24 @@ fn apply_local_mutation(cache: Option<&Cache>, op: &QueuedMutation) {
                 let _ = cache.apply_move_email(&email.id, to_mailbox_id);
             }
         }
+        QueuedMutation::ReportSpam {
+            ids,
+            spam,
+            to_mailbox_id,
+            ..
+        } => {
+            for id in ids {
+                for (keyword, value) in spam_report_keywords(*spam) {
+                    let _ = cache.apply_set_keyword(id, keyword, value);
+                }
+                let _ = cache.apply_move_email(id, to_mailbox_id);
+            }
+        }
+        QueuedMutation::SetMailboxes {
+            id, mailbox_ids, ..
+        } => {
+            let _ = cache.apply_set_mailboxes(id, mailbox_ids);
+        }
         QueuedMutation::DestroyEmail { id, .. } => {
             let _ = cache.apply_destroy_email(id);
         }
@@ -426,6 +843,9 @@ fn apply_local_mutation(cache: Option<&Cache>, op: &QueuedMutation) {
         QueuedMutation::MarkMailboxRead { mailbox_id, .. } => {
             let _ = cache.apply_mark_mailbox_read(mailbox_id);
         }
+        QueuedMutation::Forward { id, .. } => {
+            let _ = cache.apply_set_keyword(id, rules::FORWARDED_KEYWORD, true);
+        }
         QueuedMutation::RunRulesForMailbox { .. }
         | QueuedMutation::ExecuteRetentionExpiry { .. } => {}
     }
@@ -449,6 +869,31 @@ fn process_mutation_via_queue(
     my_email_regex: &Regex,
     cache: Option<&Cache>,
 ) -> Result<(), String> {
+    process_mutation_now_or_later(
+        client,
+        op,
+        cached_mailboxes,
+        rules,
+        custom_headers,
+        my_email_regex,
+        cache,
+    )
+    .map(|_| ())
+}
+
+/// `process_mutation_via_queue`, saying whether the server applied the
+/// mutation now (`false`: it was already gone there, or it stays queued for
+/// replay).
+#[allow(clippy::too_many_arguments)]
+fn process_mutation_now_or_later(
+    client: &JmapClient,
+    op: &QueuedMutation,
+    cached_mailboxes: &mut Vec<Mailbox>,
+    rules: &[CompiledRule],
+    custom_headers: &[String],
+    my_email_regex: &Regex,
+    cache: Option<&Cache>,
+) -> Result<bool, String> {
     let Some(cache) = cache else {
         log_warn!(
             "[Backend] cache unavailable; executing mutation without durable queue: {:?}",
@@ -462,7 +907,8 @@ fn process_mutation_via_queue(
             custom_headers,
             my_email_regex,
             None,
-        );
+        )
+        .map(|()| true);
     };
     let seq = queue_mutation(Some(cache), op)?;
     apply_local_mutation(Some(cache), op);
@@ -478,14 +924,18 @@ fn process_mutation_via_queue(
     ) {
         Ok(()) => {
             let _ = cache.remove_queued_operation(seq);
+            return Ok(true);
         }
         Err(e) => {
             let resolved = matches!(
                 op,
                 QueuedMutation::MoveEmail { .. }
                     | QueuedMutation::MoveThread { .. }
+                    | QueuedMutation::ReportSpam { .. }
+                    | QueuedMutation::SetMailboxes { .. }
                     | QueuedMutation::DestroyEmail { .. }
                     | QueuedMutation::DestroyThread { .. }
+                    | QueuedMutation::Forward { .. }
             ) && is_missing_remote_error(&e);
             if resolved {
                 log_info!(
@@ -504,6 +954,254 @@ fn process_mutation_via_queue(
             }
         }
     }
+    Ok(false)
+}
+
+/// How to reverse one completed user mutation. Recorded before the mutation
+/// is applied, while the cache still knows the original mailboxes and flags.
+/// `op_id` is that of the mutation, so a view can undo its own change.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum UndoEntry {
+    Revert {
+        #[serde(default)]
+        op_id: Option<u64>,
+        description: String,
+        ops: Vec<QueuedMutation>,
+    },
+    NotUndoable {
+        #[serde(default)]
+        op_id: Option<u64>,
+        description: String,
+    },
+}
+
+impl UndoEntry {
+    /// Whether this entry reverts the move of `email_id` by command `op_id`.
+    fn reverts_move(&self, op_id: u64, email_id: &str) -> bool {
+        match self {
+            UndoEntry::Revert {
+                op_id: Some(recorded),
+                ops,
+                ..
+            } => *recorded == op_id
+                && ops.iter().any(
+                    |op| matches!(op, QueuedMutation::SetMailboxes { id, .. } if id == email_id),
+                ),
+            _ => false,
+        }
+    }
+}
+
+fn undo_entry_for(cache: &Cache, op: &QueuedMutation) -> Option<UndoEntry> {
+    let has_keyword = |id: &str, keyword: &str, default: bool| {
+        cache
+            .get_email(id)
+            .map(|e| e.keywords.contains_key(keyword))
+            .unwrap_or(default)
+    };
+    let seen_op = |id: &str, seen: bool| {
+        let op_id = next_generated_op_id();
+        let id = id.to_string();
+        if seen {
+            QueuedMutation::MarkRead { op_id, id }
+        } else {
+            QueuedMutation::MarkUnread { op_id, id }
+        }
+    };
+    let move_back = |email: &Email| {
+        let mut mailbox_ids: Vec<String> = email.mailbox_ids.keys().cloned().collect();
+        mailbox_ids.sort();
+        (!mailbox_ids.is_empty()).then(|| QueuedMutation::SetMailboxes {
+            op_id: next_generated_op_id(),
+            id: email.id.clone(),
+            mailbox_ids,
+        })
+    };
+    let op_id = op.op_id();
+    let revert = |description: &str, ops: Vec<QueuedMutation>| UndoEntry::Revert {
+        op_id,
+        description: description.to_string(),
+        ops,
+    };
+    let not_undoable = |description: &str| UndoEntry::NotUndoable {
+        op_id,
+        description: description.to_string(),
+    };
+
+    match op {
+        QueuedMutation::MarkRead { id, .. } => Some(revert(
+            "mark read",
+            vec![seen_op(id, has_keyword(id, "$seen", false))],
+        )),
+        QueuedMutation::MarkUnread { id, .. } => Some(revert(
+            "mark unread",
+            vec![seen_op(id, has_keyword(id, "$seen", true))],
+        )),
+        QueuedMutation::SetFlagged { id, flagged, .. } => Some(revert(
+            if *flagged { "flag" } else { "unflag" },
+            vec![QueuedMutation::SetFlagged {
+                op_id: next_generated_op_id(),
+                id: id.clone(),
+                flagged: has_keyword(id, "$flagged", !flagged),
+            }],
+        )),
+        QueuedMutation::MoveEmail { id, .. } | QueuedMutation::SetMailboxes { id, .. } => {
+            Some(match cache.get_email(id).as_ref().and_then(move_back) {
+                Some(op) => revert("move", vec![op]),
+                None => not_undoable("move (original mailbox unknown)"),
+            })
+        }
+        QueuedMutation::MoveThread { thread_id, .. } => {
+            let ops: Vec<QueuedMutation> = cache
+                .get_thread_emails(thread_id)
+                .iter()
+                .filter_map(move_back)
+                .collect();
+            Some(if ops.is_empty() {
+                not_undoable("thread move (original mailboxes unknown)")
+            } else {
+                revert("thread move", ops)
+            })
+        }
+        QueuedMutation::ReportSpam { ids, spam, .. } => {
+            let description = if *spam {
+                "report spam"
+            } else {
+                "report not-spam"
+            };
+            let mut ops = Vec::new();
+            for id in ids {
+                let Some(back) = cache.get_email(id).as_ref().and_then(move_back) else {
+                    return Some(not_undoable(&format!(
+                        "{} (original mailbox unknown)",
+                        description
+                    )));
+                };
+                for (keyword, value) in spam_report_keywords(*spam) {
+                    ops.push(QueuedMutation::SetKeyword {
+                        op_id: next_generated_op_id(),
+                        id: id.clone(),
+                        keyword: keyword.to_string(),
+                        value: has_keyword(id, keyword, !value),
+                    });
+                }
+                ops.push(back);
+            }
+            Some(revert(description, ops))
+        }
+        QueuedMutation::MarkThreadRead { email_ids, .. } => Some(revert(
+            "thread mark read",
+            email_ids
+                .iter()
+                .filter(|id| !has_keyword(id, "$seen", false))
+                .map(|id| seen_op(id, false))
+                .collect(),
+        )),
+        QueuedMutation::DestroyEmail { .. } | QueuedMutation::DestroyThread { .. } => {
+            Some(not_undoable("permanent delete"))
+        }
+        QueuedMutation::MarkMailboxRead { mailbox_name, .. } => {
+            Some(not_undoable(&format!("mark all read in {}", mailbox_name)))
+        }
+        QueuedMutation::SetKeyword {
+            id, keyword, value, ..
+        } => Some(revert(
+            &format!("{} {}", if *value { "set" } else { "clear" }, keyword),
+            vec![QueuedMutation::SetKeyword {
+                op_id: next_generated_op_id(),
+                id: id.clone(),
+                keyword: keyword.clone(),
+                value: has_keyword(id, keyword, !value),
+            }],
+        )),
+        QueuedMutation::Forward { to, .. } => Some(not_undoable(&format!("forward to {}", to))),
+        QueuedMutation::RunRulesForMailbox { .. }
+        | QueuedMutation::ExecuteRetentionExpiry { .. } => None,
+    }
+}
+
+fn record_undo(cache: Option<&Cache>, entry: Option<UndoEntry>) {
+    let (Some(cache), Some(entry)) = (cache, entry) else {
+        return;
+    };
+    match serde_json::to_vec(&entry) {
+        Ok(payload) => {
+            if let Err(e) = cache.push_undo(&payload) {
+                log_warn!("[Backend] failed to record undo entry: {}", e);
+            }
+        }
+        Err(e) => log_warn!("[Backend] failed to serialize undo entry: {}", e),
+    }
+}
+
+/// Take the newest undo entry, or the one for `move_op` (see
+/// `BackendCommand::Undo`), and return its description and reverting ops.
+fn take_undo(
+    cache: Option<&Cache>,
+    move_op: Option<&(u64, String)>,
+) -> Result<(String, Vec<QueuedMutation>), String> {
+    let cache = cache.ok_or_else(|| "undo requires the local cache".to_string())?;
+    let payload = match move_op {
+        None => cache.pop_undo(),
+        Some((op_id, email_id)) => cache.take_undo_where(|payload| {
+            serde_json::from_slice::<UndoEntry>(payload)
+                .is_ok_and(|entry| entry.reverts_move(*op_id, email_id))
+        }),
+    }
+    .ok_or_else(|| "nothing to undo".to_string())?;
+    match serde_json::from_slice(&payload) {
+        Ok(UndoEntry::Revert {
+            description, ops, ..
+        }) => {
+            // A reverted move also cancels any snooze it started.
+            for op in &ops {
+                if let QueuedMutation::SetMailboxes { id, .. } = op {
+                    cache.remove_snooze(id);
+                }
+            }
+            Ok((description, ops))
+        }
+        Ok(UndoEntry::NotUndoable { description, .. }) => {
+            Err(format!("last action ({}) cannot be undone", description))
+        }
+        Err(e) => Err(format!("malformed undo entry: {}", e)),
+    }
+}
+
+/// `process_mutation_via_queue` for a mutation the user asked for, recorded
+/// in the undo log once it has been applied.
+#[allow(clippy::too_many_arguments)]
+fn process_user_mutation(
+    client: &JmapClient,
+    op: &QueuedMutation,
+    cached_mailboxes: &mut Vec<Mailbox>,
+    rules: &[CompiledRule],
+    custom_headers: &[String],
+    my_email_regex: &Regex,
+    cache: Option<&Cache>,
+) -> Result<(), String> {
+    let undo = cache.and_then(|c| undo_entry_for(c, op));
+    process_mutation_via_queue(
+        client,
+        op,
+        cached_mailboxes,
+        rules,
+        custom_headers,
+        my_email_regex,
+        cache,
+    )?;
+    record_undo(cache, undo);
+    Ok(())
+}
+
+/// Offline counterpart of `process_user_mutation`: queue for replay, apply
+/// locally, and record the undo entry.
+fn queue_user_mutation(cache: Option<&Cache>, op: &QueuedMutation) -> Result<(), String> {
+    let undo = cache.and_then(|c| undo_entry_for(c, op));
+    queue_mutation(cache, op)?;
+    apply_local_mutation(cache, op);
+    record_undo(cache, undo);
     Ok(())
 }
 
@@ -517,6 +1215,8 @@ pub fn spawn(
     rules_mailbox_regex: Arc<Regex>,
     my_email_regex: Arc<Regex>,
     spam_config: SpamConfig,
+    cache_max_bytes: Option<u64>,
+    preview_chars: usize,
 ) -> (
     mpsc::Sender<BackendCommand>,
     mpsc::Receiver<BackendResponse>,
@@ -526,8 +1226,9 @@ pub fn spawn(
 
     thread::spawn(move || {
         let cache = match Cache::open(&account_name) {
-            Ok(c) => {
+            Ok(mut c) => {
                 log_info!("[Backend] Opened cache for account '{}'", account_name);
+                c.set_preview_chars(preview_chars);
                 Some(c)
             }
             Err(e) => {
@@ -546,6 +1247,8 @@ pub fn spawn(
             spam_model.ham_messages(),
             spam_config.enabled
         );
+        let muted_path = mute::muted_path(&account_name);
+        let muted = MutedThreads::load(&muted_path);
         backend_loop(
             client,
             cmd_rx,
@@ -557,38 +1260,150 @@ pub fn spawn(
             cache,
             spam_config,
             spam_model,
+            cache_max_bytes,
+            muted,
+            muted_path,
         );
     });
 
     (cmd_tx, resp_rx)
 }
 
-/// Handle a command in offline mode. Returns true to continue, false to break (shutdown).
-fn handle_offline_command(
-    cmd: &BackendCommand,
-    resp_tx: &mpsc::Sender<BackendResponse>,
-    cache: &Option<Cache>,
-    cached_mailboxes: &mut Vec<Mailbox>,
-    command_seq: u64,
-) -> bool {
-    match cmd {
-        BackendCommand::FetchMailboxes { origin } => {
-            log_info!(
-                "[Backend/offline] cmd#{} FetchMailboxes origin='{}'",
-                command_seq,
-                origin
-            );
-            let result = if let Some(ref cache) = cache {
-                if let Some(mboxes) = cache.get_mailboxes() {
-                    if !mboxes.is_empty() {
-                        *cached_mailboxes = mboxes.clone();
-                        Ok(mboxes)
-                    } else {
-                        Err("no cached mailboxes available (offline mode)".to_string())
-                    }
-                } else {
-                    Err("no cached mailboxes available (offline mode)".to_string())
-                }
+/// Asks the account summary thread for every account's inbox unread count
+/// except `skip` (the active account), refreshed from the server when `live`.
+struct SummaryRequest {
+    skip: String,
+    live: bool,
+}
+
+/// Handle to the thread started by [`spawn_account_summary`].
+pub struct AccountSummary {
+    requests: mpsc::Sender<SummaryRequest>,
+    /// `AccountUnread` replies.
+    pub responses: mpsc::Receiver<BackendResponse>,
+}
+
+impl AccountSummary {
+    /// Report every account but `active`, asking the servers when `live`.
+    pub fn request(&self, active: &str, live: bool) {
+        let _ = self.requests.send(SummaryRequest {
+            skip: active.to_string(),
+            live,
+        });
+    }
+}
+
+/// Look up inbox unread counts for accounts other than the active one,
+/// without touching the active backend or any account's cache database.
+/// Counts come from the unread file each cache keeps beside its database;
+/// a live request also asks the server, reusing one client per account so
+/// `password_command` runs once per session rather than on every refresh.
+pub fn spawn_account_summary(accounts: Vec<AccountConfig>, http: HttpOptions) -> AccountSummary {
+    let (req_tx, req_rx) = mpsc::channel::<SummaryRequest>();
+    let (resp_tx, resp_rx) = mpsc::channel::<BackendResponse>();
+    let dir = crate::cache::cache_dir();
+    thread::spawn(move || {
+        let mut clients: HashMap<String, JmapClient> = HashMap::new();
+        while let Ok(request) = req_rx.recv() {
+            for account in accounts.iter().filter(|a| a.name != request.skip) {
+                if let Some(unread) = crate::cache::read_inbox_unread(&dir, &account.name) {
+                    let _ = resp_tx.send(BackendResponse::AccountUnread {
+                        account: account.name.clone(),
+                        result: Ok(unread),
+                    });
+                }
+                if !request.live {
+                    continue;
+                }
+                let client = match clients.get(&account.name) {
+                    Some(client) => Ok(client.clone()),
+                    None => crate::connect_account(account, http).inspect(|client| {
+                        clients.insert(account.name.clone(), client.clone());
+                    }),
+                };
+                let result = client
+                    .and_then(|client| client.get_mailboxes().map_err(|e| e.to_string()))
+                    .map(|mailboxes| {
+                        let unread = crate::cache::inbox_unread(&mailboxes);
+                        crate::cache::write_inbox_unread(&dir, &account.name, unread);
+                        unread
+                    });
+                if let Err(ref e) = result {
+                    log_warn!("[Summary] account '{}': {}", account.name, e);
+                }
+                let _ = resp_tx.send(BackendResponse::AccountUnread {
+                    account: account.name.clone(),
+                    result,
+                });
+            }
+        }
+    });
+    AccountSummary {
+        requests: req_tx,
+        responses: resp_rx,
+    }
+}
+
+/// Search cached mail, optionally limited to one mailbox. Returns one page of
+/// matches plus the total number of matches.
+#[allow(clippy::too_many_arguments)]
+fn offline_search(
+    cache: &Option<Cache>,
+    search_index: &Option<SearchIndex>,
+    mailbox_id: Option<&str>,
+    search_query: Option<&str>,
+    received_after: Option<&str>,
+    received_before: Option<&str>,
+    position: u32,
+    page_size: u32,
+) -> Result<(Vec<Email>, u32), String> {
+    let (Some(cache), Some(index)) = (cache.as_ref(), search_index.as_ref()) else {
+        return Err("cache unavailable (offline mode)".to_string());
+    };
+    // Re-read each hit so offline moves and deletes since the index was
+    // built are respected.
+    let matches: Vec<Email> = index
+        .search(search_query.unwrap_or(""), received_after, received_before)
+        .into_iter()
+        .filter_map(|id| cache.peek_email(id))
+        .filter(|email| mailbox_id.is_none_or(|m| email.mailbox_ids.get(m) == Some(&true)))
+        .collect();
+    let total = matches.len() as u32;
+    let page = matches
+        .into_iter()
+        .skip(position as usize)
+        .take(page_size as usize)
+        .collect();
+    Ok((page, total))
+}
+
+/// Handle a command in offline mode. Returns true to continue, false to break (shutdown).
+fn handle_offline_command(
+    cmd: &BackendCommand,
+    resp_tx: &mpsc::Sender<BackendResponse>,
+    cache: &Option<Cache>,
+    search_index: &Option<SearchIndex>,
+    cached_mailboxes: &mut Vec<Mailbox>,
+    command_seq: u64,
+) -> bool {
+    match cmd {
+        BackendCommand::FetchMailboxes { origin } => {
+            log_info!(
+                "[Backend/offline] cmd#{} FetchMailboxes origin='{}'",
+                command_seq,
+                origin
+            );
+            let result = if let Some(ref cache) = cache {
+                if let Some(mboxes) = cache.get_mailboxes() {
+                    if !mboxes.is_empty() {
+                        *cached_mailboxes = mboxes.clone();
+                        Ok(mboxes)
+                    } else {
+                        Err("no cached mailboxes available (offline mode)".to_string())
+                    }
+                } else {
+                    Err("no cached mailboxes available (offline mode)".to_string())
+                }
             } else {
                 Err("cache unavailable (offline mode)".to_string())
             };
@@ -597,53 +1412,63 @@ fn handle_offline_command(
         BackendCommand::QueryEmails {
             origin,
             mailbox_id,
+            page_size,
             position,
             search_query,
             received_after,
             received_before,
-            ..
         } => {
             log_info!(
-                "[Backend/offline] cmd#{} QueryEmails origin='{}' mailbox_id='{}'",
+                "[Backend/offline] cmd#{} QueryEmails origin='{}' mailbox_id='{}' search={:?}",
                 command_seq,
                 origin,
-                mailbox_id
+                mailbox_id,
+                search_query
             );
-            // Only serve first page, no search/date filters
-            if *position != 0
-                || search_query.is_some()
-                || received_after.is_some()
-                || received_before.is_some()
-            {
+            if search_query.is_some() || received_after.is_some() || received_before.is_some() {
+                let result = offline_search(
+                    cache,
+                    search_index,
+                    Some(mailbox_id),
+                    search_query.as_deref(),
+                    received_after.as_deref(),
+                    received_before.as_deref(),
+                    *position,
+                    *page_size,
+                );
+                let (emails, total) = match result {
+                    Ok((emails, total)) => (Ok(emails), Some(total)),
+                    Err(e) => (Err(e), None),
+                };
+                let loaded = emails.as_ref().map_or(0, |e| e.len() as u32);
                 let _ = resp_tx.send(BackendResponse::Emails {
                     mailbox_id: mailbox_id.clone(),
-                    emails: Err("search and pagination not available in offline mode".to_string()),
-                    total: None,
+                    emails,
+                    total,
                     position: *position,
-                    loaded: 0,
+                    loaded,
                     thread_counts: HashMap::new(),
                 });
             } else if let Some(ref cache) = cache {
-                if let Some(cached_emails) = cache.get_mailbox_emails(mailbox_id) {
-                    let loaded = cached_emails.len() as u32;
-                    let _ = resp_tx.send(BackendResponse::Emails {
-                        mailbox_id: mailbox_id.clone(),
-                        emails: Ok(cached_emails),
-                        total: None,
-                        position: 0,
-                        loaded,
-                        thread_counts: HashMap::new(),
-                    });
-                } else {
-                    let _ = resp_tx.send(BackendResponse::Emails {
-                        mailbox_id: mailbox_id.clone(),
-                        emails: Ok(Vec::new()),
-                        total: Some(0),
-                        position: 0,
-                        loaded: 0,
-                        thread_counts: HashMap::new(),
-                    });
-                }
+                // Page through the mailbox index cached by the last online
+                // query. The total is what the cache holds, not the server's
+                // count, so paging stops at the last cached message.
+                let cached_emails = cache.get_mailbox_emails(mailbox_id).unwrap_or_default();
+                let total = cached_emails.len() as u32;
+                let emails: Vec<Email> = cached_emails
+                    .into_iter()
+                    .skip(*position as usize)
+                    .take(*page_size as usize)
+                    .collect();
+                let loaded = emails.len() as u32;
+                let _ = resp_tx.send(BackendResponse::Emails {
+                    mailbox_id: mailbox_id.clone(),
+                    emails: Ok(emails),
+                    total: Some(total),
+                    position: *position,
+                    loaded,
+                    thread_counts: HashMap::new(),
+                });
             } else {
                 let _ = resp_tx.send(BackendResponse::Emails {
                     mailbox_id: mailbox_id.clone(),
@@ -671,6 +1496,13 @@ fn handle_offline_command(
                 result: Box::new(result),
             });
         }
+        BackendCommand::GetEmails { ids } => {
+            let result = cache
+                .as_ref()
+                .ok_or_else(|| "cache unavailable (offline mode)".to_string())
+                .map(|c| ids.iter().filter_map(|id| c.get_email(id)).collect());
+            let _ = resp_tx.send(BackendResponse::EmailBodies { result });
+        }
         BackendCommand::GetEmailForReply { id } => {
             let result = if let Some(ref cache) = cache {
                 if let Some(email) = cache.get_email(id) {
@@ -686,6 +1518,36 @@ fn handle_offline_command(
                 result: Box::new(result),
             });
         }
+        BackendCommand::LoadSession => {
+            let state = cache.as_ref().and_then(|c| c.get_session());
+            let _ = resp_tx.send(BackendResponse::Session(state));
+        }
+        BackendCommand::SaveSession { state } => {
+            if let Some(ref cache) = cache {
+                cache.put_session(state);
+            }
+        }
+        BackendCommand::LoadSearchHistory => {
+            let history = cache
+                .as_ref()
+                .map(|c| c.search_history())
+                .unwrap_or_default();
+            let _ = resp_tx.send(BackendResponse::SearchHistory(history));
+        }
+        BackendCommand::RecordSearch { query } => {
+            if let Some(ref cache) = cache {
+                cache.record_search(query);
+            }
+        }
+        BackendCommand::GetSeenAddresses => {
+            let seen = cache
+                .as_ref()
+                .map(|c| c.seen_addresses())
+                .unwrap_or_default();
+            let _ = resp_tx.send(BackendResponse::SeenAddresses(seen));
+        }
+        // Handled by `backend_loop` before commands are dispatched.
+        BackendCommand::ReloadRules { .. } | BackendCommand::SetThreadMuted { .. } => {}
         BackendCommand::Shutdown => {
             return false;
         }
@@ -695,9 +1557,7 @@ fn handle_offline_command(
                 op_id: *op_id,
                 id: id.clone(),
             };
-            let result = queue_mutation(cache.as_ref(), &op).map(|_| {
-                apply_local_mutation(cache.as_ref(), &op);
-            });
+            let result = queue_user_mutation(cache.as_ref(), &op);
             let _ = resp_tx.send(BackendResponse::EmailMutation {
                 op_id: *op_id,
                 id: id.clone(),
@@ -710,9 +1570,7 @@ fn handle_offline_command(
                 op_id: *op_id,
                 id: id.clone(),
             };
-            let result = queue_mutation(cache.as_ref(), &op).map(|_| {
-                apply_local_mutation(cache.as_ref(), &op);
-            });
+            let result = queue_user_mutation(cache.as_ref(), &op);
             let _ = resp_tx.send(BackendResponse::EmailMutation {
                 op_id: *op_id,
                 id: id.clone(),
@@ -728,9 +1586,7 @@ fn handle_offline_command(
                 id: id.clone(),
                 flagged: *flagged,
             };
-            let result = queue_mutation(cache.as_ref(), &op).map(|_| {
-                apply_local_mutation(cache.as_ref(), &op);
-            });
+            let result = queue_user_mutation(cache.as_ref(), &op);
             let _ = resp_tx.send(BackendResponse::EmailMutation {
                 op_id: *op_id,
                 id: id.clone(),
@@ -738,6 +1594,26 @@ fn handle_offline_command(
                 result: result.map(|_| ()),
             });
         }
+        BackendCommand::SetKeyword {
+            op_id,
+            id,
+            keyword,
+            value,
+        } => {
+            let op = QueuedMutation::SetKeyword {
+                op_id: *op_id,
+                id: id.clone(),
+                keyword: keyword.clone(),
+                value: *value,
+            };
+            let result = queue_user_mutation(cache.as_ref(), &op);
+            let _ = resp_tx.send(BackendResponse::EmailMutation {
+                op_id: *op_id,
+                id: id.clone(),
+                action: EmailMutationAction::SetKeyword(*value),
+                result: result.map(|_| ()),
+            });
+        }
         BackendCommand::MoveEmail {
             op_id,
             id,
@@ -748,9 +1624,7 @@ fn handle_offline_command(
                 id: id.clone(),
                 to_mailbox_id: to_mailbox_id.clone(),
             };
-            let result = queue_mutation(cache.as_ref(), &op).map(|_| {
-                apply_local_mutation(cache.as_ref(), &op);
-            });
+            let result = queue_user_mutation(cache.as_ref(), &op);
             let _ = resp_tx.send(BackendResponse::EmailMutation {
                 op_id: *op_id,
                 id: id.clone(),
@@ -758,6 +1632,62 @@ fn handle_offline_command(
                 result: result.map(|_| ()),
             });
         }
+        BackendCommand::ReportSpam {
+            op_id,
+            ids,
+            spam,
+            to_mailbox_id,
+        } => {
+            let op = QueuedMutation::ReportSpam {
+                op_id: *op_id,
+                ids: ids.clone(),
+                spam: *spam,
+                to_mailbox_id: to_mailbox_id.clone(),
+            };
+            let result = queue_user_mutation(cache.as_ref(), &op);
+            let _ = resp_tx.send(BackendResponse::EmailMutation {
+                op_id: *op_id,
+                id: ids.first().cloned().unwrap_or_default(),
+                action: EmailMutationAction::Move,
+                result,
+            });
+        }
+        BackendCommand::SnoozeEmail {
+            op_id,
+            id,
+            folder,
+            wake_at,
+        } => {
+            let result = cache
+                .as_ref()
+                .ok_or_else(|| "cache unavailable (offline mode)".to_string())
+                .and_then(|c| {
+                    let mailboxes = c.get_mailboxes().unwrap_or_default();
+                    let folder_id =
+                        rules::resolve_mailbox_id(folder, &mailboxes).ok_or_else(|| {
+                            format!(
+                                "snooze folder '{}' does not exist; it is created when online",
+                                folder
+                            )
+                        })?;
+                    c.put_snooze(id, *wake_at, &folder_id)?;
+                    Ok(folder_id)
+                })
+                .and_then(|folder_id| {
+                    let op = QueuedMutation::MoveEmail {
+                        op_id: *op_id,
+                        id: id.clone(),
+                        to_mailbox_id: folder_id,
+                    };
+                    queue_user_mutation(cache.as_ref(), &op)
+                });
+            let _ = resp_tx.send(BackendResponse::EmailMutation {
+                op_id: *op_id,
+                id: id.clone(),
+                action: EmailMutationAction::Move,
+                result,
+            });
+        }
         BackendCommand::MoveThread {
             op_id,
             thread_id,
@@ -768,9 +1698,7 @@ fn handle_offline_command(
                 thread_id: thread_id.clone(),
                 to_mailbox_id: to_mailbox_id.clone(),
             };
-            let result = queue_mutation(cache.as_ref(), &op).map(|_| {
-                apply_local_mutation(cache.as_ref(), &op);
-            });
+            let result = queue_user_mutation(cache.as_ref(), &op);
             let _ = resp_tx.send(BackendResponse::EmailMutation {
                 op_id: *op_id,
                 id: thread_id.clone(),
@@ -783,9 +1711,7 @@ fn handle_offline_command(
                 op_id: *op_id,
                 id: id.clone(),
             };
-            let result = queue_mutation(cache.as_ref(), &op).map(|_| {
-                apply_local_mutation(cache.as_ref(), &op);
-            });
+            let result = queue_user_mutation(cache.as_ref(), &op);
             let _ = resp_tx.send(BackendResponse::EmailMutation {
                 op_id: *op_id,
                 id: id.clone(),
@@ -800,9 +1726,7 @@ fn handle_offline_command(
                 op_id: *op_id,
                 thread_id: thread_id.clone(),
             };
-            let result = queue_mutation(cache.as_ref(), &op).map(|_| {
-                apply_local_mutation(cache.as_ref(), &op);
-            });
+            let result = queue_user_mutation(cache.as_ref(), &op);
             let _ = resp_tx.send(BackendResponse::EmailMutation {
                 op_id: *op_id,
                 id: thread_id.clone(),
@@ -841,9 +1765,7 @@ fn handle_offline_command(
                 thread_id: thread_id.clone(),
                 email_ids: email_ids.clone(),
             };
-            let result = queue_mutation(cache.as_ref(), &op).map(|_| {
-                apply_local_mutation(cache.as_ref(), &op);
-            });
+            let result = queue_user_mutation(cache.as_ref(), &op);
             let _ = resp_tx.send(BackendResponse::ThreadMarkedRead {
                 thread_id: thread_id.clone(),
                 result: result.map(|_| ()),
@@ -867,9 +1789,7 @@ fn handle_offline_command(
                         .count()
                 })
                 .unwrap_or(0);
-            let result = queue_mutation(cache.as_ref(), &op).map(|_| {
-                apply_local_mutation(cache.as_ref(), &op);
-            });
+            let result = queue_user_mutation(cache.as_ref(), &op);
             let _ = resp_tx.send(BackendResponse::MailboxMarkedRead {
                 mailbox_id: mailbox_id.clone(),
                 mailbox_name: mailbox_name.clone(),
@@ -877,6 +1797,44 @@ fn handle_offline_command(
                 result: result.map(|_| ()),
             });
         }
+        BackendCommand::PreviewEmptyMailbox {
+            mailbox_id,
+            mailbox_name,
+        } => {
+            let ids = cache
+                .as_ref()
+                .and_then(|c| c.get_mailbox_emails(mailbox_id))
+                .unwrap_or_default()
+                .into_iter()
+                .map(|e| e.id)
+                .collect();
+            let _ = resp_tx.send(BackendResponse::EmptyMailboxPreview {
+                mailbox_id: mailbox_id.clone(),
+                mailbox_name: mailbox_name.clone(),
+                result: Ok(ids),
+            });
+        }
+        BackendCommand::EmptyMailbox {
+            mailbox_id,
+            mailbox_name,
+            ids,
+        } => {
+            // Queued for replay: nothing is destroyed on the server yet.
+            let result = queued_mutations_for_empty(ids).iter().try_for_each(|op| {
+                queue_mutation(cache.as_ref(), op)?;
+                apply_local_mutation(cache.as_ref(), op);
+                Ok(())
+            });
+            if result.is_ok() {
+                record_undo(cache.as_ref(), Some(empty_undo_entry(mailbox_name)));
+            }
+            let _ = resp_tx.send(BackendResponse::MailboxEmptied {
+                mailbox_id: mailbox_id.clone(),
+                mailbox_name: mailbox_name.clone(),
+                destroyed: 0,
+                result,
+            });
+        }
         BackendCommand::GetEmailRawHeaders { id } => {
             let _ = resp_tx.send(BackendResponse::EmailRawHeaders {
                 id: id.clone(),
@@ -895,6 +1853,37 @@ fn handle_offline_command(
                 result: Err("not available in offline mode".to_string()),
             });
         }
+        BackendCommand::OpenAttachment { name, .. } => {
+            let _ = resp_tx.send(BackendResponse::AttachmentReadyToOpen {
+                name: name.clone(),
+                result: Err("not available in offline mode".to_string()),
+            });
+        }
+        BackendCommand::DownloadAttachments { attachments, .. } => {
+            let _ = resp_tx.send(BackendResponse::AttachmentsDownloaded {
+                saved: Vec::new(),
+                failed: attachments
+                    .iter()
+                    .map(|a| format!("{}: not available in offline mode", a.name))
+                    .collect(),
+            });
+        }
+        BackendCommand::FetchInlineImage { blob_id, .. } => {
+            let _ = resp_tx.send(BackendResponse::InlineImage {
+                blob_id: blob_id.clone(),
+                result: Err("not available in offline mode".to_string()),
+            });
+        }
+        BackendCommand::SendEmail { .. } => {
+            let _ = resp_tx.send(BackendResponse::EmailSent {
+                result: Err("sending requires an online connection".to_string()),
+            });
+        }
+        BackendCommand::SaveDraft { .. } => {
+            let _ = resp_tx.send(BackendResponse::DraftSaved {
+                result: Err("saving drafts requires an online connection".to_string()),
+            });
+        }
         BackendCommand::PreviewRetentionExpiry { .. } => {
             let _ = resp_tx.send(BackendResponse::RetentionPreview {
                 result: Err("not available in offline mode".to_string()),
@@ -912,6 +1901,21 @@ fn handle_offline_command(
                 }),
             });
         }
+        BackendCommand::ExpireRetentionCandidates { candidates } => {
+            let result = queued_mutations_for_retention(candidates)
+                .iter()
+                .try_for_each(|op| {
+                    queue_mutation(cache.as_ref(), op)?;
+                    apply_local_mutation(cache.as_ref(), op);
+                    Ok(())
+                });
+            let _ = resp_tx.send(BackendResponse::RetentionExecuted {
+                result: result.map(|_| RetentionExecutionResult {
+                    deleted: 0,
+                    failed_batches: Vec::new(),
+                }),
+            });
+        }
         BackendCommand::PreviewRulesForMailbox {
             mailbox_id,
             mailbox_name,
@@ -923,6 +1927,12 @@ fn handle_offline_command(
                 result: Err("not available in offline mode".to_string()),
             });
         }
+        BackendCommand::TestRulesForEmail { id } => {
+            let _ = resp_tx.send(BackendResponse::RulesTest {
+                id: id.clone(),
+                result: Err("not available in offline mode".to_string()),
+            });
+        }
         BackendCommand::RunRulesForMailbox {
             mailbox_id,
             mailbox_name,
@@ -966,6 +1976,61 @@ fn handle_offline_command(
                 result: Err("classification requires an online connection".to_string()),
             });
         }
+        BackendCommand::Undo { move_op } => {
+            let result =
+                take_undo(cache.as_ref(), move_op.as_ref()).and_then(|(description, ops)| {
+                    for op in &ops {
+                        queue_mutation(cache.as_ref(), op)?;
+                        apply_local_mutation(cache.as_ref(), op);
+                    }
+                    Ok(description)
+                });
+            let _ = resp_tx.send(BackendResponse::Undone { result });
+        }
+        BackendCommand::GetVacation | BackendCommand::SetVacation { .. } => {
+            let _ = resp_tx.send(BackendResponse::Vacation {
+                result: Err("vacation settings require an online connection".to_string()),
+            });
+        }
+        BackendCommand::GetQuota => {
+            let _ = resp_tx.send(BackendResponse::Quota { result: Ok(None) });
+        }
+        BackendCommand::Watch { .. } => {
+            // Nothing changes server-side while offline; there is nothing to report.
+            log_info!("[Backend/offline] cmd#{} Watch ignored", command_seq);
+        }
+        BackendCommand::SearchAllEmails {
+            page_size,
+            position,
+            search_query,
+            received_after,
+            received_before,
+        } => {
+            log_info!(
+                "[Backend/offline] cmd#{} SearchAllEmails search={:?}",
+                command_seq,
+                search_query
+            );
+            let result = offline_search(
+                cache,
+                search_index,
+                None,
+                search_query.as_deref(),
+                received_after.as_deref(),
+                received_before.as_deref(),
+                *position,
+                *page_size,
+            );
+            let (emails, total) = match result {
+                Ok((emails, total)) => (Ok(emails), Some(total)),
+                Err(e) => (Err(e), None),
+            };
+            let _ = resp_tx.send(BackendResponse::SearchResults {
+                emails,
+                total,
+                position: *position,
+            });
+        }
     }
     true
 }
@@ -990,11 +2055,29 @@ fn execute_remote_mutation(
         QueuedMutation::SetFlagged { id, flagged, .. } => client
             .set_email_flagged(id, *flagged)
             .map_err(|e| e.to_string()),
+        QueuedMutation::SetKeyword {
+            id, keyword, value, ..
+        } => client
+            .set_email_keyword(id, keyword, *value)
+            .map_err(|e| e.to_string()),
         QueuedMutation::MoveEmail {
             id, to_mailbox_id, ..
         } => client
             .move_email(id, to_mailbox_id)
             .map_err(|e| e.to_string()),
+        QueuedMutation::SetMailboxes {
+            id, mailbox_ids, ..
+        } => client
+            .set_mailboxes(id, mailbox_ids)
+            .map_err(|e| e.to_string()),
+        QueuedMutation::ReportSpam {
+            ids,
+            spam,
+            to_mailbox_id,
+            ..
+        } => client
+            .set_keywords_and_move(ids, &spam_report_keywords(*spam), to_mailbox_id)
+            .map_err(|e| e.to_string()),
         QueuedMutation::MoveThread {
             thread_id,
             to_mailbox_id,
@@ -1020,6 +2103,7 @@ fn execute_remote_mutation(
             let ids: Vec<String> = emails.into_iter().map(|e| e.id).collect();
             client.destroy_emails(&ids).map_err(|e| e.to_string())
         }
+        QueuedMutation::Forward { id, to, .. } => forward_email(client, cached_mailboxes, id, to),
         QueuedMutation::MarkThreadRead { email_ids, .. } => {
             if email_ids.is_empty() {
                 Ok(())
@@ -1049,6 +2133,7 @@ fn execute_remote_mutation(
                 mailbox_id,
                 cache,
                 None,
+                None,
             )?;
             Ok(())
         }
@@ -1124,8 +2209,11 @@ fn replay_queued_mutations(
                     op,
                     QueuedMutation::MoveEmail { .. }
                         | QueuedMutation::MoveThread { .. }
+                        | QueuedMutation::ReportSpam { .. }
+                        | QueuedMutation::SetMailboxes { .. }
                         | QueuedMutation::DestroyEmail { .. }
                         | QueuedMutation::DestroyThread { .. }
+                        | QueuedMutation::Forward { .. }
                 ) && is_missing_remote_error(&e);
                 if resolved {
                     log_info!(
@@ -1148,21 +2236,90 @@ fn replay_queued_mutations(
     }
 }
 
+/// How often the backend checks the cache file against `[cache].max_bytes`.
+const CACHE_EVICTION_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Emails that queued offline mutations still refer to; eviction must keep
+/// them so replay and undo can find them.
+fn queued_email_ids(cache: &Cache) -> HashSet<String> {
+    let mut ids = HashSet::new();
+    for (_, payload) in cache.queued_operations() {
+        let Ok(op) = serde_json::from_slice::<QueuedMutation>(&payload) else {
+            continue;
+        };
+        match op {
+            QueuedMutation::MarkRead { id, .. }
+            | QueuedMutation::MarkUnread { id, .. }
+            | QueuedMutation::SetFlagged { id, .. }
+            | QueuedMutation::SetKeyword { id, .. }
+            | QueuedMutation::MoveEmail { id, .. }
+            | QueuedMutation::SetMailboxes { id, .. }
+            | QueuedMutation::DestroyEmail { id, .. }
+            | QueuedMutation::Forward { id, .. } => {
+                ids.insert(id);
+            }
+            QueuedMutation::MoveThread { thread_id, .. }
+            | QueuedMutation::DestroyThread { thread_id, .. } => {
+                ids.extend(
+                    cache
+                        .get_thread_emails(&thread_id)
+                        .into_iter()
+                        .map(|e| e.id),
+                );
+            }
+            QueuedMutation::MarkThreadRead { email_ids, .. }
+            | QueuedMutation::ReportSpam { ids: email_ids, .. } => ids.extend(email_ids),
+            QueuedMutation::MarkMailboxRead { .. }
+            | QueuedMutation::RunRulesForMailbox { .. }
+            | QueuedMutation::ExecuteRetentionExpiry { .. } => {}
+        }
+    }
+    ids
+}
+
+/// Evict least recently used emails once the cache outgrows `max_bytes`,
+/// checking at most every [`CACHE_EVICTION_INTERVAL`].
+fn maybe_evict_cache(
+    cache: &mut Option<Cache>,
+    max_bytes: Option<u64>,
+    last_check: &mut Option<Instant>,
+) {
+    let (Some(cache), Some(max_bytes)) = (cache.as_mut(), max_bytes) else {
+        return;
+    };
+    if last_check.is_some_and(|t| t.elapsed() < CACHE_EVICTION_INTERVAL) {
+        return;
+    }
+    *last_check = Some(Instant::now());
+    if cache.file_size() <= max_bytes {
+        return;
+    }
+    let protected = queued_email_ids(cache);
+    cache.evict_lru(max_bytes, &protected);
+}
+
 #[allow(clippy::too_many_arguments)]
 fn backend_loop(
     client: Option<JmapClient>,
     cmd_rx: mpsc::Receiver<BackendCommand>,
     resp_tx: mpsc::Sender<BackendResponse>,
-    rules: Arc<Vec<CompiledRule>>,
-    custom_headers: Arc<Vec<String>>,
+    mut rules: Arc<Vec<CompiledRule>>,
+    mut custom_headers: Arc<Vec<String>>,
     rules_mailbox_regex: Arc<Regex>,
     my_email_regex: Arc<Regex>,
-    cache: Option<Cache>,
+    mut cache: Option<Cache>,
     spam_config: SpamConfig,
     mut spam_model: SpamModel,
+    cache_max_bytes: Option<u64>,
+    mut muted: MutedThreads,
+    muted_path: std::path::PathBuf,
 ) {
     let mut cached_mailboxes: Vec<Mailbox> = Vec::new();
+    // Server state of the last mailbox list and first pages, for delta syncs.
+    let mut synced_mailboxes: Option<(String, Vec<Mailbox>)> = None;
+    let mut synced_pages: HashMap<String, SyncedPage> = HashMap::new();
     let mut command_seq: u64 = 0;
+    let mut watch_stop: Option<Arc<AtomicBool>> = None;
     let offline = client.is_none();
     if let Some(cache) = cache.as_ref() {
         if let Some(mboxes) = cache.get_mailboxes() {
@@ -1181,12 +2338,72 @@ fn backend_loop(
             );
         }
     }
+    let search_index = if offline {
+        cache.as_ref().map(|cache| {
+            let index = SearchIndex::build(cache.all_emails());
+            log_info!("[Backend/offline] indexed {} cached emails", index.len());
+            index
+        })
+    } else {
+        None
+    };
+    let mut last_eviction_check = None;
+    maybe_evict_cache(&mut cache, cache_max_bytes, &mut last_eviction_check);
 
     while let Ok(cmd) = cmd_rx.recv() {
         command_seq = command_seq.wrapping_add(1);
+        maybe_evict_cache(&mut cache, cache_max_bytes, &mut last_eviction_check);
+
+        if let BackendCommand::ReloadRules {
+            rules: new_rules,
+            custom_headers: new_headers,
+        } = cmd
+        {
+            log_info!(
+                "[Backend] cmd#{} ReloadRules: {} rule(s), {} custom header(s)",
+                command_seq,
+                new_rules.len(),
+                new_headers.len()
+            );
+            rules = new_rules;
+            custom_headers = new_headers;
+            continue;
+        }
+
+        // The mute list is a local file, so this works offline too.
+        if let BackendCommand::SetThreadMuted {
+            ref thread_id,
+            muted: want,
+        } = cmd
+        {
+            log_info!(
+                "[Backend] cmd#{} SetThreadMuted thread_id={} muted={:?}",
+                command_seq,
+                thread_id,
+                want
+            );
+            let now_muted = want.unwrap_or(!muted.contains(thread_id));
+            let result = if muted.set(thread_id, now_muted, snooze::now_secs() as i64) {
+                muted.save(&muted_path).map(|_| now_muted)
+            } else {
+                Ok(now_muted)
+            };
+            let _ = resp_tx.send(BackendResponse::ThreadMuted {
+                thread_id: thread_id.clone(),
+                result,
+            });
+            continue;
+        }
 
         if offline {
-            if handle_offline_command(&cmd, &resp_tx, &cache, &mut cached_mailboxes, command_seq) {
+            if handle_offline_command(
+                &cmd,
+                &resp_tx,
+                &cache,
+                &search_index,
+                &mut cached_mailboxes,
+                command_seq,
+            ) {
                 continue;
             } else {
                 break; // Shutdown
@@ -1220,10 +2437,23 @@ fn backend_loop(
                     }
                 }
 
-                let result = client.get_mailboxes().map_err(|e| e.to_string());
-                if let Ok(ref mailboxes) = result {
-                    cached_mailboxes = mailboxes.clone();
-                    if let Some(ref cache) = cache {
+                if let Some(ref cache) = cache {
+                    wake_snoozed_emails(
+                        client,
+                        cache,
+                        snooze::now_secs(),
+                        &mut cached_mailboxes,
+                        &rules,
+                        &custom_headers,
+                        &my_email_regex,
+                    );
+                }
+
+                let result = fetch_mailboxes_incremental(client, &mut synced_mailboxes)
+                    .map_err(|e| e.to_string());
+                if let Ok(ref mailboxes) = result {
+                    cached_mailboxes = mailboxes.clone();
+                    if let Some(ref cache) = cache {
                         cache.put_mailboxes(mailboxes);
                     }
                 }
@@ -1274,14 +2504,13 @@ fn backend_loop(
                     received_before
                 );
 
-                // For TUI open flows, serve cached mailbox emails immediately so
-                // folder open is instant. CLI expects one response per command.
-                let can_serve_cached_first = !origin.starts_with("cli")
-                    && position == 0
+                let first_page = position == 0
                     && search_query.is_none()
                     && received_after.is_none()
                     && received_before.is_none();
-                if can_serve_cached_first {
+                // For TUI open flows, serve cached mailbox emails immediately so
+                // folder open is instant. CLI expects one response per command.
+                if first_page && !origin.starts_with("cli") {
                     if let Some(ref cache) = cache {
                         if let Some(cached_emails) = cache.get_mailbox_emails(&mailbox_id) {
                             let cached_total = cached_mailboxes
@@ -1301,48 +2530,114 @@ fn backend_loop(
                     }
                 }
 
+                // Set when mutes or rules changed mail, so folder counts are stale.
+                let mut counts_changed = false;
+                // Email state the TUI's first page was fetched at, kept in
+                // `synced_pages` so periodic syncs only pull what changed.
+                let mut page_state = None;
                 let result = (|| {
-                    let query = client
-                        .query_emails(
-                            &mailbox_id,
-                            page_size,
-                            position,
-                            search_query.as_deref(),
-                            received_after.as_deref(),
-                            received_before.as_deref(),
-                        )
-                        .map_err(|e| e.to_string())?;
-                    let total = query.total;
-                    let position = query.position;
-                    let loaded = query.ids.len() as u32;
-                    let mut emails = if query.ids.is_empty() {
-                        Ok(Vec::new())
-                    } else {
-                        fetch_emails_chunked(client, &query.ids, &custom_headers)
-                    }?;
+                    let mut refreshed = None;
+                    if first_page && is_incremental_sync(&origin) {
+                        if let Some(synced) = synced_pages
+                            .get(&mailbox_id)
+                            .filter(|p| p.page_size == page_size)
+                        {
+                            match sync_page_changes(
+                                client,
+                                synced,
+                                &mailbox_id,
+                                &custom_headers,
+                                cache.as_ref(),
+                            ) {
+                                Some((state, None)) => {
+                                    page_state = Some(state);
+                                    return Ok((
+                                        synced.emails.clone(),
+                                        synced.total,
+                                        0,
+                                        synced.loaded,
+                                        synced.thread_counts.clone(),
+                                    ));
+                                }
+                                Some((state, Some(page))) => {
+                                    page_state = Some(state);
+                                    refreshed = Some(page);
+                                }
+                                None => {}
+                            }
+                        }
+                    }
+                    let (mut emails, total, position, loaded) = match refreshed {
+                        Some(page) => page,
+                        None => {
+                            if first_page
+                                && (!origin.starts_with("cli") || is_incremental_sync(&origin))
+                            {
+                                page_state = client
+                                    .get_email_state()
+                                    .map_err(|e| {
+                                        log_warn!("[Sync] Email state fetch failed: {}", e)
+                                    })
+                                    .ok();
+                            }
+                            let query = client
+                                .query_emails(
+                                    &mailbox_id,
+                                    page_size,
+                                    position,
+                                    search_query.as_deref(),
+                                    received_after.as_deref(),
+                                    received_before.as_deref(),
+                                )
+                                .map_err(|e| e.to_string())?;
+                            let emails = if query.ids.is_empty() {
+                                Ok(Vec::new())
+                            } else {
+                                fetch_emails_chunked(client, &query.ids, &custom_headers)
+                            }?;
+                            (emails, query.total, query.position, query.ids.len() as u32)
+                        }
+                    };
 
                     // Cache fetched emails
                     if let Some(ref cache) = cache {
                         cache.put_emails(&emails);
                         // Update mailbox index for first-page non-search queries
-                        if position == 0
-                            && search_query.is_none()
-                            && received_after.is_none()
-                            && received_before.is_none()
-                        {
+                        if first_page {
                             let ids: Vec<String> = emails.iter().map(|e| e.id.clone()).collect();
                             cache.put_mailbox_index(&mailbox_id, &ids);
                         }
                     }
 
+                    let mailbox_name = cached_mailboxes
+                        .iter()
+                        .find(|m| m.id.as_str() == mailbox_id.as_str())
+                        .map(|m| m.name.clone())
+                        .unwrap_or_default();
+
+                    // Muted threads go first so their new mail never reaches the rules
+                    if !muted.is_empty() && rules_mailbox_regex.is_match(&mailbox_name) {
+                        let moved = apply_thread_mutes(
+                            client,
+                            &emails,
+                            &muted,
+                            &mut cached_mailboxes,
+                            &rules,
+                            &custom_headers,
+                            &my_email_regex,
+                            cache.as_ref(),
+                        );
+                        counts_changed |= !moved.is_empty();
+                        emails.retain(|e| !moved.contains(&e.id));
+                    }
+
                     // Apply filtering rules (only to unprocessed emails)
+                    let mailbox_path = rules::mailbox_path(&mailbox_id, &cached_mailboxes);
+                    let runs_here = |rule: &CompiledRule| {
+                        rule.runs_in(&mailbox_name, &mailbox_path, &rules_mailbox_regex)
+                    };
                     if !rules.is_empty() {
-                        let mailbox_name = cached_mailboxes
-                            .iter()
-                            .find(|m| m.id.as_str() == mailbox_id.as_str())
-                            .map(|m| m.name.clone())
-                            .unwrap_or_default();
-                        if rules_mailbox_regex.is_match(&mailbox_name) {
+                        if rules.iter().any(runs_here) {
                             let mut emails_for_rules = if let Some(ref cache) = cache {
                                 let all_ids: Vec<String> =
                                     emails.iter().map(|e| e.id.clone()).collect();
@@ -1377,11 +2672,12 @@ fn backend_loop(
                                     &mailbox_id,
                                     &mut emails_for_rules,
                                 );
-                                let applications = rules::apply_rules(
+                                let applications = rules::apply_rules_where(
                                     &rules,
                                     &emails_for_rules,
                                     &cached_mailboxes,
                                     &my_email_regex,
+                                    runs_here,
                                 );
                                 if !applications.is_empty() {
                                     log_info!(
@@ -1394,6 +2690,7 @@ fn backend_loop(
                                         &applications,
                                         &cached_mailboxes,
                                     );
+                                    counts_changed |= !ops.is_empty();
                                     let mut removed_ids = HashSet::new();
                                     for op in &ops {
                                         if let QueuedMutation::MoveEmail { id, .. } = op {
@@ -1434,7 +2731,7 @@ fn backend_loop(
                             }
                         } else {
                             log_debug!(
-                                "[Rules] Skipping auto-run for mailbox '{}' (does not match regex '{}')",
+                                "[Rules] Skipping auto-run for mailbox '{}' (no rule's mailbox pattern or regex '{}' matches)",
                                 mailbox_name,
                                 rules_mailbox_regex.as_str()
                             );
@@ -1487,6 +2784,21 @@ fn backend_loop(
                     Ok((emails, total, position, loaded, thread_counts))
                 })();
 
+                if let (Ok((emails, total, _, loaded, thread_counts)), Some(state)) =
+                    (&result, page_state)
+                {
+                    synced_pages.insert(
+                        mailbox_id.clone(),
+                        SyncedPage {
+                            state,
+                            page_size,
+                            emails: emails.clone(),
+                            total: *total,
+                            loaded: *loaded,
+                            thread_counts: thread_counts.clone(),
+                        },
+                    );
+                }
                 let (emails, total, position, loaded, thread_counts) = match result {
                     Ok((emails, total, position, loaded, thread_counts)) => {
                         (Ok(emails), total, position, loaded, thread_counts)
@@ -1502,6 +2814,63 @@ fn backend_loop(
                     loaded,
                     thread_counts,
                 });
+
+                // Refresh folder counts after mail moved; the CLI expects a
+                // single response per command, so only the TUI gets this.
+                if counts_changed && !origin.starts_with("cli") {
+                    if let Ok(mailboxes) = client.get_mailboxes() {
+                        cached_mailboxes = mailboxes.clone();
+                        if let Some(ref cache) = cache {
+                            cache.put_mailboxes(&mailboxes);
+                        }
+                        let _ = resp_tx.send(BackendResponse::Mailboxes(Ok(mailboxes)));
+                    }
+                }
+            }
+            BackendCommand::SearchAllEmails {
+                page_size,
+                position,
+                search_query,
+                received_after,
+                received_before,
+            } => {
+                log_info!(
+                    "[Backend] cmd#{} SearchAllEmails page_size={} position={} search={:?} after={:?} before={:?}",
+                    command_seq,
+                    page_size,
+                    position,
+                    search_query,
+                    received_after,
+                    received_before
+                );
+                let result = client
+                    .search_all_emails(
+                        page_size,
+                        position,
+                        search_query.as_deref(),
+                        received_after.as_deref(),
+                        received_before.as_deref(),
+                    )
+                    .map_err(|e| e.to_string())
+                    .and_then(|query| {
+                        let emails = fetch_emails_chunked(client, &query.ids, &custom_headers)?;
+                        if let Some(ref cache) = cache {
+                            cache.put_emails(&emails);
+                        }
+                        Ok((emails, query.total, query.position))
+                    });
+                let _ = resp_tx.send(match result {
+                    Ok((emails, total, position)) => BackendResponse::SearchResults {
+                        emails: Ok(emails),
+                        total,
+                        position,
+                    },
+                    Err(e) => BackendResponse::SearchResults {
+                        emails: Err(e),
+                        total: None,
+                        position,
+                    },
+                });
             }
             BackendCommand::QueryThreadEmails { thread_id } => {
                 let result = client
@@ -1536,6 +2905,36 @@ fn backend_loop(
                     result: Box::new(result),
                 });
             }
+            BackendCommand::GetEmails { ids } => {
+                let mut found: HashMap<String, Email> = HashMap::new();
+                if let Some(ref cache) = cache {
+                    for id in &ids {
+                        if let Some(email) = cache.get_email(id) {
+                            found.insert(id.clone(), email);
+                        }
+                    }
+                }
+                let misses: Vec<String> = ids
+                    .iter()
+                    .filter(|id| !found.contains_key(*id))
+                    .cloned()
+                    .collect();
+                log_info!(
+                    "[Backend] cmd#{} GetEmails ids={} cache_hits={}",
+                    command_seq,
+                    ids.len(),
+                    found.len()
+                );
+                let result =
+                    fetch_emails_chunked(client, &misses, &custom_headers).map(|fetched| {
+                        if let Some(ref cache) = cache {
+                            cache.put_emails(&fetched);
+                        }
+                        found.extend(fetched.into_iter().map(|e| (e.id.clone(), e)));
+                        ids.iter().filter_map(|id| found.remove(id)).collect()
+                    });
+                let _ = resp_tx.send(BackendResponse::EmailBodies { result });
+            }
             BackendCommand::GetEmailForReply { id } => {
                 let result = client
                     .get_email_for_reply(&id)
@@ -1552,7 +2951,7 @@ fn backend_loop(
                     op_id,
                     id: id.clone(),
                 };
-                let result = process_mutation_via_queue(
+                let result = process_user_mutation(
                     client,
                     &op,
                     &mut cached_mailboxes,
@@ -1577,7 +2976,7 @@ fn backend_loop(
                     op_id,
                     id: id.clone(),
                 };
-                let result = process_mutation_via_queue(
+                let result = process_user_mutation(
                     client,
                     &op,
                     &mut cached_mailboxes,
@@ -1604,7 +3003,7 @@ fn backend_loop(
                     id: id.clone(),
                     flagged,
                 };
-                let result = process_mutation_via_queue(
+                let result = process_user_mutation(
                     client,
                     &op,
                     &mut cached_mailboxes,
@@ -1625,6 +3024,39 @@ fn backend_loop(
                     result,
                 });
             }
+            BackendCommand::SetKeyword {
+                op_id,
+                id,
+                keyword,
+                value,
+            } => {
+                let op = QueuedMutation::SetKeyword {
+                    op_id,
+                    id: id.clone(),
+                    keyword: keyword.clone(),
+                    value,
+                };
+                let result = process_user_mutation(
+                    client,
+                    &op,
+                    &mut cached_mailboxes,
+                    &rules,
+                    &custom_headers,
+                    &my_email_regex,
+                    cache.as_ref(),
+                )
+                .map_err(|msg| {
+                    let msg = msg.to_string();
+                    log_warn!("Failed to set email {} {}={}: {}", id, keyword, value, msg);
+                    msg
+                });
+                let _ = resp_tx.send(BackendResponse::EmailMutation {
+                    op_id,
+                    id,
+                    action: EmailMutationAction::SetKeyword(value),
+                    result,
+                });
+            }
             BackendCommand::MoveEmail {
                 op_id,
                 id,
@@ -1635,7 +3067,7 @@ fn backend_loop(
                     id: id.clone(),
                     to_mailbox_id: to_mailbox_id.clone(),
                 };
-                let result = process_mutation_via_queue(
+                let result = process_user_mutation(
                     client,
                     &op,
                     &mut cached_mailboxes,
@@ -1656,6 +3088,84 @@ fn backend_loop(
                     result,
                 });
             }
+            BackendCommand::ReportSpam {
+                op_id,
+                ids,
+                spam,
+                to_mailbox_id,
+            } => {
+                let op = QueuedMutation::ReportSpam {
+                    op_id,
+                    ids: ids.clone(),
+                    spam,
+                    to_mailbox_id,
+                };
+                let result = process_user_mutation(
+                    client,
+                    &op,
+                    &mut cached_mailboxes,
+                    &rules,
+                    &custom_headers,
+                    &my_email_regex,
+                    cache.as_ref(),
+                )
+                .map_err(|msg| {
+                    log_warn!("Failed to report {:?}: {}", ids, msg);
+                    msg
+                });
+                let _ = resp_tx.send(BackendResponse::EmailMutation {
+                    op_id,
+                    id: ids.into_iter().next().unwrap_or_default(),
+                    action: EmailMutationAction::Move,
+                    result,
+                });
+            }
+            BackendCommand::SnoozeEmail {
+                op_id,
+                id,
+                folder,
+                wake_at,
+            } => {
+                let result = match cache.as_ref() {
+                    Some(cache) => {
+                        ensure_mailbox(client, &mut cached_mailboxes, Some(cache), &folder)
+                            .and_then(|folder_id| {
+                                cache.put_snooze(&id, wake_at, &folder_id)?;
+                                Ok(folder_id)
+                            })
+                    }
+                    None => Err("snoozing requires the local cache".to_string()),
+                }
+                .and_then(|folder_id| {
+                    let op = QueuedMutation::MoveEmail {
+                        op_id,
+                        id: id.clone(),
+                        to_mailbox_id: folder_id,
+                    };
+                    process_user_mutation(
+                        client,
+                        &op,
+                        &mut cached_mailboxes,
+                        &rules,
+                        &custom_headers,
+                        &my_email_regex,
+                        cache.as_ref(),
+                    )
+                })
+                .map_err(|msg| {
+                    log_warn!("Failed to snooze email {}: {}", id, msg);
+                    if let Some(ref cache) = cache {
+                        cache.remove_snooze(&id);
+                    }
+                    msg
+                });
+                let _ = resp_tx.send(BackendResponse::EmailMutation {
+                    op_id,
+                    id,
+                    action: EmailMutationAction::Move,
+                    result,
+                });
+            }
             BackendCommand::MoveThread {
                 op_id,
                 thread_id,
@@ -1666,7 +3176,7 @@ fn backend_loop(
                     thread_id: thread_id.clone(),
                     to_mailbox_id,
                 };
-                let result = process_mutation_via_queue(
+                let result = process_user_mutation(
                     client,
                     &op,
                     &mut cached_mailboxes,
@@ -1691,7 +3201,7 @@ fn backend_loop(
                     op_id,
                     id: id.clone(),
                 };
-                let result = process_mutation_via_queue(
+                let result = process_user_mutation(
                     client,
                     &op,
                     &mut cached_mailboxes,
@@ -1717,7 +3227,7 @@ fn backend_loop(
                     op_id,
                     thread_id: thread_id.clone(),
                 };
-                let result = process_mutation_via_queue(
+                let result = process_user_mutation(
                     client,
                     &op,
                     &mut cached_mailboxes,
@@ -1745,7 +3255,7 @@ fn backend_loop(
                     thread_id: thread_id.clone(),
                     email_ids,
                 };
-                let result = process_mutation_via_queue(
+                let result = process_user_mutation(
                     client,
                     &op,
                     &mut cached_mailboxes,
@@ -1773,7 +3283,7 @@ fn backend_loop(
                 let updated = fetch_all_mailbox_email_ids(client, &mailbox_id)
                     .map(|ids| ids.len())
                     .unwrap_or(0);
-                let result = process_mutation_via_queue(
+                let result = process_user_mutation(
                     client,
                     &op,
                     &mut cached_mailboxes,
@@ -1789,12 +3299,63 @@ fn backend_loop(
                     result,
                 });
             }
+            BackendCommand::PreviewEmptyMailbox {
+                mailbox_id,
+                mailbox_name,
+            } => {
+                let result = fetch_all_mailbox_email_ids(client, &mailbox_id);
+                let _ = resp_tx.send(BackendResponse::EmptyMailboxPreview {
+                    mailbox_id,
+                    mailbox_name,
+                    result,
+                });
+            }
+            BackendCommand::EmptyMailbox {
+                mailbox_id,
+                mailbox_name,
+                ids,
+            } => {
+                log_info!(
+                    "[Backend] cmd#{} EmptyMailbox mailbox='{}' mailbox_id='{}' messages={}",
+                    command_seq,
+                    mailbox_name,
+                    mailbox_id,
+                    ids.len()
+                );
+                let mut destroyed = 0;
+                let mut result = Ok(());
+                for op in &queued_mutations_for_empty(&ids) {
+                    match process_mutation_now_or_later(
+                        client,
+                        op,
+                        &mut cached_mailboxes,
+                        &rules,
+                        &custom_headers,
+                        &my_email_regex,
+                        cache.as_ref(),
+                    ) {
+                        Ok(applied) => destroyed += usize::from(applied),
+                        Err(e) => {
+                            result = Err(e);
+                            break;
+                        }
+                    }
+                }
+                record_undo(cache.as_ref(), Some(empty_undo_entry(&mailbox_name)));
+                let _ = resp_tx.send(BackendResponse::MailboxEmptied {
+                    mailbox_id,
+                    mailbox_name,
+                    destroyed,
+                    result,
+                });
+            }
             BackendCommand::GetEmailRawHeaders { id } => {
                 let result = client
                     .get_email_raw(&id)
                     .map_err(|e| e.to_string())
                     .and_then(|opt| opt.ok_or_else(|| "Email not found".to_string()))
                     .map(|raw| {
+                        let raw = String::from_utf8_lossy(&raw).into_owned();
                         // Extract just the headers (everything before the first blank line)
                         if let Some(pos) = raw.find("\r\n\r\n") {
                             raw[..pos].to_string()
@@ -1817,37 +3378,104 @@ fn backend_loop(
                 blob_id,
                 name,
                 content_type,
+                dir,
+            } => {
+                let result = save_attachment(client, &blob_id, &name, &content_type, &dir);
+                let _ = resp_tx.send(BackendResponse::AttachmentDownloaded { name, result });
+            }
+            BackendCommand::DownloadAttachments { attachments, dir } => {
+                let mut saved = Vec::new();
+                let mut failed = Vec::new();
+                for att in &attachments {
+                    match save_attachment(client, &att.blob_id, &att.name, &att.content_type, &dir)
+                    {
+                        Ok(path) => saved.push(path),
+                        Err(e) => failed.push(format!("{}: {}", att.name, e)),
+                    }
+                }
+                let _ = resp_tx.send(BackendResponse::AttachmentsDownloaded { saved, failed });
+            }
+            BackendCommand::FetchInlineImage {
+                blob_id,
+                content_type,
+                protocol,
+            } => {
+                let result = client
+                    .download_blob(&blob_id, "image", &content_type)
+                    .map_err(|e| e.to_string())
+                    .map(|data| graphics::encode(protocol, &data, &content_type));
+                let _ = resp_tx.send(BackendResponse::InlineImage { blob_id, result });
+            }
+            BackendCommand::OpenAttachment {
+                blob_id,
+                name,
+                content_type,
             } => {
                 let result = (|| {
                     let bytes = client
                         .download_blob(&blob_id, &name, &content_type)
                         .map_err(|e| e.to_string())?;
-
-                    let dir = std::env::var("XDG_DOWNLOAD_DIR")
-                        .map(std::path::PathBuf::from)
-                        .unwrap_or_else(|_| {
-                            std::env::var("HOME")
-                                .map(std::path::PathBuf::from)
-                                .unwrap_or_else(|_| std::env::temp_dir())
-                                .join("Downloads")
-                        });
-                    std::fs::create_dir_all(&dir)
-                        .map_err(|e| format!("Failed to create download dir: {}", e))?;
-
-                    let safe_name = name.replace(['/', '\\', '\0'], "_");
-                    let path = dir.join(&safe_name);
+                    let dir = open_attachment_dir();
+                    std::fs::DirBuilder::new()
+                        .recursive(true)
+                        .mode(0o700)
+                        .create(&dir)
+                        .map_err(|e| format!("Failed to create temp dir: {}", e))?;
+                    let path = dir.join(safe_file_name(&name));
                     std::fs::write(&path, &bytes)
                         .map_err(|e| format!("Failed to write file: {}", e))?;
-
                     log_info!(
-                        "[Backend] Attachment saved: {} ({} bytes)",
+                        "[Backend] Attachment staged for opening: {} ({} bytes)",
                         path.display(),
                         bytes.len()
                     );
                     Ok(path)
                 })();
 
-                let _ = resp_tx.send(BackendResponse::AttachmentDownloaded { name, result });
+                let _ = resp_tx.send(BackendResponse::AttachmentReadyToOpen { name, result });
+            }
+            BackendCommand::SendEmail { draft, attachments } => {
+                let result = (|| {
+                    if cached_mailboxes.is_empty() {
+                        cached_mailboxes = client.get_mailboxes().map_err(|e| e.to_string())?;
+                    }
+                    let mailbox_id = sent_mailbox_id(&cached_mailboxes)
+                        .ok_or_else(|| "No Sent or Drafts mailbox found".to_string())?;
+                    if attachments.is_empty() {
+                        let raw_mime = compose::build_mime_message(&draft);
+                        let from = compose::sender_address(&raw_mime);
+                        client.send_email(&raw_mime, from.as_deref(), &mailbox_id)
+                    } else {
+                        let email = compose::build_outgoing_email(&draft, attachments);
+                        client.send_email_with_attachments(&email, &mailbox_id)
+                    }
+                    .map_err(|e| e.to_string())
+                })();
+                if let Ok(ref id) = result {
+                    log_info!("[Backend] Email sent, id={}", id);
+                }
+                let _ = resp_tx.send(BackendResponse::EmailSent { result });
+            }
+            BackendCommand::SaveDraft {
+                draft,
+                attachments,
+                folder,
+            } => {
+                let result = (|| {
+                    if cached_mailboxes.is_empty() {
+                        cached_mailboxes = client.get_mailboxes().map_err(|e| e.to_string())?;
+                    }
+                    let mailbox_id = rules::resolve_mailbox_id(&folder, &cached_mailboxes)
+                        .ok_or_else(|| format!("cannot resolve drafts folder '{}'", folder))?;
+                    let email = compose::build_outgoing_email(&draft, attachments);
+                    client
+                        .save_draft(&email, &mailbox_id)
+                        .map_err(|e| e.to_string())
+                })();
+                if let Ok(ref id) = result {
+                    log_info!("[Backend] Draft saved, id={}", id);
+                }
+                let _ = resp_tx.send(BackendResponse::DraftSaved { result });
             }
             BackendCommand::PreviewRetentionExpiry { policies } => {
                 let result = collect_retention_candidates(client, &cached_mailboxes, &policies)
@@ -1855,41 +3483,37 @@ fn backend_loop(
                 let _ = resp_tx.send(BackendResponse::RetentionPreview { result });
             }
             BackendCommand::ExecuteRetentionExpiry { policies } => {
-                let result = (|| {
-                    let candidates =
-                        collect_retention_candidates(client, &cached_mailboxes, &policies)?;
-                    let ops = queued_mutations_for_retention(&candidates);
-                    let mut deleted = 0usize;
-                    let mut failed_batches = Vec::new();
-                    for op in &ops {
-                        match process_mutation_via_queue(
+                let result = collect_retention_candidates(client, &cached_mailboxes, &policies)
+                    .map(|candidates| {
+                        expire_retention_candidates(
                             client,
-                            op,
+                            &candidates,
                             &mut cached_mailboxes,
                             &rules,
                             &custom_headers,
                             &my_email_regex,
                             cache.as_ref(),
-                        ) {
-                            Ok(()) => {
-                                deleted += 1;
-                            }
-                            Err(e) => {
-                                failed_batches.push(e);
-                            }
-                        }
-                    }
-                    Ok(RetentionExecutionResult {
-                        deleted,
-                        failed_batches,
-                    })
-                })();
+                        )
+                    });
                 let _ = resp_tx.send(BackendResponse::RetentionExecuted { result });
             }
+            BackendCommand::ExpireRetentionCandidates { candidates } => {
+                let result = expire_retention_candidates(
+                    client,
+                    &candidates,
+                    &mut cached_mailboxes,
+                    &rules,
+                    &custom_headers,
+                    &my_email_regex,
+                    cache.as_ref(),
+                );
+                let _ = resp_tx.send(BackendResponse::RetentionExecuted { result: Ok(result) });
+            }
             BackendCommand::PreviewRulesForMailbox {
                 origin,
                 mailbox_id,
                 mailbox_name,
+                progress,
             } => {
                 log_info!(
                     "[Backend] cmd#{} PreviewRulesForMailbox origin='{}' mailbox='{}' (full mailbox scan)",
@@ -1901,25 +3525,67 @@ fn backend_loop(
                     config: &spam_config,
                     model: &spam_model,
                 };
-                let result = preview_rules_for_mailbox(
+                let report = |scanned, total| {
+                    let _ = resp_tx.send(BackendResponse::Progress { scanned, total });
+                };
+                let emails = if !rules
+                    .iter()
+                    .any(rules::runs_on_demand(&mailbox_id, &cached_mailboxes))
+                {
+                    Ok(Vec::new())
+                } else {
+                    fetch_mailbox_for_rules(
+                        client,
+                        &cached_mailboxes,
+                        &custom_headers,
+                        &mailbox_id,
+                        Some(&scorer),
+                        progress.then_some(&report as ProgressFn),
+                    )
+                };
+                let result = emails.map(|emails| {
+                    preview_rules_for_mailbox(
+                        &cached_mailboxes,
+                        &rules,
+                        &my_email_regex,
+                        &mailbox_id,
+                        &emails,
+                    )
+                });
+                let _ = resp_tx.send(BackendResponse::RulesDryRun {
+                    mailbox_id,
+                    mailbox_name,
+                    result,
+                });
+            }
+            BackendCommand::TestRulesForEmail { id } => {
+                // Move targets resolve against the mailbox list.
+                if cached_mailboxes.is_empty() {
+                    match client.get_mailboxes() {
+                        Ok(mailboxes) => cached_mailboxes = mailboxes,
+                        Err(e) => log_warn!("[Rules] failed to fetch mailboxes: {}", e),
+                    }
+                }
+                let scorer = SpamScorer {
+                    config: &spam_config,
+                    model: &spam_model,
+                };
+                let result = test_rules_for_email(
                     client,
                     &cached_mailboxes,
                     &rules,
                     &custom_headers,
                     &my_email_regex,
-                    &mailbox_id,
+                    &id,
                     Some(&scorer),
                 );
-                let _ = resp_tx.send(BackendResponse::RulesDryRun {
-                    mailbox_id,
-                    mailbox_name,
-                    result,
-                });
+                let _ = resp_tx.send(BackendResponse::RulesTest { id, result });
             }
             BackendCommand::RunRulesForMailbox {
                 origin,
                 mailbox_id,
                 mailbox_name,
+                progress,
             } => {
                 log_info!(
                     "[Backend] cmd#{} RunRulesForMailbox origin='{}' mailbox='{}' (full mailbox scan)",
@@ -1931,6 +3597,9 @@ fn backend_loop(
                     config: &spam_config,
                     model: &spam_model,
                 };
+                let report = |scanned, total| {
+                    let _ = resp_tx.send(BackendResponse::Progress { scanned, total });
+                };
                 let result = run_rules_for_mailbox(
                     client,
                     &cached_mailboxes,
@@ -1940,6 +3609,7 @@ fn backend_loop(
                     &mailbox_id,
                     cache.as_ref(),
                     Some(&scorer),
+                    progress.then_some(&report as ProgressFn),
                 );
                 let _ = resp_tx.send(BackendResponse::RulesRun {
                     mailbox_id,
@@ -2042,15 +3712,106 @@ fn backend_loop(
                 let result = classify_message(client, &spam_model, &spam_config, &id);
                 let _ = resp_tx.send(BackendResponse::MessageClassified { id, result });
             }
-            BackendCommand::Shutdown => {
-                break;
+            BackendCommand::Undo { move_op } => {
+                log_info!("[Backend] cmd#{} Undo move_op={:?}", command_seq, move_op);
+                let result =
+                    take_undo(cache.as_ref(), move_op.as_ref()).and_then(|(description, ops)| {
+                        for op in &ops {
+                            process_mutation_via_queue(
+                                client,
+                                op,
+                                &mut cached_mailboxes,
+                                &rules,
+                                &custom_headers,
+                                &my_email_regex,
+                                cache.as_ref(),
+                            )?;
+                        }
+                        Ok(description)
+                    });
+                let _ = resp_tx.send(BackendResponse::Undone { result });
             }
-        }
-    }
-}
-
-/// Fetch a message's raw source, train the model with the given label, and
-/// persist the updated model to disk.
+            BackendCommand::GetVacation => {
+                log_info!("[Backend] cmd#{} GetVacation", command_seq);
+                let result = client.get_vacation_response().map_err(|e| e.to_string());
+                let _ = resp_tx.send(BackendResponse::Vacation { result });
+            }
+            BackendCommand::SetVacation { vacation } => {
+                log_info!(
+                    "[Backend] cmd#{} SetVacation enabled={}",
+                    command_seq,
+                    vacation.is_enabled
+                );
+                let result = client
+                    .set_vacation_response(&vacation)
+                    .and_then(|_| client.get_vacation_response())
+                    .map_err(|e| e.to_string());
+                let _ = resp_tx.send(BackendResponse::Vacation { result });
+            }
+            BackendCommand::GetQuota => {
+                log_info!("[Backend] cmd#{} GetQuota", command_seq);
+                let result = client.get_quota().map_err(|e| e.to_string());
+                let _ = resp_tx.send(BackendResponse::Quota { result });
+            }
+            BackendCommand::Watch { interval_secs } => {
+                log_info!(
+                    "[Backend] cmd#{} Watch interval={}s",
+                    command_seq,
+                    interval_secs
+                );
+                if let Some(stop) = watch_stop.take() {
+                    stop.store(true, Ordering::SeqCst);
+                }
+                let stop = Arc::new(AtomicBool::new(false));
+                watch_stop = Some(stop.clone());
+                let client = client.clone();
+                let resp_tx = resp_tx.clone();
+                thread::spawn(move || {
+                    watch_state_changes(&client, interval_secs.max(1), &resp_tx, &stop);
+                });
+            }
+            BackendCommand::LoadSession => {
+                let state = cache.as_ref().and_then(|c| c.get_session());
+                let _ = resp_tx.send(BackendResponse::Session(state));
+            }
+            BackendCommand::SaveSession { state } => {
+                if let Some(ref cache) = cache {
+                    cache.put_session(&state);
+                }
+            }
+            BackendCommand::LoadSearchHistory => {
+                let history = cache
+                    .as_ref()
+                    .map(|c| c.search_history())
+                    .unwrap_or_default();
+                let _ = resp_tx.send(BackendResponse::SearchHistory(history));
+            }
+            BackendCommand::RecordSearch { query } => {
+                if let Some(ref cache) = cache {
+                    cache.record_search(&query);
+                }
+            }
+            BackendCommand::GetSeenAddresses => {
+                let seen = cache
+                    .as_ref()
+                    .map(|c| c.seen_addresses())
+                    .unwrap_or_default();
+                let _ = resp_tx.send(BackendResponse::SeenAddresses(seen));
+            }
+            // Swapped in above, before dispatch.
+            BackendCommand::ReloadRules { .. } | BackendCommand::SetThreadMuted { .. } => {}
+            BackendCommand::Shutdown => {
+                if let Some(stop) = watch_stop.take() {
+                    stop.store(true, Ordering::SeqCst);
+                }
+                break;
+            }
+        }
+    }
+}
+
+/// Fetch a message's raw source, train the model with the given label, and
+/// persist the updated model to disk.
 fn train_message(
     client: &JmapClient,
     model: &mut SpamModel,
@@ -2061,7 +3822,7 @@ fn train_message(
         .get_email_raw(id)
         .map_err(|e| e.to_string())?
         .ok_or_else(|| "no raw message source available".to_string())?;
-    let msg = spam::RawMessage::from_bytes(raw.as_bytes());
+    let msg = spam::RawMessage::from_bytes(&raw);
     model.train(&msg, label);
     model.save(&spam::model_path())
 }
@@ -2084,7 +3845,7 @@ fn train_mailbox(
     for id in &ids {
         match client.get_email_raw(id) {
             Ok(Some(raw)) => {
-                let msg = spam::RawMessage::from_bytes(raw.as_bytes());
+                let msg = spam::RawMessage::from_bytes(&raw);
                 model.train(&msg, label);
                 trained += 1;
             }
@@ -2113,7 +3874,7 @@ fn classify_message(
         .get_email_raw(id)
         .map_err(|e| e.to_string())?
         .ok_or_else(|| "no raw message source available".to_string())?;
-    let msg = spam::RawMessage::from_bytes(raw.as_bytes());
+    let msg = spam::RawMessage::from_bytes(&raw);
     let (score, verdict) = model.classify(
         &msg,
         config.threshold,
@@ -2146,7 +3907,7 @@ fn classify_mailbox(
                 continue;
             }
         };
-        let msg = spam::RawMessage::from_bytes(raw.as_bytes());
+        let msg = spam::RawMessage::from_bytes(&raw);
         let (score, verdict) = model.classify(
             &msg,
             config.threshold,
@@ -2172,53 +3933,332 @@ fn classify_mailbox(
 
 const EMAIL_GET_CHUNK_SIZE: usize = 50;
 
+/// Reports `(emails fetched so far, total)` while a mailbox scan runs.
+type ProgressFn<'a> = &'a (dyn Fn(usize, usize) + Sync);
+
 fn fetch_emails_chunked(
     client: &JmapClient,
     ids: &[String],
     custom_headers: &[String],
 ) -> Result<Vec<Email>, String> {
-    if ids.is_empty() {
-        return Ok(Vec::new());
-    }
-
-    let mut out = Vec::with_capacity(ids.len());
-    for chunk in ids.chunks(EMAIL_GET_CHUNK_SIZE) {
-        let mut batch = if custom_headers.is_empty() {
+    fetch_chunks(ids, client.fetch_concurrency(), |chunk| {
+        if custom_headers.is_empty() {
             client.get_emails(chunk)
         } else {
             client.get_emails_with_extra_properties(chunk, custom_headers)
         }
-        .map_err(|e| e.to_string())?;
-        out.append(&mut batch);
-    }
-    Ok(out)
+    })
 }
 
 fn fetch_rule_emails_chunked(
     client: &JmapClient,
     ids: &[String],
     custom_headers: &[String],
+    progress: Option<ProgressFn>,
 ) -> Result<Vec<Email>, String> {
-    if ids.is_empty() {
-        return Ok(Vec::new());
+    let fetched = AtomicUsize::new(0);
+    fetch_chunks(ids, client.fetch_concurrency(), |chunk| {
+        let emails = client.get_emails_for_rules(chunk, custom_headers)?;
+        if let Some(report) = progress {
+            let done = fetched.fetch_add(chunk.len(), Ordering::Relaxed) + chunk.len();
+            report(done, ids.len());
+        }
+        Ok(emails)
+    })
+}
+
+/// Run `fetch` over `EMAIL_GET_CHUNK_SIZE` chunks of `ids`, with up to
+/// `concurrency` requests in flight. Results keep the order of `ids`; the
+/// first failing chunk (in order) fails the whole fetch.
+fn fetch_chunks<F>(ids: &[String], concurrency: usize, fetch: F) -> Result<Vec<Email>, String>
+where
+    F: Fn(&[String]) -> Result<Vec<Email>, JmapError> + Sync,
+{
+    let chunks: Vec<&[String]> = ids.chunks(EMAIL_GET_CHUNK_SIZE).collect();
+    let workers = concurrency.min(chunks.len());
+    if workers <= 1 {
+        let mut out = Vec::with_capacity(ids.len());
+        for chunk in chunks {
+            out.append(&mut fetch(chunk).map_err(|e| e.to_string())?);
+        }
+        return Ok(out);
     }
 
+    let next = AtomicUsize::new(0);
+    let results: Vec<ChunkSlot> = chunks.iter().map(|_| Mutex::new(None)).collect();
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(chunk) = chunks.get(i) else {
+                    break;
+                };
+                let result = fetch(chunk);
+                let failed = result.is_err();
+                *results[i].lock().unwrap_or_else(|e| e.into_inner()) = Some(result);
+                if failed {
+                    // Stop handing out chunks; the fetch fails anyway.
+                    next.store(chunks.len(), Ordering::Relaxed);
+                }
+            });
+        }
+    });
+
     let mut out = Vec::with_capacity(ids.len());
-    for chunk in ids.chunks(EMAIL_GET_CHUNK_SIZE) {
-        let mut batch = client
-            .get_emails_for_rules(chunk, custom_headers)
-            .map_err(|e| e.to_string())?;
-        out.append(&mut batch);
+    let mut skipped = false;
+    for slot in results {
+        match slot.into_inner().unwrap_or_else(|e| e.into_inner()) {
+            Some(Ok(mut batch)) => out.append(&mut batch),
+            Some(Err(e)) => return Err(e.to_string()),
+            // Never fetched because a later chunk failed first; keep looking
+            // for that error.
+            None => skipped = true,
+        }
+    }
+    if skipped {
+        return Err("email fetch aborted after a chunk failed".to_string());
     }
     Ok(out)
 }
 
+/// Result slot for one chunk in `fetch_chunks`, filled by whichever worker
+/// fetched it.
+type ChunkSlot = Mutex<Option<Result<Vec<Email>, JmapError>>>;
+
+/// First page of a mailbox as last sent to the UI, with the Email state it
+/// was fetched at, so a periodic sync can ask `Email/changes` what moved
+/// instead of re-fetching every email.
+struct SyncedPage {
+    state: String,
+    page_size: u32,
+    emails: Vec<Email>,
+    total: Option<u32>,
+    loaded: u32,
+    thread_counts: HashMap<String, (usize, usize)>,
+}
+
+/// Whether a first-page query may be answered through `Email/changes` from
+/// the page synced earlier: the TUI's idle syncs and `query_emails` with
+/// `sync: true`.
+fn is_incremental_sync(origin: &str) -> bool {
+    origin.ends_with("idle_sync") || origin == "cli:sync"
+}
+
+/// `(emails, total, position, loaded)` for one page of a mailbox.
+type FetchedPage = (Vec<Email>, Option<u32>, u32, u32);
+
+/// Bring a synced first page up to date from `Email/changes`. Returns the new
+/// state and, when anything changed, the refreshed `(emails, total, position,
+/// loaded)`: the page is re-queried for ids only and just the created,
+/// updated or unknown emails are fetched. `None` means a full query is
+/// needed (the server can't calculate changes, there are too many, or a
+/// request failed).
+fn sync_page_changes(
+    client: &JmapClient,
+    synced: &SyncedPage,
+    mailbox_id: &str,
+    custom_headers: &[String],
+    cache: Option<&Cache>,
+) -> Option<(String, Option<FetchedPage>)> {
+    let changes = match client.email_changes(&synced.state) {
+        Ok(Some(changes)) if !changes.has_more_changes => changes,
+        Ok(_) => {
+            log_info!(
+                "[Sync] Email/changes unavailable, re-querying '{}'",
+                mailbox_id
+            );
+            return None;
+        }
+        Err(e) => {
+            log_warn!("[Sync] Email/changes failed, re-querying: {}", e);
+            return None;
+        }
+    };
+    if changes.is_empty() {
+        log_debug!("[Sync] '{}' unchanged since {}", mailbox_id, synced.state);
+        return Some((changes.new_state, None));
+    }
+    if let Some(cache) = cache {
+        for id in &changes.destroyed {
+            cache.remove_email(id);
+        }
+    }
+
+    let query = client
+        .query_emails(mailbox_id, synced.page_size, 0, None, None, None)
+        .map_err(|e| log_warn!("[Sync] Email/query failed: {}", e))
+        .ok()?;
+    let changed: HashSet<&str> = changes
+        .created
+        .iter()
+        .chain(&changes.updated)
+        .map(|id| id.as_str())
+        .collect();
+    let known: HashSet<&str> = synced.emails.iter().map(|e| e.id.as_str()).collect();
+    let stale: Vec<String> = query
+        .ids
+        .iter()
+        .filter(|id| changed.contains(id.as_str()) || !known.contains(id.as_str()))
+        .cloned()
+        .collect();
+    log_info!(
+        "[Sync] '{}': {} change(s), refetching {} of {} emails",
+        mailbox_id,
+        changed.len() + changes.destroyed.len(),
+        stale.len(),
+        query.ids.len()
+    );
+    let fetched = if stale.is_empty() {
+        Vec::new()
+    } else {
+        fetch_emails_chunked(client, &stale, custom_headers)
+            .map_err(|e| log_warn!("[Sync] Email/get failed: {}", e))
+            .ok()?
+    };
+    let emails = merge_page(&query.ids, &synced.emails, fetched);
+    let loaded = query.ids.len() as u32;
+    Some((
+        changes.new_state,
+        Some((emails, query.total, query.position, loaded)),
+    ))
+}
+
+/// Emails for `ids` in order, taking freshly fetched ones over `previous`.
+/// Ids found in neither (destroyed in between) are dropped.
+fn merge_page(ids: &[String], previous: &[Email], fetched: Vec<Email>) -> Vec<Email> {
+    let mut by_id: HashMap<String, Email> =
+        previous.iter().map(|e| (e.id.clone(), e.clone())).collect();
+    by_id.extend(fetched.into_iter().map(|e| (e.id.clone(), e)));
+    ids.iter().filter_map(|id| by_id.remove(id)).collect()
+}
+
+/// Fetch the mailbox list, through `Mailbox/changes` when `synced` holds the
+/// state and list of an earlier fetch; falls back to a full `Mailbox/get`.
+fn fetch_mailboxes_incremental(
+    client: &JmapClient,
+    synced: &mut Option<(String, Vec<Mailbox>)>,
+) -> Result<Vec<Mailbox>, JmapError> {
+    if let Some((state, previous)) = synced.as_ref() {
+        match client.mailbox_changes(state) {
+            Ok(Some(changes)) if !changes.has_more_changes => {
+                let ids: Vec<String> = changes
+                    .created
+                    .iter()
+                    .chain(&changes.updated)
+                    .cloned()
+                    .collect();
+                let fetched = if ids.is_empty() {
+                    Ok(Vec::new())
+                } else {
+                    client
+                        .get_mailboxes_with_state(Some(&ids))
+                        .map(|(mailboxes, _)| mailboxes)
+                };
+                match fetched {
+                    Ok(fetched) => {
+                        let mailboxes = merge_mailboxes(previous, &changes.destroyed, fetched);
+                        *synced = Some((changes.new_state, mailboxes.clone()));
+                        return Ok(mailboxes);
+                    }
+                    Err(e) => log_warn!("[Sync] changed mailbox fetch failed: {}", e),
+                }
+            }
+            Ok(_) => log_info!("[Sync] Mailbox/changes unavailable, refetching all mailboxes"),
+            Err(e) => log_warn!("[Sync] Mailbox/changes failed, refetching: {}", e),
+        }
+    }
+    let (mailboxes, state) = client.get_mailboxes_with_state(None)?;
+    *synced = Some((state, mailboxes.clone()));
+    Ok(mailboxes)
+}
+
+/// `previous` with `destroyed` mailboxes removed and `fetched` ones replaced
+/// in place or appended.
+fn merge_mailboxes(
+    previous: &[Mailbox],
+    destroyed: &[String],
+    fetched: Vec<Mailbox>,
+) -> Vec<Mailbox> {
+    let mut fetched: HashMap<String, Mailbox> =
+        fetched.into_iter().map(|m| (m.id.clone(), m)).collect();
+    let mut mailboxes: Vec<Mailbox> = previous
+        .iter()
+        .filter(|m| !destroyed.contains(&m.id))
+        .map(|m| fetched.remove(&m.id).unwrap_or_else(|| m.clone()))
+        .collect();
+    let mut created: Vec<Mailbox> = fetched.into_values().collect();
+    created.sort_by(|a, b| {
+        a.sort_order
+            .cmp(&b.sort_order)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    mailboxes.extend(created);
+    mailboxes
+}
+
 /// Read-only bundle used to score messages during rule evaluation.
 struct SpamScorer<'a> {
     config: &'a SpamConfig,
     model: &'a SpamModel,
 }
 
+/// Mailbox that sent messages are filed into: Sent, falling back to Drafts.
+fn sent_mailbox_id(mailboxes: &[Mailbox]) -> Option<String> {
+    ["sent", "drafts"].iter().find_map(|role| {
+        mailboxes
+            .iter()
+            .find(|m| m.role.as_deref() == Some(*role))
+            .map(|m| m.id.clone())
+    })
+}
+
+/// Send a rule forward of email `id` to `to` from the first identity, filed
+/// into Sent like any other outgoing message.
+fn forward_email(
+    client: &JmapClient,
+    cached_mailboxes: &mut Vec<Mailbox>,
+    id: &str,
+    to: &str,
+) -> Result<(), String> {
+    let email = client
+        .get_email_for_reply(id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("email {} not found", id))?;
+    if cached_mailboxes.is_empty() {
+        *cached_mailboxes = client.get_mailboxes().map_err(|e| e.to_string())?;
+    }
+    let mailbox_id = sent_mailbox_id(cached_mailboxes)
+        .ok_or_else(|| "No Sent or Drafts mailbox found".to_string())?;
+    let identity = client
+        .get_identities()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .next()
+        .ok_or_else(|| "No sending identity available".to_string())?;
+    let from = crate::jmap::types::EmailAddress {
+        name: Some(identity.name).filter(|n| !n.is_empty()),
+        email: Some(identity.email.clone()),
+    }
+    .to_string();
+
+    let draft = compose::build_rule_forward(&email, &from, to)?;
+    let raw_mime = compose::build_mime_message(&draft);
+    let sent_id = client
+        .send_email(&raw_mime, Some(&identity.email), &mailbox_id)
+        .map_err(|e| e.to_string())?;
+    log_info!(
+        "[Backend] Forwarded email {} to {} (sent id={})",
+        id,
+        to,
+        sent_id
+    );
+    // The forward went out; failing here would queue a second one.
+    if let Err(e) = client.set_email_keyword(id, rules::FORWARDED_KEYWORD, true) {
+        log_warn!("[Backend] Failed to mark email {} forwarded: {}", id, e);
+    }
+    Ok(())
+}
+
 fn mailbox_is_inbox(mailboxes: &[Mailbox], mailbox_id: &str) -> bool {
     mailboxes
         .iter()
@@ -2266,7 +4306,7 @@ fn annotate_inbox_spam(
                 continue;
             }
         };
-        let msg = spam::RawMessage::from_bytes(raw.as_bytes());
+        let msg = spam::RawMessage::from_bytes(&raw);
         let (score, verdict) = scorer.model.classify(
             &msg,
             scorer.config.threshold,
@@ -2300,17 +4340,10 @@ fn run_rules_for_mailbox(
     mailbox_id: &str,
     cache: Option<&Cache>,
     spam: Option<&SpamScorer>,
+    progress: Option<ProgressFn>,
 ) -> Result<RulesRunResult, String> {
-    if rules.is_empty() {
-        return Ok(RulesRunResult {
-            scanned: 0,
-            matched_rules: 0,
-            actions: 0,
-        });
-    }
-
-    let ids = fetch_all_mailbox_email_ids(client, mailbox_id)?;
-    if ids.is_empty() {
+    let runs_here = rules::runs_on_demand(mailbox_id, mailboxes);
+    if !rules.iter().any(&runs_here) {
         return Ok(RulesRunResult {
             scanned: 0,
             matched_rules: 0,
@@ -2318,10 +4351,17 @@ fn run_rules_for_mailbox(
         });
     }
 
-    let mut emails = fetch_rule_emails_chunked(client, &ids, custom_headers)?;
+    let emails = fetch_mailbox_for_rules(
+        client,
+        mailboxes,
+        custom_headers,
+        mailbox_id,
+        spam,
+        progress,
+    )?;
     let scanned = emails.len();
-    annotate_inbox_spam(client, spam, mailboxes, mailbox_id, &mut emails);
-    let applications = rules::apply_rules(rules, &emails, mailboxes, my_email_regex);
+    let applications =
+        rules::apply_rules_where(rules, &emails, mailboxes, my_email_regex, runs_here);
     let matched_rules = applications.len();
     let actions = applications.iter().map(|a| a.actions.len()).sum::<usize>();
     if !applications.is_empty() {
@@ -2354,77 +4394,134 @@ fn run_rules_for_mailbox(
     })
 }
 
-fn preview_rules_for_mailbox(
+fn test_rules_for_email(
     client: &JmapClient,
     mailboxes: &[Mailbox],
     rules: &[CompiledRule],
     custom_headers: &[String],
     my_email_regex: &Regex,
-    mailbox_id: &str,
+    id: &str,
     spam: Option<&SpamScorer>,
-) -> Result<RulesDryRunResult, String> {
-    if rules.is_empty() {
-        return Ok(RulesDryRunResult {
-            scanned: 0,
-            matched_rules: 0,
-            actions: 0,
-            entries: Vec::new(),
-        });
+) -> Result<RulesTestResult, String> {
+    let mut emails = fetch_rule_emails_chunked(client, &[id.to_string()], custom_headers, None)?;
+    let Some(email) = emails.first() else {
+        return Err(format!("email {} not found", id));
+    };
+    // Score like the sync path would, so spam-verdict rules can be tested.
+    if let Some(inbox_id) = email
+        .mailbox_ids
+        .keys()
+        .find(|m| mailbox_is_inbox(mailboxes, m))
+        .cloned()
+    {
+        annotate_inbox_spam(client, spam, mailboxes, &inbox_id, &mut emails);
     }
+    let email = &emails[0];
+
+    let matches = rules::apply_rules(rules, &emails, mailboxes, my_email_regex)
+        .into_iter()
+        .map(|app| {
+            let actions = app.actions.iter().map(format_rule_action).collect();
+            (app.rule_name, actions)
+        })
+        .collect();
+    Ok(RulesTestResult {
+        subject: email
+            .subject
+            .clone()
+            .unwrap_or_else(|| "(no subject)".to_string()),
+        from: email
+            .from
+            .as_ref()
+            .and_then(|f| f.first())
+            .map(|a| a.to_string())
+            .unwrap_or_else(|| "(unknown)".to_string()),
+        matches,
+        traces: rules::trace_rules(rules, email, my_email_regex),
+    })
+}
 
+/// Every email in `mailbox_id`, fetched with the headers rules look at and
+/// spam-scored like the sync path would.
+fn fetch_mailbox_for_rules(
+    client: &JmapClient,
+    mailboxes: &[Mailbox],
+    custom_headers: &[String],
+    mailbox_id: &str,
+    spam: Option<&SpamScorer>,
+    progress: Option<ProgressFn>,
+) -> Result<Vec<Email>, String> {
     let ids = fetch_all_mailbox_email_ids(client, mailbox_id)?;
     if ids.is_empty() {
-        return Ok(RulesDryRunResult {
-            scanned: 0,
-            matched_rules: 0,
-            actions: 0,
-            entries: Vec::new(),
-        });
+        return Ok(Vec::new());
     }
-
-    let mut emails = fetch_rule_emails_chunked(client, &ids, custom_headers)?;
-    let scanned = emails.len();
+    let mut emails = fetch_rule_emails_chunked(client, &ids, custom_headers, progress)?;
     annotate_inbox_spam(client, spam, mailboxes, mailbox_id, &mut emails);
-    let mut entries = Vec::new();
-    let email_by_id: HashMap<String, &Email> = emails.iter().map(|e| (e.id.clone(), e)).collect();
-    let applications = rules::apply_rules(rules, &emails, mailboxes, my_email_regex);
-    let matched_rules = applications.len();
-    let actions = applications.iter().map(|a| a.actions.len()).sum::<usize>();
+    Ok(emails)
+}
 
-    for app in applications {
-        if let Some(email) = email_by_id.get(&app.email_id) {
-            let from = email
+/// What the rules that run in `mailbox_id` would do to `emails`, without
+/// doing it.
+fn preview_rules_for_mailbox(
+    mailboxes: &[Mailbox],
+    rules: &[CompiledRule],
+    my_email_regex: &Regex,
+    mailbox_id: &str,
+    emails: &[Email],
+) -> RulesDryRunResult {
+    let runs_here = rules::runs_on_demand(mailbox_id, mailboxes);
+    let scanned = emails.len();
+    let scanned_emails: Vec<RulesScannedEmail> = emails
+        .iter()
+        .map(|email| RulesScannedEmail {
+            id: email.id.clone(),
+            received_at: email
+                .received_at
+                .as_deref()
+                .map(|d| d.chars().take(10).collect::<String>())
+                .unwrap_or_else(|| "(unknown)".to_string()),
+            from: email
                 .from
                 .as_ref()
                 .and_then(|f| f.first())
                 .map(|a| a.to_string())
-                .unwrap_or_else(|| "(unknown)".to_string());
-            let received_at = email
-                .received_at
-                .as_deref()
-                .map(|d| d.chars().take(10).collect::<String>())
-                .unwrap_or_else(|| "(unknown)".to_string());
-            let subject = email
+                .unwrap_or_else(|| "(unknown)".to_string()),
+            subject: email
                 .subject
                 .clone()
-                .unwrap_or_else(|| "(no subject)".to_string());
+                .unwrap_or_else(|| "(no subject)".to_string()),
+            matched: rules::any_rule_matches_where(rules, email, my_email_regex, &runs_here),
+        })
+        .collect();
+    let mut entries = Vec::new();
+    let email_by_id: HashMap<&str, &RulesScannedEmail> =
+        scanned_emails.iter().map(|e| (e.id.as_str(), e)).collect();
+    let applications =
+        rules::apply_rules_where(rules, emails, mailboxes, my_email_regex, runs_here);
+    let matched_rules = applications.len();
+    let actions = applications.iter().map(|a| a.actions.len()).sum::<usize>();
+
+    for app in applications {
+        if let Some(email) = email_by_id.get(app.email_id.as_str()) {
             let action_names = app.actions.iter().map(format_rule_action).collect();
             entries.push(RulesDryRunEntry {
-                received_at,
-                from,
-                subject,
+                id: app.email_id.clone(),
+                received_at: email.received_at.clone(),
+                from: email.from.clone(),
+                subject: email.subject.clone(),
                 rule_name: app.rule_name,
                 actions: action_names,
             });
         }
     }
 
-    Ok(RulesDryRunResult {
+    RulesDryRunResult {
         scanned,
         matched_rules,
         actions,
         entries,
-    })
+        scanned_emails,
+    }
 }
 
 const MAILBOX_QUERY_CHUNK_SIZE: u32 = 500;
@@ -2474,90 +4571,552 @@ fn format_rule_action(action: &rules::Action) -> String {
         rules::Action::Unflag => "unflag".to_string(),
         rules::Action::Move { target } => format!("move_to={}", target),
         rules::Action::Delete => "delete".to_string(),
+        rules::Action::SetKeyword { keyword, value } => {
+            format!("keyword {}={}", keyword, value)
+        }
+        rules::Action::Forward { to } => format!("forward_to={}", to),
+        rules::Action::Stop => "stop".to_string(),
     }
 }
 
-fn next_generated_op_id() -> u64 {
-    GENERATED_OP_ID.fetch_add(1, Ordering::Relaxed)
+/// Directory for saved attachments and exported messages: `[ui].download_dir`
+/// when configured, else `$XDG_DOWNLOAD_DIR`, else `~/Downloads`.
+pub fn download_dir(configured: Option<&std::path::Path>) -> std::path::PathBuf {
+    if let Some(dir) = configured {
+        return dir.to_path_buf();
+    }
+    std::env::var("XDG_DOWNLOAD_DIR")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|_| {
+            std::env::var("HOME")
+                .map(std::path::PathBuf::from)
+                .unwrap_or_else(|_| std::env::temp_dir())
+                .join("Downloads")
+        })
 }
 
-fn queued_mutations_for_rule_actions(
-    applications: &[rules::RuleApplication],
-    mailboxes: &[Mailbox],
-) -> Vec<QueuedMutation> {
-    let trash_id = mailboxes
-        .iter()
-        .find(|m| m.role.as_deref() == Some("trash"))
-        .map(|m| m.id.clone());
-    let mut out = Vec::new();
+/// Prefix of the per-process directories holding attachments staged for
+/// opening, followed by the process id.
+const OPEN_ATTACHMENTS_PREFIX: &str = "tmc-open-";
 
-    for app in applications {
-        for action in &app.actions {
-            let op_id = next_generated_op_id();
-            let queued =
-                match action {
-                    rules::Action::MarkRead => Some(QueuedMutation::MarkRead {
-                        op_id,
-                        id: app.email_id.clone(),
-                    }),
-                    rules::Action::MarkUnread => Some(QueuedMutation::MarkUnread {
-                        op_id,
-                        id: app.email_id.clone(),
-                    }),
-                    rules::Action::Flag => Some(QueuedMutation::SetFlagged {
-                        op_id,
-                        id: app.email_id.clone(),
-                        flagged: true,
-                    }),
-                    rules::Action::Unflag => Some(QueuedMutation::SetFlagged {
-                        op_id,
-                        id: app.email_id.clone(),
-                        flagged: false,
-                    }),
-                    rules::Action::Move { target } => rules::resolve_mailbox_id(target, mailboxes)
-                        .map(|to_mailbox_id| QueuedMutation::MoveEmail {
-                            op_id,
-                            id: app.email_id.clone(),
-                            to_mailbox_id,
-                        }),
-                    rules::Action::Delete => {
-                        trash_id
-                            .as_ref()
-                            .map(|to_mailbox_id| QueuedMutation::MoveEmail {
-                                op_id,
-                                id: app.email_id.clone(),
-                                to_mailbox_id: to_mailbox_id.clone(),
-                            })
-                    }
-                };
-            if let Some(queued) = queued {
-                out.push(queued);
+/// This process's directory for attachments staged for opening. Openers
+/// often hand the file to an already running viewer and exit at once, so
+/// the files stay until tmc exits.
+fn open_attachments_root() -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("{}{}", OPEN_ATTACHMENTS_PREFIX, std::process::id()))
+}
+
+/// A fresh per-attachment directory under `open_attachments_root`.
+fn open_attachment_dir() -> std::path::PathBuf {
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    open_attachments_root().join(stamp.to_string())
+}
+
+/// Remove the attachments this process staged for opening.
+pub fn remove_open_attachments() {
+    let _ = std::fs::remove_dir_all(open_attachments_root());
+}
+
+/// Remove attachments staged by tmc processes that are no longer running,
+/// e.g. after a crash.
+pub fn remove_stale_open_attachments() {
+    remove_stale_open_attachments_in(&std::env::temp_dir());
+}
+
+fn remove_stale_open_attachments_in(base: &std::path::Path) {
+    let Ok(entries) = std::fs::read_dir(base) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let pid = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.strip_prefix(OPEN_ATTACHMENTS_PREFIX))
+            .and_then(|pid| pid.parse::<libc::pid_t>().ok());
+        if let Some(pid) = pid {
+            if !process_is_running(pid) {
+                let _ = std::fs::remove_dir_all(entry.path());
             }
         }
     }
-    out
 }
 
-fn queued_mutations_for_retention(candidates: &[RetentionCandidate]) -> Vec<QueuedMutation> {
-    candidates
-        .iter()
-        .map(|c| QueuedMutation::DestroyEmail {
-            op_id: next_generated_op_id(),
-            id: c.id.clone(),
-        })
-        .collect()
+fn process_is_running(pid: libc::pid_t) -> bool {
+    // Signal 0 only checks for the process; EPERM means it exists but
+    // belongs to someone else.
+    let found = unsafe { libc::kill(pid, 0) } == 0;
+    found || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
 }
 
-fn collect_retention_candidates(
+/// Download a blob and write it to `dir` under its sanitized name.
+fn save_attachment(
     client: &JmapClient,
-    mailboxes: &[Mailbox],
-    policies: &[RetentionPolicyConfig],
-) -> Result<Vec<RetentionCandidate>, String> {
+    blob_id: &str,
+    name: &str,
+    content_type: &str,
+    dir: &std::path::Path,
+) -> Result<std::path::PathBuf, String> {
+    let bytes = client
+        .download_blob(blob_id, name, content_type)
+        .map_err(|e| e.to_string())?;
+
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create download dir: {}", e))?;
+
+    let path = dir.join(safe_file_name(name));
+    std::fs::write(&path, &bytes).map_err(|e| format!("Failed to write file: {}", e))?;
+
+    log_info!(
+        "[Backend] Attachment saved: {} ({} bytes)",
+        path.display(),
+        bytes.len()
+    );
+    Ok(path)
+}
+
+/// One attachment of a `DownloadAttachments` batch.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttachmentDownload {
+    pub blob_id: String,
+    pub name: String,
+    pub content_type: String,
+}
+
+/// The attachments of `email` to save with "download all": parts without a
+/// blob are skipped, as are unnamed inline images unless `include_inline`.
+/// Names are made unique so no file overwrites another.
+pub fn attachment_downloads(email: &Email, include_inline: bool) -> Vec<AttachmentDownload> {
+    let mut used = HashSet::new();
+    let mut downloads = Vec::new();
+    let parts = email.attachments.as_deref().unwrap_or_default();
+    for (i, part) in parts.iter().enumerate() {
+        let Some(blob_id) = part.blob_id.clone() else {
+            continue;
+        };
+        if !include_inline && part.is_unnamed_inline_image() {
+            continue;
+        }
+        let base = part
+            .name
+            .as_deref()
+            .filter(|n| !n.trim().is_empty())
+            .map(safe_file_name)
+            .unwrap_or_else(|| format!("attachment-{}", i + 1));
+        let mut name = base.clone();
+        let mut n = 2;
+        while !used.insert(name.clone()) {
+            name = match base.rsplit_once('.') {
+                Some((stem, ext)) if !stem.is_empty() => format!("{}-{}.{}", stem, n, ext),
+                _ => format!("{}-{}", base, n),
+            };
+            n += 1;
+        }
+        downloads.push(AttachmentDownload {
+            blob_id,
+            name,
+            content_type: part
+                .r#type
+                .clone()
+                .unwrap_or_else(|| "application/octet-stream".to_string()),
+        });
+    }
+    downloads
+}
+
+/// Make a server-supplied name safe to join onto the target directory:
+/// separators and control characters become `_`, and leading dots are
+/// stripped so the result is never `.`, `..` or a hidden file.
+pub fn safe_file_name(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let cleaned = cleaned.trim_start_matches(['.', ' ']).trim_end();
+    if cleaned.is_empty() {
+        "download".to_string()
+    } else {
+        cleaned.to_string()
+    }
+}
+
+/// Resolve `name` to a mailbox id, creating the mailbox when the account
+/// has none by that name.
+fn ensure_mailbox(
+    client: &JmapClient,
+    cached_mailboxes: &mut Vec<Mailbox>,
+    cache: Option<&Cache>,
+    name: &str,
+) -> Result<String, String> {
+    if let Some(id) = rules::resolve_mailbox_id(name, cached_mailboxes) {
+        return Ok(id);
+    }
+    log_info!("[Backend] creating missing mailbox '{}'", name);
+    client
+        .create_mailbox(name)
+        .map_err(|e| format!("cannot create folder '{}': {}", name, e))?;
+    *cached_mailboxes = client.get_mailboxes().map_err(|e| e.to_string())?;
+    if let Some(cache) = cache {
+        cache.put_mailboxes(cached_mailboxes);
+    }
+    rules::resolve_mailbox_id(name, cached_mailboxes)
+        .ok_or_else(|| format!("folder '{}' was created but is not listed", name))
+}
+
+/// Split due snoozes into the ids still in their snooze folder, which go back
+/// to the inbox, and the ids moved elsewhere or deleted since, whose snooze is
+/// just dropped. `current` holds the emails as the server has them now.
+fn split_due_snoozes(
+    due: Vec<(String, Option<String>)>,
+    current: &[Email],
+) -> (Vec<String>, Vec<String>) {
+    let mut wake = Vec::new();
+    let mut stale = Vec::new();
+    for (id, folder) in due {
+        let still_snoozed = current.iter().find(|e| e.id == id).is_some_and(|email| {
+            folder
+                .as_ref()
+                .is_none_or(|folder| email.mailbox_ids.contains_key(folder))
+        });
+        if still_snoozed {
+            wake.push(id);
+        } else {
+            stale.push(id);
+        }
+    }
+    (wake, stale)
+}
+
+/// Move snoozed emails whose wake time (as of `now`) has passed back to the
+/// inbox, unless they have left the snooze folder since. Each move goes
+/// through the durable queue, so a failed move is replayed later.
+#[allow(clippy::too_many_arguments)]
+fn wake_snoozed_emails(
+    client: &JmapClient,
+    cache: &Cache,
+    now: u64,
+    cached_mailboxes: &mut Vec<Mailbox>,
+    rules: &[CompiledRule],
+    custom_headers: &[String],
+    my_email_regex: &Regex,
+) {
+    let due = cache.due_snoozes(now);
+    if due.is_empty() {
+        return;
+    }
+    let Some(inbox_id) = cached_mailboxes
+        .iter()
+        .find(|m| m.role.as_deref() == Some("inbox"))
+        .map(|m| m.id.clone())
+    else {
+        log_warn!(
+            "[Backend] {} snoozed email(s) due but no inbox mailbox is known",
+            due.len()
+        );
+        return;
+    };
+    let ids: Vec<String> = due.iter().map(|(id, _)| id.clone()).collect();
+    let current = match client.get_emails(&ids) {
+        Ok(emails) => emails,
+        Err(e) => {
+            log_warn!("[Backend] cannot check snoozed emails: {}", e);
+            return;
+        }
+    };
+    let (wake, stale) = split_due_snoozes(due, &current);
+    for id in stale {
+        log_info!(
+            "[Backend] snoozed email {} left the snooze folder; not waking it",
+            id
+        );
+        cache.remove_snooze(&id);
+    }
+    for id in wake {
+        let op = QueuedMutation::MoveEmail {
+            op_id: next_generated_op_id(),
+            id: id.clone(),
+            to_mailbox_id: inbox_id.clone(),
+        };
+        match process_mutation_via_queue(
+            client,
+            &op,
+            cached_mailboxes,
+            rules,
+            custom_headers,
+            my_email_regex,
+            Some(cache),
+        ) {
+            Ok(()) => {
+                log_info!("[Backend] woke snoozed email {}", id);
+                cache.remove_snooze(&id);
+            }
+            Err(e) => log_warn!("[Backend] failed to wake snoozed email {}: {}", id, e),
+        }
+    }
+}
+
+fn next_generated_op_id() -> u64 {
+    GENERATED_OP_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Archive fetched messages that arrived in a thread after it was muted, or
+/// mark them read when the account has no archive mailbox. Returns the ids
+/// moved out of the list.
+#[allow(clippy::too_many_arguments)]
+fn apply_thread_mutes(
+    client: &JmapClient,
+    emails: &[Email],
+    muted: &MutedThreads,
+    cached_mailboxes: &mut Vec<Mailbox>,
+    rules: &[CompiledRule],
+    custom_headers: &[String],
+    my_email_regex: &Regex,
+    cache: Option<&Cache>,
+) -> HashSet<String> {
+    let archive_id = rules::resolve_mailbox_id("archive", cached_mailboxes);
+    let mut moved = HashSet::new();
+    for email in emails {
+        if !muted.mutes(email) {
+            continue;
+        }
+        let thread_id = email.thread_id.as_deref().unwrap_or_default();
+        let op_id = next_generated_op_id();
+        let op = match archive_id {
+            Some(ref to_mailbox_id) if !email.mailbox_ids.contains_key(to_mailbox_id) => {
+                QueuedMutation::MoveEmail {
+                    op_id,
+                    id: email.id.clone(),
+                    to_mailbox_id: to_mailbox_id.clone(),
+                }
+            }
+            _ if !email.keywords.contains_key("$seen") => QueuedMutation::MarkRead {
+                op_id,
+                id: email.id.clone(),
+            },
+            _ => continue,
+        };
+        log_info!(
+            "[Mute] thread {} is muted; {} email {}",
+            thread_id,
+            if matches!(op, QueuedMutation::MoveEmail { .. }) {
+                "archiving"
+            } else {
+                "marking read"
+            },
+            email.id
+        );
+        match process_mutation_via_queue(
+            client,
+            &op,
+            cached_mailboxes,
+            rules,
+            custom_headers,
+            my_email_regex,
+            cache,
+        ) {
+            Ok(()) => {
+                if matches!(op, QueuedMutation::MoveEmail { .. }) {
+                    moved.insert(email.id.clone());
+                }
+            }
+            Err(e) => log_warn!("[Mute] failed to act on email {}: {}", email.id, e),
+        }
+    }
+    moved
+}
+
+fn queued_mutations_for_rule_actions(
+    applications: &[rules::RuleApplication],
+    mailboxes: &[Mailbox],
+) -> Vec<QueuedMutation> {
+    let trash_id = mailboxes
+        .iter()
+        .find(|m| m.role.as_deref() == Some("trash"))
+        .map(|m| m.id.clone());
+    let mut out = Vec::new();
+
+    for app in applications {
+        for action in &app.actions {
+            let op_id = next_generated_op_id();
+            let queued = match action {
+                rules::Action::MarkRead => Some(QueuedMutation::MarkRead {
+                    op_id,
+                    id: app.email_id.clone(),
+                }),
+                rules::Action::MarkUnread => Some(QueuedMutation::MarkUnread {
+                    op_id,
+                    id: app.email_id.clone(),
+                }),
+                rules::Action::Flag => Some(QueuedMutation::SetFlagged {
+                    op_id,
+                    id: app.email_id.clone(),
+                    flagged: true,
+                }),
+                rules::Action::Unflag => Some(QueuedMutation::SetFlagged {
+                    op_id,
+                    id: app.email_id.clone(),
+                    flagged: false,
+                }),
+                rules::Action::Move { target } => {
+                    rules::resolve_mailbox_id(target, mailboxes).map(|to_mailbox_id| {
+                        QueuedMutation::MoveEmail {
+                            op_id,
+                            id: app.email_id.clone(),
+                            to_mailbox_id,
+                        }
+                    })
+                }
+                rules::Action::SetKeyword { keyword, value } => Some(QueuedMutation::SetKeyword {
+                    op_id,
+                    id: app.email_id.clone(),
+                    keyword: keyword.clone(),
+                    value: *value,
+                }),
+                rules::Action::Delete => {
+                    trash_id
+                        .as_ref()
+                        .map(|to_mailbox_id| QueuedMutation::MoveEmail {
+                            op_id,
+                            id: app.email_id.clone(),
+                            to_mailbox_id: to_mailbox_id.clone(),
+                        })
+                }
+                rules::Action::Forward { to } => Some(QueuedMutation::Forward {
+                    op_id,
+                    id: app.email_id.clone(),
+                    to: to.clone(),
+                }),
+                rules::Action::Stop => None,
+            };
+            if let Some(queued) = queued {
+                out.push(queued);
+            }
+        }
+    }
+    out
+}
+
+fn queued_mutations_for_retention(candidates: &[RetentionCandidate]) -> Vec<QueuedMutation> {
+    candidates
+        .iter()
+        .map(|c| QueuedMutation::DestroyEmail {
+            op_id: next_generated_op_id(),
+            id: c.id.clone(),
+        })
+        .collect()
+}
+
+/// One `DestroyEmail` per message listed when an empty was confirmed.
+fn queued_mutations_for_empty(ids: &[String]) -> Vec<QueuedMutation> {
+    ids.iter()
+        .map(|id| QueuedMutation::DestroyEmail {
+            op_id: next_generated_op_id(),
+            id: id.clone(),
+        })
+        .collect()
+}
+
+/// The single undo-log entry for emptying `mailbox_name`, in place of one
+/// per destroyed message.
+fn empty_undo_entry(mailbox_name: &str) -> UndoEntry {
+    UndoEntry::NotUndoable {
+        op_id: None,
+        description: format!("empty {}", mailbox_name),
+    }
+}
+
+/// Destroy `candidates` one by one, collecting failures rather than
+/// stopping at the first one.
+fn expire_retention_candidates(
+    client: &JmapClient,
+    candidates: &[RetentionCandidate],
+    cached_mailboxes: &mut Vec<Mailbox>,
+    rules: &[CompiledRule],
+    custom_headers: &[String],
+    my_email_regex: &Regex,
+    cache: Option<&Cache>,
+) -> RetentionExecutionResult {
+    let mut deleted = 0usize;
+    let mut failed_batches = Vec::new();
+    for op in &queued_mutations_for_retention(candidates) {
+        match process_mutation_via_queue(
+            client,
+            op,
+            cached_mailboxes,
+            rules,
+            custom_headers,
+            my_email_regex,
+            cache,
+        ) {
+            Ok(()) => deleted += 1,
+            Err(e) => failed_batches.push(e),
+        }
+    }
+    RetentionExecutionResult {
+        deleted,
+        failed_batches,
+    }
+}
+
+/// The `only_read`/`only_unflagged` qualifiers: unread or flagged mail is
+/// kept when the policy asks for it.
+fn retention_keywords_allow(policy: &RetentionPolicyConfig, email: &Email) -> bool {
+    let has = |keyword: &str| email.keywords.get(keyword).copied().unwrap_or(false);
+    (!policy.only_read || has("$seen")) && (!policy.only_unflagged || !has("$flagged"))
+}
+
+/// The emails in a policy's folder that it expires: everything older than
+/// `days`, or everything past the newest `keep_newest`, minus mail the
+/// keyword qualifiers keep.
+fn select_retention_expired(
+    policy: &RetentionPolicyConfig,
+    emails: Vec<Email>,
+    today_days: i64,
+) -> Vec<Email> {
+    let expired: Vec<Email> = match (policy.keep_newest, policy.days) {
+        (Some(keep), _) => {
+            // Newest first by instant; mail without a usable date is kept.
+            let mut dated: Vec<(i64, Email)> = emails
+                .into_iter()
+                .filter_map(|e| Some((rules::email_received_epoch(&e)?, e)))
+                .collect();
+            dated.sort_by_key(|(at, _)| std::cmp::Reverse(*at));
+            dated
+                .into_iter()
+                .skip(keep as usize)
+                .map(|(_, e)| e)
+                .collect()
+        }
+        (None, Some(days)) => {
+            let cutoff_days = today_days - (days as i64);
+            emails
+                .into_iter()
+                .filter(|e| rules::email_received_days(e).is_some_and(|d| d < cutoff_days))
+                .collect()
+        }
+        (None, None) => Vec::new(),
+    };
+    expired
+        .into_iter()
+        .filter(|e| retention_keywords_allow(policy, e))
+        .collect()
+}
+
+fn collect_retention_candidates(
+    client: &JmapClient,
+    mailboxes: &[Mailbox],
+    policies: &[RetentionPolicyConfig],
+) -> Result<Vec<RetentionCandidate>, String> {
     if policies.is_empty() {
         return Ok(Vec::new());
     }
 
-    let today_days = current_days_since_epoch()?;
+    let today_days = rules::current_days_since_epoch()?;
     let mut candidates = Vec::new();
     let mut seen_email_ids = HashSet::new();
 
@@ -2575,8 +5134,9 @@ fn collect_retention_candidates(
             .find(|m| m.id == mailbox_id)
             .map(|m| m.name.clone())
             .unwrap_or_else(|| policy.folder.clone());
-        let cutoff_days = today_days - (policy.days as i64);
 
+        // `keep_newest` ranks the whole folder, so gather it before selecting.
+        let mut folder_emails = Vec::new();
         let mut position = 0u32;
         loop {
             let query = client
@@ -2586,39 +5146,7 @@ fn collect_retention_candidates(
                 break;
             }
 
-            let emails = fetch_emails_chunked(client, &query.ids, &[])?;
-            for email in emails {
-                if !seen_email_ids.insert(email.id.clone()) {
-                    continue;
-                }
-                let Some(received_days) = email_received_days(&email) else {
-                    continue;
-                };
-                if received_days >= cutoff_days {
-                    continue;
-                }
-
-                let from = email
-                    .from
-                    .as_ref()
-                    .and_then(|f| f.first())
-                    .map(|a| a.to_string())
-                    .unwrap_or_else(|| "(unknown)".to_string());
-                let received_at = email
-                    .received_at
-                    .as_deref()
-                    .map(|d| d.chars().take(10).collect::<String>())
-                    .unwrap_or_else(|| "(unknown)".to_string());
-
-                candidates.push(RetentionCandidate {
-                    id: email.id,
-                    mailbox: mailbox_name.clone(),
-                    policy: policy.name.clone(),
-                    received_at,
-                    from,
-                    subject: email.subject.unwrap_or_else(|| "(no subject)".to_string()),
-                });
-            }
+            folder_emails.extend(fetch_emails_chunked(client, &query.ids, &[])?);
 
             let loaded = query.ids.len() as u32;
             position = query.position.saturating_add(loaded);
@@ -2631,6 +5159,32 @@ fn collect_retention_candidates(
                 }
             }
         }
+
+        for email in select_retention_expired(policy, folder_emails, today_days) {
+            if !seen_email_ids.insert(email.id.clone()) {
+                continue;
+            }
+            let from = email
+                .from
+                .as_ref()
+                .and_then(|f| f.first())
+                .map(|a| a.to_string())
+                .unwrap_or_else(|| "(unknown)".to_string());
+            let received_at = email
+                .received_at
+                .as_deref()
+                .map(|d| d.chars().take(10).collect::<String>())
+                .unwrap_or_else(|| "(unknown)".to_string());
+
+            candidates.push(RetentionCandidate {
+                id: email.id,
+                mailbox: mailbox_name.clone(),
+                policy: policy.name.clone(),
+                received_at,
+                from,
+                subject: email.subject.unwrap_or_else(|| "(no subject)".to_string()),
+            });
+        }
     }
 
     candidates.sort_by(|a, b| {
@@ -2642,33 +5196,103 @@ fn collect_retention_candidates(
     Ok(candidates)
 }
 
-fn current_days_since_epoch() -> Result<i64, String> {
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map_err(|e| format!("system clock error: {}", e))?;
-    Ok((now.as_secs() / 86_400) as i64)
-}
+/// Watch loop behind `BackendCommand::Watch`. Waits on the EventSource when
+/// the server has one (falling back to polling if it fails), refreshes
+/// mailboxes via `Mailbox/changes`, and sends the ones whose counts moved.
+fn watch_state_changes(
+    client: &JmapClient,
+    interval_secs: u64,
+    resp_tx: &mpsc::Sender<BackendResponse>,
+    stop: &AtomicBool,
+) {
+    let mut synced = None;
+    let mut known = match fetch_mailboxes_incremental(client, &mut synced) {
+        Ok(mailboxes) => mailboxes,
+        Err(e) => {
+            log_warn!("[Watch] initial mailbox fetch failed: {}", e);
+            Vec::new()
+        }
+    };
+    let mut events = match client.open_event_source(interval_secs) {
+        Ok(events) => {
+            log_info!("[Watch] using push notifications");
+            Some(events)
+        }
+        Err(e) => {
+            log_info!(
+                "[Watch] push unavailable ({}), polling every {}s",
+                e,
+                interval_secs
+            );
+            None
+        }
+    };
+    if resp_tx
+        .send(BackendResponse::WatchStarted {
+            push: events.is_some(),
+        })
+        .is_err()
+    {
+        return;
+    }
 
-fn email_received_days(email: &Email) -> Option<i64> {
-    let received = email.received_at.as_deref()?;
-    let y = received.get(0..4)?.parse::<i32>().ok()?;
-    let m = received.get(5..7)?.parse::<u32>().ok()?;
-    let d = received.get(8..10)?.parse::<u32>().ok()?;
-    ymd_to_days_since_epoch(y, m, d)
-}
+    while !stop.load(Ordering::SeqCst) {
+        match events.as_mut().map(|es| es.next_change()) {
+            Some(Ok(types)) => {
+                log_debug!("[Watch] state change: {:?}", types);
+            }
+            Some(Err(e)) => {
+                log_warn!(
+                    "[Watch] push channel failed ({}), falling back to polling",
+                    e
+                );
+                events = None;
+                continue;
+            }
+            None => {
+                for _ in 0..interval_secs {
+                    if stop.load(Ordering::SeqCst) {
+                        return;
+                    }
+                    thread::sleep(Duration::from_secs(1));
+                }
+            }
+        }
+        if stop.load(Ordering::SeqCst) {
+            return;
+        }
 
-// Convert calendar date to day index since Unix epoch.
-fn ymd_to_days_since_epoch(year: i32, month: u32, day: u32) -> Option<i64> {
-    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
-        return None;
+        let current = match fetch_mailboxes_incremental(client, &mut synced) {
+            Ok(mailboxes) => mailboxes,
+            Err(e) => {
+                log_warn!("[Watch] mailbox fetch failed: {}", e);
+                continue;
+            }
+        };
+        let changed = changed_mailboxes(&known, &current);
+        known = current;
+        if !changed.is_empty()
+            && resp_tx
+                .send(BackendResponse::StateChanged { mailboxes: changed })
+                .is_err()
+        {
+            return;
+        }
     }
-    let y = year as i64 - if month <= 2 { 1 } else { 0 };
-    let era = if y >= 0 { y } else { y - 399 } / 400;
-    let yoe = y - era * 400;
-    let mp = month as i64 + if month > 2 { -3 } else { 9 };
-    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
-    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
-    Some(era * 146_097 + doe - 719_468)
+}
+
+/// Mailboxes in `current` that are new or whose total/unread counts differ
+/// from `previous`.
+fn changed_mailboxes(previous: &[Mailbox], current: &[Mailbox]) -> Vec<Mailbox> {
+    let counts: HashMap<&str, (u32, u32)> = previous
+        .iter()
+        .map(|m| (m.id.as_str(), (m.total_emails, m.unread_emails)))
+        .collect();
+    current
+        .iter()
+        .filter(|m| counts.get(m.id.as_str()) != Some(&(m.total_emails, m.unread_emails)))
+        .cloned()
+        .collect()
 }
 
 #[cfg(test)]
@@ -2701,6 +5325,84 @@ mod tests {
         }
     }
 
+    #[test]
+    fn fetch_chunks_keeps_order_and_fails_on_any_chunk() {
+        let ids: Vec<String> = (0..263).map(|i| format!("e{}", i)).collect();
+        let fetch = |chunk: &[String]| -> Result<Vec<Email>, JmapError> {
+            // Later chunks (of six) finish first to exercise reordering.
+            let index = chunk[0][1..].parse::<u64>().unwrap() / EMAIL_GET_CHUNK_SIZE as u64;
+            std::thread::sleep(Duration::from_millis((6 - index) * 5));
+            Ok(chunk.iter().map(|id| make_email(id)).collect())
+        };
+        for concurrency in [1, 4, 32] {
+            let emails = fetch_chunks(&ids, concurrency, fetch).unwrap();
+            let got: Vec<&str> = emails.iter().map(|e| e.id.as_str()).collect();
+            assert_eq!(got, ids, "concurrency {}", concurrency);
+        }
+
+        let err = fetch_chunks(&ids, 4, |chunk| {
+            if chunk.iter().any(|id| id == "e120") {
+                Err(JmapError::Api("boom".to_string()))
+            } else {
+                Ok(chunk.iter().map(|id| make_email(id)).collect())
+            }
+        })
+        .unwrap_err();
+        assert!(err.contains("boom"), "{}", err);
+        assert!(fetch_chunks(&[], 4, fetch).unwrap().is_empty());
+    }
+
+    #[test]
+    fn merge_page_prefers_fetched_and_follows_query_order() {
+        let mut stale = make_email("e2");
+        stale.subject = Some("old".to_string());
+        let previous = vec![make_email("e1"), stale, make_email("e3")];
+        let mut fresh = make_email("e2");
+        fresh.subject = Some("new".to_string());
+        let ids: Vec<String> = ["e0", "e2", "e1", "gone"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let page = merge_page(&ids, &previous, vec![make_email("e0"), fresh]);
+        let got: Vec<&str> = page.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(got, ["e0", "e2", "e1"]);
+        assert_eq!(page[1].subject.as_deref(), Some("new"));
+    }
+
+    #[test]
+    fn merge_mailboxes_applies_changes_in_place() {
+        let mailbox = |id: &str, unread: u32, sort_order: u32| Mailbox {
+            id: id.to_string(),
+            name: id.to_string(),
+            parent_id: None,
+            role: None,
+            total_emails: 10,
+            unread_emails: unread,
+            sort_order,
+        };
+        let previous = vec![
+            mailbox("inbox", 2, 1),
+            mailbox("old", 0, 2),
+            mailbox("archive", 0, 3),
+        ];
+        let fetched = vec![
+            mailbox("lists", 0, 9),
+            mailbox("inbox", 5, 1),
+            mailbox("drafts", 0, 4),
+        ];
+
+        let merged = merge_mailboxes(&previous, &["old".to_string()], fetched);
+        let got: Vec<(&str, u32)> = merged
+            .iter()
+            .map(|m| (m.id.as_str(), m.unread_emails))
+            .collect();
+        assert_eq!(
+            got,
+            [("inbox", 5), ("archive", 0), ("drafts", 0), ("lists", 0)]
+        );
+    }
+
     #[test]
     fn queued_rule_actions_compile_to_email_mutations() {
         let mailboxes = vec![
@@ -2767,6 +5469,167 @@ mod tests {
         assert!(move_targets.contains(&"trash".to_string()));
     }
 
+    #[test]
+    fn account_summary_reads_counts_without_opening_caches() {
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("XDG_CACHE_HOME", dir.path());
+        // Held open for the whole test, as the active backend would.
+        let cache = Cache::open("summary_other").unwrap();
+        cache.put_mailboxes(&[Mailbox {
+            id: "inbox".to_string(),
+            name: "Inbox".to_string(),
+            parent_id: None,
+            role: Some("inbox".to_string()),
+            total_emails: 9,
+            unread_emails: 4,
+            sort_order: 0,
+        }]);
+        let account = |name: &str| AccountConfig {
+            name: name.to_string(),
+            well_known_url: String::new(),
+            username: String::new(),
+            password_command: "exit 1".to_string(),
+            auth: Default::default(),
+            token_refresh_command: None,
+            identities: Vec::new(),
+            signature: None,
+            prefer_html: None,
+            color: None,
+            theme: None,
+            authserv_ids: Vec::new(),
+        };
+        let summary = spawn_account_summary(
+            vec![account("summary_active"), account("summary_other")],
+            HttpOptions::default(),
+        );
+
+        summary.request("summary_active", false);
+        match summary.responses.recv_timeout(Duration::from_secs(5)) {
+            Ok(BackendResponse::AccountUnread { account, result }) => {
+                assert_eq!(account, "summary_other");
+                assert_eq!(result, Ok(4));
+            }
+            _ => panic!("expected an AccountUnread reply"),
+        }
+        // The active account is skipped and nothing else is reported.
+        assert!(summary
+            .responses
+            .recv_timeout(Duration::from_millis(200))
+            .is_err());
+    }
+
+    #[test]
+    fn stale_open_attachment_dirs_are_removed() {
+        let base = tempfile::tempdir().unwrap();
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let dead_pid = child.id();
+        child.wait().unwrap();
+        let dead = base.path().join(format!("tmc-open-{}", dead_pid));
+        let live = base.path().join(format!("tmc-open-{}", std::process::id()));
+        let other = base.path().join("tmc-open-notes");
+        for dir in [&dead, &live, &other] {
+            std::fs::create_dir_all(dir.join("1")).unwrap();
+        }
+
+        remove_stale_open_attachments_in(base.path());
+        assert!(!dead.exists());
+        assert!(live.exists());
+        assert!(other.exists());
+    }
+
+    #[test]
+    fn undo_reverses_moves_and_flags_and_refuses_destroy() {
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("XDG_CACHE_HOME", dir.path());
+        let cache = Cache::open("backend_undo").unwrap();
+
+        let mut e1 = make_email("e1");
+        e1.mailbox_ids.insert("inbox".to_string(), true);
+        e1.keywords.insert("$flagged".to_string(), true);
+        cache.put_emails(&[e1]);
+
+        let move_op = QueuedMutation::MoveEmail {
+            op_id: 1,
+            id: "e1".to_string(),
+            to_mailbox_id: "archive".to_string(),
+        };
+        queue_user_mutation(Some(&cache), &move_op).unwrap();
+        // Already flagged: undoing this must leave the flag set.
+        let flag_op = QueuedMutation::SetFlagged {
+            op_id: 2,
+            id: "e1".to_string(),
+            flagged: true,
+        };
+        queue_user_mutation(Some(&cache), &flag_op).unwrap();
+        let destroy_op = QueuedMutation::DestroyEmail {
+            op_id: 3,
+            id: "e2".to_string(),
+        };
+        queue_user_mutation(Some(&cache), &destroy_op).unwrap();
+
+        let err = take_undo(Some(&cache), None).unwrap_err();
+        assert!(err.contains("cannot be undone"), "{}", err);
+
+        let (description, ops) = take_undo(Some(&cache), None).unwrap();
+        assert_eq!(description, "flag");
+        assert!(matches!(
+            ops.as_slice(),
+            [QueuedMutation::SetFlagged { flagged: true, .. }]
+        ));
+
+        let (description, ops) = take_undo(Some(&cache), None).unwrap();
+        assert_eq!(description, "move");
+        assert!(matches!(
+            ops.as_slice(),
+            [QueuedMutation::SetMailboxes { id, mailbox_ids, .. }]
+                if id == "e1" && mailbox_ids == &["inbox".to_string()]
+        ));
+
+        assert_eq!(
+            take_undo(Some(&cache), None).unwrap_err(),
+            "nothing to undo"
+        );
+    }
+
+    #[test]
+    fn undo_of_a_move_skips_newer_entries_and_restores_every_mailbox() {
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("XDG_CACHE_HOME", dir.path());
+        let cache = Cache::open("backend_undo_move").unwrap();
+
+        let mut e1 = make_email("e1");
+        e1.mailbox_ids.insert("work".to_string(), true);
+        e1.mailbox_ids.insert("inbox".to_string(), true);
+        cache.put_emails(&[e1, make_email("e2")]);
+
+        let move_op = QueuedMutation::MoveEmail {
+            op_id: 7,
+            id: "e1".to_string(),
+            to_mailbox_id: "archive".to_string(),
+        };
+        queue_user_mutation(Some(&cache), &move_op).unwrap();
+        let newer = QueuedMutation::SetFlagged {
+            op_id: 7,
+            id: "e2".to_string(),
+            flagged: true,
+        };
+        queue_user_mutation(Some(&cache), &newer).unwrap();
+
+        let (description, ops) = take_undo(Some(&cache), Some(&(7, "e1".to_string()))).unwrap();
+        assert_eq!(description, "move");
+        for op in &ops {
+            apply_local_mutation(Some(&cache), op);
+        }
+        let restored = cache.get_email("e1").unwrap();
+        let mut mailbox_ids: Vec<&str> = restored.mailbox_ids.keys().map(String::as_str).collect();
+        mailbox_ids.sort();
+        assert_eq!(mailbox_ids, ["inbox", "work"]);
+
+        // The newer flag change is still the next thing to undo.
+        let (description, _) = take_undo(Some(&cache), None).unwrap();
+        assert_eq!(description, "flag");
+    }
+
     #[test]
     fn apply_local_mark_thread_read_updates_seen_and_unread_counts() {
         let dir = tempfile::tempdir().unwrap();
@@ -2808,6 +5671,114 @@ mod tests {
         assert_eq!(cache.get_mailboxes().unwrap()[0].unread_emails, 0);
     }
 
+    #[test]
+    fn empty_mailbox_targets_trash_or_junk_and_clears_cache() {
+        let mailbox = |id: &str, name: &str, role: Option<&str>| Mailbox {
+            id: id.to_string(),
+            name: name.to_string(),
+            parent_id: None,
+            role: role.map(str::to_string),
+            total_emails: 1,
+            unread_emails: 0,
+            sort_order: 0,
+        };
+        let mailboxes = vec![
+            mailbox("inbox", "INBOX", Some("inbox")),
+            mailbox("trash", "Trash", Some("trash")),
+            mailbox("spam", "Spam", None),
+        ];
+        assert!(is_emptiable_mailbox("trash", &mailboxes, "trash", "junk"));
+        assert!(is_emptiable_mailbox("spam", &mailboxes, "trash", "Spam"));
+        assert!(!is_emptiable_mailbox("spam", &mailboxes, "trash", "junk"));
+        assert!(!is_emptiable_mailbox("inbox", &mailboxes, "trash", "junk"));
+
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("XDG_CACHE_HOME", dir.path());
+        let cache = Cache::open("backend_empty_mailbox").unwrap();
+        let mut e1 = make_email("e1");
+        e1.mailbox_ids.insert("trash".to_string(), true);
+        let mut e2 = make_email("e2");
+        e2.mailbox_ids.insert("trash".to_string(), true);
+        cache.put_emails(&[e1, e2]);
+        cache.put_mailbox_index("trash", &["e1".into(), "e2".into()]);
+        cache.put_mailboxes(&mailboxes);
+
+        // Only the messages listed when the empty was confirmed go.
+        for op in &queued_mutations_for_empty(&["e1".to_string()]) {
+            apply_local_mutation(Some(&cache), op);
+        }
+        assert!(cache.get_email("e1").is_none());
+        let left: Vec<String> = cache
+            .get_mailbox_emails("trash")
+            .unwrap_or_default()
+            .into_iter()
+            .map(|e| e.id)
+            .collect();
+        assert_eq!(left, vec!["e2".to_string()]);
+    }
+
+    #[test]
+    fn retention_qualifiers_keep_unread_and_flagged_mail() {
+        let policy = |only_read, only_unflagged| RetentionPolicyConfig {
+            name: "p".to_string(),
+            folder: "Archive".to_string(),
+            days: Some(30),
+            keep_newest: None,
+            only_read,
+            only_unflagged,
+        };
+        let unread = make_email("unread");
+        let mut read = make_email("read");
+        read.keywords.insert("$seen".to_string(), true);
+        let mut flagged = read.clone();
+        flagged.keywords.insert("$flagged".to_string(), true);
+
+        assert!(retention_keywords_allow(&policy(false, false), &unread));
+        assert!(retention_keywords_allow(&policy(false, false), &flagged));
+        assert!(!retention_keywords_allow(&policy(true, false), &unread));
+        assert!(retention_keywords_allow(&policy(true, false), &flagged));
+        assert!(!retention_keywords_allow(&policy(false, true), &flagged));
+        assert!(retention_keywords_allow(&policy(true, true), &read));
+    }
+
+    #[test]
+    fn keep_newest_expires_everything_past_the_newest_n() {
+        let policy = RetentionPolicyConfig {
+            name: "logs".to_string(),
+            folder: "Logs".to_string(),
+            days: None,
+            keep_newest: Some(2),
+            only_read: false,
+            only_unflagged: true,
+        };
+        let email = |id: &str, date: &str| {
+            let mut e = make_email(id);
+            e.received_at = Some(date.to_string());
+            e
+        };
+        let mut flagged_old = email("flagged-old", "2024-01-01T00:00:00Z");
+        flagged_old.keywords.insert("$flagged".to_string(), true);
+        let emails = vec![
+            email("mid", "2025-02-01T00:00:00Z"),
+            email("old", "2024-06-01T00:00:00Z"),
+            email("new", "2025-03-01T00:00:00Z"),
+            // Later as text, but 2025-02-28T20:00Z as an instant.
+            email("east", "2025-03-01T05:00:00+09:00"),
+            flagged_old,
+            Email {
+                received_at: None,
+                ..make_email("undated")
+            },
+            email("garbled", "yesterday"),
+            email("newest", "2025-04-01T00:00:00Z"),
+        ];
+        let expired: Vec<String> = select_retention_expired(&policy, emails, 0)
+            .into_iter()
+            .map(|e| e.id)
+            .collect();
+        assert_eq!(expired, vec!["east", "mid", "old"]);
+    }
+
     #[test]
     fn queued_retention_actions_map_to_destroy_email_ops() {
         let candidates = vec![
@@ -2839,4 +5810,121 @@ mod tests {
             QueuedMutation::DestroyEmail { id, .. } if id == "e2"
         ));
     }
+
+    #[test]
+    fn attachment_downloads_skip_unnamed_inline_images_and_dedupe_names() {
+        let part = |blob: &str, name: Option<&str>, ty: &str, disposition: Option<&str>| {
+            crate::jmap::types::BodyPart {
+                part_id: blob.to_string(),
+                blob_id: Some(blob.to_string()),
+                r#type: Some(ty.to_string()),
+                name: name.map(str::to_string),
+                size: None,
+                disposition: disposition.map(str::to_string),
+                cid: None,
+            }
+        };
+        let mut email = make_email("e1");
+        email.attachments = Some(vec![
+            part(
+                "b1",
+                Some("report.pdf"),
+                "application/pdf",
+                Some("attachment"),
+            ),
+            part("b2", None, "image/png", Some("inline")),
+            part(
+                "b3",
+                Some("report.pdf"),
+                "application/pdf",
+                Some("attachment"),
+            ),
+            part("b4", None, "text/csv", Some("attachment")),
+        ]);
+
+        let names: Vec<String> = attachment_downloads(&email, false)
+            .into_iter()
+            .map(|a| a.name)
+            .collect();
+        assert_eq!(names, vec!["report.pdf", "report-2.pdf", "attachment-4"]);
+
+        let all = attachment_downloads(&email, true);
+        assert_eq!(all.len(), 4);
+        assert_eq!(all[1].name, "attachment-2");
+        assert_eq!(all[1].content_type, "image/png");
+    }
+
+    #[test]
+    fn safe_file_name_blocks_traversal_and_hidden_names() {
+        assert_eq!(safe_file_name("report.pdf"), "report.pdf");
+        assert_eq!(safe_file_name("../../etc/passwd"), "_.._etc_passwd");
+        assert_eq!(safe_file_name(".."), "download");
+        assert_eq!(safe_file_name(".bashrc"), "bashrc");
+        assert_eq!(safe_file_name("a\\b\0c\nd"), "a_b_c_d");
+        assert_eq!(safe_file_name(" . "), "download");
+    }
+
+    #[test]
+    fn download_dir_prefers_configured_dir() {
+        let configured = std::path::Path::new("/srv/mail-files");
+        assert_eq!(download_dir(Some(configured)), configured);
+    }
+
+    #[test]
+    fn changed_mailboxes_reports_new_and_recounted_mailboxes() {
+        let mailbox = |id: &str, total: u32, unread: u32| Mailbox {
+            id: id.to_string(),
+            name: id.to_string(),
+            parent_id: None,
+            role: None,
+            total_emails: total,
+            unread_emails: unread,
+            sort_order: 0,
+        };
+        let previous = vec![mailbox("inbox", 4, 2), mailbox("archive", 1, 0)];
+        let current = vec![
+            mailbox("inbox", 4, 3),
+            mailbox("archive", 1, 0),
+            mailbox("lists", 0, 0),
+        ];
+        let ids: Vec<String> = changed_mailboxes(&previous, &current)
+            .into_iter()
+            .map(|m| m.id)
+            .collect();
+        assert_eq!(ids, vec!["inbox".to_string(), "lists".to_string()]);
+        assert!(changed_mailboxes(&current, &current).is_empty());
+    }
+
+    #[test]
+    fn test_wake_skips_snoozes_that_left_the_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("XDG_CACHE_HOME", dir.path());
+        let cache = Cache::open("backend_snooze_wake").unwrap();
+        cache.put_snooze("waiting", 1_000, "snoozed").unwrap();
+        cache.put_snooze("moved", 1_000, "snoozed").unwrap();
+        cache.put_snooze("deleted", 1_000, "snoozed").unwrap();
+        cache.put_snooze("later", 5_000, "snoozed").unwrap();
+
+        let in_mailbox = |id: &str, mailbox: &str| {
+            let mut email = make_email(id);
+            email.mailbox_ids.insert(mailbox.to_string(), true);
+            email
+        };
+        let current = vec![
+            in_mailbox("waiting", "snoozed"),
+            in_mailbox("moved", "archive"),
+            in_mailbox("later", "snoozed"),
+        ];
+
+        assert!(cache.due_snoozes(999).is_empty());
+        let (wake, mut stale) = split_due_snoozes(cache.due_snoozes(1_000), &current);
+        stale.sort();
+        assert_eq!(wake, vec!["waiting".to_string()]);
+        assert_eq!(stale, vec!["deleted".to_string(), "moved".to_string()]);
+
+        // Snoozes recorded without a folder wake wherever the mail is.
+        let (wake, stale) = split_due_snoozes(vec![("moved".to_string(), None)], &current);
+        assert_eq!(wake, vec!["moved".to_string()]);
+        assert!(stale.is_empty());
+    }
 }
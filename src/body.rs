@@ -0,0 +1,76 @@
+//! Plain-text view of JMAP email bodies, shared by drafts, rules, the cache's
+//! derived previews and the CLI.
+
+use crate::jmap::types::Email;
+
+/// Plain text of an email: the text/plain body as written, HTML converted to
+/// text when that is all there is, else the preview.
+pub fn plain_text(email: &Email) -> String {
+    // Prefer textBody (plain text) — it preserves the author's formatting.
+    if let Some(ref text_body) = email.text_body {
+        for part in text_body {
+            if let Some(value) = email.body_values.get(&part.part_id) {
+                if looks_like_html(&value.value)
+                    || part
+                        .r#type
+                        .as_deref()
+                        .map(|t| t.eq_ignore_ascii_case("text/html"))
+                        .unwrap_or(false)
+                {
+                    return html_to_plain(&value.value);
+                }
+                return value.value.clone();
+            }
+        }
+    }
+    // Fall back to htmlBody when no plain text is available
+    if let Some(ref html_body) = email.html_body {
+        for part in html_body {
+            if let Some(value) = email.body_values.get(&part.part_id) {
+                return html_to_plain(&value.value);
+            }
+        }
+    }
+    email.preview.as_deref().unwrap_or("(no body)").to_string()
+}
+
+/// Heuristic check: does this text look like HTML rather than plain text?
+/// Checks for common HTML structural tags anywhere in the content.
+pub(crate) fn looks_like_html(text: &str) -> bool {
+    // Walk back to the nearest char boundary so multi-byte UTF-8 doesn't panic.
+    let mut end = 2000.min(text.len());
+    while end > 0 && !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let sample = &text[..end];
+    let lower = sample.to_ascii_lowercase();
+    lower.contains("<!doctype")
+        || lower.contains("<html")
+        || lower.contains("<head")
+        || lower.contains("<body")
+        || lower.contains("<style")
+        || lower.contains("<table")
+        || lower.contains("<div")
+}
+
+/// Convert HTML to plain text (no ANSI formatting).
+fn html_to_plain(html: &str) -> String {
+    html2text::from_read(html.as_bytes(), 80).unwrap_or_else(|_| html.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_looks_like_html_handles_multibyte_at_sample_boundary() {
+        // Regression: text with multi-byte UTF-8 (U+034F CGJ, as found in
+        // newsletter preview "tracking pixel" runs) used to panic when the
+        // 2000-byte sample boundary fell inside a code point.
+        let mut s = String::from("AAA");
+        for _ in 0..1000 {
+            s.push_str("X\u{34f}");
+        }
+        assert!(!looks_like_html(&s));
+    }
+}
@@ -1,18 +1,111 @@
 use crate::jmap::types::{Email, Mailbox};
-use redb::{Database, ReadableTable, TableDefinition};
-use std::path::PathBuf;
+use redb::{Database, Durability, ReadableTable, TableDefinition};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 const EMAILS: TableDefinition<&str, &[u8]> = TableDefinition::new("emails");
 const RULES_PROCESSED: TableDefinition<&str, &[u8]> = TableDefinition::new("rules_processed");
 const MAILBOX_INDEX: TableDefinition<&str, &[u8]> = TableDefinition::new("mailbox_index");
 const MAILBOXES: TableDefinition<&str, &[u8]> = TableDefinition::new("mailboxes");
 const OP_QUEUE: TableDefinition<u64, &[u8]> = TableDefinition::new("op_queue");
+/// Snoozed email id -> wake time (seconds since the Unix epoch).
+const SNOOZED: TableDefinition<&str, u64> = TableDefinition::new("snoozed");
+/// Snoozed email id -> id of the snooze folder it was moved to.
+const SNOOZE_FOLDERS: TableDefinition<&str, &str> = TableDefinition::new("snooze_folders");
+/// Completed user mutations, newest last, with what it takes to reverse them.
+const UNDO_LOG: TableDefinition<u64, &[u8]> = TableDefinition::new("undo_log");
+/// Oldest undo entries are dropped beyond this many.
+const UNDO_LOG_LIMIT: usize = 50;
+/// Cached email id -> last time it was stored or read (milliseconds since the
+/// Unix epoch), used to pick eviction victims.
+const EMAIL_ACCESS: TableDefinition<&str, u64> = TableDefinition::new("email_access");
+/// Reads recorded in memory before they are written to `EMAIL_ACCESS` in one
+/// transaction.
+const ACCESS_FLUSH_THRESHOLD: usize = 256;
+/// Where the UI was left, restored on the next start (`[ui].restore_session`).
+const SESSION: TableDefinition<&str, &[u8]> = TableDefinition::new("session");
+/// Lowercased address -> [`SeenAddress`], counted once per cached email.
+const ADDRESSES: TableDefinition<&str, &[u8]> = TableDefinition::new("addresses");
+/// Ids of emails already counted in `ADDRESSES`. Kept when an email is
+/// evicted or removed, so fetching it again does not count it twice.
+const ADDRESSES_COUNTED: TableDefinition<&str, ()> = TableDefinition::new("addresses_counted");
 
 pub struct Cache {
     db: Database,
+    path: PathBuf,
+    /// Length of the preview derived for emails stored without one; 0 keeps
+    /// them as they are.
+    preview_chars: usize,
+    /// Email id -> last read time not yet written to `EMAIL_ACCESS`.
+    pending_access: Mutex<HashMap<String, u64>>,
 }
 
-fn cache_dir() -> PathBuf {
+impl Drop for Cache {
+    fn drop(&mut self) {
+        self.flush_access_times();
+    }
+}
+
+/// Recent email-list searches kept per account.
+pub const SEARCH_HISTORY_LIMIT: usize = 50;
+
+/// `[ui].preview_chars` default.
+pub const DEFAULT_PREVIEW_CHARS: usize = 200;
+
+/// Mailbox list position saved across runs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionState {
+    /// Mailbox that was open, or `None` when the user was on the list.
+    pub last_mailbox_id: Option<String>,
+    pub cursor: usize,
+}
+
+/// An address from the From/To/Cc of cached mail, for address completion.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SeenAddress {
+    pub email: String,
+    /// Most recent non-empty display name.
+    pub name: Option<String>,
+    /// Number of cached emails it appeared in.
+    pub count: u32,
+    /// Latest `receivedAt` of those emails.
+    pub last_seen: Option<String>,
+}
+
+/// Outcome of one [`Cache::evict_lru`] pass.
+#[derive(Debug, Default, PartialEq)]
+pub struct EvictionSummary {
+    pub evicted: usize,
+    pub evicted_bytes: u64,
+    pub protected: usize,
+    pub size_before: u64,
+    pub size_after: u64,
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Append a search to `history`, skipping blanks and a repeat of the newest
+/// entry, and dropping the oldest beyond [`SEARCH_HISTORY_LIMIT`].
+pub fn push_search_history(history: &mut Vec<String>, query: &str) {
+    let query = query.trim();
+    if query.is_empty() || history.last().is_some_and(|last| last == query) {
+        return;
+    }
+    history.push(query.to_string());
+    if history.len() > SEARCH_HISTORY_LIMIT {
+        history.drain(..history.len() - SEARCH_HISTORY_LIMIT);
+    }
+}
+
+pub(crate) fn cache_dir() -> PathBuf {
     if let Ok(xdg) = std::env::var("XDG_CACHE_HOME") {
         PathBuf::from(xdg).join("tmc")
     } else if let Ok(home) = std::env::var("HOME") {
@@ -27,6 +120,85 @@ fn db_path(account_name: &str) -> PathBuf {
     cache_dir().join(format!("{}.redb", safe_name))
 }
 
+/// File beside an account's database (in `dir`, normally [`cache_dir`])
+/// holding its inbox unread count, so the count can be read without opening
+/// the database, which only one handle may hold at a time.
+fn unread_path(dir: &Path, account_name: &str) -> PathBuf {
+    let safe_name = account_name.replace(['/', '\\', '\0'], "_");
+    dir.join(format!("{}.unread", safe_name))
+}
+
+/// Inbox unread count last cached for an account.
+pub fn read_inbox_unread(dir: &Path, account_name: &str) -> Option<u32> {
+    std::fs::read_to_string(unread_path(dir, account_name))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Record an account's inbox unread count.
+pub fn write_inbox_unread(dir: &Path, account_name: &str, unread: u32) {
+    if let Err(e) = std::fs::create_dir_all(dir) {
+        log_warn!("[Cache] failed to create {}: {}", dir.display(), e);
+        return;
+    }
+    write_unread_file(&unread_path(dir, account_name), unread);
+}
+
+/// Write the count to a temp file, then rename it into place.
+fn write_unread_file(path: &Path, unread: u32) {
+    let tmp = path.with_extension("unread.tmp");
+    let result = std::fs::write(&tmp, unread.to_string()).and_then(|_| std::fs::rename(&tmp, path));
+    if let Err(e) = result {
+        log_warn!("[Cache] failed to write {}: {}", path.display(), e);
+    }
+}
+
+/// Unread count of the inbox, 0 when there is none.
+pub fn inbox_unread(mailboxes: &[Mailbox]) -> u32 {
+    mailboxes
+        .iter()
+        .find(|m| m.role.as_deref() == Some("inbox"))
+        .map_or(0, |m| m.unread_emails)
+}
+
+/// Bump the [`SeenAddress`] entries for everyone on an email's From/To/Cc.
+fn record_addresses(table: &mut redb::Table<&str, &[u8]>, email: &Email) {
+    let mut keys = HashSet::new();
+    let people = [&email.from, &email.to, &email.cc];
+    for addr in people.into_iter().flatten().flatten() {
+        let Some(address) = addr.email.as_deref().map(str::trim) else {
+            continue;
+        };
+        let key = address.to_lowercase();
+        if !address.contains('@') || !keys.insert(key.clone()) {
+            continue;
+        }
+        let existing: Option<SeenAddress> = table
+            .get(key.as_str())
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_slice(v.value()).ok());
+        let mut entry = existing.unwrap_or(SeenAddress {
+            email: address.to_string(),
+            name: None,
+            count: 0,
+            last_seen: None,
+        });
+        entry.count += 1;
+        if let Some(name) = addr.name.as_deref().filter(|n| !n.trim().is_empty()) {
+            entry.name = Some(name.trim().to_string());
+        }
+        if email.received_at > entry.last_seen {
+            entry.last_seen = email.received_at.clone();
+        }
+        if let Ok(bytes) = serde_json::to_vec(&entry) {
+            let _ = table.insert(key.as_str(), bytes.as_slice());
+        }
+    }
+}
+
 impl Cache {
     pub fn open(account_name: &str) -> Result<Cache, String> {
         let path = db_path(account_name);
@@ -47,19 +219,73 @@ impl Cache {
             let _ = txn.open_table(MAILBOX_INDEX);
             let _ = txn.open_table(MAILBOXES);
             let _ = txn.open_table(OP_QUEUE);
+            let _ = txn.open_table(SNOOZED);
+            let _ = txn.open_table(UNDO_LOG);
+            let _ = txn.open_table(EMAIL_ACCESS);
+            let _ = txn.open_table(SESSION);
+            let _ = txn.open_table(ADDRESSES);
+            let _ = txn.open_table(ADDRESSES_COUNTED);
         }
         txn.commit().map_err(|e| format!("cache commit: {}", e))?;
 
-        Ok(Cache { db })
+        Ok(Cache {
+            db,
+            path,
+            preview_chars: DEFAULT_PREVIEW_CHARS,
+            pending_access: Mutex::new(HashMap::new()),
+        })
     }
 
     pub fn get_email(&self, id: &str) -> Option<Email> {
+        let email = self.peek_email(id)?;
+        self.touch_email(id);
+        Some(email)
+    }
+
+    /// Like [`Cache::get_email`] but without counting as a use for eviction.
+    pub fn peek_email(&self, id: &str) -> Option<Email> {
         let txn = self.db.begin_read().ok()?;
         let table = txn.open_table(EMAILS).ok()?;
         let value = table.get(id).ok()??;
         serde_json::from_slice(value.value()).ok()
     }
 
+    /// Record a read for LRU eviction. Reads are kept in memory and written
+    /// in batches by [`Cache::flush_access_times`], so reading stays free of
+    /// write transactions.
+    fn touch_email(&self, id: &str) {
+        let Ok(mut pending) = self.pending_access.lock() else {
+            return;
+        };
+        pending.insert(id.to_string(), now_millis());
+        if pending.len() >= ACCESS_FLUSH_THRESHOLD {
+            drop(pending);
+            self.flush_access_times();
+        }
+    }
+
+    /// Write the batched read times. Not made durable on its own; it rides
+    /// along with the next regular commit.
+    pub fn flush_access_times(&self) {
+        let pending = match self.pending_access.lock() {
+            Ok(mut pending) => std::mem::take(&mut *pending),
+            Err(_) => return,
+        };
+        if pending.is_empty() {
+            return;
+        }
+        let Ok(mut txn) = self.db.begin_write() else {
+            return;
+        };
+        txn.set_durability(Durability::None);
+        if let Ok(mut table) = txn.open_table(EMAIL_ACCESS) {
+            for (id, at) in &pending {
+                let _ = table.insert(id.as_str(), *at);
+            }
+        }
+        let _ = txn.commit();
+    }
+
     #[allow(dead_code)]
     pub fn update_email_seen(&self, id: &str, seen: bool) {
         let _ = self.apply_mark_seen(id, seen);
@@ -75,6 +301,31 @@ impl Cache {
         let _ = self.apply_destroy_email(id);
     }
 
+    pub fn set_preview_chars(&mut self, chars: usize) {
+        self.preview_chars = chars;
+    }
+
+    /// Whitespace-collapsed start of the body, for emails whose server
+    /// preview is missing. `None` when there is no fetched body to use.
+    fn derived_preview(&self, email: &Email) -> Option<String> {
+        let has_preview = email
+            .preview
+            .as_deref()
+            .is_some_and(|p| !p.trim().is_empty());
+        if has_preview || self.preview_chars == 0 || email.body_values.is_empty() {
+            return None;
+        }
+        let text = crate::body::plain_text(email);
+        let preview: String = text
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .chars()
+            .take(self.preview_chars)
+            .collect();
+        (!preview.is_empty()).then_some(preview)
+    }
+
     pub fn put_emails(&self, emails: &[Email]) {
         if emails.is_empty() {
             return;
@@ -94,9 +345,28 @@ impl Cache {
                     return;
                 }
             };
+            let mut access = txn.open_table(EMAIL_ACCESS).ok();
+            let mut addresses = txn.open_table(ADDRESSES).ok();
+            let mut counted = txn.open_table(ADDRESSES_COUNTED).ok();
+            let now = now_millis();
             for email in emails {
+                // Count addresses the first time an email is stored, not on
+                // every refresh or re-fetch.
+                if let (Some(addresses), Some(counted)) = (addresses.as_mut(), counted.as_mut()) {
+                    if matches!(counted.insert(email.id.as_str(), ()), Ok(None)) {
+                        record_addresses(addresses, email);
+                    }
+                }
+                let with_preview = self.derived_preview(email).map(|preview| Email {
+                    preview: Some(preview),
+                    ..email.clone()
+                });
+                let email = with_preview.as_ref().unwrap_or(email);
                 if let Ok(bytes) = serde_json::to_vec(email) {
                     let _ = table.insert(email.id.as_str(), bytes.as_slice());
+                    if let Some(access) = access.as_mut() {
+                        let _ = access.insert(email.id.as_str(), now);
+                    }
                 }
             }
         }
@@ -105,6 +375,112 @@ impl Cache {
         }
     }
 
+    /// Size of the cache file on disk.
+    pub fn file_size(&self) -> u64 {
+        std::fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0)
+    }
+
+    /// When the cache file is larger than `max_bytes`, drop the least recently
+    /// used cached emails until roughly 10% below the limit, then compact.
+    /// Evicted emails are also dropped from the mailbox indexes. Mailboxes and
+    /// the offline queue are left alone, as are emails in `protected`. Returns
+    /// `None` when nothing needed doing.
+    pub fn evict_lru(
+        &mut self,
+        max_bytes: u64,
+        protected: &HashSet<String>,
+    ) -> Option<EvictionSummary> {
+        let size_before = self.file_size();
+        if size_before <= max_bytes {
+            return None;
+        }
+        let target = max_bytes - max_bytes / 10;
+        let to_free = size_before - target;
+        self.flush_access_times();
+
+        let mut summary = EvictionSummary {
+            size_before,
+            ..Default::default()
+        };
+        let txn = self.db.begin_write().ok()?;
+        {
+            let mut emails = txn.open_table(EMAILS).ok()?;
+            let mut access = txn.open_table(EMAIL_ACCESS).ok()?;
+            let mut candidates: Vec<(u64, String, u64)> = Vec::new();
+            for (key, value) in emails.iter().ok()?.flatten() {
+                let id = key.value().to_string();
+                if protected.contains(&id) {
+                    summary.protected += 1;
+                    continue;
+                }
+                let last_used = access
+                    .get(id.as_str())
+                    .ok()
+                    .flatten()
+                    .map(|v| v.value())
+                    .unwrap_or(0);
+                candidates.push((last_used, id, value.value().len() as u64));
+            }
+            candidates.sort();
+            let mut evicted = HashSet::new();
+            for (_, id, size) in candidates {
+                if summary.evicted_bytes >= to_free {
+                    break;
+                }
+                let _ = emails.remove(id.as_str());
+                let _ = access.remove(id.as_str());
+                summary.evicted += 1;
+                summary.evicted_bytes += size;
+                evicted.insert(id);
+            }
+            let mut index = txn.open_table(MAILBOX_INDEX).ok()?;
+            let mut trimmed = Vec::new();
+            for (key, value) in index.iter().ok()?.flatten() {
+                let Ok(ids) = serde_json::from_slice::<Vec<String>>(value.value()) else {
+                    continue;
+                };
+                if ids.iter().any(|id| evicted.contains(id)) {
+                    let kept: Vec<String> =
+                        ids.into_iter().filter(|id| !evicted.contains(id)).collect();
+                    trimmed.push((key.value().to_string(), kept));
+                }
+            }
+            for (mailbox_id, ids) in trimmed {
+                if let Ok(bytes) = serde_json::to_vec(&ids) {
+                    let _ = index.insert(mailbox_id.as_str(), bytes.as_slice());
+                }
+            }
+            // Drop access times left behind by emails destroyed elsewhere.
+            let orphans: Vec<String> = access
+                .iter()
+                .ok()?
+                .flatten()
+                .map(|(k, _)| k.value().to_string())
+                .filter(|id| !matches!(emails.get(id.as_str()), Ok(Some(_))))
+                .collect();
+            for id in &orphans {
+                let _ = access.remove(id.as_str());
+            }
+        }
+        if let Err(e) = txn.commit() {
+            log_warn!("[Cache] failed to commit eviction: {}", e);
+            return None;
+        }
+        if let Err(e) = self.db.compact() {
+            log_warn!("[Cache] compaction after eviction failed: {}", e);
+        }
+        summary.size_after = self.file_size();
+        log_info!(
+            "[Cache] evicted {} emails ({} bytes); {} protected by queued operations; file {} -> {} bytes",
+            summary.evicted,
+            summary.evicted_bytes,
+            summary.protected,
+            summary.size_before,
+            summary.size_after
+        );
+        Some(summary)
+    }
+
     pub fn get_mailbox_emails(&self, mailbox_id: &str) -> Option<Vec<Email>> {
         let txn = self.db.begin_read().ok()?;
         let index_table = txn.open_table(MAILBOX_INDEX).ok()?;
@@ -184,9 +560,122 @@ impl Cache {
         }
         if let Err(e) = txn.commit() {
             log_warn!("[Cache] failed to commit mailboxes: {}", e);
+            return;
+        }
+        write_unread_file(&self.path.with_extension("unread"), inbox_unread(mailboxes));
+    }
+
+    pub fn get_session(&self) -> Option<SessionState> {
+        let txn = self.db.begin_read().ok()?;
+        let table = txn.open_table(SESSION).ok()?;
+        let value = table.get("state").ok()??;
+        serde_json::from_slice(value.value()).ok()
+    }
+
+    pub fn put_session(&self, state: &SessionState) {
+        let txn = match self.db.begin_write() {
+            Ok(t) => t,
+            Err(e) => {
+                log_warn!("[Cache] failed to begin write txn: {}", e);
+                return;
+            }
+        };
+        {
+            let mut table = match txn.open_table(SESSION) {
+                Ok(t) => t,
+                Err(e) => {
+                    log_warn!("[Cache] failed to open session table: {}", e);
+                    return;
+                }
+            };
+            if let Ok(bytes) = serde_json::to_vec(state) {
+                let _ = table.insert("state", bytes.as_slice());
+            }
+        }
+        if let Err(e) = txn.commit() {
+            log_warn!("[Cache] failed to commit session: {}", e);
+        }
+    }
+
+    /// Recent searches, oldest first.
+    pub fn search_history(&self) -> Vec<String> {
+        let Ok(txn) = self.db.begin_read() else {
+            return Vec::new();
+        };
+        let Ok(table) = txn.open_table(SESSION) else {
+            return Vec::new();
+        };
+        table
+            .get("search_history")
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_slice(v.value()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Append `query` to the search history (see [`push_search_history`]).
+    pub fn record_search(&self, query: &str) {
+        let mut history = self.search_history();
+        push_search_history(&mut history, query);
+        let txn = match self.db.begin_write() {
+            Ok(t) => t,
+            Err(e) => {
+                log_warn!("[Cache] failed to begin write txn: {}", e);
+                return;
+            }
+        };
+        {
+            let mut table = match txn.open_table(SESSION) {
+                Ok(t) => t,
+                Err(e) => {
+                    log_warn!("[Cache] failed to open session table: {}", e);
+                    return;
+                }
+            };
+            if let Ok(bytes) = serde_json::to_vec(&history) {
+                let _ = table.insert("search_history", bytes.as_slice());
+            }
+        }
+        if let Err(e) = txn.commit() {
+            log_warn!("[Cache] failed to commit search history: {}", e);
         }
     }
 
+    /// Addresses seen in cached mail, most frequent first.
+    pub fn seen_addresses(&self) -> Vec<SeenAddress> {
+        let Ok(txn) = self.db.begin_read() else {
+            return Vec::new();
+        };
+        let Ok(table) = txn.open_table(ADDRESSES) else {
+            return Vec::new();
+        };
+        let Ok(iter) = table.iter() else {
+            return Vec::new();
+        };
+        let mut seen: Vec<SeenAddress> = iter
+            .flatten()
+            .filter_map(|(_, v)| serde_json::from_slice(v.value()).ok())
+            .collect();
+        seen.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.email.cmp(&b.email)));
+        seen
+    }
+
+    /// Every cached email, in id order.
+    pub fn all_emails(&self) -> Vec<Email> {
+        let Ok(txn) = self.db.begin_read() else {
+            return Vec::new();
+        };
+        let Ok(table) = txn.open_table(EMAILS) else {
+            return Vec::new();
+        };
+        let Ok(iter) = table.iter() else {
+            return Vec::new();
+        };
+        iter.flatten()
+            .filter_map(|(_, value)| serde_json::from_slice::<Email>(value.value()).ok())
+            .collect()
+    }
+
     pub fn get_thread_emails(&self, thread_id: &str) -> Vec<Email> {
         let Ok(txn) = self.db.begin_read() else {
             return Vec::new();
@@ -287,6 +776,15 @@ impl Cache {
     }
 
     pub fn apply_set_flagged(&self, id: &str, flagged: bool) -> bool {
+        self.apply_set_keyword(id, "$flagged", flagged)
+    }
+
+    /// Set or clear an arbitrary keyword on a cached email. `$seen` goes
+    /// through `apply_mark_seen` so mailbox unread counts stay in sync.
+    pub fn apply_set_keyword(&self, id: &str, keyword: &str, value: bool) -> bool {
+        if keyword == "$seen" {
+            return self.apply_mark_seen(id, value);
+        }
         let txn = match self.db.begin_write() {
             Ok(t) => t,
             Err(_) => return false,
@@ -305,14 +803,13 @@ impl Cache {
             email
         };
 
-        let was_flagged = email.keywords.contains_key("$flagged");
-        if was_flagged == flagged {
+        if email.keywords.contains_key(keyword) == value {
             return false;
         }
-        if flagged {
-            email.keywords.insert("$flagged".to_string(), true);
+        if value {
+            email.keywords.insert(keyword.to_string(), true);
         } else {
-            email.keywords.remove("$flagged");
+            email.keywords.remove(keyword);
         }
         {
             let Ok(mut email_table) = txn.open_table(EMAILS) else {
@@ -327,6 +824,12 @@ impl Cache {
     }
 
     pub fn apply_move_email(&self, id: &str, to_mailbox_id: &str) -> bool {
+        self.apply_set_mailboxes(id, &[to_mailbox_id.to_string()])
+    }
+
+    /// Replace the mailboxes an email is in, updating the mailbox index and
+    /// counts to match.
+    pub fn apply_set_mailboxes(&self, id: &str, mailbox_ids: &[String]) -> bool {
         let txn = match self.db.begin_write() {
             Ok(t) => t,
             Err(_) => return false,
@@ -347,8 +850,7 @@ impl Cache {
         let was_seen = email.keywords.contains_key("$seen");
         let previous_mailboxes: Vec<String> = email.mailbox_ids.keys().cloned().collect();
 
-        email.mailbox_ids.clear();
-        email.mailbox_ids.insert(to_mailbox_id.to_string(), true);
+        email.mailbox_ids = mailbox_ids.iter().map(|m| (m.clone(), true)).collect();
 
         {
             let Ok(mut email_table) = txn.open_table(EMAILS) else {
@@ -376,19 +878,21 @@ impl Cache {
                     }
                 }
             }
-            let target_raw = index_table
-                .get(to_mailbox_id)
-                .ok()
-                .flatten()
-                .map(|v| v.value().to_vec());
-            let mut target_ids = target_raw
-                .as_ref()
-                .and_then(|v| serde_json::from_slice::<Vec<String>>(v).ok())
-                .unwrap_or_default();
-            target_ids.retain(|eid| eid != id);
-            target_ids.insert(0, id.to_string());
-            if let Ok(bytes) = serde_json::to_vec(&target_ids) {
-                let _ = index_table.insert(to_mailbox_id, bytes.as_slice());
+            for to_mailbox_id in mailbox_ids {
+                let target_raw = index_table
+                    .get(to_mailbox_id.as_str())
+                    .ok()
+                    .flatten()
+                    .map(|v| v.value().to_vec());
+                let mut target_ids = target_raw
+                    .as_ref()
+                    .and_then(|v| serde_json::from_slice::<Vec<String>>(v).ok())
+                    .unwrap_or_default();
+                target_ids.retain(|eid| eid != id);
+                target_ids.insert(0, id.to_string());
+                if let Ok(bytes) = serde_json::to_vec(&target_ids) {
+                    let _ = index_table.insert(to_mailbox_id.as_str(), bytes.as_slice());
+                }
             }
         }
 
@@ -402,7 +906,7 @@ impl Cache {
                 if let Ok(mut mailboxes) = serde_json::from_slice::<Vec<Mailbox>>(&raw) {
                     for mailbox in &mut mailboxes {
                         let was_in = previous_mailboxes.iter().any(|m| m == &mailbox.id);
-                        let now_in = mailbox.id == to_mailbox_id;
+                        let now_in = mailbox_ids.contains(&mailbox.id);
                         if was_in && !now_in {
                             mailbox.total_emails = mailbox.total_emails.saturating_sub(1);
                             if !was_seen {
@@ -595,10 +1099,8 @@ impl Cache {
                 .map_err(|e| format!("queue open table: {}", e))?;
             let mut max_id = 0u64;
             if let Ok(iter) = table.iter() {
-                for entry in iter {
-                    if let Ok((k, _)) = entry {
-                        max_id = max_id.max(k.value());
-                    }
+                for (k, _) in iter.flatten() {
+                    max_id = max_id.max(k.value());
                 }
             }
             let seq = max_id.saturating_add(1);
@@ -622,10 +1124,8 @@ impl Cache {
             return Vec::new();
         };
         let mut out = Vec::new();
-        for entry in iter {
-            if let Ok((k, v)) = entry {
-                out.push((k.value(), v.value().to_vec()));
-            }
+        for (k, v) in iter.flatten() {
+            out.push((k.value(), v.value().to_vec()));
         }
         out
     }
@@ -644,6 +1144,135 @@ impl Cache {
         txn.commit().is_ok()
     }
 
+    /// Append an undo entry, trimming the log to the newest `UNDO_LOG_LIMIT`.
+    pub fn push_undo(&self, payload: &[u8]) -> Result<(), String> {
+        let txn = self
+            .db
+            .begin_write()
+            .map_err(|e| format!("undo write txn: {}", e))?;
+        {
+            let mut table = txn
+                .open_table(UNDO_LOG)
+                .map_err(|e| format!("undo open table: {}", e))?;
+            let mut keys = Vec::new();
+            if let Ok(iter) = table.iter() {
+                for (k, _) in iter.flatten() {
+                    keys.push(k.value());
+                }
+            }
+            let seq = keys.last().copied().unwrap_or(0).saturating_add(1);
+            table
+                .insert(seq, payload)
+                .map_err(|e| format!("undo insert: {}", e))?;
+            let excess = (keys.len() + 1).saturating_sub(UNDO_LOG_LIMIT);
+            for old in keys.into_iter().take(excess) {
+                let _ = table.remove(old);
+            }
+        }
+        txn.commit().map_err(|e| format!("undo commit: {}", e))
+    }
+
+    /// Remove and return the newest undo entry.
+    pub fn pop_undo(&self) -> Option<Vec<u8>> {
+        let txn = self.db.begin_write().ok()?;
+        let payload = {
+            let mut table = txn.open_table(UNDO_LOG).ok()?;
+            let (seq, payload) = {
+                let (k, v) = table.last().ok()??;
+                (k.value(), v.value().to_vec())
+            };
+            table.remove(seq).ok()?;
+            payload
+        };
+        txn.commit().ok()?;
+        Some(payload)
+    }
+
+    /// Remove and return the newest undo entry that `matches` accepts.
+    pub fn take_undo_where(&self, matches: impl Fn(&[u8]) -> bool) -> Option<Vec<u8>> {
+        let txn = self.db.begin_write().ok()?;
+        let payload = {
+            let mut table = txn.open_table(UNDO_LOG).ok()?;
+            let (seq, payload) = table
+                .iter()
+                .ok()?
+                .rev()
+                .flatten()
+                .map(|(k, v)| (k.value(), v.value().to_vec()))
+                .find(|(_, v)| matches(v))?;
+            table.remove(seq).ok()?;
+            payload
+        };
+        txn.commit().ok()?;
+        Some(payload)
+    }
+
+    pub fn put_snooze(&self, id: &str, wake_at: u64, folder_id: &str) -> Result<(), String> {
+        let txn = self
+            .db
+            .begin_write()
+            .map_err(|e| format!("snooze write txn: {}", e))?;
+        {
+            let mut table = txn
+                .open_table(SNOOZED)
+                .map_err(|e| format!("snooze open table: {}", e))?;
+            table
+                .insert(id, wake_at)
+                .map_err(|e| format!("snooze insert: {}", e))?;
+            let mut folders = txn
+                .open_table(SNOOZE_FOLDERS)
+                .map_err(|e| format!("snooze open table: {}", e))?;
+            folders
+                .insert(id, folder_id)
+                .map_err(|e| format!("snooze insert: {}", e))?;
+        }
+        txn.commit().map_err(|e| format!("snooze commit: {}", e))
+    }
+
+    pub fn remove_snooze(&self, id: &str) -> bool {
+        let txn = match self.db.begin_write() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+        {
+            let Ok(mut table) = txn.open_table(SNOOZED) else {
+                return false;
+            };
+            let _ = table.remove(id);
+            if let Ok(mut folders) = txn.open_table(SNOOZE_FOLDERS) {
+                let _ = folders.remove(id);
+            }
+        }
+        txn.commit().is_ok()
+    }
+
+    /// Snoozed emails whose wake time is at or before `now`, with the
+    /// snooze folder each was moved to (unknown for snoozes recorded before
+    /// the folder was kept).
+    pub fn due_snoozes(&self, now: u64) -> Vec<(String, Option<String>)> {
+        let Ok(txn) = self.db.begin_read() else {
+            return Vec::new();
+        };
+        let Ok(table) = txn.open_table(SNOOZED) else {
+            return Vec::new();
+        };
+        let Ok(iter) = table.iter() else {
+            return Vec::new();
+        };
+        let folders = txn.open_table(SNOOZE_FOLDERS).ok();
+        iter.flatten()
+            .filter(|(_, v)| v.value() <= now)
+            .map(|(k, _)| {
+                let id = k.value().to_string();
+                let folder = folders
+                    .as_ref()
+                    .and_then(|t| t.get(id.as_str()).ok().flatten())
+                    .map(|v| v.value().to_string());
+                (id, folder)
+            })
+            .collect()
+    }
+
     pub fn filter_unprocessed(&self, ids: &[String]) -> Vec<String> {
         let Ok(txn) = self.db.begin_read() else {
             return ids.to_vec();
@@ -686,14 +1315,21 @@ impl Cache {
         }
     }
 
-    pub fn clear_all_accounts() {
+    /// Delete every account's cache file (and `.unread` side file). Returns
+    /// the names of the accounts whose caches were removed.
+    pub fn clear_all_accounts() -> Vec<String> {
+        let mut cleared = Vec::new();
         let dir = cache_dir();
         if !dir.exists() {
-            return;
+            return cleared;
         }
         if let Ok(entries) = std::fs::read_dir(&dir) {
             for entry in entries.flatten() {
                 let path = entry.path();
+                if path.extension().and_then(|e| e.to_str()) == Some("unread") {
+                    let _ = std::fs::remove_file(&path);
+                    continue;
+                }
                 if path.extension().and_then(|e| e.to_str()) == Some("redb") {
                     if let Err(e) = std::fs::remove_file(&path) {
                         eprintln!(
@@ -701,10 +1337,41 @@ impl Cache {
                             path.display(),
                             e
                         );
+                    } else if let Some(name) = path.file_stem().and_then(|n| n.to_str()) {
+                        cleared.push(name.to_string());
                     }
                 }
             }
         }
+        cleared.sort();
+        cleared
+    }
+
+    /// Delete one account's cache file and its `.unread` side file. Returns
+    /// false when it had no cache.
+    pub fn clear_account(account_name: &str) -> Result<bool, String> {
+        let unread = unread_path(&cache_dir(), account_name);
+        match std::fs::remove_file(&unread) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(format!(
+                    "failed to remove unread count file {}: {}",
+                    unread.display(),
+                    e
+                ))
+            }
+        }
+        let path = db_path(account_name);
+        match std::fs::remove_file(&path) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(format!(
+                "failed to remove cache file {}: {}",
+                path.display(),
+                e
+            )),
+        }
     }
 }
 
@@ -778,6 +1445,19 @@ impl Cache {
                     let _ = table.remove(id.as_str());
                 }
             }
+            if let Ok(mut table) = txn.open_table(SNOOZED) {
+                let ids: Vec<String> = table
+                    .iter()
+                    .ok()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|entry| entry.ok())
+                    .map(|(k, _)| k.value().to_string())
+                    .collect();
+                for id in &ids {
+                    let _ = table.remove(id.as_str());
+                }
+            }
             if let Ok(mut table) = txn.open_table(OP_QUEUE) {
                 let ids: Vec<u64> = table
                     .iter()
@@ -832,7 +1512,7 @@ mod tests {
         let cache = Cache::open("test_account").unwrap();
 
         let email = make_test_email("e1");
-        cache.put_emails(&[email.clone()]);
+        cache.put_emails(std::slice::from_ref(&email));
 
         let cached = cache.get_email("e1").unwrap();
         assert_eq!(cached.id, "e1");
@@ -841,6 +1521,54 @@ mod tests {
         assert!(cache.get_email("nonexistent").is_none());
     }
 
+    #[test]
+    fn test_cache_derives_missing_preview() {
+        use crate::jmap::types::{BodyPart, BodyValue};
+
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("XDG_CACHE_HOME", dir.path());
+        let mut cache = Cache::open("test_preview").unwrap();
+        cache.set_preview_chars(12);
+
+        let mut email = make_test_email("e1");
+        email.text_body = Some(vec![BodyPart {
+            part_id: "1".into(),
+            blob_id: None,
+            r#type: Some("text/plain".into()),
+            name: None,
+            size: None,
+            disposition: None,
+            cid: None,
+        }]);
+        email.body_values.insert(
+            "1".into(),
+            BodyValue {
+                value: "Hello\n\n   there,  old friend".into(),
+                is_encoding_problem: false,
+                is_truncated: false,
+            },
+        );
+        let mut with_preview = make_test_email("e2");
+        with_preview.preview = Some("server preview".into());
+        cache.put_emails(&[email.clone(), with_preview, make_test_email("e3")]);
+
+        assert_eq!(
+            cache.get_email("e1").unwrap().preview.as_deref(),
+            Some("Hello there,")
+        );
+        assert_eq!(
+            cache.get_email("e2").unwrap().preview.as_deref(),
+            Some("server preview")
+        );
+        // No fetched body: nothing to derive from.
+        assert!(cache.get_email("e3").unwrap().preview.is_none());
+
+        cache.set_preview_chars(0);
+        email.id = "e4".into();
+        cache.put_emails(&[email]);
+        assert!(cache.get_email("e4").unwrap().preview.is_none());
+    }
+
     #[test]
     fn test_cache_rules_processed() {
         let dir = tempfile::tempdir().unwrap();
@@ -901,6 +1629,81 @@ mod tests {
         assert_eq!(cached[1].name, "Sent");
     }
 
+    #[test]
+    fn test_cache_counts_seen_addresses_once_per_email() {
+        use crate::jmap::types::EmailAddress;
+
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("XDG_CACHE_HOME", dir.path());
+        let cache = Cache::open("test_addresses").unwrap();
+
+        let addr = |name: Option<&str>, email: &str| EmailAddress {
+            name: name.map(str::to_string),
+            email: Some(email.to_string()),
+        };
+        let mut e1 = make_test_email("e1");
+        e1.from = Some(vec![addr(None, "Ann@Example.com")]);
+        e1.to = Some(vec![addr(Some("Bob"), "bob@example.com")]);
+        e1.received_at = Some("2024-01-02T00:00:00Z".into());
+        let mut e2 = make_test_email("e2");
+        e2.from = Some(vec![addr(Some("Ann Lee"), "ann@example.com")]);
+        e2.cc = Some(vec![addr(Some("Ann"), "ann@example.com")]);
+        e2.received_at = Some("2024-01-01T00:00:00Z".into());
+
+        cache.put_emails(&[e1.clone(), e2]);
+        cache.put_emails(&[e1.clone()]);
+        // Dropped from the cache (eviction, destroy) and fetched again.
+        cache.remove_email("e1");
+        cache.put_emails(&[e1]);
+
+        let seen = cache.seen_addresses();
+        assert_eq!(seen.len(), 2);
+        assert_eq!(seen[0].email, "Ann@Example.com");
+        assert_eq!(seen[0].name.as_deref(), Some("Ann Lee"));
+        assert_eq!(seen[0].count, 2);
+        assert_eq!(seen[0].last_seen.as_deref(), Some("2024-01-02T00:00:00Z"));
+        assert_eq!(seen[1].email, "bob@example.com");
+        assert_eq!(seen[1].count, 1);
+    }
+
+    #[test]
+    fn test_cache_session_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("XDG_CACHE_HOME", dir.path());
+        let cache = Cache::open("test_session").unwrap();
+
+        assert!(cache.get_session().is_none());
+        let state = SessionState {
+            last_mailbox_id: Some("m2".into()),
+            cursor: 4,
+        };
+        cache.put_session(&state);
+        assert_eq!(cache.get_session(), Some(state));
+
+        cache.put_session(&SessionState::default());
+        assert_eq!(cache.get_session(), Some(SessionState::default()));
+    }
+
+    #[test]
+    fn test_cache_search_history() {
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("XDG_CACHE_HOME", dir.path());
+        let cache = Cache::open("test_search_history").unwrap();
+
+        assert!(cache.search_history().is_empty());
+        for query in ["from:ann", "from:ann", " ", "invoice", "from:ann"] {
+            cache.record_search(query);
+        }
+        assert_eq!(cache.search_history(), ["from:ann", "invoice", "from:ann"]);
+
+        for i in 0..SEARCH_HISTORY_LIMIT {
+            cache.record_search(&format!("q{}", i));
+        }
+        let history = cache.search_history();
+        assert_eq!(history.len(), SEARCH_HISTORY_LIMIT);
+        assert_eq!(history[0], "q0");
+    }
+
     #[test]
     fn test_cache_mailbox_index() {
         let dir = tempfile::tempdir().unwrap();
@@ -1080,4 +1883,101 @@ mod tests {
         assert_eq!(ops.len(), 1);
         assert_eq!(ops[0].0, s2);
     }
+
+    #[test]
+    fn test_cache_undo_log_is_lifo_and_bounded() {
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("XDG_CACHE_HOME", dir.path());
+
+        let cache = Cache::open("test_undo").unwrap();
+        assert!(cache.pop_undo().is_none());
+        for i in 0..UNDO_LOG_LIMIT + 5 {
+            cache.push_undo(format!("op-{}", i).as_bytes()).unwrap();
+        }
+        let last = format!("op-{}", UNDO_LOG_LIMIT + 4);
+        assert_eq!(cache.pop_undo().as_deref(), Some(last.as_bytes()));
+        let mut remaining = 1;
+        while cache.pop_undo().is_some() {
+            remaining += 1;
+        }
+        assert_eq!(remaining, UNDO_LOG_LIMIT);
+    }
+
+    #[test]
+    fn test_cache_snooze_due() {
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("XDG_CACHE_HOME", dir.path());
+
+        let cache = Cache::open("test_snooze").unwrap();
+        cache.put_snooze("e1", 100, "snoozed").unwrap();
+        cache.put_snooze("e2", 200, "snoozed").unwrap();
+        assert!(cache.due_snoozes(99).is_empty());
+        assert_eq!(
+            cache.due_snoozes(150),
+            vec![("e1".to_string(), Some("snoozed".to_string()))]
+        );
+        assert!(cache.remove_snooze("e1"));
+        assert_eq!(
+            cache.due_snoozes(300),
+            vec![("e2".to_string(), Some("snoozed".to_string()))]
+        );
+    }
+
+    #[test]
+    fn test_cache_evict_lru_keeps_recent_and_protected() {
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("XDG_CACHE_HOME", dir.path());
+
+        let mut cache = Cache::open("test_evict").unwrap();
+        for i in 0..100 {
+            let mut email = make_test_email(&format!("e{}", i));
+            email.preview = Some("x".repeat(4096));
+            cache.put_emails(&[email]);
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        cache.put_mailboxes(&[]);
+        let all_ids: Vec<String> = (0..100).map(|i| format!("e{}", i)).collect();
+        cache.put_mailbox_index("inbox", &all_ids);
+        // e0 is the oldest write, but reading it makes it the most recent.
+        assert!(cache.get_email("e0").is_some());
+
+        let size = cache.file_size();
+        assert!(cache.evict_lru(size, &HashSet::new()).is_none());
+
+        let protected: HashSet<String> = ["e1".to_string()].into_iter().collect();
+        let summary = cache.evict_lru(size - 50_000, &protected).unwrap();
+        assert!(summary.evicted > 0);
+        assert_eq!(summary.protected, 1);
+        assert!(cache.get_email("e0").is_some());
+        assert!(cache.get_email("e1").is_some());
+        assert!(cache.get_email("e2").is_none());
+        assert!(cache.get_email("e99").is_some());
+        assert!(cache.get_mailboxes().is_some());
+
+        // The index only lists what is still cached.
+        let listed = cache.get_mailbox_emails("inbox").unwrap();
+        assert_eq!(listed.len(), 100 - summary.evicted);
+        let txn = cache.db.begin_read().unwrap();
+        let index = txn.open_table(MAILBOX_INDEX).unwrap();
+        let raw = index.get("inbox").unwrap().unwrap();
+        let ids: Vec<String> = serde_json::from_slice(raw.value()).unwrap();
+        assert_eq!(ids.len(), listed.len());
+    }
+
+    #[test]
+    fn test_cache_clear_account_leaves_others() {
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("XDG_CACHE_HOME", dir.path());
+
+        drop(Cache::open("clear_one").unwrap());
+        drop(Cache::open("clear_other").unwrap());
+        write_inbox_unread(&cache_dir(), "clear_one", 4);
+        write_inbox_unread(&cache_dir(), "clear_other", 2);
+        assert_eq!(Cache::clear_account("clear_one"), Ok(true));
+        assert_eq!(Cache::clear_account("clear_one"), Ok(false));
+        assert!(!db_path("clear_one").exists());
+        assert_eq!(read_inbox_unread(&cache_dir(), "clear_one"), None);
+        assert!(db_path("clear_other").exists());
+        assert_eq!(read_inbox_unread(&cache_dir(), "clear_other"), Some(2));
+    }
 }
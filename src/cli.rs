@@ -1,14 +1,25 @@
 use crate::backend::{self, BackendCommand, BackendResponse};
-use crate::compose;
-use crate::config::Config;
-use crate::jmap::types::{Email, Mailbox};
+use crate::cache::SeenAddress;
+use crate::compose::{self, ComposeDraft};
+use crate::config::{Config, IdentityConfig, RetentionPolicyConfig};
+use crate::contacts;
+use crate::drafts::{DraftStore, SavedDraft};
+use crate::jmap::types::{
+    validate_keyword, Email, EmailAddress, Mailbox, OutgoingAttachment, Quota, VacationResponse,
+};
 use crate::keybindings;
 use crate::rules::{self, CompiledRule};
+use crate::snooze;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
 use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
 
 #[derive(Clone, Copy)]
 enum TriageTarget {
@@ -28,15 +39,76 @@ struct TriageItem {
     confidence: f32,
 }
 
+/// Page size of `triage_suggest` when it reports progress.
+const TRIAGE_PROGRESS_PAGE: u32 = 100;
+
 struct TriagePlan {
     archive_ids: Vec<String>,
     trash_ids: Vec<String>,
 }
 
+/// A backend left running for an account that is no longer the active one,
+/// so `connect` can switch back without re-authenticating or rediscovering
+/// the session.
+struct PooledBackend {
+    cmd_tx: mpsc::Sender<BackendCommand>,
+    resp_rx: mpsc::Receiver<BackendResponse>,
+    username: String,
+    cached_mailboxes: Vec<Mailbox>,
+}
+
+impl PooledBackend {
+    /// Whether the backend thread is still running. Stray responses are
+    /// logged and dropped so the next command reads its own reply.
+    fn is_live(&self) -> bool {
+        loop {
+            match self.resp_rx.try_recv() {
+                Ok(resp) => log_debug!("[CLI] dropping stray {} response", resp.name()),
+                Err(mpsc::TryRecvError::Empty) => return true,
+                Err(mpsc::TryRecvError::Disconnected) => return false,
+            }
+        }
+    }
+
+    fn shutdown(self) {
+        let _ = self.cmd_tx.send(BackendCommand::Shutdown);
+    }
+}
+
+/// Paging state behind `query_emails`' opaque `cursor` / `next_cursor`
+/// token (URL-safe base64 of this struct as JSON).
+#[derive(Serialize, Deserialize)]
+struct QueryCursor {
+    mailbox_id: String,
+    position: u32,
+    search: Option<String>,
+    received_after: Option<String>,
+    received_before: Option<String>,
+}
+
+impl QueryCursor {
+    fn encode(&self) -> String {
+        use base64::Engine;
+        let json = serde_json::to_vec(self).unwrap_or_default();
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(json)
+    }
+
+    fn decode(token: &str) -> Result<Self, String> {
+        use base64::Engine;
+        base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(token)
+            .ok()
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .ok_or_else(|| "invalid 'cursor'".to_string())
+    }
+}
+
 struct CliState {
     config: Config,
     cmd_tx: Option<mpsc::Sender<BackendCommand>>,
     resp_rx: Option<mpsc::Receiver<BackendResponse>>,
+    /// Backends of previously connected accounts, by account name.
+    pool: HashMap<String, PooledBackend>,
     connected_account: Option<String>,
     connected_username: Option<String>,
     cached_mailboxes: Vec<Mailbox>,
@@ -52,6 +124,11 @@ struct CliState {
     next_plan_id: u64,
     triage_plans: HashMap<String, TriagePlan>,
     offline: bool,
+    /// Mailbox changes from `watch` that arrived while a command waited for
+    /// its reply; written out after that reply.
+    watch_events: RefCell<Vec<Mailbox>>,
+    /// `[contacts]` address book, loaded (and its command run) on first use.
+    address_book: Option<Vec<EmailAddress>>,
 }
 
 impl CliState {
@@ -60,6 +137,14 @@ impl CliState {
         self.next_op_id
     }
 
+    /// Saved drafts of the connected account.
+    fn draft_store(&self) -> Result<DraftStore, String> {
+        self.connected_account
+            .as_deref()
+            .map(DraftStore::for_account)
+            .ok_or_else(|| "not connected".to_string())
+    }
+
     fn send_cmd(&self, cmd: BackendCommand) -> Result<(), String> {
         self.cmd_tx
             .as_ref()
@@ -69,11 +154,56 @@ impl CliState {
     }
 
     fn recv_resp(&self) -> Result<BackendResponse, String> {
-        self.resp_rx
+        let resp_rx = self
+            .resp_rx
             .as_ref()
-            .ok_or_else(|| "not connected".to_string())?
-            .recv()
-            .map_err(|_| "backend channel closed".to_string())
+            .ok_or_else(|| "not connected".to_string())?;
+        loop {
+            match resp_rx
+                .recv()
+                .map_err(|_| "backend channel closed".to_string())?
+            {
+                BackendResponse::StateChanged { mailboxes } => {
+                    self.watch_events.borrow_mut().extend(mailboxes)
+                }
+                resp => return Ok(resp),
+            }
+        }
+    }
+
+    /// Detach the active backend, leaving the state disconnected.
+    fn take_active(&mut self) -> Option<(String, PooledBackend)> {
+        let backend = PooledBackend {
+            cmd_tx: self.cmd_tx.take()?,
+            resp_rx: self.resp_rx.take()?,
+            username: self.connected_username.take().unwrap_or_default(),
+            cached_mailboxes: std::mem::take(&mut self.cached_mailboxes),
+        };
+        Some((self.connected_account.take()?, backend))
+    }
+
+    fn activate(&mut self, account: String, backend: PooledBackend) {
+        self.cmd_tx = Some(backend.cmd_tx);
+        self.resp_rx = Some(backend.resp_rx);
+        self.connected_account = Some(account);
+        self.connected_username = Some(backend.username);
+        self.cached_mailboxes = backend.cached_mailboxes;
+    }
+
+    /// Like `recv_resp`, but first forwards any `Progress` responses as
+    /// progress lines when the request set `emit_progress`.
+    fn recv_resp_with_progress(&self, input: &Value) -> Result<BackendResponse, String> {
+        let emit = wants_progress(input);
+        loop {
+            match self.recv_resp()? {
+                BackendResponse::Progress { scanned, total } => {
+                    if emit {
+                        write_progress(scanned, total);
+                    }
+                }
+                resp => return Ok(resp),
+            }
+        }
     }
 
     fn next_plan_id(&mut self) -> String {
@@ -123,6 +253,34 @@ impl CliState {
             .or_else(|| self.connected_username.clone())
             .unwrap_or_else(|| "user@example.com".to_string())
     }
+
+    /// Sender identities configured for the connected account.
+    fn identities(&self) -> &[IdentityConfig] {
+        self.connected_account
+            .as_deref()
+            .and_then(|name| self.config.accounts.iter().find(|a| a.name == name))
+            .map(|a| a.identities.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Identity selection for drafts; `[mail].reply_from` takes precedence.
+    fn identity_choice(&self) -> compose::IdentityChoice {
+        compose::IdentityChoice::new(
+            self.identities().to_vec(),
+            self.config.mail.reply_from.is_some(),
+        )
+    }
+
+    fn signature_options(&self) -> compose::SignatureOptions {
+        compose::SignatureOptions {
+            account_signature: self
+                .connected_account
+                .as_deref()
+                .and_then(|name| self.config.accounts.iter().find(|a| a.name == name))
+                .and_then(|a| a.signature.clone()),
+            above_quote: self.config.ui.signature_above_quote,
+        }
+    }
 }
 
 fn ok_response(data: Value) -> Value {
@@ -164,6 +322,8 @@ fn serialize_email(email: &Email, headers_only: bool, max_body_chars: usize) ->
 
     let is_read = email.keywords.contains_key("$seen");
     let is_flagged = email.keywords.contains_key("$flagged");
+    let mut keywords: Vec<&String> = email.keywords.keys().collect();
+    keywords.sort();
 
     let attachments: Option<Vec<Value>> = email.attachments.as_ref().map(|parts| {
         parts
@@ -180,6 +340,8 @@ fn serialize_email(email: &Email, headers_only: bool, max_body_chars: usize) ->
             .collect()
     });
 
+    let received_epoch = rules::email_received_epoch(email);
+
     let mut obj = json!({
         "id": email.id,
         "thread_id": email.thread_id,
@@ -188,9 +350,12 @@ fn serialize_email(email: &Email, headers_only: bool, max_body_chars: usize) ->
         "cc": cc,
         "subject": email.subject,
         "received_at": email.received_at,
+        "received_epoch": received_epoch,
+        "received_date": received_epoch.map(crate::datefmt::utc_date),
         "sent_at": email.sent_at,
         "is_read": is_read,
         "is_flagged": is_flagged,
+        "keywords": keywords,
         "mailbox_ids": email.mailbox_ids.keys().collect::<Vec<_>>(),
         "message_id": email.message_id,
         "references": email.references,
@@ -198,7 +363,7 @@ fn serialize_email(email: &Email, headers_only: bool, max_body_chars: usize) ->
     });
 
     if !headers_only {
-        let body_text = compose::extract_body_text(email);
+        let body_text = crate::body::plain_text(email);
         let (body, truncated) = if max_body_chars > 0 && body_text.len() > max_body_chars {
             let truncated_body: String = body_text.chars().take(max_body_chars).collect();
             (truncated_body, true)
@@ -225,6 +390,22 @@ fn serialize_mailbox(mbox: &Mailbox) -> Value {
     })
 }
 
+fn wants_progress(input: &Value) -> bool {
+    input
+        .get("emit_progress")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+/// Write a `{"progress": {...}}` line ahead of the command's response.
+fn write_progress(scanned: usize, total: usize) {
+    let mut out = io::stdout().lock();
+    let line = json!({"progress": {"scanned": scanned, "total": total}});
+    let _ = serde_json::to_writer(&mut out, &line);
+    let _ = out.write_all(b"\n");
+    let _ = out.flush();
+}
+
 fn dispatch(state: &mut CliState, input: &Value) -> Value {
     let command = match input.get("command").and_then(|v| v.as_str()) {
         Some(c) => c,
@@ -233,37 +414,71 @@ fn dispatch(state: &mut CliState, input: &Value) -> Value {
 
     match command {
         "list_accounts" => cmd_list_accounts(state),
+        "list_identities" => cmd_list_identities(state),
         "connect" => cmd_connect(state, input),
+        "disconnect" => cmd_disconnect(state, input),
         "status" => cmd_status(state),
         "list_mailboxes" => cmd_list_mailboxes(state),
+        "unread_counts" => cmd_unread_counts(state, input),
+        "get_quota" => cmd_get_quota(state),
         "create_mailbox" => cmd_create_mailbox(state, input),
         "delete_mailbox" => cmd_delete_mailbox(state, input),
         "query_emails" => cmd_query_emails(state, input),
+        "search_all" => cmd_search_all(state, input),
+        "watch" => cmd_watch(state, input),
         "get_email" => cmd_get_email(state, input),
+        "get_emails" => cmd_get_emails(state, input),
         "get_thread" => cmd_get_thread(state, input),
         "mark_read" => cmd_mark_read(state, input),
         "mark_unread" => cmd_mark_unread(state, input),
         "flag" => cmd_flag(state, input),
         "unflag" => cmd_unflag(state, input),
+        "set_keyword" => cmd_set_keyword(state, input),
         "move_email" => cmd_move_email(state, input),
         "bulk_move" => cmd_bulk_move(state, input),
         "archive" => cmd_archive(state, input),
         "bulk_archive" => cmd_bulk_archive(state, input),
+        "snooze" => cmd_snooze(state, input),
+        "mark_spam" => cmd_report_spam(state, input, true),
+        "mark_not_spam" => cmd_report_spam(state, input, false),
         "delete_email" => cmd_delete_email(state, input),
         "bulk_delete_email" => cmd_bulk_delete_email(state, input),
+        "move_thread" => cmd_move_thread(state, input),
+        "archive_thread" => cmd_archive_thread(state, input),
+        "delete_thread" => cmd_delete_thread(state, input),
+        "mute_thread" => cmd_set_thread_muted(state, input, true),
+        "unmute_thread" => cmd_set_thread_muted(state, input, false),
         "destroy" => cmd_destroy(state, input),
+        "undo" => cmd_undo(state),
+        "get_vacation" => cmd_get_vacation(state),
+        "set_vacation" => cmd_set_vacation(state, input),
         "triage_suggest" => cmd_triage_suggest(state, input),
         "apply_triage_plan" => cmd_apply_triage_plan(state, input),
+        "dry_run_rules" => cmd_dry_run_rules(state, input),
+        "run_rules" => cmd_run_rules(state, input),
+        "test_rules" => cmd_test_rules(state, input),
+        "retention_preview" => cmd_retention_preview(state, input),
+        "retention_execute" => cmd_retention_execute(state, input),
         "train" => cmd_train(state, input),
         "train_mailbox" => cmd_train_mailbox(state, input),
         "classify" => cmd_classify(state, input),
         "mark_mailbox_read" => cmd_mark_mailbox_read(state, input),
+        "empty_mailbox" => cmd_empty_mailbox(state, input),
         "get_raw_headers" => cmd_get_raw_headers(state, input),
+        "get_raw" => cmd_get_raw(state, input),
         "download_attachment" => cmd_download_attachment(state, input),
+        "download_all_attachments" => cmd_download_all_attachments(state, input),
+        "export_eml" => cmd_export_eml(state, input),
+        "complete_address" => cmd_complete_address(state, input),
         "compose_draft" => cmd_compose_draft(state),
         "reply_draft" => cmd_reply_draft(state, input),
         "forward_draft" => cmd_forward_draft(state, input),
-        "keybindings" => cmd_keybindings(),
+        "send_email" => cmd_send_email(state, input),
+        "save_draft" => cmd_save_draft(state, input),
+        "list_drafts" => cmd_list_drafts(state),
+        "resume_draft" => cmd_resume_draft(state, input),
+        "delete_draft" => cmd_delete_draft(state, input),
+        "keybindings" => cmd_keybindings(state),
         _ => err_response(&format!("unknown command '{}'", command)),
     }
 }
@@ -286,6 +501,25 @@ fn cmd_list_accounts(state: &CliState) -> Value {
     ok_response(json!({"accounts": accounts}))
 }
 
+fn cmd_list_identities(state: &CliState) -> Value {
+    if state.connected_account.is_none() {
+        return err_response("not connected");
+    }
+    let identities: Vec<Value> = state
+        .identities()
+        .iter()
+        .map(|i| {
+            json!({
+                "name": i.name,
+                "email": i.email,
+                "from": i.display_address(),
+                "signature": i.signature,
+            })
+        })
+        .collect();
+    ok_response(json!({"identities": identities}))
+}
+
 fn cmd_connect(state: &mut CliState, input: &Value) -> Value {
     let account_name = match input.get("account").and_then(|v| v.as_str()) {
         Some(name) => name,
@@ -302,20 +536,33 @@ fn cmd_connect(state: &mut CliState, input: &Value) -> Value {
         None => return err_response(&format!("unknown account '{}'", account_name)),
     };
 
-    // Disconnect existing backend if any
-    if let Some(ref tx) = state.cmd_tx {
-        let _ = tx.send(BackendCommand::Shutdown);
+    // Keep the current backend around for a later switch back.
+    if let Some((name, backend)) = state.take_active() {
+        state.pool.insert(name, backend);
+    }
+
+    let fresh = input
+        .get("fresh")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    if let Some(backend) = state.pool.remove(&account.name) {
+        if fresh {
+            backend.shutdown();
+        } else if backend.is_live() {
+            state.activate(account.name.clone(), backend);
+            return ok_response(json!({
+                "account": account.name,
+                "username": account.username,
+                "offline": state.offline,
+                "reused": true,
+            }));
+        }
     }
-    state.cmd_tx = None;
-    state.resp_rx = None;
-    state.connected_account = None;
-    state.connected_username = None;
-    state.cached_mailboxes.clear();
 
     let client = if state.offline {
         None
     } else {
-        match crate::connect_account(&account) {
+        match crate::connect_account(&account, state.config.ui.http_options()) {
             Ok(c) => Some(c),
             Err(e) => return err_response(&format!("connection failed: {}", e)),
         }
@@ -329,26 +576,59 @@ fn cmd_connect(state: &mut CliState, input: &Value) -> Value {
         state.rules_mailbox_regex.clone(),
         state.my_email_regex.clone(),
         state.config.spam.clone(),
+        state.config.cache.max_bytes,
+        state.config.ui.preview_chars,
     );
 
-    state.cmd_tx = Some(cmd_tx);
-    state.resp_rx = Some(resp_rx);
-    state.connected_account = Some(account.name.clone());
-    state.connected_username = Some(account.username.clone());
+    state.activate(
+        account.name.clone(),
+        PooledBackend {
+            cmd_tx,
+            resp_rx,
+            username: account.username.clone(),
+            cached_mailboxes: Vec::new(),
+        },
+    );
 
     ok_response(json!({
         "account": account.name,
         "username": account.username,
         "offline": state.offline,
+        "reused": false,
     }))
 }
 
+/// Shut down an account's backend: the named one (active or pooled), or the
+/// active one when no account is given.
+fn cmd_disconnect(state: &mut CliState, input: &Value) -> Value {
+    let account = match input.get("account").and_then(|v| v.as_str()) {
+        Some(name) => name.to_string(),
+        None => match &state.connected_account {
+            Some(name) => name.clone(),
+            None => return err_response("not connected"),
+        },
+    };
+    if state.connected_account.as_deref() == Some(account.as_str()) {
+        if let Some((_, backend)) = state.take_active() {
+            backend.shutdown();
+        }
+    } else if let Some(backend) = state.pool.remove(&account) {
+        backend.shutdown();
+    } else {
+        return err_response(&format!("not connected to '{}'", account));
+    }
+    ok_response(json!({"account": account}))
+}
+
 fn cmd_status(state: &CliState) -> Value {
+    let mut pooled: Vec<&String> = state.pool.keys().collect();
+    pooled.sort();
     ok_response(json!({
         "connected": state.cmd_tx.is_some(),
         "account": state.connected_account,
         "username": state.connected_username,
         "cached_mailboxes": state.cached_mailboxes.len(),
+        "pooled": pooled,
     }))
 }
 
@@ -371,6 +651,68 @@ fn cmd_list_mailboxes(state: &mut CliState) -> Value {
     }
 }
 
+fn cmd_unread_counts(state: &mut CliState, input: &Value) -> Value {
+    let only_unread = input
+        .get("only_unread")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    if let Err(e) = state.send_cmd(BackendCommand::FetchMailboxes {
+        origin: "cli".to_string(),
+    }) {
+        return err_response(&e);
+    }
+
+    match state.recv_resp() {
+        Ok(BackendResponse::Mailboxes(Ok(mailboxes))) => {
+            state.cached_mailboxes = mailboxes.clone();
+            let list: Vec<Value> = mailboxes
+                .iter()
+                .filter(|m| !only_unread || m.unread_emails > 0)
+                .map(|m| {
+                    json!({
+                        "mailbox_id": m.id,
+                        "name": m.name,
+                        "unread": m.unread_emails,
+                        "total": m.total_emails,
+                    })
+                })
+                .collect();
+            ok_response(json!({"mailboxes": list}))
+        }
+        Ok(BackendResponse::Mailboxes(Err(e))) => err_response(&e),
+        Ok(_) => err_response("unexpected response from backend"),
+        Err(e) => err_response(&e),
+    }
+}
+
+fn serialize_quota(quota: &Quota) -> Value {
+    json!({
+        "id": quota.id,
+        "name": quota.name,
+        "resource_type": quota.resource_type,
+        "scope": quota.scope,
+        "used": quota.used,
+        "limit": quota.hard_limit,
+    })
+}
+
+fn cmd_get_quota(state: &mut CliState) -> Value {
+    if let Err(e) = state.send_cmd(BackendCommand::GetQuota) {
+        return err_response(&e);
+    }
+
+    match state.recv_resp() {
+        Ok(BackendResponse::Quota { result: Ok(quotas) }) => {
+            let quota = quotas.map(|list| list.iter().map(serialize_quota).collect::<Vec<_>>());
+            ok_response(json!({"quota": quota}))
+        }
+        Ok(BackendResponse::Quota { result: Err(e) }) => err_response(&e),
+        Ok(_) => err_response("unexpected response from backend"),
+        Err(e) => err_response(&e),
+    }
+}
+
 fn cmd_create_mailbox(state: &mut CliState, input: &Value) -> Value {
     let name = match input.get("name").and_then(|v| v.as_str()) {
         Some(n) => n.to_string(),
@@ -422,27 +764,69 @@ fn cmd_delete_mailbox(state: &mut CliState, input: &Value) -> Value {
 }
 
 fn cmd_query_emails(state: &mut CliState, input: &Value) -> Value {
-    let mailbox_id = match input.get("mailbox_id").and_then(|v| v.as_str()) {
-        Some(id) => id.to_string(),
-        None => return err_response("missing 'mailbox_id' field"),
-    };
     let limit = input.get("limit").and_then(|v| v.as_u64()).unwrap_or(50) as u32;
-    let position = input.get("position").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
-    let search = input
-        .get("search")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
-    let received_after = input
-        .get("received_after")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
-    let received_before = input
-        .get("received_before")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
+    let str_field = |name: &str| input.get(name).and_then(|v| v.as_str()).map(str::to_string);
+    let mut mailbox_id = str_field("mailbox_id");
+    let mut position = input
+        .get("position")
+        .and_then(|v| v.as_u64())
+        .map(|p| p as u32);
+    let mut search = str_field("search");
+    let mut received_after = str_field("received_after");
+    let mut received_before = str_field("received_before");
+
+    // A cursor carries the mailbox, position and filters of the previous
+    // page; explicit fields may repeat them but not contradict them.
+    if let Some(token) = input.get("cursor").filter(|v| !v.is_null()) {
+        let cursor = match token.as_str().map(QueryCursor::decode) {
+            Some(Ok(cursor)) => cursor,
+            Some(Err(e)) => return err_response(&e),
+            None => return err_response("'cursor' must be a string"),
+        };
+        if position.is_some() {
+            return err_response("pass either 'cursor' or 'position', not both");
+        }
+        if let Some(id) = mailbox_id.as_deref().filter(|id| *id != cursor.mailbox_id) {
+            return err_response(&format!(
+                "cursor belongs to mailbox '{}', not '{}'",
+                cursor.mailbox_id, id
+            ));
+        }
+        for (name, given, from_cursor) in [
+            ("search", &mut search, cursor.search),
+            ("received_after", &mut received_after, cursor.received_after),
+            (
+                "received_before",
+                &mut received_before,
+                cursor.received_before,
+            ),
+        ] {
+            match given {
+                Some(value) if Some(&*value) != from_cursor.as_ref() => {
+                    return err_response(&format!(
+                        "cursor was issued for a different '{}'; query again without a cursor",
+                        name
+                    ));
+                }
+                _ => *given = from_cursor,
+            }
+        }
+        mailbox_id = Some(cursor.mailbox_id);
+        position = Some(cursor.position);
+    }
+    let Some(mailbox_id) = mailbox_id else {
+        return err_response("missing 'mailbox_id' field");
+    };
+    let position = position.unwrap_or(0);
+    let filters = (
+        search.clone(),
+        received_after.clone(),
+        received_before.clone(),
+    );
 
+    let sync = input.get("sync").and_then(|v| v.as_bool()).unwrap_or(false);
     if let Err(e) = state.send_cmd(BackendCommand::QueryEmails {
-        origin: "cli".to_string(),
+        origin: if sync { "cli:sync" } else { "cli" }.to_string(),
         mailbox_id: mailbox_id.clone(),
         page_size: limit,
         position,
@@ -484,12 +868,30 @@ fn cmd_query_emails(state: &mut CliState, input: &Value) -> Value {
                 .collect::<serde_json::Map<String, Value>>()
                 .into();
 
+            let next = position + loaded;
+            let more = match total {
+                Some(total) => loaded > 0 && next < total,
+                None => loaded > 0 && loaded >= limit,
+            };
+            let next_cursor = more.then(|| {
+                let (search, received_after, received_before) = filters;
+                QueryCursor {
+                    mailbox_id,
+                    position: next,
+                    search,
+                    received_after,
+                    received_before,
+                }
+                .encode()
+            });
+
             ok_response(json!({
                 "emails": list,
                 "total": total,
                 "position": position,
                 "loaded": loaded,
                 "thread_counts": tc,
+                "next_cursor": next_cursor,
             }))
         }
         Ok(BackendResponse::Emails { emails: Err(e), .. }) => err_response(&e),
@@ -498,6 +900,117 @@ fn cmd_query_emails(state: &mut CliState, input: &Value) -> Value {
     }
 }
 
+fn cmd_search_all(state: &mut CliState, input: &Value) -> Value {
+    let limit = input.get("limit").and_then(|v| v.as_u64()).unwrap_or(50) as u32;
+    let position = input.get("position").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+    let search = input
+        .get("search")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    let received_after = input
+        .get("received_after")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    let received_before = input
+        .get("received_before")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
+    if let Err(e) = state.send_cmd(BackendCommand::SearchAllEmails {
+        page_size: limit,
+        position,
+        search_query: search,
+        received_after,
+        received_before,
+    }) {
+        return err_response(&e);
+    }
+
+    match state.recv_resp() {
+        Ok(BackendResponse::SearchResults {
+            emails: Ok(emails),
+            total,
+            position,
+        }) => {
+            let headers_only = input
+                .get("headers_only")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            let max_body_chars = input
+                .get("max_body_chars")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0) as usize;
+
+            let list: Vec<Value> = emails
+                .iter()
+                .map(|e| serialize_email(e, headers_only, max_body_chars))
+                .collect();
+
+            ok_response(json!({
+                "emails": list,
+                "total": total,
+                "position": position,
+                "loaded": emails.len(),
+            }))
+        }
+        Ok(BackendResponse::SearchResults { emails: Err(e), .. }) => err_response(&e),
+        Ok(_) => err_response("unexpected response from backend"),
+        Err(e) => err_response(&e),
+    }
+}
+
+fn cmd_watch(state: &mut CliState, input: &Value) -> Value {
+    if state.offline {
+        return err_response("watch requires an online connection");
+    }
+    let interval_secs = input
+        .get("interval_secs")
+        .and_then(|v| v.as_u64())
+        .or(state.config.ui.sync_interval_secs)
+        .unwrap_or(60)
+        .max(1);
+    if let Err(e) = state.send_cmd(BackendCommand::Watch { interval_secs }) {
+        return err_response(&e);
+    }
+    match state.recv_resp() {
+        Ok(BackendResponse::WatchStarted { push }) => ok_response(json!({
+            "watching": true,
+            "interval_secs": interval_secs,
+            "push": push,
+        })),
+        Ok(_) => err_response("unexpected response from backend"),
+        Err(e) => err_response(&e),
+    }
+}
+
+/// Write one NDJSON event per mailbox `watch` reported changed since the
+/// last call, including any that arrived during a command.
+fn write_watch_events(state: &CliState, out: &mut impl Write) {
+    let mut mailboxes = std::mem::take(&mut *state.watch_events.borrow_mut());
+    if let Some(resp_rx) = state.resp_rx.as_ref() {
+        while let Ok(resp) = resp_rx.try_recv() {
+            if let BackendResponse::StateChanged { mailboxes: changed } = resp {
+                mailboxes.extend(changed);
+            }
+        }
+    }
+    if mailboxes.is_empty() {
+        return;
+    }
+    for mbox in &mailboxes {
+        let event = json!({
+            "event": "mailbox_changed",
+            "mailbox_id": mbox.id,
+            "name": mbox.name,
+            "total_emails": mbox.total_emails,
+            "unread_emails": mbox.unread_emails,
+        });
+        let _ = serde_json::to_writer(&mut *out, &event);
+        let _ = out.write_all(b"\n");
+    }
+    let _ = out.flush();
+}
+
 fn cmd_get_email(state: &mut CliState, input: &Value) -> Value {
     let id = match input.get("id").and_then(|v| v.as_str()) {
         Some(id) => id.to_string(),
@@ -533,6 +1046,46 @@ fn cmd_get_email(state: &mut CliState, input: &Value) -> Value {
     }
 }
 
+fn cmd_get_emails(state: &mut CliState, input: &Value) -> Value {
+    let ids = match parse_ids(input) {
+        Ok(ids) => ids,
+        Err(e) => return err_response(&e),
+    };
+
+    let headers_only = input
+        .get("headers_only")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let max_body_chars = input
+        .get("max_body_chars")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0) as usize;
+
+    if let Err(e) = state.send_cmd(BackendCommand::GetEmails { ids: ids.clone() }) {
+        return err_response(&e);
+    }
+
+    match state.recv_resp() {
+        Ok(BackendResponse::EmailBodies { result: Ok(emails) }) => {
+            let missing: Vec<&String> = ids
+                .iter()
+                .filter(|id| !emails.iter().any(|e| &e.id == *id))
+                .collect();
+            let list: Vec<Value> = emails
+                .iter()
+                .map(|e| serialize_email(e, headers_only, max_body_chars))
+                .collect();
+            ok_response(json!({
+                "emails": list,
+                "missing": missing,
+            }))
+        }
+        Ok(BackendResponse::EmailBodies { result: Err(e) }) => err_response(&e),
+        Ok(_) => err_response("unexpected response from backend"),
+        Err(e) => err_response(&e),
+    }
+}
+
 fn cmd_get_thread(state: &mut CliState, input: &Value) -> Value {
     let thread_id = match input.get("thread_id").and_then(|v| v.as_str()) {
         Some(id) => id.to_string(),
@@ -637,21 +1190,51 @@ fn cmd_unflag(state: &mut CliState, input: &Value) -> Value {
     recv_mutation_response(state)
 }
 
-fn cmd_move_email(state: &mut CliState, input: &Value) -> Value {
+fn cmd_set_keyword(state: &mut CliState, input: &Value) -> Value {
     let id = match input.get("id").and_then(|v| v.as_str()) {
         Some(id) => id.to_string(),
         None => return err_response("missing 'id' field"),
     };
-    let to_mailbox_id = match input.get("to_mailbox_id").and_then(|v| v.as_str()) {
-        Some(id) => id.to_string(),
-        None => return err_response("missing 'to_mailbox_id' field"),
+    let keyword = match input.get("keyword").and_then(|v| v.as_str()) {
+        Some(k) if !k.is_empty() => k.to_string(),
+        _ => return err_response("missing 'keyword' field"),
+    };
+    if let Err(e) = validate_keyword(&keyword) {
+        return err_response(&e);
+    }
+    let value = match input.get("value").and_then(|v| v.as_bool()) {
+        Some(v) => v,
+        None => return err_response("missing 'value' field (bool)"),
     };
     let op_id = state.next_op_id();
 
-    if let Err(e) = state.send_cmd(BackendCommand::MoveEmail {
+    if let Err(e) = state.send_cmd(BackendCommand::SetKeyword {
         op_id,
         id,
-        to_mailbox_id,
+        keyword,
+        value,
+    }) {
+        return err_response(&e);
+    }
+
+    recv_mutation_response(state)
+}
+
+fn cmd_move_email(state: &mut CliState, input: &Value) -> Value {
+    let id = match input.get("id").and_then(|v| v.as_str()) {
+        Some(id) => id.to_string(),
+        None => return err_response("missing 'id' field"),
+    };
+    let to_mailbox_id = match input.get("to_mailbox_id").and_then(|v| v.as_str()) {
+        Some(id) => id.to_string(),
+        None => return err_response("missing 'to_mailbox_id' field"),
+    };
+    let op_id = state.next_op_id();
+
+    if let Err(e) = state.send_cmd(BackendCommand::MoveEmail {
+        op_id,
+        id,
+        to_mailbox_id,
     }) {
         return err_response(&e);
     }
@@ -771,6 +1354,79 @@ fn cmd_bulk_archive(state: &mut CliState, input: &Value) -> Value {
     mutate_many_move(state, &ids, &archive_id)
 }
 
+/// Set `$junk` (or `$notjunk`), clear the other, and move the email to the
+/// spam folder (or the inbox), as one mutation that `undo` reverses whole.
+fn cmd_report_spam(state: &mut CliState, input: &Value, is_spam: bool) -> Value {
+    let id = match input.get("id").and_then(|v| v.as_str()) {
+        Some(id) => id.to_string(),
+        None => return err_response("missing 'id' field"),
+    };
+    let folder = if is_spam {
+        state.config.mail.spam_folder.clone()
+    } else {
+        "inbox".to_string()
+    };
+    let Some(to_mailbox_id) = state.resolve_folder_id(&folder, None) else {
+        return err_response(&format!("cannot resolve folder '{}'", folder));
+    };
+
+    let op_id = state.next_op_id();
+    if let Err(e) = state.send_cmd(BackendCommand::ReportSpam {
+        op_id,
+        ids: vec![id.clone()],
+        spam: is_spam,
+        to_mailbox_id: to_mailbox_id.clone(),
+    }) {
+        return err_response(&e);
+    }
+    let resp = recv_mutation_response(state);
+    if resp.get("ok") != Some(&Value::Bool(true)) {
+        return resp;
+    }
+    let keywords: serde_json::Map<String, Value> = backend::spam_report_keywords(is_spam)
+        .into_iter()
+        .map(|(keyword, value)| (keyword.to_string(), Value::Bool(value)))
+        .collect();
+    ok_response(json!({
+        "id": id,
+        "spam": is_spam,
+        "mailbox_id": to_mailbox_id,
+        "keywords": keywords,
+    }))
+}
+
+fn cmd_snooze(state: &mut CliState, input: &Value) -> Value {
+    let id = match input.get("id").and_then(|v| v.as_str()) {
+        Some(id) => id.to_string(),
+        None => return err_response("missing 'id' field"),
+    };
+    let until = match input.get("until") {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Number(n)) => n.to_string(),
+        _ => return err_response("missing 'until' field"),
+    };
+    let wake_at = match snooze::resolve_wake_time(&until, snooze::now_secs()) {
+        Ok(wake_at) => wake_at,
+        Err(e) => return err_response(&e),
+    };
+
+    let op_id = state.next_op_id();
+    if let Err(e) = state.send_cmd(BackendCommand::SnoozeEmail {
+        op_id,
+        id,
+        folder: state.config.mail.snooze_folder.clone(),
+        wake_at,
+    }) {
+        return err_response(&e);
+    }
+
+    let mut resp = recv_mutation_response(state);
+    if resp.get("ok").and_then(|v| v.as_bool()) == Some(true) {
+        resp["wake_at"] = json!(wake_at);
+    }
+    resp
+}
+
 fn cmd_delete_email(state: &mut CliState, input: &Value) -> Value {
     let id = match input.get("id").and_then(|v| v.as_str()) {
         Some(id) => id.to_string(),
@@ -802,6 +1458,84 @@ fn cmd_delete_email(state: &mut CliState, input: &Value) -> Value {
     recv_mutation_response(state)
 }
 
+fn send_move_thread(state: &mut CliState, input: &Value, to_mailbox_id: String) -> Value {
+    let thread_id = match input.get("thread_id").and_then(|v| v.as_str()) {
+        Some(id) => id.to_string(),
+        None => return err_response("missing 'thread_id' field"),
+    };
+    let op_id = state.next_op_id();
+
+    if let Err(e) = state.send_cmd(BackendCommand::MoveThread {
+        op_id,
+        thread_id,
+        to_mailbox_id,
+    }) {
+        return err_response(&e);
+    }
+
+    recv_mutation_response(state)
+}
+
+fn cmd_move_thread(state: &mut CliState, input: &Value) -> Value {
+    let to_mailbox_id = match input.get("to_mailbox_id").and_then(|v| v.as_str()) {
+        Some(id) => id.to_string(),
+        None => return err_response("missing 'to_mailbox_id' field"),
+    };
+    send_move_thread(state, input, to_mailbox_id)
+}
+
+fn cmd_archive_thread(state: &mut CliState, input: &Value) -> Value {
+    let archive_folder = state.archive_folder.clone();
+    let archive_mailbox_id = state.archive_mailbox_id.clone();
+    let archive_id = match state.resolve_folder_id(&archive_folder, archive_mailbox_id.as_deref()) {
+        Some(id) => id,
+        None => {
+            return err_response(&format!(
+                "cannot resolve archive folder '{}'",
+                archive_folder
+            ))
+        }
+    };
+    send_move_thread(state, input, archive_id)
+}
+
+fn cmd_delete_thread(state: &mut CliState, input: &Value) -> Value {
+    let deleted_folder = state.deleted_folder.clone();
+    let deleted_mailbox_id = state.deleted_mailbox_id.clone();
+    let deleted_id = match state.resolve_folder_id(&deleted_folder, deleted_mailbox_id.as_deref()) {
+        Some(id) => id,
+        None => {
+            return err_response(&format!(
+                "cannot resolve deleted folder '{}'",
+                deleted_folder
+            ))
+        }
+    };
+    send_move_thread(state, input, deleted_id)
+}
+
+fn cmd_set_thread_muted(state: &mut CliState, input: &Value, muted: bool) -> Value {
+    let thread_id = match input.get("thread_id").and_then(|v| v.as_str()) {
+        Some(id) => id.to_string(),
+        None => return err_response("missing 'thread_id' field"),
+    };
+    if let Err(e) = state.send_cmd(BackendCommand::SetThreadMuted {
+        thread_id,
+        muted: Some(muted),
+    }) {
+        return err_response(&e);
+    }
+    match state.recv_resp() {
+        Ok(BackendResponse::ThreadMuted {
+            thread_id,
+            result: Ok(muted),
+        }) => ok_response(json!({"thread_id": thread_id, "muted": muted})),
+        Ok(BackendResponse::ThreadMuted { result: Err(e), .. }) => err_response(&e),
+        Ok(_) => err_response("unexpected response from backend"),
+        Err(e) => err_response(&e),
+    }
+}
+
 fn cmd_bulk_delete_email(state: &mut CliState, input: &Value) -> Value {
     let ids = match parse_ids(input) {
         Ok(ids) => ids,
@@ -821,19 +1555,22 @@ fn cmd_bulk_delete_email(state: &mut CliState, input: &Value) -> Value {
     mutate_many_move(state, &ids, &deleted_id)
 }
 
+/// Triage targets from the rules that run in `mailbox_id`.
 fn triage_from_rules(
     state: &CliState,
+    mailbox_id: &str,
     emails: &[Email],
 ) -> HashMap<String, (TriageTarget, String, f32)> {
     let mut out = HashMap::new();
     if state.rules.is_empty() {
         return out;
     }
-    let applications = rules::apply_rules(
+    let applications = rules::apply_rules_where(
         &state.rules,
         emails,
         &state.cached_mailboxes,
         &state.my_email_regex,
+        rules::runs_on_demand(mailbox_id, &state.cached_mailboxes),
     );
     let mut by_name = HashMap::new();
     for rule in state.rules.iter() {
@@ -988,28 +1725,58 @@ fn cmd_triage_suggest(state: &mut CliState, input: &Value) -> Value {
         .and_then(|v| v.as_str())
         .map(|s| s.to_string());
 
-    if let Err(e) = state.send_cmd(BackendCommand::QueryEmails {
-        origin: "cli:triage".to_string(),
-        mailbox_id: mailbox_id.clone(),
-        page_size: limit,
-        position,
-        search_query: search,
-        received_after,
-        received_before,
-    }) {
-        return err_response(&e);
-    }
-
-    let emails = match state.recv_resp() {
-        Ok(BackendResponse::Emails {
-            emails: Ok(list), ..
-        }) => list,
-        Ok(BackendResponse::Emails { emails: Err(e), .. }) => return err_response(&e),
-        Ok(_) => return err_response("unexpected response from backend"),
-        Err(e) => return err_response(&e),
+    // With emit_progress the mailbox is read in pages, reporting each one.
+    let emit = wants_progress(input);
+    let page_size = if emit {
+        TRIAGE_PROGRESS_PAGE.min(limit)
+    } else {
+        limit
     };
+    let mut emails = Vec::new();
+    loop {
+        let fetched = emails.len() as u32;
+        let request_size = page_size.min(limit - fetched);
+        if let Err(e) = state.send_cmd(BackendCommand::QueryEmails {
+            origin: "cli:triage".to_string(),
+            mailbox_id: mailbox_id.clone(),
+            page_size: request_size,
+            position: position + fetched,
+            search_query: search.clone(),
+            received_after: received_after.clone(),
+            received_before: received_before.clone(),
+        }) {
+            return err_response(&e);
+        }
+
+        let (page, total) = match state.recv_resp() {
+            Ok(BackendResponse::Emails {
+                emails: Ok(list),
+                total,
+                ..
+            }) => (list, total),
+            Ok(BackendResponse::Emails { emails: Err(e), .. }) => return err_response(&e),
+            Ok(_) => return err_response("unexpected response from backend"),
+            Err(e) => return err_response(&e),
+        };
+        let got = page.len() as u32;
+        emails.extend(page);
+        // Without a server total, a short page marks the end.
+        let target = total.map_or(limit, |t| t.saturating_sub(position).min(limit));
+        let done = got < request_size || emails.len() as u32 >= target;
+        if emit {
+            let total = if done { emails.len() } else { target as usize };
+            write_progress(emails.len(), total);
+        }
+        if !emit || done {
+            break;
+        }
+    }
 
-    let rule_targets = triage_from_rules(state, &emails);
+    // Rule mailbox patterns match against the folder's name and path.
+    if !state.rules.is_empty() && state.cached_mailboxes.is_empty() {
+        let _ = state.refresh_mailboxes("cli:triage");
+    }
+    let rule_targets = triage_from_rules(state, &mailbox_id, &emails);
     let mut archive = Vec::new();
     let mut trash = Vec::new();
     let mut keep = Vec::new();
@@ -1073,6 +1840,165 @@ fn cmd_triage_suggest(state: &mut CliState, input: &Value) -> Value {
     }))
 }
 
+/// Resolve `mailbox_id` from the request plus its display name for the rules commands.
+fn rules_mailbox_target(state: &mut CliState, input: &Value) -> Result<(String, String), String> {
+    let mailbox_id = input
+        .get("mailbox_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "missing 'mailbox_id' field".to_string())?
+        .to_string();
+    if !state.cached_mailboxes.iter().any(|m| m.id == mailbox_id) {
+        state.refresh_mailboxes("cli:rules")?;
+    }
+    let mailbox_name = state
+        .cached_mailboxes
+        .iter()
+        .find(|m| m.id == mailbox_id)
+        .map(|m| m.name.clone())
+        .ok_or_else(|| format!("unknown mailbox_id '{}'", mailbox_id))?;
+    Ok((mailbox_id, mailbox_name))
+}
+
+fn cmd_dry_run_rules(state: &mut CliState, input: &Value) -> Value {
+    let (mailbox_id, mailbox_name) = match rules_mailbox_target(state, input) {
+        Ok(target) => target,
+        Err(e) => return err_response(&e),
+    };
+    if let Err(e) = state.send_cmd(BackendCommand::PreviewRulesForMailbox {
+        origin: "cli:dry_run_rules".to_string(),
+        mailbox_id,
+        mailbox_name,
+        progress: wants_progress(input),
+    }) {
+        return err_response(&e);
+    }
+
+    match state.recv_resp_with_progress(input) {
+        Ok(BackendResponse::RulesDryRun {
+            mailbox_id,
+            mailbox_name,
+            result,
+        }) => match result {
+            Ok(preview) => {
+                let entries: Vec<Value> = preview
+                    .entries
+                    .iter()
+                    .map(|e| {
+                        json!({
+                            "id": e.id,
+                            "received_at": e.received_at,
+                            "from": e.from,
+                            "subject": e.subject,
+                            "rule": e.rule_name,
+                            "actions": e.actions,
+                        })
+                    })
+                    .collect();
+                ok_response(json!({
+                    "mailbox_id": mailbox_id,
+                    "mailbox_name": mailbox_name,
+                    "scanned": preview.scanned,
+                    "matched_rules": preview.matched_rules,
+                    "actions": preview.actions,
+                    "entries": entries,
+                }))
+            }
+            Err(e) => err_response(&e),
+        },
+        Ok(_) => err_response("unexpected response from backend"),
+        Err(e) => err_response(&e),
+    }
+}
+
+fn cmd_run_rules(state: &mut CliState, input: &Value) -> Value {
+    let (mailbox_id, mailbox_name) = match rules_mailbox_target(state, input) {
+        Ok(target) => target,
+        Err(e) => return err_response(&e),
+    };
+    if let Err(e) = state.send_cmd(BackendCommand::RunRulesForMailbox {
+        origin: "cli:run_rules".to_string(),
+        mailbox_id,
+        mailbox_name,
+        progress: wants_progress(input),
+    }) {
+        return err_response(&e);
+    }
+
+    match state.recv_resp_with_progress(input) {
+        Ok(BackendResponse::RulesRun {
+            mailbox_id,
+            mailbox_name,
+            result,
+        }) => match result {
+            Ok(run) => ok_response(json!({
+                "mailbox_id": mailbox_id,
+                "mailbox_name": mailbox_name,
+                "scanned": run.scanned,
+                "matched_rules": run.matched_rules,
+                "actions": run.actions,
+            })),
+            Err(e) => err_response(&e),
+        },
+        Ok(_) => err_response("unexpected response from backend"),
+        Err(e) => err_response(&e),
+    }
+}
+
+fn cmd_test_rules(state: &mut CliState, input: &Value) -> Value {
+    let id = match input.get("id").and_then(|v| v.as_str()) {
+        Some(id) => id.to_string(),
+        None => return err_response("missing 'id' field"),
+    };
+    if let Err(e) = state.send_cmd(BackendCommand::TestRulesForEmail { id }) {
+        return err_response(&e);
+    }
+
+    match state.recv_resp() {
+        Ok(BackendResponse::RulesTest { id, result }) => match result {
+            Ok(test) => {
+                let matched: Vec<Value> = test
+                    .matches
+                    .iter()
+                    .map(|(rule, actions)| json!({"rule": rule, "actions": actions}))
+                    .collect();
+                let rules: Vec<Value> = test
+                    .traces
+                    .iter()
+                    .map(|t| {
+                        let conditions: Vec<Value> = t
+                            .conditions
+                            .iter()
+                            .map(|c| {
+                                json!({
+                                    "depth": c.depth,
+                                    "condition": c.condition,
+                                    "passed": c.passed,
+                                })
+                            })
+                            .collect();
+                        json!({
+                            "rule": t.rule_name,
+                            "matched": t.matched,
+                            "skipped_to_me": t.skipped_to_me,
+                            "conditions": conditions,
+                        })
+                    })
+                    .collect();
+                ok_response(json!({
+                    "id": id,
+                    "subject": test.subject,
+                    "from": test.from,
+                    "matched": matched,
+                    "rules": rules,
+                }))
+            }
+            Err(e) => err_response(&e),
+        },
+        Ok(_) => err_response("unexpected response from backend"),
+        Err(e) => err_response(&e),
+    }
+}
+
 fn cmd_apply_triage_plan(state: &mut CliState, input: &Value) -> Value {
     let (archive_ids, trash_ids) =
         if let Some(plan_id) = input.get("plan_id").and_then(|v| v.as_str()) {
@@ -1136,6 +2062,82 @@ fn cmd_apply_triage_plan(state: &mut CliState, input: &Value) -> Value {
     }))
 }
 
+fn cmd_undo(state: &mut CliState) -> Value {
+    if let Err(e) = state.send_cmd(BackendCommand::Undo { move_op: None }) {
+        return err_response(&e);
+    }
+    match state.recv_resp() {
+        Ok(BackendResponse::Undone {
+            result: Ok(description),
+        }) => ok_response(json!({"undone": description})),
+        Ok(BackendResponse::Undone { result: Err(e) }) => err_response(&e),
+        Ok(_) => err_response("unexpected response from backend"),
+        Err(e) => err_response(&e),
+    }
+}
+
+fn serialize_vacation(vacation: &VacationResponse) -> Value {
+    json!({
+        "enabled": vacation.is_enabled,
+        "subject": vacation.subject,
+        "text_body": vacation.text_body,
+        "from_date": vacation.from_date,
+        "to_date": vacation.to_date,
+    })
+}
+
+fn recv_vacation_response(state: &CliState) -> Value {
+    match state.recv_resp() {
+        Ok(BackendResponse::Vacation {
+            result: Ok(vacation),
+        }) => ok_response(serialize_vacation(&vacation)),
+        Ok(BackendResponse::Vacation { result: Err(e) }) => err_response(&e),
+        Ok(_) => err_response("unexpected response from backend"),
+        Err(e) => err_response(&e),
+    }
+}
+
+fn cmd_get_vacation(state: &mut CliState) -> Value {
+    if let Err(e) = state.send_cmd(BackendCommand::GetVacation) {
+        return err_response(&e);
+    }
+    recv_vacation_response(state)
+}
+
+fn cmd_set_vacation(state: &mut CliState, input: &Value) -> Value {
+    let enabled = match input.get("enabled").and_then(|v| v.as_bool()) {
+        Some(enabled) => enabled,
+        None => return err_response("missing 'enabled' field (bool)"),
+    };
+    let text = |field: &str| match input.get(field) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s.clone())),
+        Some(_) => Err(format!("'{}' must be a string", field)),
+    };
+    let vacation = match (
+        text("subject"),
+        text("text_body"),
+        text("from_date"),
+        text("to_date"),
+    ) {
+        (Ok(subject), Ok(text_body), Ok(from_date), Ok(to_date)) => VacationResponse {
+            is_enabled: enabled,
+            subject,
+            text_body,
+            from_date,
+            to_date,
+        },
+        (Err(e), ..) | (_, Err(e), ..) | (_, _, Err(e), _) | (.., Err(e)) => {
+            return err_response(&e)
+        }
+    };
+
+    if let Err(e) = state.send_cmd(BackendCommand::SetVacation { vacation }) {
+        return err_response(&e);
+    }
+    recv_vacation_response(state)
+}
+
 fn cmd_destroy(state: &mut CliState, input: &Value) -> Value {
     let ids = match input.get("ids").and_then(|v| v.as_array()) {
         Some(arr) => {
@@ -1177,14 +2179,178 @@ fn cmd_destroy(state: &mut CliState, input: &Value) -> Value {
         }
     }
 
-    ok_response(json!({"results": results}))
+    ok_response(json!({"results": results}))
+}
+
+/// Policies from the request's `policies` array, or `[retention.*]` from the
+/// config when it is absent.
+fn retention_policies(
+    state: &CliState,
+    input: &Value,
+) -> Result<Vec<RetentionPolicyConfig>, String> {
+    let Some(raw) = input.get("policies") else {
+        if state.config.mail.retention_policies.is_empty() {
+            return Err("no [retention.*] policies configured".to_string());
+        }
+        return Ok(state.config.mail.retention_policies.clone());
+    };
+    let raw = raw
+        .as_array()
+        .ok_or_else(|| "'policies' must be an array".to_string())?;
+    let mut policies = Vec::new();
+    for (i, policy) in raw.iter().enumerate() {
+        let name = policy
+            .get("name")
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("policy-{}", i + 1));
+        let folder = policy
+            .get("folder")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| format!("missing folder in policy '{}'", name))?
+            .to_string();
+        let count = |key: &str| {
+            policy
+                .get(key)
+                .and_then(|v| v.as_u64())
+                .map(|n| n.min(u32::MAX as u64) as u32)
+        };
+        let policy = RetentionPolicyConfig {
+            name,
+            folder,
+            days: count("days"),
+            keep_newest: count("keep_newest"),
+            only_read: policy
+                .get("only_read")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            only_unflagged: policy
+                .get("only_unflagged")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+        };
+        policy
+            .check()
+            .map_err(|e| format!("{} in policy '{}'", e, policy.name))?;
+        policies.push(policy);
+    }
+    Ok(policies)
+}
+
+fn cmd_retention_preview(state: &mut CliState, input: &Value) -> Value {
+    let policies = match retention_policies(state, input) {
+        Ok(policies) => policies,
+        Err(e) => return err_response(&e),
+    };
+    if let Err(e) = state.send_cmd(BackendCommand::PreviewRetentionExpiry { policies }) {
+        return err_response(&e);
+    }
+
+    match state.recv_resp() {
+        Ok(BackendResponse::RetentionPreview { result }) => match result {
+            Ok(preview) => {
+                let candidates: Vec<Value> = preview
+                    .candidates
+                    .iter()
+                    .map(|c| {
+                        json!({
+                            "id": c.id,
+                            "mailbox": c.mailbox,
+                            "policy": c.policy,
+                            "received_at": c.received_at,
+                            "from": c.from,
+                            "subject": c.subject,
+                        })
+                    })
+                    .collect();
+                ok_response(json!({
+                    "count": candidates.len(),
+                    "candidates": candidates,
+                }))
+            }
+            Err(e) => err_response(&e),
+        },
+        Ok(_) => err_response("unexpected response from backend"),
+        Err(e) => err_response(&e),
+    }
+}
+
+fn cmd_retention_execute(state: &mut CliState, input: &Value) -> Value {
+    let policies = match retention_policies(state, input) {
+        Ok(policies) => policies,
+        Err(e) => return err_response(&e),
+    };
+    if let Err(e) = state.send_cmd(BackendCommand::ExecuteRetentionExpiry { policies }) {
+        return err_response(&e);
+    }
+
+    match state.recv_resp() {
+        Ok(BackendResponse::RetentionExecuted { result }) => match result {
+            Ok(summary) => ok_response(json!({
+                "deleted": summary.deleted,
+                "failed_batches": summary.failed_batches,
+            })),
+            Err(e) => err_response(&e),
+        },
+        Ok(_) => err_response("unexpected response from backend"),
+        Err(e) => err_response(&e),
+    }
+}
+
+fn cmd_mark_mailbox_read(state: &mut CliState, input: &Value) -> Value {
+    let mailbox_id = match input.get("mailbox_id").and_then(|v| v.as_str()) {
+        Some(id) => id.to_string(),
+        None => return err_response("missing 'mailbox_id' field"),
+    };
+
+    let mailbox_name = state
+        .cached_mailboxes
+        .iter()
+        .find(|m| m.id == mailbox_id)
+        .map(|m| m.name.clone())
+        .unwrap_or_else(|| mailbox_id.clone());
+
+    if let Err(e) = state.send_cmd(BackendCommand::MarkMailboxRead {
+        mailbox_id,
+        mailbox_name,
+    }) {
+        return err_response(&e);
+    }
+
+    match state.recv_resp() {
+        Ok(BackendResponse::MailboxMarkedRead {
+            mailbox_name,
+            updated,
+            result,
+            ..
+        }) => match result {
+            Ok(()) => ok_response(json!({
+                "mailbox_name": mailbox_name,
+                "updated": updated,
+            })),
+            Err(e) => err_response(&e),
+        },
+        Ok(_) => err_response("unexpected response from backend"),
+        Err(e) => err_response(&e),
+    }
 }
 
-fn cmd_mark_mailbox_read(state: &mut CliState, input: &Value) -> Value {
+fn cmd_empty_mailbox(state: &mut CliState, input: &Value) -> Value {
     let mailbox_id = match input.get("mailbox_id").and_then(|v| v.as_str()) {
         Some(id) => id.to_string(),
         None => return err_response("missing 'mailbox_id' field"),
     };
+    if state.cached_mailboxes.is_empty() {
+        let _ = state.refresh_mailboxes("cli:empty-mailbox");
+    }
+    if !backend::is_emptiable_mailbox(
+        &mailbox_id,
+        &state.cached_mailboxes,
+        &state.config.mail.deleted_folder,
+        &state.config.mail.spam_folder,
+    ) {
+        return err_response("only the Trash or Junk mailbox can be emptied");
+    }
 
     let mailbox_name = state
         .cached_mailboxes
@@ -1193,23 +2359,39 @@ fn cmd_mark_mailbox_read(state: &mut CliState, input: &Value) -> Value {
         .map(|m| m.name.clone())
         .unwrap_or_else(|| mailbox_id.clone());
 
-    if let Err(e) = state.send_cmd(BackendCommand::MarkMailboxRead {
+    // Destroy what is there now, not mail that arrives while this runs.
+    if let Err(e) = state.send_cmd(BackendCommand::PreviewEmptyMailbox {
+        mailbox_id: mailbox_id.clone(),
+        mailbox_name: mailbox_name.clone(),
+    }) {
+        return err_response(&e);
+    }
+    let ids = match state.recv_resp() {
+        Ok(BackendResponse::EmptyMailboxPreview { result, .. }) => match result {
+            Ok(ids) => ids,
+            Err(e) => return err_response(&e),
+        },
+        Ok(_) => return err_response("unexpected response from backend"),
+        Err(e) => return err_response(&e),
+    };
+    if let Err(e) = state.send_cmd(BackendCommand::EmptyMailbox {
         mailbox_id,
         mailbox_name,
+        ids,
     }) {
         return err_response(&e);
     }
 
     match state.recv_resp() {
-        Ok(BackendResponse::MailboxMarkedRead {
+        Ok(BackendResponse::MailboxEmptied {
             mailbox_name,
-            updated,
+            destroyed,
             result,
             ..
         }) => match result {
             Ok(()) => ok_response(json!({
                 "mailbox_name": mailbox_name,
-                "updated": updated,
+                "destroyed": destroyed,
             })),
             Err(e) => err_response(&e),
         },
@@ -1238,6 +2420,46 @@ fn cmd_get_raw_headers(state: &mut CliState, input: &Value) -> Value {
     }
 }
 
+/// The complete RFC822 source, body included (`get_raw_headers` stops at
+/// the first blank line). Sources that are not valid UTF-8 come back
+/// base64-encoded so no octet is lost; `bytes` always counts the original.
+fn cmd_get_raw(state: &mut CliState, input: &Value) -> Value {
+    let id = match input.get("id").and_then(|v| v.as_str()) {
+        Some(id) => id.to_string(),
+        None => return err_response("missing 'id' field"),
+    };
+
+    if let Err(e) = state.send_cmd(BackendCommand::GetEmailRaw { id }) {
+        return err_response(&e);
+    }
+
+    match state.recv_resp() {
+        Ok(BackendResponse::EmailRaw { id, result }) => match result {
+            Ok(raw) => {
+                let (encoding, text) = match std::str::from_utf8(&raw) {
+                    Ok(text) => ("utf-8", text.to_string()),
+                    Err(_) => {
+                        use base64::Engine;
+                        (
+                            "base64",
+                            base64::engine::general_purpose::STANDARD.encode(&raw),
+                        )
+                    }
+                };
+                ok_response(json!({
+                    "id": id,
+                    "bytes": raw.len(),
+                    "encoding": encoding,
+                    "raw": text,
+                }))
+            }
+            Err(e) => err_response(&e),
+        },
+        Ok(_) => err_response("unexpected response from backend"),
+        Err(e) => err_response(&e),
+    }
+}
+
 fn cmd_download_attachment(state: &mut CliState, input: &Value) -> Value {
     let blob_id = match input.get("blob_id").and_then(|v| v.as_str()) {
         Some(id) => id.to_string(),
@@ -1253,11 +2475,16 @@ fn cmd_download_attachment(state: &mut CliState, input: &Value) -> Value {
         .and_then(|v| v.as_str())
         .unwrap_or("application/octet-stream")
         .to_string();
+    let dir = match input.get("dir").and_then(|v| v.as_str()) {
+        Some(dir) => crate::config::expand_path(dir),
+        None => backend::download_dir(state.config.ui.download_dir.as_deref()),
+    };
 
     if let Err(e) = state.send_cmd(BackendCommand::DownloadAttachment {
         blob_id,
         name,
         content_type,
+        dir,
     }) {
         return err_response(&e);
     }
@@ -1275,10 +2502,181 @@ fn cmd_download_attachment(state: &mut CliState, input: &Value) -> Value {
     }
 }
 
+fn cmd_download_all_attachments(state: &mut CliState, input: &Value) -> Value {
+    let id = match input.get("id").and_then(|v| v.as_str()) {
+        Some(id) => id.to_string(),
+        None => return err_response("missing 'id' field"),
+    };
+    let include_inline = input
+        .get("include_inline")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let dir = match input.get("dir").and_then(|v| v.as_str()) {
+        Some(dir) => crate::config::expand_path(dir),
+        None => backend::download_dir(state.config.ui.download_dir.as_deref()),
+    };
+
+    if let Err(e) = state.send_cmd(BackendCommand::GetEmail { id: id.clone() }) {
+        return err_response(&e);
+    }
+    let email = match state.recv_resp() {
+        Ok(BackendResponse::EmailBody {
+            result: boxed_result,
+            ..
+        }) => match *boxed_result {
+            Ok(email) => email,
+            Err(e) => return err_response(&e),
+        },
+        Ok(_) => return err_response("unexpected response from backend"),
+        Err(e) => return err_response(&e),
+    };
+
+    let attachments = backend::attachment_downloads(&email, include_inline);
+    if attachments.is_empty() {
+        return ok_response(json!({"id": id, "paths": [], "failed": []}));
+    }
+    if let Err(e) = state.send_cmd(BackendCommand::DownloadAttachments { attachments, dir }) {
+        return err_response(&e);
+    }
+
+    match state.recv_resp() {
+        Ok(BackendResponse::AttachmentsDownloaded { saved, failed }) => {
+            let paths: Vec<String> = saved
+                .iter()
+                .map(|p| p.to_string_lossy().into_owned())
+                .collect();
+            ok_response(json!({"id": id, "paths": paths, "failed": failed}))
+        }
+        Ok(_) => err_response("unexpected response from backend"),
+        Err(e) => err_response(&e),
+    }
+}
+
+fn cmd_export_eml(state: &mut CliState, input: &Value) -> Value {
+    let id = match input.get("id").and_then(|v| v.as_str()) {
+        Some(id) => id.to_string(),
+        None => return err_response("missing 'id' field"),
+    };
+    let path = match input.get("path").and_then(|v| v.as_str()) {
+        Some(p) => PathBuf::from(p),
+        None => backend::download_dir(state.config.ui.download_dir.as_deref())
+            .join(format!("{}.eml", backend::safe_file_name(&id))),
+    };
+
+    if let Err(e) = state.send_cmd(BackendCommand::GetEmailRaw { id }) {
+        return err_response(&e);
+    }
+
+    match state.recv_resp() {
+        Ok(BackendResponse::EmailRaw { id, result }) => match result {
+            Ok(raw) => {
+                if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                    if let Err(e) = std::fs::create_dir_all(parent) {
+                        return err_response(&format!("Failed to create directory: {}", e));
+                    }
+                }
+                if let Err(e) = std::fs::write(&path, &raw) {
+                    return err_response(&format!("Failed to write file: {}", e));
+                }
+                ok_response(json!({
+                    "id": id,
+                    "path": path.to_string_lossy(),
+                    "bytes": raw.len(),
+                }))
+            }
+            Err(e) => err_response(&e),
+        },
+        Ok(_) => err_response("unexpected response from backend"),
+        Err(e) => err_response(&e),
+    }
+}
+
+const ATTACH_HINT: &str =
+    "To attach files, add a header line such as 'Attach: ~/report.pdf' or 'Attach: [\"~/report.pdf\", \"/tmp/photo.jpg\"]' before the --text follows this line-- marker.";
+
+/// How many recently seen addresses `compose_draft` lists.
+const RECENT_ADDRESSES: usize = 10;
+
+/// Addresses counted from the connected account's cached mail, minus the
+/// user's own (username, identities, `my_email_regex`). Empty when not
+/// connected.
+fn seen_addresses(state: &CliState) -> Vec<SeenAddress> {
+    if state.send_cmd(BackendCommand::GetSeenAddresses).is_err() {
+        return Vec::new();
+    }
+    let is_mine = |email: &str| {
+        state.my_email_regex.is_match(email)
+            || state
+                .connected_username
+                .as_deref()
+                .is_some_and(|u| u.eq_ignore_ascii_case(email))
+            || state
+                .identities()
+                .iter()
+                .any(|i| i.email.eq_ignore_ascii_case(email))
+    };
+    match state.recv_resp() {
+        Ok(BackendResponse::SeenAddresses(seen)) => {
+            seen.into_iter().filter(|a| !is_mine(&a.email)).collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn cmd_complete_address(state: &mut CliState, input: &Value) -> Value {
+    let prefix = match input.get("prefix").and_then(|v| v.as_str()) {
+        Some(p) => p,
+        None => return err_response("missing 'prefix' field"),
+    };
+    let limit = input.get("limit").and_then(|v| v.as_u64()).unwrap_or(20) as usize;
+    if state.address_book.is_none() {
+        match state.config.contacts.load() {
+            Ok(c) => state.address_book = Some(c),
+            Err(e) => return err_response(&e),
+        }
+    }
+    let seen = seen_addresses(state);
+    let address_book = state.address_book.as_deref().unwrap_or_default();
+    let suggestions = contacts::complete(prefix, address_book, &seen, limit);
+    let list: Vec<Value> = suggestions
+        .iter()
+        .map(|s| {
+            json!({
+                "name": s.name,
+                "email": s.email,
+                "display": s.display(),
+                "count": s.count,
+                "contact": s.contact,
+            })
+        })
+        .collect();
+    ok_response(json!({"contacts": list}))
+}
+
 fn cmd_compose_draft(state: &CliState) -> Value {
     let from = state.reply_from_header();
-    let draft = compose::build_compose_draft(&from);
-    ok_response(json!({"draft": draft}))
+    let mut seen = seen_addresses(state);
+    seen.sort_by(|a, b| b.last_seen.cmp(&a.last_seen));
+    let recent: Vec<String> = seen
+        .iter()
+        .take(RECENT_ADDRESSES)
+        .map(|a| {
+            EmailAddress {
+                name: a.name.clone(),
+                email: Some(a.email.clone()),
+            }
+            .to_string()
+        })
+        .collect();
+    let draft = compose::with_recent_addresses(
+        &compose::build_compose_draft(
+            &from,
+            state.identity_choice().for_compose(),
+            &state.signature_options(),
+        ),
+        &recent,
+    );
+    ok_response(json!({"draft": draft, "attach_hint": ATTACH_HINT, "recent": recent}))
 }
 
 fn cmd_reply_draft(state: &mut CliState, input: &Value) -> Value {
@@ -1302,7 +2700,14 @@ fn cmd_reply_draft(state: &mut CliState, input: &Value) -> Value {
         }) => match *boxed_result {
             Ok(email) => {
                 let from = state.reply_from_header();
-                let draft = compose::build_reply_draft(&email, reply_all, &from);
+                let draft = compose::build_reply_draft(
+                    &email,
+                    reply_all,
+                    &from,
+                    state.identity_choice().for_response(Some(&email)),
+                    &state.signature_options(),
+                    &compose::QuoteStyle::from_config(&state.config.ui),
+                );
                 ok_response(json!({"draft": draft}))
             }
             Err(e) => err_response(&e),
@@ -1329,7 +2734,13 @@ fn cmd_forward_draft(state: &mut CliState, input: &Value) -> Value {
         }) => match *boxed_result {
             Ok(email) => {
                 let from = state.reply_from_header();
-                let draft = compose::build_forward_draft(&email, &from);
+                let draft = compose::build_forward_draft(
+                    &email,
+                    &from,
+                    state.identity_choice().for_response(Some(&email)),
+                    &state.signature_options(),
+                    &compose::QuoteStyle::from_config(&state.config.ui),
+                );
                 ok_response(json!({"draft": draft}))
             }
             Err(e) => err_response(&e),
@@ -1339,14 +2750,194 @@ fn cmd_forward_draft(state: &mut CliState, input: &Value) -> Value {
     }
 }
 
-fn cmd_keybindings() -> Value {
+fn cmd_send_email(state: &mut CliState, input: &Value) -> Value {
+    let text = match input.get("draft").and_then(|v| v.as_str()) {
+        Some(t) => t,
+        None => return err_response("missing 'draft' field"),
+    };
+    let mut draft = match compose::parse_draft(text) {
+        Ok(d) => d,
+        Err(e) => return err_response(&e),
+    };
+    draft.format.get_or_insert(state.config.ui.compose_format);
+    draft.flowed = state.config.ui.format_flowed;
+    draft.quote_prefix = state.config.ui.quote_prefix.clone();
+    let mut attachments = match compose::load_attachments(&draft.attachments) {
+        Ok(a) => a,
+        Err(e) => return err_response(&e),
+    };
+    // A resumed draft also carries the attachments saved with it.
+    let draft_id = input.get("draft_id").and_then(|v| v.as_str());
+    if let Some(draft_id) = draft_id {
+        match state.draft_store().and_then(|store| store.load(draft_id)) {
+            Ok((_, saved)) => {
+                attachments.extend(saved.attachments.into_iter().map(|a| OutgoingAttachment {
+                    name: a.filename,
+                    content_type: a.content_type,
+                    data: a.data,
+                }))
+            }
+            Err(e) => return err_response(&e),
+        }
+    }
+
+    if let Err(e) = state.send_cmd(BackendCommand::SendEmail { draft, attachments }) {
+        return err_response(&e);
+    }
+
+    match state.recv_resp() {
+        Ok(BackendResponse::EmailSent { result }) => match result {
+            Ok(id) => {
+                if let Some(draft_id) = draft_id {
+                    if let Err(e) = state.draft_store().and_then(|store| store.remove(draft_id)) {
+                        log_warn!("[CLI] failed to remove sent draft {}: {}", draft_id, e);
+                    }
+                }
+                ok_response(json!({"id": id}))
+            }
+            Err(e) => err_response(&e),
+        },
+        Ok(_) => err_response("unexpected response from backend"),
+        Err(e) => err_response(&e),
+    }
+}
+
+/// Store a draft in `[mail].drafts_folder` with the `$draft` keyword, or with
+/// `local` in the account's saved drafts. Unlike send_email, recipients may
+/// still be missing.
+fn cmd_save_draft(state: &mut CliState, input: &Value) -> Value {
+    let text = match input.get("draft").and_then(|v| v.as_str()) {
+        Some(t) => t,
+        None => return err_response("missing 'draft' field"),
+    };
+    if input
+        .get("local")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+    {
+        return save_local_draft(state, input, text);
+    }
+    let mut draft = match compose::parse_unfinished_draft(text) {
+        Ok(d) => d,
+        Err(e) => return err_response(&e),
+    };
+    draft.format.get_or_insert(state.config.ui.compose_format);
+    draft.flowed = state.config.ui.format_flowed;
+    draft.quote_prefix = state.config.ui.quote_prefix.clone();
+    let attachments = match compose::load_attachments(&draft.attachments) {
+        Ok(a) => a,
+        Err(e) => return err_response(&e),
+    };
+
+    let folder = state.config.mail.drafts_folder.clone();
+    if let Err(e) = state.send_cmd(BackendCommand::SaveDraft {
+        draft,
+        attachments,
+        folder: folder.clone(),
+    }) {
+        return err_response(&e);
+    }
+
+    match state.recv_resp() {
+        Ok(BackendResponse::DraftSaved { result }) => match result {
+            Ok(id) => {
+                // Safe on the server now, so the local copy can go.
+                if let Some(draft_id) = input.get("draft_id").and_then(|v| v.as_str()) {
+                    if let Err(e) = state.draft_store().and_then(|store| store.remove(draft_id)) {
+                        log_warn!("[CLI] failed to remove saved draft {}: {}", draft_id, e);
+                    }
+                }
+                ok_response(json!({"id": id, "folder": folder}))
+            }
+            Err(e) => err_response(&e),
+        },
+        Ok(_) => err_response("unexpected response from backend"),
+        Err(e) => err_response(&e),
+    }
+}
+
+/// Write `text` to the saved drafts: a new draft, or the one named by
+/// `draft_id`.
+fn save_local_draft(state: &CliState, input: &Value, text: &str) -> Value {
+    let store = match state.draft_store() {
+        Ok(store) => store,
+        Err(e) => return err_response(&e),
+    };
+    let saved = match input.get("draft_id").and_then(|v| v.as_str()) {
+        Some(id) => store.update(id, text),
+        None => store.create(&ComposeDraft::text(text.to_string())),
+    };
+    match saved {
+        Ok(saved) => ok_response(json!({"draft_id": saved.id})),
+        Err(e) => err_response(&e),
+    }
+}
+
+fn saved_draft_json(draft: &SavedDraft) -> Value {
+    let attachments: Vec<&str> = draft
+        .attachments
+        .iter()
+        .map(|a| a.filename.as_str())
+        .collect();
+    json!({
+        "id": draft.id,
+        "subject": draft.subject,
+        "to": draft.to,
+        "saved_at": draft.saved_at,
+        "attachments": attachments,
+    })
+}
+
+fn cmd_list_drafts(state: &CliState) -> Value {
+    let store = match state.draft_store() {
+        Ok(store) => store,
+        Err(e) => return err_response(&e),
+    };
+    let drafts: Vec<Value> = store.list().iter().map(saved_draft_json).collect();
+    ok_response(json!({"drafts": drafts}))
+}
+
+fn cmd_resume_draft(state: &CliState, input: &Value) -> Value {
+    let id = match input.get("id").and_then(|v| v.as_str()) {
+        Some(id) => id,
+        None => return err_response("missing 'id' field"),
+    };
+    match state.draft_store().and_then(|store| store.load(id)) {
+        Ok((meta, draft)) => {
+            let mut resp = saved_draft_json(&meta);
+            resp["draft_id"] = json!(meta.id);
+            resp["draft"] = json!(draft.body);
+            ok_response(resp)
+        }
+        Err(e) => err_response(&e),
+    }
+}
+
+fn cmd_delete_draft(state: &CliState, input: &Value) -> Value {
+    let id = match input.get("id").and_then(|v| v.as_str()) {
+        Some(id) => id,
+        None => return err_response("missing 'id' field"),
+    };
+    match state.draft_store().and_then(|store| store.remove(id)) {
+        Ok(true) => ok_response(json!({"id": id})),
+        Ok(false) => err_response(&format!("no saved draft '{}'", id)),
+        Err(e) => err_response(&e),
+    }
+}
+
+fn cmd_keybindings(state: &CliState) -> Value {
     let bindings = keybindings::all_keybindings();
+    let keymap = &state.config.keybindings;
     let list: Vec<Value> = bindings
         .iter()
         .map(|kb| {
+            let key = match keymap.key_for(kb.view, kb.action) {
+                Some(c) => c.to_string(),
+                None => kb.key.to_string(),
+            };
             json!({
                 "view": kb.view,
-                "key": kb.key,
+                "key": key,
                 "action": kb.action,
                 "description": kb.description,
             })
@@ -1543,6 +3134,7 @@ pub fn run_cli(
         config,
         cmd_tx: None,
         resp_rx: None,
+        pool: HashMap::new(),
         connected_account: None,
         connected_username: None,
         cached_mailboxes: Vec::new(),
@@ -1558,18 +3150,34 @@ pub fn run_cli(
         next_plan_id: 0,
         triage_plans: HashMap::new(),
         offline,
+        watch_events: RefCell::new(Vec::new()),
+        address_book: None,
     };
 
-    let stdin = io::stdin();
+    // Read stdin on its own thread so `watch` events are written while
+    // waiting for the next command.
+    let (line_tx, line_rx) = mpsc::channel::<String>();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            if line_tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
     let stdout = io::stdout();
     let mut stdout = stdout.lock();
 
-    for line in stdin.lock().lines() {
-        let line = match line {
-            Ok(l) => l,
-            Err(_) => break,
+    loop {
+        let line = match line_rx.recv_timeout(Duration::from_millis(200)) {
+            Ok(line) => line,
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                write_watch_events(&state, &mut stdout);
+                continue;
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
         };
-
         let trimmed = line.trim();
         if trimmed.is_empty() {
             continue;
@@ -1590,11 +3198,15 @@ pub fn run_cli(
         let _ = serde_json::to_writer(&mut stdout, &response);
         let _ = stdout.write_all(b"\n");
         let _ = stdout.flush();
+        write_watch_events(&state, &mut stdout);
     }
 
-    // Shutdown backend on exit
-    if let Some(ref tx) = state.cmd_tx {
-        let _ = tx.send(BackendCommand::Shutdown);
+    // Shutdown backends on exit
+    if let Some((_, backend)) = state.take_active() {
+        backend.shutdown();
+    }
+    for (_, backend) in state.pool.drain() {
+        backend.shutdown();
     }
 }
 
@@ -1616,11 +3228,25 @@ Connection Flow
 
 2. Connect to an account:
    > {{"command": "connect", "account": "personal"}}
-   < {{"ok": true, "account": "personal", "username": "me@example.com"}}
+   < {{"ok": true, "account": "personal", "username": "me@example.com", "reused": false}}
+   Connecting to another account keeps the previous account's backend running;
+   connecting back to it reuses that backend ("reused": true) without running
+   password_command or session discovery again. "fresh": true shuts down a
+   pooled backend and connects anew (e.g. after changing the password).
+   > {{"command": "connect", "account": "personal", "fresh": true}}
 
 3. Check status:
    > {{"command": "status"}}
-   < {{"ok": true, "connected": true, "account": "personal", "username": "me@example.com", "cached_mailboxes": 0}}
+   < {{"ok": true, "connected": true, "account": "personal", "username": "me@example.com", "cached_mailboxes": 0, "pooled": ["work"]}}
+   pooled lists the other accounts with a backend still running.
+
+   Shut down an account's backend (default: the active account):
+   > {{"command": "disconnect", "account": "work"}}
+   < {{"ok": true, "account": "work"}}
+
+4. List sender identities configured for the connected account:
+   > {{"command": "list_identities"}}
+   < {{"ok": true, "identities": [{{"name": "Me", "email": "me@example.com", "from": "Me <me@example.com>", "signature": null}}]}}
 
 Mailbox Commands
 ----------------
@@ -1628,6 +3254,11 @@ list_mailboxes: Fetch and cache all mailboxes.
    > {{"command": "list_mailboxes"}}
    < {{"ok": true, "mailboxes": [{{"id": "...", "name": "INBOX", "role": "inbox", "total_emails": 42, "unread_emails": 3, ...}}]}}
 
+unread_counts: Unread and total message counts for every mailbox.
+   > {{"command": "unread_counts", "only_unread": true}}
+   Optional: only_unread (bool, default false) — skip mailboxes with no unread mail
+   < {{"ok": true, "mailboxes": [{{"mailbox_id": "...", "name": "INBOX", "unread": 3, "total": 42}}]}}
+
 create_mailbox: Create a new mailbox.
    > {{"command": "create_mailbox", "name": "NewFolder"}}
    < {{"ok": true, "name": "NewFolder"}}
@@ -1640,17 +3271,46 @@ mark_mailbox_read: Mark all emails in a mailbox as read.
    > {{"command": "mark_mailbox_read", "mailbox_id": "mbox-id"}}
    < {{"ok": true, "mailbox_name": "INBOX", "updated": 15}}
 
+empty_mailbox: Permanently destroy every email in the Trash or Junk mailbox.
+   > {{"command": "empty_mailbox", "mailbox_id": "mbox-id"}}
+   < {{"ok": true, "mailbox_name": "Trash", "destroyed": 42}}
+   destroyed counts what the server confirmed; offline (or on a server error) the rest is queued for replay.
+
+get_quota: Report storage quotas (urn:ietf:params:jmap:quota); null if unsupported.
+   > {{"command": "get_quota"}}
+   < {{"ok": true, "quota": [{{"id": "...", "name": "Mail", "resource_type": "octets", "scope": "account", "used": 1048576, "limit": 10737418240}}]}}
+
 Email Query Commands
 --------------------
 query_emails: Query emails in a mailbox.
    > {{"command": "query_emails", "mailbox_id": "mbox-id", "limit": 50, "position": 0, "search": null}}
    Optional: headers_only (bool), max_body_chars (int), received_after (RFC3339/date), received_before (RFC3339/date)
-   < {{"ok": true, "emails": [...], "total": 100, "position": 0, "loaded": 50, "thread_counts": {{...}}}}
+   Optional: sync (bool) - on the first unfiltered page, fetch only the emails
+   changed (Email/changes) since the previous sync query of this mailbox
+   < {{"ok": true, "emails": [...], "total": 100, "position": 0, "loaded": 50, "thread_counts": {{...}}, "next_cursor": "eyJt..."}}
+   next_cursor is null on the last page. Pass it back instead of position to
+   fetch the next page with the same mailbox and filters:
+   > {{"command": "query_emails", "cursor": "eyJt...", "limit": 50}}
+   A cursor is rejected alongside position, or with a mailbox_id or filter
+   that differs from the one it was issued for.
+
+search_all: Search emails across every mailbox. Each result carries its mailbox_ids.
+   > {{"command": "search_all", "search": "invoice", "limit": 50, "position": 0}}
+   Optional: headers_only (bool), max_body_chars (int), received_after (RFC3339/date), received_before (RFC3339/date)
+   < {{"ok": true, "emails": [...], "total": 12, "position": 0, "loaded": 12}}
 
 get_email: Fetch a single email with full body.
    > {{"command": "get_email", "id": "email-id"}}
    Optional: headers_only (bool, default false), max_body_chars (int, 0=unlimited)
    < {{"ok": true, "id": "...", "subject": "...", "body": "...", "body_truncated": false, ...}}
+   Every email object carries received_at/sent_at as sent by the server plus
+   received_epoch (unix seconds) and received_date (UTC "YYYY-MM-DD").
+
+get_emails: Fetch several emails with full bodies in one call (cache misses are
+   fetched from the server in batches of 50).
+   > {{"command": "get_emails", "ids": ["id1", "id2"]}}
+   Optional: headers_only (bool, default false), max_body_chars (int, 0=unlimited)
+   < {{"ok": true, "emails": [...], "missing": ["ids not found"]}}
 
 get_thread: Fetch all emails in a thread.
    > {{"command": "get_thread", "thread_id": "thread-id"}}
@@ -1661,9 +3321,15 @@ get_raw_headers: Get raw RFC headers for an email.
    > {{"command": "get_raw_headers", "id": "email-id"}}
    < {{"ok": true, "headers": "From: ...\nTo: ...\n..."}}
 
+get_raw: Get the complete raw RFC822 message, headers and body.
+   > {{"command": "get_raw", "id": "email-id"}}
+   < {{"ok": true, "id": "...", "bytes": 1234, "encoding": "utf-8", "raw": "From: ...\r\n\r\nbody..."}}
+   encoding is "utf-8" when the source is valid UTF-8, otherwise "base64"
+   (raw is then the base64 of the exact source). bytes counts the octets.
+
 Context Control
 ---------------
-Both get_email, get_thread, and query_emails accept:
+get_email, get_emails, get_thread, query_emails and search_all accept:
   - "headers_only": true — omit body/preview, return only metadata
   - "max_body_chars": 500 — truncate body text; response includes "body_truncated": true if truncated
 
@@ -1673,13 +3339,24 @@ mark_read:    {{"command": "mark_read", "id": "email-id"}}
 mark_unread:  {{"command": "mark_unread", "id": "email-id"}}
 flag:         {{"command": "flag", "id": "email-id"}}
 unflag:       {{"command": "unflag", "id": "email-id"}}
+set_keyword:  {{"command": "set_keyword", "id": "email-id", "keyword": "$label1", "value": true}}  (false clears it)
 move_email:   {{"command": "move_email", "id": "email-id", "to_mailbox_id": "mbox-id"}}
 bulk_move:    {{"command": "bulk_move", "ids": ["id1", "id2"], "to_mailbox_id": "mbox-id"}}
 archive:      {{"command": "archive", "id": "email-id"}}  (uses configured archive folder)
 bulk_archive: {{"command": "bulk_archive", "ids": ["id1", "id2"]}}
+snooze:       {{"command": "snooze", "id": "email-id", "until": "3d"}}  (m/h/d/w or Unix time; returns "wake_at")
+mark_spam:    {{"command": "mark_spam", "id": "email-id"}}  (sets $junk, clears $notjunk, moves to [mail].spam_folder)
+mark_not_spam: {{"command": "mark_not_spam", "id": "email-id"}}  (sets $notjunk, clears $junk, moves to the inbox)
 delete_email: {{"command": "delete_email", "id": "email-id"}}  (uses configured deleted folder)
 bulk_delete_email: {{"command": "bulk_delete_email", "ids": ["id1", "id2"]}}
+move_thread:  {{"command": "move_thread", "thread_id": "thread-id", "to_mailbox_id": "mbox-id"}}  (every message in the thread)
+archive_thread: {{"command": "archive_thread", "thread_id": "thread-id"}}  (uses configured archive folder)
+delete_thread: {{"command": "delete_thread", "thread_id": "thread-id"}}  (uses configured deleted folder)
+mute_thread:  {{"command": "mute_thread", "thread_id": "thread-id"}}  (INBOX mail arriving in the thread afterwards is archived, or marked read without an Archive mailbox; works offline)
+unmute_thread: {{"command": "unmute_thread", "thread_id": "thread-id"}}  (both return "thread_id" and "muted")
 destroy:      {{"command": "destroy", "ids": ["id1", "id2"]}}  (permanently delete)
+undo:         {{"command": "undo"}}  (reverse the most recent move/flag/read change; returns "undone")
+              Permanent deletes and mark-all-read are reported as not undoable.
 
 Triage Automation
 -----------------
@@ -1691,6 +3368,39 @@ apply_triage_plan: Apply a saved plan safely, or explicit approved IDs.
    > {{"command": "apply_triage_plan", "plan_id": "plan-1"}}
    > {{"command": "apply_triage_plan", "archive_ids": ["id1"], "trash_ids": ["id2"]}}
 
+dry_run_rules: Preview what rules.toml would do to every message in a mailbox (no changes).
+   > {{"command": "dry_run_rules", "mailbox_id": "mbox-id"}}
+   < {{"ok": true, "mailbox_id": "...", "scanned": 120, "matched_rules": 3, "actions": 4, "entries": [{{"id": "..", "rule": "..", "actions": ["move_to=Archive"]}}]}}
+
+run_rules: Apply rules.toml to every message in a mailbox.
+   > {{"command": "run_rules", "mailbox_id": "mbox-id"}}
+   < {{"ok": true, "mailbox_id": "...", "scanned": 120, "matched_rules": 3, "actions": 4}}
+
+Progress: triage_suggest, dry_run_rules and run_rules accept "emit_progress": true.
+   Progress lines then precede the single final response:
+   < {{"progress": {{"scanned": 50, "total": 120}}}}
+   < {{"progress": {{"scanned": 100, "total": 120}}}}
+   < {{"ok": true, ...}}
+   triage_suggest reads the mailbox 100 messages at a time to report them.
+
+test_rules: Explain how rules.toml evaluates one message (no changes). "matched" lists what run_rules
+would apply; "rules" shows every rule with each condition's result (nested conditions at depth + 1).
+   > {{"command": "test_rules", "id": "email-id"}}
+   < {{"ok": true, "id": "..", "subject": "..", "from": "..", "matched": [{{"rule": "..", "actions": ["flag"]}}],
+      "rules": [{{"rule": "..", "matched": true, "skipped_to_me": false, "conditions": [{{"depth": 0, "condition": "Subject =~ /x/", "passed": true}}]}}]}}
+
+retention_preview: List mail the retention policies would expire (no changes). Uses the
+[retention.*] config unless "policies" is given.
+   > {{"command": "retention_preview"}}
+   > {{"command": "retention_preview", "policies": [{{"name": "trash", "folder": "Trash", "days": 30, "only_read": true, "only_unflagged": true}}]}}
+   < {{"ok": true, "count": 1, "candidates": [{{"id": "..", "mailbox": "Trash", "policy": "trash", "received_at": "2025-01-01", "from": "..", "subject": ".."}}]}}
+
+"keep_newest": N replaces "days" to expire everything but the newest N messages in the folder.
+
+retention_execute: Permanently destroy the mail retention_preview lists (same "policies" option).
+   > {{"command": "retention_execute"}}
+   < {{"ok": true, "deleted": 12, "failed_batches": []}}
+
 train: Train the built-in spam classifier on a message (spam: true/false or "spam"/"ham").
    > {{"command": "train", "id": "email-id", "spam": true}}
    < {{"ok": true, "id": "email-id", "trained_as": "spam"}}
@@ -1711,22 +3421,104 @@ Attachment Commands
 -------------------
 download_attachment: Download an attachment blob.
    > {{"command": "download_attachment", "blob_id": "blob-id", "name": "file.pdf", "content_type": "application/pdf"}}
+   Optional: dir (overrides [ui].download_dir; ~ and $VARS are expanded)
    < {{"ok": true, "name": "file.pdf", "path": "/tmp/tmc-attachments/file.pdf"}}
 
+download_all_attachments: Save every attachment of an email (duplicate names get a -2 suffix).
+   > {{"command": "download_all_attachments", "id": "email-id"}}
+   Optional: dir (as above), include_inline (also save unnamed inline images; default false)
+   < {{"ok": true, "id": "email-id", "paths": ["/tmp/tmc-attachments/a.pdf", "/tmp/tmc-attachments/b.png"], "failed": []}}
+
+export_eml: Save the full raw RFC822 message (default: download dir, named {{id}}.eml).
+   > {{"command": "export_eml", "id": "email-id", "path": "/tmp/message.eml"}}
+   < {{"ok": true, "id": "email-id", "path": "/tmp/message.eml", "bytes": 2048}}
+
 Compose Commands
 ----------------
-compose_draft: Generate a blank compose template.
+compose_draft: Generate a blank compose template. "recent" lists addresses
+   from the most recently cached mail (empty when not connected); the draft
+   carries them on a "Recent:" line, which is ignored when sending.
    > {{"command": "compose_draft"}}
-   < {{"ok": true, "draft": "From: me@example.com\nTo: \nSubject: \n\n"}}
+   < {{"ok": true, "draft": "From: me@example.com\nTo: \nSubject: \n\n", "attach_hint": "...", "recent": ["Bob <bob@example.com>"]}}
+
+complete_address: Suggest addresses for a To:/Cc: line. Matches the start of
+   the address, name or any name word (case-insensitive) across the [contacts]
+   address book and addresses seen in cached mail, most frequent first.
+   Optional "limit" (default 20). The [contacts] command runs once per
+   session.
+   > {{"command": "complete_address", "prefix": "ali"}}
+   < {{"ok": true, "contacts": [{{"name": "Alice", "email": "alice@example.com", "display": "Alice <alice@example.com>", "count": 3, "contact": true}}]}}
 
 reply_draft: Generate a reply draft.
    > {{"command": "reply_draft", "id": "email-id", "reply_all": false}}
-   < {{"ok": true, "draft": "From: ...\nTo: ...\nSubject: Re: ...\n\n> ..."}}
+   < {{"ok": true, "draft": "From: ...\nTo: ...\nSubject: Re: ...\n\n> ...", "draft_id": "..."}}
 
 forward_draft: Generate a forward draft.
    > {{"command": "forward_draft", "id": "email-id"}}
    < {{"ok": true, "draft": "From: ...\nTo: \nSubject: Fwd: ...\n\n---------- Forwarded message ----------\n..."}}
 
+send_email: Send an edited draft (headers, "--text follows this line--", body).
+   Requires the server's urn:ietf:params:jmap:submission capability.
+   > {{"command": "send_email", "draft": "From: me@example.com\nTo: you@example.com\nSubject: Hi\n--text follows this line--\nHello"}}
+   Attach files with "Attach:" header lines naming one path each, or several as
+   a TOML array, e.g. "Attach: [\"~/report.pdf\", \"/tmp/photo.jpg\"]";
+   unreadable paths fail before sending.
+   "Content-Type: text/markdown" (or [ui].compose_format = "markdown") sends the
+   body as multipart/alternative with an HTML part rendered from the Markdown.
+   With [ui].format_flowed, the plain-text part of every message is sent as
+   format=flowed.
+   Optional "draft_id" names the saved draft being sent: its saved attachments
+   are included and the draft is removed once the send succeeds.
+   < {{"ok": true, "id": "email-id"}}
+
+save_draft: Store an unfinished draft in the [mail].drafts_folder mailbox
+   (default "Drafts") with the $draft keyword instead of sending it. Takes
+   the same draft text as send_email, but recipients may be empty. Optional
+   "draft_id" removes that locally saved draft once the server has it.
+   > {{"command": "save_draft", "draft": "From: me@example.com\nTo: \nSubject: Later\n--text follows this line--\nNot done yet"}}
+   < {{"ok": true, "id": "email-id", "folder": "Drafts"}}
+   With "local": true the draft is kept in the account's saved drafts under
+   the cache dir instead (replacing "draft_id" when given); see list_drafts.
+   > {{"command": "save_draft", "draft": "From: ...", "local": true}}
+   < {{"ok": true, "draft_id": "1760000000000-4242-0"}}
+
+list_drafts: The connected account's saved drafts (from the TUI, or
+   save_draft with "local"), most recently saved first. "saved_at" is
+   seconds since the Unix epoch.
+   > {{"command": "list_drafts"}}
+   < {{"ok": true, "drafts": [{{"id": "...", "subject": "Lunch", "to": "bob@example.com", "saved_at": 1760000000, "attachments": []}}]}}
+
+resume_draft: Fetch a saved draft's text to continue editing it; pass the
+   same "draft_id" to send_email to send it.
+   > {{"command": "resume_draft", "id": "draft-id"}}
+   < {{"ok": true, "id": "...", "draft_id": "...", "draft": "From: ...", "subject": "Lunch", "to": "bob@example.com", "saved_at": 1760000000, "attachments": []}}
+
+delete_draft: Discard a saved draft without sending it.
+   > {{"command": "delete_draft", "id": "draft-id"}}
+   < {{"ok": true, "id": "draft-id"}}
+
+Vacation Responder
+------------------
+Requires the server's urn:ietf:params:jmap:vacationresponse capability.
+get_vacation: Show the current auto-reply settings.
+   > {{"command": "get_vacation"}}
+   < {{"ok": true, "enabled": false, "subject": null, "text_body": null, "from_date": null, "to_date": null}}
+
+set_vacation: Replace the auto-reply settings; omitted fields are cleared.
+   > {{"command": "set_vacation", "enabled": true, "subject": "Away", "text_body": "Back Monday."}}
+   Optional: from_date, to_date (UTC, e.g. "2025-07-01T00:00:00Z")
+   < {{"ok": true, "enabled": true, "subject": "Away", ...}}
+
+Watch Mode
+----------
+watch: Stream mailbox changes as event lines between command responses until
+   stdin is closed. Uses the server's JMAP EventSource push channel when available
+   (push: true), otherwise polls every interval_secs (default:
+   [ui].sync_interval_secs, or 60). Other commands keep working while watching.
+   > {{"command": "watch", "interval_secs": 30}}
+   < {{"ok": true, "watching": true, "interval_secs": 30, "push": false}}
+   < {{"event": "mailbox_changed", "mailbox_id": "mbox-id", "name": "INBOX", "total_emails": 12, "unread_emails": 3}}
+
 Keybindings
 -----------
 keybindings: Export the TUI keybinding dictionary.
@@ -1,8 +1,15 @@
+use crate::config::{
+    ComposeFormat, IdentityConfig, UiConfig, DEFAULT_FORWARD_ATTRIBUTION, DEFAULT_QUOTE_PREFIX,
+    DEFAULT_REPLY_ATTRIBUTION,
+};
+use crate::jmap::types::{EmailAddress, OutgoingAttachment, OutgoingEmail};
+use std::cell::RefCell;
 use std::fs;
 use std::io;
 use std::os::unix::fs::OpenOptionsExt;
 use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 /// A draft ready to hand to `$EDITOR`: the editor text plus any files that
@@ -38,16 +45,223 @@ pub struct DraftAttachment {
     pub data: Vec<u8>,
 }
 
-/// Build a blank compose draft template.
-pub fn build_compose_draft(from: &str) -> String {
-    format!(
+/// Pick the identity to send as when responding to `email`: the one whose
+/// address received the original (To/Cc), else the first configured identity.
+fn select_identity<'a>(
+    identities: &'a [IdentityConfig],
+    email: Option<&crate::jmap::types::Email>,
+) -> Option<&'a IdentityConfig> {
+    let recipients = email
+        .into_iter()
+        .flat_map(|e| e.to.iter().chain(e.cc.iter()).flatten())
+        .filter_map(|addr| addr.email.as_deref());
+    for addr in recipients {
+        if let Some(identity) = identities
+            .iter()
+            .find(|i| i.email.eq_ignore_ascii_case(addr))
+        {
+            return Some(identity);
+        }
+    }
+    identities.first()
+}
+
+/// An account's sender identities and the one picked with `i`. Without a
+/// pick, a configured `[mail].reply_from` stays the From address; otherwise
+/// the identity is chosen automatically.
+#[derive(Debug, Clone, Default)]
+pub struct IdentityChoice {
+    identities: Vec<IdentityConfig>,
+    chosen: Option<usize>,
+    reply_from_set: bool,
+}
+
+/// One `IdentityChoice` per account, shared by all of its views so a pick
+/// survives opening another view.
+pub type SharedIdentities = Rc<RefCell<IdentityChoice>>;
+
+impl IdentityChoice {
+    pub fn new(identities: Vec<IdentityConfig>, reply_from_set: bool) -> Self {
+        IdentityChoice {
+            identities,
+            chosen: None,
+            reply_from_set,
+        }
+    }
+
+    pub fn shared(identities: Vec<IdentityConfig>, reply_from_set: bool) -> SharedIdentities {
+        Rc::new(RefCell::new(Self::new(identities, reply_from_set)))
+    }
+
+    /// Pick the next identity for new drafts, returning a status line
+    /// describing the identity now in effect.
+    pub fn cycle(&mut self) -> String {
+        if self.identities.is_empty() {
+            return "No identities configured".to_string();
+        }
+        let next = match self.chosen {
+            Some(idx) => (idx + 1) % self.identities.len(),
+            // The automatic pick for new mail is the first identity, so
+            // the first press moves past it unless reply_from was in effect.
+            None if self.reply_from_set => 0,
+            None => 1 % self.identities.len(),
+        };
+        self.chosen = Some(next);
+        format!("Composing as {}", self.identities[next].display_address())
+    }
+
+    fn picked(&self) -> Option<&IdentityConfig> {
+        self.chosen.and_then(|idx| self.identities.get(idx))
+    }
+
+    /// The identity for a new message.
+    pub fn for_compose(&self) -> Option<&IdentityConfig> {
+        match self.picked() {
+            Some(identity) => Some(identity),
+            None if self.reply_from_set => None,
+            None => self.identities.first(),
+        }
+    }
+
+    /// The identity for replying to or forwarding `email`: the picked one,
+    /// else the one the original was addressed to (see `select_identity`).
+    pub fn for_response(
+        &self,
+        email: Option<&crate::jmap::types::Email>,
+    ) -> Option<&IdentityConfig> {
+        match self.picked() {
+            Some(identity) => Some(identity),
+            None if self.reply_from_set => None,
+            None => select_identity(&self.identities, email),
+        }
+    }
+}
+
+/// Resolve the From header: the chosen identity, else the `from` fallback.
+fn identity_from(identity: Option<&IdentityConfig>, from: &str) -> String {
+    identity
+        .map(|i| i.display_address())
+        .unwrap_or_else(|| from.to_string())
+}
+
+/// Account-wide signature settings applied on top of the chosen identity.
+#[derive(Debug, Clone, Default)]
+pub struct SignatureOptions {
+    /// Used when the sending identity has no signature of its own.
+    pub account_signature: Option<String>,
+    /// Place the signature above the quoted text in replies.
+    pub above_quote: bool,
+}
+
+/// Quoting style from `[ui].quote_prefix`, `[ui].reply_attribution` and
+/// `[ui].forward_attribution`. Attributions take `{date}`, `{from}` and
+/// `{subject}` from the original message; an empty one is left out.
+#[derive(Debug, Clone)]
+pub struct QuoteStyle {
+    pub prefix: String,
+    pub reply_attribution: String,
+    pub forward_attribution: String,
+}
+
+impl QuoteStyle {
+    pub fn from_config(ui: &UiConfig) -> Self {
+        QuoteStyle {
+            prefix: ui.quote_prefix.clone(),
+            reply_attribution: ui.reply_attribution.clone(),
+            forward_attribution: ui.forward_attribution.clone(),
+        }
+    }
+}
+
+impl Default for QuoteStyle {
+    fn default() -> Self {
+        QuoteStyle {
+            prefix: DEFAULT_QUOTE_PREFIX.to_string(),
+            reply_attribution: DEFAULT_REPLY_ATTRIBUTION.to_string(),
+            forward_attribution: DEFAULT_FORWARD_ATTRIBUTION.to_string(),
+        }
+    }
+}
+
+/// Fill an attribution template; the result ends with a newline unless the
+/// template is empty.
+fn attribution_line(template: &str, date: &str, from: &str, subject: &str) -> String {
+    if template.is_empty() {
+        return String::new();
+    }
+    let line = template
+        .replace("{date}", date)
+        .replace("{subject}", subject)
+        .replace("{from}", from);
+    format!("{}\n", line)
+}
+
+impl SignatureOptions {
+    fn resolve<'a>(&'a self, identity: Option<&'a IdentityConfig>) -> Option<&'a str> {
+        identity
+            .and_then(|i| i.signature.as_deref())
+            .or(self.account_signature.as_deref())
+            .filter(|s| !s.trim().is_empty())
+    }
+}
+
+/// Render a signature with the conventional `-- ` delimiter line.
+fn signature_block(signature: &str) -> String {
+    format!("\n-- \n{}\n", signature.trim_end_matches('\n'))
+}
+
+/// Drop a trailing `-- ` signature block so replies don't quote it again.
+pub(crate) fn strip_signature(text: &str) -> &str {
+    let end = if text.starts_with("-- \n") || text.starts_with("-- \r\n") {
+        0
+    } else {
+        text.rfind("\n-- \n")
+            .or_else(|| text.rfind("\n-- \r\n"))
+            .unwrap_or(text.len())
+    };
+    text[..end].trim_end()
+}
+
+/// Build a blank compose draft template, sent as `identity` when given.
+pub fn build_compose_draft(
+    from: &str,
+    identity: Option<&IdentityConfig>,
+    signature: &SignatureOptions,
+) -> String {
+    let mut draft = format!(
         "From: {}\nTo: \nCc: \nSubject: \n--text follows this line--\n\n",
-        from
-    )
+        identity_from(identity, from)
+    );
+    if let Some(sig) = signature.resolve(identity) {
+        draft.push_str(&signature_block(sig));
+    }
+    draft
+}
+
+/// Add a `Recent:` line listing `recent` addresses to a draft's headers, for
+/// copying into To:/Cc:. Like any unknown header it is dropped on sending.
+pub fn with_recent_addresses(draft: &str, recent: &[String]) -> String {
+    if recent.is_empty() {
+        return draft.to_string();
+    }
+    let line = format!("Recent: {}\n", recent.join(", "));
+    match draft.find(BODY_SEPARATOR) {
+        Some(at) => format!("{}{}{}", &draft[..at], line, &draft[at..]),
+        None => format!("{}{}", line, draft),
+    }
 }
 
-/// Build a reply draft from an existing email.
-pub fn build_reply_draft(email: &crate::jmap::types::Email, reply_all: bool, from: &str) -> String {
+/// Build a reply draft from an existing email, sent as `identity` when given.
+pub fn build_reply_draft(
+    email: &crate::jmap::types::Email,
+    reply_all: bool,
+    from: &str,
+    identity: Option<&IdentityConfig>,
+    signature: &SignatureOptions,
+    quote: &QuoteStyle,
+) -> String {
+    let from = &identity_from(identity, from);
+
     // Determine To: address
     let to = if let Some(ref reply_to) = email.reply_to {
         format_address_list(reply_to)
@@ -134,8 +348,9 @@ pub fn build_reply_draft(email: &crate::jmap::types::Email, reply_all: bool, fro
         }
     };
 
-    // Quoted body
-    let body_text = extract_body_text(email);
+    // Quoted body, minus the original's signature
+    let body_text = crate::body::plain_text(email);
+    let body_text = strip_signature(&body_text);
     let sender_display = email
         .from
         .as_ref()
@@ -150,7 +365,7 @@ pub fn build_reply_draft(email: &crate::jmap::types::Email, reply_all: bool, fro
 
     let quoted: String = body_text
         .lines()
-        .map(|line| format!("> {}", line))
+        .map(|line| format!("{}{}", quote.prefix, line))
         .collect::<Vec<_>>()
         .join("\n");
 
@@ -166,16 +381,54 @@ pub fn build_reply_draft(email: &crate::jmap::types::Email, reply_all: bool, fro
         draft.push_str(&format!("References: {}\n", refs));
     }
     draft.push_str("--text follows this line--\n");
-    draft.push_str(&format!(
-        "\nOn {}, {} wrote:\n{}\n",
-        date, sender_display, quoted
-    ));
+    let attribution = format!(
+        "\n{}{}\n",
+        attribution_line(
+            &quote.reply_attribution,
+            date,
+            &sender_display,
+            email.subject.as_deref().unwrap_or("(no subject)"),
+        ),
+        quoted
+    );
+    match signature.resolve(identity) {
+        Some(sig) if signature.above_quote => {
+            draft.push('\n');
+            draft.push_str(&signature_block(sig));
+            draft.push_str(&attribution);
+        }
+        Some(sig) => {
+            draft.push_str(&attribution);
+            draft.push_str(&signature_block(sig));
+        }
+        None => draft.push_str(&attribution),
+    }
 
     draft
 }
 
-/// Build a forward draft from an existing email.
-pub fn build_forward_draft(email: &crate::jmap::types::Email, from: &str) -> String {
+/// Build a forward draft from an existing email, sent as `identity` when given.
+pub fn build_forward_draft(
+    email: &crate::jmap::types::Email,
+    from: &str,
+    identity: Option<&IdentityConfig>,
+    signature: &SignatureOptions,
+    quote: &QuoteStyle,
+) -> String {
+    forward_draft_to(email, from, "", identity, signature, quote)
+}
+
+/// Inline forward draft addressed to `to` (empty leaves `To:` for the user).
+fn forward_draft_to(
+    email: &crate::jmap::types::Email,
+    from: &str,
+    to: &str,
+    identity: Option<&IdentityConfig>,
+    signature: &SignatureOptions,
+    quote: &QuoteStyle,
+) -> String {
+    let from = &identity_from(identity, from);
+
     // Subject with Fwd: prefix
     let subject = match email.subject.as_deref() {
         Some(s) if s.starts_with("Fwd: ") || s.starts_with("fwd: ") => s.to_string(),
@@ -206,12 +459,18 @@ pub fn build_forward_draft(email: &crate::jmap::types::Email, from: &str) -> Str
         .unwrap_or("(unknown date)");
     let orig_subject = email.subject.as_deref().unwrap_or("(no subject)");
 
-    let body_text = extract_body_text(email);
+    let body_text = crate::body::plain_text(email);
 
-    let mut draft = format!("From: {}\nTo: \nSubject: {}\n", from, subject);
+    let mut draft = format!("From: {}\nTo: {}\nSubject: {}\n", from, to, subject);
 
     draft.push_str("--text follows this line--\n");
-    draft.push_str("\n---------- Forwarded message ----------\n");
+    draft.push('\n');
+    draft.push_str(&attribution_line(
+        &quote.forward_attribution,
+        date,
+        &orig_from,
+        orig_subject,
+    ));
     draft.push_str(&format!("From: {}\n", orig_from));
     draft.push_str(&format!("Date: {}\n", date));
     draft.push_str(&format!("Subject: {}\n", orig_subject));
@@ -222,10 +481,42 @@ pub fn build_forward_draft(email: &crate::jmap::types::Email, from: &str) -> Str
     draft.push('\n');
     draft.push_str(&body_text);
     draft.push('\n');
+    if let Some(sig) = signature.resolve(identity) {
+        draft.push_str(&signature_block(sig));
+    }
 
     draft
 }
 
+/// Inline forward of `email` to `to` for a `forward_to` rule, stamped with
+/// [`crate::rules::FORWARDED_HEADER`] (carrying the original Message-ID) so
+/// the forwarded copy is never forwarded again.
+pub fn build_rule_forward(
+    email: &crate::jmap::types::Email,
+    from: &str,
+    to: &str,
+) -> Result<ParsedDraft, String> {
+    let text = forward_draft_to(
+        email,
+        from,
+        to,
+        None,
+        &SignatureOptions::default(),
+        &QuoteStyle::default(),
+    );
+    let mut draft = parse_draft(&text)?;
+    let marker = email
+        .message_id
+        .as_ref()
+        .and_then(|ids| ids.first())
+        .map(|id| format!("<{}>", id))
+        .unwrap_or_else(|| email.id.clone());
+    draft
+        .extra_headers
+        .push((crate::rules::FORWARDED_HEADER.to_string(), marker));
+    Ok(draft)
+}
+
 /// Build a forward draft that carries the original message as a
 /// `message/rfc822` attachment (preserving the HTML part and everything else).
 ///
@@ -236,7 +527,11 @@ pub fn build_forward_attachment_draft(
     email: Option<&crate::jmap::types::Email>,
     raw: Vec<u8>,
     from: &str,
+    identity: Option<&IdentityConfig>,
+    signature: &SignatureOptions,
 ) -> ComposeDraft {
+    let from = &identity_from(identity, from);
+
     let orig_subject = email
         .and_then(|e| e.subject.as_deref())
         .unwrap_or("(no subject)");
@@ -250,6 +545,9 @@ pub fn build_forward_attachment_draft(
     let mut body = format!("From: {}\nTo: \nSubject: {}\n", from, subject);
     body.push_str("--text follows this line--\n");
     body.push_str("\n(forwarded message attached)\n");
+    if let Some(sig) = signature.resolve(identity) {
+        body.push_str(&signature_block(sig));
+    }
 
     let attachment = DraftAttachment {
         filename: forward_attachment_filename(orig_subject),
@@ -319,57 +617,516 @@ fn extract_email_addr(from_header: &str) -> Option<String> {
     }
 }
 
-pub(crate) fn extract_body_text(email: &crate::jmap::types::Email) -> String {
-    // Prefer textBody (plain text) — it preserves the author's formatting.
-    if let Some(ref text_body) = email.text_body {
-        for part in text_body {
-            if let Some(value) = email.body_values.get(&part.part_id) {
-                if looks_like_html(&value.value)
-                    || part
-                        .r#type
-                        .as_deref()
-                        .map(|t| t.eq_ignore_ascii_case("text/html"))
-                        .unwrap_or(false)
-                {
-                    return html_to_plain(&value.value);
-                }
-                return value.value.clone();
+/// Marker line separating the editable headers from the body in a draft.
+pub(crate) const BODY_SEPARATOR: &str = "--text follows this line--";
+
+/// Headers and body recovered from an edited draft.
+#[derive(Debug, Default)]
+pub struct ParsedDraft {
+    pub from: String,
+    pub to: String,
+    pub cc: String,
+    pub subject: String,
+    pub in_reply_to: Option<String>,
+    pub references: Option<String>,
+    /// Files named by `Attach:` pseudo-headers, in order.
+    pub attachments: Vec<PathBuf>,
+    /// Body format from a `Content-Type:` pseudo-header; `None` leaves it to
+    /// `[ui].compose_format`.
+    pub format: Option<ComposeFormat>,
+    /// Encode a plain-text body as `format=flowed` (`[ui].format_flowed`).
+    pub flowed: bool,
+    /// `[ui].quote_prefix` the body's quotes were written with, turned into
+    /// `>` markers when flowed; `>` quotes are always recognized.
+    pub quote_prefix: String,
+    /// Extra raw headers (name, value) written before `MIME-Version`. Not
+    /// read from drafts; set by callers such as rule forwarding.
+    pub extra_headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl ParsedDraft {
+    /// The text/plain body as sent: `format=flowed` encoded when `flowed`.
+    pub fn text_body(&self) -> String {
+        if self.flowed {
+            encode_flowed(&self.body, &self.quote_prefix)
+        } else {
+            self.body.clone()
+        }
+    }
+
+    /// Rendered HTML alternative for Markdown drafts.
+    pub fn html_body(&self) -> Option<String> {
+        (self.format == Some(ComposeFormat::Markdown)).then(|| crate::markdown::to_html(&self.body))
+    }
+}
+
+/// Parse an edited draft back into headers and body. Headers end at the
+/// `--text follows this line--` marker (or the first blank line). Each
+/// `Attach:` line names one file, or several as a TOML array of strings
+/// (`Attach: ["a.pdf", "b, c.png"]`); the line may repeat.
+/// `Content-Type: text/markdown` (or `text/plain`) picks the body format.
+pub fn parse_draft(text: &str) -> Result<ParsedDraft, String> {
+    let draft = parse_unfinished_draft(text)?;
+    if draft.to.is_empty() && draft.cc.is_empty() {
+        return Err("draft has no recipients (To/Cc)".to_string());
+    }
+    Ok(draft)
+}
+
+/// Like [`parse_draft`] but allows a draft with no recipients yet, for
+/// saving to the Drafts mailbox.
+pub fn parse_unfinished_draft(text: &str) -> Result<ParsedDraft, String> {
+    let mut draft = ParsedDraft::default();
+    let mut lines = text.lines();
+    for line in lines.by_ref() {
+        if line.trim() == BODY_SEPARATOR || line.trim().is_empty() {
+            break;
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            return Err(format!("malformed header line: {}", line));
+        };
+        let value = value.trim().to_string();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "from" => draft.from = value,
+            "to" => draft.to = value,
+            "cc" => draft.cc = value,
+            "subject" => draft.subject = value,
+            "in-reply-to" if !value.is_empty() => draft.in_reply_to = Some(value),
+            "references" if !value.is_empty() => draft.references = Some(value),
+            "attach" => draft.attachments.extend(parse_attach_value(&value)?),
+            "content-type" => {
+                let mime = value.split(';').next().unwrap_or("").trim();
+                draft.format = match mime.to_ascii_lowercase().as_str() {
+                    "text/plain" => Some(ComposeFormat::Plain),
+                    "text/markdown" => Some(ComposeFormat::Markdown),
+                    _ => {
+                        return Err(format!(
+                            "unsupported Content-Type '{}': expected text/plain or text/markdown",
+                            mime
+                        ))
+                    }
+                };
+            }
+            _ => {}
+        }
+    }
+    draft.body = lines
+        .skip_while(|l| l.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    if draft.from.is_empty() {
+        return Err("draft has no From header".to_string());
+    }
+    Ok(draft)
+}
+
+/// Paths named by one `Attach:` value: a single path, or a TOML array of
+/// path strings when it starts with `[`.
+fn parse_attach_value(value: &str) -> Result<Vec<PathBuf>, String> {
+    #[derive(serde::Deserialize)]
+    struct AttachList {
+        paths: Vec<String>,
+    }
+    let paths = if value.starts_with('[') {
+        toml::from_str::<AttachList>(&format!("paths = {}", value))
+            .map_err(|e| format!("malformed Attach list {}: {}", value, e.message()))?
+            .paths
+    } else {
+        vec![value.to_string()]
+    };
+    Ok(paths
+        .iter()
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .map(crate::config::expand_path)
+        .collect())
+}
+
+/// Read every `Attach:` file of a draft, failing on the first path that is
+/// missing, not a regular file, or unreadable.
+pub fn load_attachments(paths: &[PathBuf]) -> Result<Vec<OutgoingAttachment>, String> {
+    paths
+        .iter()
+        .map(|path| {
+            if !path.is_file() {
+                return Err(format!(
+                    "attachment {} does not exist or is not a file",
+                    path.display()
+                ));
             }
+            let data = fs::read(path)
+                .map_err(|e| format!("cannot read attachment {}: {}", path.display(), e))?;
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "attachment".to_string());
+            Ok(OutgoingAttachment {
+                content_type: guess_content_type(&name).to_string(),
+                name,
+                data,
+            })
+        })
+        .collect()
+}
+
+/// MIME type for an attachment, from its file extension.
+fn guess_content_type(name: &str) -> &'static str {
+    let ext = name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "txt" | "log" => "text/plain",
+        "md" => "text/markdown",
+        "csv" => "text/csv",
+        "htm" | "html" => "text/html",
+        "ics" => "text/calendar",
+        "pdf" => "application/pdf",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "zip" => "application/zip",
+        "gz" | "tgz" => "application/gzip",
+        "tar" => "application/x-tar",
+        "doc" => "application/msword",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "xls" => "application/vnd.ms-excel",
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "ppt" => "application/vnd.ms-powerpoint",
+        "pptx" => "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+        "odt" => "application/vnd.oasis.opendocument.text",
+        "eml" => "message/rfc822",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "mp4" => "video/mp4",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Split an address header such as `A <a@x>, "Doe, J" <j@x>, b@x` into
+/// JMAP addresses. Commas inside quotes or angle brackets do not split.
+pub(crate) fn parse_address_list(value: &str) -> Vec<EmailAddress> {
+    let mut entries = Vec::new();
+    let mut current = String::new();
+    let (mut in_quotes, mut in_angle) = (false, false);
+    for c in value.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '<' if !in_quotes => in_angle = true,
+            '>' if !in_quotes => in_angle = false,
+            ',' if !in_quotes && !in_angle => {
+                entries.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    entries.push(current);
+
+    entries
+        .iter()
+        .map(|entry| entry.trim())
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let name = entry
+                .find('<')
+                .map(|i| entry[..i].trim().trim_matches('"').trim())
+                .filter(|n| !n.is_empty())
+                .map(str::to_string);
+            EmailAddress {
+                name,
+                email: extract_email_addr(entry).or_else(|| Some(entry.to_string())),
+            }
+        })
+        .collect()
+}
+
+/// Structured form of a draft for `Email/set`, used when it has attachments.
+pub fn build_outgoing_email(
+    draft: &ParsedDraft,
+    attachments: Vec<OutgoingAttachment>,
+) -> OutgoingEmail {
+    let message_ids = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(|v| {
+                v.split_whitespace()
+                    .map(|id| id.trim_matches(['<', '>']).to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    OutgoingEmail {
+        from: parse_address_list(&draft.from),
+        to: parse_address_list(&draft.to),
+        cc: parse_address_list(&draft.cc),
+        subject: draft.subject.clone(),
+        in_reply_to: message_ids(&draft.in_reply_to),
+        references: message_ids(&draft.references),
+        text_body: draft.text_body(),
+        flowed: draft.flowed,
+        html_body: draft.html_body(),
+        attachments,
+    }
+}
+
+/// Render a parsed draft as an RFC 5322 message with CRLF endings: a single
+/// text/plain part, or multipart/alternative with a rendered HTML part for
+/// Markdown drafts.
+pub fn build_mime_message(draft: &ParsedDraft) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let domain = extract_email_addr(&draft.from)
+        .and_then(|addr| addr.split_once('@').map(|(_, d)| d.to_string()))
+        .unwrap_or_else(|| "localhost".to_string());
+
+    let mut headers = vec![
+        format!("From: {}", encode_address_header(&draft.from)),
+        format!("To: {}", encode_address_header(&draft.to)),
+    ];
+    if !draft.cc.is_empty() {
+        headers.push(format!("Cc: {}", encode_address_header(&draft.cc)));
+    }
+    headers.push(format!("Subject: {}", encode_header_value(&draft.subject)));
+    headers.push(format!("Date: {}", rfc2822_date(now.as_secs() as i64)));
+    headers.push(format!(
+        "Message-ID: <{}.{}@{}>",
+        now.as_nanos(),
+        std::process::id(),
+        domain
+    ));
+    if let Some(ref irt) = draft.in_reply_to {
+        headers.push(format!("In-Reply-To: {}", irt));
+    }
+    if let Some(ref refs) = draft.references {
+        headers.push(format!("References: {}", refs));
+    }
+    for (name, value) in &draft.extra_headers {
+        headers.push(format!("{}: {}", name, value));
+    }
+    headers.push("MIME-Version: 1.0".to_string());
+
+    let crlf = |text: &str| text.lines().collect::<Vec<_>>().join("\r\n");
+    let body = crlf(&draft.text_body());
+    let text_type = if draft.flowed {
+        "text/plain; charset=utf-8; format=flowed"
+    } else {
+        "text/plain; charset=utf-8"
+    };
+    let Some(html) = draft.html_body() else {
+        headers.push(format!("Content-Type: {}", text_type));
+        headers.push("Content-Transfer-Encoding: 8bit".to_string());
+        return format!("{}\r\n\r\n{}\r\n", headers.join("\r\n"), body);
+    };
+
+    let boundary = format!("tmc-alt-{}.{}", now.as_nanos(), std::process::id());
+    headers.push(format!(
+        "Content-Type: multipart/alternative; boundary=\"{}\"",
+        boundary
+    ));
+    let part = |content_type: &str, content: &str| {
+        format!(
+            "--{}\r\nContent-Type: {}\r\nContent-Transfer-Encoding: 8bit\r\n\r\n{}\r\n",
+            boundary, content_type, content
+        )
+    };
+    format!(
+        "{}\r\n\r\n{}{}--{}--\r\n",
+        headers.join("\r\n"),
+        part(text_type, &body),
+        part("text/html; charset=utf-8", &crlf(&html)),
+        boundary
+    )
+}
+
+/// Line length flowed paragraphs are wrapped to, quote markers included.
+const FLOWED_WIDTH: usize = 72;
+
+/// Encode a body as RFC 3676 `format=flowed` (DelSp=no): long lines are
+/// wrapped with a trailing space marking each soft break, hard breaks lose
+/// their trailing spaces, and lines starting with a space, `>` or `From `
+/// are space-stuffed. Quote prefixes such as `> > `, or `| | ` with
+/// `quote_prefix = "| "`, are normalized to `>> `; the `-- ` signature
+/// separator is left alone.
+fn encode_flowed(body: &str, quote_prefix: &str) -> String {
+    let marker = quote_prefix.trim();
+    let mut out = Vec::new();
+    for line in body.lines() {
+        if line == "-- " {
+            out.push(line.to_string());
+            continue;
+        }
+        let (depth, content_start) = quote_depth(line, marker);
+        let (prefix, content) = if depth > 0 {
+            (">".repeat(depth), &line[content_start..])
+        } else {
+            (String::new(), line)
+        };
+        let content = content.trim_end();
+        if content.is_empty() {
+            out.push(prefix);
+            continue;
+        }
+        let avail = FLOWED_WIDTH.saturating_sub(prefix.len() + 1).max(1);
+        for segment in wrap_flowed(content, avail) {
+            // After quote markers the space is stuffing; unquoted lines
+            // only need it when they would otherwise be misread.
+            let stuff =
+                depth > 0 || segment.starts_with([' ', '>']) || segment.starts_with("From ");
+            out.push(format!(
+                "{}{}{}",
+                prefix,
+                if stuff { " " } else { "" },
+                segment
+            ));
         }
     }
-    // Fall back to htmlBody when no plain text is available
-    if let Some(ref html_body) = email.html_body {
-        for part in html_body {
-            if let Some(value) = email.body_values.get(&part.part_id) {
-                return html_to_plain(&value.value);
+    out.join("\n")
+}
+
+/// Nesting depth of the quote markers (`>` or `marker`) opening `line`,
+/// ignoring spaces around them, and the byte offset where its text starts.
+fn quote_depth(line: &str, marker: &str) -> (usize, usize) {
+    let mut depth = 0;
+    let mut rest = line;
+    loop {
+        let trimmed = rest.trim_start_matches(' ');
+        if let Some(after) = trimmed.strip_prefix('>') {
+            rest = after;
+        } else if let Some(after) = trimmed.strip_prefix(marker).filter(|_| !marker.is_empty()) {
+            rest = after;
+        } else {
+            rest = trimmed;
+            break;
+        }
+        depth += 1;
+    }
+    (depth, line.len() - rest.len())
+}
+
+/// Split `text` at spaces into pieces of at most `width` chars, each but the
+/// last keeping its trailing space. Words longer than `width` stay whole.
+fn wrap_flowed(text: &str, width: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut rest = text;
+    while let Some((limit, _)) = rest.char_indices().nth(width) {
+        let split = match rest[..limit].rfind(' ').filter(|&i| i > 0) {
+            Some(i) => i + 1,
+            None => match rest[limit..].find(' ') {
+                Some(i) => limit + i + 1,
+                None => break,
+            },
+        };
+        pieces.push(&rest[..split]);
+        rest = &rest[split..];
+    }
+    pieces.push(rest);
+    pieces
+}
+
+/// Return the bare address from the `From:` header of a raw message,
+/// following folded continuation lines.
+pub fn sender_address(raw: &str) -> Option<String> {
+    let mut from: Option<String> = None;
+    for line in raw.lines().take_while(|l| !l.trim().is_empty()) {
+        if line.starts_with([' ', '\t']) {
+            if let Some(value) = from.as_mut() {
+                value.push_str(line);
+            }
+            continue;
+        }
+        if from.is_some() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("from") {
+                from = Some(value.to_string());
             }
         }
     }
-    email.preview.as_deref().unwrap_or("(no body)").to_string()
+    extract_email_addr(&from?)
 }
 
-/// Heuristic check: does this text look like HTML rather than plain text?
-/// Checks for common HTML structural tags anywhere in the content.
-fn looks_like_html(text: &str) -> bool {
-    // Walk back to the nearest char boundary so multi-byte UTF-8 doesn't panic.
-    let mut end = 2000.min(text.len());
-    while end > 0 && !text.is_char_boundary(end) {
-        end -= 1;
+/// Longest UTF-8 run that fits one encoded-word: `=?UTF-8?B?` and `?=`
+/// leave 63 of RFC 2047's 75 characters, i.e. 15 base64 quads.
+const ENCODED_WORD_BYTES: usize = 45;
+
+/// RFC 2047-encode a header value when it contains non-ASCII characters.
+/// Long values become several encoded-words on folded lines, each split on
+/// a character boundary.
+fn encode_header_value(value: &str) -> String {
+    use base64::Engine;
+    if value.is_ascii() {
+        return value.to_string();
+    }
+    let mut words = Vec::new();
+    let mut start = 0;
+    while start < value.len() {
+        let mut end = (start + ENCODED_WORD_BYTES).min(value.len());
+        while !value.is_char_boundary(end) {
+            end -= 1;
+        }
+        words.push(format!(
+            "=?UTF-8?B?{}?=",
+            base64::engine::general_purpose::STANDARD.encode(&value[start..end])
+        ));
+        start = end;
     }
-    let sample = &text[..end];
-    let lower = sample.to_ascii_lowercase();
-    lower.contains("<!doctype")
-        || lower.contains("<html")
-        || lower.contains("<head")
-        || lower.contains("<body")
-        || lower.contains("<style")
-        || lower.contains("<table")
-        || lower.contains("<div")
+    words.join("\r\n ")
 }
 
-/// Convert HTML to plain text (no ANSI formatting). Used for drafts and CLI.
-fn html_to_plain(html: &str) -> String {
-    html2text::from_read(html.as_bytes(), 80).unwrap_or_else(|_| html.to_string())
+/// Render an address header with non-ASCII display names RFC 2047-encoded.
+/// All-ASCII values are passed through as typed.
+fn encode_address_header(value: &str) -> String {
+    if value.is_ascii() {
+        return value.to_string();
+    }
+    parse_address_list(value)
+        .into_iter()
+        .map(|address| {
+            let email = address.email.unwrap_or_default();
+            match address.name {
+                Some(name) if !name.is_ascii() => {
+                    format!("{} <{}>", encode_header_value(&name), email)
+                }
+                Some(name) if name.contains(|c| "()<>[]:;@\\,.\"".contains(c)) => {
+                    format!("\"{}\" <{}>", name.replace('"', "\\\""), email)
+                }
+                Some(name) => format!("{} <{}>", name, email),
+                None => email,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Format a Unix timestamp as an RFC 2822 date in UTC.
+fn rfc2822_date(secs: i64) -> String {
+    const DAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let days = secs.div_euclid(86400);
+    let rem = secs.rem_euclid(86400);
+    let (year, month, day) = crate::datefmt::civil_from_days(days);
+    format!(
+        "{}, {:02} {} {} {:02}:{:02}:{:02} +0000",
+        DAYS[days.rem_euclid(7) as usize],
+        day,
+        MONTHS[(month - 1) as usize],
+        year,
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    )
 }
 
 /// Paths produced by [`write_compose_draft`] that the caller must remove once
@@ -458,6 +1215,18 @@ fn mml_part(content_type: &str, path: &Path, description: Option<&str>) -> Strin
     tag
 }
 
+/// Undo [`mml_part`]: drop the `<#part ...>` / `<#/part>` tag lines that
+/// [`write_compose_draft`] appended, leaving the text the draft started from.
+pub fn strip_mml_parts(text: &str) -> String {
+    let kept: Vec<&str> = text
+        .lines()
+        .filter(|l| !l.starts_with("<#part ") && *l != "<#/part>")
+        .collect();
+    let mut out = kept.join("\n").trim_end_matches('\n').to_string();
+    out.push('\n');
+    out
+}
+
 /// Make a filename safe to use as a single path component.
 fn sanitize_filename(name: &str) -> String {
     let cleaned: String = name
@@ -512,11 +1281,23 @@ mod tests {
 
     #[test]
     fn test_build_compose_draft() {
-        let draft = build_compose_draft("me@example.com");
+        let draft = build_compose_draft("me@example.com", None, &SignatureOptions::default());
         assert!(draft.contains("From: me@example.com"));
         assert!(draft.contains("To: \n"));
         assert!(draft.contains("Subject: \n"));
         assert!(draft.contains("--text follows this line--"));
+
+        let recent = [
+            "Bob <bob@example.com>".to_string(),
+            "ann@example.com".to_string(),
+        ];
+        let with_recent = with_recent_addresses(&draft, &recent);
+        assert!(with_recent.contains(
+            "Subject: \nRecent: Bob <bob@example.com>, ann@example.com\n--text follows this line--"
+        ));
+        let parsed = parse_unfinished_draft(&with_recent).unwrap();
+        assert!(parsed.to.is_empty() && parsed.cc.is_empty());
+        assert_eq!(with_recent_addresses(&draft, &[]), draft);
     }
 
     #[test]
@@ -552,15 +1333,44 @@ mod tests {
             extra: HashMap::new(),
         };
 
-        let draft = build_reply_draft(&email, false, "me@example.com");
+        let draft = build_reply_draft(
+            &email,
+            false,
+            "me@example.com",
+            None,
+            &SignatureOptions::default(),
+            &QuoteStyle::default(),
+        );
         assert!(draft.contains("To: Sender <sender@example.com>"));
         assert!(draft.contains("Subject: Re: Hello"));
         assert!(draft.contains("In-Reply-To: <abc@example.com>"));
         assert!(draft.contains("> Preview text"));
 
         // Reply-all should include original To minus self
-        let draft_all = build_reply_draft(&email, true, "me@example.com");
+        let draft_all = build_reply_draft(
+            &email,
+            true,
+            "me@example.com",
+            None,
+            &SignatureOptions::default(),
+            &QuoteStyle::default(),
+        );
         assert!(!draft_all.contains("Cc:")); // self was the only To recipient
+
+        let signature = SignatureOptions::default();
+        let custom = QuoteStyle {
+            prefix: "| ".to_string(),
+            reply_attribution: "{from} wrote about \"{subject}\" ({date}):".to_string(),
+            forward_attribution: String::new(),
+        };
+        let draft = build_reply_draft(&email, false, "me@example.com", None, &signature, &custom);
+        assert!(draft.contains(
+            "\nSender <sender@example.com> wrote about \"Hello\" (2024-01-01T00:00:00Z):\n| Preview text\n"
+        ));
+        assert!(!draft.contains("> Preview text"));
+        let forward = build_forward_draft(&email, "me@example.com", None, &signature, &custom);
+        assert!(!forward.contains("Forwarded message"));
+        assert!(forward.contains("--text follows this line--\n\nFrom: Sender"));
     }
 
     #[test]
@@ -602,11 +1412,90 @@ mod tests {
             extra: HashMap::new(),
         };
 
-        let draft = build_reply_draft(&email, true, "Example User <user@example.com>");
+        let draft = build_reply_draft(
+            &email,
+            true,
+            "Example User <user@example.com>",
+            None,
+            &SignatureOptions::default(),
+            &QuoteStyle::default(),
+        );
         assert!(!draft.contains("Cc: Example User <user@example.com>"));
         assert!(draft.contains("Cc: Other <other@example.com>"));
     }
 
+    #[test]
+    fn test_reply_picks_identity_matching_recipient() {
+        use crate::jmap::types::{Email, EmailAddress};
+
+        let identities = vec![
+            IdentityConfig {
+                name: "Me".to_string(),
+                email: "me@example.com".to_string(),
+                signature: None,
+            },
+            IdentityConfig {
+                name: "Sales".to_string(),
+                email: "sales@example.com".to_string(),
+                signature: None,
+            },
+        ];
+        let mut email: Email = serde_json::from_value(serde_json::json!({
+            "id": "test-id",
+            "from": [{"name": "Sender", "email": "sender@example.com"}],
+            "to": [{"name": null, "email": "someone@example.com"}],
+            "cc": [{"name": null, "email": "SALES@example.com"}],
+            "subject": "Quote"
+        }))
+        .unwrap();
+
+        let reply = |choice: &IdentityChoice, email: &Email| {
+            build_reply_draft(
+                email,
+                false,
+                "fallback@example.com",
+                choice.for_response(Some(email)),
+                &SignatureOptions::default(),
+                &QuoteStyle::default(),
+            )
+        };
+        let compose = |choice: &IdentityChoice| {
+            build_compose_draft(
+                "fallback@example.com",
+                choice.for_compose(),
+                &SignatureOptions::default(),
+            )
+        };
+
+        // Without reply_from, replies use the addressed identity.
+        let mut choice = IdentityChoice::new(identities.clone(), false);
+        assert!(reply(&choice, &email).starts_with("From: Sales <sales@example.com>\n"));
+        assert!(compose(&choice).starts_with("From: Me <me@example.com>\n"));
+
+        // A configured reply_from wins until an identity is picked with `i`.
+        let mut with_reply_from = IdentityChoice::new(identities.clone(), true);
+        assert!(reply(&with_reply_from, &email).starts_with("From: fallback@example.com\n"));
+        assert!(compose(&with_reply_from).starts_with("From: fallback@example.com\n"));
+        assert_eq!(with_reply_from.cycle(), "Composing as Me <me@example.com>");
+        assert!(reply(&with_reply_from, &email).starts_with("From: Me <me@example.com>\n"));
+
+        email.cc = Some(vec![EmailAddress {
+            name: None,
+            email: Some("nobody@example.com".to_string()),
+        }]);
+        assert!(reply(&choice, &email).starts_with("From: Me <me@example.com>\n"));
+        assert_eq!(choice.cycle(), "Composing as Sales <sales@example.com>");
+        assert!(compose(&choice).starts_with("From: Sales <sales@example.com>\n"));
+        assert_eq!(choice.cycle(), "Composing as Me <me@example.com>");
+
+        let none = IdentityChoice::new(Vec::new(), false);
+        assert!(reply(&none, &email).starts_with("From: fallback@example.com\n"));
+        assert_eq!(
+            IdentityChoice::default().cycle(),
+            "No identities configured"
+        );
+    }
+
     #[test]
     fn test_build_forward_draft() {
         use crate::jmap::types::{Email, EmailAddress};
@@ -643,7 +1532,13 @@ mod tests {
             extra: HashMap::new(),
         };
 
-        let draft = build_forward_draft(&email, "me@example.com");
+        let draft = build_forward_draft(
+            &email,
+            "me@example.com",
+            None,
+            &SignatureOptions::default(),
+            &QuoteStyle::default(),
+        );
         assert!(draft.contains("From: me@example.com"));
         assert!(draft.contains("To: \n"));
         assert!(draft.contains("Subject: Fwd: Hello"));
@@ -686,12 +1581,56 @@ mod tests {
             extra: HashMap::new(),
         };
 
-        let draft = build_forward_draft(&email, "me@example.com");
+        let draft = build_forward_draft(
+            &email,
+            "me@example.com",
+            None,
+            &SignatureOptions::default(),
+            &QuoteStyle::default(),
+        );
         assert!(draft.contains("Subject: Fwd: Already forwarded\n"));
         // Should not double-prefix
         assert!(!draft.contains("Fwd: Fwd:"));
     }
 
+    #[test]
+    fn test_build_rule_forward() {
+        use crate::jmap::types::{Email, EmailAddress};
+        use std::collections::HashMap;
+
+        let email = Email {
+            id: "test-id".to_string(),
+            thread_id: None,
+            from: Some(vec![EmailAddress {
+                name: None,
+                email: Some("shop@example.com".to_string()),
+            }]),
+            to: None,
+            cc: None,
+            reply_to: None,
+            subject: Some("Invoice 42".to_string()),
+            received_at: None,
+            sent_at: None,
+            preview: Some("Total due".to_string()),
+            text_body: None,
+            html_body: None,
+            body_values: HashMap::new(),
+            keywords: HashMap::new(),
+            mailbox_ids: HashMap::new(),
+            message_id: Some(vec!["inv42@example.com".to_string()]),
+            references: None,
+            attachments: None,
+            extra: HashMap::new(),
+        };
+
+        let draft = build_rule_forward(&email, "Me <me@example.com>", "books@example.net").unwrap();
+        assert_eq!(draft.to, "books@example.net");
+        assert_eq!(draft.subject, "Fwd: Invoice 42");
+        assert!(draft.body.contains("Total due"));
+        let mime = build_mime_message(&draft);
+        assert!(mime.contains("\r\nX-Tmc-Forwarded: <inv42@example.com>\r\nMIME-Version: 1.0\r\n"));
+    }
+
     #[test]
     fn test_build_forward_attachment_draft() {
         use crate::jmap::types::{Email, EmailAddress};
@@ -723,7 +1662,13 @@ mod tests {
         };
 
         let raw = b"Subject: Hello World\r\nContent-Type: text/html\r\n\r\n<b>hi</b>";
-        let draft = build_forward_attachment_draft(Some(&email), raw.to_vec(), "me@example.com");
+        let draft = build_forward_attachment_draft(
+            Some(&email),
+            raw.to_vec(),
+            "me@example.com",
+            None,
+            &SignatureOptions::default(),
+        );
 
         assert!(draft.body.contains("Subject: Fwd: Hello World"));
         assert!(draft.body.contains("--text follows this line--"));
@@ -741,7 +1686,13 @@ mod tests {
     #[test]
     fn test_build_forward_attachment_draft_no_metadata() {
         // Falls back to a generic subject/filename when the email is unknown.
-        let draft = build_forward_attachment_draft(None, b"raw".to_vec(), "me@example.com");
+        let draft = build_forward_attachment_draft(
+            None,
+            b"raw".to_vec(),
+            "me@example.com",
+            None,
+            &SignatureOptions::default(),
+        );
         assert!(draft.body.contains("Subject: Fwd: \n"));
         assert_eq!(draft.attachments[0].filename, "no_subject.eml");
     }
@@ -775,6 +1726,17 @@ mod tests {
         assert!(part.contains("say 'hi' there"));
     }
 
+    #[test]
+    fn test_strip_mml_parts_undoes_attachment_tags() {
+        let body = "To: a@example.com\nSubject: Fwd\n\nsee attached\n";
+        let tagged = format!(
+            "{}{}",
+            body,
+            mml_part("message/rfc822", Path::new("/tmp/x/f.eml"), Some("Fwd"))
+        );
+        assert_eq!(strip_mml_parts(&tagged), body);
+    }
+
     #[test]
     fn test_sanitize_filename() {
         assert_eq!(sanitize_filename("a/b"), "a_b");
@@ -821,14 +1783,342 @@ mod tests {
     }
 
     #[test]
-    fn test_looks_like_html_handles_multibyte_at_sample_boundary() {
-        // Regression: text with multi-byte UTF-8 (U+034F CGJ, as found in
-        // newsletter preview "tracking pixel" runs) used to panic when the
-        // 2000-byte sample boundary fell inside a code point.
-        let mut s = String::from("AAA");
-        for _ in 0..1000 {
-            s.push_str("X\u{34f}");
-        }
-        assert!(!looks_like_html(&s));
+    fn test_parse_draft_reads_headers_and_body() {
+        let text = "From: Me <me@example.com>\nTo: you@example.com\nCc: \nSubject: Hi\nIn-Reply-To: <abc@example.com>\n--text follows this line--\n\nHello there\n\nBye\n";
+        let draft = parse_draft(text).unwrap();
+        assert_eq!(draft.from, "Me <me@example.com>");
+        assert_eq!(draft.to, "you@example.com");
+        assert_eq!(draft.cc, "");
+        assert_eq!(draft.subject, "Hi");
+        assert_eq!(draft.in_reply_to.as_deref(), Some("<abc@example.com>"));
+        assert_eq!(draft.body, "Hello there\n\nBye");
+    }
+
+    #[test]
+    fn test_parse_draft_collects_attach_lines() {
+        let text = "From: me@example.com\nTo: you@example.com\nAttach: [\"/tmp/a.pdf\", \"/tmp/b, c.png\"]\nAttach: /tmp/d,e.txt\n--text follows this line--\nHi\n";
+        let draft = parse_draft(text).unwrap();
+        assert_eq!(
+            draft.attachments,
+            vec![
+                PathBuf::from("/tmp/a.pdf"),
+                PathBuf::from("/tmp/b, c.png"),
+                PathBuf::from("/tmp/d,e.txt"),
+            ]
+        );
+        assert_eq!(draft.body, "Hi");
+
+        let text = "From: me@example.com\nTo: you@example.com\nAttach: [\"/tmp/a.pdf\"\n\nHi\n";
+        let err = parse_draft(text).unwrap_err();
+        assert!(err.contains("malformed Attach list"), "{}", err);
+    }
+
+    #[test]
+    fn test_load_attachments_reads_files_and_rejects_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Report.PDF");
+        fs::write(&path, b"%PDF").unwrap();
+        let loaded = load_attachments(std::slice::from_ref(&path)).unwrap();
+        assert_eq!(loaded[0].name, "Report.PDF");
+        assert_eq!(loaded[0].content_type, "application/pdf");
+        assert_eq!(loaded[0].data, b"%PDF");
+
+        let err = load_attachments(&[dir.path().join("nope.txt")]).unwrap_err();
+        assert!(err.contains("nope.txt"));
+        assert!(load_attachments(&[dir.path().to_path_buf()]).is_err());
+        assert_eq!(guess_content_type("archive"), "application/octet-stream");
+    }
+
+    #[test]
+    fn test_build_outgoing_email() {
+        let draft = ParsedDraft {
+            from: "Me <me@example.com>".to_string(),
+            to: "\"Doe, Jane\" <jane@example.com>, bob@example.com".to_string(),
+            in_reply_to: Some("<abc@example.com>".to_string()),
+            references: Some("<x@example.com> <abc@example.com>".to_string()),
+            body: "Hello".to_string(),
+            ..Default::default()
+        };
+        let email = build_outgoing_email(&draft, Vec::new());
+        assert_eq!(email.from[0].name.as_deref(), Some("Me"));
+        assert_eq!(email.from[0].email.as_deref(), Some("me@example.com"));
+        assert_eq!(email.to.len(), 2);
+        assert_eq!(email.to[0].name.as_deref(), Some("Doe, Jane"));
+        assert_eq!(email.to[1].name, None);
+        assert_eq!(email.to[1].email.as_deref(), Some("bob@example.com"));
+        assert!(email.cc.is_empty());
+        assert_eq!(email.in_reply_to, vec!["abc@example.com"]);
+        assert_eq!(email.references, vec!["x@example.com", "abc@example.com"]);
+        assert_eq!(email.text_body, "Hello");
+    }
+
+    #[test]
+    fn test_parse_draft_requires_recipient() {
+        let err = parse_draft(&build_compose_draft(
+            "me@example.com",
+            None,
+            &SignatureOptions::default(),
+        ))
+        .unwrap_err();
+        assert!(err.contains("no recipients"));
+
+        let unfinished = parse_unfinished_draft(&build_compose_draft(
+            "me@example.com",
+            None,
+            &SignatureOptions::default(),
+        ))
+        .unwrap();
+        assert_eq!(unfinished.from, "me@example.com");
+        assert!(unfinished.to.is_empty());
+    }
+
+    #[test]
+    fn test_build_mime_message() {
+        let draft = ParsedDraft {
+            from: "Me <me@example.com>".to_string(),
+            to: "you@example.com".to_string(),
+            subject: "Caf\u{e9}".to_string(),
+            body: "line one\nline two".to_string(),
+            ..Default::default()
+        };
+        let mime = build_mime_message(&draft);
+        assert!(mime.starts_with("From: Me <me@example.com>\r\nTo: you@example.com\r\n"));
+        assert!(mime.contains("Subject: =?UTF-8?B?Q2Fmw6k=?=\r\n"));
+        assert!(mime.contains("Message-ID: <"));
+        assert!(mime.contains("@example.com>\r\n"));
+        assert!(!mime.contains("Cc:"));
+        assert!(mime.ends_with("\r\n\r\nline one\r\nline two\r\n"));
+        assert_eq!(sender_address(&mime).as_deref(), Some("me@example.com"));
+    }
+
+    #[test]
+    fn test_build_mime_message_encodes_display_names() {
+        let draft = ParsedDraft {
+            from: "Jos\u{e9} <jose@example.com>".to_string(),
+            to: "\"Doe, J\" <j@example.com>, Ren\u{e9}e <renee@example.com>".to_string(),
+            cc: "M\u{fc}ller <m@example.com>".to_string(),
+            subject: "hi".to_string(),
+            body: "body".to_string(),
+            ..Default::default()
+        };
+        let mime = build_mime_message(&draft);
+        assert!(mime.starts_with("From: =?UTF-8?B?Sm9zw6k=?= <jose@example.com>\r\n"));
+        assert!(mime.contains(
+            "To: \"Doe, J\" <j@example.com>, =?UTF-8?B?UmVuw6ll?= <renee@example.com>\r\n"
+        ));
+        assert!(mime.contains("Cc: =?UTF-8?B?TcO8bGxlcg==?= <m@example.com>\r\n"));
+        assert!(mime.contains("Subject: hi\r\n"));
+        assert_eq!(sender_address(&mime).as_deref(), Some("jose@example.com"));
+    }
+
+    #[test]
+    fn test_long_subject_splits_into_encoded_words() {
+        use base64::Engine;
+        let subject = "\u{e9}t\u{e9} ".repeat(20);
+        let encoded = encode_header_value(&subject);
+        let mut decoded = Vec::new();
+        for (i, line) in encoded.split("\r\n").enumerate() {
+            let word = if i == 0 {
+                line
+            } else {
+                line.strip_prefix(' ').unwrap()
+            };
+            assert!(word.len() <= 75, "encoded-word too long: {}", word);
+            let payload = word
+                .strip_prefix("=?UTF-8?B?")
+                .and_then(|w| w.strip_suffix("?="))
+                .unwrap();
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(payload)
+                .unwrap();
+            // Every word decodes on its own: no character is split.
+            assert!(std::str::from_utf8(&bytes).is_ok());
+            decoded.extend(bytes);
+        }
+        assert!(encoded.contains("\r\n "));
+        assert_eq!(String::from_utf8(decoded).unwrap(), subject);
+        assert_eq!(encode_header_value("plain"), "plain");
+    }
+
+    #[test]
+    fn test_sender_address_follows_folded_from() {
+        let raw = "From: =?UTF-8?B?YQ==?=\r\n =?UTF-8?B?Yg==?= <ab@example.com>\r\nTo: x@example.com\r\n\r\nbody";
+        assert_eq!(sender_address(raw).as_deref(), Some("ab@example.com"));
+    }
+
+    #[test]
+    fn test_markdown_draft_builds_multipart_alternative() {
+        let text = "From: me@example.com\nTo: you@example.com\nSubject: Notes\nContent-Type: text/markdown; charset=utf-8\n--text follows this line--\nSome **bold** news\n";
+        let draft = parse_draft(text).unwrap();
+        assert_eq!(draft.format, Some(ComposeFormat::Markdown));
+
+        let mime = build_mime_message(&draft);
+        assert!(mime.contains("Content-Type: multipart/alternative; boundary=\"tmc-alt-"));
+        assert!(mime.contains("Content-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: 8bit\r\n\r\nSome **bold** news\r\n"));
+        assert!(mime.contains("Content-Type: text/html; charset=utf-8"));
+        assert!(mime.contains("<p>Some <strong>bold</strong> news</p>"));
+        assert!(mime.trim_end().ends_with("--"));
+
+        let email = build_outgoing_email(&draft, Vec::new());
+        assert_eq!(email.text_body, "Some **bold** news");
+        assert!(email.html_body.unwrap().contains("<strong>bold</strong>"));
+
+        let plain = parse_draft(&text.replace("text/markdown", "text/plain")).unwrap();
+        assert_eq!(plain.format, Some(ComposeFormat::Plain));
+        assert!(plain.html_body().is_none());
+        assert!(parse_draft(&text.replace("text/markdown", "text/html")).is_err());
+    }
+
+    #[test]
+    fn test_format_flowed() {
+        let long = "word ".repeat(20);
+        let body = format!(
+            "{}\nFrom here on\n> > nested quote  \n> \n>plain quote\n-- \nMe",
+            long.trim_end()
+        );
+        let encoded = encode_flowed(&body, "> ");
+        let lines: Vec<&str> = encoded.lines().collect();
+        assert!(lines[0].ends_with(' ') && lines[0].len() <= FLOWED_WIDTH);
+        assert!(!lines[1].ends_with(' '));
+        assert_eq!(
+            format!("{}{}", lines[0], lines[1]),
+            long.trim_end(),
+            "soft breaks rejoin to the original paragraph"
+        );
+        assert_eq!(
+            &lines[2..],
+            [
+                " From here on",
+                ">> nested quote",
+                ">",
+                "> plain quote",
+                "-- ",
+                "Me"
+            ]
+        );
+
+        let long_quote = format!("> {}", long);
+        let encoded = encode_flowed(&long_quote, "> ");
+        assert!(encoded.lines().all(|l| l.starts_with("> ")));
+        assert_eq!(
+            wrap_flowed("a_very_long_word b", 4),
+            ["a_very_long_word ", "b"]
+        );
+
+        let draft = ParsedDraft {
+            from: "me@example.com".to_string(),
+            to: "you@example.com".to_string(),
+            flowed: true,
+            body,
+            ..Default::default()
+        };
+        let mime = build_mime_message(&draft);
+        assert!(mime.contains("Content-Type: text/plain; charset=utf-8; format=flowed\r\n"));
+        assert!(mime.contains("\r\n From here on\r\n"));
+    }
+
+    #[test]
+    fn flowed_covers_every_text_part_and_the_quote_prefix() {
+        let body = "| | nested\n|plain\n> | mixed\nfine | pipe";
+        assert_eq!(
+            encode_flowed(body, "| "),
+            ">> nested\n> plain\n>> mixed\nfine | pipe"
+        );
+
+        let draft = ParsedDraft {
+            from: "me@example.com".to_string(),
+            to: "you@example.com".to_string(),
+            format: Some(ComposeFormat::Markdown),
+            flowed: true,
+            quote_prefix: "| ".to_string(),
+            body: body.to_string(),
+            ..Default::default()
+        };
+        let mime = build_mime_message(&draft);
+        assert!(mime.contains(
+            "Content-Type: text/plain; charset=utf-8; format=flowed\r\n\
+             Content-Transfer-Encoding: 8bit\r\n\r\n>> nested\r\n"
+        ));
+        assert!(mime.contains("Content-Type: text/html; charset=utf-8\r\n"));
+
+        let email = build_outgoing_email(&draft, Vec::new());
+        assert!(email.flowed);
+        assert!(email.text_body.starts_with(">> nested\n> plain"));
+    }
+
+    #[test]
+    fn test_rfc2822_date() {
+        assert_eq!(rfc2822_date(0), "Thu, 01 Jan 1970 00:00:00 +0000");
+        assert_eq!(rfc2822_date(1709210096), "Thu, 29 Feb 2024 12:34:56 +0000");
+    }
+
+    #[test]
+    fn test_signature_placement() {
+        use crate::jmap::types::Email;
+
+        let email: Email = serde_json::from_value(serde_json::json!({
+            "id": "test-id",
+            "from": [{"name": "Sender", "email": "sender@example.com"}],
+            "to": [{"name": null, "email": "me@example.com"}],
+            "subject": "Hello",
+            "preview": "Original text\n-- \nSender's signature"
+        }))
+        .unwrap();
+        let identity = IdentityConfig {
+            name: "Me".to_string(),
+            email: "me@example.com".to_string(),
+            signature: Some("Me, Esq.".to_string()),
+        };
+        let quote = QuoteStyle::default();
+        let below = SignatureOptions::default();
+
+        let draft = build_reply_draft(
+            &email,
+            false,
+            "me@example.com",
+            Some(&identity),
+            &below,
+            &quote,
+        );
+        assert!(draft.ends_with("wrote:\n> Original text\n\n-- \nMe, Esq.\n"));
+        assert!(!draft.contains("Sender's signature"));
+
+        let above = SignatureOptions {
+            account_signature: None,
+            above_quote: true,
+        };
+        let draft = build_reply_draft(
+            &email,
+            false,
+            "me@example.com",
+            Some(&identity),
+            &above,
+            &quote,
+        );
+        assert!(draft.contains("--text follows this line--\n\n\n-- \nMe, Esq.\n\nOn "));
+
+        // Account signature applies when no identity provides one.
+        let account = SignatureOptions {
+            account_signature: Some("Account sig".to_string()),
+            above_quote: false,
+        };
+        let draft = build_compose_draft("me@example.com", None, &account);
+        assert!(draft.ends_with("--text follows this line--\n\n\n-- \nAccount sig\n"));
+        let draft = build_forward_draft(&email, "me@example.com", None, &account, &quote);
+        assert!(draft.ends_with("\n-- \nAccount sig\n"));
+        assert_eq!(draft.matches("\n-- \n").count(), 2);
+
+        let parsed = parse_draft(&draft.replace("To: \n", "To: you@example.com\n")).unwrap();
+        assert!(parsed.body.ends_with("\n-- \nAccount sig"));
+    }
+
+    #[test]
+    fn test_strip_signature() {
+        assert_eq!(strip_signature("body\n\n-- \nsig"), "body");
+        assert_eq!(strip_signature("-- \nonly sig"), "");
+        assert_eq!(
+            strip_signature("no sig\n--\nnot a delimiter"),
+            "no sig\n--\nnot a delimiter"
+        );
     }
 }
@@ -1,8 +1,13 @@
+use crate::datefmt::DateFormat;
+use crate::jmap::client::HttpOptions;
+use crate::jmap::types::EmailAddress;
+use crate::keybindings::Keymap;
 use regex::Regex;
 use serde::Deserialize;
 use std::collections::BTreeMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 #[derive(Debug, Clone)]
 pub struct AccountConfig {
@@ -10,6 +15,98 @@ pub struct AccountConfig {
     pub well_known_url: String,
     pub username: String,
     pub password_command: String,
+    /// How `password_command`'s output is sent to the server.
+    pub auth: AuthMode,
+    /// With `auth = "oauth2"`, prints a fresh access token after a 401.
+    pub token_refresh_command: Option<String>,
+    pub identities: Vec<IdentityConfig>,
+    /// Signature used when the sending identity has none of its own.
+    pub signature: Option<String>,
+    /// Overrides `[ui].prefer_html` for this account.
+    pub prefer_html: Option<bool>,
+    /// `color = "#RRGGBB"`: the account name is shown in this color in the
+    /// mailbox- and email-list headers.
+    pub color: Option<(u8, u8, u8)>,
+    /// `[account.NAME.theme]` layered over `[theme]`, used while the account
+    /// is active; `None` keeps the global theme.
+    pub theme: Option<Theme>,
+    /// `authserv_ids`: `Authentication-Results` ids (and their subdomains)
+    /// written by the account's own receiving servers.
+    pub authserv_ids: Vec<String>,
+}
+
+/// Account authentication scheme (`auth = "basic"` or `"oauth2"`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AuthMode {
+    /// HTTP Basic; `password_command` prints the password.
+    #[default]
+    Basic,
+    /// Bearer token; `password_command` prints an OAuth2 access token.
+    OAuth2,
+}
+
+impl AccountConfig {
+    /// The configured `authserv_ids` plus the domains of `username` and of
+    /// the `well_known_url` host, lowercased.
+    pub fn trusted_authserv_ids(&self) -> Vec<String> {
+        let host = self
+            .well_known_url
+            .split_once("://")
+            .map_or(self.well_known_url.as_str(), |(_, rest)| rest)
+            .split(['/', ':'])
+            .next()
+            .unwrap_or("");
+        // `jmap.example.com` -> `example.com`, so `mx1.example.com` matches.
+        let host_domain = match host.split_once('.') {
+            Some((_, parent)) if parent.contains('.') => parent,
+            _ => host,
+        };
+        let candidates = self
+            .authserv_ids
+            .iter()
+            .map(String::as_str)
+            .chain(self.username.rsplit_once('@').map(|(_, domain)| domain))
+            .chain(Some(host_domain));
+        let mut ids: Vec<String> = Vec::new();
+        for id in candidates.map(str::to_ascii_lowercase) {
+            if !id.is_empty() && !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        ids
+    }
+}
+
+impl AuthMode {
+    fn parse(s: &str, section: &str) -> Result<Self, ConfigError> {
+        match s {
+            "basic" => Ok(AuthMode::Basic),
+            "oauth2" => Ok(AuthMode::OAuth2),
+            _ => Err(ConfigError::Parse(format!(
+                "invalid auth '{}' in {}: expected basic or oauth2",
+                s, section
+            ))),
+        }
+    }
+}
+
+/// A sender identity (From address) configured for an account.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdentityConfig {
+    pub name: String,
+    pub email: String,
+    pub signature: Option<String>,
+}
+
+impl IdentityConfig {
+    /// Format as a From header value, e.g. `Name <email>`.
+    pub fn display_address(&self) -> String {
+        if self.name.is_empty() {
+            self.email.clone()
+        } else {
+            format!("{} <{}>", self.name, self.email)
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -22,13 +119,79 @@ pub struct Theme {
     pub status_bg: Option<(u8, u8, u8)>,
     pub status_fg: Option<(u8, u8, u8)>,
     pub header_fg: Option<(u8, u8, u8)>,
+    /// Quoted (`>`) lines in the message body.
+    pub quote_fg: Option<(u8, u8, u8)>,
+    /// Entries of the message view's `Links:` list.
+    pub link_fg: Option<(u8, u8, u8)>,
+    /// The message view's attachment list.
+    pub attachment_fg: Option<(u8, u8, u8)>,
+    /// Passing SPF/DKIM/DMARC verdicts in the full-headers summary.
+    pub auth_pass_fg: Option<(u8, u8, u8)>,
+    /// Failing SPF/DKIM/DMARC verdicts in the full-headers summary.
+    pub auth_fail_fg: Option<(u8, u8, u8)>,
+    /// How colors are sent to the terminal; see [`ColorMode`]. `None` when
+    /// unset, leaving the TUI to pick one from `$COLORTERM`.
+    pub color_mode: Option<ColorMode>,
+    /// `[theme.senders]`: email-list row color for From addresses matching
+    /// the regex, tried in the order the file lists them.
+    pub senders: Vec<(Regex, (u8, u8, u8))>,
+    /// `[theme.keywords]`: row color for messages carrying the keyword,
+    /// tried in file order; takes precedence over sender colors.
+    pub keywords: Vec<(String, (u8, u8, u8))>,
+}
+
+impl Theme {
+    /// Row color for a message, or `None` to keep the default `fg`.
+    /// `from` is the From header as `Name <email>`.
+    pub fn row_fg(&self, from: &str, has_keyword: impl Fn(&str) -> bool) -> Option<(u8, u8, u8)> {
+        if let Some((_, color)) = self.keywords.iter().find(|(kw, _)| has_keyword(kw)) {
+            return Some(*color);
+        }
+        self.senders
+            .iter()
+            .find(|(re, _)| re.is_match(from))
+            .map(|(_, color)| *color)
+    }
+}
+
+/// `[theme].color_mode`: the color depth of the terminal. Theme colors and
+/// the truecolor escapes of rendered HTML are downsampled to fit.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ColorMode {
+    #[default]
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+impl ColorMode {
+    /// Mode for an unset `color_mode` given `$COLORTERM`: truecolor unless
+    /// the variable names something other than truecolor/24bit.
+    pub fn detect(colorterm: Option<&str>) -> Self {
+        match colorterm.map(|v| v.trim().to_ascii_lowercase()) {
+            Some(v) if !v.is_empty() && v != "truecolor" && v != "24bit" => ColorMode::Ansi256,
+            _ => ColorMode::TrueColor,
+        }
+    }
+
+    fn parse(value: &str, section: &str) -> Result<Self, ConfigError> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "truecolor" | "24bit" => Ok(ColorMode::TrueColor),
+            "256" => Ok(ColorMode::Ansi256),
+            "16" => Ok(ColorMode::Ansi16),
+            other => Err(ConfigError::Parse(format!(
+                "invalid {}.color_mode '{}': expected \"truecolor\", \"256\" or \"16\"",
+                section, other
+            ))),
+        }
+    }
 }
 
 fn parse_hex_color(s: &str, field: &str) -> Result<(u8, u8, u8), ConfigError> {
     let s = s.trim();
     if !s.starts_with('#') || s.len() != 7 {
         return Err(ConfigError::Parse(format!(
-            "invalid color '{}' for theme.{}: expected #RRGGBB format",
+            "invalid color '{}' for {}: expected #RRGGBB format",
             s, field
         )));
     }
@@ -38,7 +201,7 @@ fn parse_hex_color(s: &str, field: &str) -> Result<(u8, u8, u8), ConfigError> {
     match (r, g, b) {
         (Ok(r), Ok(g), Ok(b)) => Ok((r, g, b)),
         _ => Err(ConfigError::Parse(format!(
-            "invalid hex digits in color '{}' for theme.{}",
+            "invalid hex digits in color '{}' for {}",
             s, field
         ))),
     }
@@ -51,13 +214,126 @@ fn resolve_color(value: &Option<String>, field: &str) -> Result<Option<(u8, u8,
     }
 }
 
+/// Resolve a `[theme]`-shaped section; `section` names it in errors.
+fn build_theme(raw: &RawThemeConfig, section: &str) -> Result<Theme, ConfigError> {
+    let color = |value: &Option<String>, field: &str| {
+        resolve_color(value, &format!("{}.{}", section, field))
+    };
+    Ok(Theme {
+        bg: color(&raw.bg, "bg")?,
+        fg: color(&raw.fg, "fg")?,
+        bold_fg: color(&raw.bold_fg, "bold_fg")?,
+        selection_bg: color(&raw.selection_bg, "selection_bg")?,
+        selection_fg: color(&raw.selection_fg, "selection_fg")?,
+        status_bg: color(&raw.status_bg, "status_bg")?,
+        status_fg: color(&raw.status_fg, "status_fg")?,
+        header_fg: color(&raw.header_fg, "header_fg")?,
+        quote_fg: color(&raw.quote_fg, "quote_fg")?,
+        link_fg: color(&raw.link_fg, "link_fg")?,
+        attachment_fg: color(&raw.attachment_fg, "attachment_fg")?,
+        auth_pass_fg: color(&raw.auth_pass_fg, "auth_pass_fg")?,
+        auth_fail_fg: color(&raw.auth_fail_fg, "auth_fail_fg")?,
+        color_mode: raw
+            .color_mode
+            .as_deref()
+            .map(|mode| ColorMode::parse(mode, section))
+            .transpose()?,
+        senders: raw
+            .senders
+            .iter()
+            .map(|(pattern, color)| {
+                let re = Regex::new(pattern).map_err(|e| {
+                    ConfigError::Parse(format!(
+                        "invalid regex '{}' in {}.senders: {}",
+                        pattern, section, e
+                    ))
+                })?;
+                Ok((
+                    re,
+                    parse_hex_color(color, &format!("{}.senders.{}", section, pattern))?,
+                ))
+            })
+            .collect::<Result<_, ConfigError>>()?,
+        keywords: raw
+            .keywords
+            .iter()
+            .map(|(keyword, color)| {
+                Ok((
+                    keyword.clone(),
+                    parse_hex_color(color, &format!("{}.keywords.{}", section, keyword))?,
+                ))
+            })
+            .collect::<Result<_, ConfigError>>()?,
+    })
+}
+
 #[derive(Debug)]
 pub struct Config {
     pub accounts: Vec<AccountConfig>,
     pub ui: UiConfig,
     pub mail: MailConfig,
     pub spam: SpamConfig,
+    pub cache: CacheConfig,
+    pub contacts: ContactsConfig,
     pub theme: Theme,
+    /// Email-list key remapping from `[keybindings]`.
+    pub keybindings: Keymap,
+    /// `[[saved_search]]` entries, listed below the mailboxes.
+    pub saved_searches: Vec<SavedSearchConfig>,
+}
+
+/// A named search from `[[saved_search]]`, opened like a mailbox. Without
+/// a `mailbox` it searches every mailbox.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SavedSearchConfig {
+    pub name: String,
+    pub query: String,
+    pub mailbox: Option<String>,
+}
+
+/// Local message cache limits from `[cache]`.
+#[derive(Debug, Clone, Default)]
+pub struct CacheConfig {
+    /// Once the cache file grows past this many bytes, the least recently
+    /// read messages are evicted; `None` leaves the cache unbounded.
+    pub max_bytes: Option<u64>,
+}
+
+/// Address book for completion from `[contacts]`: a file and/or a command
+/// printing one `Name <email>` per line.
+#[derive(Debug, Clone, Default)]
+pub struct ContactsConfig {
+    pub file: Option<PathBuf>,
+    pub command: Option<String>,
+}
+
+impl ContactsConfig {
+    /// Read the address book, file entries first. Nothing configured gives
+    /// an empty list.
+    pub fn load(&self) -> Result<Vec<EmailAddress>, String> {
+        let mut text = String::new();
+        if let Some(ref file) = self.file {
+            text = fs::read_to_string(file)
+                .map_err(|e| format!("cannot read contacts file {}: {}", file.display(), e))?;
+            text.push('\n');
+        }
+        if let Some(ref command) = self.command {
+            let output = std::process::Command::new("sh")
+                .arg("-c")
+                .arg(command)
+                .output()
+                .map_err(|e| format!("failed to execute contacts command: {}", e))?;
+            if !output.status.success() {
+                return Err(format!(
+                    "contacts command exited with {}: {}",
+                    output.status,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ));
+            }
+            text.push_str(&String::from_utf8_lossy(&output.stdout));
+        }
+        Ok(crate::contacts::parse_contacts(&text))
+    }
 }
 
 /// Tunables for the built-in Bayesian spam classifier. The classifier scores
@@ -75,27 +351,236 @@ pub struct SpamConfig {
     pub min_training: u32,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortField {
+    Date,
+    From,
+    Subject,
+}
+
+impl SortField {
+    pub fn name(self) -> &'static str {
+        match self {
+            SortField::Date => "date",
+            SortField::From => "from",
+            SortField::Subject => "subject",
+        }
+    }
+
+    /// The field `o` cycles to after this one.
+    pub fn next(self) -> Self {
+        match self {
+            SortField::Date => SortField::From,
+            SortField::From => SortField::Subject,
+            SortField::Subject => SortField::Date,
+        }
+    }
+
+    /// Direction used when the field is first picked: newest first for
+    /// dates, A-Z for text.
+    pub fn default_descending(self) -> bool {
+        self == SortField::Date
+    }
+}
+
+/// Client-side email list order (`[ui].sort`, `o`/`O` in the list).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortOrder {
+    pub field: SortField,
+    pub descending: bool,
+}
+
+impl Default for SortOrder {
+    fn default() -> Self {
+        SortOrder {
+            field: SortField::Date,
+            descending: true,
+        }
+    }
+}
+
+impl SortOrder {
+    /// Parse a field name (`date`, `from`, `subject`) optionally followed by
+    /// `asc` or `desc`, e.g. `"from"` or `"date asc"`.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut parts = s.split_whitespace();
+        let field = match parts.next() {
+            Some("date") => SortField::Date,
+            Some("from") => SortField::From,
+            Some("subject") => SortField::Subject,
+            _ => {
+                return Err(format!(
+                    "invalid ui.sort '{}': expected date, from or subject",
+                    s
+                ))
+            }
+        };
+        let descending = match parts.next() {
+            None => field.default_descending(),
+            Some("asc") => false,
+            Some("desc") => true,
+            Some(other) => {
+                return Err(format!(
+                    "invalid ui.sort direction '{}': expected asc or desc",
+                    other
+                ))
+            }
+        };
+        if parts.next().is_some() {
+            return Err(format!("invalid ui.sort '{}': too many words", s));
+        }
+        Ok(SortOrder { field, descending })
+    }
+
+    pub fn label(&self) -> String {
+        let direction = if self.descending { "desc" } else { "asc" };
+        format!("{} {}", self.field.name(), direction)
+    }
+}
+
+/// How draft bodies are written (`[ui].compose_format`, or a draft's
+/// `Content-Type:` pseudo-header).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ComposeFormat {
+    /// Sent as-is as a single text/plain part.
+    #[default]
+    Plain,
+    /// Sent as multipart/alternative: the Markdown source as text/plain
+    /// plus a rendered text/html part.
+    Markdown,
+}
+
+impl ComposeFormat {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "plain" => Ok(ComposeFormat::Plain),
+            "markdown" => Ok(ComposeFormat::Markdown),
+            _ => Err(format!(
+                "invalid ui.compose_format '{}': expected plain or markdown",
+                s
+            )),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct UiConfig {
     pub editor: Option<String>,
+    /// Compose in the built-in editor view instead of spawning `editor`.
+    pub internal_editor: bool,
     pub browser: Option<String>,
     pub page_size: u32,
     pub scrolloff: usize,
     pub mouse: bool,
     pub sync_interval_secs: Option<u64>,
+    /// Also refresh every mailbox's counts on each background sync, not
+    /// just the open folder.
+    pub sync_all_mailboxes: bool,
+    /// Extra attempts for JMAP requests that fail with a connection error,
+    /// 429 or 5xx.
+    pub max_retries: u32,
+    /// JMAP connect and whole-request limits; `None` disables a limit.
+    pub connect_timeout_secs: Option<u64>,
+    pub request_timeout_secs: Option<u64>,
+    /// Email/get chunk requests kept in flight when loading many messages;
+    /// 0 or 1 fetches one chunk at a time.
+    pub fetch_concurrency: usize,
+    pub signature_above_quote: bool,
+    /// Prefix put before each quoted line in replies.
+    pub quote_prefix: String,
+    /// Line introducing quoted text in replies and forwarded text, with
+    /// `{date}`, `{from}` and `{subject}`; empty leaves it out.
+    pub reply_attribution: String,
+    pub forward_attribution: String,
+    /// Ask for y/n confirmation before permanently destroying mail.
+    pub confirm_destructive: bool,
+    /// Start the email list with the message preview pane open.
+    pub preview_pane: bool,
+    /// Open messages on their HTML body instead of plain text.
+    pub prefer_html: bool,
+    /// Draw inline images with kitty or sixel graphics when the terminal
+    /// supports them.
+    pub inline_images: bool,
+    /// Leave remote images out of HTML bodies and show a count instead.
+    pub block_remote_content: bool,
+    /// Mailbox list row template with `{name}`, `{unread}` and `{total}`.
+    pub mailbox_format: String,
+    /// Leave folders holding no mail out of the mailbox list.
+    pub hide_empty_mailboxes: bool,
+    /// Reopen the mailbox and cursor position left at the last exit.
+    pub restore_session: bool,
+    /// Length of the preview cached for messages the server sent without
+    /// one; 0 disables it.
+    pub preview_chars: usize,
+    /// Widest column count HTML bodies are laid out at, however wide the
+    /// terminal; 0 means the full terminal width.
+    pub max_render_width: usize,
+    /// Body format for drafts that don't set `Content-Type:` themselves.
+    pub compose_format: ComposeFormat,
+    /// Send plain-text bodies as RFC 3676 `format=flowed`.
+    pub format_flowed: bool,
+    /// Initial email list order.
+    pub sort: SortOrder,
+    pub date_format: DateFormat,
+    /// Command run for new-mail desktop notifications; `None` when
+    /// `[ui].notify` is off.
+    pub notify_command: Option<String>,
+    /// Where attachments and saved messages go, with `~` and `$VARS`
+    /// expanded; `None` uses the XDG download directory.
+    pub download_dir: Option<PathBuf>,
+}
+
+impl UiConfig {
+    pub fn http_options(&self) -> HttpOptions {
+        HttpOptions {
+            max_retries: self.max_retries,
+            connect_timeout: self.connect_timeout_secs.map(Duration::from_secs),
+            request_timeout: self.request_timeout_secs.map(Duration::from_secs),
+            fetch_concurrency: self.fetch_concurrency,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct RetentionPolicyConfig {
     pub name: String,
     pub folder: String,
-    pub days: u32,
+    /// Expire mail older than this many days. Exclusive with `keep_newest`.
+    pub days: Option<u32>,
+    /// Keep only the newest N messages in the folder and expire the rest.
+    pub keep_newest: Option<u32>,
+    /// Only expire mail that has been read (`$seen`).
+    pub only_read: bool,
+    /// Only expire mail that is not flagged (`$flagged`).
+    pub only_unflagged: bool,
+}
+
+impl RetentionPolicyConfig {
+    /// Exactly one of `days` (age) and `keep_newest` (count) must be set,
+    /// and it must be positive.
+    pub fn check(&self) -> Result<(), String> {
+        match (self.days, self.keep_newest) {
+            (Some(_), Some(_)) => Err("days and keep_newest are mutually exclusive".to_string()),
+            (None, None) => Err("missing days or keep_newest".to_string()),
+            (Some(0), None) => Err("days must be greater than 0".to_string()),
+            (None, Some(0)) => Err("keep_newest must be greater than 0".to_string()),
+            _ => Ok(()),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct MailConfig {
     pub archive_folder: String,
     pub deleted_folder: String,
+    /// Folder that holds snoozed mail until its wake time.
+    pub snooze_folder: String,
+    /// Folder that `!` / `mark_spam` move spam to (a name or role).
+    pub spam_folder: String,
+    /// Folder that unsent drafts are saved to.
+    pub drafts_folder: String,
+    /// Single-key shortcuts in the email list that move mail to a folder.
+    pub quick_move: BTreeMap<char, String>,
     pub archive_mailbox_id: Option<String>,
     pub deleted_mailbox_id: Option<String>,
     pub reply_from: Option<String>,
@@ -119,7 +604,7 @@ impl std::fmt::Display for ConfigError {
     }
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, Clone)]
 #[serde(deny_unknown_fields)]
 struct RawThemeConfig {
     #[serde(default)]
@@ -138,6 +623,85 @@ struct RawThemeConfig {
     status_fg: Option<String>,
     #[serde(default)]
     header_fg: Option<String>,
+    #[serde(default)]
+    quote_fg: Option<String>,
+    #[serde(default)]
+    link_fg: Option<String>,
+    #[serde(default)]
+    attachment_fg: Option<String>,
+    #[serde(default)]
+    auth_pass_fg: Option<String>,
+    #[serde(default)]
+    auth_fail_fg: Option<String>,
+    #[serde(default)]
+    color_mode: Option<String>,
+    #[serde(default, deserialize_with = "ordered_table")]
+    senders: Vec<(String, String)>,
+    #[serde(default, deserialize_with = "ordered_table")]
+    keywords: Vec<(String, String)>,
+}
+
+/// A table of string values as `(key, value)` pairs in file order, for
+/// tables whose entries are tried first to last.
+fn ordered_table<'de, D>(deserializer: D) -> Result<Vec<(String, String)>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    struct Visitor;
+
+    impl<'de> serde::de::Visitor<'de> for Visitor {
+        type Value = Vec<(String, String)>;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("a table of strings")
+        }
+
+        fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: serde::de::MapAccess<'de>,
+        {
+            let mut entries = Vec::new();
+            while let Some(entry) = map.next_entry()? {
+                entries.push(entry);
+            }
+            Ok(entries)
+        }
+    }
+
+    deserializer.deserialize_map(Visitor)
+}
+
+impl RawThemeConfig {
+    /// This (account) theme with unset entries taken from `base`.
+    fn over(self, base: &RawThemeConfig) -> RawThemeConfig {
+        let pick = |own: Option<String>, base: &Option<String>| own.or_else(|| base.clone());
+        RawThemeConfig {
+            bg: pick(self.bg, &base.bg),
+            fg: pick(self.fg, &base.fg),
+            bold_fg: pick(self.bold_fg, &base.bold_fg),
+            selection_bg: pick(self.selection_bg, &base.selection_bg),
+            selection_fg: pick(self.selection_fg, &base.selection_fg),
+            status_bg: pick(self.status_bg, &base.status_bg),
+            status_fg: pick(self.status_fg, &base.status_fg),
+            header_fg: pick(self.header_fg, &base.header_fg),
+            quote_fg: pick(self.quote_fg, &base.quote_fg),
+            link_fg: pick(self.link_fg, &base.link_fg),
+            attachment_fg: pick(self.attachment_fg, &base.attachment_fg),
+            auth_pass_fg: pick(self.auth_pass_fg, &base.auth_pass_fg),
+            auth_fail_fg: pick(self.auth_fail_fg, &base.auth_fail_fg),
+            color_mode: pick(self.color_mode, &base.color_mode),
+            senders: if self.senders.is_empty() {
+                base.senders.clone()
+            } else {
+                self.senders
+            },
+            keywords: if self.keywords.is_empty() {
+                base.keywords.clone()
+            } else {
+                self.keywords
+            },
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -156,7 +720,32 @@ struct RawConfig {
     #[serde(default)]
     spam: RawSpamConfig,
     #[serde(default)]
+    cache: RawCacheConfig,
+    #[serde(default)]
+    contacts: RawContactsConfig,
+    #[serde(default)]
     theme: RawThemeConfig,
+    #[serde(default)]
+    keybindings: BTreeMap<String, String>,
+    #[serde(default)]
+    saved_search: Vec<RawSavedSearch>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawCacheConfig {
+    /// 0 = unbounded.
+    #[serde(default)]
+    max_bytes: u64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawContactsConfig {
+    #[serde(default)]
+    file: Option<String>,
+    #[serde(default)]
+    command: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -189,6 +778,8 @@ struct RawUiConfig {
     #[serde(default)]
     editor: Option<String>,
     #[serde(default)]
+    internal_editor: bool,
+    #[serde(default)]
     browser: Option<String>,
     #[serde(default = "default_page_size")]
     page_size: u32,
@@ -198,17 +789,96 @@ struct RawUiConfig {
     mouse: bool,
     #[serde(default = "default_sync_interval_secs")]
     sync_interval_secs: u64,
+    #[serde(default)]
+    sync_all_mailboxes: bool,
+    #[serde(default = "default_max_retries")]
+    max_retries: u32,
+    #[serde(default = "default_connect_timeout_secs")]
+    connect_timeout_secs: u64,
+    #[serde(default = "default_request_timeout_secs")]
+    request_timeout_secs: u64,
+    #[serde(default = "default_fetch_concurrency")]
+    fetch_concurrency: usize,
+    #[serde(default)]
+    signature_above_quote: bool,
+    #[serde(default = "default_quote_prefix")]
+    quote_prefix: String,
+    #[serde(default = "default_reply_attribution")]
+    reply_attribution: String,
+    #[serde(default = "default_forward_attribution")]
+    forward_attribution: String,
+    #[serde(default = "default_confirm_destructive")]
+    confirm_destructive: bool,
+    #[serde(default)]
+    preview_pane: bool,
+    #[serde(default)]
+    prefer_html: bool,
+    #[serde(default)]
+    inline_images: bool,
+    #[serde(default = "default_block_remote_content")]
+    block_remote_content: bool,
+    #[serde(default = "default_mailbox_format")]
+    mailbox_format: String,
+    #[serde(default)]
+    hide_empty_mailboxes: bool,
+    #[serde(default = "default_restore_session")]
+    restore_session: bool,
+    #[serde(default = "default_preview_chars")]
+    preview_chars: usize,
+    #[serde(default = "default_max_render_width")]
+    max_render_width: usize,
+    #[serde(default = "default_compose_format")]
+    compose_format: String,
+    #[serde(default)]
+    format_flowed: bool,
+    #[serde(default = "default_sort")]
+    sort: String,
+    #[serde(default = "default_date_format")]
+    date_format: String,
+    #[serde(default)]
+    notify: bool,
+    #[serde(default = "default_notify_command")]
+    notify_command: String,
+    #[serde(default)]
+    download_dir: Option<String>,
 }
 
 impl Default for RawUiConfig {
     fn default() -> Self {
         Self {
             editor: None,
+            internal_editor: false,
             browser: None,
             page_size: default_page_size(),
             scrolloff: default_scrolloff(),
             mouse: default_mouse(),
             sync_interval_secs: default_sync_interval_secs(),
+            sync_all_mailboxes: false,
+            max_retries: default_max_retries(),
+            connect_timeout_secs: default_connect_timeout_secs(),
+            request_timeout_secs: default_request_timeout_secs(),
+            fetch_concurrency: default_fetch_concurrency(),
+            signature_above_quote: false,
+            quote_prefix: default_quote_prefix(),
+            reply_attribution: default_reply_attribution(),
+            forward_attribution: default_forward_attribution(),
+            confirm_destructive: default_confirm_destructive(),
+            preview_pane: false,
+            prefer_html: false,
+            inline_images: false,
+            block_remote_content: default_block_remote_content(),
+            mailbox_format: default_mailbox_format(),
+            hide_empty_mailboxes: false,
+            restore_session: default_restore_session(),
+            preview_chars: default_preview_chars(),
+            max_render_width: default_max_render_width(),
+            compose_format: default_compose_format(),
+            format_flowed: false,
+            sort: default_sort(),
+            date_format: default_date_format(),
+            notify: false,
+            notify_command: default_notify_command(),
+            download_dir: None,
         }
     }
 }
@@ -220,6 +890,14 @@ struct RawMailConfig {
     archive_folder: String,
     #[serde(default = "default_deleted_folder")]
     deleted_folder: String,
+    #[serde(default = "default_snooze_folder")]
+    snooze_folder: String,
+    #[serde(default = "default_spam_folder")]
+    spam_folder: String,
+    #[serde(default = "default_drafts_folder")]
+    drafts_folder: String,
+    #[serde(default)]
+    quick_move: BTreeMap<String, String>,
     #[serde(default)]
     archive_mailbox_id: Option<String>,
     #[serde(default)]
@@ -237,6 +915,10 @@ impl Default for RawMailConfig {
         Self {
             archive_folder: default_archive_folder(),
             deleted_folder: default_deleted_folder(),
+            snooze_folder: default_snooze_folder(),
+            spam_folder: default_spam_folder(),
+            drafts_folder: default_drafts_folder(),
+            quick_move: BTreeMap::new(),
             archive_mailbox_id: None,
             deleted_mailbox_id: None,
             reply_from: None,
@@ -252,6 +934,41 @@ struct RawAccountFields {
     well_known_url: Option<String>,
     username: Option<String>,
     password_command: Option<String>,
+    #[serde(default)]
+    auth: Option<String>,
+    #[serde(default)]
+    token_refresh_command: Option<String>,
+    #[serde(default)]
+    identities: Vec<RawIdentity>,
+    #[serde(default)]
+    signature: Option<String>,
+    #[serde(default)]
+    prefer_html: Option<bool>,
+    #[serde(default)]
+    color: Option<String>,
+    #[serde(default)]
+    theme: Option<RawThemeConfig>,
+    #[serde(default)]
+    authserv_ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawIdentity {
+    #[serde(default)]
+    name: String,
+    email: Option<String>,
+    #[serde(default)]
+    signature: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawSavedSearch {
+    name: Option<String>,
+    query: Option<String>,
+    #[serde(default)]
+    mailbox: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -259,6 +976,11 @@ struct RawAccountFields {
 struct RawRetentionPolicy {
     folder: Option<String>,
     days: Option<u32>,
+    keep_newest: Option<u32>,
+    #[serde(default)]
+    only_read: bool,
+    #[serde(default)]
+    only_unflagged: bool,
 }
 
 fn default_page_size() -> u32 {
@@ -277,6 +999,147 @@ fn default_sync_interval_secs() -> u64 {
     60
 }
 
+fn default_max_retries() -> u32 {
+    HttpOptions::default().max_retries
+}
+
+fn default_fetch_concurrency() -> usize {
+    HttpOptions::default().fetch_concurrency
+}
+
+fn default_connect_timeout_secs() -> u64 {
+    HttpOptions::default()
+        .connect_timeout
+        .map_or(0, |t| t.as_secs())
+}
+
+fn default_request_timeout_secs() -> u64 {
+    HttpOptions::default()
+        .request_timeout
+        .map_or(0, |t| t.as_secs())
+}
+
+fn default_confirm_destructive() -> bool {
+    true
+}
+
+fn default_preview_chars() -> usize {
+    crate::cache::DEFAULT_PREVIEW_CHARS
+}
+
+fn default_block_remote_content() -> bool {
+    true
+}
+
+fn default_max_render_width() -> usize {
+    120
+}
+
+fn default_restore_session() -> bool {
+    true
+}
+
+fn default_mailbox_format() -> String {
+    "{name} ({unread}/{total})".to_string()
+}
+
+const ATTRIBUTION_KEYS: &[&str] = &["date", "from", "subject"];
+
+pub const DEFAULT_QUOTE_PREFIX: &str = "> ";
+pub const DEFAULT_REPLY_ATTRIBUTION: &str = "On {date}, {from} wrote:";
+pub const DEFAULT_FORWARD_ATTRIBUTION: &str = "---------- Forwarded message ----------";
+
+fn default_quote_prefix() -> String {
+    DEFAULT_QUOTE_PREFIX.to_string()
+}
+
+fn default_reply_attribution() -> String {
+    DEFAULT_REPLY_ATTRIBUTION.to_string()
+}
+
+fn default_forward_attribution() -> String {
+    DEFAULT_FORWARD_ATTRIBUTION.to_string()
+}
+
+/// Reject `{...}` placeholders in the `option` template other than `keys`.
+fn parse_template(option: &str, format: String, keys: &[&str]) -> Result<String, String> {
+    let mut rest = format.as_str();
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            return Err(format!("unclosed '{{' in {} '{}'", option, format));
+        };
+        let key = &rest[start + 1..start + len];
+        if !keys.contains(&key) {
+            let expected: Vec<String> = keys.iter().map(|k| format!("{{{}}}", k)).collect();
+            return Err(format!(
+                "unknown placeholder '{{{}}}' in {} (expected {})",
+                key,
+                option,
+                expected.join(", ")
+            ));
+        }
+        rest = &rest[start + len + 1..];
+    }
+    Ok(format)
+}
+
+fn default_compose_format() -> String {
+    "plain".to_string()
+}
+
+fn default_sort() -> String {
+    "date".to_string()
+}
+
+fn default_date_format() -> String {
+    "iso".to_string()
+}
+
+fn default_notify_command() -> String {
+    "notify-send".to_string()
+}
+
+/// Expand a leading `~` and `$VAR` / `${VAR}` references in a path.
+/// Unset variables expand to nothing.
+pub fn expand_path(path: &str) -> PathBuf {
+    let (mut out, rest) = match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+            (std::env::var("HOME").unwrap_or_default(), rest)
+        }
+        _ => (String::new(), path),
+    };
+
+    let mut chars = rest.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            out.push(c);
+            continue;
+        }
+        let braced = chars.peek() == Some(&'{');
+        if braced {
+            chars.next();
+        }
+        let mut name = String::new();
+        while let Some(&n) = chars.peek() {
+            if n.is_ascii_alphanumeric() || n == '_' {
+                name.push(n);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        if braced && chars.peek() == Some(&'}') {
+            chars.next();
+        }
+        if name.is_empty() {
+            out.push('$');
+        } else {
+            out.push_str(&std::env::var(&name).unwrap_or_default());
+        }
+    }
+    PathBuf::from(out)
+}
+
 fn default_archive_folder() -> String {
     "archive".to_string()
 }
@@ -285,6 +1148,18 @@ fn default_deleted_folder() -> String {
     "trash".to_string()
 }
 
+fn default_snooze_folder() -> String {
+    "Snoozed".to_string()
+}
+
+fn default_spam_folder() -> String {
+    "junk".to_string()
+}
+
+fn default_drafts_folder() -> String {
+    "Drafts".to_string()
+}
+
 fn default_rules_mailbox_regex() -> String {
     "^INBOX$".to_string()
 }
@@ -332,21 +1207,84 @@ impl Config {
             ))
         })?;
 
+        let mut quick_move = BTreeMap::new();
+        for (key, folder) in raw.mail.quick_move {
+            let mut chars = key.chars();
+            let (Some(c), None) = (chars.next(), chars.next()) else {
+                return Err(ConfigError::Parse(format!(
+                    "mail.quick_move key '{}' must be a single character",
+                    key
+                )));
+            };
+            if folder.trim().is_empty() {
+                return Err(ConfigError::Parse(format!(
+                    "mail.quick_move '{}' has an empty folder",
+                    key
+                )));
+            }
+            quick_move.insert(c, folder);
+        }
+
+        let mut key_overrides = BTreeMap::new();
+        for (action, key) in raw.keybindings {
+            let mut chars = key.chars();
+            let (Some(c), None) = (chars.next(), chars.next()) else {
+                return Err(ConfigError::Parse(format!(
+                    "keybindings.{} must be a single character, got '{}'",
+                    action, key
+                )));
+            };
+            key_overrides.insert(action, c);
+        }
+        let keybindings = Keymap::from_overrides(key_overrides).map_err(ConfigError::Parse)?;
+        keybindings
+            .check_quick_move_keys(quick_move.keys().copied())
+            .map_err(ConfigError::Parse)?;
+
         let mut retention_policies = Vec::new();
         for (name, policy) in raw.retention {
             let folder = policy.folder.ok_or_else(|| {
                 ConfigError::Parse(format!("missing folder in [retention.{}]", name))
             })?;
-            let days = policy.days.ok_or_else(|| {
-                ConfigError::Parse(format!("missing days in [retention.{}]", name))
-            })?;
-            if days == 0 {
+            let policy = RetentionPolicyConfig {
+                name,
+                folder,
+                days: policy.days,
+                keep_newest: policy.keep_newest,
+                only_read: policy.only_read,
+                only_unflagged: policy.only_unflagged,
+            };
+            policy
+                .check()
+                .map_err(|e| ConfigError::Parse(format!("{} in [retention.{}]", e, policy.name)))?;
+            retention_policies.push(policy);
+        }
+
+        let mut saved_searches: Vec<SavedSearchConfig> = Vec::new();
+        for (i, search) in raw.saved_search.into_iter().enumerate() {
+            let name = search
+                .name
+                .filter(|n| !n.trim().is_empty())
+                .ok_or_else(|| {
+                    ConfigError::Parse(format!("missing name in [[saved_search]] #{}", i + 1))
+                })?;
+            let query = search
+                .query
+                .filter(|q| !q.trim().is_empty())
+                .ok_or_else(|| {
+                    ConfigError::Parse(format!("missing query in saved search '{}'", name))
+                })?;
+            if saved_searches.iter().any(|s| s.name == name) {
                 return Err(ConfigError::Parse(format!(
-                    "days must be greater than 0 in [retention.{}]",
+                    "duplicate saved search '{}'",
                     name
                 )));
             }
-            retention_policies.push(RetentionPolicyConfig { name, folder, days });
+            saved_searches.push(SavedSearchConfig {
+                name,
+                query,
+                mailbox: search.mailbox.filter(|m| !m.trim().is_empty()),
+            });
         }
 
         let mut accounts = Vec::new();
@@ -366,6 +1304,30 @@ impl Config {
                     account.password_command,
                     &format!("missing password_command in [account.{}]", account_name),
                 )?,
+                auth: account
+                    .auth
+                    .as_deref()
+                    .map(|a| AuthMode::parse(a, &format!("[account.{}]", account_name)))
+                    .transpose()?
+                    .unwrap_or_default(),
+                token_refresh_command: account.token_refresh_command,
+                identities: convert_identities(
+                    account.identities,
+                    &format!("[account.{}]", account_name),
+                )?,
+                signature: account.signature,
+                prefer_html: account.prefer_html,
+                color: resolve_color(&account.color, &format!("account.{}.color", account_name))?,
+                theme: account
+                    .theme
+                    .map(|t| {
+                        build_theme(
+                            &t.over(&raw.theme),
+                            &format!("account.{}.theme", account_name),
+                        )
+                    })
+                    .transpose()?,
+                authserv_ids: account.authserv_ids,
             });
         }
 
@@ -389,6 +1351,22 @@ impl Config {
                     jmap.password_command,
                     "missing password_command (in [jmap] or [account.NAME])",
                 )?,
+                auth: jmap
+                    .auth
+                    .as_deref()
+                    .map(|a| AuthMode::parse(a, "[jmap]"))
+                    .transpose()?
+                    .unwrap_or_default(),
+                token_refresh_command: jmap.token_refresh_command,
+                identities: convert_identities(jmap.identities, "[jmap]")?,
+                signature: jmap.signature,
+                prefer_html: jmap.prefer_html,
+                color: resolve_color(&jmap.color, "jmap.color")?,
+                theme: jmap
+                    .theme
+                    .map(|t| build_theme(&t.over(&raw.theme), "jmap.theme"))
+                    .transpose()?,
+                authserv_ids: jmap.authserv_ids,
             });
         }
 
@@ -410,22 +1388,16 @@ impl Config {
             )));
         }
 
-        let theme = Theme {
-            bg: resolve_color(&raw.theme.bg, "bg")?,
-            fg: resolve_color(&raw.theme.fg, "fg")?,
-            bold_fg: resolve_color(&raw.theme.bold_fg, "bold_fg")?,
-            selection_bg: resolve_color(&raw.theme.selection_bg, "selection_bg")?,
-            selection_fg: resolve_color(&raw.theme.selection_fg, "selection_fg")?,
-            status_bg: resolve_color(&raw.theme.status_bg, "status_bg")?,
-            status_fg: resolve_color(&raw.theme.status_fg, "status_fg")?,
-            header_fg: resolve_color(&raw.theme.header_fg, "header_fg")?,
-        };
+        let theme = build_theme(&raw.theme, "theme")?;
 
         Ok(Config {
             accounts,
             theme,
+            keybindings,
+            saved_searches,
             ui: UiConfig {
                 editor: raw.ui.editor,
+                internal_editor: raw.ui.internal_editor,
                 browser: raw.ui.browser,
                 page_size: raw.ui.page_size,
                 scrolloff: raw.ui.scrolloff,
@@ -435,10 +1407,65 @@ impl Config {
                 } else {
                     Some(raw.ui.sync_interval_secs)
                 },
+                sync_all_mailboxes: raw.ui.sync_all_mailboxes,
+                max_retries: raw.ui.max_retries,
+                connect_timeout_secs: (raw.ui.connect_timeout_secs > 0)
+                    .then_some(raw.ui.connect_timeout_secs),
+                request_timeout_secs: (raw.ui.request_timeout_secs > 0)
+                    .then_some(raw.ui.request_timeout_secs),
+                fetch_concurrency: raw.ui.fetch_concurrency,
+                signature_above_quote: raw.ui.signature_above_quote,
+                quote_prefix: raw.ui.quote_prefix,
+                reply_attribution: parse_template(
+                    "reply_attribution",
+                    raw.ui.reply_attribution,
+                    ATTRIBUTION_KEYS,
+                )
+                .map_err(ConfigError::Parse)?,
+                forward_attribution: parse_template(
+                    "forward_attribution",
+                    raw.ui.forward_attribution,
+                    ATTRIBUTION_KEYS,
+                )
+                .map_err(ConfigError::Parse)?,
+                confirm_destructive: raw.ui.confirm_destructive,
+                preview_pane: raw.ui.preview_pane,
+                prefer_html: raw.ui.prefer_html,
+                inline_images: raw.ui.inline_images,
+                block_remote_content: raw.ui.block_remote_content,
+                mailbox_format: parse_template(
+                    "mailbox_format",
+                    raw.ui.mailbox_format,
+                    &["name", "unread", "total"],
+                )
+                .map_err(ConfigError::Parse)?,
+                hide_empty_mailboxes: raw.ui.hide_empty_mailboxes,
+                restore_session: raw.ui.restore_session,
+                preview_chars: raw.ui.preview_chars,
+                max_render_width: raw.ui.max_render_width,
+                compose_format: ComposeFormat::parse(&raw.ui.compose_format)
+                    .map_err(ConfigError::Parse)?,
+                format_flowed: raw.ui.format_flowed,
+                sort: SortOrder::parse(&raw.ui.sort).map_err(ConfigError::Parse)?,
+                date_format: DateFormat::parse(&raw.ui.date_format).map_err(ConfigError::Parse)?,
+                notify_command: if raw.ui.notify {
+                    Some(raw.ui.notify_command)
+                } else {
+                    None
+                },
+                download_dir: raw
+                    .ui
+                    .download_dir
+                    .filter(|d| !d.trim().is_empty())
+                    .map(|d| expand_path(&d)),
             },
             mail: MailConfig {
                 archive_folder: raw.mail.archive_folder,
                 deleted_folder: raw.mail.deleted_folder,
+                snooze_folder: raw.mail.snooze_folder,
+                spam_folder: raw.mail.spam_folder,
+                drafts_folder: raw.mail.drafts_folder,
+                quick_move,
                 archive_mailbox_id: raw.mail.archive_mailbox_id,
                 deleted_mailbox_id: raw.mail.deleted_mailbox_id,
                 reply_from: raw.mail.reply_from,
@@ -452,6 +1479,17 @@ impl Config {
                 ham_threshold: raw.spam.ham_threshold,
                 min_training: raw.spam.min_training,
             },
+            cache: CacheConfig {
+                max_bytes: (raw.cache.max_bytes > 0).then_some(raw.cache.max_bytes),
+            },
+            contacts: ContactsConfig {
+                file: raw
+                    .contacts
+                    .file
+                    .filter(|f| !f.trim().is_empty())
+                    .map(|f| expand_path(&f)),
+                command: raw.contacts.command.filter(|c| !c.trim().is_empty()),
+            },
         })
     }
 }
@@ -460,6 +1498,25 @@ fn require_field(value: Option<String>, err: &str) -> Result<String, ConfigError
     value.ok_or_else(|| ConfigError::Parse(err.to_string()))
 }
 
+fn convert_identities(
+    raw: Vec<RawIdentity>,
+    section: &str,
+) -> Result<Vec<IdentityConfig>, ConfigError> {
+    raw.into_iter()
+        .map(|identity| {
+            let email = require_field(
+                identity.email,
+                &format!("missing email in {} identities", section),
+            )?;
+            Ok(IdentityConfig {
+                name: identity.name,
+                email,
+                signature: identity.signature,
+            })
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -575,7 +1632,10 @@ password_command = "pass show email/work.com"
         assert_eq!(config.accounts[0].name, "personal");
         assert_eq!(config.accounts[1].name, "work");
         assert_eq!(config.ui.editor.as_deref(), Some("nvim"));
+        assert!(!config.ui.internal_editor);
         assert_eq!(config.ui.scrolloff, 2);
+        let config = Config::parse(&jmap_config("[ui]\ninternal_editor = true")).unwrap();
+        assert!(config.ui.internal_editor);
     }
 
     #[test]
@@ -584,10 +1644,394 @@ password_command = "pass show email/work.com"
         assert_eq!(config.ui.page_size, 500);
         assert_eq!(config.ui.scrolloff, 1);
         assert_eq!(config.ui.sync_interval_secs, None);
+        assert!(config.ui.confirm_destructive);
+        assert!(!config.ui.sync_all_mailboxes);
+        assert_eq!(config.ui.max_retries, 3);
+        assert_eq!(config.ui.connect_timeout_secs, Some(10));
+        assert_eq!(config.ui.request_timeout_secs, Some(120));
+        assert_eq!(config.ui.fetch_concurrency, 4);
+
+        assert!(!config.ui.preview_pane);
+        assert!(!config.ui.inline_images);
+        assert!(config.ui.block_remote_content);
+
+        let config = Config::parse(&jmap_config(
+            "[ui]\nconfirm_destructive = false\npreview_pane = true\ninline_images = true\nblock_remote_content = false\nsync_all_mailboxes = true\nmax_retries = 0\nrequest_timeout_secs = 0\nfetch_concurrency = 1",
+        ))
+        .unwrap();
+        assert_eq!(config.ui.http_options().fetch_concurrency, 1);
+        assert!(!config.ui.confirm_destructive);
+        assert!(config.ui.preview_pane);
+        assert!(config.ui.inline_images);
+        assert!(!config.ui.block_remote_content);
+        assert!(config.ui.sync_all_mailboxes);
+        assert_eq!(config.ui.max_retries, 0);
+        assert_eq!(config.ui.request_timeout_secs, None);
     }
 
     #[test]
-    fn test_unknown_section_or_key_errors() {
+    fn test_sort_order() {
+        let config = Config::parse(&jmap_config("")).unwrap();
+        assert_eq!(config.ui.sort, SortOrder::default());
+
+        let config = Config::parse(&jmap_config("[ui]\nsort = \"from\"")).unwrap();
+        assert_eq!(
+            config.ui.sort,
+            SortOrder {
+                field: SortField::From,
+                descending: false
+            }
+        );
+
+        let config = Config::parse(&jmap_config("[ui]\nsort = \"date asc\"")).unwrap();
+        assert!(!config.ui.sort.descending);
+
+        assert!(Config::parse(&jmap_config("[ui]\nsort = \"size\"")).is_err());
+        assert!(Config::parse(&jmap_config("[ui]\nsort = \"date up\"")).is_err());
+    }
+
+    #[test]
+    fn test_date_format() {
+        let config = Config::parse(&jmap_config("")).unwrap();
+        assert_eq!(config.ui.date_format, DateFormat::Iso);
+
+        let config = Config::parse(&jmap_config("[ui]\ndate_format = \"relative\"")).unwrap();
+        assert_eq!(config.ui.date_format, DateFormat::Relative);
+
+        let config = Config::parse(&jmap_config("[ui]\ndate_format = \"%d %b\"")).unwrap();
+        assert_eq!(
+            config.ui.date_format,
+            DateFormat::Pattern("%d %b".to_string())
+        );
+
+        assert!(Config::parse(&jmap_config("[ui]\ndate_format = \"short\"")).is_err());
+    }
+
+    #[test]
+    fn test_download_dir_expands_home_and_vars() {
+        let config = Config::parse(&jmap_config("")).unwrap();
+        assert_eq!(config.ui.download_dir, None);
+
+        let home = std::env::var("HOME").unwrap_or_default();
+        let config = Config::parse(&jmap_config("[ui]\ndownload_dir = \"~/Mail files\"")).unwrap();
+        assert_eq!(
+            config.ui.download_dir,
+            Some(PathBuf::from(format!("{}/Mail files", home)))
+        );
+
+        assert_eq!(
+            expand_path("${HOME}/x/$HOME"),
+            PathBuf::from(format!("{}/x/{}", home, home))
+        );
+        assert_eq!(expand_path("~user/a"), PathBuf::from("~user/a"));
+        assert_eq!(expand_path("/tmp/$"), PathBuf::from("/tmp/$"));
+        assert_eq!(
+            expand_path("/tmp/$TMC_SURELY_UNSET_VAR/a"),
+            PathBuf::from("/tmp//a")
+        );
+    }
+
+    #[test]
+    fn test_account_auth_mode() {
+        let config = Config::parse(&jmap_config("")).unwrap();
+        assert_eq!(config.accounts[0].auth, AuthMode::Basic);
+        assert_eq!(config.accounts[0].token_refresh_command, None);
+
+        let config = Config::parse(
+            r#"
+[account.oauth]
+well_known_url = "https://example.com/.well-known/jmap"
+username = "me@example.com"
+password_command = "oauth-token get"
+auth = "oauth2"
+token_refresh_command = "oauth-token refresh"
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.accounts[0].auth, AuthMode::OAuth2);
+        assert_eq!(
+            config.accounts[0].token_refresh_command.as_deref(),
+            Some("oauth-token refresh")
+        );
+
+        let err = Config::parse(
+            r#"
+[account.bad]
+well_known_url = "https://example.com/.well-known/jmap"
+username = "me@example.com"
+password_command = "echo pw"
+auth = "kerberos"
+"#,
+        )
+        .unwrap_err();
+        match err {
+            ConfigError::Parse(msg) => assert!(msg.contains("[account.bad]"), "got: {}", msg),
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_preview_chars() {
+        let config = Config::parse(&jmap_config("")).unwrap();
+        assert_eq!(config.ui.preview_chars, 200);
+        let config = Config::parse(&jmap_config("[ui]\npreview_chars = 0")).unwrap();
+        assert_eq!(config.ui.preview_chars, 0);
+    }
+
+    #[test]
+    fn test_max_render_width() {
+        let config = Config::parse(&jmap_config("")).unwrap();
+        assert_eq!(config.ui.max_render_width, 120);
+        let config = Config::parse(&jmap_config("[ui]\nmax_render_width = 0")).unwrap();
+        assert_eq!(config.ui.max_render_width, 0);
+    }
+
+    #[test]
+    fn test_quote_style() {
+        let config = Config::parse(&jmap_config("")).unwrap();
+        let ui = &config.ui;
+        assert_eq!(ui.quote_prefix, "> ");
+        assert_eq!(ui.reply_attribution, "On {date}, {from} wrote:");
+        assert_eq!(
+            ui.forward_attribution,
+            "---------- Forwarded message ----------"
+        );
+
+        let config = Config::parse(&jmap_config(
+            "[ui]\nquote_prefix = \"| \"\nreply_attribution = \"{from} ({date}):\"\nforward_attribution = \"\"",
+        ))
+        .unwrap();
+        let ui = &config.ui;
+        assert_eq!(ui.quote_prefix, "| ");
+        assert_eq!(ui.reply_attribution, "{from} ({date}):");
+        assert_eq!(ui.forward_attribution, "");
+
+        let err = Config::parse(&jmap_config(
+            "[ui]\nreply_attribution = \"{sender} wrote:\"",
+        ))
+        .unwrap_err();
+        assert!(matches!(err, ConfigError::Parse(msg) if msg.contains("reply_attribution")));
+    }
+
+    #[test]
+    fn test_compose_format() {
+        let config = Config::parse(&jmap_config("")).unwrap();
+        assert_eq!(config.ui.compose_format, ComposeFormat::Plain);
+        assert!(!config.ui.format_flowed);
+        let config = Config::parse(&jmap_config("[ui]\ncompose_format = \"markdown\"")).unwrap();
+        assert_eq!(config.ui.compose_format, ComposeFormat::Markdown);
+        let config = Config::parse(&jmap_config("[ui]\nformat_flowed = true")).unwrap();
+        assert!(config.ui.format_flowed);
+        assert!(Config::parse(&jmap_config("[ui]\ncompose_format = \"html\"")).is_err());
+    }
+
+    #[test]
+    fn test_mailbox_format_and_hide_empty() {
+        let config = Config::parse(&jmap_config("")).unwrap();
+        assert_eq!(config.ui.mailbox_format, "{name} ({unread}/{total})");
+        assert!(!config.ui.hide_empty_mailboxes);
+
+        let config = Config::parse(&jmap_config(
+            "[ui]\nmailbox_format = \"{unread} {name}\"\nhide_empty_mailboxes = true",
+        ))
+        .unwrap();
+        assert_eq!(config.ui.mailbox_format, "{unread} {name}");
+        assert!(config.ui.hide_empty_mailboxes);
+
+        assert!(Config::parse(&jmap_config("[ui]\nmailbox_format = \"{count}\"")).is_err());
+        assert!(Config::parse(&jmap_config("[ui]\nmailbox_format = \"{name\"")).is_err());
+    }
+
+    #[test]
+    fn test_restore_session_default_and_opt_out() {
+        let config = Config::parse(&jmap_config("")).unwrap();
+        assert!(config.ui.restore_session);
+
+        let config = Config::parse(&jmap_config("[ui]\nrestore_session = false")).unwrap();
+        assert!(!config.ui.restore_session);
+    }
+
+    #[test]
+    fn test_prefer_html_default_and_account_override() {
+        let config = Config::parse(&jmap_config("")).unwrap();
+        assert!(!config.ui.prefer_html);
+        assert_eq!(config.accounts[0].prefer_html, None);
+
+        let config = Config::parse(
+            r#"
+[ui]
+prefer_html = true
+
+[account.work]
+well_known_url = "https://example.com/.well-known/jmap"
+username = "me@example.com"
+password_command = "pass"
+
+[account.news]
+well_known_url = "https://example.com/.well-known/jmap"
+username = "news@example.com"
+password_command = "pass"
+prefer_html = false
+"#,
+        )
+        .unwrap();
+        assert!(config.ui.prefer_html);
+        let news = config.accounts.iter().find(|a| a.name == "news").unwrap();
+        let work = config.accounts.iter().find(|a| a.name == "work").unwrap();
+        assert_eq!(news.prefer_html, Some(false));
+        assert_eq!(work.prefer_html, None);
+    }
+
+    #[test]
+    fn test_trusted_authserv_ids_add_account_domains() {
+        let config = Config::parse(&jmap_config("")).unwrap();
+        assert_eq!(
+            config.accounts[0].trusted_authserv_ids(),
+            vec!["example.com".to_string()]
+        );
+
+        let config = Config::parse(
+            r#"
+[account.work]
+well_known_url = "https://api.mailhost.example:8443/.well-known/jmap"
+username = "Me@Work.Example"
+password_command = "pass"
+authserv_ids = ["MX.Relay.Example"]
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.accounts[0].trusted_authserv_ids(),
+            vec![
+                "mx.relay.example".to_string(),
+                "work.example".to_string(),
+                "mailhost.example".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_account_theme_overrides_global() {
+        let config = Config::parse(
+            r##"
+[theme]
+fg = "#839496"
+status_bg = "#586e75"
+
+[account.work]
+well_known_url = "https://example.com/.well-known/jmap"
+username = "me@example.com"
+password_command = "pass"
+
+[account.work.theme]
+status_bg = "#dc322f"
+
+[account.home]
+well_known_url = "https://example.com/.well-known/jmap"
+username = "home@example.com"
+password_command = "pass"
+"##,
+        )
+        .unwrap();
+        let work = config.accounts.iter().find(|a| a.name == "work").unwrap();
+        let home = config.accounts.iter().find(|a| a.name == "home").unwrap();
+        let work_theme = work.theme.as_ref().unwrap();
+        assert_eq!(work_theme.status_bg, Some((0xdc, 0x32, 0x2f)));
+        assert_eq!(work_theme.fg, Some((0x83, 0x94, 0x96)));
+        assert!(home.theme.is_none());
+        assert_eq!(config.theme.status_bg, Some((0x58, 0x6e, 0x75)));
+
+        let err = Config::parse(
+            r#"
+[account.work]
+well_known_url = "https://example.com/.well-known/jmap"
+username = "me@example.com"
+password_command = "pass"
+
+[account.work.theme]
+bg = "red"
+"#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("account.work.theme.bg"));
+    }
+
+    #[test]
+    fn test_account_color() {
+        let config = Config::parse(&jmap_config("")).unwrap();
+        assert_eq!(config.accounts[0].color, None);
+
+        let account = |color: &str| {
+            format!(
+                "[account.work]\nwell_known_url = \"https://example.com/.well-known/jmap\"\nusername = \"me@example.com\"\npassword_command = \"pass\"\ncolor = \"{}\"\n",
+                color
+            )
+        };
+        let config = Config::parse(&account("#dc322f")).unwrap();
+        assert_eq!(config.accounts[0].color, Some((0xdc, 0x32, 0x2f)));
+
+        let err = Config::parse(&account("red")).unwrap_err();
+        assert!(
+            err.to_string().contains("account.work.color"),
+            "got: {}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_contacts_file_and_command() {
+        let config = Config::parse(&jmap_config("")).unwrap();
+        assert!(config.contacts.load().unwrap().is_empty());
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("contacts.txt");
+        fs::write(&file, "Ann <ann@example.com>\n").unwrap();
+        let config = Config::parse(&jmap_config(&format!(
+            "[contacts]\nfile = \"{}\"\ncommand = \"echo bob@example.com\"",
+            file.display()
+        )))
+        .unwrap();
+        let contacts = config.contacts.load().unwrap();
+        assert_eq!(contacts.len(), 2);
+        assert_eq!(contacts[0].to_string(), "Ann <ann@example.com>");
+        assert_eq!(contacts[1].email.as_deref(), Some("bob@example.com"));
+
+        let config = Config::parse(&jmap_config("[contacts]\ncommand = \"exit 3\"")).unwrap();
+        assert!(config.contacts.load().unwrap_err().contains("exited"));
+        assert!(Config::parse(&jmap_config("[contacts]\npath = \"x\"")).is_err());
+    }
+
+    #[test]
+    fn test_cache_max_bytes() {
+        let config = Config::parse(&jmap_config("")).unwrap();
+        assert_eq!(config.cache.max_bytes, None);
+
+        let config = Config::parse(&jmap_config("[cache]\nmax_bytes = 0")).unwrap();
+        assert_eq!(config.cache.max_bytes, None);
+
+        let config = Config::parse(&jmap_config("[cache]\nmax_bytes = 104857600")).unwrap();
+        assert_eq!(config.cache.max_bytes, Some(104_857_600));
+
+        assert!(Config::parse(&jmap_config("[cache]\nmax_size = 1")).is_err());
+    }
+
+    #[test]
+    fn test_notify_command() {
+        let config = Config::parse(&jmap_config("")).unwrap();
+        assert_eq!(config.ui.notify_command, None);
+
+        let config = Config::parse(&jmap_config("[ui]\nnotify = true")).unwrap();
+        assert_eq!(config.ui.notify_command.as_deref(), Some("notify-send"));
+
+        let config = Config::parse(&jmap_config(
+            "[ui]\nnotify = true\nnotify_command = \"dunstify -a tmc\"",
+        ))
+        .unwrap();
+        assert_eq!(config.ui.notify_command.as_deref(), Some("dunstify -a tmc"));
+    }
+
+    #[test]
+    fn test_unknown_section_or_key_errors() {
         let err = Config::parse(
             r#"
 [bogus]
@@ -647,6 +2091,8 @@ days = 365
 [retention.trash]
 folder = "Trash"
 days = 30
+only_read = true
+only_unflagged = true
 
 [jmap]
 well_known_url = "https://mx.example.com/.well-known/jmap"
@@ -658,7 +2104,137 @@ password_command = "pass show email/example.com"
 
         assert_eq!(config.mail.retention_policies.len(), 2);
         assert_eq!(config.mail.retention_policies[0].name, "archive");
-        assert_eq!(config.mail.retention_policies[1].days, 30);
+        assert_eq!(config.mail.retention_policies[1].days, Some(30));
+        assert!(!config.mail.retention_policies[0].only_read);
+        assert!(config.mail.retention_policies[1].only_read);
+        assert!(config.mail.retention_policies[1].only_unflagged);
+    }
+
+    #[test]
+    fn test_retention_keep_newest_excludes_days() {
+        let parse = |body: &str| {
+            Config::parse(&format!(
+                "{}\n[retention.logs]\nfolder = \"Logs\"\n{}\n",
+                jmap_config(""),
+                body
+            ))
+        };
+        let config = parse("keep_newest = 200").unwrap();
+        assert_eq!(config.mail.retention_policies[0].keep_newest, Some(200));
+        assert_eq!(config.mail.retention_policies[0].days, None);
+
+        let err = parse("keep_newest = 200\ndays = 30")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("mutually exclusive"), "{}", err);
+        let err = parse("").unwrap_err().to_string();
+        assert!(err.contains("missing days or keep_newest"), "{}", err);
+        assert!(parse("keep_newest = 0").is_err());
+    }
+
+    #[test]
+    fn test_saved_searches() {
+        let config = Config::parse(&format!(
+            "{}{}",
+            jmap_config(""),
+            r#"
+[[saved_search]]
+name = "Unread from boss"
+query = "from:boss@example.com"
+mailbox = "Inbox"
+
+[[saved_search]]
+name = "Invoices"
+query = "invoice"
+"#
+        ))
+        .unwrap();
+        assert_eq!(
+            config.saved_searches,
+            [
+                SavedSearchConfig {
+                    name: "Unread from boss".to_string(),
+                    query: "from:boss@example.com".to_string(),
+                    mailbox: Some("Inbox".to_string()),
+                },
+                SavedSearchConfig {
+                    name: "Invoices".to_string(),
+                    query: "invoice".to_string(),
+                    mailbox: None,
+                },
+            ]
+        );
+
+        let err = Config::parse(&format!(
+            "{}\n[[saved_search]]\nname = \"x\"\n",
+            jmap_config("")
+        ))
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("missing query in saved search 'x'"));
+    }
+
+    #[test]
+    fn test_quick_move() {
+        let config = Config::parse(
+            r#"
+[mail.quick_move]
+x = "INBOX/Todo"
+X = "Receipts"
+
+[jmap]
+well_known_url = "https://mx.example.com/.well-known/jmap"
+username = "user@example.com"
+password_command = "pass show email/example.com"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.mail.quick_move.len(), 2);
+        assert_eq!(config.mail.quick_move[&'x'], "INBOX/Todo");
+        assert_eq!(config.mail.quick_move[&'X'], "Receipts");
+
+        let err = Config::parse(
+            r#"
+[mail]
+quick_move = { tt = "Todo" }
+
+[jmap]
+well_known_url = "https://mx.example.com/.well-known/jmap"
+username = "user@example.com"
+password_command = "pass show email/example.com"
+"#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("single character"));
+
+        let err = Config::parse(&jmap_config("[mail.quick_move]\nR = \"Receipts\"\n")).unwrap_err();
+        assert!(err.to_string().contains("conflicts with 'reply_all'"));
+
+        let err = Config::parse(&jmap_config(
+            "[mail.quick_move]\nx = \"Todo\"\n\n[keybindings]\narchive = \"x\"\n",
+        ))
+        .unwrap_err();
+        assert!(err.to_string().contains("bound to 'archive'"));
+    }
+
+    #[test]
+    fn test_keybindings_section() {
+        let err = Config::parse(&jmap_config("[keybindings]\nopen = \"x\"\n")).unwrap_err();
+        assert!(err.to_string().contains("unknown keybinding action"));
+
+        let config = Config::parse(&jmap_config("[keybindings]\narchive = \"x\"\n")).unwrap();
+        assert_eq!(
+            config.keybindings.key_for("email_list", "archive"),
+            Some('x')
+        );
+        assert_eq!(config.keybindings.translate('x'), Some('a'));
+
+        let err = Config::parse(&jmap_config("[keybindings]\narchive = \"k\"\n")).unwrap_err();
+        assert!(err.to_string().contains("navigation key"));
+        let err = Config::parse(&jmap_config("[keybindings]\narchive = \"xy\"\n")).unwrap_err();
+        assert!(err.to_string().contains("single character"));
     }
 
     #[test]
@@ -698,6 +2274,43 @@ password_command = "pass show email/example.com"
         assert!(config.theme.status_bg.is_none());
         assert!(config.theme.status_fg.is_none());
         assert!(config.theme.header_fg.is_none());
+        assert!(config.theme.quote_fg.is_none());
+        assert!(config.theme.link_fg.is_none());
+        assert!(config.theme.attachment_fg.is_none());
+    }
+
+    #[test]
+    fn test_theme_color_mode() {
+        assert_eq!(ColorMode::detect(Some("truecolor")), ColorMode::TrueColor);
+        assert_eq!(ColorMode::detect(Some("24bit")), ColorMode::TrueColor);
+        assert_eq!(ColorMode::detect(Some("")), ColorMode::TrueColor);
+        assert_eq!(ColorMode::detect(None), ColorMode::TrueColor);
+        assert_eq!(ColorMode::detect(Some("rxvt-xpm")), ColorMode::Ansi256);
+        assert!(Config::parse(&jmap_config(""))
+            .unwrap()
+            .theme
+            .color_mode
+            .is_none());
+
+        let config = Config::parse(&jmap_config(
+            r##"[theme]
+color_mode = "16"
+quote_fg = "#586e75"
+link_fg = "#268bd2"
+attachment_fg = "#b58900"
+auth_fail_fg = "#dc322f"
+"##,
+        ))
+        .unwrap();
+        assert_eq!(config.theme.color_mode, Some(ColorMode::Ansi16));
+        assert_eq!(config.theme.quote_fg, Some((0x58, 0x6e, 0x75)));
+        assert_eq!(config.theme.link_fg, Some((0x26, 0x8b, 0xd2)));
+        assert_eq!(config.theme.attachment_fg, Some((0xb5, 0x89, 0x00)));
+        assert_eq!(config.theme.auth_fail_fg, Some((0xdc, 0x32, 0x2f)));
+        assert!(config.theme.auth_pass_fg.is_none());
+
+        let err = Config::parse(&jmap_config("[theme]\ncolor_mode = \"8\"\n")).unwrap_err();
+        assert!(err.to_string().contains("color_mode"));
     }
 
     #[test]
@@ -725,6 +2338,52 @@ header_fg = "#268bd2"
         assert_eq!(config.theme.header_fg, Some((0x26, 0x8b, 0xd2)));
     }
 
+    #[test]
+    fn test_theme_row_colors() {
+        let config = Config::parse(&jmap_config(
+            r##"[theme.senders]
+"@lists\\.example\\.org" = "#268bd2"
+"(?i)^boss" = "#dc322f"
+
+[theme.keywords]
+"$label1" = "#859900"
+"##,
+        ))
+        .unwrap();
+        let theme = &config.theme;
+        let none = |_: &str| false;
+        assert_eq!(
+            theme.row_fg("Dev <dev@lists.example.org>", none),
+            Some((0x26, 0x8b, 0xd2))
+        );
+        assert_eq!(
+            theme.row_fg("Boss <b@work.com>", none),
+            Some((0xdc, 0x32, 0x2f))
+        );
+        assert_eq!(theme.row_fg("friend@example.com", none), None);
+        // Both patterns match; the one listed first wins even though it
+        // sorts after the other.
+        assert_eq!(
+            theme.row_fg("Boss <boss@lists.example.org>", none),
+            Some((0x26, 0x8b, 0xd2))
+        );
+        assert_eq!(
+            theme.row_fg("Boss <b@work.com>", |k| k == "$label1"),
+            Some((0x85, 0x99, 0x00))
+        );
+
+        let err = Config::parse(&jmap_config(
+            "[theme.senders]
+\"(\" = \"#000000\"",
+        ))
+        .unwrap_err();
+        match err {
+            ConfigError::Parse(msg) => assert!(msg.contains("theme.senders"), "got: {}", msg),
+            other => panic!("unexpected error: {:?}", other),
+        }
+        assert!(Config::parse(&jmap_config("[theme.keywords]\nx = \"red\"")).is_err());
+    }
+
     #[test]
     fn test_theme_partial_colors() {
         let config = Config::parse(&jmap_config(
@@ -780,4 +2439,70 @@ password_command = "pass show email/example.com"
             Some("Example User <user@example.com>")
         );
     }
+
+    #[test]
+    fn test_account_identities() {
+        let config = Config::parse(
+            r#"
+[account.work]
+well_known_url = "https://mx.example.com/.well-known/jmap"
+username = "me@example.com"
+password_command = "echo pw"
+
+[[account.work.identities]]
+name = "Me"
+email = "me@example.com"
+signature = "Cheers"
+
+[[account.work.identities]]
+email = "sales@example.com"
+"#,
+        )
+        .unwrap();
+        let ids = &config.accounts[0].identities;
+        assert_eq!(ids.len(), 2);
+        assert_eq!(ids[0].display_address(), "Me <me@example.com>");
+        assert_eq!(ids[0].signature.as_deref(), Some("Cheers"));
+        assert_eq!(ids[1].display_address(), "sales@example.com");
+        assert!(ids[1].signature.is_none());
+        assert!(config.accounts[0].signature.is_none());
+        assert!(!config.ui.signature_above_quote);
+
+        let err = Config::parse(
+            r#"
+[jmap]
+well_known_url = "https://mx.example.com/.well-known/jmap"
+username = "me@example.com"
+password_command = "echo pw"
+
+[[jmap.identities]]
+name = "No Address"
+"#,
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("missing email in [jmap] identities"));
+    }
+
+    #[test]
+    fn test_account_signature_and_placement() {
+        let config = Config::parse(&jmap_config("[ui]\nsignature_above_quote = true\n")).unwrap();
+        assert!(config.ui.signature_above_quote);
+
+        let config = Config::parse(
+            r#"
+[account.home]
+well_known_url = "https://mx.example.com/.well-known/jmap"
+username = "me@example.com"
+password_command = "echo pw"
+signature = "Sent from tmc"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.accounts[0].signature.as_deref(),
+            Some("Sent from tmc")
+        );
+    }
 }
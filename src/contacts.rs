@@ -0,0 +1,163 @@
+//! Address completion: the `[contacts]` address book merged with addresses
+//! counted from cached mail (`Cache::seen_addresses`).
+
+use crate::cache::SeenAddress;
+use crate::jmap::types::EmailAddress;
+use std::collections::HashMap;
+
+/// One completion candidate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub name: Option<String>,
+    pub email: String,
+    /// Cached emails the address appeared in; 0 for address-book-only entries.
+    pub count: u32,
+    /// Whether it came from the `[contacts]` address book.
+    pub contact: bool,
+}
+
+impl Suggestion {
+    /// `Name <email>`, ready for a `To:` line.
+    pub fn display(&self) -> String {
+        EmailAddress {
+            name: self.name.clone(),
+            email: Some(self.email.clone()),
+        }
+        .to_string()
+    }
+}
+
+/// Parse address book text: one `Name <email>` (or bare address) per line.
+/// Blank lines and `#` comments are skipped.
+pub fn parse_contacts(text: &str) -> Vec<EmailAddress> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .flat_map(crate::compose::parse_address_list)
+        .filter(|a| a.email.as_deref().is_some_and(|e| e.contains('@')))
+        .collect()
+}
+
+/// Candidates whose address, display name or any word of the name starts
+/// with `prefix` (case-insensitive; empty matches everything). Address book
+/// entries and seen addresses are merged by address and ranked by how often
+/// they were seen, address book entries first on ties.
+pub fn complete(
+    prefix: &str,
+    contacts: &[EmailAddress],
+    seen: &[SeenAddress],
+    limit: usize,
+) -> Vec<Suggestion> {
+    let mut merged: Vec<Suggestion> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for contact in contacts {
+        let Some(email) = contact.email.as_deref() else {
+            continue;
+        };
+        let key = email.to_lowercase();
+        if index.contains_key(&key) {
+            continue;
+        }
+        index.insert(key, merged.len());
+        merged.push(Suggestion {
+            name: contact.name.clone(),
+            email: email.to_string(),
+            count: 0,
+            contact: true,
+        });
+    }
+    for address in seen {
+        match index.get(&address.email.to_lowercase()) {
+            Some(&i) => {
+                let entry = &mut merged[i];
+                entry.count = address.count;
+                if entry.name.is_none() {
+                    entry.name = address.name.clone();
+                }
+            }
+            None => {
+                index.insert(address.email.to_lowercase(), merged.len());
+                merged.push(Suggestion {
+                    name: address.name.clone(),
+                    email: address.email.clone(),
+                    count: address.count,
+                    contact: false,
+                });
+            }
+        }
+    }
+
+    let prefix = prefix.trim().to_lowercase();
+    let mut matches: Vec<Suggestion> = merged
+        .into_iter()
+        .filter(|s| {
+            let name = s.name.as_deref().unwrap_or("").to_lowercase();
+            s.email.to_lowercase().starts_with(&prefix)
+                || name.starts_with(&prefix)
+                || name.split_whitespace().any(|w| w.starts_with(&prefix))
+        })
+        .collect();
+    matches.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then(b.contact.cmp(&a.contact))
+            .then_with(|| a.email.to_lowercase().cmp(&b.email.to_lowercase()))
+    });
+    matches.truncate(limit);
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seen(email: &str, name: Option<&str>, count: u32) -> SeenAddress {
+        SeenAddress {
+            email: email.to_string(),
+            name: name.map(str::to_string),
+            count,
+            last_seen: None,
+        }
+    }
+
+    #[test]
+    fn parse_contacts_skips_comments_and_bare_names() {
+        let contacts = parse_contacts(
+            "# team\nAnn Lee <ann@example.com>\n\nbob@example.com\nnot an address\n\"Doe, J\" <j@example.com>\n",
+        );
+        let shown: Vec<String> = contacts.iter().map(|c| c.to_string()).collect();
+        assert_eq!(
+            shown,
+            [
+                "Ann Lee <ann@example.com>",
+                "bob@example.com",
+                "Doe, J <j@example.com>"
+            ]
+        );
+    }
+
+    #[test]
+    fn complete_merges_and_ranks() {
+        let contacts = parse_contacts("Ann Lee <ann@example.com>\nAndy <andy@example.com>");
+        let seen = [
+            seen("ANN@example.com", Some("Annie"), 3),
+            seen("anders@example.org", None, 5),
+            seen("bob@example.com", Some("Bob"), 9),
+        ];
+
+        let all = complete("an", &contacts, &seen, 10);
+        let emails: Vec<&str> = all.iter().map(|s| s.email.as_str()).collect();
+        assert_eq!(
+            emails,
+            ["anders@example.org", "ann@example.com", "andy@example.com"]
+        );
+        assert_eq!(all[1].name.as_deref(), Some("Ann Lee"));
+        assert_eq!(all[1].count, 3);
+        assert!(all[1].contact && !all[0].contact);
+
+        let by_last_name = complete("LEE", &contacts, &seen, 10);
+        assert_eq!(by_last_name[0].display(), "Ann Lee <ann@example.com>");
+        assert_eq!(complete("", &contacts, &seen, 2).len(), 2);
+        assert!(complete("zed", &contacts, &seen, 10).is_empty());
+    }
+}
@@ -0,0 +1,345 @@
+//! Date column formatting for the email list (`[ui].date_format`), plus the
+//! calendar conversions and timestamp parsing shared with rules and compose.
+//!
+//! Every format pads to a fixed width so list rows stay aligned no matter
+//! how old a message is.
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum DateFormat {
+    /// `YYYY-MM-DD` as sent by the server.
+    #[default]
+    Iso,
+    /// Age for recent mail (`5m`, `2h`, `3d`), then `Jan 12`, then `Jan 2023`.
+    Relative,
+    /// strftime-like pattern, e.g. `%d %b %H:%M`.
+    Pattern(String),
+}
+
+/// Broken-down local time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DateTime {
+    year: i64,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+    weekday: u32,
+}
+
+/// Fixed time used to measure how wide a custom pattern renders.
+const SAMPLE: DateTime = DateTime {
+    year: 2000,
+    month: 12,
+    day: 31,
+    hour: 23,
+    minute: 59,
+    second: 59,
+    weekday: 0,
+};
+
+impl DateFormat {
+    /// Parse `iso`, `relative`, or a pattern containing `%` directives.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "iso" => Ok(DateFormat::Iso),
+            "relative" => Ok(DateFormat::Relative),
+            _ if s.contains('%') => Ok(DateFormat::Pattern(s.to_string())),
+            _ => Err(format!(
+                "invalid ui.date_format '{}': expected iso, relative or a %-pattern",
+                s
+            )),
+        }
+    }
+
+    /// Width of the date column.
+    pub fn width(&self) -> usize {
+        match self {
+            DateFormat::Iso => 10,
+            DateFormat::Relative => 8,
+            DateFormat::Pattern(pattern) => format_pattern(pattern, &SAMPLE).chars().count(),
+        }
+    }
+
+    /// Format an email's `receivedAt` for the list, padded to `width()`.
+    /// `now` is the current time in seconds since the Unix epoch.
+    pub fn format(&self, received_at: Option<&str>, now: i64) -> String {
+        let width = self.width();
+        let day_prefix = |d: &str| d.get(..10).unwrap_or(d).to_string();
+        let text = match received_at {
+            None => String::new(),
+            Some(d) => match (self, parse_utc_timestamp(d)) {
+                (DateFormat::Relative, Some(secs)) => {
+                    let local = local_datetime(secs);
+                    let same_year = local_datetime(now).year == local.year;
+                    format_relative(now - secs, &local, same_year)
+                }
+                (DateFormat::Pattern(pattern), Some(secs)) => {
+                    format_pattern(pattern, &local_datetime(secs))
+                }
+                _ => day_prefix(d),
+            },
+        };
+        let text: String = text.chars().take(width).collect();
+        format!("{:<width$}", text, width = width)
+    }
+}
+
+/// Format seconds since the Unix epoch in local time with a `%` pattern.
+pub fn format_local(secs: i64, pattern: &str) -> String {
+    format_pattern(pattern, &local_datetime(secs))
+}
+
+/// Parse an RFC 3339 timestamp such as `2025-01-12T09:30:00Z` or
+/// `2025-01-12T09:30:00.123+02:00` into seconds since the Unix epoch.
+pub fn parse_utc_timestamp(s: &str) -> Option<i64> {
+    let year = s.get(0..4)?.parse::<i32>().ok()?;
+    let month = s.get(5..7)?.parse::<u32>().ok()?;
+    let day = s.get(8..10)?.parse::<u32>().ok()?;
+    let hour = s.get(11..13)?.parse::<i64>().ok()?;
+    let minute = s.get(14..16)?.parse::<i64>().ok()?;
+    let second = s.get(17..19)?.parse::<i64>().ok()?;
+    let mut rest = s.get(19..)?;
+    if let Some(frac) = rest.strip_prefix('.') {
+        rest = frac.trim_start_matches(|c: char| c.is_ascii_digit());
+    }
+    let offset = match rest {
+        "Z" | "z" | "" => 0,
+        _ => {
+            let sign = match rest.get(0..1)? {
+                "+" => 1,
+                "-" => -1,
+                _ => return None,
+            };
+            let oh = rest.get(1..3)?.parse::<i64>().ok()?;
+            let om = rest.get(4..6)?.parse::<i64>().ok()?;
+            sign * (oh * 3600 + om * 60)
+        }
+    };
+    let days = ymd_to_days_since_epoch(year, month, day)?;
+    Some(days * 86_400 + hour * 3600 + minute * 60 + second - offset)
+}
+
+/// Parse an HTTP-date in its preferred IMF-fixdate form, e.g.
+/// `Sun, 06 Nov 1994 08:49:37 GMT`, into seconds since the Unix epoch.
+pub fn parse_http_date(s: &str) -> Option<i64> {
+    let (_, rest) = s.trim().split_once(", ")?;
+    let mut parts = rest.split(' ');
+    let day = parts.next()?.parse::<u32>().ok()?;
+    let month = MONTHS.iter().position(|m| Some(*m) == parts.next())? as u32 + 1;
+    let year = parts.next()?.parse::<i32>().ok()?;
+    let mut time = parts.next()?.split(':').map(|p| p.parse::<i64>().ok());
+    let (hour, minute, second) = (time.next()??, time.next()??, time.next()??);
+    if parts.next()? != "GMT" || parts.next().is_some() {
+        return None;
+    }
+    let days = ymd_to_days_since_epoch(year, month, day)?;
+    Some(days * 86_400 + hour * 3600 + minute * 60 + second)
+}
+
+/// Day index since the Unix epoch of the `YYYY-MM-DD` date that starts `s`.
+pub fn parse_date_days(s: &str) -> Option<i64> {
+    let year = s.get(0..4)?.parse::<i32>().ok()?;
+    let month = s.get(5..7)?.parse::<u32>().ok()?;
+    let day = s.get(8..10)?.parse::<u32>().ok()?;
+    ymd_to_days_since_epoch(year, month, day)
+}
+
+/// `YYYY-MM-DD` (UTC) for seconds since the Unix epoch.
+pub fn utc_date(secs: i64) -> String {
+    let (y, m, d) = civil_from_days(secs.div_euclid(86_400));
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+
+/// `(year, month, day)` of a day index since the Unix epoch (Howard
+/// Hinnant's civil-from-days).
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+// Convert calendar date to day index since Unix epoch.
+fn ymd_to_days_since_epoch(year: i32, month: u32, day: u32) -> Option<i64> {
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let y = year as i64 - if month <= 2 { 1 } else { 0 };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let mp = month as i64 + if month > 2 { -3 } else { 9 };
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    Some(era * 146_097 + doe - 719_468)
+}
+
+fn local_datetime(secs: i64) -> DateTime {
+    let timestamp = secs as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    unsafe {
+        libc::localtime_r(&timestamp, &mut tm);
+    }
+    DateTime {
+        year: tm.tm_year as i64 + 1900,
+        month: (tm.tm_mon + 1) as u32,
+        day: tm.tm_mday as u32,
+        hour: tm.tm_hour as u32,
+        minute: tm.tm_min as u32,
+        second: tm.tm_sec as u32,
+        weekday: tm.tm_wday as u32,
+    }
+}
+
+fn month_name(dt: &DateTime) -> &'static str {
+    MONTHS[(dt.month.clamp(1, 12) - 1) as usize]
+}
+
+/// `age` is seconds between the message and now; future dates fall through
+/// to the calendar form.
+fn format_relative(age: i64, dt: &DateTime, same_year: bool) -> String {
+    match age {
+        0..=59 => "now".to_string(),
+        60..=3_599 => format!("{}m", age / 60),
+        3_600..=86_399 => format!("{}h", age / 3_600),
+        86_400..=604_799 => format!("{}d", age / 86_400),
+        _ if same_year => format!("{} {}", month_name(dt), dt.day),
+        _ => format!("{} {}", month_name(dt), dt.year),
+    }
+}
+
+/// Expand `%Y %y %m %d %e %H %I %M %S %p %b %a %%`; anything else is copied
+/// through unchanged.
+fn format_pattern(pattern: &str, dt: &DateTime) -> String {
+    let mut out = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => out.push_str(&dt.year.to_string()),
+            Some('y') => out.push_str(&format!("{:02}", dt.year.rem_euclid(100))),
+            Some('m') => out.push_str(&format!("{:02}", dt.month)),
+            Some('d') => out.push_str(&format!("{:02}", dt.day)),
+            Some('e') => out.push_str(&format!("{:>2}", dt.day)),
+            Some('H') => out.push_str(&format!("{:02}", dt.hour)),
+            Some('I') => out.push_str(&format!("{:02}", (dt.hour + 11) % 12 + 1)),
+            Some('M') => out.push_str(&format!("{:02}", dt.minute)),
+            Some('S') => out.push_str(&format!("{:02}", dt.second)),
+            Some('p') => out.push_str(if dt.hour < 12 { "AM" } else { "PM" }),
+            Some('b') => out.push_str(month_name(dt)),
+            Some('a') => out.push_str(WEEKDAYS[(dt.weekday % 7) as usize]),
+            Some('%') => out.push('%'),
+            Some(other) => {
+                out.push('%');
+                out.push(other);
+            }
+            None => out.push('%'),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_civil_round_trip() {
+        for (y, m, d) in [(1970, 1, 1), (2000, 2, 29), (2024, 12, 31), (1969, 12, 31)] {
+            let days = ymd_to_days_since_epoch(y, m, d).unwrap();
+            assert_eq!(civil_from_days(days), (y as i64, m, d));
+        }
+        assert_eq!(parse_date_days("2025-01-15T10:30:00Z"), Some(20_103));
+        assert_eq!(parse_date_days("2025-13-01"), None);
+        assert_eq!(utc_date(-1), "1969-12-31");
+    }
+
+    #[test]
+    fn test_parse_formats() {
+        assert_eq!(DateFormat::parse("iso"), Ok(DateFormat::Iso));
+        assert_eq!(DateFormat::parse("relative"), Ok(DateFormat::Relative));
+        assert_eq!(
+            DateFormat::parse("%d %b"),
+            Ok(DateFormat::Pattern("%d %b".to_string()))
+        );
+        assert!(DateFormat::parse("short").is_err());
+    }
+
+    #[test]
+    fn test_parse_utc_timestamp() {
+        assert_eq!(parse_utc_timestamp("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(
+            parse_utc_timestamp("2025-01-12T09:30:00Z"),
+            Some(1_736_674_200)
+        );
+        assert_eq!(
+            parse_utc_timestamp("2025-01-12T11:30:00.250+02:00"),
+            Some(1_736_674_200)
+        );
+        assert_eq!(parse_utc_timestamp("2025-01-12"), None);
+    }
+
+    #[test]
+    fn test_parse_http_date() {
+        assert_eq!(
+            parse_http_date("Sun, 12 Jan 2025 09:30:00 GMT"),
+            Some(1_736_674_200)
+        );
+        assert_eq!(parse_http_date("Sun, 12 Foo 2025 09:30:00 GMT"), None);
+        assert_eq!(parse_http_date("Sun, 12 Jan 2025 09:30:00 PST"), None);
+        assert_eq!(parse_http_date("120"), None);
+    }
+
+    #[test]
+    fn test_format_relative() {
+        let dt = DateTime {
+            year: 2024,
+            month: 1,
+            day: 12,
+            ..SAMPLE
+        };
+        assert_eq!(format_relative(30, &dt, true), "now");
+        assert_eq!(format_relative(5 * 60, &dt, true), "5m");
+        assert_eq!(format_relative(2 * 3600, &dt, true), "2h");
+        assert_eq!(format_relative(3 * 86_400, &dt, true), "3d");
+        assert_eq!(format_relative(30 * 86_400, &dt, true), "Jan 12");
+        assert_eq!(format_relative(400 * 86_400, &dt, false), "Jan 2024");
+    }
+
+    #[test]
+    fn test_format_pattern_and_width() {
+        let dt = DateTime {
+            year: 2025,
+            month: 3,
+            day: 7,
+            hour: 14,
+            minute: 5,
+            second: 9,
+            weekday: 5,
+        };
+        assert_eq!(format_pattern("%Y-%m-%d %H:%M", &dt), "2025-03-07 14:05");
+        assert_eq!(format_pattern("%a %e %b %I%p %%", &dt), "Fri  7 Mar 02PM %");
+        let format = DateFormat::Pattern("%d %b %H:%M".to_string());
+        assert_eq!(format.width(), 12);
+        assert_eq!(format.format(None, 0), " ".repeat(12));
+        assert_eq!(
+            DateFormat::Iso.format(Some("2025-01-12T09:30:00Z"), 0),
+            "2025-01-12"
+        );
+        assert_eq!(DateFormat::Relative.format(Some("garbage"), 0).len(), 8);
+    }
+}
@@ -0,0 +1,318 @@
+//! Saved drafts, one store per account under
+//! `$XDG_CACHE_HOME/tmc/drafts/<account>/`. The TUI writes every
+//! compose/reply/forward draft there when it is created, so it survives an
+//! editor or terminal crash, and drops it again if it is left untouched; the
+//! CLI saves only on an explicit `save_draft` with `local`. A draft is
+//! removed once it is sent (or explicitly discarded); until then it can be
+//! resumed from the TUI or the CLI.
+//!
+//! Each draft is three entries named by its id: `<id>.eml` holds the editor
+//! text, `<id>.json` the metadata shown in lists, and `<id>.att/` any
+//! in-memory attachments (e.g. a forwarded message).
+
+use crate::compose::{ComposeDraft, DraftAttachment};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Metadata of a saved draft.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedDraft {
+    pub id: String,
+    pub subject: String,
+    pub to: String,
+    /// Seconds since the Unix epoch of the last save.
+    pub saved_at: u64,
+    #[serde(default)]
+    pub attachments: Vec<SavedAttachment>,
+}
+
+/// An attachment stored next to a draft, as `<id>.att/<index>`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedAttachment {
+    pub filename: String,
+    pub content_type: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DraftStore {
+    dir: PathBuf,
+}
+
+impl DraftStore {
+    pub fn new(dir: PathBuf) -> Self {
+        DraftStore { dir }
+    }
+
+    /// The store of `account_name` under the cache dir.
+    pub fn for_account(account_name: &str) -> Self {
+        let safe_name = account_name.replace(['/', '\\', '\0'], "_");
+        DraftStore::new(crate::cache::cache_dir().join("drafts").join(safe_name))
+    }
+
+    /// Persist a new draft and return its metadata.
+    pub fn create(&self, draft: &ComposeDraft) -> Result<SavedDraft, String> {
+        self.ensure_dir()?;
+        let id = new_id();
+        let mut attachments = Vec::new();
+        if !draft.attachments.is_empty() {
+            let att_dir = self.attachment_dir(&id);
+            fs::create_dir_all(&att_dir)
+                .map_err(|e| format!("create draft dir {:?}: {}", att_dir, e))?;
+            for (i, att) in draft.attachments.iter().enumerate() {
+                write_private(&att_dir.join(i.to_string()), &att.data)?;
+                attachments.push(SavedAttachment {
+                    filename: att.filename.clone(),
+                    content_type: att.content_type.clone(),
+                    description: att.description.clone(),
+                });
+            }
+        }
+        let mut meta = SavedDraft {
+            id,
+            subject: String::new(),
+            to: String::new(),
+            saved_at: 0,
+            attachments,
+        };
+        self.write(&mut meta, &draft.body)?;
+        Ok(meta)
+    }
+
+    /// Replace the text of a saved draft, refreshing its metadata.
+    pub fn update(&self, id: &str, text: &str) -> Result<SavedDraft, String> {
+        let mut meta = self.metadata(id)?;
+        self.write(&mut meta, text)?;
+        Ok(meta)
+    }
+
+    /// Saved drafts, most recently saved first. Unreadable entries are skipped.
+    pub fn list(&self) -> Vec<SavedDraft> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut drafts: Vec<SavedDraft> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                    return None;
+                }
+                let id = path.file_stem()?.to_str()?;
+                self.metadata(id).ok()
+            })
+            .collect();
+        drafts.sort_by(|a, b| b.saved_at.cmp(&a.saved_at).then(b.id.cmp(&a.id)));
+        drafts
+    }
+
+    /// Load a saved draft back into a [`ComposeDraft`].
+    pub fn load(&self, id: &str) -> Result<(SavedDraft, ComposeDraft), String> {
+        let meta = self.metadata(id)?;
+        let text_path = self.text_path(id);
+        let body = fs::read_to_string(&text_path)
+            .map_err(|e| format!("read draft {:?}: {}", text_path, e))?;
+        let attachments = meta
+            .attachments
+            .iter()
+            .enumerate()
+            .map(|(i, att)| {
+                let path = self.attachment_dir(id).join(i.to_string());
+                let data =
+                    fs::read(&path).map_err(|e| format!("read attachment {:?}: {}", path, e))?;
+                Ok(DraftAttachment {
+                    filename: att.filename.clone(),
+                    content_type: att.content_type.clone(),
+                    description: att.description.clone(),
+                    data,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok((meta, ComposeDraft { body, attachments }))
+    }
+
+    /// Delete a saved draft. Returns false when there was no such draft.
+    pub fn remove(&self, id: &str) -> Result<bool, String> {
+        check_id(id)?;
+        let existed = self.meta_path(id).exists();
+        for path in [self.meta_path(id), self.text_path(id)] {
+            match fs::remove_file(&path) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(format!("remove {:?}: {}", path, e)),
+            }
+        }
+        let att_dir = self.attachment_dir(id);
+        if att_dir.exists() {
+            fs::remove_dir_all(&att_dir).map_err(|e| format!("remove {:?}: {}", att_dir, e))?;
+        }
+        Ok(existed)
+    }
+
+    fn metadata(&self, id: &str) -> Result<SavedDraft, String> {
+        check_id(id)?;
+        let path = self.meta_path(id);
+        let bytes = fs::read(&path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => format!("no saved draft '{}'", id),
+            _ => format!("read {:?}: {}", path, e),
+        })?;
+        serde_json::from_slice(&bytes).map_err(|e| format!("parse {:?}: {}", path, e))
+    }
+
+    /// Write the text first, then the metadata, each atomically, so a crash
+    /// never leaves metadata pointing at a half-written draft.
+    fn write(&self, meta: &mut SavedDraft, text: &str) -> Result<(), String> {
+        let (to, subject) = summary_headers(text);
+        meta.to = to;
+        meta.subject = subject;
+        meta.saved_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let json =
+            serde_json::to_vec(meta).map_err(|e| format!("serialize draft metadata: {}", e))?;
+        replace_private(&self.text_path(&meta.id), text.as_bytes())?;
+        replace_private(&self.meta_path(&meta.id), &json)
+    }
+
+    fn ensure_dir(&self) -> Result<(), String> {
+        fs::create_dir_all(&self.dir)
+            .and_then(|_| fs::set_permissions(&self.dir, fs::Permissions::from_mode(0o700)))
+            .map_err(|e| format!("create drafts dir {:?}: {}", self.dir, e))
+    }
+
+    fn text_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.eml", id))
+    }
+
+    fn meta_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    fn attachment_dir(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.att", id))
+    }
+}
+
+/// Ids are generated by [`new_id`]; anything else (notably path separators
+/// from a CLI caller) is rejected.
+fn check_id(id: &str) -> Result<(), String> {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(format!("invalid draft id '{}'", id));
+    }
+    Ok(())
+}
+
+fn new_id() -> String {
+    static SEQ: AtomicU32 = AtomicU32::new(0);
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    format!(
+        "{}-{}-{}",
+        millis,
+        std::process::id(),
+        SEQ.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// `To:` and `Subject:` from the draft's header block, without the strict
+/// checks of `compose::parse_draft` (a fresh draft has no recipient yet).
+fn summary_headers(text: &str) -> (String, String) {
+    let mut to = String::new();
+    let mut subject = String::new();
+    for line in text.lines() {
+        if line.trim().is_empty() || line.trim() == crate::compose::BODY_SEPARATOR {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            match name.trim().to_ascii_lowercase().as_str() {
+                "to" => to = value.trim().to_string(),
+                "subject" => subject = value.trim().to_string(),
+                _ => {}
+            }
+        }
+    }
+    (to, subject)
+}
+
+/// Write `bytes` to a fresh 0600 file.
+fn write_private(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .create(true)
+        .mode(0o600)
+        .open(path)
+        .map_err(|e| format!("write {:?}: {}", path, e))?;
+    std::io::Write::write_all(&mut file, bytes).map_err(|e| format!("write {:?}: {}", path, e))
+}
+
+/// Replace `path` atomically (write to a temp file, then rename).
+fn replace_private(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    write_private(&tmp, bytes)?;
+    fs::rename(&tmp, path).map_err(|e| format!("rename into {:?}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_update_list_load_and_remove() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = DraftStore::new(dir.path().join("drafts"));
+        assert!(store.list().is_empty());
+
+        let first = store
+            .create(&ComposeDraft::text(
+                "From: me@example.com\nTo: \nSubject: \n--text follows this line--\n".into(),
+            ))
+            .unwrap();
+        assert_eq!((first.to.as_str(), first.subject.as_str()), ("", ""));
+
+        let mut forward = ComposeDraft::text("To: ann@example.com\nSubject: Fwd: hi\n\n".into());
+        forward.attachments.push(DraftAttachment {
+            filename: "hi.eml".to_string(),
+            content_type: "message/rfc822".to_string(),
+            description: Some("hi".to_string()),
+            data: b"Subject: hi\r\n\r\nbody".to_vec(),
+        });
+        let second = store.create(&forward).unwrap();
+        assert_ne!(first.id, second.id);
+
+        let updated = store
+            .update(
+                &first.id,
+                "From: me@example.com\nTo: bob@example.com\nSubject: Lunch\n--text follows this line--\nNoon?\n",
+            )
+            .unwrap();
+        assert_eq!(updated.to, "bob@example.com");
+        assert_eq!(updated.subject, "Lunch");
+
+        let ids: Vec<String> = store.list().into_iter().map(|d| d.id).collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&first.id) && ids.contains(&second.id));
+
+        let (meta, loaded) = store.load(&second.id).unwrap();
+        assert_eq!(meta.subject, "Fwd: hi");
+        assert_eq!(loaded.body, forward.body);
+        assert_eq!(loaded.attachments.len(), 1);
+        assert_eq!(loaded.attachments[0].filename, "hi.eml");
+        assert_eq!(loaded.attachments[0].data, forward.attachments[0].data);
+
+        assert!(store.remove(&second.id).unwrap());
+        assert!(!store.remove(&second.id).unwrap());
+        assert!(store.load(&second.id).is_err());
+        assert_eq!(store.list().len(), 1);
+        assert!(store.load("../config").is_err());
+    }
+}
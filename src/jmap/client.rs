@@ -1,6 +1,8 @@
 use base64::Engine;
-use serde_json::json;
-use std::io::Read as _;
+use serde_json::{json, Value};
+use std::io::{BufReader, Read as _};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 /// Read a ureq response body into a String without the default 10 MB size
 /// limit imposed by `Response::into_string()`.  Large JMAP responses (e.g.
@@ -13,21 +15,258 @@ fn read_response_body(resp: ureq::Response) -> Result<String, std::io::Error> {
     Ok(buf)
 }
 
+use super::push::EventSource;
 use super::types::*;
 
+/// Credentials sent in the `Authorization` header of every request.
+#[derive(Clone, Debug)]
+pub enum Credentials {
+    /// HTTP Basic with the account username and password.
+    Basic { username: String, password: String },
+    /// OAuth2 access token. After a 401, `refresh_command` is run to print a
+    /// new token and the request is retried once.
+    Bearer {
+        token: String,
+        refresh_command: Option<String>,
+    },
+}
+
+impl Credentials {
+    fn header(&self) -> String {
+        match self {
+            Credentials::Basic { username, password } => {
+                let credentials = format!("{}:{}", username, password);
+                let encoded = base64::engine::general_purpose::STANDARD.encode(credentials);
+                format!("Basic {}", encoded)
+            }
+            Credentials::Bearer { token, .. } => format!("Bearer {}", token),
+        }
+    }
+
+    /// Fetch a new bearer token. Returns false when there is nothing to
+    /// refresh or the refresh command failed.
+    fn refresh(&mut self) -> bool {
+        let Credentials::Bearer {
+            token,
+            refresh_command: Some(command),
+        } = self
+        else {
+            return false;
+        };
+        log_info!("[JMAP] Access token rejected, running token_refresh_command");
+        match crate::run_password_command(command) {
+            Ok(new_token) if !new_token.is_empty() => {
+                *token = new_token;
+                true
+            }
+            Ok(_) => {
+                log_error!("[JMAP] token_refresh_command printed an empty token");
+                false
+            }
+            Err(e) => {
+                log_error!("[JMAP] token_refresh_command failed: {}", e);
+                false
+            }
+        }
+    }
+}
+
+/// Run `request` with the current Authorization header; on 401, refresh the
+/// credentials and retry once.
+fn with_auth<T>(
+    credentials: &Mutex<Credentials>,
+    request: impl Fn(&str) -> Result<T, JmapError>,
+) -> Result<T, JmapError> {
+    let header = |c: &Mutex<Credentials>| c.lock().map(|c| c.header()).unwrap_or_default();
+    let result = request(&header(credentials));
+    if !matches!(result, Err(JmapError::Unauthorized)) {
+        return result;
+    }
+    let refreshed = credentials.lock().map(|mut c| c.refresh()).unwrap_or(false);
+    if refreshed {
+        request(&header(credentials))
+    } else {
+        result
+    }
+}
+
+/// Network settings shared by every request a client makes.
+#[derive(Debug, Clone, Copy)]
+pub struct HttpOptions {
+    /// Extra attempts after a connection error, 429 or 5xx (`[ui].max_retries`).
+    pub max_retries: u32,
+    /// Limit on establishing a connection; `None` waits indefinitely.
+    pub connect_timeout: Option<Duration>,
+    /// Limit on a whole request including the response body; `None` waits
+    /// indefinitely.
+    pub request_timeout: Option<Duration>,
+    /// Email/get chunk requests in flight at once when loading many
+    /// messages (`[ui].fetch_concurrency`); 0 or 1 is sequential.
+    pub fetch_concurrency: usize,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        HttpOptions {
+            max_retries: 3,
+            connect_timeout: Some(Duration::from_secs(10)),
+            request_timeout: Some(Duration::from_secs(120)),
+            fetch_concurrency: 4,
+        }
+    }
+}
+
+impl HttpOptions {
+    fn agent_builder(&self) -> ureq::AgentBuilder {
+        let mut builder = ureq::AgentBuilder::new();
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.timeout_connect(timeout);
+        }
+        if let Some(timeout) = self.request_timeout {
+            builder = builder.timeout(timeout);
+        }
+        builder
+    }
+}
+
+/// Whether a transport failure was a timeout rather than, say, a refused
+/// connection.
+fn is_timeout(e: &ureq::Transport) -> bool {
+    let mut source = std::error::Error::source(e);
+    while let Some(err) = source {
+        if let Some(io) = err.downcast_ref::<std::io::Error>() {
+            return matches!(
+                io.kind(),
+                std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock
+            );
+        }
+        source = err.source();
+    }
+    false
+}
+
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Whether a failed request is worth repeating: the connection failed, or
+/// the server is rate limiting or temporarily broken. Timeouts are not
+/// retried, since each attempt would wait the full timeout again.
+fn is_transient(e: &ureq::Error) -> bool {
+    match e {
+        ureq::Error::Status(code, _) => *code == 429 || (500..600).contains(code),
+        ureq::Error::Transport(t) if is_timeout(t) => false,
+        ureq::Error::Transport(t) => matches!(
+            t.kind(),
+            ureq::ErrorKind::Dns
+                | ureq::ErrorKind::ConnectionFailed
+                | ureq::ErrorKind::Io
+                | ureq::ErrorKind::ProxyConnect
+        ),
+    }
+}
+
+/// Delay before retry `attempt` (0-based): the server's `Retry-After`
+/// (delay seconds or an HTTP-date) when given, otherwise exponential
+/// backoff; capped either way. `now` is seconds since the Unix epoch.
+fn retry_delay(attempt: u32, retry_after: Option<&str>, now: i64) -> Duration {
+    retry_after
+        .and_then(|v| {
+            v.trim().parse::<u64>().ok().or_else(|| {
+                crate::datefmt::parse_http_date(v).map(|at| at.saturating_sub(now).max(0) as u64)
+            })
+        })
+        .map(Duration::from_secs)
+        .unwrap_or_else(|| RETRY_BASE_DELAY.saturating_mul(1 << attempt.min(16)))
+        .min(RETRY_MAX_DELAY)
+}
+
+/// Whether every call in `request` only reads server state, so sending it
+/// twice is harmless. Anything else (`*/set`, `EmailSubmission/set`, ...)
+/// may already have been applied when the response is lost.
+fn is_read_only(request: &JmapRequest) -> bool {
+    request.method_calls.iter().all(|MethodCall(name, _, _)| {
+        name.ends_with("/get") || name.ends_with("/query") || name.ends_with("/changes")
+    })
+}
+
+/// Run `request`, repeating it up to `max_retries` times while it fails
+/// with a transient error.
+fn with_retry<T>(
+    max_retries: u32,
+    request: impl Fn() -> Result<T, Box<ureq::Error>>,
+) -> Result<T, Box<ureq::Error>> {
+    let mut attempt = 0;
+    loop {
+        match request() {
+            Err(e) if attempt < max_retries && is_transient(&e) => {
+                let retry_after = match &*e {
+                    ureq::Error::Status(_, resp) => resp.header("retry-after").map(str::to_string),
+                    ureq::Error::Transport(_) => None,
+                };
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs() as i64)
+                    .unwrap_or(0);
+                let delay = retry_delay(attempt, retry_after.as_deref(), now);
+                attempt += 1;
+                log_warn!(
+                    "[JMAP] Transient error ({}), retry {}/{} in {:?}",
+                    e,
+                    attempt,
+                    max_retries,
+                    delay
+                );
+                std::thread::sleep(delay);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Map a ureq failure, keeping 401 distinct so callers can refresh tokens.
+fn http_error(e: ureq::Error) -> JmapError {
+    match e {
+        ureq::Error::Status(401, _) => JmapError::Unauthorized,
+        ureq::Error::Transport(ref t) if is_timeout(t) => JmapError::Timeout,
+        e => JmapError::Http(e.to_string()),
+    }
+}
+
+#[derive(Clone)]
 pub struct JmapClient {
-    username: String,
-    password: String,
+    /// Shared between clones so a refreshed token is seen by all of them.
+    credentials: Arc<Mutex<Credentials>>,
     api_url: String,
     account_id: String,
     download_url: Option<String>,
+    upload_url: Option<String>,
+    event_source_url: Option<String>,
+    can_submit: bool,
+    can_vacation: bool,
+    can_quota: bool,
+    http: HttpOptions,
 }
 
+const SUBMISSION_CAPABILITY: &str = "urn:ietf:params:jmap:submission";
+const VACATION_CAPABILITY: &str = "urn:ietf:params:jmap:vacationresponse";
+const QUOTA_CAPABILITY: &str = "urn:ietf:params:jmap:quota";
+
+/// Body bytes fetched (and scanned) per email when a rule matches on `body`.
+pub const RULES_MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// Upper bound on ids per `*/changes` response; a sync with more changes
+/// than this refetches in full instead of paging through them.
+const MAX_CHANGES: u32 = 500;
+
 #[derive(Debug)]
 pub enum JmapError {
     Http(String),
     Parse(String),
     Api(String),
+    /// The server answered 401 Unauthorized.
+    Unauthorized,
+    /// Connecting or the request took longer than the configured timeout.
+    Timeout,
 }
 
 impl std::fmt::Display for JmapError {
@@ -36,33 +275,38 @@ impl std::fmt::Display for JmapError {
             JmapError::Http(e) => write!(f, "HTTP error: {}", e),
             JmapError::Parse(e) => write!(f, "Parse error: {}", e),
             JmapError::Api(e) => write!(f, "API error: {}", e),
+            JmapError::Unauthorized => {
+                write!(f, "HTTP error: Authentication failed (401 Unauthorized)")
+            }
+            JmapError::Timeout => write!(f, "request timed out"),
         }
     }
 }
 
 impl JmapClient {
-    fn auth_header(username: &str, password: &str) -> String {
-        let credentials = format!("{}:{}", username, password);
-        let encoded = base64::engine::general_purpose::STANDARD.encode(credentials);
-        format!("Basic {}", encoded)
-    }
-
     /// Fetch a URL following redirects manually while preserving the auth header.
     fn fetch_with_auth_following_redirects(
         url: &str,
         auth: &str,
         max_redirects: u32,
+        http: &HttpOptions,
     ) -> Result<(String, String), JmapError> {
-        let agent = ureq::AgentBuilder::new().redirects(0).build();
+        let agent = http.agent_builder().redirects(0).build();
 
         let mut current_url = url.to_string();
 
         for i in 0..max_redirects {
             log_debug!("[JMAP] Request {} to: {}", i + 1, current_url);
 
-            let response = agent.get(&current_url).set("Authorization", auth).call();
+            let response = with_retry(http.max_retries, || {
+                agent
+                    .get(&current_url)
+                    .set("Authorization", auth)
+                    .call()
+                    .map_err(Box::new)
+            });
 
-            match response {
+            match response.map_err(|e| *e) {
                 Ok(resp) => {
                     let status = resp.status();
                     log_debug!("[JMAP] Got {} response", status);
@@ -109,9 +353,7 @@ impl JmapClient {
                     log_error!("[JMAP] HTTP error {}: {}", code, body);
 
                     if code == 401 {
-                        return Err(JmapError::Http(
-                            "Authentication failed (401 Unauthorized)".to_string(),
-                        ));
+                        return Err(JmapError::Unauthorized);
                     }
 
                     return Err(JmapError::Http(format!(
@@ -126,7 +368,7 @@ impl JmapClient {
                 }
                 Err(e) => {
                     log_error!("[JMAP] Connection error: {}", e);
-                    return Err(JmapError::Http(e.to_string()));
+                    return Err(http_error(e));
                 }
             }
         }
@@ -159,14 +401,15 @@ impl JmapClient {
 
     pub fn discover(
         well_known_url: &str,
-        username: &str,
-        password: &str,
+        credentials: Credentials,
+        http: HttpOptions,
     ) -> Result<(JmapSession, Self), JmapError> {
         log_info!("[JMAP] Discovering JMAP session from: {}", well_known_url);
-        let auth = Self::auth_header(username, password);
+        let credentials = Arc::new(Mutex::new(credentials));
 
-        let (_final_url, response_text) =
-            Self::fetch_with_auth_following_redirects(well_known_url, &auth, 5)?;
+        let (_final_url, response_text) = with_auth(&credentials, |auth| {
+            Self::fetch_with_auth_following_redirects(well_known_url, auth, 5, &http)
+        })?;
 
         log_debug!("[JMAP] Session response received, parsing...");
 
@@ -193,11 +436,16 @@ impl JmapClient {
         log_info!("[JMAP] Discovery successful, account_id: {}", account_id);
 
         let client = JmapClient {
-            username: username.to_string(),
-            password: password.to_string(),
+            credentials,
             api_url: session.api_url.clone(),
             account_id,
             download_url: session.download_url.clone(),
+            upload_url: session.upload_url.clone(),
+            event_source_url: session.event_source_url.clone(),
+            can_submit: session.capabilities.contains_key(SUBMISSION_CAPABILITY),
+            can_vacation: session.capabilities.contains_key(VACATION_CAPABILITY),
+            can_quota: session.capabilities.contains_key(QUOTA_CAPABILITY),
+            http,
         };
 
         Ok((session, client))
@@ -207,21 +455,74 @@ impl JmapClient {
         &self.account_id
     }
 
-    fn call(&self, request: JmapRequest) -> Result<JmapResponse, JmapError> {
-        let auth = Self::auth_header(&self.username, &self.password);
+    /// Parallel Email/get requests allowed for chunked fetches (at least 1).
+    pub fn fetch_concurrency(&self) -> usize {
+        self.http.fetch_concurrency.max(1)
+    }
+
+    /// Open the session's EventSource push channel for Mailbox and Email
+    /// changes. The server pings every `ping_secs`, so a read that stalls
+    /// much longer than that means the connection is gone.
+    pub fn open_event_source(&self, ping_secs: u64) -> Result<EventSource, JmapError> {
+        let template = self.event_source_url.as_deref().ok_or_else(|| {
+            JmapError::Api("Server does not advertise an eventSourceUrl".to_string())
+        })?;
+        let url = template
+            .replace("{types}", "Mailbox,Email")
+            .replace("{closeafter}", "no")
+            .replace("{ping}", &ping_secs.to_string());
+
+        log_info!("[JMAP] Opening event source: {}", url);
 
+        let mut builder =
+            ureq::AgentBuilder::new().timeout_read(Duration::from_secs(ping_secs * 2 + 10));
+        if let Some(timeout) = self.http.connect_timeout {
+            builder = builder.timeout_connect(timeout);
+        }
+        let agent = builder.build();
+        let response = with_auth(&self.credentials, |auth| {
+            agent
+                .get(&url)
+                .set("Authorization", auth)
+                .set("Accept", "text/event-stream")
+                .call()
+                .map_err(|e| {
+                    log_error!("[JMAP] Event source failed: {}", e);
+                    http_error(e)
+                })
+        })?;
+
+        Ok(EventSource::new(
+            Box::new(BufReader::new(response.into_reader())),
+            self.account_id.clone(),
+        ))
+    }
+
+    fn call(&self, request: JmapRequest) -> Result<JmapResponse, JmapError> {
         let request_json = serde_json::to_string(&request)
             .map_err(|e| JmapError::Parse(format!("Failed to serialize request: {}", e)))?;
         log_debug!("[JMAP] Request body: {}", truncate_str(&request_json, 500));
 
-        let response = ureq::post(&self.api_url)
-            .set("Authorization", &auth)
-            .set("Content-Type", "application/json")
-            .send_json(&request)
+        let retries = if is_read_only(&request) {
+            self.http.max_retries
+        } else {
+            0
+        };
+        let agent = self.http.agent_builder().build();
+        let response = with_auth(&self.credentials, |auth| {
+            with_retry(retries, || {
+                agent
+                    .post(&self.api_url)
+                    .set("Authorization", auth)
+                    .set("Content-Type", "application/json")
+                    .send_string(&request_json)
+                    .map_err(Box::new)
+            })
             .map_err(|e| {
                 log_error!("[JMAP] API call failed: {}", e);
-                JmapError::Http(e.to_string())
-            })?;
+                http_error(*e)
+            })
+        })?;
 
         let response_text = read_response_body(response)
             .map_err(|e| JmapError::Parse(format!("Failed to read response: {}", e)))?;
@@ -239,7 +540,21 @@ impl JmapClient {
     }
 
     pub fn get_mailboxes(&self) -> Result<Vec<Mailbox>, JmapError> {
-        log_info!("[JMAP] Fetching mailboxes for account: {}", self.account_id);
+        self.get_mailboxes_with_state(None)
+            .map(|(mailboxes, _)| mailboxes)
+    }
+
+    /// Mailbox/get for `ids` (all mailboxes when `None`), along with the
+    /// Mailbox state string to pass to [`Self::mailbox_changes`] later.
+    pub fn get_mailboxes_with_state(
+        &self,
+        ids: Option<&[String]>,
+    ) -> Result<(Vec<Mailbox>, String), JmapError> {
+        log_info!(
+            "[JMAP] Fetching mailboxes for account: {} (ids: {:?})",
+            self.account_id,
+            ids.map(|ids| ids.len())
+        );
 
         let request = JmapRequest {
             using: vec!["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail"],
@@ -247,7 +562,7 @@ impl JmapClient {
                 "Mailbox/get",
                 json!({
                     "accountId": self.account_id,
-                    "ids": null
+                    "ids": ids
                 }),
                 "0".to_string(),
             )],
@@ -264,7 +579,101 @@ impl JmapClient {
                     "[JMAP] Mailbox/get returned {} mailboxes",
                     mailbox_response.list.len()
                 );
-                return Ok(mailbox_response.list);
+                return Ok((mailbox_response.list, mailbox_response.state));
+            }
+        }
+
+        Err(JmapError::Api("Unexpected response".to_string()))
+    }
+
+    /// Current Email state string, for a later [`Self::email_changes`].
+    pub fn get_email_state(&self) -> Result<String, JmapError> {
+        let request = JmapRequest {
+            using: vec!["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail"],
+            method_calls: vec![MethodCall(
+                "Email/get",
+                json!({
+                    "accountId": self.account_id,
+                    "ids": [],
+                    "properties": ["id"]
+                }),
+                "0".to_string(),
+            )],
+        };
+
+        let response = self.call(request)?;
+
+        if let Some(method_response) = response.method_responses.first() {
+            if method_response.0 == "Email/get" {
+                let email_response: EmailGetResponse =
+                    serde_json::from_value(method_response.1.clone())
+                        .map_err(|e| JmapError::Parse(e.to_string()))?;
+                return Ok(email_response.state);
+            }
+        }
+
+        Err(JmapError::Api("Unexpected response".to_string()))
+    }
+
+    /// Email/changes since `since_state`. `Ok(None)` when the server answers
+    /// `cannotCalculateChanges`, meaning the caller has to refetch in full.
+    pub fn email_changes(&self, since_state: &str) -> Result<Option<ChangesResponse>, JmapError> {
+        self.changes("Email/changes", since_state)
+    }
+
+    /// Mailbox/changes since `since_state`; `Ok(None)` as for
+    /// [`Self::email_changes`].
+    pub fn mailbox_changes(&self, since_state: &str) -> Result<Option<ChangesResponse>, JmapError> {
+        self.changes("Mailbox/changes", since_state)
+    }
+
+    fn changes(
+        &self,
+        method: &'static str,
+        since_state: &str,
+    ) -> Result<Option<ChangesResponse>, JmapError> {
+        log_info!("[JMAP] {} since state {}", method, since_state);
+
+        let request = JmapRequest {
+            using: vec!["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail"],
+            method_calls: vec![MethodCall(
+                method,
+                json!({
+                    "accountId": self.account_id,
+                    "sinceState": since_state,
+                    "maxChanges": MAX_CHANGES
+                }),
+                "0".to_string(),
+            )],
+        };
+
+        let response = self.call(request)?;
+
+        if let Some(method_response) = response.method_responses.first() {
+            if method_response.0 == method {
+                let changes: ChangesResponse = serde_json::from_value(method_response.1.clone())
+                    .map_err(|e| JmapError::Parse(e.to_string()))?;
+                log_info!(
+                    "[JMAP] {} returned {} created, {} updated, {} destroyed (more: {})",
+                    method,
+                    changes.created.len(),
+                    changes.updated.len(),
+                    changes.destroyed.len(),
+                    changes.has_more_changes
+                );
+                return Ok(Some(changes));
+            }
+            if method_response.0 == "error" {
+                let error_type = method_response
+                    .1
+                    .get("type")
+                    .and_then(|t| t.as_str())
+                    .unwrap_or("unknown");
+                if error_type == "cannotCalculateChanges" {
+                    log_info!("[JMAP] {}: server cannot calculate changes", method);
+                    return Ok(None);
+                }
+                return Err(JmapError::Api(format!("{} failed: {}", method, error_type)));
             }
         }
 
@@ -373,26 +782,49 @@ impl JmapClient {
         );
 
         let mut conditions = vec![json!({ "inMailbox": mailbox_id })];
-        if let Some(text) = search_text {
-            conditions.push(json!({ "text": text }));
-        }
-        if let Some(after) = received_after {
-            conditions.push(json!({ "after": after }));
-        }
-        if let Some(before) = received_before {
-            conditions.push(json!({ "before": before }));
-        }
+        conditions.extend(search_conditions(
+            search_text,
+            received_after,
+            received_before,
+        ));
+        self.run_email_query(conditions, limit, position)
+    }
 
-        let filter = if conditions.len() == 1 {
-            conditions
-                .into_iter()
-                .next()
-                .unwrap_or_else(|| json!({ "inMailbox": mailbox_id }))
-        } else {
-            json!({
+    /// Email/query across every mailbox in the account. Without any
+    /// condition this lists the whole account, newest first.
+    pub fn search_all_emails(
+        &self,
+        limit: u32,
+        position: u32,
+        search_text: Option<&str>,
+        received_after: Option<&str>,
+        received_before: Option<&str>,
+    ) -> Result<EmailQueryResult, JmapError> {
+        log_info!(
+            "[JMAP] Email/query across all mailboxes (limit: {}, position: {}, search: {:?}, after: {:?}, before: {:?})",
+            limit,
+            position,
+            search_text,
+            received_after,
+            received_before
+        );
+        let conditions = search_conditions(search_text, received_after, received_before);
+        self.run_email_query(conditions, limit, position)
+    }
+
+    fn run_email_query(
+        &self,
+        conditions: Vec<Value>,
+        limit: u32,
+        position: u32,
+    ) -> Result<EmailQueryResult, JmapError> {
+        let filter = match conditions.len() {
+            0 => json!({}),
+            1 => conditions.into_iter().next().unwrap_or_else(|| json!({})),
+            _ => json!({
                 "operator": "AND",
                 "conditions": conditions
-            })
+            }),
         };
 
         let request = JmapRequest {
@@ -557,7 +989,12 @@ impl JmapClient {
             "attachments",
         ];
 
-        let extra_strs: Vec<&str> = extra_properties.iter().map(|s| s.as_str()).collect();
+        // Body properties requested by `body` rules are already in the list.
+        let extra_strs: Vec<&str> = extra_properties
+            .iter()
+            .map(|s| s.as_str())
+            .filter(|p| !properties.contains(p))
+            .collect();
         properties.extend(extra_strs);
 
         let request = JmapRequest {
@@ -593,6 +1030,9 @@ impl JmapClient {
         Err(JmapError::Api("Unexpected response".to_string()))
     }
 
+    /// Email/get for rule evaluation. `extra_properties` come from
+    /// `rules::extract_custom_headers`; when they include `bodyValues`, text
+    /// and HTML body values are fetched, capped at `RULES_MAX_BODY_BYTES`.
     pub fn get_emails_for_rules(
         &self,
         ids: &[String],
@@ -620,22 +1060,26 @@ impl JmapClient {
             "receivedAt",
             "keywords",
             "mailboxIds",
+            "hasAttachment",
         ];
 
         let extra_strs: Vec<&str> = extra_properties.iter().map(|s| s.as_str()).collect();
         properties.extend(extra_strs);
 
+        let mut args = json!({
+            "accountId": self.account_id,
+            "ids": ids,
+            "properties": properties
+        });
+        if extra_properties.iter().any(|p| p == "bodyValues") {
+            args["fetchTextBodyValues"] = json!(true);
+            args["fetchHTMLBodyValues"] = json!(true);
+            args["maxBodyValueBytes"] = json!(RULES_MAX_BODY_BYTES);
+        }
+
         let request = JmapRequest {
             using: vec!["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail"],
-            method_calls: vec![MethodCall(
-                "Email/get",
-                json!({
-                    "accountId": self.account_id,
-                    "ids": ids,
-                    "properties": properties
-                }),
-                "0".to_string(),
-            )],
+            method_calls: vec![MethodCall("Email/get", args, "0".to_string())],
         };
 
         let response = self.call(request)?;
@@ -863,12 +1307,66 @@ impl JmapClient {
         ))
     }
 
+    pub fn set_email_keyword(&self, id: &str, keyword: &str, value: bool) -> Result<(), JmapError> {
+        log_info!("[JMAP] Email/set keyword {}={} for: {}", keyword, value, id);
+        validate_keyword(keyword).map_err(JmapError::Api)?;
+
+        let mut patch = serde_json::Map::new();
+        patch.insert(
+            keyword_patch_path(keyword),
+            if value { json!(true) } else { json!(null) },
+        );
+
+        let request = JmapRequest {
+            using: vec!["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail"],
+            method_calls: vec![MethodCall(
+                "Email/set",
+                json!({
+                    "accountId": self.account_id,
+                    "update": {
+                        id: patch
+                    }
+                }),
+                "0".to_string(),
+            )],
+        };
+
+        let response = self.call(request)?;
+
+        if let Some(method_response) = response.method_responses.first() {
+            if method_response.0 == "Email/set" {
+                if let Some(not_updated) = method_response.1.get("notUpdated") {
+                    if not_updated.get(id).is_some() {
+                        return Err(JmapError::Api(format!(
+                            "Failed to set keyword {}: {:?}",
+                            keyword, not_updated
+                        )));
+                    }
+                }
+                return Ok(());
+            }
+        }
+
+        Err(JmapError::Api(
+            "Unexpected response for Email/set".to_string(),
+        ))
+    }
+
     pub fn move_email(&self, id: &str, to_mailbox_id: &str) -> Result<(), JmapError> {
+        self.set_mailboxes(id, &[to_mailbox_id.to_string()])
+    }
+
+    /// Replace the set of mailboxes an email is in.
+    pub fn set_mailboxes(&self, id: &str, mailbox_ids: &[String]) -> Result<(), JmapError> {
         log_info!(
-            "[JMAP] Email/set moving {} to mailbox {}",
+            "[JMAP] Email/set moving {} to mailboxes {:?}",
             id,
-            to_mailbox_id
+            mailbox_ids
         );
+        let mailbox_ids: serde_json::Map<String, Value> = mailbox_ids
+            .iter()
+            .map(|m| (m.clone(), Value::Bool(true)))
+            .collect();
 
         let request = JmapRequest {
             using: vec!["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail"],
@@ -878,7 +1376,7 @@ impl JmapClient {
                     "accountId": self.account_id,
                     "update": {
                         id: {
-                            "mailboxIds": { to_mailbox_id: true }
+                            "mailboxIds": mailbox_ids
                         }
                     }
                 }),
@@ -907,6 +1405,67 @@ impl JmapClient {
         ))
     }
 
+    /// Set or clear `keywords` on each of `ids` and move them all to
+    /// `to_mailbox_id`, in a single Email/set.
+    pub fn set_keywords_and_move(
+        &self,
+        ids: &[String],
+        keywords: &[(&str, bool)],
+        to_mailbox_id: &str,
+    ) -> Result<(), JmapError> {
+        log_info!(
+            "[JMAP] Email/set keywords {:?} and moving {} emails to {}",
+            keywords,
+            ids.len(),
+            to_mailbox_id
+        );
+        let mut patch = serde_json::Map::new();
+        for (keyword, value) in keywords {
+            validate_keyword(keyword).map_err(JmapError::Api)?;
+            patch.insert(
+                keyword_patch_path(keyword),
+                if *value { json!(true) } else { json!(null) },
+            );
+        }
+        patch.insert("mailboxIds".to_string(), json!({ to_mailbox_id: true }));
+        let update: serde_json::Map<String, Value> = ids
+            .iter()
+            .map(|id| (id.clone(), Value::Object(patch.clone())))
+            .collect();
+
+        let request = JmapRequest {
+            using: vec!["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail"],
+            method_calls: vec![MethodCall(
+                "Email/set",
+                json!({
+                    "accountId": self.account_id,
+                    "update": update
+                }),
+                "0".to_string(),
+            )],
+        };
+
+        let response = self.call(request)?;
+
+        if let Some(method_response) = response.method_responses.first() {
+            if method_response.0 == "Email/set" {
+                if let Some(not_updated) = method_response.1.get("notUpdated") {
+                    if ids.iter().any(|id| not_updated.get(id).is_some()) {
+                        return Err(JmapError::Api(format!(
+                            "Failed to update emails: {:?}",
+                            not_updated
+                        )));
+                    }
+                }
+                return Ok(());
+            }
+        }
+
+        Err(JmapError::Api(
+            "Unexpected response for Email/set".to_string(),
+        ))
+    }
+
     pub fn destroy_emails(&self, ids: &[String]) -> Result<(), JmapError> {
         if ids.is_empty() {
             return Ok(());
@@ -967,55 +1526,65 @@ impl JmapClient {
 
         log_debug!("[JMAP] Downloading blob from: {}", url);
 
-        let auth = Self::auth_header(&self.username, &self.password);
-        let agent = ureq::AgentBuilder::new().redirects(0).build();
+        let agent = self.http.agent_builder().redirects(0).build();
+
+        with_auth(&self.credentials, |auth| {
+            let mut current_url = url.clone();
+            for _ in 0..5 {
+                let response = with_retry(self.http.max_retries, || {
+                    agent
+                        .get(&current_url)
+                        .set("Authorization", auth)
+                        .call()
+                        .map_err(Box::new)
+                });
+
+                match response.map_err(|e| *e) {
+                    Ok(resp) => {
+                        let status = resp.status();
+                        if (300..400).contains(&status) {
+                            if let Some(location) = resp.header("location") {
+                                current_url = Self::resolve_redirect(&current_url, location);
+                                continue;
+                            }
+                            return Err(JmapError::Http(format!(
+                                "Redirect {} without Location header",
+                                status
+                            )));
+                        }
 
-        let mut current_url = url;
-        for _ in 0..5 {
-            let response = agent.get(&current_url).set("Authorization", &auth).call();
+                        let mut bytes = Vec::new();
+                        resp.into_reader()
+                            .read_to_end(&mut bytes)
+                            .map_err(|e| JmapError::Parse(format!("Failed to read blob: {}", e)))?;
 
-            match response {
-                Ok(resp) => {
-                    let status = resp.status();
-                    if (300..400).contains(&status) {
+                        log_info!("[JMAP] Blob downloaded, {} bytes", bytes.len());
+                        return Ok(bytes);
+                    }
+                    Err(ureq::Error::Status(code, resp)) if (300..400).contains(&code) => {
                         if let Some(location) = resp.header("location") {
                             current_url = Self::resolve_redirect(&current_url, location);
-                            continue;
+                        } else {
+                            return Err(JmapError::Http(format!(
+                                "Redirect {} without Location header",
+                                code
+                            )));
                         }
-                        return Err(JmapError::Http(format!(
-                            "Redirect {} without Location header",
-                            status
-                        )));
                     }
-
-                    let mut bytes = Vec::new();
-                    resp.into_reader()
-                        .read_to_end(&mut bytes)
-                        .map_err(|e| JmapError::Parse(format!("Failed to read blob: {}", e)))?;
-
-                    log_info!("[JMAP] Blob downloaded, {} bytes", bytes.len());
-                    return Ok(bytes);
-                }
-                Err(ureq::Error::Status(code, resp)) if (300..400).contains(&code) => {
-                    if let Some(location) = resp.header("location") {
-                        current_url = Self::resolve_redirect(&current_url, location);
-                    } else {
-                        return Err(JmapError::Http(format!(
-                            "Redirect {} without Location header",
-                            code
-                        )));
+                    Err(ureq::Error::Status(401, _)) => {
+                        return Err(JmapError::Unauthorized);
+                    }
+                    Err(ureq::Error::Status(code, _)) => {
+                        return Err(JmapError::Http(format!("HTTP {} error", code)));
+                    }
+                    Err(e) => {
+                        return Err(http_error(e));
                     }
-                }
-                Err(ureq::Error::Status(code, _)) => {
-                    return Err(JmapError::Http(format!("HTTP {} error", code)));
-                }
-                Err(e) => {
-                    return Err(JmapError::Http(e.to_string()));
                 }
             }
-        }
 
-        Err(JmapError::Http("Too many redirects".to_string()))
+            Err(JmapError::Http("Too many redirects".to_string()))
+        })
     }
 
     pub fn get_email_keywords(&self, ids: &[String]) -> Result<Vec<Email>, JmapError> {
@@ -1132,7 +1701,8 @@ impl JmapClient {
         self.get_emails(&ids)
     }
 
-    pub fn get_email_raw(&self, id: &str) -> Result<Option<String>, JmapError> {
+    /// The message's exact RFC 5322 source, as the server stores it.
+    pub fn get_email_raw(&self, id: &str) -> Result<Option<Vec<u8>>, JmapError> {
         log_info!("[JMAP] Fetching raw email via blob: {}", id);
 
         // First, get the blobId for this email
@@ -1173,26 +1743,480 @@ impl JmapClient {
             }
         };
 
-        let download_url = match &self.download_url {
+        let body = self.download_blob(&blob_id, "email.eml", "message/rfc822")?;
+        log_info!("[JMAP] Raw email downloaded, {} bytes", body.len());
+        Ok(Some(body))
+    }
+    pub fn upload_blob(&self, bytes: &[u8], content_type: &str) -> Result<String, JmapError> {
+        let upload_url = match &self.upload_url {
             Some(url) => url,
             None => {
-                return Err(JmapError::Api("No download URL available".to_string()));
+                return Err(JmapError::Api("No upload URL available".to_string()));
             }
         };
+        let url = upload_url.replace("{accountId}", &self.account_id);
+
+        log_debug!("[JMAP] Uploading {} bytes to: {}", bytes.len(), url);
+
+        let agent = self.http.agent_builder().build();
+        let response = with_auth(&self.credentials, |auth| {
+            with_retry(self.http.max_retries, || {
+                agent
+                    .post(&url)
+                    .set("Authorization", auth)
+                    .set("Content-Type", content_type)
+                    .send_bytes(bytes)
+                    .map_err(Box::new)
+            })
+            .map_err(|e| {
+                log_error!("[JMAP] Upload failed: {}", e);
+                http_error(*e)
+            })
+        })?;
 
-        let url = download_url
-            .replace("{accountId}", &self.account_id)
-            .replace("{blobId}", &blob_id)
-            .replace("{name}", "email.eml")
-            .replace("{type}", "message/rfc822");
+        let response_text = read_response_body(response)
+            .map_err(|e| JmapError::Parse(format!("Failed to read response: {}", e)))?;
+        let parsed: serde_json::Value = serde_json::from_str(&response_text)
+            .map_err(|e| JmapError::Parse(format!("Failed to parse upload response: {}", e)))?;
 
-        log_debug!("[JMAP] Downloading blob from: {}", url);
+        parsed["blobId"]
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| JmapError::Api("Upload response missing blobId".to_string()))
+    }
 
-        let auth = Self::auth_header(&self.username, &self.password);
-        let (_, body) = Self::fetch_with_auth_following_redirects(&url, &auth, 5)?;
+    pub fn get_identities(&self) -> Result<Vec<Identity>, JmapError> {
+        log_info!(
+            "[JMAP] Fetching identities for account: {}",
+            self.account_id
+        );
 
-        log_info!("[JMAP] Raw email downloaded, {} bytes", body.len());
-        Ok(Some(body))
+        let request = JmapRequest {
+            using: vec!["urn:ietf:params:jmap:core", SUBMISSION_CAPABILITY],
+            method_calls: vec![MethodCall(
+                "Identity/get",
+                json!({
+                    "accountId": self.account_id,
+                    "ids": null
+                }),
+                "0".to_string(),
+            )],
+        };
+
+        let response = self.call(request)?;
+
+        if let Some(method_response) = response.method_responses.first() {
+            if method_response.0 == "Identity/get" {
+                let identity_response: IdentityGetResponse =
+                    serde_json::from_value(method_response.1.clone())
+                        .map_err(|e| JmapError::Parse(e.to_string()))?;
+                return Ok(identity_response.list);
+            }
+        }
+
+        Err(JmapError::Api("Unexpected response".to_string()))
+    }
+
+    fn require_vacation(&self) -> Result<(), JmapError> {
+        if self.can_vacation {
+            Ok(())
+        } else {
+            Err(JmapError::Api(format!(
+                "Server does not support vacation responses (missing {} capability)",
+                VACATION_CAPABILITY
+            )))
+        }
+    }
+
+    pub fn get_vacation_response(&self) -> Result<VacationResponse, JmapError> {
+        self.require_vacation()?;
+        log_info!(
+            "[JMAP] Fetching vacation response for account: {}",
+            self.account_id
+        );
+
+        let request = JmapRequest {
+            using: vec!["urn:ietf:params:jmap:core", VACATION_CAPABILITY],
+            method_calls: vec![MethodCall(
+                "VacationResponse/get",
+                json!({
+                    "accountId": self.account_id,
+                    "ids": ["singleton"]
+                }),
+                "0".to_string(),
+            )],
+        };
+
+        let response = self.call(request)?;
+
+        if let Some(method_response) = response.method_responses.first() {
+            if method_response.0 == "VacationResponse/get" {
+                let get_response: VacationResponseGetResponse =
+                    serde_json::from_value(method_response.1.clone())
+                        .map_err(|e| JmapError::Parse(e.to_string()))?;
+                return get_response.list.into_iter().next().ok_or_else(|| {
+                    JmapError::Api("Server returned no VacationResponse".to_string())
+                });
+            }
+        }
+
+        Err(JmapError::Api("Unexpected response".to_string()))
+    }
+
+    /// Replace the account's vacation response (the `singleton` object).
+    pub fn set_vacation_response(&self, vacation: &VacationResponse) -> Result<(), JmapError> {
+        self.require_vacation()?;
+        log_info!(
+            "[JMAP] VacationResponse/set enabled={} for account: {}",
+            vacation.is_enabled,
+            self.account_id
+        );
+
+        let request = JmapRequest {
+            using: vec!["urn:ietf:params:jmap:core", VACATION_CAPABILITY],
+            method_calls: vec![MethodCall(
+                "VacationResponse/set",
+                json!({
+                    "accountId": self.account_id,
+                    "update": {
+                        "singleton": vacation
+                    }
+                }),
+                "0".to_string(),
+            )],
+        };
+
+        let response = self.call(request)?;
+
+        if let Some(method_response) = response.method_responses.first() {
+            if method_response.0 == "VacationResponse/set" {
+                if let Some(err) = method_response
+                    .1
+                    .get("notUpdated")
+                    .and_then(|v| v.get("singleton"))
+                {
+                    return Err(JmapError::Api(format!(
+                        "Failed to update vacation response: {}",
+                        err
+                    )));
+                }
+                return Ok(());
+            }
+        }
+
+        Err(JmapError::Api(
+            "Unexpected response for VacationResponse/set".to_string(),
+        ))
+    }
+
+    /// Fetch every quota that applies to the account. Returns `None` when the
+    /// server does not advertise quota support.
+    pub fn get_quota(&self) -> Result<Option<Vec<Quota>>, JmapError> {
+        if !self.can_quota {
+            return Ok(None);
+        }
+        log_info!("[JMAP] Fetching quotas for account: {}", self.account_id);
+
+        let request = JmapRequest {
+            using: vec!["urn:ietf:params:jmap:core", QUOTA_CAPABILITY],
+            method_calls: vec![MethodCall(
+                "Quota/get",
+                json!({
+                    "accountId": self.account_id,
+                    "ids": null
+                }),
+                "0".to_string(),
+            )],
+        };
+
+        let response = self.call(request)?;
+
+        if let Some(method_response) = response.method_responses.first() {
+            if method_response.0 == "Quota/get" {
+                let get_response: QuotaGetResponse =
+                    serde_json::from_value(method_response.1.clone())
+                        .map_err(|e| JmapError::Parse(e.to_string()))?;
+                return Ok(Some(get_response.list));
+            }
+        }
+
+        Err(JmapError::Api("Unexpected response".to_string()))
+    }
+
+    fn require_submission(&self) -> Result<(), JmapError> {
+        if self.can_submit {
+            Ok(())
+        } else {
+            Err(JmapError::Api(format!(
+                "Server does not support sending mail (missing {} capability)",
+                SUBMISSION_CAPABILITY
+            )))
+        }
+    }
+
+    /// The identity whose address matches `from_email`, else the first one.
+    fn sending_identity(&self, from_email: Option<&str>) -> Result<Identity, JmapError> {
+        let identities = self.get_identities()?;
+        let matching = from_email.and_then(|addr| {
+            identities
+                .iter()
+                .position(|i| i.email.eq_ignore_ascii_case(addr))
+        });
+        identities
+            .into_iter()
+            .nth(matching.unwrap_or(0))
+            .ok_or_else(|| JmapError::Api("No sending identity available".to_string()))
+    }
+
+    /// Send a complete RFC 5322 message: upload it as a blob, import it into
+    /// `mailbox_id` (normally Sent), and submit it using the identity that
+    /// matches `from_email` (or the first identity). Returns the new email id.
+    pub fn send_email(
+        &self,
+        raw_mime: &str,
+        from_email: Option<&str>,
+        mailbox_id: &str,
+    ) -> Result<String, JmapError> {
+        self.require_submission()?;
+        let identity = self.sending_identity(from_email)?;
+        let blob_id = self.upload_blob(raw_mime.as_bytes(), "message/rfc822")?;
+
+        log_info!(
+            "[JMAP] Email/import + EmailSubmission/set sending as {}",
+            identity.email
+        );
+
+        self.create_and_submit(
+            "Email/import",
+            json!({
+                "accountId": self.account_id,
+                "emails": {
+                    "outgoing": {
+                        "blobId": blob_id,
+                        "mailboxIds": { mailbox_id: true },
+                        "keywords": { "$seen": true }
+                    }
+                }
+            }),
+            &identity.id,
+        )
+    }
+
+    /// Send a message with attachments: upload each file as a blob, create
+    /// the email in `mailbox_id` with `Email/set` referencing those blobs,
+    /// and submit it. Returns the new email id.
+    pub fn send_email_with_attachments(
+        &self,
+        email: &OutgoingEmail,
+        mailbox_id: &str,
+    ) -> Result<String, JmapError> {
+        self.require_submission()?;
+        let from_email = email.from.first().and_then(|a| a.email.as_deref());
+        let identity = self.sending_identity(from_email)?;
+        let create = self.email_create(email, mailbox_id, json!({ "$seen": true }))?;
+
+        log_info!(
+            "[JMAP] Email/set + EmailSubmission/set sending as {} with {} attachment(s)",
+            identity.email,
+            email.attachments.len()
+        );
+
+        self.create_and_submit(
+            "Email/set",
+            json!({
+                "accountId": self.account_id,
+                "create": { "outgoing": create }
+            }),
+            &identity.id,
+        )
+    }
+
+    /// Store a message in `mailbox_id` (normally Drafts) with the `$draft`
+    /// keyword, without submitting it. Returns the new email id.
+    pub fn save_draft(&self, email: &OutgoingEmail, mailbox_id: &str) -> Result<String, JmapError> {
+        let create =
+            self.email_create(email, mailbox_id, json!({ "$draft": true, "$seen": true }))?;
+        log_info!(
+            "[JMAP] Email/set saving draft '{}' to {}",
+            email.subject,
+            mailbox_id
+        );
+
+        let request = JmapRequest {
+            using: vec!["urn:ietf:params:jmap:core", "urn:ietf:params:jmap:mail"],
+            method_calls: vec![MethodCall(
+                "Email/set",
+                json!({
+                    "accountId": self.account_id,
+                    "create": { "draft": create }
+                }),
+                "0".to_string(),
+            )],
+        };
+        let response = self.call(request)?;
+
+        match response.method_responses.first() {
+            Some(method_response) if method_response.0 == "Email/set" => {
+                if let Some(not_created) = method_response
+                    .1
+                    .get("notCreated")
+                    .and_then(|v| v.get("draft"))
+                {
+                    return Err(JmapError::Api(format!(
+                        "Failed to save draft: {}",
+                        not_created
+                    )));
+                }
+                method_response.1["created"]["draft"]["id"]
+                    .as_str()
+                    .map(|s| s.to_string())
+                    .ok_or_else(|| JmapError::Api("Email/set did not return an email id".into()))
+            }
+            _ => Err(JmapError::Api(
+                "Unexpected response for Email/set".to_string(),
+            )),
+        }
+    }
+
+    /// `Email/set` creation object for `email` in `mailbox_id`, uploading
+    /// each attachment as a blob first.
+    fn email_create(
+        &self,
+        email: &OutgoingEmail,
+        mailbox_id: &str,
+        keywords: Value,
+    ) -> Result<Value, JmapError> {
+        let mut attachments = Vec::new();
+        for attachment in &email.attachments {
+            let blob_id = self
+                .upload_blob(&attachment.data, &attachment.content_type)
+                .map_err(|e| {
+                    JmapError::Api(format!("Failed to upload {}: {}", attachment.name, e))
+                })?;
+            attachments.push(json!({
+                "blobId": blob_id,
+                "type": attachment.content_type,
+                "name": attachment.name,
+                "disposition": "attachment"
+            }));
+        }
+
+        let mut create = json!({
+            "mailboxIds": { mailbox_id: true },
+            "keywords": keywords,
+            "from": email.from,
+            "to": email.to,
+            "subject": email.subject,
+            "bodyValues": { "body": { "value": email.text_body } },
+            "textBody": [{ "partId": "body", "type": "text/plain" }],
+            "attachments": attachments
+        });
+        if email.flowed {
+            // Body part types carry no parameters, so format=flowed goes in
+            // the part's own Content-Type header.
+            create["textBody"] = json!([{
+                "partId": "body",
+                "headers": [{
+                    "name": "Content-Type",
+                    "value": "text/plain; charset=utf-8; format=flowed"
+                }]
+            }]);
+        }
+        if let Some(html) = &email.html_body {
+            create["bodyValues"]["html"] = json!({ "value": html });
+            create["htmlBody"] = json!([{ "partId": "html", "type": "text/html" }]);
+        }
+        if !email.cc.is_empty() {
+            create["cc"] = json!(email.cc);
+        }
+        if !email.in_reply_to.is_empty() {
+            create["inReplyTo"] = json!(email.in_reply_to);
+        }
+        if !email.references.is_empty() {
+            create["references"] = json!(email.references);
+        }
+        Ok(create)
+    }
+
+    /// Run `create_method` (which must create an email under the `outgoing`
+    /// key) followed by an EmailSubmission for it, in one request.
+    fn create_and_submit(
+        &self,
+        create_method: &'static str,
+        create_args: Value,
+        identity_id: &str,
+    ) -> Result<String, JmapError> {
+        let request = JmapRequest {
+            using: vec![
+                "urn:ietf:params:jmap:core",
+                "urn:ietf:params:jmap:mail",
+                SUBMISSION_CAPABILITY,
+            ],
+            method_calls: vec![
+                MethodCall(create_method, create_args, "0".to_string()),
+                MethodCall(
+                    "EmailSubmission/set",
+                    json!({
+                        "accountId": self.account_id,
+                        "create": {
+                            "submission": {
+                                "identityId": identity_id,
+                                "emailId": "#outgoing"
+                            }
+                        }
+                    }),
+                    "1".to_string(),
+                ),
+            ],
+        };
+
+        let response = self.call(request)?;
+
+        let email_id = match response.method_responses.first() {
+            Some(method_response) if method_response.0 == create_method => {
+                if let Some(not_created) = method_response
+                    .1
+                    .get("notCreated")
+                    .and_then(|v| v.get("outgoing"))
+                {
+                    return Err(JmapError::Api(format!(
+                        "Failed to create message: {}",
+                        not_created
+                    )));
+                }
+                method_response.1["created"]["outgoing"]["id"]
+                    .as_str()
+                    .map(|s| s.to_string())
+                    .ok_or_else(|| {
+                        JmapError::Api(format!("{} did not return an email id", create_method))
+                    })?
+            }
+            _ => {
+                return Err(JmapError::Api(format!(
+                    "Unexpected response for {}",
+                    create_method
+                )))
+            }
+        };
+
+        match response.method_responses.get(1) {
+            Some(method_response) if method_response.0 == "EmailSubmission/set" => {
+                if let Some(not_created) = method_response
+                    .1
+                    .get("notCreated")
+                    .and_then(|v| v.get("submission"))
+                {
+                    return Err(JmapError::Api(format!(
+                        "Failed to submit message: {}",
+                        not_created
+                    )));
+                }
+                Ok(email_id)
+            }
+            _ => Err(JmapError::Api(
+                "Unexpected response for EmailSubmission/set".to_string(),
+            )),
+        }
     }
 }
 
@@ -1208,3 +2232,65 @@ fn truncate_str(s: &str, max_len: usize) -> &str {
         &s[..end]
     }
 }
+
+/// Optional text and receivedAt-range conditions shared by Email/query calls.
+fn search_conditions(
+    search_text: Option<&str>,
+    received_after: Option<&str>,
+    received_before: Option<&str>,
+) -> Vec<Value> {
+    let mut conditions = Vec::new();
+    if let Some(text) = search_text {
+        conditions.push(json!({ "text": text }));
+    }
+    if let Some(after) = received_after {
+        conditions.push(json!({ "after": after }));
+    }
+    if let Some(before) = received_before {
+        conditions.push(json!({ "before": before }));
+    }
+    conditions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(methods: &[&'static str]) -> JmapRequest {
+        JmapRequest {
+            using: vec![],
+            method_calls: methods
+                .iter()
+                .map(|m| MethodCall(m, json!({}), "0".to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn only_read_only_requests_are_retried() {
+        assert!(is_read_only(&request(&["Email/query", "Email/get"])));
+        assert!(is_read_only(&request(&["Mailbox/changes"])));
+        assert!(!is_read_only(&request(&["Email/set"])));
+        assert!(!is_read_only(&request(&[
+            "Email/set",
+            "EmailSubmission/set"
+        ])));
+        assert!(!is_read_only(&request(&["Email/get", "Email/set"])));
+    }
+
+    #[test]
+    fn retry_delay_honours_retry_after() {
+        let now = 1_736_674_200;
+        assert_eq!(retry_delay(0, Some("3"), now), Duration::from_secs(3));
+        assert_eq!(
+            retry_delay(0, Some("Sun, 12 Jan 2025 09:30:10 GMT"), now),
+            Duration::from_secs(10)
+        );
+        assert_eq!(
+            retry_delay(0, Some("Sun, 12 Jan 2025 09:00:00 GMT"), now),
+            Duration::ZERO
+        );
+        assert_eq!(retry_delay(1, None, now), RETRY_BASE_DELAY * 2);
+        assert_eq!(retry_delay(0, Some("3600"), now), RETRY_MAX_DELAY);
+    }
+}
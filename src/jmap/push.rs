@@ -0,0 +1,101 @@
+//! JMAP push over EventSource (RFC 8620 section 7.3).
+
+use super::client::JmapError;
+use serde_json::Value;
+use std::io::BufRead;
+
+/// An open `text/event-stream` connection to the server's eventSourceUrl.
+pub struct EventSource {
+    reader: Box<dyn BufRead + Send>,
+    account_id: String,
+}
+
+impl EventSource {
+    pub(crate) fn new(reader: Box<dyn BufRead + Send>, account_id: String) -> Self {
+        EventSource { reader, account_id }
+    }
+
+    /// Block until the server reports a state change for this account and
+    /// return the data types that changed (e.g. `Mailbox`, `Email`). Pings and
+    /// changes for other accounts are skipped.
+    pub fn next_change(&mut self) -> Result<Vec<String>, JmapError> {
+        let mut event = String::new();
+        let mut data = String::new();
+        loop {
+            let mut line = String::new();
+            let read = self
+                .reader
+                .read_line(&mut line)
+                .map_err(|e| JmapError::Http(e.to_string()))?;
+            if read == 0 {
+                return Err(JmapError::Http("Event source closed".to_string()));
+            }
+
+            let line = line.trim_end_matches(['\r', '\n']);
+            if line.is_empty() {
+                if matches!(event.as_str(), "" | "state") {
+                    let types = changed_types(&data, &self.account_id);
+                    if !types.is_empty() {
+                        return Ok(types);
+                    }
+                }
+                event.clear();
+                data.clear();
+            } else if let Some(value) = line.strip_prefix("event:") {
+                event = value.trim().to_string();
+            } else if let Some(value) = line.strip_prefix("data:") {
+                if !data.is_empty() {
+                    data.push('\n');
+                }
+                data.push_str(value.strip_prefix(' ').unwrap_or(value));
+            }
+            // `id:`, `retry:` and `:` comment lines carry nothing we need.
+        }
+    }
+}
+
+/// Data types listed for `account_id` in a StateChange object.
+fn changed_types(data: &str, account_id: &str) -> Vec<String> {
+    let mut types: Vec<String> = serde_json::from_str::<Value>(data)
+        .ok()
+        .as_ref()
+        .and_then(|v| v.get("changed")?.get(account_id)?.as_object())
+        .map(|changed| changed.keys().cloned().collect())
+        .unwrap_or_default();
+    types.sort();
+    types
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn source(stream: &str) -> EventSource {
+        EventSource::new(
+            Box::new(Cursor::new(stream.as_bytes().to_vec())),
+            "account-001".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_next_change_skips_pings_and_other_accounts() {
+        let mut es = source(
+            ": connected\r\n\r\n\
+             event: ping\r\ndata: {\"interval\":30}\r\n\r\n\
+             event: state\r\ndata: {\"@type\":\"StateChange\",\"changed\":{\"other\":{\"Email\":\"s9\"}}}\r\n\r\n\
+             event: state\r\ndata: {\"@type\":\"StateChange\",\"changed\":\r\ndata: {\"account-001\":{\"Mailbox\":\"s2\",\"Email\":\"s1\"}}}\r\n\r\n",
+        );
+        assert_eq!(
+            es.next_change().unwrap(),
+            vec!["Email".to_string(), "Mailbox".to_string()]
+        );
+        assert!(es.next_change().is_err());
+    }
+
+    #[test]
+    fn test_changed_types_ignores_malformed_data() {
+        assert!(changed_types("not json", "account-001").is_empty());
+        assert!(changed_types("{\"changed\":{}}", "account-001").is_empty());
+    }
+}
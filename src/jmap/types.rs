@@ -10,6 +10,12 @@ pub struct JmapSession {
     #[serde(default)]
     pub download_url: Option<String>,
     #[serde(default)]
+    pub upload_url: Option<String>,
+    #[serde(default)]
+    pub event_source_url: Option<String>,
+    #[serde(default)]
+    pub capabilities: HashMap<String, serde_json::Value>,
+    #[serde(default)]
     pub primary_accounts: HashMap<String, String>,
     #[serde(default)]
     pub accounts: HashMap<String, JmapAccount>,
@@ -91,7 +97,6 @@ pub struct Mailbox {
 pub struct MailboxGetResponse {
     #[allow(dead_code)]
     pub account_id: String,
-    #[allow(dead_code)]
     pub state: String,
     pub list: Vec<Mailbox>,
     #[serde(default)]
@@ -171,6 +176,34 @@ pub struct EmailAddress {
     pub email: Option<String>,
 }
 
+/// Check `keyword` against the RFC 8621 keyword syntax: 1-255 printable
+/// ASCII characters other than `( ) { ] % * " \`.
+pub fn validate_keyword(keyword: &str) -> Result<(), String> {
+    if keyword.is_empty() || keyword.len() > 255 {
+        return Err(format!(
+            "keyword '{}' must be 1-255 characters",
+            keyword.escape_debug()
+        ));
+    }
+    if let Some(c) = keyword
+        .chars()
+        .find(|&c| !c.is_ascii_graphic() || "(){]%*\"\\".contains(c))
+    {
+        return Err(format!(
+            "keyword '{}' contains '{}', which keywords cannot hold",
+            keyword.escape_debug(),
+            c.escape_debug()
+        ));
+    }
+    Ok(())
+}
+
+/// The `Email/set` patch path for `keyword`, escaped as a JSON pointer
+/// segment (RFC 6901: `~` -> `~0`, `/` -> `~1`).
+pub fn keyword_patch_path(keyword: &str) -> String {
+    format!("keywords/{}", keyword.replace('~', "~0").replace('/', "~1"))
+}
+
 impl std::fmt::Display for EmailAddress {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match (&self.name, &self.email) {
@@ -182,6 +215,32 @@ impl std::fmt::Display for EmailAddress {
     }
 }
 
+/// A file to attach to an outgoing message, already read from disk.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutgoingAttachment {
+    pub name: String,
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+/// A message to create with `Email/set` and submit, used when sending with
+/// attachments. Message ids keep their angle brackets stripped per JMAP.
+#[derive(Debug, Clone, Default)]
+pub struct OutgoingEmail {
+    pub from: Vec<EmailAddress>,
+    pub to: Vec<EmailAddress>,
+    pub cc: Vec<EmailAddress>,
+    pub subject: String,
+    pub in_reply_to: Vec<String>,
+    pub references: Vec<String>,
+    pub text_body: String,
+    /// `text_body` is already encoded as RFC 3676 `format=flowed`.
+    pub flowed: bool,
+    /// HTML alternative to `text_body`, rendered from Markdown drafts.
+    pub html_body: Option<String>,
+    pub attachments: Vec<OutgoingAttachment>,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct BodyPart {
@@ -194,6 +253,23 @@ pub struct BodyPart {
     pub name: Option<String>,
     #[serde(default)]
     pub size: Option<u64>,
+    #[serde(default)]
+    pub disposition: Option<String>,
+    /// Content-ID that HTML bodies reference inline images by.
+    #[serde(default)]
+    pub cid: Option<String>,
+}
+
+impl BodyPart {
+    /// An inline image without a filename, e.g. a logo embedded in HTML.
+    pub fn is_unnamed_inline_image(&self) -> bool {
+        self.name.as_deref().is_none_or(|n| n.trim().is_empty())
+            && (self.disposition.as_deref() == Some("inline") || self.cid.is_some())
+            && self
+                .r#type
+                .as_deref()
+                .is_some_and(|t| t.starts_with("image/"))
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -211,7 +287,6 @@ pub struct BodyValue {
 pub struct EmailGetResponse {
     #[allow(dead_code)]
     pub account_id: String,
-    #[allow(dead_code)]
     pub state: String,
     pub list: Vec<Email>,
     #[serde(default)]
@@ -219,6 +294,32 @@ pub struct EmailGetResponse {
     pub not_found: Vec<String>,
 }
 
+/// `Email/changes` or `Mailbox/changes` response: ids touched since
+/// `old_state`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangesResponse {
+    #[allow(dead_code)]
+    pub account_id: String,
+    #[allow(dead_code)]
+    pub old_state: String,
+    pub new_state: String,
+    #[serde(default)]
+    pub has_more_changes: bool,
+    #[serde(default)]
+    pub created: Vec<String>,
+    #[serde(default)]
+    pub updated: Vec<String>,
+    #[serde(default)]
+    pub destroyed: Vec<String>,
+}
+
+impl ChangesResponse {
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty() && self.updated.is_empty() && self.destroyed.is_empty()
+    }
+}
+
 // Thread types
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -240,11 +341,90 @@ pub struct ThreadGetResponse {
     pub not_found: Vec<String>,
 }
 
+// Identity types (urn:ietf:params:jmap:submission)
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Identity {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdentityGetResponse {
+    #[allow(dead_code)]
+    pub account_id: String,
+    #[allow(dead_code)]
+    pub state: String,
+    pub list: Vec<Identity>,
+}
+
+// VacationResponse types (urn:ietf:params:jmap:vacationresponse)
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct VacationResponse {
+    #[serde(default)]
+    pub is_enabled: bool,
+    #[serde(default)]
+    pub from_date: Option<String>,
+    #[serde(default)]
+    pub to_date: Option<String>,
+    #[serde(default)]
+    pub subject: Option<String>,
+    #[serde(default)]
+    pub text_body: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VacationResponseGetResponse {
+    #[allow(dead_code)]
+    pub account_id: String,
+    pub list: Vec<VacationResponse>,
+}
+
+// Quota types (urn:ietf:params:jmap:quota, RFC 9425)
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Quota {
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    /// `octets` or `count`.
+    pub resource_type: String,
+    pub used: u64,
+    pub hard_limit: u64,
+    #[serde(default)]
+    pub scope: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaGetResponse {
+    #[allow(dead_code)]
+    pub account_id: String,
+    pub list: Vec<Quota>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use serde_json::json;
 
+    #[test]
+    fn test_keyword_validation_and_patch_path() {
+        assert!(validate_keyword("$label/work~1").is_ok());
+        assert!(validate_keyword("").is_err());
+        assert!(validate_keyword("has space").is_err());
+        assert!(validate_keyword("bad*").is_err());
+        assert!(validate_keyword("naïve").is_err());
+        assert!(validate_keyword(&"k".repeat(256)).is_err());
+        assert_eq!(keyword_patch_path("$seen"), "keywords/$seen");
+        assert_eq!(keyword_patch_path("a/b~c"), "keywords/a~1b~0c");
+    }
+
     #[test]
     fn test_deserialize_minimal_jmap_session() {
         let data = json!({
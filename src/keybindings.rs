@@ -1,4 +1,5 @@
 use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Debug, Clone, Serialize)]
 pub struct KeyBinding {
@@ -48,6 +49,18 @@ pub fn all_keybindings() -> Vec<KeyBinding> {
             action: "open",
             description: "Open mailbox",
         },
+        KeyBinding {
+            view: "mailbox_list",
+            key: "/",
+            action: "jump_filter",
+            description: "Filter folders by name; Enter opens the first match",
+        },
+        KeyBinding {
+            view: "mailbox_list",
+            key: "G",
+            action: "refresh_all_accounts",
+            description: "Refresh unread counts for all accounts",
+        },
         KeyBinding {
             view: "mailbox_list",
             key: "g",
@@ -72,6 +85,12 @@ pub fn all_keybindings() -> Vec<KeyBinding> {
             action: "mark_all_read",
             description: "Mark all mail in selected folder read",
         },
+        KeyBinding {
+            view: "mailbox_list",
+            key: "E",
+            action: "empty_mailbox",
+            description: "Empty the selected Trash/Junk folder (asks first)",
+        },
         KeyBinding {
             view: "mailbox_list",
             key: "x",
@@ -84,6 +103,18 @@ pub fn all_keybindings() -> Vec<KeyBinding> {
             action: "expire_retention",
             description: "Expire retained mail now",
         },
+        KeyBinding {
+            view: "mailbox_list",
+            key: "r",
+            action: "drafts",
+            description: "List saved drafts to resume or delete",
+        },
+        KeyBinding {
+            view: "mailbox_list",
+            key: "i",
+            action: "cycle_identity",
+            description: "Cycle sender identity for new drafts",
+        },
         KeyBinding {
             view: "mailbox_list",
             key: "PgDn",
@@ -127,11 +158,59 @@ pub fn all_keybindings() -> Vec<KeyBinding> {
             action: "prev",
             description: "Previous email",
         },
+        KeyBinding {
+            view: "email_list",
+            key: "N",
+            action: "next_unread",
+            description: "Jump to next unread email",
+        },
+        KeyBinding {
+            view: "email_list",
+            key: "P",
+            action: "prev_unread",
+            description: "Jump to previous unread email",
+        },
+        KeyBinding {
+            view: "email_list",
+            key: "U",
+            action: "toggle_unread_only",
+            description: "Toggle showing unread emails only",
+        },
+        KeyBinding {
+            view: "email_list",
+            key: "C",
+            action: "toggle_collapse_threads",
+            description: "Toggle collapsing threads into one row",
+        },
+        KeyBinding {
+            view: "email_list",
+            key: "v",
+            action: "toggle_preview_pane",
+            description: "Toggle the message preview pane",
+        },
+        KeyBinding {
+            view: "email_list",
+            key: "o",
+            action: "cycle_sort",
+            description: "Cycle sort field (date, from, subject)",
+        },
+        KeyBinding {
+            view: "email_list",
+            key: "O",
+            action: "reverse_sort",
+            description: "Reverse sort direction",
+        },
+        KeyBinding {
+            view: "email_list",
+            key: "Tab",
+            action: "toggle_thread_expanded",
+            description: "Expand/collapse the thread under the cursor",
+        },
         KeyBinding {
             view: "email_list",
             key: "Enter",
             action: "open",
-            description: "Open email / thread reading view",
+            description: "Open email / thread reading view (expands a collapsed thread)",
         },
         KeyBinding {
             view: "email_list",
@@ -181,6 +260,12 @@ pub fn all_keybindings() -> Vec<KeyBinding> {
             action: "archive",
             description: "Archive selected email/thread",
         },
+        KeyBinding {
+            view: "email_list",
+            key: "z",
+            action: "snooze",
+            description: "Snooze selected email for a duration (e.g. 3d)",
+        },
         KeyBinding {
             view: "email_list",
             key: "d",
@@ -193,6 +278,12 @@ pub fn all_keybindings() -> Vec<KeyBinding> {
             action: "destroy",
             description: "Expire selected email/thread now (deleted folder only)",
         },
+        KeyBinding {
+            view: "email_list",
+            key: "!",
+            action: "report_spam",
+            description: "Report spam: set $junk and move to the spam folder (not spam in Junk)",
+        },
         KeyBinding {
             view: "email_list",
             key: "J",
@@ -223,17 +314,53 @@ pub fn all_keybindings() -> Vec<KeyBinding> {
             action: "toggle_read",
             description: "Toggle read/unread",
         },
+        KeyBinding {
+            view: "email_list",
+            key: "M",
+            action: "mark_thread_read",
+            description: "Mark the whole thread read",
+        },
+        KeyBinding {
+            view: "email_list",
+            key: "K",
+            action: "toggle_mute_thread",
+            description: "Mute/unmute thread (new replies are archived)",
+        },
         KeyBinding {
             view: "email_list",
             key: "m",
             action: "move",
             description: "Move to folder",
         },
+        KeyBinding {
+            view: "email_list",
+            key: "Z",
+            action: "undo_move",
+            description: "Undo the last move, flag or read/unread change",
+        },
+        KeyBinding {
+            view: "email_list",
+            key: "Space",
+            action: "toggle_mark",
+            description: "Mark/unmark email for bulk a/d/m/f/u",
+        },
         KeyBinding {
             view: "email_list",
             key: "s",
             action: "search",
-            description: "Search in mailbox",
+            description: "Search in mailbox (Up/Down recall recent searches)",
+        },
+        KeyBinding {
+            view: "email_list",
+            key: "L",
+            action: "search_last",
+            description: "Re-run the last search",
+        },
+        KeyBinding {
+            view: "email_list",
+            key: "w",
+            action: "date_filter",
+            description: "Filter by received date (after:YYYY-MM-DD before:YYYY-MM-DD)",
         },
         KeyBinding {
             view: "email_list",
@@ -241,11 +368,17 @@ pub fn all_keybindings() -> Vec<KeyBinding> {
             action: "load_more",
             description: "Load more messages",
         },
+        KeyBinding {
+            view: "email_list",
+            key: "i",
+            action: "cycle_identity",
+            description: "Cycle sender identity for new drafts",
+        },
         KeyBinding {
             view: "email_list",
             key: "Escape",
             action: "clear_search",
-            description: "Clear search",
+            description: "Clear marks, then search and date filter",
         },
         KeyBinding {
             view: "email_list",
@@ -439,7 +572,13 @@ pub fn all_keybindings() -> Vec<KeyBinding> {
             view: "email_view",
             key: "A",
             action: "attachment",
-            description: "Download/open attachment",
+            description: "Download/open attachment (then a: save all)",
+        },
+        KeyBinding {
+            view: "email_view",
+            key: "O",
+            action: "open_attachment",
+            description: "Open a temporary copy of an attachment",
         },
         KeyBinding {
             view: "email_view",
@@ -451,7 +590,43 @@ pub fn all_keybindings() -> Vec<KeyBinding> {
             view: "email_view",
             key: "v",
             action: "raw_headers",
-            description: "Toggle raw headers (DKIM, Received, etc)",
+            description: "Toggle raw headers with an SPF/DKIM/DMARC summary",
+        },
+        KeyBinding {
+            view: "email_view",
+            key: "T",
+            action: "collapse_quotes",
+            description: "Collapse/expand quoted reply text",
+        },
+        KeyBinding {
+            view: "email_view",
+            key: "K",
+            action: "toggle_mute_thread",
+            description: "Mute/unmute thread (new replies are archived)",
+        },
+        KeyBinding {
+            view: "email_view",
+            key: "s",
+            action: "save_source",
+            description: "Save message source (.eml) to downloads",
+        },
+        KeyBinding {
+            view: "email_view",
+            key: "/",
+            action: "find",
+            description: "Find text in message",
+        },
+        KeyBinding {
+            view: "email_view",
+            key: "n/N",
+            action: "find_next_prev",
+            description: "Next/previous match (while a find is active)",
+        },
+        KeyBinding {
+            view: "email_view",
+            key: "Escape",
+            action: "clear_find",
+            description: "Clear find",
         },
         KeyBinding {
             view: "email_view",
@@ -489,6 +664,12 @@ pub fn all_keybindings() -> Vec<KeyBinding> {
             action: "move",
             description: "Move to mailbox (interactive picker)",
         },
+        KeyBinding {
+            view: "email_view",
+            key: "!",
+            action: "report_spam",
+            description: "Report spam: set $junk and move to the spam folder (not spam in Junk)",
+        },
         KeyBinding {
             view: "email_view",
             key: "J",
@@ -507,17 +688,287 @@ pub fn all_keybindings() -> Vec<KeyBinding> {
             action: "show_spam_score",
             description: "Show this message's spam score and verdict",
         },
+        KeyBinding {
+            view: "email_view",
+            key: "]/[",
+            action: "next_prev_message",
+            description: "Jump to the next/previous message of a thread",
+        },
+        KeyBinding {
+            view: "email_view",
+            key: "U",
+            action: "first_unread_message",
+            description: "Jump to the thread's first unread message",
+        },
+        KeyBinding {
+            view: "email_view",
+            key: "C",
+            action: "collapse_read_messages",
+            description: "Collapse/expand the read messages of a thread",
+        },
         KeyBinding {
             view: "email_view",
             key: "b",
             action: "browse_urls",
             description: "Browse URLs found in message body",
         },
+        KeyBinding {
+            view: "email_view",
+            key: "B",
+            action: "show_blocked_resources",
+            description: "Show/hide remote resources blocked in the HTML body",
+        },
+        KeyBinding {
+            view: "email_view",
+            key: "o",
+            action: "open_link_number",
+            description: "Open link by number (prompts for [N] from Links list)",
+        },
         KeyBinding {
             view: "email_view",
             key: "1-9",
             action: "open_url",
             description: "Open URL by number in configured browser",
         },
+        // Compose ([ui].internal_editor)
+        KeyBinding {
+            view: "compose",
+            key: "C-s",
+            action: "send",
+            description: "Send the draft",
+        },
+        KeyBinding {
+            view: "compose",
+            key: "Esc",
+            action: "discard",
+            description: "Discard the draft (press twice if modified; s saves it for later)",
+        },
+        KeyBinding {
+            view: "compose",
+            key: "Esc d",
+            action: "save_to_drafts",
+            description: "Save the draft to the Drafts folder ([mail].drafts_folder) and close",
+        },
+        // Rules dry-run preview
+        KeyBinding {
+            view: "rules_preview",
+            key: "a",
+            action: "toggle_show_all",
+            description: "Toggle between messages with rule actions and every scanned message",
+        },
+        KeyBinding {
+            view: "rules_preview",
+            key: "j/k",
+            action: "scroll",
+            description: "Scroll down/up",
+        },
+        KeyBinding {
+            view: "rules_preview",
+            key: "q",
+            action: "close",
+            description: "Close the preview",
+        },
     ]
 }
+
+/// Email-list actions that `[keybindings]` may rebind, with their default key.
+pub const REBINDABLE_LIST_ACTIONS: &[(&str, char)] = &[
+    ("archive", 'a'),
+    ("delete", 'd'),
+    ("move", 'm'),
+    ("reply", 'r'),
+    ("reply_all", 'R'),
+    ("toggle_flagged", 'f'),
+    ("toggle_read", 'u'),
+    ("snooze", 'z'),
+    ("search", 's'),
+    ("refresh", 'g'),
+    ("undo_move", 'Z'),
+];
+
+/// Actions the email list runs for `key` by default, including global ones.
+fn email_list_actions_on(key: char) -> Vec<&'static str> {
+    all_keybindings()
+        .into_iter()
+        .filter(|kb| kb.view == "email_list" || kb.view == "global")
+        .filter(|kb| {
+            kb.key.split('/').any(|k| match k {
+                "Space" => key == ' ',
+                k => k.chars().eq(std::iter::once(key)),
+            })
+        })
+        .map(|kb| kb.action)
+        .collect()
+}
+
+/// Navigation keys that can never be rebound to an action.
+const NAVIGATION_KEYS: &[char] = &['q', 'n', 'j', 'p', 'k', 'N', 'P', '?'];
+
+/// User remapping of email-list action keys from `[keybindings]`. Views run
+/// each pressed key through [`Keymap::translate`] before matching on the
+/// default keys.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Keymap {
+    /// Action name -> key chosen by the user.
+    overrides: BTreeMap<String, char>,
+    /// Pressed key -> default key of the action it triggers. A rebound
+    /// action's default key maps to `None` unless another action took it.
+    remap: HashMap<char, Option<char>>,
+}
+
+impl Keymap {
+    /// Build a keymap from action -> key overrides, rejecting unknown actions,
+    /// navigation keys, and keys that collide with another binding.
+    pub fn from_overrides(overrides: BTreeMap<String, char>) -> Result<Self, String> {
+        let mut remap = HashMap::new();
+        for (action, key) in &overrides {
+            let Some(&(_, default)) = REBINDABLE_LIST_ACTIONS.iter().find(|(a, _)| a == action)
+            else {
+                let names: Vec<&str> = REBINDABLE_LIST_ACTIONS.iter().map(|(a, _)| *a).collect();
+                return Err(format!(
+                    "unknown keybinding action '{}' (expected one of: {})",
+                    action,
+                    names.join(", ")
+                ));
+            };
+            if NAVIGATION_KEYS.contains(key) {
+                return Err(format!(
+                    "keybinding '{}' cannot use navigation key '{}'",
+                    action, key
+                ));
+            }
+            if let Some((other, _)) = overrides.iter().find(|(a, k)| *a != action && *k == key) {
+                return Err(format!(
+                    "keybindings '{}' and '{}' are both bound to '{}'",
+                    action, other, key
+                ));
+            }
+            if let Some(other) = email_list_actions_on(*key)
+                .into_iter()
+                .find(|other| *other != action.as_str() && !overrides.contains_key(*other))
+            {
+                return Err(format!(
+                    "keybinding '{}' = '{}' conflicts with '{}' in the email list",
+                    action, key, other
+                ));
+            }
+            remap.entry(default).or_insert(None);
+            remap.insert(*key, Some(default));
+        }
+        Ok(Keymap { overrides, remap })
+    }
+
+    /// Map a pressed key to the default key of the action it is bound to.
+    /// Returns `None` for a default key whose action was moved elsewhere.
+    pub fn translate(&self, key: char) -> Option<char> {
+        match self.remap.get(&key) {
+            Some(mapped) => *mapped,
+            None => Some(key),
+        }
+    }
+
+    pub fn overrides(&self) -> &BTreeMap<String, char> {
+        &self.overrides
+    }
+
+    /// Reject `[mail].quick_move` keys that the email list already uses for
+    /// navigation or an action, after applying this keymap.
+    pub fn check_quick_move_keys(
+        &self,
+        keys: impl IntoIterator<Item = char>,
+    ) -> Result<(), String> {
+        for key in keys {
+            if NAVIGATION_KEYS.contains(&key) {
+                return Err(format!("mail.quick_move key '{}' is a navigation key", key));
+            }
+            if let Some((action, _)) = self.overrides.iter().find(|(_, k)| **k == key) {
+                return Err(format!(
+                    "mail.quick_move key '{}' is bound to '{}' in [keybindings]",
+                    key, action
+                ));
+            }
+            // A default key whose action was rebound elsewhere is free.
+            let Some(default) = self.translate(key) else {
+                continue;
+            };
+            if let Some(action) = email_list_actions_on(default).first() {
+                return Err(format!(
+                    "mail.quick_move key '{}' conflicts with '{}' in the email list",
+                    key, action
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The user's key for a rebound action in `view`, if any.
+    pub fn key_for(&self, view: &str, action: &str) -> Option<char> {
+        if view != "email_list" {
+            return None;
+        }
+        self.overrides.get(action).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keymap(pairs: &[(&str, char)]) -> Result<Keymap, String> {
+        Keymap::from_overrides(pairs.iter().map(|(a, k)| (a.to_string(), *k)).collect())
+    }
+
+    #[test]
+    fn test_keymap_translates_rebound_keys() {
+        let map = keymap(&[("archive", 'x')]).unwrap();
+        assert_eq!(map.translate('x'), Some('a'));
+        assert_eq!(map.translate('a'), None);
+        assert_eq!(map.translate('d'), Some('d'));
+    }
+
+    #[test]
+    fn test_keymap_allows_swapping_actions() {
+        let map = keymap(&[("archive", 'd'), ("delete", 'a')]).unwrap();
+        assert_eq!(map.translate('d'), Some('a'));
+        assert_eq!(map.translate('a'), Some('d'));
+    }
+
+    #[test]
+    fn test_keymap_rejects_conflicts() {
+        assert!(keymap(&[("archive", 'j')])
+            .unwrap_err()
+            .contains("navigation"));
+        assert!(keymap(&[("archive", 'f')])
+            .unwrap_err()
+            .contains("toggle_flagged"));
+        assert!(keymap(&[("archive", 'x'), ("delete", 'x')])
+            .unwrap_err()
+            .contains("both bound"));
+        assert!(keymap(&[("open", 'x')]).unwrap_err().contains("unknown"));
+        // Global keys and keys listed as alternatives count too.
+        assert!(keymap(&[("archive", 'c')]).unwrap_err().contains("compose"));
+        assert!(keymap(&[("archive", ' ')])
+            .unwrap_err()
+            .contains("conflicts"));
+    }
+
+    #[test]
+    fn test_quick_move_keys_must_be_free() {
+        let map = keymap(&[("archive", 'x')]).unwrap();
+        assert!(map.check_quick_move_keys(['b', 'A', '1']).is_ok());
+        // 'a' was freed by moving archive to 'x'.
+        assert!(map.check_quick_move_keys(['a']).is_ok());
+        assert!(map
+            .check_quick_move_keys(['x'])
+            .unwrap_err()
+            .contains("'archive' in [keybindings]"));
+        assert!(map
+            .check_quick_move_keys(['R'])
+            .unwrap_err()
+            .contains("conflicts with 'reply_all'"));
+        assert!(map
+            .check_quick_move_keys(['j'])
+            .unwrap_err()
+            .contains("navigation"));
+    }
+}
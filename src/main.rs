@@ -2,18 +2,26 @@
 mod log;
 
 mod backend;
+mod body;
 mod cache;
 mod cli;
 mod compose;
 mod config;
+mod contacts;
+mod datefmt;
+mod drafts;
 mod jmap;
 mod keybindings;
+mod markdown;
+mod mute;
+mod offline_search;
 mod rules;
+mod snooze;
 mod spam;
 mod tui;
 
-use config::{AccountConfig, Config};
-use jmap::client::JmapClient;
+use config::{AccountConfig, AuthMode, Config};
+use jmap::client::{Credentials, HttpOptions, JmapClient};
 use std::io::{self, Write};
 use std::path::PathBuf;
 use std::process::Command;
@@ -52,11 +60,20 @@ pub fn run_password_command(cmd: &str) -> Result<String, String> {
     Ok(password.trim_end_matches('\n').to_string())
 }
 
-pub fn connect_account(account: &AccountConfig) -> Result<JmapClient, String> {
-    let password = run_password_command(&account.password_command)?;
-    let (_session, client) =
-        JmapClient::discover(&account.well_known_url, &account.username, &password)
-            .map_err(|e| format!("JMAP discovery error: {}", e))?;
+pub fn connect_account(account: &AccountConfig, http: HttpOptions) -> Result<JmapClient, String> {
+    let secret = run_password_command(&account.password_command)?;
+    let credentials = match account.auth {
+        AuthMode::Basic => Credentials::Basic {
+            username: account.username.clone(),
+            password: secret,
+        },
+        AuthMode::OAuth2 => Credentials::Bearer {
+            token: secret,
+            refresh_command: account.token_refresh_command.clone(),
+        },
+    };
+    let (_session, client) = JmapClient::discover(&account.well_known_url, credentials, http)
+        .map_err(|e| format!("JMAP discovery error: {}", e))?;
     Ok(client)
 }
 
@@ -93,7 +110,9 @@ fn print_rules() {
     let loaded = match rules::load_rules(&rules_path) {
         Ok(rules) => rules,
         Err(e) => {
-            eprintln!("Failed to load rules from {}: {}", rules_path.display(), e);
+            eprintln!("Rules file: {}", rules_path.display());
+            eprintln!("Validation: failed");
+            eprintln!("{}", e);
             std::process::exit(1);
         }
     };
@@ -102,6 +121,14 @@ fn print_rules() {
     println!("Rules file: {}", rules_path.display());
     println!("Rules loaded: {}", loaded.len());
     println!("Custom headers requested: {}", custom_headers.len());
+    println!("Validation: ok (regexes compile, move_to paths well-formed)");
+    let warnings = loaded
+        .iter()
+        .filter(|rule| rule.warning().is_some())
+        .count();
+    if warnings > 0 {
+        println!("Warnings: {} (see the rules below)", warnings);
+    }
     println!();
     print!("{}", rules::format_rules_for_display(&loaded));
 }
@@ -111,7 +138,7 @@ fn print_prompt(topic: &str) {
         "config" => {
             let config_path = default_config_path();
             print!(
-                r#"I need help generating a configuration file for tmc (Timmy's Mail Console), a terminal email client that connects via JMAP.
+                r##"I need help generating a configuration file for tmc (Timmy's Mail Console), a terminal email client that connects via JMAP.
 
 The config file goes at: {}
 
@@ -120,15 +147,45 @@ Here is the format:
 ```toml
 [ui]
 editor = "nvim"          # optional: editor for composing ($EDITOR fallback)
+internal_editor = false   # optional: compose inside tmc (Ctrl-S sends) instead of the editor (default false)
 browser = "firefox"      # optional: browser for opening URLs ($BROWSER fallback, then xdg-open)
 page_size = 100           # optional: emails per page (default 500)
 scrolloff = 1             # optional: keep this many context lines above/below cursor (default 1)
 mouse = true              # optional: enable mouse support (default true)
 sync_interval_secs = 60   # optional: background sync interval (default 60, 0 = off)
+sync_all_mailboxes = true # optional: background sync also refreshes every folder's unread counts (default false)
+max_retries = 3           # optional: retries for JMAP requests failing with connection errors, 429 or 5xx (default 3, 0 = off)
+connect_timeout_secs = 10 # optional: give up connecting to the server after this long (default 10, 0 = no limit)
+request_timeout_secs = 120  # optional: give up on a JMAP request after this long (default 120, 0 = no limit)
+fetch_concurrency = 4     # optional: email chunks fetched in parallel when loading large folders (default 4, 1 = sequential)
+quote_prefix = "> "        # optional: prefix for quoted lines in replies (default "> ")
+reply_attribution = "On {{date}}, {{from}} wrote:"  # optional: line above quoted text; {{date}}, {{from}}, {{subject}}; "" omits it
+forward_attribution = "---------- Forwarded message ----------"  # optional: line above forwarded text, same placeholders
+confirm_destructive = true  # optional: ask y/n before permanently destroying mail with D/X (default true)
+preview_pane = true       # optional: open the email list with a message preview below it (default false)
+prefer_html = false       # optional: open messages on their HTML body instead of plain text; h toggles it per message (default false)
+inline_images = false     # optional: draw images embedded in HTML mail on kitty/sixel terminals (default false)
+block_remote_content = true  # optional: hide remote images in HTML mail behind a count; B lists them (default true)
+mailbox_format = "{{name}} ({{unread}}/{{total}})"  # optional: mailbox list rows; placeholders {{name}}, {{unread}}, {{total}}
+hide_empty_mailboxes = false  # optional: leave folders with no mail out of the mailbox list (default false)
+restore_session = true    # optional: reopen the folder and list position left at the last exit (default true)
+preview_chars = 200       # optional: preview length cached for messages the server sent without one (default 200, 0 = off)
+max_render_width = 120    # optional: widest column count HTML mail is laid out at (default 120, 0 = terminal width)
+compose_format = "plain"  # optional: "markdown" sends drafts as text plus rendered HTML; a draft's Content-Type: header overrides (default "plain")
+format_flowed = false     # optional: send plain-text mail as format=flowed (RFC 3676) so clients re-wrap it (default false)
+sort = "date"             # optional: email list order: date, from or subject, plus asc/desc (default "date", newest first)
+date_format = "relative"  # optional: list dates as "iso", "relative" (2h, 3d, Jan 12) or a pattern like "%d %b %H:%M" (default "iso")
+notify = true             # optional: desktop notification when new INBOX mail arrives (default false)
+notify_command = "notify-send"  # optional: command given the summary and body as arguments (default "notify-send")
+download_dir = "~/Downloads/mail"  # optional: where attachments are saved; ~ and $VARS are expanded (default $XDG_DOWNLOAD_DIR, then ~/Downloads)
 
 [mail]
 archive_folder = "Archive"  # optional: target folder for 'a' archive action (default "archive")
 deleted_folder = "Trash"    # optional: target folder for 'd' delete action (default "trash")
+snooze_folder = "Snoozed"   # optional: holds snoozed mail until it wakes (default "Snoozed")
+spam_folder = "Junk"        # optional: where `!` reports spam (default: the junk-role mailbox)
+drafts_folder = "Drafts"    # optional: where "save as draft" stores unsent mail (default "Drafts")
+quick_move = {{ x = "INBOX/Todo", X = "Receipts" }}  # optional: one-key moves in the email list
 rules_mailbox_regex = "^INBOX$"  # optional: auto-run rules only when mailbox name matches (default "^INBOX$")
 my_email_regex = "(?i)(timmy@example\\.com|me@work\\.com)" # optional: your addresses used by rules skip_if_to_me (default "^$")
 
@@ -138,14 +195,35 @@ threshold = 0.9           # optional: score >= this -> verdict "spam" (default 0
 ham_threshold = 0.2       # optional: score <= this -> verdict "ham"; between is "unsure" (default 0.2)
 min_training = 20         # optional: trained messages per class before verdicts go live (default 20)
 
+[cache]
+max_bytes = 104857600     # optional: evict least recently read messages past this cache size (default 0 = unbounded)
+
+[contacts]
+file = "~/.config/tmc/contacts"  # optional: address book, one "Name <email>" per line (# comments allowed)
+command = "khard email --parsable | awk -F'\\t' '{{print $2 \" <\" $1 \">\"}}'"  # optional: command printing the same format
+
+[keybindings]
+archive = "x"             # optional: rebind email list actions (see rules below)
+
 [retention.archive]
 folder = "Archive"
 days = 365                  # expire mail older than 365 days in Archive when pressing X
+only_read = true            # optional: keep unread mail (default false)
+only_unflagged = true       # optional: keep flagged mail (default false)
 
 [retention.trash]
 folder = "Trash"
 days = 30                   # expire mail older than 30 days in Trash when pressing X
 
+[retention.logs]
+folder = "Logs"
+keep_newest = 200           # instead of days: keep the newest 200 messages, expire the rest
+
+[[saved_search]]            # optional: named searches listed below the mailboxes
+name = "From boss"
+query = "from:boss@example.com"
+mailbox = "Inbox"           # optional: omit to search every mailbox
+
 [account.personal]
 well_known_url = "https://mx.example.com/.well-known/jmap"
 username = "me@example.com"
@@ -155,25 +233,41 @@ password_command = "pass show email/example.com"
 well_known_url = "https://mx.work.com/.well-known/jmap"
 username = "me@work.com"
 password_command = "pass show email/work.com"
+prefer_html = true          # optional: per-account override of [ui].prefer_html
+color = "#dc322f"           # optional: account name color in the list headers
+authserv_ids = ["mx.mailhost.example"]  # optional: extra trusted Authentication-Results ids
+
+[account.work.theme]        # optional: [theme] overrides while this account is active
+status_bg = "#dc322f"
 ```
 
 Rules:
 - At least one [account.NAME] section is required (or legacy [jmap] with the same three fields).
 - `well_known_url`, `username`, and `password_command` are required per account.
 - `password_command` is a shell command that prints the password to stdout.
+- `auth = "oauth2"` (default `"basic"`) makes `password_command` print an OAuth2 access token, sent as `Authorization: Bearer`; `token_refresh_command` prints a fresh token when the server answers 401, and the request is retried once.
 - Quoted strings support \", \\, \n, \t escapes.
 - `scrolloff` controls how many lines of context are kept above and below the cursor in list views.
+- `notify` runs `notify_command` with a summary and body (sender and subject of the newest message) when a sync finds new unread INBOX mail; a burst of new mail gives one notification per sync.
 - `archive_folder` and `deleted_folder` are mailbox targets for `a` and `d` in list views.
-- `rules_mailbox_regex` controls which mailbox names auto-run rules on refresh/fetch; default is `^INBOX$`.
+- `snooze_folder` holds mail snoozed with `z` and is created on first use; mail still in it returns to the inbox on the first sync after its wake time, while mail moved out of it meanwhile stays where it is.
+- `spam_folder` receives mail reported with `!` (which also sets `$junk`); `!` inside it sets `$notjunk` and moves the mail back to the inbox.
+- `drafts_folder` receives drafts saved with the compose view's "save to Drafts" (or CLI `save_draft`), stored with the `$draft` keyword. A folder name or role.
+- `quick_move` maps single characters to folders; pressing one in the email list moves the selected (or marked) mail there. Keys the email list already uses (including `[keybindings]` overrides) are rejected.
+- `[keybindings]` rebinds email list actions to another single character. Rebindable actions: archive, delete, move, reply, reply_all, toggle_flagged, toggle_read, snooze, search, refresh, undo_move. Navigation keys (q, j, k, n, p, N, P, ?) cannot be used, and a new key may not collide with another binding. The CLI `keybindings` command reports the effective keys.
+- `rules_mailbox_regex` controls which mailbox names auto-run rules on refresh/fetch; default is `^INBOX$`. A rule with its own `mailbox` (glob) or `mailbox_regex` in rules.toml runs where that matches instead.
 - `my_email_regex` is matched against combined To/Cc and used by rules with `skip_if_to_me = true`.
 - `[spam]` configures the built-in Bayesian classifier: it scores new INBOX mail and sets an `X-Tmc-Spam-Verdict` header that rules.toml can act on (train with `J`/`H` in the message view). See `tmc --prompt=rules`.
-- `[retention.NAME]` sections are optional folder retention policies used by `x` (preview) and `X` (expire) in mailbox view.
+- `color` (`#RRGGBB`) shows the account name in that color in the mailbox- and email-list headers.
+- `[account.NAME.theme]` takes the same keys as `[theme]`; keys it sets replace the global ones while that account is active, so accounts can be told apart at a glance.
+- `[retention.NAME]` sections are optional folder retention policies used by `x` (preview) and `X` (expire) in mailbox view. Each sets either `days` (expire by age) or `keep_newest` (keep the newest N messages), not both. `only_read`/`only_unflagged` keep unread or flagged mail past the limit.
 - Retention policy fields:
   - `folder` (required): mailbox name, role, or path (e.g. "INBOX/Alerts")
-  - `days` (required): positive integer; emails older than this are deleted on `X`.
+  - one of `days`/`keep_newest`: `days` is a positive integer, and emails older than that many days are deleted on `X`; `keep_newest` keeps the newest N messages (by received time; undated mail is never deleted) and deletes the rest.
+- `[[saved_search]]` entries (`name`, `query`, optional `mailbox`) are listed as `[search] NAME` below the mailboxes; opening one lists the matches in that mailbox, or in every mailbox when `mailbox` is omitted.
 
 Please ask me for my email provider, username, and how I store passwords, then generate a config file.
-"#,
+"##,
                 config_path.display()
             );
         }
@@ -207,7 +301,7 @@ name = "flag urgent from boss"
 [rule.match]
 all = [
     {{ header = "From", regex = "boss@example\\.com" }},
-    {{ header = "Subject", regex = "(?i)urgent" }},
+    {{ header = "Subject", regex = "urgent", case_sensitive = false, whole_word = true }},
 ]
 [rule.actions]
 flag = true
@@ -221,9 +315,21 @@ regex = "\\[ALERT\\]"
 [rule.actions]
 move_to = "INBOX/Alerts"
 
-# Continue processing allows subsequent rules to also match
+# Only in the shared team folders, not INBOX
+[[rule]]
+name = "flag team questions"
+mailbox = "Shared/*"
+[rule.match]
+header = "Subject"
+regex = "\\?$"
+[rule.actions]
+flag = true
+
+# Continue processing allows subsequent rules to also match;
+# lower priority runs first (default 0, ties keep file order)
 [[rule]]
 name = "tag and continue"
+priority = -10
 continue_processing = true
 [rule.match]
 header = "To"
@@ -239,6 +345,22 @@ not = {{ header = "From", regex = "boss@" }}
 [rule.actions]
 mark_read = true
 
+# Message age: older_than_days / newer_than_days compare against the received date
+[[rule]]
+name = "archive old inbox mail"
+[rule.match]
+older_than_days = 90
+[rule.actions]
+move_to = "Archive"
+
+# Attachments
+[[rule]]
+name = "file mail with attachments"
+[rule.match]
+has_attachment = true
+[rule.actions]
+move_to = "Attachments"
+
 # Built-in spam classifier verdict (see the [spam] config section)
 [[rule]]
 name = "file spam"
@@ -263,11 +385,21 @@ Available actions:
 - unflag = true
 - move_to = "MailboxName"  (supports name, role, or path like "INBOX/Sub")
 - delete = true  (moves to Trash)
-
-Conditions support: header/regex, all = [...], any = [...], not = {{...}}
-
-By default, only the first matching rule applies per email. Set `continue_processing = true` to allow subsequent rules to also match.
+- keywords = {{ "$label1" = true, "todo" = false }}  (set or clear JMAP keywords)
+- forward_to = "someone@example.com"  (sends an inline forward from your first identity; the original is marked $forwarded and sent only once; the copy carries an X-Tmc-Forwarded header and mail that already has it is never forwarded again)
+
+Conditions support: header/regex, body = "regex", list_id = "regex", older_than_days = N, newer_than_days = N, has_attachment = true/false, all = [...], any = [...], not = {{...}}
+Regex conditions (header, body, list_id) are case-sensitive; set `case_sensitive = false` (or use an inline `(?i)`) to ignore case, and add `whole_word = true` to match only at word boundaries. Combining `case_sensitive = true` with an inline `(?i)` flag, or `whole_word` with a `^`/`$` anchored regex, is rejected when rules load.
+`list_id` matches the mailing list identifier from the List-Id header with the display name and angle brackets removed, so `Rust Users <users.rust-lang.org>` is matched as `users.rust-lang.org`.
+`body` matches the plain-text body (HTML-only mail is converted to text), scanning the first 64 KiB; mail whose body was not fetched (e.g. offline, from the cache) is matched against its preview instead, with a warning in the log. Body rules make tmc download message bodies while evaluating rules, so they are slower than header rules; prefer a header match when one will do.
+Age conditions are checked whenever rules run; new mail is processed once, so press `E` in a mailbox to re-apply age rules to older messages.
+
+By default, only the first matching rule applies per email. Set `continue_processing = true` to allow subsequent rules to also match, or put `default_continue = true` under a top-level `[rules]` table to make that the default for rules that don't set it.
+Rules run in file order unless given `priority = N`: lower numbers run first, rules without one count as 0, and ties keep file order. `tmc --print-rules` lists them in the order they run.
+`move_to` must be a mailbox name or `Parent/Child` path without empty segments; `tmc --print-rules` reports each invalid rule with its name and line number. A rule with no actions (and no `[rule.triage]` hint) still loads but changes nothing, and `tmc --print-rules` shows a warning under it.
+Add `stop = true` to the actions to end processing at that rule no matter what; a rule with only `stop = true` protects matching mail from every later rule.
 Set `skip_if_to_me = true` to skip a rule when `mail.my_email_regex` matches To or Cc.
+New mail is checked against rules in folders matching `mail.rules_mailbox_regex` (default `^INBOX$`). Scope a single rule to other folders with `mailbox = "GLOB"` (`*` and `?` wildcards, matched against the folder name or its `Parent/Child` path, e.g. `mailbox = "Shared/*"`) or `mailbox_regex = "REGEX"`; such a rule ignores `rules_mailbox_regex` and runs only in the folders it names. Running rules by hand on a folder skips `rules_mailbox_regex` but still honors each rule's own `mailbox`/`mailbox_regex`.
 
 Please ask me what kinds of emails I receive and how I want them organized, then generate a rules file.
 "#,
@@ -294,6 +426,7 @@ fn print_help_config() {
     println!(
         "  editor = \"nvim\"              # Editor for composing (fallback: $EDITOR, then vi)"
     );
+    println!("  internal_editor = false      # Compose in a built-in editor view; Ctrl-S sends (default: false)");
     println!("  browser = \"firefox\"           # Browser for opening URLs (fallback: $BROWSER, xdg-open)");
     println!("  page_size = 500              # Emails per page (default: 500)");
     println!(
@@ -301,10 +434,46 @@ fn print_help_config() {
     );
     println!("  mouse = true                 # Enable mouse support (default: true)");
     println!("  sync_interval_secs = 60      # Background sync interval in seconds (default: 60, 0 = off)");
+    println!("  sync_all_mailboxes = false   # Background sync also refreshes every folder's counts (default: false)");
+    println!("  max_retries = 3              # Retries with backoff on connection errors, 429 and 5xx (default: 3)");
+    println!("  connect_timeout_secs = 10    # Connection timeout for JMAP requests (default: 10, 0 = none)");
+    println!("  request_timeout_secs = 120   # Whole-request timeout for JMAP requests (default: 120, 0 = none)");
+    println!("  fetch_concurrency = 4        # Email/get chunks fetched in parallel (default: 4, 1 = sequential)");
+    println!("  signature_above_quote = false  # Put the signature above quoted text in replies (default: false)");
+    println!("  quote_prefix = \"> \"          # Prefix for each quoted line in replies (default: \"> \")");
+    println!("  reply_attribution = \"On {{date}}, {{from}} wrote:\"  # Line above quoted text; {{date}} {{from}} {{subject}}; \"\" omits it");
+    println!("  forward_attribution = \"---------- Forwarded message ----------\"  # Line above inline-forwarded text; same placeholders");
+    println!(
+        "  confirm_destructive = true   # Ask y/n before permanent expire with D/X (default: true)"
+    );
+    println!("  preview_pane = false         # Split the email list with a preview of the selected message (default: false)");
+    println!("  prefer_html = false          # Open messages on the HTML body; h toggles it per message (default: false)");
+    println!("  block_remote_content = true  # Drop remote images/tracking pixels from HTML mail, show \"N remote resources blocked\"; B lists the URLs (default: true)");
+    println!("  inline_images = false        # Draw cid: images in HTML mail with kitty graphics (PNG) or sixel via img2sixel, detected from $TERM (default: false)");
+    println!("  mailbox_format = \"{{name}} ({{unread}}/{{total}})\"  # Mailbox list row; folders with unread mail are bold");
+    println!("  hide_empty_mailboxes = false # Hide folders with zero messages; the inbox always shows (default: false)");
+    println!("  restore_session = true       # Per account, reopen the last folder and cursor position; INBOX if the folder is gone (default: true)");
+    println!("  preview_chars = 200          # Derive a cached preview of this many chars from the body when the server sends none (default: 200, 0 = off)");
+    println!("  max_render_width = 120       # HTML mail is laid out at the terminal width, capped here (default: 120, 0 = no cap)");
+    println!("  compose_format = \"plain\"     # Draft body format: plain | markdown (text/plain + rendered text/html); per draft via Content-Type: text/markdown (default: \"plain\")");
+    println!("  format_flowed = false        # Wrap plain-text mail at 72 columns as format=flowed, quotes included (default: false)");
+    println!("  sort = \"date\"                # Email list order: date|from|subject [asc|desc] (default: \"date\" newest first)");
+    println!("  date_format = \"iso\"         # List dates: iso | relative | pattern (%Y %y %m %d %e %H %I %M %S %p %b %a) (default: \"iso\")");
+    println!("  notify = false               # Desktop notification for new INBOX mail on sync (default: false)");
+    println!("  notify_command = \"notify-send\"  # Notification command, given summary and body (default: \"notify-send\")");
+    println!("  download_dir = \"~/Downloads\"  # Where attachments are saved; ~ and $VARS expanded (default: $XDG_DOWNLOAD_DIR, ~/Downloads)");
     println!();
     println!("[mail]");
     println!("  archive_folder = \"archive\"   # Target folder for 'a' archive action (default: \"archive\")");
     println!("  deleted_folder = \"trash\"     # Target folder for 'd' delete action (default: \"trash\")");
+    println!("  snooze_folder = \"Snoozed\"   # Folder holding 'z' snoozed mail until it wakes (default: \"Snoozed\")");
+    println!(
+        "  spam_folder = \"Junk\"        # Folder '!' reports spam to (default: \"junk\" role)"
+    );
+    println!(
+        "  drafts_folder = \"Drafts\"    # Folder unsent drafts are saved to (default: \"Drafts\")"
+    );
+    println!("  quick_move = {{ x = \"Todo\" }}  # Single-key moves in the email list (must not clash with other keys)");
     println!("  archive_mailbox_id = \"id\"    # Override archive folder by JMAP mailbox ID");
     println!("  deleted_mailbox_id = \"id\"    # Override deleted folder by JMAP mailbox ID");
     println!("  reply_from = \"Name <email>\"  # Override From header for replies/compose/forward");
@@ -319,16 +488,53 @@ fn print_help_config() {
     println!("  # Train with J (spam) / H (not-spam) in the message view; act on the");
     println!("  # X-Tmc-Spam-Verdict header from rules.toml (see: tmc --prompt=rules).");
     println!();
+    println!("[cache]                          # Per-account offline message cache");
+    println!("  max_bytes = 104857600        # Evict least recently read messages past this size (default: 0 = unbounded)");
+    println!();
+    println!("[contacts]                       # Address completion (CLI complete_address), merged with addresses seen in cached mail");
+    println!("  file = \"~/.config/tmc/contacts\"  # One \"Name <email>\" or bare address per line; # starts a comment");
+    println!("  command = \"my-contacts-export\"  # Shell command printing the same format; used alongside file");
+    println!();
+    println!("[keybindings]                    # Rebind email list actions to another single key");
+    println!("  archive = \"x\"                # Actions: archive delete move reply reply_all toggle_flagged");
+    println!("                                 #   toggle_read snooze search refresh undo_move");
+    println!();
     println!("[account.NAME]                   # At least one account required");
     println!(
         "  well_known_url = \"https://.../.well-known/jmap\"  # JMAP discovery URL (required)"
     );
     println!("  username = \"user@example.com\"                    # Email address (required)");
     println!("  password_command = \"pass show email/example\"     # Shell command returning password (required)");
+    println!("  auth = \"basic\"                                   # \"basic\" or \"oauth2\" (password_command prints a bearer token)");
+    println!("  token_refresh_command = \"...\"                    # oauth2: prints a new token after a 401");
+    println!("  signature = \"...\"                                # Signature for identities without their own");
+    println!("  prefer_html = true                               # Override [ui].prefer_html for this account");
+    println!("  color = \"#dc322f\"                                # Account name color in the list headers");
+    println!("  authserv_ids = [\"mx.host.example\"]              # Trusted Authentication-Results ids besides the account's domains");
+    println!();
+    println!("[account.NAME.theme]             # Optional [theme] keys overriding the global theme for this account");
+    println!("  status_bg = \"#dc322f\"");
+    println!();
+    println!("[[account.NAME.identities]]      # Optional sender identities (replies use the one addressed; `i` picks one, overriding reply_from)");
+    println!("  name = \"Your Name\"            # Display name");
+    println!("  email = \"you@example.com\"     # From address (required)");
+    println!("  signature = \"...\"            # Optional signature text");
     println!();
     println!("[retention.NAME]                 # Optional folder retention policies");
     println!("  folder = \"Archive\"            # Mailbox name to apply retention (required)");
-    println!("  days = 365                   # Expire mail older than this many days (required)");
+    println!("  days = 365                   # Expire mail older than this many days");
+    println!("  keep_newest = 200            # Or: keep only the newest N messages (one of days/keep_newest)");
+    println!("  only_read = true             # Only expire read mail (default: false)");
+    println!(
+        "  only_unflagged = true        # Only expire mail that is not flagged (default: false)"
+    );
+    println!();
+    println!(
+        "[[saved_search]]                 # Optional named searches listed below the mailboxes"
+    );
+    println!("  name = \"From boss\"            # Label in the mailbox list (required, unique)");
+    println!("  query = \"from:boss@example.com\"  # Search text (required)");
+    println!("  mailbox = \"Inbox\"             # Mailbox to search; omit to search every mailbox");
     println!();
     println!("[theme]                          # Optional color customization (#RRGGBB hex)");
     println!("  bg = \"#002b36\"               # Background color");
@@ -339,6 +545,18 @@ fn print_help_config() {
     println!("  status_bg = \"#586e75\"        # Status bar background");
     println!("  status_fg = \"#eee8d5\"        # Status bar foreground");
     println!("  header_fg = \"#268bd2\"        # Header text color");
+    println!("  quote_fg = \"#586e75\"         # Quoted (>) lines in messages");
+    println!("  link_fg = \"#268bd2\"          # Message link list");
+    println!("  attachment_fg = \"#b58900\"    # Message attachment list");
+    println!("  auth_pass_fg = \"#859900\"     # Passing SPF/DKIM/DMARC in the v (full headers) summary (default: green)");
+    println!("  auth_fail_fg = \"#dc322f\"     # Failing SPF/DKIM/DMARC verdicts (default: red)");
+    println!("  color_mode = \"256\"           # truecolor, 256 or 16 (default: truecolor, or 256 when $COLORTERM names anything else)");
+    println!();
+    println!("[theme.senders]                  # Optional email list row colors by From (regex -> color)");
+    println!("  \"@lists\\\\.example\\\\.org\" = \"#268bd2\"");
+    println!();
+    println!("[theme.keywords]                 # Optional row colors by keyword; win over senders");
+    println!("  \"$label1\" = \"#859900\"");
     println!();
     println!(
         "Legacy: [jmap] section with well_known_url, username, password_command is also supported."
@@ -355,10 +573,11 @@ fn main() {
         eprintln!("  --config=PATH    Use config file at PATH instead of default");
         eprintln!("  --rules=PATH     Use rules file at PATH instead of default");
         eprintln!("  --clear-cache    Delete all local email cache files");
+        eprintln!("  --clear-cache=ACCOUNT  Delete only ACCOUNT's cache file");
         eprintln!("  --clear-log      Truncate the log file at startup");
         eprintln!("  --log            View the log file in $PAGER");
         eprintln!("  --offline        Browse cached mail without network access");
-        eprintln!("  --print-rules    Parse and print rules.toml");
+        eprintln!("  --print-rules    Parse and print rules.toml in evaluation order");
         eprintln!("  --prompt=TOPIC   Print an AI-friendly prompt (config, rules)");
         eprintln!("  --cli            Run in JSON-over-stdin/stdout CLI mode");
         eprintln!("  --help-cli       Print CLI mode protocol documentation");
@@ -368,8 +587,23 @@ fn main() {
     }
 
     if args.iter().any(|a| a == "--clear-cache") {
-        cache::Cache::clear_all_accounts();
-        eprintln!("Cache cleared.");
+        let cleared = cache::Cache::clear_all_accounts();
+        if cleared.is_empty() {
+            eprintln!("Cache cleared (no account caches found).");
+        } else {
+            eprintln!("Cache cleared for: {}", cleared.join(", "));
+        }
+    }
+    for arg in args.iter().filter(|a| a.starts_with("--clear-cache=")) {
+        let account = &arg["--clear-cache=".len()..];
+        match cache::Cache::clear_account(account) {
+            Ok(true) => eprintln!("Cache cleared for: {}", account),
+            Ok(false) => eprintln!("No cache found for account '{}'.", account),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
     }
 
     if args.iter().any(|a| a == "--clear-log") {
@@ -503,7 +737,7 @@ fn main() {
         );
         io::stderr().flush().ok();
 
-        match connect_account(first_account) {
+        match connect_account(first_account, config.ui.http_options()) {
             Ok(client) => {
                 eprintln!(" OK");
                 Some(client)
@@ -516,31 +750,15 @@ fn main() {
         }
     };
 
-    let first_account_name = first_account.name.clone();
-
     // Enter TUI
     if let Err(e) = tui::run(
         client,
-        config.accounts,
-        0,
-        first_account_name,
-        config.ui.page_size,
-        config.ui.scrolloff,
-        config.ui.editor,
-        config.ui.browser,
-        config.ui.mouse,
-        config.ui.sync_interval_secs,
-        config.mail.archive_folder,
-        config.mail.deleted_folder,
-        config.mail.reply_from,
-        config.mail.rules_mailbox_regex,
-        config.mail.my_email_regex,
-        config.mail.retention_policies,
+        config,
         compiled_rules,
         custom_headers,
-        config.theme,
-        config.spam,
         offline,
+        config_path,
+        rules_path,
     ) {
         eprintln!("TUI error: {}", e);
         std::process::exit(1);
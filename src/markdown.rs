@@ -0,0 +1,277 @@
+//! Minimal Markdown to HTML conversion for the HTML part of messages
+//! composed as `text/markdown`.
+//!
+//! Covers what people write in mail: paragraphs, ATX headings, `>` quotes,
+//! `-`/`*`/`1.` lists, fenced code, rules, emphasis, code spans and links.
+//! Anything else passes through as escaped text, so the output is always
+//! safe to embed. A `-- ` signature block keeps its line breaks.
+
+/// Render a Markdown body as a standalone HTML document.
+pub fn to_html(markdown: &str) -> String {
+    let lines: Vec<&str> = markdown.lines().collect();
+    format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"></head><body>\n{}</body></html>\n",
+        blocks(&lines)
+    )
+}
+
+fn blocks(lines: &[&str]) -> String {
+    let mut out = String::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim_start();
+
+        if trimmed.is_empty() {
+            i += 1;
+        } else if line == "-- " {
+            let signature: Vec<String> = lines[i..].iter().map(|l| escape(l)).collect();
+            out.push_str(&format!("<p>{}</p>\n", signature.join("<br>\n")));
+            break;
+        } else if trimmed.starts_with("```") {
+            let mut code = Vec::new();
+            i += 1;
+            while i < lines.len() && !lines[i].trim_start().starts_with("```") {
+                code.push(escape(lines[i]));
+                i += 1;
+            }
+            i += 1;
+            out.push_str(&format!("<pre><code>{}</code></pre>\n", code.join("\n")));
+        } else if let Some((level, text)) = heading(trimmed) {
+            out.push_str(&format!("<h{0}>{1}</h{0}>\n", level, inline(text)));
+            i += 1;
+        } else if is_rule(trimmed) {
+            out.push_str("<hr>\n");
+            i += 1;
+        } else if trimmed.starts_with('>') {
+            let mut quoted = Vec::new();
+            while i < lines.len() && lines[i].trim_start().starts_with('>') {
+                let rest = &lines[i].trim_start()[1..];
+                quoted.push(rest.strip_prefix(' ').unwrap_or(rest));
+                i += 1;
+            }
+            out.push_str(&format!("<blockquote>\n{}</blockquote>\n", blocks(&quoted)));
+        } else if let Some(ordered) = list_item(trimmed).map(|(ordered, _)| ordered) {
+            let tag = if ordered { "ol" } else { "ul" };
+            out.push_str(&format!("<{}>\n", tag));
+            while i < lines.len() {
+                let Some((item_ordered, text)) = list_item(lines[i].trim_start()) else {
+                    break;
+                };
+                if item_ordered != ordered {
+                    break;
+                }
+                let mut item = vec![text.to_string()];
+                i += 1;
+                // Indented lines continue the item.
+                while i < lines.len()
+                    && lines[i].starts_with([' ', '\t'])
+                    && !lines[i].trim().is_empty()
+                    && list_item(lines[i].trim_start()).is_none()
+                {
+                    item.push(lines[i].trim().to_string());
+                    i += 1;
+                }
+                out.push_str(&format!("<li>{}</li>\n", inline(&item.join("\n"))));
+            }
+            out.push_str(&format!("</{}>\n", tag));
+        } else {
+            let mut para = Vec::new();
+            while i < lines.len() && starts_paragraph_line(lines[i]) {
+                para.push(lines[i].trim());
+                i += 1;
+            }
+            out.push_str(&format!("<p>{}</p>\n", inline(&para.join("\n"))));
+        }
+    }
+    out
+}
+
+/// Whether a line continues the current paragraph rather than starting a
+/// new block.
+fn starts_paragraph_line(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    !trimmed.is_empty()
+        && line != "-- "
+        && !trimmed.starts_with("```")
+        && !trimmed.starts_with('>')
+        && heading(trimmed).is_none()
+        && !is_rule(trimmed)
+        && list_item(trimmed).is_none()
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &line[level..];
+    if rest.is_empty() {
+        return Some((level, ""));
+    }
+    rest.strip_prefix(' ')
+        .map(|text| (level, text.trim().trim_end_matches('#').trim_end()))
+}
+
+fn is_rule(line: &str) -> bool {
+    let compact: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    compact.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|&m| compact.chars().all(|c| c == m))
+}
+
+/// `(ordered, text)` for `- item`, `* item`, `+ item` or `12. item`.
+fn list_item(line: &str) -> Option<(bool, &str)> {
+    for marker in ["- ", "* ", "+ "] {
+        if let Some(text) = line.strip_prefix(marker) {
+            return Some((false, text));
+        }
+    }
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 {
+        if let Some(text) = line[digits..].strip_prefix(". ") {
+            return Some((true, text));
+        }
+    }
+    None
+}
+
+/// Inline spans: `code`, **strong**, *em*/_em_ and [links](url).
+fn inline(text: &str) -> String {
+    let mut out = String::new();
+    let mut i = 0;
+    while i < text.len() {
+        let rest = &text[i..];
+        let prev = text[..i].chars().next_back();
+
+        if let Some(inner) = rest.strip_prefix('`') {
+            if let Some(end) = inner.find('`') {
+                out.push_str(&format!("<code>{}</code>", escape(&inner[..end])));
+                i += end + 2;
+                continue;
+            }
+        }
+        if let Some((label, url, len)) = link(rest) {
+            out.push_str(&format!(
+                "<a href=\"{}\">{}</a>",
+                escape(url),
+                inline(label)
+            ));
+            i += len;
+            continue;
+        }
+        if let Some(delim) = ["**", "__"].into_iter().find(|d| rest.starts_with(d)) {
+            if let Some(end) = rest[2..].find(delim).filter(|&end| end > 0) {
+                out.push_str(&format!("<strong>{}</strong>", inline(&rest[2..2 + end])));
+                i += end + 4;
+                continue;
+            }
+        }
+        if let Some(delim) = ['*', '_'].into_iter().find(|&d| rest.starts_with(d)) {
+            // `_` inside a word (snake_case) is not emphasis.
+            let word_inside = delim == '_' && prev.is_some_and(|c| c.is_alphanumeric());
+            let opens = rest[1..].starts_with(|c: char| !c.is_whitespace());
+            if !word_inside && opens {
+                if let Some(end) = rest[1..].find(delim).filter(|&end| end > 0) {
+                    out.push_str(&format!("<em>{}</em>", inline(&rest[1..1 + end])));
+                    i += end + 2;
+                    continue;
+                }
+            }
+        }
+
+        let c = rest.chars().next().unwrap_or_default();
+        out.push_str(&escape(&c.to_string()));
+        i += c.len_utf8();
+    }
+    out
+}
+
+/// `[label](url)` at the start of `text`: (label, url, bytes consumed).
+/// Only http, https and mailto URLs make links; anything else (say
+/// `javascript:` or `data:`) is left as escaped text.
+fn link(text: &str) -> Option<(&str, &str, usize)> {
+    let inner = text.strip_prefix('[')?;
+    let label_end = inner.find("](")?;
+    let after = &inner[label_end + 2..];
+    let url_end = after.find(')')?;
+    let url = after[..url_end].trim();
+    if url.is_empty() || url.contains(char::is_whitespace) {
+        return None;
+    }
+    let scheme = url.split_once(':')?.0.to_ascii_lowercase();
+    if !matches!(scheme.as_str(), "http" | "https" | "mailto") {
+        return None;
+    }
+    Some((&inner[..label_end], url, 1 + label_end + 2 + url_end + 1))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(markdown: &str) -> String {
+        let lines: Vec<&str> = markdown.lines().collect();
+        blocks(&lines)
+    }
+
+    #[test]
+    fn renders_blocks() {
+        let md = "# Plan\n\nFirst line\nsame paragraph.\n\n- one\n- two\n  continued\n\n1. a\n2. b\n\n> quoted\n> more\n\n---\n```\nlet x = <1>;\n```";
+        assert_eq!(
+            body(md),
+            "<h1>Plan</h1>\n\
+             <p>First line\nsame paragraph.</p>\n\
+             <ul>\n<li>one</li>\n<li>two\ncontinued</li>\n</ul>\n\
+             <ol>\n<li>a</li>\n<li>b</li>\n</ol>\n\
+             <blockquote>\n<p>quoted\nmore</p>\n</blockquote>\n\
+             <hr>\n\
+             <pre><code>let x = &lt;1&gt;;</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn renders_inline_spans_and_escapes() {
+        assert_eq!(
+            inline("**bold** and *it* and _it_ with `a<b` see [docs](https://x.test/?a=1&b=2)"),
+            "<strong>bold</strong> and <em>it</em> and <em>it</em> with <code>a&lt;b</code> \
+             see <a href=\"https://x.test/?a=1&amp;b=2\">docs</a>"
+        );
+        assert_eq!(inline("snake_case_name"), "snake_case_name");
+        assert_eq!(inline("2 * 3 * 4"), "2 * 3 * 4");
+        assert_eq!(inline("<script>"), "&lt;script&gt;");
+    }
+
+    #[test]
+    fn links_only_web_and_mail_urls() {
+        assert_eq!(
+            inline("[mail](MAILTO:a@x.test) [web](http://x.test)"),
+            "<a href=\"MAILTO:a@x.test\">mail</a> <a href=\"http://x.test\">web</a>"
+        );
+        assert_eq!(
+            inline("[x](javascript:alert(1))"),
+            "[x](javascript:alert(1))"
+        );
+        assert_eq!(
+            inline("[x](data:text/html,<b>)"),
+            "[x](data:text/html,&lt;b&gt;)"
+        );
+        assert_eq!(inline("[x](/relative)"), "[x](/relative)");
+    }
+
+    #[test]
+    fn keeps_signature_lines() {
+        assert_eq!(
+            body("Thanks\n\n-- \nAda\nLovelace Ltd"),
+            "<p>Thanks</p>\n<p>-- <br>\nAda<br>\nLovelace Ltd</p>\n"
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! Muted threads: per-account set of JMAP thread ids whose new messages are
+//! moved out of the inbox as they arrive. Mail already in the thread when it
+//! was muted stays where it is.
+//!
+//! Like the spam model this is user-curated state, so it lives under the data
+//! dir and survives cache clears.
+
+use crate::jmap::types::Email;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MutedThreads {
+    /// Thread id -> when it was muted, in seconds since the Unix epoch.
+    threads: BTreeMap<String, i64>,
+}
+
+impl MutedThreads {
+    /// Load the mute set, starting empty if the file is missing or unreadable.
+    pub fn load(path: &Path) -> Self {
+        match std::fs::read(path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                log_warn!("[Mute] failed to parse {:?}: {}", path, e);
+                MutedThreads::default()
+            }),
+            Err(_) => MutedThreads::default(),
+        }
+    }
+
+    /// Persist atomically (write to a temp file, then rename).
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("create mute dir {:?}: {}", parent, e))?;
+        }
+        let bytes =
+            serde_json::to_vec(self).map_err(|e| format!("serialize muted threads: {}", e))?;
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, &bytes).map_err(|e| format!("write {:?}: {}", tmp, e))?;
+        std::fs::rename(&tmp, path).map_err(|e| format!("rename into {:?}: {}", path, e))?;
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.threads.is_empty()
+    }
+
+    pub fn contains(&self, thread_id: &str) -> bool {
+        self.threads.contains_key(thread_id)
+    }
+
+    /// Whether `email` arrived in a muted thread after it was muted. Undated
+    /// mail is left alone.
+    pub fn mutes(&self, email: &Email) -> bool {
+        let Some(&muted_at) = email
+            .thread_id
+            .as_deref()
+            .and_then(|thread_id| self.threads.get(thread_id))
+        else {
+            return false;
+        };
+        crate::rules::email_received_epoch(email).is_some_and(|received| received > muted_at)
+    }
+
+    /// Mute (as of `now`) or unmute a thread. Returns false when it was
+    /// already in that state.
+    pub fn set(&mut self, thread_id: &str, muted: bool, now: i64) -> bool {
+        if muted {
+            if self.contains(thread_id) {
+                return false;
+            }
+            self.threads.insert(thread_id.to_string(), now);
+            true
+        } else {
+            self.threads.remove(thread_id).is_some()
+        }
+    }
+}
+
+/// Mute file for an account: `$XDG_DATA_HOME/tmc/muted/<account>.json`.
+pub fn muted_path(account_name: &str) -> PathBuf {
+    let data_dir = if let Ok(xdg) = std::env::var("XDG_DATA_HOME") {
+        PathBuf::from(xdg)
+    } else if let Ok(home) = std::env::var("HOME") {
+        PathBuf::from(home).join(".local").join("share")
+    } else {
+        PathBuf::from(".")
+    };
+    let safe_name = account_name.replace(['/', '\\', '\0'], "_");
+    data_dir
+        .join("tmc")
+        .join("muted")
+        .join(format!("{}.json", safe_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_save_and_load_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("muted").join("acct.json");
+
+        let mut muted = MutedThreads::load(&path);
+        assert!(muted.is_empty());
+        assert!(muted.set("T1", true, 100));
+        assert!(!muted.set("T1", true, 200));
+        assert!(muted.set("T2", true, 100));
+        assert!(muted.set("T2", false, 100));
+        assert!(!muted.set("T2", false, 100));
+        muted.save(&path).unwrap();
+
+        let loaded = MutedThreads::load(&path);
+        assert!(loaded.contains("T1"));
+        assert!(!loaded.contains("T2"));
+    }
+
+    #[test]
+    fn only_mail_received_after_muting_is_muted() {
+        let email = |thread_id: &str, received_at: Option<&str>| -> Email {
+            serde_json::from_value(serde_json::json!({
+                "id": "e1",
+                "threadId": thread_id,
+                "receivedAt": received_at,
+                "mailboxIds": {},
+            }))
+            .unwrap()
+        };
+        let mut muted = MutedThreads::default();
+        // 2025-01-12T09:30:00Z
+        muted.set("T1", true, 1_736_674_200);
+        assert!(muted.mutes(&email("T1", Some("2025-01-12T09:31:00Z"))));
+        assert!(!muted.mutes(&email("T1", Some("2025-01-12T09:29:00Z"))));
+        assert!(!muted.mutes(&email("T1", None)));
+        assert!(!muted.mutes(&email("T2", Some("2025-01-12T09:31:00Z"))));
+    }
+}
@@ -0,0 +1,132 @@
+//! In-memory text index over cached mail, used to answer searches in
+//! `--offline` mode.
+//!
+//! Matching is a case-insensitive substring test: every whitespace-separated
+//! term of the query must appear somewhere in the subject, senders, preview
+//! or text body.
+
+use crate::jmap::types::Email;
+
+struct Entry {
+    id: String,
+    received_at: Option<String>,
+    /// Lowercased subject, senders, preview and text body.
+    text: String,
+}
+
+#[derive(Default)]
+pub struct SearchIndex {
+    /// Newest first.
+    entries: Vec<Entry>,
+}
+
+fn searchable_text(email: &Email) -> String {
+    let mut text = String::new();
+    let mut push = |s: &str| {
+        text.push_str(&s.to_lowercase());
+        text.push('\n');
+    };
+    if let Some(subject) = &email.subject {
+        push(subject);
+    }
+    for addr in email.from.iter().flatten() {
+        push(&addr.to_string());
+    }
+    if let Some(preview) = &email.preview {
+        push(preview);
+    }
+    for part in email.text_body.iter().flatten() {
+        if let Some(body) = email.body_values.get(&part.part_id) {
+            push(&body.value);
+        }
+    }
+    text
+}
+
+impl SearchIndex {
+    pub fn build(emails: Vec<Email>) -> Self {
+        let mut entries: Vec<Entry> = emails
+            .iter()
+            .map(|email| Entry {
+                id: email.id.clone(),
+                received_at: email.received_at.clone(),
+                text: searchable_text(email),
+            })
+            .collect();
+        entries.sort_by(|a, b| b.received_at.cmp(&a.received_at));
+        SearchIndex { entries }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Ids of indexed emails matching `query` and received within the
+    /// optional `[after, before)` range, newest first. Timestamps are
+    /// compared as RFC 3339 UTC strings.
+    pub fn search(&self, query: &str, after: Option<&str>, before: Option<&str>) -> Vec<&str> {
+        let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        self.entries
+            .iter()
+            .filter(|e| terms.iter().all(|t| e.text.contains(t.as_str())))
+            .filter(|e| match (after, e.received_at.as_deref()) {
+                (Some(after), Some(at)) => at >= after,
+                (Some(_), None) => false,
+                (None, _) => true,
+            })
+            .filter(|e| match (before, e.received_at.as_deref()) {
+                (Some(before), Some(at)) => at < before,
+                (Some(_), None) => false,
+                (None, _) => true,
+            })
+            .map(|e| e.id.as_str())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn email(id: &str, subject: &str, received_at: &str, body: &str) -> Email {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "subject": subject,
+            "receivedAt": received_at,
+            "from": [{"name": "Alice Example", "email": "alice@example.com"}],
+            "textBody": [{"partId": "1"}],
+            "bodyValues": {"1": {"value": body}},
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_search_matches_all_terms_case_insensitively() {
+        let index = SearchIndex::build(vec![
+            email("e1", "Flight itinerary", "2025-01-10T08:00:00Z", "Gate B12"),
+            email("e2", "Lunch", "2025-01-12T08:00:00Z", "See you at the gate"),
+            email("e3", "Invoice", "2025-01-11T08:00:00Z", "Amount due"),
+        ]);
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.search("GATE", None, None), vec!["e2", "e1"]);
+        assert_eq!(index.search("gate flight", None, None), vec!["e1"]);
+        assert_eq!(index.search("alice@example", None, None).len(), 3);
+        assert!(index.search("nothing", None, None).is_empty());
+    }
+
+    #[test]
+    fn test_search_date_range() {
+        let index = SearchIndex::build(vec![
+            email("e1", "a", "2025-01-10T08:00:00Z", ""),
+            email("e2", "a", "2025-01-12T08:00:00Z", ""),
+        ]);
+        assert_eq!(
+            index.search("", Some("2025-01-11T00:00:00Z"), None),
+            vec!["e2"]
+        );
+        assert_eq!(
+            index.search("", None, Some("2025-01-11T00:00:00Z")),
+            vec!["e1"]
+        );
+    }
+}
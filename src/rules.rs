@@ -1,21 +1,44 @@
-use crate::jmap::client::JmapClient;
-use crate::jmap::types::{Email, EmailAddress, Mailbox};
-use regex::Regex;
+use crate::jmap::client::{JmapClient, RULES_MAX_BODY_BYTES};
+use crate::jmap::types::{validate_keyword, Email, EmailAddress, Mailbox};
+use regex::{Regex, RegexBuilder};
 use serde::Deserialize;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 // --- TOML deserialization types ---
 
 #[derive(Debug, Deserialize)]
 pub struct RulesConfig {
     #[serde(default)]
-    pub rule: Vec<RuleDef>,
+    pub rules: RulesSettings,
+    /// Each `[[rule]]` with the byte span of its table, header included.
+    #[serde(default)]
+    pub rule: Vec<toml::Spanned<RuleDef>>,
+}
+
+/// The `[rules]` table: defaults for every `[[rule]]`.
+#[derive(Debug, Default, Deserialize)]
+pub struct RulesSettings {
+    /// `continue_processing` for rules that don't set it.
+    #[serde(default)]
+    pub default_continue: bool,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct RuleDef {
     pub name: String,
+    /// Evaluation order: lower runs first; rules without one count as 0 and
+    /// ties keep file order.
+    #[serde(default)]
+    pub priority: Option<i64>,
+    /// Folders where new mail gets this rule, as a glob (`*`, `?`) on the
+    /// folder name or `Parent/Child` path. Overrides `rules_mailbox_regex`.
+    #[serde(default)]
+    pub mailbox: Option<String>,
+    /// Like `mailbox`, as a regex.
+    #[serde(default)]
+    pub mailbox_regex: Option<String>,
     #[serde(default)]
     pub continue_processing: Option<bool>,
     #[serde(default)]
@@ -45,10 +68,52 @@ pub enum TriageHintActionDef {
 #[derive(Debug, Deserialize)]
 #[serde(untagged)]
 pub enum ConditionDef {
-    Header { header: String, regex: String },
-    All { all: Vec<ConditionDef> },
-    Any { any: Vec<ConditionDef> },
-    Not { not: Box<ConditionDef> },
+    Header {
+        header: String,
+        regex: String,
+        #[serde(flatten)]
+        flags: RegexFlagsDef,
+    },
+    Body {
+        body: String,
+        #[serde(flatten)]
+        flags: RegexFlagsDef,
+    },
+    ListId {
+        list_id: String,
+        #[serde(flatten)]
+        flags: RegexFlagsDef,
+    },
+    OlderThan {
+        older_than_days: u32,
+    },
+    NewerThan {
+        newer_than_days: u32,
+    },
+    HasAttachment {
+        has_attachment: bool,
+    },
+    All {
+        all: Vec<ConditionDef>,
+    },
+    Any {
+        any: Vec<ConditionDef>,
+    },
+    Not {
+        not: Box<ConditionDef>,
+    },
+}
+
+/// Options shared by the regex conditions (`header`, `body`, `list_id`).
+#[derive(Debug, Default, Deserialize)]
+pub struct RegexFlagsDef {
+    /// `false` ignores case; by default matching is case-sensitive, as with
+    /// a plain regex.
+    #[serde(default)]
+    pub case_sensitive: Option<bool>,
+    /// Only match at word boundaries (`\b(?:...)\b`).
+    #[serde(default)]
+    pub whole_word: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -65,6 +130,15 @@ pub struct ActionsDef {
     pub move_to: Option<String>,
     #[serde(default)]
     pub delete: Option<bool>,
+    /// Keywords to set (`true`) or clear (`false`), e.g. `{ "$label1" = true }`.
+    #[serde(default)]
+    pub keywords: BTreeMap<String, bool>,
+    /// Address to forward matching mail to.
+    #[serde(default)]
+    pub forward_to: Option<String>,
+    /// Stop evaluating later rules, even with `continue_processing`.
+    #[serde(default)]
+    pub stop: Option<bool>,
 }
 
 // --- Compiled types ---
@@ -72,6 +146,9 @@ pub struct ActionsDef {
 #[derive(Debug)]
 pub struct CompiledRule {
     pub name: String,
+    pub priority: i64,
+    /// From `mailbox`/`mailbox_regex`; `None` defers to `rules_mailbox_regex`.
+    pub mailbox: Option<Regex>,
     pub continue_processing: bool,
     pub skip_if_to_me: bool,
     pub actions: Vec<Action>,
@@ -80,19 +157,80 @@ pub struct CompiledRule {
     pub triage_confidence: Option<f32>,
 }
 
+impl CompiledRule {
+    /// Whether new mail in the folder `name` (at `path`) gets this rule: its
+    /// own `mailbox` pattern, tried on both, or else the global
+    /// `rules_mailbox_regex` on the name.
+    pub fn runs_in(&self, name: &str, path: &str, rules_mailbox_regex: &Regex) -> bool {
+        match &self.mailbox {
+            Some(pattern) => pattern.is_match(name) || pattern.is_match(path),
+            None => rules_mailbox_regex.is_match(name),
+        }
+    }
+
+    /// Whether an explicit run over the folder `name` (at `path`) uses this
+    /// rule: its own `mailbox` pattern decides as for new mail, but a rule
+    /// without one runs in whatever folder was picked.
+    pub fn runs_on_demand_in(&self, name: &str, path: &str) -> bool {
+        self.mailbox
+            .as_ref()
+            .is_none_or(|pattern| pattern.is_match(name) || pattern.is_match(path))
+    }
+
+    /// Why the rule, though valid, never changes anything: it has no
+    /// actions and no triage hint.
+    pub fn warning(&self) -> Option<&'static str> {
+        (self.actions.is_empty() && self.triage_action.is_none())
+            .then_some("no actions, so matching mail is left as is (add one under [rule.actions])")
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Action {
     MarkRead,
     MarkUnread,
     Flag,
     Unflag,
-    Move { target: String },
+    Move {
+        target: String,
+    },
     Delete,
+    SetKeyword {
+        keyword: String,
+        value: bool,
+    },
+    Forward {
+        to: String,
+    },
+    /// Halt the rule chain for this email; changes nothing on the server.
+    Stop,
 }
 
+/// Header stamped on mail sent by a `forward_to` rule. Mail that already
+/// carries it is never forwarded again, so two accounts forwarding to each
+/// other cannot loop.
+pub const FORWARDED_HEADER: &str = "X-Tmc-Forwarded";
+
+/// Keyword set on the original once a `forward_to` rule has forwarded it,
+/// so running the rules again does not send it a second time.
+pub const FORWARDED_KEYWORD: &str = "$forwarded";
+
 #[derive(Debug)]
 pub enum CompiledCondition {
-    Header { header: String, regex: Regex },
+    Header {
+        header: String,
+        regex: Regex,
+    },
+    /// Regex over the extracted text body (first `RULES_MAX_BODY_BYTES`).
+    Body(Regex),
+    /// Regex over the `List-Id` identifier, without the display name and
+    /// angle brackets.
+    ListId(Regex),
+    /// Received more than N days ago.
+    OlderThanDays(u32),
+    /// Received N days ago or more recently.
+    NewerThanDays(u32),
+    HasAttachment(bool),
     All(Vec<CompiledCondition>),
     Any(Vec<CompiledCondition>),
     Not(Box<CompiledCondition>),
@@ -105,12 +243,51 @@ pub fn load_rules(path: &Path) -> Result<Vec<CompiledRule>, String> {
         std::fs::read_to_string(path).map_err(|e| format!("Failed to read rules file: {}", e))?;
     let config: RulesConfig =
         toml::from_str(&content).map_err(|e| format!("Failed to parse rules TOML: {}", e))?;
+    compile_rules(config, &content)
+}
 
-    config
+/// Compile every rule in evaluation order: sorted by `priority` (stable,
+/// so equal priorities keep file order), with `[rules].default_continue`
+/// filling in an unset `continue_processing`. Every rule is checked, and
+/// all problems are reported together, each with the rule's name and the
+/// line of its `[[rule]]` header in `content`, the text `config` was
+/// parsed from.
+fn compile_rules(config: RulesConfig, content: &str) -> Result<Vec<CompiledRule>, String> {
+    let default_continue = config.rules.default_continue;
+    let mut defs: Vec<(Option<usize>, RuleDef)> = config
         .rule
         .into_iter()
-        .map(compile_rule)
-        .collect::<Result<Vec<_>, _>>()
+        .map(|def| {
+            let line = content
+                .get(..def.span().start)
+                .map(|before| before.matches('\n').count() + 1);
+            (line, def.into_inner())
+        })
+        .collect();
+    defs.sort_by_key(|(_, def)| def.priority.unwrap_or(0));
+
+    let mut rules = Vec::new();
+    let mut problems = Vec::new();
+    for (line, mut def) in defs {
+        def.continue_processing = Some(def.continue_processing.unwrap_or(default_continue));
+        let name = def.name.clone();
+        match build_rule(def) {
+            Ok(rule) => rules.push(rule),
+            Err(e) => match line {
+                Some(line) => problems.push(format!("Rule '{}' (line {}): {}", name, line, e)),
+                None => problems.push(format!("Rule '{}': {}", name, e)),
+            },
+        }
+    }
+    if problems.is_empty() {
+        Ok(rules)
+    } else {
+        Err(format!(
+            "{} invalid rule(s):\n  {}",
+            problems.len(),
+            problems.join("\n  ")
+        ))
+    }
 }
 
 pub fn format_rules_for_display(rules: &[CompiledRule]) -> String {
@@ -121,6 +298,10 @@ pub fn format_rules_for_display(rules: &[CompiledRule]) -> String {
     let mut out = String::new();
     for (idx, rule) in rules.iter().enumerate() {
         out.push_str(&format!("Rule {}: {}\n", idx + 1, rule.name));
+        out.push_str(&format!("  Priority: {}\n", rule.priority));
+        if let Some(mailbox) = &rule.mailbox {
+            out.push_str(&format!("  Mailbox: /{}/\n", mailbox.as_str()));
+        }
         out.push_str(&format!(
             "  Continue processing: {}\n",
             if rule.continue_processing {
@@ -141,17 +322,37 @@ pub fn format_rules_for_display(rules: &[CompiledRule]) -> String {
             "  Actions: {}\n",
             format_actions_for_display(&rule.actions)
         ));
+        if let Some(warning) = rule.warning() {
+            out.push_str(&format!("  Warning: {}\n", warning));
+        }
     }
     out
 }
 
-fn compile_rule(def: RuleDef) -> Result<CompiledRule, String> {
+/// Validate and compile one rule; errors are not yet labelled with the rule.
+fn build_rule(def: RuleDef) -> Result<CompiledRule, String> {
+    if let Some(target) = &def.actions.move_to {
+        check_mailbox_path(target)?;
+    }
+    for keyword in def.actions.keywords.keys() {
+        validate_keyword(keyword)?;
+    }
     let actions = compile_actions(&def.actions);
-    let condition = compile_condition(def.match_condition)
-        .map_err(|e| format!("Rule '{}': {}", def.name, e))?;
+    let mailbox = match (&def.mailbox, &def.mailbox_regex) {
+        (Some(_), Some(_)) => return Err("set mailbox or mailbox_regex, not both".to_string()),
+        (Some(glob), None) => Some(glob_to_regex(glob)?),
+        (None, Some(pattern)) => Some(
+            Regex::new(pattern)
+                .map_err(|e| format!("Invalid mailbox_regex '{}': {}", pattern, e))?,
+        ),
+        (None, None) => None,
+    };
+    let condition = compile_condition(def.match_condition)?;
 
     Ok(CompiledRule {
         name: def.name,
+        priority: def.priority.unwrap_or(0),
+        mailbox,
         continue_processing: def.continue_processing.unwrap_or(false),
         skip_if_to_me: def.skip_if_to_me.unwrap_or(false),
         actions,
@@ -170,6 +371,12 @@ fn format_condition_for_display(condition: &CompiledCondition) -> String {
         CompiledCondition::Header { header, regex } => {
             format!("{} =~ /{}/", header, regex.as_str())
         }
+        CompiledCondition::Body(regex) => format!("body =~ /{}/", regex.as_str()),
+        CompiledCondition::ListId(regex) => format!("list_id =~ /{}/", regex.as_str()),
+        CompiledCondition::OlderThanDays(days) => format!("age > {} days", days),
+        CompiledCondition::NewerThanDays(days) => format!("age <= {} days", days),
+        CompiledCondition::HasAttachment(true) => "has attachment".to_string(),
+        CompiledCondition::HasAttachment(false) => "no attachment".to_string(),
         CompiledCondition::All(conditions) => {
             let parts: Vec<String> = conditions
                 .iter()
@@ -204,6 +411,9 @@ fn format_actions_for_display(actions: &[Action]) -> String {
             Action::Unflag => "unflag".to_string(),
             Action::Move { target } => format!("move_to={}", target),
             Action::Delete => "delete".to_string(),
+            Action::SetKeyword { keyword, value } => format!("keyword {}={}", keyword, value),
+            Action::Forward { to } => format!("forward_to={}", to),
+            Action::Stop => "stop".to_string(),
         })
         .collect();
     parts.join(", ")
@@ -231,18 +441,138 @@ fn compile_actions(def: &ActionsDef) -> Vec<Action> {
     if def.delete == Some(true) {
         actions.push(Action::Delete);
     }
+    for (keyword, value) in &def.keywords {
+        actions.push(Action::SetKeyword {
+            keyword: keyword.clone(),
+            value: *value,
+        });
+    }
+    if let Some(ref to) = def.forward_to {
+        actions.push(Action::Forward { to: to.clone() });
+    }
+    if def.stop == Some(true) {
+        actions.push(Action::Stop);
+    }
     actions
 }
 
+/// Compile a `mailbox` glob into a regex matching the whole name: `*` is
+/// any run of characters (including `/`), `?` any one character.
+fn glob_to_regex(glob: &str) -> Result<Regex, String> {
+    let mut pattern = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            c => pattern.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern).map_err(|e| format!("Invalid mailbox glob '{}': {}", glob, e))
+}
+
+/// Reject `move_to` values that cannot name a mailbox. Whether the mailbox
+/// exists is only known once connected, so this checks the shape: not
+/// empty, no empty `/` segments, no control characters.
+fn check_mailbox_path(target: &str) -> Result<(), String> {
+    if target.trim().is_empty() {
+        return Err("move_to is empty".to_string());
+    }
+    if target.chars().any(char::is_control) {
+        return Err(format!(
+            "move_to '{}' contains control characters",
+            target.escape_debug()
+        ));
+    }
+    if target.split('/').any(|segment| segment.trim().is_empty()) {
+        return Err(format!(
+            "move_to '{}' has an empty path segment (stray '/'?)",
+            target
+        ));
+    }
+    Ok(())
+}
+
+/// Compile a condition regex, applying `case_sensitive` and `whole_word`.
+/// Contradictory settings are rejected rather than silently ignored.
+fn compile_regex(pattern: &str, flags: &RegexFlagsDef) -> Result<Regex, String> {
+    let whole_word = flags.whole_word.unwrap_or(false);
+    if flags.case_sensitive == Some(true) && has_inline_ignore_case(pattern) {
+        return Err(format!(
+            "regex '{}' has an inline (?i) flag but case_sensitive = true",
+            pattern
+        ));
+    }
+    if whole_word && (pattern.starts_with('^') || pattern.ends_with('$')) {
+        return Err(format!(
+            "regex '{}' is anchored with ^/$; whole_word = true cannot apply",
+            pattern
+        ));
+    }
+
+    let full = if whole_word {
+        format!(r"\b(?:{})\b", pattern)
+    } else {
+        pattern.to_string()
+    };
+    RegexBuilder::new(&full)
+        .case_insensitive(flags.case_sensitive == Some(false))
+        .build()
+        .map_err(|e| format!("Invalid regex '{}': {}", pattern, e))
+}
+
+/// Whether `pattern` turns on case-insensitivity with an inline flag group
+/// such as `(?i)`, `(?mi)` or `(?si:...)`, as opposed to `(?-i)`.
+fn has_inline_ignore_case(pattern: &str) -> bool {
+    let mut escaped = false;
+    for (i, c) in pattern.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' => escaped = true,
+            '(' if pattern[i + 1..].starts_with('?') => {
+                let flags: String = pattern[i + 2..]
+                    .chars()
+                    .take_while(|c| c.is_ascii_alphabetic() || *c == '-')
+                    .collect();
+                let rest = &pattern[i + 2 + flags.len()..];
+                let enabled = flags.split('-').next().unwrap_or("");
+                if (rest.starts_with(')') || rest.starts_with(':')) && enabled.contains('i') {
+                    return true;
+                }
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
 fn compile_condition(def: ConditionDef) -> Result<CompiledCondition, String> {
     match def {
-        ConditionDef::Header { header, regex } => {
-            let compiled =
-                Regex::new(&regex).map_err(|e| format!("Invalid regex '{}': {}", regex, e))?;
-            Ok(CompiledCondition::Header {
-                header,
-                regex: compiled,
-            })
+        ConditionDef::Header {
+            header,
+            regex,
+            flags,
+        } => Ok(CompiledCondition::Header {
+            header,
+            regex: compile_regex(&regex, &flags)?,
+        }),
+        ConditionDef::Body { body, flags } => {
+            Ok(CompiledCondition::Body(compile_regex(&body, &flags)?))
+        }
+        ConditionDef::ListId { list_id, flags } => {
+            Ok(CompiledCondition::ListId(compile_regex(&list_id, &flags)?))
+        }
+        ConditionDef::OlderThan { older_than_days } => {
+            Ok(CompiledCondition::OlderThanDays(older_than_days))
+        }
+        ConditionDef::NewerThan { newer_than_days } => {
+            Ok(CompiledCondition::NewerThanDays(newer_than_days))
+        }
+        ConditionDef::HasAttachment { has_attachment } => {
+            Ok(CompiledCondition::HasAttachment(has_attachment))
         }
         ConditionDef::All { all } => {
             let conditions = all
@@ -268,17 +598,43 @@ fn compile_condition(def: ConditionDef) -> Result<CompiledCondition, String> {
 // --- Header extraction for JMAP ---
 
 /// Collect custom (non-standard) header names from all rules and return
-/// them as JMAP property strings like `header:X-Custom:asText`.
+/// them as JMAP property strings like `header:X-Custom:asText`. When a rule
+/// matches on `body`, the body properties are requested too.
 pub fn extract_custom_headers(rules: &[CompiledRule]) -> Vec<String> {
     let mut headers = std::collections::HashSet::new();
+    let mut needs_body = false;
     for rule in rules {
+        needs_body |= condition_uses_body(&rule.condition);
         collect_headers_from_condition(&rule.condition, &mut headers);
+        // The loop guard needs the marker of already-forwarded mail.
+        if rule
+            .actions
+            .iter()
+            .any(|a| matches!(a, Action::Forward { .. }))
+        {
+            headers.insert(FORWARDED_HEADER.to_string());
+        }
     }
-    headers
+    let mut properties: Vec<String> = headers
         .into_iter()
         .filter(|h| !is_standard_header(h))
         .map(|h| format!("header:{}:asText", h))
-        .collect()
+        .collect();
+    if needs_body {
+        properties.extend(["textBody", "htmlBody", "bodyValues"].map(String::from));
+    }
+    properties
+}
+
+fn condition_uses_body(condition: &CompiledCondition) -> bool {
+    match condition {
+        CompiledCondition::Body(_) => true,
+        CompiledCondition::All(conditions) | CompiledCondition::Any(conditions) => {
+            conditions.iter().any(condition_uses_body)
+        }
+        CompiledCondition::Not(inner) => condition_uses_body(inner),
+        _ => false,
+    }
 }
 
 fn collect_headers_from_condition(
@@ -289,6 +645,13 @@ fn collect_headers_from_condition(
         CompiledCondition::Header { header, .. } => {
             headers.insert(header.clone());
         }
+        CompiledCondition::ListId(_) => {
+            headers.insert(LIST_ID_HEADER.to_string());
+        }
+        CompiledCondition::Body(_)
+        | CompiledCondition::OlderThanDays(_)
+        | CompiledCondition::NewerThanDays(_)
+        | CompiledCondition::HasAttachment(_) => {}
         CompiledCondition::All(conditions) | CompiledCondition::Any(conditions) => {
             for c in conditions {
                 collect_headers_from_condition(c, headers);
@@ -310,19 +673,162 @@ fn is_standard_header(header: &str) -> bool {
 // --- Condition evaluation ---
 
 pub fn evaluate_condition(condition: &CompiledCondition, email: &Email) -> bool {
+    // A broken clock only affects age conditions; header matches still work.
+    let today = current_days_since_epoch().unwrap_or(i64::MAX);
+    evaluate_condition_at(condition, email, today)
+}
+
+fn evaluate_condition_at(condition: &CompiledCondition, email: &Email, today: i64) -> bool {
     match condition {
         CompiledCondition::Header { header, regex } => match resolve_header_value(header, email) {
             Some(value) => regex.is_match(&value),
             None => false,
         },
-        CompiledCondition::All(conditions) => {
-            conditions.iter().all(|c| evaluate_condition(c, email))
-        }
-        CompiledCondition::Any(conditions) => {
-            conditions.iter().any(|c| evaluate_condition(c, email))
+        CompiledCondition::Body(regex) => regex.is_match(&email_body_text(email)),
+        CompiledCondition::ListId(regex) => resolve_header_value(LIST_ID_HEADER, email)
+            .is_some_and(|value| regex.is_match(normalize_list_id(&value))),
+        CompiledCondition::OlderThanDays(days) => match email_received_days(email) {
+            Some(received) => received < today.saturating_sub(*days as i64),
+            None => false,
+        },
+        CompiledCondition::NewerThanDays(days) => match email_received_days(email) {
+            Some(received) => received >= today.saturating_sub(*days as i64),
+            None => false,
+        },
+        CompiledCondition::HasAttachment(expected) => email_has_attachment(email) == *expected,
+        CompiledCondition::All(conditions) => conditions
+            .iter()
+            .all(|c| evaluate_condition_at(c, email, today)),
+        CompiledCondition::Any(conditions) => conditions
+            .iter()
+            .any(|c| evaluate_condition_at(c, email, today)),
+        CompiledCondition::Not(inner) => !evaluate_condition_at(inner, email, today),
+    }
+}
+
+// --- Rule tracing (debugging) ---
+
+/// One condition of a [`RuleTrace`], listed depth-first; `all`/`any`/`not`
+/// are followed by their children at `depth + 1`.
+#[derive(Debug, Clone)]
+pub struct ConditionTrace {
+    pub depth: usize,
+    pub condition: String,
+    pub passed: bool,
+}
+
+/// How one rule evaluated against one email.
+#[derive(Debug, Clone)]
+pub struct RuleTrace {
+    pub rule_name: String,
+    /// `skip_if_to_me` is set and the email is addressed to me.
+    pub skipped_to_me: bool,
+    pub matched: bool,
+    pub conditions: Vec<ConditionTrace>,
+}
+
+/// Evaluate every rule against `email` and record each condition's result,
+/// regardless of `continue_processing`, to explain why a rule did or did not
+/// fire. `apply_rules` remains the source of truth for what actually runs.
+pub fn trace_rules(
+    rules: &[CompiledRule],
+    email: &Email,
+    my_email_regex: &Regex,
+) -> Vec<RuleTrace> {
+    let today = current_days_since_epoch().unwrap_or(i64::MAX);
+    rules
+        .iter()
+        .map(|rule| {
+            let mut conditions = Vec::new();
+            let matched = trace_condition(&rule.condition, email, today, 0, &mut conditions);
+            RuleTrace {
+                rule_name: rule.name.clone(),
+                skipped_to_me: rule.skip_if_to_me && is_email_to_me(email, my_email_regex),
+                matched,
+                conditions,
+            }
+        })
+        .collect()
+}
+
+fn trace_condition(
+    condition: &CompiledCondition,
+    email: &Email,
+    today: i64,
+    depth: usize,
+    out: &mut Vec<ConditionTrace>,
+) -> bool {
+    let passed = evaluate_condition_at(condition, email, today);
+    let (label, children): (String, Vec<&CompiledCondition>) = match condition {
+        CompiledCondition::All(conditions) => ("all".to_string(), conditions.iter().collect()),
+        CompiledCondition::Any(conditions) => ("any".to_string(), conditions.iter().collect()),
+        CompiledCondition::Not(inner) => ("not".to_string(), vec![inner.as_ref()]),
+        leaf => (format_condition_for_display(leaf), Vec::new()),
+    };
+    out.push(ConditionTrace {
+        depth,
+        condition: label,
+        passed,
+    });
+    for child in children {
+        trace_condition(child, email, today, depth + 1, out);
+    }
+    passed
+}
+
+// --- Date helpers (shared with retention expiry) ---
+
+pub fn current_days_since_epoch() -> Result<i64, String> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| format!("system clock error: {}", e))?;
+    Ok((now.as_secs() / 86_400) as i64)
+}
+
+/// Day index (since the Unix epoch) of an email's `receivedAt` date.
+pub fn email_received_days(email: &Email) -> Option<i64> {
+    crate::datefmt::parse_date_days(email.received_at.as_deref()?)
+}
+
+/// Seconds since the Unix epoch of an email's `receivedAt`, honoring its
+/// UTC offset.
+pub fn email_received_epoch(email: &Email) -> Option<i64> {
+    crate::datefmt::parse_utc_timestamp(email.received_at.as_deref()?)
+}
+
+/// Text body for `body` conditions, cut to `RULES_MAX_BODY_BYTES`. Emails
+/// fetched without body values (e.g. from the cache) are matched against
+/// their preview instead, which is logged.
+fn email_body_text(email: &Email) -> String {
+    let mut text = if email.body_values.is_empty() {
+        log_warn!(
+            "[Rules] Email {} has no fetched body; body condition checked against its preview",
+            email.id
+        );
+        email.preview.clone().unwrap_or_default()
+    } else {
+        crate::body::plain_text(email)
+    };
+    if text.len() > RULES_MAX_BODY_BYTES {
+        let mut end = RULES_MAX_BODY_BYTES;
+        while !text.is_char_boundary(end) {
+            end -= 1;
         }
-        CompiledCondition::Not(inner) => !evaluate_condition(inner, email),
+        text.truncate(end);
     }
+    text
+}
+
+/// Prefer the server's `hasAttachment` property; fall back to the parsed
+/// attachment list when only `attachments` was fetched.
+fn email_has_attachment(email: &Email) -> bool {
+    if let Some(has) = email.extra.get("hasAttachment").and_then(|v| v.as_bool()) {
+        return has;
+    }
+    email
+        .attachments
+        .as_ref()
+        .is_some_and(|parts| !parts.is_empty())
 }
 
 fn format_addresses(addrs: &Option<Vec<EmailAddress>>) -> Option<String> {
@@ -334,6 +840,18 @@ fn format_addresses(addrs: &Option<Vec<EmailAddress>>) -> Option<String> {
     })
 }
 
+const LIST_ID_HEADER: &str = "List-Id";
+
+/// The identifier part of a `List-Id` value: `Rust Users <users.rust-lang.org>`
+/// becomes `users.rust-lang.org`. Values without brackets are only trimmed.
+fn normalize_list_id(value: &str) -> &str {
+    let value = value.trim();
+    match (value.rfind('<'), value.rfind('>')) {
+        (Some(start), Some(end)) if start < end => value[start + 1..end].trim(),
+        _ => value,
+    }
+}
+
 fn resolve_header_value(header: &str, email: &Email) -> Option<String> {
     match header.to_lowercase().as_str() {
         "from" => format_addresses(&email.from),
@@ -368,6 +886,18 @@ pub fn apply_rules(
     emails: &[Email],
     mailboxes: &[Mailbox],
     my_email_regex: &Regex,
+) -> Vec<RuleApplication> {
+    apply_rules_where(rules, emails, mailboxes, my_email_regex, |_| true)
+}
+
+/// `apply_rules` restricted to the rules `include` accepts, e.g. those that
+/// run in the mailbox being synced (see [`CompiledRule::runs_in`]).
+pub fn apply_rules_where(
+    rules: &[CompiledRule],
+    emails: &[Email],
+    mailboxes: &[Mailbox],
+    my_email_regex: &Regex,
+    include: impl Fn(&CompiledRule) -> bool,
 ) -> Vec<RuleApplication> {
     let mut applications = Vec::new();
     let mut total_actions = 0usize;
@@ -381,7 +911,7 @@ pub fn apply_rules(
     for email in emails {
         let mut matched_rule_names = Vec::new();
         let mut queued_rule_summaries = Vec::new();
-        for rule in rules {
+        for rule in rules.iter().filter(|rule| include(rule)) {
             if rule.skip_if_to_me && is_email_to_me(email, my_email_regex) {
                 continue;
             }
@@ -406,7 +936,8 @@ pub fn apply_rules(
                     });
                 }
 
-                if !rule.continue_processing {
+                let stop = rule.actions.iter().any(|a| matches!(a, Action::Stop));
+                if stop || !rule.continue_processing {
                     break;
                 }
             }
@@ -443,6 +974,21 @@ pub fn apply_rules(
     applications
 }
 
+/// Whether any rule's condition matches `email` (honoring `skip_if_to_me`),
+/// whether or not it would leave anything to do.
+/// Only the rules `include` accepts are tried.
+pub fn any_rule_matches_where(
+    rules: &[CompiledRule],
+    email: &Email,
+    my_email_regex: &Regex,
+    include: impl Fn(&CompiledRule) -> bool,
+) -> bool {
+    rules.iter().filter(|rule| include(rule)).any(|rule| {
+        !(rule.skip_if_to_me && is_email_to_me(email, my_email_regex))
+            && evaluate_condition(&rule.condition, email)
+    })
+}
+
 fn is_email_to_me(email: &Email, my_email_regex: &Regex) -> bool {
     let mut combined = String::new();
     if let Some(to) = format_addresses(&email.to) {
@@ -516,6 +1062,39 @@ fn filter_noop_actions(actions: &[Action], email: &Email, mailboxes: &[Mailbox])
                 }
             }
             Action::Delete => true,
+            Action::SetKeyword { keyword, value } => {
+                let keep = email.keywords.contains_key(keyword) != *value;
+                if !keep {
+                    log_debug!(
+                        "[Rules] Email {} skip keyword {}={}: already set",
+                        email.id,
+                        keyword,
+                        value
+                    );
+                }
+                keep
+            }
+            // Kept so previews show why later rules did not run.
+            Action::Stop => true,
+            Action::Forward { to } => {
+                let forwarded_copy = resolve_header_value(FORWARDED_HEADER, email)
+                    .is_some_and(|v| !v.trim().is_empty());
+                let already_sent = email.keywords.contains_key(FORWARDED_KEYWORD);
+                if forwarded_copy {
+                    log_info!(
+                        "[Rules] Email {} skip forward_to='{}': a copy forwarded by tmc",
+                        email.id,
+                        to
+                    );
+                } else if already_sent {
+                    log_info!(
+                        "[Rules] Email {} skip forward_to='{}': already forwarded",
+                        email.id,
+                        to
+                    );
+                }
+                !forwarded_copy && !already_sent
+            }
         };
 
         if keep {
@@ -525,6 +1104,34 @@ fn filter_noop_actions(actions: &[Action], email: &Email, mailboxes: &[Mailbox])
     filtered
 }
 
+/// [`CompiledRule::runs_on_demand_in`] for the folder `mailbox_id`.
+pub fn runs_on_demand(mailbox_id: &str, mailboxes: &[Mailbox]) -> impl Fn(&CompiledRule) -> bool {
+    let name = mailboxes
+        .iter()
+        .find(|m| m.id == mailbox_id)
+        .map(|m| m.name.clone())
+        .unwrap_or_default();
+    let path = mailbox_path(mailbox_id, mailboxes);
+    move |rule| rule.runs_on_demand_in(&name, &path)
+}
+
+/// The `Parent/Child` path of mailbox `id`, or just its name at the top
+/// level; empty if unknown.
+pub fn mailbox_path(id: &str, mailboxes: &[Mailbox]) -> String {
+    let mut parts = Vec::new();
+    let mut current = mailboxes.iter().find(|m| m.id == id);
+    // Bounded by the mailbox count in case the server reports a cycle.
+    while let Some(mbox) = current.filter(|_| parts.len() < mailboxes.len()) {
+        parts.push(mbox.name.as_str());
+        current = mbox
+            .parent_id
+            .as_ref()
+            .and_then(|pid| mailboxes.iter().find(|m| &m.id == pid));
+    }
+    parts.reverse();
+    parts.join("/")
+}
+
 /// Resolve a mailbox name/path to a JMAP mailbox ID.
 /// Supports simple names ("Archive") and paths ("INBOX/Alerts").
 pub fn resolve_mailbox_id(name: &str, mailboxes: &[Mailbox]) -> Option<String> {
@@ -617,6 +1224,9 @@ pub fn execute_rule_actions(
                 Action::MarkUnread => client.mark_email_unread(&app.email_id),
                 Action::Flag => client.set_email_flagged(&app.email_id, true),
                 Action::Unflag => client.set_email_flagged(&app.email_id, false),
+                Action::SetKeyword { keyword, value } => {
+                    client.set_email_keyword(&app.email_id, keyword, *value)
+                }
                 Action::Move { target } => {
                     if let Some(target_id) = resolve_mailbox_id(target, mailboxes) {
                         client.move_email(&app.email_id, &target_id)
@@ -629,6 +1239,15 @@ pub fn execute_rule_actions(
                         continue;
                     }
                 }
+                Action::Stop => continue,
+                Action::Forward { to } => {
+                    log_warn!(
+                        "[Rules] forward_to='{}' is only applied through the backend queue (rule '{}')",
+                        to,
+                        app.rule_name
+                    );
+                    continue;
+                }
                 Action::Delete => {
                     // Move to Trash
                     if let Some(trash_id) = mailboxes
@@ -682,6 +1301,9 @@ fn format_action_name(action: &Action) -> String {
         Action::Unflag => "unflag".to_string(),
         Action::Move { target } => format!("move_to={}", target),
         Action::Delete => "delete".to_string(),
+        Action::SetKeyword { keyword, value } => format!("keyword {}={}", keyword, value),
+        Action::Forward { to } => format!("forward_to={}", to),
+        Action::Stop => "stop".to_string(),
     }
 }
 
@@ -690,6 +1312,13 @@ mod tests {
     use super::*;
     use std::collections::HashMap;
 
+    /// Compile one rule outside a file, labelled like `load_rules` does.
+    fn compile_rule(def: toml::Spanned<RuleDef>) -> Result<CompiledRule, String> {
+        let def = def.into_inner();
+        let name = def.name.clone();
+        build_rule(def).map_err(|e| format!("Rule '{}': {}", name, e))
+    }
+
     fn make_email(id: &str) -> Email {
         Email {
             id: id.to_string(),
@@ -733,7 +1362,7 @@ mark_read = true
 "#;
         let config: RulesConfig = toml::from_str(toml_str).unwrap();
         assert_eq!(config.rule.len(), 1);
-        assert_eq!(config.rule[0].name, "mark newsletters read");
+        assert_eq!(config.rule[0].get_ref().name, "mark newsletters read");
     }
 
     #[test]
@@ -821,10 +1450,10 @@ mark_read = true
 "#;
         let config: RulesConfig = toml::from_str(toml_str).unwrap();
         assert_eq!(config.rule.len(), 2);
-        assert_eq!(config.rule[0].continue_processing, Some(true));
-        assert_eq!(config.rule[0].skip_if_to_me, None);
-        assert_eq!(config.rule[1].continue_processing, None);
-        assert_eq!(config.rule[1].skip_if_to_me, None);
+        assert_eq!(config.rule[0].get_ref().continue_processing, Some(true));
+        assert_eq!(config.rule[0].get_ref().skip_if_to_me, None);
+        assert_eq!(config.rule[1].get_ref().continue_processing, None);
+        assert_eq!(config.rule[1].get_ref().skip_if_to_me, None);
     }
 
     #[test]
@@ -1003,6 +1632,92 @@ flag = true
         assert_eq!(custom.len(), 2);
     }
 
+    #[test]
+    fn test_body_condition() {
+        let toml_str = r#"
+[[rule]]
+name = "newsletters"
+[rule.match]
+any = [
+    { header = "List-Id", regex = "news" },
+    { body = "(?i)unsubscribe" },
+]
+[rule.actions]
+move_to = "News"
+"#;
+        let config: RulesConfig = toml::from_str(toml_str).unwrap();
+        let rules: Vec<CompiledRule> = config
+            .rule
+            .into_iter()
+            .map(compile_rule)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert!(format_rules_for_display(&rules).contains("body =~ /(?i)unsubscribe/"));
+        let custom = extract_custom_headers(&rules);
+        assert_eq!(custom[0], "header:List-Id:asText");
+        assert_eq!(&custom[1..], ["textBody", "htmlBody", "bodyValues"]);
+
+        let condition = &rules[0].condition;
+        let mut email = make_email("e1");
+        email.preview = Some("Weekly digest".to_string());
+        assert!(!evaluate_condition(condition, &email));
+
+        email.text_body = serde_json::from_value(serde_json::json!([{"partId": "1"}])).unwrap();
+        let body = format!("{}\nClick here to Unsubscribe.", "x".repeat(10));
+        email.body_values.insert(
+            "1".to_string(),
+            serde_json::from_value(serde_json::json!({ "value": body })).unwrap(),
+        );
+        assert!(evaluate_condition(condition, &email));
+
+        // Text past the scan limit is ignored.
+        let body = format!("{}Unsubscribe", "x".repeat(RULES_MAX_BODY_BYTES));
+        email.body_values.insert(
+            "1".to_string(),
+            serde_json::from_value(serde_json::json!({ "value": body })).unwrap(),
+        );
+        assert!(!evaluate_condition(condition, &email));
+    }
+
+    #[test]
+    fn test_list_id_condition() {
+        let toml_str = r#"
+[[rule]]
+name = "rust list"
+[rule.match]
+list_id = "^users\\.rust-lang\\.org$"
+[rule.actions]
+move_to = "Lists/Rust"
+"#;
+        let config: RulesConfig = toml::from_str(toml_str).unwrap();
+        let rules: Vec<CompiledRule> = config
+            .rule
+            .into_iter()
+            .map(compile_rule)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            extract_custom_headers(&rules),
+            vec!["header:List-Id:asText".to_string()]
+        );
+
+        let condition = &rules[0].condition;
+        let mut email = make_email("e1");
+        assert!(!evaluate_condition(condition, &email));
+        for (value, expected) in [
+            (" Rust Users <users.rust-lang.org>", true),
+            ("<users.rust-lang.org>", true),
+            ("users.rust-lang.org", true),
+            ("Other <dev.rust-lang.org>", false),
+        ] {
+            email.extra.insert(
+                "header:List-Id:asText".to_string(),
+                serde_json::json!(value),
+            );
+            assert_eq!(evaluate_condition(condition, &email), expected, "{}", value);
+        }
+    }
+
     #[test]
     fn test_no_custom_headers_for_standard() {
         let toml_str = r#"
@@ -1068,6 +1783,50 @@ mark_read = true
         assert!(filtered.is_empty());
     }
 
+    #[test]
+    fn test_forward_skips_already_forwarded_mail() {
+        let toml_str = r#"
+[[rule]]
+name = "to accountant"
+[rule.match]
+header = "Subject"
+regex = "invoice"
+[rule.actions]
+forward_to = "books@example.net"
+"#;
+        let config: RulesConfig = toml::from_str(toml_str).unwrap();
+        let rules: Vec<CompiledRule> = config
+            .rule
+            .into_iter()
+            .map(compile_rule)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let forward = Action::Forward {
+            to: "books@example.net".to_string(),
+        };
+        assert!(matches!(
+            rules[0].actions.as_slice(),
+            [Action::Forward { to }] if to == "books@example.net"
+        ));
+        assert_eq!(
+            extract_custom_headers(&rules),
+            vec!["header:X-Tmc-Forwarded:asText".to_string()]
+        );
+
+        let mut email = make_email("e1");
+        let actions = vec![forward];
+        assert_eq!(filter_noop_actions(&actions, &email, &[]).len(), 1);
+        email.extra.insert(
+            "header:X-Tmc-Forwarded:asText".to_string(),
+            serde_json::json!(" <abc@example.com>"),
+        );
+        assert!(filter_noop_actions(&actions, &email, &[]).is_empty());
+
+        let mut email = make_email("e2");
+        email.keywords.insert(FORWARDED_KEYWORD.to_string(), true);
+        assert!(filter_noop_actions(&actions, &email, &[]).is_empty());
+    }
+
     #[test]
     fn test_apply_rules_stops_after_first_match() {
         let toml_str = r#"
@@ -1139,26 +1898,270 @@ mark_read = true
     }
 
     #[test]
-    fn test_skip_if_to_me_does_not_skip_when_to_or_cc_does_not_match() {
+    fn test_priority_orders_rules_and_default_continue_cascades() {
         let toml_str = r#"
+[rules]
+default_continue = true
+
 [[rule]]
-name = "match if addressed to me"
-skip_if_to_me = true
+name = "late"
+priority = 10
+[rule.match]
+header = "Subject"
+regex = "Test"
+[rule.actions]
+mark_read = true
+
+[[rule]]
+name = "plain"
 [rule.match]
 header = "From"
 regex = "alice@"
 [rule.actions]
 flag = true
-"#;
-        let config: RulesConfig = toml::from_str(toml_str).unwrap();
-        let rules: Vec<CompiledRule> = config
-            .rule
-            .into_iter()
-            .map(compile_rule)
-            .collect::<Result<_, _>>()
-            .unwrap();
 
-        let email = make_email("e1");
+[[rule]]
+name = "early"
+priority = -5
+continue_processing = false
+[rule.match]
+header = "From"
+regex = "nobody@"
+[rule.actions]
+flag = true
+
+[[rule]]
+name = "also plain"
+[rule.match]
+header = "From"
+regex = "alice@"
+[rule.actions]
+keywords = { "$label1" = true }
+"#;
+        let rules = compile_rules(toml::from_str(toml_str).unwrap(), toml_str).unwrap();
+        let names: Vec<&str> = rules.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["early", "plain", "also plain", "late"]);
+        assert!(!rules[0].continue_processing);
+        assert!(rules[1].continue_processing);
+
+        // default_continue lets every later match apply too.
+        let email = make_email("e1");
+        let my_email_regex = Regex::new(".*").unwrap();
+        let apps = apply_rules(&rules, &[email], &[], &my_email_regex);
+        let applied: Vec<&str> = apps.iter().map(|a| a.rule_name.as_str()).collect();
+        assert_eq!(applied, vec!["plain", "also plain", "late"]);
+
+        let display = format_rules_for_display(&rules);
+        assert!(display.starts_with("Rule 1: early\n  Priority: -5\n"));
+    }
+
+    #[test]
+    fn test_mailbox_scoped_rules() {
+        let toml_str = r#"
+[[rule]]
+name = "inbox default"
+continue_processing = true
+[rule.match]
+header = "From"
+regex = "alice@"
+[rule.actions]
+flag = true
+
+[[rule]]
+name = "shared lists"
+mailbox = "Shared/*"
+[rule.match]
+header = "From"
+regex = "alice@"
+[rule.actions]
+mark_read = true
+
+[[rule]]
+name = "any inbox-ish"
+mailbox_regex = "(?i)^inbox$"
+[rule.match]
+header = "Subject"
+regex = "Test"
+[rule.actions]
+keywords = { "$label1" = true }
+"#;
+        let config: RulesConfig = toml::from_str(toml_str).unwrap();
+        let rules: Vec<CompiledRule> = config
+            .rule
+            .into_iter()
+            .map(compile_rule)
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let mailboxes: Vec<Mailbox> = serde_json::from_value(serde_json::json!([
+            { "id": "m-inbox", "name": "INBOX", "role": "inbox" },
+            { "id": "m-shared", "name": "Shared" },
+            { "id": "m-team", "name": "Team", "parentId": "m-shared" },
+        ]))
+        .unwrap();
+        assert_eq!(mailbox_path("m-team", &mailboxes), "Shared/Team");
+        assert_eq!(mailbox_path("m-inbox", &mailboxes), "INBOX");
+        assert_eq!(mailbox_path("missing", &mailboxes), "");
+
+        let global = Regex::new("^INBOX$").unwrap();
+        let in_folder = |id: &str| {
+            let mailbox = mailboxes.iter().find(|m| m.id == id).unwrap();
+            let path = mailbox_path(id, &mailboxes);
+            let applied: Vec<String> =
+                apply_rules_where(&rules, &[make_email("e1")], &[], &global, |rule| {
+                    rule.runs_in(&mailbox.name, &path, &global)
+                })
+                .into_iter()
+                .map(|a| a.rule_name)
+                .collect();
+            applied
+        };
+        assert_eq!(in_folder("m-inbox"), vec!["inbox default", "any inbox-ish"]);
+        assert_eq!(in_folder("m-team"), vec!["shared lists"]);
+        // "Shared/*" needs a child folder; the parent itself gets nothing.
+        assert!(in_folder("m-shared").is_empty());
+
+        // A manual run skips the global regex but keeps each rule's own pattern.
+        let on_demand = |id: &str| -> Vec<String> {
+            let include = runs_on_demand(id, &mailboxes);
+            apply_rules_where(&rules, &[make_email("e1")], &[], &global, include)
+                .into_iter()
+                .map(|a| a.rule_name)
+                .collect()
+        };
+        assert_eq!(on_demand("m-team"), vec!["inbox default", "shared lists"]);
+        assert_eq!(on_demand("m-shared"), vec!["inbox default"]);
+
+        let both = r#"
+[[rule]]
+name = "both"
+mailbox = "INBOX"
+mailbox_regex = "INBOX"
+[rule.match]
+header = "From"
+regex = "x"
+[rule.actions]
+flag = true
+"#;
+        let config: RulesConfig = toml::from_str(both).unwrap();
+        let err = compile_rule(config.rule.into_iter().next().unwrap()).unwrap_err();
+        assert!(err.contains("not both"), "{}", err);
+
+        let glob = glob_to_regex("Lists/rust-?.*").unwrap();
+        assert!(glob.is_match("Lists/rust-1.x"));
+        assert!(!glob.is_match("Lists/rust-1x"));
+        assert!(!glob.is_match("Old/Lists/rust-1.x"));
+    }
+
+    #[test]
+    fn test_apply_rules_stop_action() {
+        let toml_str = r#"
+[[rule]]
+name = "vip"
+continue_processing = true
+[rule.match]
+header = "From"
+regex = "alice@"
+[rule.actions]
+stop = true
+
+[[rule]]
+name = "second"
+[rule.match]
+header = "Subject"
+regex = "Test"
+[rule.actions]
+mark_read = true
+"#;
+        let config: RulesConfig = toml::from_str(toml_str).unwrap();
+        let rules: Vec<CompiledRule> = config
+            .rule
+            .into_iter()
+            .map(compile_rule)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert!(format_rules_for_display(&rules).contains("Actions: stop\n"));
+
+        let email = make_email("e1");
+        let my_email_regex = Regex::new(".*").unwrap();
+        let apps = apply_rules(&rules, &[email], &[], &my_email_regex);
+        assert_eq!(apps.len(), 1);
+        assert_eq!(apps[0].rule_name, "vip");
+        assert!(matches!(apps[0].actions.as_slice(), [Action::Stop]));
+    }
+
+    #[test]
+    fn test_trace_rules() {
+        let toml_str = r#"
+[[rule]]
+name = "alice receipts"
+skip_if_to_me = true
+[rule.match]
+all = [
+    { header = "From", regex = "alice@" },
+    { not = { header = "Subject", regex = "receipt" } },
+]
+[rule.actions]
+flag = true
+
+[[rule]]
+name = "attachments"
+[rule.match]
+has_attachment = true
+[rule.actions]
+move_to = "Attachments"
+"#;
+        let config: RulesConfig = toml::from_str(toml_str).unwrap();
+        let rules: Vec<CompiledRule> = config
+            .rule
+            .into_iter()
+            .map(compile_rule)
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let email = make_email("e1");
+        let traces = trace_rules(&rules, &email, &Regex::new("bob@").unwrap());
+        assert_eq!(traces.len(), 2);
+        assert!(traces[0].matched && traces[0].skipped_to_me);
+        let steps: Vec<(usize, &str, bool)> = traces[0]
+            .conditions
+            .iter()
+            .map(|c| (c.depth, c.condition.as_str(), c.passed))
+            .collect();
+        assert_eq!(
+            steps,
+            [
+                (0, "all", true),
+                (1, "From =~ /alice@/", true),
+                (1, "not", true),
+                (2, "Subject =~ /receipt/", false),
+            ]
+        );
+        assert!(!traces[1].matched && !traces[1].skipped_to_me);
+        assert_eq!(traces[1].conditions[0].condition, "has attachment");
+    }
+
+    #[test]
+    fn test_skip_if_to_me_does_not_skip_when_to_or_cc_does_not_match() {
+        let toml_str = r#"
+[[rule]]
+name = "match if addressed to me"
+skip_if_to_me = true
+[rule.match]
+header = "From"
+regex = "alice@"
+[rule.actions]
+flag = true
+"#;
+        let config: RulesConfig = toml::from_str(toml_str).unwrap();
+        let rules: Vec<CompiledRule> = config
+            .rule
+            .into_iter()
+            .map(compile_rule)
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let email = make_email("e1");
         let my_email_regex = Regex::new("(?i)me@example\\.com").unwrap();
         let apps = apply_rules(&rules, &[email], &[], &my_email_regex);
         assert_eq!(apps.len(), 1);
@@ -1194,6 +2197,59 @@ flag = true
         assert!(apps.is_empty());
     }
 
+    #[test]
+    fn test_any_rule_matches_even_without_actions_left() {
+        let toml_str = r#"
+[[rule]]
+name = "flag alice"
+skip_if_to_me = true
+[rule.match]
+header = "From"
+regex = "alice@"
+[rule.actions]
+flag = true
+"#;
+        let config: RulesConfig = toml::from_str(toml_str).unwrap();
+        let rules: Vec<CompiledRule> = config
+            .rule
+            .into_iter()
+            .map(compile_rule)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let my_email_regex = Regex::new("(?i)me@example\\.com").unwrap();
+
+        // Already flagged: nothing to apply, but the rule still matched.
+        let mut email = make_email("e1");
+        email.keywords.insert("$flagged".to_string(), true);
+        assert!(apply_rules(&rules, &[email.clone()], &[], &my_email_regex).is_empty());
+        assert!(any_rule_matches_where(
+            &rules,
+            &email,
+            &my_email_regex,
+            |_| true
+        ));
+
+        email.to = Some(vec![EmailAddress {
+            name: None,
+            email: Some("me@example.com".to_string()),
+        }]);
+        assert!(!any_rule_matches_where(
+            &rules,
+            &email,
+            &my_email_regex,
+            |_| true
+        ));
+
+        let mut other = make_email("e2");
+        other.from = None;
+        assert!(!any_rule_matches_where(
+            &rules,
+            &other,
+            &my_email_regex,
+            |_| true
+        ));
+    }
+
     #[test]
     fn test_skip_if_to_me_skips_when_cc_matches() {
         let toml_str = r#"
@@ -1330,6 +2386,175 @@ mark_read = true
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_load_reports_every_invalid_rule_with_its_line() {
+        let toml_str = r#"# filters
+[[rule]]
+name = "bad regex"
+[rule.match]
+header = "From"
+regex = "[invalid"
+[rule.actions]
+mark_read = true
+
+[[rule]]
+name = "fine"
+[rule.match]
+header = "From"
+regex = "alice@"
+[rule.actions]
+flag = true
+
+  [[ rule ]]  # spaced header
+name = "stray slash"
+[rule.match]
+header = "Subject"
+regex = "x"
+[rule.actions]
+move_to = "Archive//2024"
+
+[[rule]]
+name = "does nothing"
+[rule.match]
+header = "Subject"
+regex = "x"
+[rule.actions]
+
+[[rule]]
+name = "triage only"
+[rule.match]
+header = "Subject"
+regex = "x"
+[rule.actions]
+[rule.triage]
+action = "keep"
+"#;
+        let config: RulesConfig = toml::from_str(toml_str).unwrap();
+        let err = compile_rules(config, toml_str).unwrap_err();
+        assert!(err.starts_with("2 invalid rule(s):\n  Rule 'bad regex' (line 2): Invalid regex"));
+        assert!(err.contains("\n  Rule 'stray slash' (line 18): move_to 'Archive//2024'"));
+        for valid in ["'fine'", "'does nothing'", "'triage only'"] {
+            assert!(!err.contains(valid));
+        }
+
+        assert!(check_mailbox_path("INBOX/Alerts").is_ok());
+        assert!(check_mailbox_path("trash").is_ok());
+        assert!(check_mailbox_path(" ").is_err());
+        assert!(check_mailbox_path("/Archive").is_err());
+        assert!(check_mailbox_path("Archive\n").is_err());
+    }
+
+    #[test]
+    fn test_rule_without_actions_loads_with_a_warning() {
+        let toml_str = r#"
+[[rule]]
+name = "does nothing"
+[rule.match]
+header = "Subject"
+regex = "x"
+[rule.actions]
+
+[[rule]]
+name = "triage only"
+[rule.match]
+header = "Subject"
+regex = "x"
+[rule.actions]
+[rule.triage]
+action = "keep"
+"#;
+        let rules = compile_rules(toml::from_str(toml_str).unwrap(), toml_str).unwrap();
+        assert_eq!(rules.len(), 2);
+        assert!(rules[0].warning().is_some());
+        assert!(rules[1].warning().is_none());
+        let shown = format_rules_for_display(&rules);
+        assert!(shown.contains("  Actions: (none)\n  Warning: no actions"));
+        assert_eq!(shown.matches("Warning:").count(), 1);
+    }
+
+    #[test]
+    fn test_regex_flags() {
+        let toml_str = r#"
+[[rule]]
+name = "default"
+[rule.match]
+header = "Subject"
+regex = "test"
+[rule.actions]
+flag = true
+
+[[rule]]
+name = "ignore case"
+[rule.match]
+header = "Subject"
+regex = "test"
+case_sensitive = false
+[rule.actions]
+flag = true
+
+[[rule]]
+name = "word"
+[rule.match]
+any = [
+    { header = "Subject", regex = "Sub", whole_word = true },
+    { body = "SUBJECT", whole_word = true, case_sensitive = false },
+]
+[rule.actions]
+flag = true
+
+[[rule]]
+name = "inline flags"
+[rule.match]
+header = "Subject"
+regex = "(?mi)^test"
+[rule.actions]
+flag = true
+"#;
+        let config: RulesConfig = toml::from_str(toml_str).unwrap();
+        let rules: Vec<CompiledRule> = config
+            .rule
+            .into_iter()
+            .map(compile_rule)
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        // make_email's subject is "Test Subject".
+        let mut email = make_email("e1");
+        assert!(!evaluate_condition(&rules[0].condition, &email));
+        assert!(evaluate_condition(&rules[1].condition, &email));
+        assert!(!evaluate_condition(&rules[2].condition, &email));
+        assert!(evaluate_condition(&rules[3].condition, &email));
+        email.preview = Some("re: subject line".to_string());
+        assert!(evaluate_condition(&rules[2].condition, &email));
+
+        assert!(has_inline_ignore_case("(?i)test"));
+        assert!(has_inline_ignore_case("a(?si:b)"));
+        assert!(!has_inline_ignore_case("(?-i)test"));
+        assert!(!has_inline_ignore_case(r"\(?i\)"));
+        assert!(!has_inline_ignore_case("(?P<name>i)"));
+
+        for (flags, message) in [
+            (
+                "regex = \"(?i)test\"\ncase_sensitive = true",
+                "case_sensitive",
+            ),
+            (
+                "regex = \"(?mi)test\"\ncase_sensitive = true",
+                "case_sensitive",
+            ),
+            ("regex = \"^test$\"\nwhole_word = true", "whole_word"),
+        ] {
+            let toml_str = format!(
+                "[[rule]]\nname = \"conflict\"\n[rule.match]\nheader = \"Subject\"\n{}\n[rule.actions]\nflag = true\n",
+                flags
+            );
+            let config: RulesConfig = toml::from_str(&toml_str).unwrap();
+            let err = compile_rule(config.rule.into_iter().next().unwrap()).unwrap_err();
+            assert!(err.starts_with("Rule 'conflict': "), "{}", err);
+            assert!(err.contains(message), "{}", err);
+        }
+    }
+
     #[test]
     fn test_missing_header_returns_false() {
         let mut email = make_email("e1");
@@ -1341,4 +2566,152 @@ mark_read = true
         };
         assert!(!evaluate_condition(&condition, &email));
     }
+
+    #[test]
+    fn test_age_conditions() {
+        let toml_str = r#"
+[[rule]]
+name = "old inbox mail"
+[rule.match]
+all = [
+    { header = "Subject", regex = "Test" },
+    { older_than_days = 90 },
+]
+[rule.actions]
+move_to = "Archive"
+
+[[rule]]
+name = "recent mail"
+[rule.match]
+not = { newer_than_days = 7 }
+[rule.actions]
+mark_read = true
+"#;
+        let config: RulesConfig = toml::from_str(toml_str).unwrap();
+        let rules: Vec<CompiledRule> = config
+            .rule
+            .into_iter()
+            .map(compile_rule)
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let today = crate::datefmt::parse_date_days("2025-06-01").unwrap();
+        let mut email = make_email("e1");
+        email.received_at = Some("2025-01-01T10:00:00Z".to_string());
+        assert!(evaluate_condition_at(&rules[0].condition, &email, today));
+        assert!(evaluate_condition_at(&rules[1].condition, &email, today));
+
+        email.received_at = Some("2025-05-30T10:00:00Z".to_string());
+        assert!(!evaluate_condition_at(&rules[0].condition, &email, today));
+        assert!(!evaluate_condition_at(&rules[1].condition, &email, today));
+
+        email.received_at = None;
+        assert!(!evaluate_condition_at(&rules[0].condition, &email, today));
+
+        let display = format_rules_for_display(&rules);
+        assert!(display.contains("all(Subject =~ /Test/, age > 90 days)"));
+        assert!(display.contains("not(age <= 7 days)"));
+    }
+
+    #[test]
+    fn test_received_epoch_and_date() {
+        let mut email = make_email("e1");
+        email.received_at = Some("2025-01-15T10:30:00Z".to_string());
+        assert_eq!(email_received_epoch(&email), Some(1_736_937_000));
+        email.received_at = Some("2025-01-15T01:30:00+02:00".to_string());
+        let secs = email_received_epoch(&email).unwrap();
+        assert_eq!(crate::datefmt::utc_date(secs), "2025-01-14");
+        email.received_at = Some("yesterday".to_string());
+        assert_eq!(email_received_epoch(&email), None);
+    }
+
+    #[test]
+    fn test_keyword_actions() {
+        let toml_str = r#"
+[[rule]]
+name = "label receipts"
+[rule.match]
+header = "Subject"
+regex = "Test"
+[rule.actions]
+keywords = { "$label1" = true, "todo" = false }
+"#;
+        let config: RulesConfig = toml::from_str(toml_str).unwrap();
+        let rules: Vec<CompiledRule> = config
+            .rule
+            .into_iter()
+            .map(compile_rule)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            format_actions_for_display(&rules[0].actions),
+            "keyword $label1=true, keyword todo=false"
+        );
+
+        let mut email = make_email("e1");
+        email.keywords.insert("todo".to_string(), true);
+        let filtered = filter_noop_actions(&rules[0].actions, &email, &[]);
+        assert_eq!(filtered.len(), 2);
+
+        email.keywords.insert("$label1".to_string(), true);
+        email.keywords.remove("todo");
+        let filtered = filter_noop_actions(&rules[0].actions, &email, &[]);
+        assert!(filtered.is_empty());
+
+        let bad: RulesConfig = toml::from_str(
+            r#"
+[[rule]]
+name = "bad keyword"
+[rule.match]
+header = "Subject"
+regex = "Test"
+[rule.actions]
+keywords = { "two words" = true }
+"#,
+        )
+        .unwrap();
+        let err = compile_rule(bad.rule.into_iter().next().unwrap()).unwrap_err();
+        assert!(err.contains("two words"), "{}", err);
+    }
+
+    #[test]
+    fn test_has_attachment_condition() {
+        let toml_str = r#"
+[[rule]]
+name = "file attachments"
+[rule.match]
+all = [
+    { header = "From", regex = "alice@" },
+    { has_attachment = true },
+]
+[rule.actions]
+move_to = "Attachments"
+"#;
+        let config: RulesConfig = toml::from_str(toml_str).unwrap();
+        let rules: Vec<CompiledRule> = config
+            .rule
+            .into_iter()
+            .map(compile_rule)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert!(format_rules_for_display(&rules).contains("has attachment"));
+
+        let mut email = make_email("e1");
+        assert!(!evaluate_condition(&rules[0].condition, &email));
+
+        email
+            .extra
+            .insert("hasAttachment".to_string(), serde_json::json!(true));
+        assert!(evaluate_condition(&rules[0].condition, &email));
+
+        email.extra.clear();
+        email.attachments = Some(vec![serde_json::from_value(serde_json::json!({
+            "partId": "2",
+            "blobId": "blob-1",
+            "type": "application/pdf",
+            "name": "report.pdf"
+        }))
+        .unwrap()]);
+        assert!(evaluate_condition(&rules[0].condition, &email));
+    }
 }
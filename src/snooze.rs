@@ -0,0 +1,94 @@
+//! Snooze wake-time parsing.
+//!
+//! Snoozed mail is moved to the configured snooze folder and its wake time is
+//! recorded in the cache; the backend moves it back to the inbox once that
+//! time has passed. This module only turns user input into a wake timestamp.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Parse a relative duration such as `30m`, `4h`, `3d` or `2w` into seconds.
+pub fn parse_duration(input: &str) -> Result<u64, String> {
+    let input = input.trim();
+    let Some(unit) = input.chars().last() else {
+        return Err("empty snooze duration".to_string());
+    };
+    let multiplier = match unit.to_ascii_lowercase() {
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        'w' => 7 * 24 * 60 * 60,
+        _ => {
+            return Err(format!(
+                "invalid snooze duration '{}': expected a number followed by m, h, d or w",
+                input
+            ))
+        }
+    };
+    let amount: u64 = input[..input.len() - unit.len_utf8()]
+        .parse()
+        .map_err(|_| {
+            format!(
+                "invalid snooze duration '{}': expected a number followed by m, h, d or w",
+                input
+            )
+        })?;
+    if amount == 0 {
+        return Err("snooze duration must be greater than 0".to_string());
+    }
+    amount
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("snooze duration '{}' is too large", input))
+}
+
+/// Resolve `until` to a wake time in seconds since the Unix epoch. Accepts a
+/// relative duration (see [`parse_duration`]) or an absolute Unix timestamp.
+pub fn resolve_wake_time(until: &str, now: u64) -> Result<u64, String> {
+    let until = until.trim();
+    if !until.is_empty() && until.bytes().all(|b| b.is_ascii_digit()) {
+        let wake_at: u64 = until
+            .parse()
+            .map_err(|_| format!("invalid snooze timestamp '{}'", until))?;
+        if wake_at <= now {
+            return Err(format!("snooze time {} is in the past", wake_at));
+        }
+        return Ok(wake_at);
+    }
+    parse_duration(until).map(|secs| now.saturating_add(secs))
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration_units() {
+        assert_eq!(parse_duration("30m"), Ok(1800));
+        assert_eq!(parse_duration("4h"), Ok(14_400));
+        assert_eq!(parse_duration("3d"), Ok(259_200));
+        assert_eq!(parse_duration("2W"), Ok(1_209_600));
+    }
+
+    #[test]
+    fn test_parse_duration_rejects_bad_input() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("3").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("0d").is_err());
+        assert!(parse_duration("-1d").is_err());
+        assert!(parse_duration("3y").is_err());
+    }
+
+    #[test]
+    fn test_resolve_wake_time() {
+        assert_eq!(resolve_wake_time("1h", 1000), Ok(4600));
+        assert_eq!(resolve_wake_time("5000", 1000), Ok(5000));
+        assert!(resolve_wake_time("500", 1000).is_err());
+    }
+}
@@ -0,0 +1,188 @@
+//! Inline images in the email view (`[ui].inline_images`): the kitty
+//! graphics protocol, or sixel through `img2sixel`. Images are drawn in a
+//! block of `IMAGE_ROWS` reserved lines; anything that cannot be shown keeps
+//! the `[img: ...]` placeholder.
+
+use base64::Engine;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Lines reserved for each inline image.
+pub const IMAGE_ROWS: usize = 12;
+/// Images larger than this are left as placeholders.
+pub const MAX_IMAGE_BYTES: usize = 2 * 1024 * 1024;
+/// Cell height assumed when the terminal does not report pixel sizes.
+const DEFAULT_CELL_HEIGHT: u16 = 20;
+
+/// Removes every kitty image placement on screen, keeping the image data
+/// so it can be placed again without retransmitting.
+pub const KITTY_CLEAR_PLACEMENTS: &str = "\x1b_Ga=d,d=a,q=2\x1b\\";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImageProtocol {
+    Kitty,
+    Sixel,
+}
+
+/// An image ready to draw.
+#[derive(Clone, Debug)]
+pub enum EncodedImage {
+    /// PNG data, transmitted once under `id` and then only placed.
+    Kitty { id: u32, png_base64: String },
+    /// A complete sixel sequence.
+    Sixel(String),
+}
+
+/// The protocol the terminal speaks, judged from the environment.
+pub fn detect() -> Option<ImageProtocol> {
+    let var = |name: &str| std::env::var(name).unwrap_or_default();
+    protocol_for(
+        &var("TERM"),
+        &var("TERM_PROGRAM"),
+        std::env::var_os("KITTY_WINDOW_ID").is_some(),
+    )
+}
+
+fn protocol_for(term: &str, term_program: &str, kitty_window: bool) -> Option<ImageProtocol> {
+    let term = term.to_ascii_lowercase();
+    let program = term_program.to_ascii_lowercase();
+    if kitty_window
+        || term.contains("kitty")
+        || term.contains("ghostty")
+        || matches!(program.as_str(), "wezterm" | "ghostty")
+    {
+        Some(ImageProtocol::Kitty)
+    } else if term.contains("sixel")
+        || term.starts_with("foot")
+        || term.starts_with("mlterm")
+        || term.starts_with("yaft")
+        || matches!(program.as_str(), "mintty" | "iterm.app")
+    {
+        Some(ImageProtocol::Sixel)
+    } else {
+        None
+    }
+}
+
+/// Prepare `data` for `protocol`. Kitty takes PNG as is; sixel needs
+/// `img2sixel` on `$PATH`. `None` means keep the placeholder.
+pub fn encode(protocol: ImageProtocol, data: &[u8], content_type: &str) -> Option<EncodedImage> {
+    if data.is_empty() || data.len() > MAX_IMAGE_BYTES {
+        return None;
+    }
+    match protocol {
+        ImageProtocol::Kitty => {
+            const PNG_MAGIC: &[u8] = b"\x89PNG\r\n\x1a\n";
+            if !content_type.eq_ignore_ascii_case("image/png") || !data.starts_with(PNG_MAGIC) {
+                return None;
+            }
+            static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+            Some(EncodedImage::Kitty {
+                id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+                png_base64: base64::engine::general_purpose::STANDARD.encode(data),
+            })
+        }
+        ImageProtocol::Sixel => {
+            let cell = crate::tui::screen::cell_pixel_height().unwrap_or(DEFAULT_CELL_HEIGHT);
+            let height = (IMAGE_ROWS as u32 * cell as u32).to_string();
+            let mut child = Command::new("img2sixel")
+                .args(["-h", &height])
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .ok()?;
+            child.stdin.take()?.write_all(data).ok()?;
+            let output = child.wait_with_output().ok()?;
+            if !output.status.success() || output.stdout.is_empty() {
+                return None;
+            }
+            String::from_utf8(output.stdout)
+                .ok()
+                .map(EncodedImage::Sixel)
+        }
+    }
+}
+
+/// Escape sequence that sends a kitty image's data to the terminal, in the
+/// 4096-byte chunks the protocol requires. Empty for sixel.
+pub fn transmit(image: &EncodedImage) -> String {
+    let EncodedImage::Kitty { id, png_base64 } = image else {
+        return String::new();
+    };
+    let chunks: Vec<&[u8]> = png_base64.as_bytes().chunks(4096).collect();
+    let mut out = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        let chunk = std::str::from_utf8(chunk).unwrap_or_default();
+        if i == 0 {
+            out.push_str(&format!(
+                "\x1b_Ga=t,f=100,i={},q=2,m={};{}\x1b\\",
+                id, more, chunk
+            ));
+        } else {
+            out.push_str(&format!("\x1b_Gm={};{}\x1b\\", more, chunk));
+        }
+    }
+    out
+}
+
+/// Escape sequence that draws `image` at the cursor, `IMAGE_ROWS` cells
+/// tall with its aspect ratio kept, leaving the cursor where it was.
+pub fn place(image: &EncodedImage) -> String {
+    match image {
+        EncodedImage::Kitty { id, .. } => {
+            format!("\x1b_Ga=p,i={},r={},C=1,q=2\x1b\\", id, IMAGE_ROWS)
+        }
+        EncodedImage::Sixel(data) => format!("\x1b7{}\x1b8", data),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_protocol_from_environment() {
+        assert_eq!(
+            protocol_for("xterm-kitty", "", false),
+            Some(ImageProtocol::Kitty)
+        );
+        assert_eq!(
+            protocol_for("xterm-256color", "WezTerm", false),
+            Some(ImageProtocol::Kitty)
+        );
+        assert_eq!(
+            protocol_for("xterm-256color", "", true),
+            Some(ImageProtocol::Kitty)
+        );
+        assert_eq!(protocol_for("foot", "", false), Some(ImageProtocol::Sixel));
+        assert_eq!(protocol_for("xterm-256color", "", false), None);
+    }
+
+    #[test]
+    fn kitty_sends_png_in_chunks_and_places_by_id() {
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png.resize(5000, 0);
+        assert!(encode(ImageProtocol::Kitty, &png, "image/jpeg").is_none());
+        assert!(encode(ImageProtocol::Kitty, &png[8..], "image/png").is_none());
+
+        let image = encode(ImageProtocol::Kitty, &png, "image/png").unwrap();
+        let EncodedImage::Kitty { id, .. } = image else {
+            panic!("expected a kitty image");
+        };
+        let sent = transmit(&image);
+        // 5000 bytes -> 6668 base64 characters -> two chunks.
+        assert_eq!(sent.matches("\x1b_G").count(), 2);
+        assert!(sent.starts_with(&format!("\x1b_Ga=t,f=100,i={},q=2,m=1;", id)));
+        assert!(sent.contains("\x1b_Gm=0;"));
+        assert_eq!(
+            place(&image),
+            format!("\x1b_Ga=p,i={},r={},C=1,q=2\x1b\\", id, IMAGE_ROWS)
+        );
+
+        let too_big = vec![0u8; MAX_IMAGE_BYTES + 1];
+        assert!(encode(ImageProtocol::Kitty, &too_big, "image/png").is_none());
+    }
+}
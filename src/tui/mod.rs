@@ -1,51 +1,67 @@
+pub mod graphics;
 pub mod input;
 pub mod screen;
 pub mod views;
 
 use crate::backend::{self, BackendCommand};
 use crate::compose;
-use crate::config::{AccountConfig, RetentionPolicyConfig, SpamConfig, Theme};
+use crate::config::{AccountConfig, ColorMode, ComposeFormat, Config, Theme};
+use crate::drafts::DraftStore;
 use crate::jmap::client::JmapClient;
-use crate::rules::CompiledRule;
+use crate::rules::{self, CompiledRule};
 use input::read_key;
 use regex::Regex;
 use screen::Terminal;
 use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
 use std::time::{Duration, Instant};
+use views::compose_view::ComposeView;
 use views::mailbox_list::MailboxListView;
-use views::{ViewAction, ViewStack};
+use views::{ReloadedSettings, ViewAction, ViewOptions, ViewStack};
+
+static RELOAD_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn handle_sighup(_: libc::c_int) {
+    RELOAD_REQUESTED.store(true, Ordering::Relaxed);
+}
 
 fn sync_mouse_for_view(term: &mut Terminal, stack: &ViewStack) -> io::Result<()> {
     let wants_mouse = stack.current().map(|v| v.wants_mouse()).unwrap_or(true);
     term.set_mouse_enabled(wants_mouse)
 }
 
-#[allow(clippy::too_many_arguments)]
 pub fn run(
     client: Option<JmapClient>,
-    accounts: Vec<AccountConfig>,
-    current_account_idx: usize,
-    initial_account_name: String,
-    page_size: u32,
-    scrolloff: usize,
-    editor: Option<String>,
-    browser: Option<String>,
-    mouse: bool,
-    sync_interval_secs: Option<u64>,
-    archive_folder: String,
-    deleted_folder: String,
-    reply_from: Option<String>,
-    rules_mailbox_regex: String,
-    my_email_regex: String,
-    retention_policies: Vec<RetentionPolicyConfig>,
+    config: Config,
     rules: Vec<CompiledRule>,
     custom_headers: Vec<String>,
-    theme: Theme,
-    spam_config: SpamConfig,
     offline: bool,
+    config_path: PathBuf,
+    rules_path: PathBuf,
 ) -> io::Result<()> {
-    let rules = std::sync::Arc::new(rules);
-    let custom_headers = std::sync::Arc::new(custom_headers);
+    let http = config.ui.http_options();
+    let image_protocol = if config.ui.inline_images {
+        graphics::detect()
+    } else {
+        None
+    };
+    let mut options = ViewOptions::from_config(&config, image_protocol);
+    let Config {
+        mut accounts,
+        ui,
+        mail,
+        spam: spam_config,
+        cache,
+        mut theme,
+        ..
+    } = config;
+    let rules_mailbox_regex = mail.rules_mailbox_regex;
+    let my_email_regex = mail.my_email_regex;
+    let reply_from = mail.reply_from;
+    let mut rules = std::sync::Arc::new(rules);
+    let mut custom_headers = std::sync::Arc::new(custom_headers);
     let rules_mailbox_regex =
         std::sync::Arc::new(Regex::new(&rules_mailbox_regex).map_err(|e| {
             io::Error::new(
@@ -64,50 +80,130 @@ pub fn run(
     })?);
     let (mut cmd_tx, mut resp_rx) = backend::spawn(
         client,
-        initial_account_name,
+        accounts[0].name.clone(),
         rules.clone(),
         custom_headers.clone(),
         rules_mailbox_regex.clone(),
         my_email_regex.clone(),
         spam_config.clone(),
+        cache.max_bytes,
+        ui.preview_chars,
     );
-    let mut term = Terminal::new(mouse, theme)?;
+    backend::remove_stale_open_attachments();
+    let mut term = Terminal::new(ui.mouse, account_theme(&accounts[0], &theme))?;
+    // SIGHUP (`kill -HUP`) re-reads config and rules instead of exiting.
+    unsafe {
+        let mut sa: libc::sigaction = std::mem::zeroed();
+        sa.sa_sigaction = handle_sighup as *const () as libc::sighandler_t;
+        libc::sigemptyset(&mut sa.sa_mask);
+        sa.sa_flags = 0;
+        libc::sigaction(libc::SIGHUP, &sa, std::ptr::null_mut());
+    }
 
     let account_names: Vec<String> = accounts.iter().map(|a| a.name.clone()).collect();
-    let mut current_idx = current_account_idx;
+    let mut current_idx = 0;
 
     let mailbox_view = MailboxListView::new(
         cmd_tx.clone(),
+        options.clone(),
         accounts[current_idx].username.clone(),
         reply_from.clone(),
-        browser.clone(),
-        page_size,
-        scrolloff,
+        compose::IdentityChoice::shared(
+            accounts[current_idx].identities.clone(),
+            reply_from.is_some(),
+        ),
+        compose::SignatureOptions {
+            account_signature: accounts[current_idx].signature.clone(),
+            above_quote: ui.signature_above_quote,
+        },
         account_names.clone(),
         accounts[current_idx].name.clone(),
-        archive_folder.clone(),
-        deleted_folder.clone(),
-        retention_policies.clone(),
-        sync_interval_secs,
+        accounts[current_idx].color,
+        accounts[current_idx].prefer_html.unwrap_or(ui.prefer_html),
     );
     let _ = cmd_tx.send(BackendCommand::FetchMailboxes {
         origin: "startup".to_string(),
     });
 
-    let mut stack = ViewStack::new(Box::new(mailbox_view));
-    let sync_interval = sync_interval_secs.map(Duration::from_secs);
+    // Unread counts for the other accounts, shown atop the mailbox list.
+    let summary =
+        (accounts.len() > 1).then(|| backend::spawn_account_summary(accounts.clone(), http));
+    if let Some(ref summary) = summary {
+        summary.request(&accounts[current_idx].name, false);
+    }
+
+    let mut stack = ViewStack::new(
+        Box::new(mailbox_view),
+        render_width(term.cols, ui.max_render_width),
+    );
+    let mut sync_interval = options.sync_interval_secs.map(Duration::from_secs);
     let mut last_user_activity = Instant::now();
     let mut last_idle_sync = Instant::now();
 
-    let editor_cmd = editor
-        .or_else(|| std::env::var("EDITOR").ok())
-        .unwrap_or_else(|| "vi".to_string());
+    let composer = if ui.internal_editor {
+        Composer::Internal {
+            format: ui.compose_format,
+            flowed: ui.format_flowed,
+            quote_prefix: ui.quote_prefix.clone(),
+            drafts_folder: mail.drafts_folder,
+        }
+    } else {
+        Composer::External(
+            ui.editor
+                .or_else(|| std::env::var("EDITOR").ok())
+                .unwrap_or_else(|| "vi".to_string()),
+        )
+    };
 
     sync_mouse_for_view(&mut term, &stack)?;
     stack.render_current(&mut term)?;
 
     loop {
         if term.check_resize() {
+            stack.set_render_width(render_width(term.cols, ui.max_render_width));
+            sync_mouse_for_view(&mut term, &stack)?;
+            stack.render_current(&mut term)?;
+        }
+
+        if RELOAD_REQUESTED.swap(false, Ordering::Relaxed) {
+            // A real hangup raises SIGHUP too; with the terminal gone, quit.
+            if screen::terminal_hung_up() {
+                break;
+            }
+            let (config, new_rules, message) = reload_from_disk(&config_path, &rules_path);
+            let settings = match config {
+                Some(config) => {
+                    theme = config.theme;
+                    for account in &mut accounts {
+                        if let Some(fresh) = config.accounts.iter().find(|a| a.name == account.name)
+                        {
+                            account.theme = fresh.theme.clone();
+                        }
+                    }
+                    term.set_theme(account_theme(&accounts[current_idx], &theme))?;
+                    let settings = ReloadedSettings {
+                        archive_folder: config.mail.archive_folder,
+                        deleted_folder: config.mail.deleted_folder,
+                        spam_folder: config.mail.spam_folder,
+                        snooze_folder: config.mail.snooze_folder,
+                        sync_interval_secs: config.ui.sync_interval_secs,
+                        sync_all_mailboxes: config.ui.sync_all_mailboxes,
+                    };
+                    options.apply_reload(&settings);
+                    sync_interval = options.sync_interval_secs.map(Duration::from_secs);
+                    Some(settings)
+                }
+                None => None,
+            };
+            if let Some(new_rules) = new_rules {
+                custom_headers = std::sync::Arc::new(rules::extract_custom_headers(&new_rules));
+                rules = std::sync::Arc::new(new_rules);
+                let _ = cmd_tx.send(BackendCommand::ReloadRules {
+                    rules: rules.clone(),
+                    custom_headers: custom_headers.clone(),
+                });
+            }
+            stack.reload(settings.as_ref(), &message);
             sync_mouse_for_view(&mut term, &stack)?;
             stack.render_current(&mut term)?;
         }
@@ -117,10 +213,10 @@ pub fn run(
             if stack.handle_response(&response) {
                 needs_render = true;
             }
-
-            if let Some(view) = stack.current_mut() {
-                if let Some(ViewAction::Compose(draft_text)) = view.take_pending_action() {
-                    spawn_editor(&draft_text, &editor_cmd);
+        }
+        if let Some(ref summary) = summary {
+            while let Ok(response) = summary.responses.try_recv() {
+                if stack.handle_response(&response) {
                     needs_render = true;
                 }
             }
@@ -140,7 +236,33 @@ pub fn run(
                         stack.render_current(&mut term)?;
                     }
                     ViewAction::Compose(draft_text) => {
-                        spawn_editor(&draft_text, &editor_cmd);
+                        start_compose(
+                            &mut stack,
+                            draft_text,
+                            &accounts[current_idx].name,
+                            &composer,
+                            &cmd_tx,
+                        );
+                        sync_mouse_for_view(&mut term, &stack)?;
+                        stack.render_current(&mut term)?;
+                    }
+                    ViewAction::ResumeDraft(id) => {
+                        resume_draft(
+                            &mut stack,
+                            &id,
+                            &accounts[current_idx].name,
+                            &composer,
+                            &cmd_tx,
+                        );
+                        sync_mouse_for_view(&mut term, &stack)?;
+                        stack.render_current(&mut term)?;
+                    }
+                    ViewAction::Pop => {
+                        if stack.pop() {
+                            if let Some(view) = stack.current_mut() {
+                                view.on_reveal();
+                            }
+                        }
                         sync_mouse_for_view(&mut term, &stack)?;
                         stack.render_current(&mut term)?;
                     }
@@ -182,7 +304,24 @@ pub fn run(
                     break;
                 }
                 ViewAction::Compose(draft_text) => {
-                    spawn_editor(&draft_text, &editor_cmd);
+                    start_compose(
+                        &mut stack,
+                        draft_text,
+                        &accounts[current_idx].name,
+                        &composer,
+                        &cmd_tx,
+                    );
+                    sync_mouse_for_view(&mut term, &stack)?;
+                    stack.render_current(&mut term)?;
+                }
+                ViewAction::ResumeDraft(id) => {
+                    resume_draft(
+                        &mut stack,
+                        &id,
+                        &accounts[current_idx].name,
+                        &composer,
+                        &cmd_tx,
+                    );
                     sync_mouse_for_view(&mut term, &stack)?;
                     stack.render_current(&mut term)?;
                 }
@@ -197,7 +336,7 @@ pub fn run(
                         let new_client = if offline {
                             Ok(None)
                         } else {
-                            match crate::connect_account(account) {
+                            match crate::connect_account(account, http) {
                                 Ok(c) => Ok(Some(c)),
                                 Err(e) => Err(e),
                             }
@@ -213,28 +352,42 @@ pub fn run(
                                     rules_mailbox_regex.clone(),
                                     my_email_regex.clone(),
                                     spam_config.clone(),
+                                    cache.max_bytes,
+                                    ui.preview_chars,
                                 );
                                 cmd_tx = new_cmd_tx;
                                 resp_rx = new_resp_rx;
+                                options.authserv_ids = account.trusted_authserv_ids();
 
                                 let mailbox_view = MailboxListView::new(
                                     cmd_tx.clone(),
+                                    options.clone(),
                                     account.username.clone(),
                                     reply_from.clone(),
-                                    browser.clone(),
-                                    page_size,
-                                    scrolloff,
+                                    compose::IdentityChoice::shared(
+                                        account.identities.clone(),
+                                        reply_from.is_some(),
+                                    ),
+                                    compose::SignatureOptions {
+                                        account_signature: account.signature.clone(),
+                                        above_quote: ui.signature_above_quote,
+                                    },
                                     account_names.clone(),
                                     account.name.clone(),
-                                    archive_folder.clone(),
-                                    deleted_folder.clone(),
-                                    retention_policies.clone(),
-                                    sync_interval_secs,
+                                    account.color,
+                                    account.prefer_html.unwrap_or(ui.prefer_html),
                                 );
                                 let _ = cmd_tx.send(BackendCommand::FetchMailboxes {
                                     origin: "switch_account".to_string(),
                                 });
-                                stack = ViewStack::new(Box::new(mailbox_view));
+                                stack = ViewStack::new(
+                                    Box::new(mailbox_view),
+                                    render_width(term.cols, ui.max_render_width),
+                                );
+                                term.set_theme(account_theme(account, &theme))?;
+                                if let Some(ref summary) = summary {
+                                    summary.request(&account.name, false);
+                                }
                                 last_idle_sync = Instant::now();
                             }
                             Err(e) => {
@@ -246,6 +399,12 @@ pub fn run(
                         stack.render_current(&mut term)?;
                     }
                 }
+                ViewAction::RefreshAccounts => {
+                    if let Some(ref summary) = summary {
+                        summary.request(&accounts[current_idx].name, !offline);
+                    }
+                    stack.render_current(&mut term)?;
+                }
             }
         } else if let Some(interval) = sync_interval {
             if last_user_activity.elapsed() >= interval && last_idle_sync.elapsed() >= interval {
@@ -259,11 +418,160 @@ pub fn run(
     }
 
     let _ = cmd_tx.send(BackendCommand::Shutdown);
+    backend::remove_open_attachments();
 
     Ok(())
 }
 
-fn spawn_editor(draft: &compose::ComposeDraft, editor_cmd: &str) {
+/// Re-read the config and rules files after SIGHUP. Each half can fail on
+/// its own and then keeps its current settings; failures are logged in
+/// full and summarized on one line in the returned status message.
+fn reload_from_disk(
+    config_path: &Path,
+    rules_path: &Path,
+) -> (Option<Config>, Option<Vec<CompiledRule>>, String) {
+    let config = Config::load(config_path).map_err(|e| e.to_string());
+    // A rules file deleted since startup means no rules, as at startup.
+    let loaded_rules = if rules_path.exists() {
+        rules::load_rules(rules_path)
+    } else {
+        Ok(Vec::new())
+    };
+
+    let mut failed = Vec::new();
+    if let Err(e) = &config {
+        crate::log_error!("Reload of {} failed: {}", config_path.display(), e);
+        failed.push(format!("config kept ({})", one_line(e)));
+    }
+    if let Err(e) = &loaded_rules {
+        crate::log_error!("Reload of {} failed: {}", rules_path.display(), e);
+        failed.push(format!("rules kept ({})", one_line(e)));
+    }
+    let message = match (&config, &loaded_rules) {
+        (Ok(_), Ok(r)) => format!("Reloaded config and {} rule(s)", r.len()),
+        (Ok(_), Err(_)) => format!("Reloaded config; {}", failed.join("; ")),
+        (Err(_), Ok(r)) => format!("Reloaded {} rule(s); {}", r.len(), failed.join("; ")),
+        (Err(_), Err(_)) => format!("Reload failed: {}", failed.join("; ")),
+    };
+    (config.ok(), loaded_rules.ok(), message)
+}
+
+/// Collapse a multi-line error (TOML snippets, rule lists) for the status bar.
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The account's `[account.NAME.theme]`, or the global theme without one,
+/// with an unset `color_mode` detected from `$COLORTERM`.
+fn account_theme(account: &AccountConfig, global: &Theme) -> Theme {
+    let mut theme = account.theme.clone().unwrap_or_else(|| global.clone());
+    theme
+        .color_mode
+        .get_or_insert_with(|| ColorMode::detect(std::env::var("COLORTERM").ok().as_deref()));
+    theme
+}
+
+/// Where drafts are edited: a detached `$EDITOR`, or the built-in compose
+/// view (`[ui].internal_editor`).
+enum Composer {
+    External(String),
+    Internal {
+        format: ComposeFormat,
+        flowed: bool,
+        quote_prefix: String,
+        drafts_folder: String,
+    },
+}
+
+fn start_compose(
+    stack: &mut ViewStack,
+    draft: compose::ComposeDraft,
+    account_name: &str,
+    composer: &Composer,
+    cmd_tx: &mpsc::Sender<BackendCommand>,
+) {
+    // Save every new draft up front so it survives an editor or terminal
+    // crash; without a saved copy composing still works.
+    let store = DraftStore::for_account(account_name);
+    let saved = match store.create(&draft) {
+        Ok(meta) => Some((store, meta.id)),
+        Err(e) => {
+            crate::log_error!("Failed to save draft: {}", e);
+            None
+        }
+    };
+    open_composer(stack, draft, saved, false, composer, cmd_tx);
+}
+
+fn resume_draft(
+    stack: &mut ViewStack,
+    id: &str,
+    account_name: &str,
+    composer: &Composer,
+    cmd_tx: &mpsc::Sender<BackendCommand>,
+) {
+    let store = DraftStore::for_account(account_name);
+    match store.load(id) {
+        Ok((meta, draft)) => {
+            open_composer(stack, draft, Some((store, meta.id)), true, composer, cmd_tx)
+        }
+        Err(e) => crate::log_error!("Failed to resume draft {}: {}", id, e),
+    }
+}
+
+fn open_composer(
+    stack: &mut ViewStack,
+    draft: compose::ComposeDraft,
+    saved: Option<(DraftStore, String)>,
+    resumed: bool,
+    composer: &Composer,
+    cmd_tx: &mpsc::Sender<BackendCommand>,
+) {
+    match composer {
+        Composer::External(editor_cmd) => spawn_editor(&draft, editor_cmd, saved, resumed),
+        Composer::Internal {
+            format,
+            flowed,
+            quote_prefix,
+            drafts_folder,
+        } => {
+            let mut view = ComposeView::new(
+                cmd_tx.clone(),
+                draft,
+                *format,
+                *flowed,
+                quote_prefix.clone(),
+                drafts_folder.clone(),
+            );
+            if let Some((store, id)) = saved {
+                view = view.with_saved_draft(store, id, resumed);
+            }
+            stack.push(Box::new(view));
+        }
+    }
+}
+
+/// Column width for laying out HTML bodies: the terminal width, capped by
+/// `[ui].max_render_width` (0 = no cap).
+fn render_width(cols: u16, max_render_width: usize) -> usize {
+    let cols = (cols as usize).max(1);
+    if max_render_width == 0 {
+        cols
+    } else {
+        cols.min(max_render_width)
+    }
+}
+
+/// How often the edited temp file is copied back into the saved draft while
+/// an external editor runs.
+const EDITOR_SYNC_INTERVAL: Duration = Duration::from_secs(2);
+
+fn spawn_editor(
+    draft: &compose::ComposeDraft,
+    editor_cmd: &str,
+    saved: Option<(DraftStore, String)>,
+    resumed: bool,
+) {
     // Write draft (and any attachment sidecar files) to temp storage.
     let prepared = match compose::write_compose_draft(draft) {
         Ok(prepared) => prepared,
@@ -287,9 +595,33 @@ fn spawn_editor(draft: &compose::ComposeDraft, editor_cmd: &str) {
 
     match child {
         Ok(mut child) => {
-            // Background thread waits for editor exit then cleans up temp files
+            // Background thread copies each save back into the saved draft,
+            // then cleans up the temp files once the editor exits.
             std::thread::spawn(move || {
-                let _ = child.wait();
+                let modified = |path: &std::path::Path| {
+                    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+                };
+                let mut synced = modified(&draft_path);
+                let mut edited = false;
+                loop {
+                    let exited = !matches!(child.try_wait(), Ok(None));
+                    let current = modified(&draft_path);
+                    if current != synced {
+                        synced = current;
+                        edited = true;
+                        if let Some((store, id)) = &saved {
+                            sync_saved_draft(store, id, &draft_path, attachment_dir.is_some());
+                        }
+                    }
+                    if exited {
+                        break;
+                    }
+                    std::thread::sleep(EDITOR_SYNC_INTERVAL);
+                }
+                // An untouched new draft is not worth keeping.
+                if let (Some((store, id)), false, false) = (&saved, edited, resumed) {
+                    let _ = store.remove(id);
+                }
                 let _ = std::fs::remove_file(&draft_path);
                 if let Some(dir) = attachment_dir {
                     let _ = std::fs::remove_dir_all(&dir);
@@ -305,3 +637,79 @@ fn spawn_editor(draft: &compose::ComposeDraft, editor_cmd: &str) {
         }
     }
 }
+
+fn sync_saved_draft(store: &DraftStore, id: &str, path: &std::path::Path, has_parts: bool) {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            crate::log_warn!("Failed to read draft {:?}: {}", path, e);
+            return;
+        }
+    };
+    // Attachments are saved separately; keep their MML tags out of the text.
+    let text = if has_parts {
+        compose::strip_mml_parts(&text)
+    } else {
+        text
+    };
+    if let Err(e) = store.update(id, &text) {
+        crate::log_warn!("Failed to save draft {}: {}", id, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reload_keeps_the_half_that_fails_to_parse() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        let rules_path = dir.path().join("rules.toml");
+        std::fs::write(
+            &config_path,
+            r#"
+[mail]
+archive_folder = "Old"
+
+[account.personal]
+well_known_url = "https://mx.example.com/.well-known/jmap"
+username = "user@example.com"
+password_command = "pass show email/example.com"
+"#,
+        )
+        .unwrap();
+
+        // No rules file: reloading clears the rules, as at startup.
+        let (config, loaded, message) = reload_from_disk(&config_path, &rules_path);
+        assert_eq!(config.unwrap().mail.archive_folder, "Old");
+        assert!(loaded.unwrap().is_empty());
+        assert_eq!(message, "Reloaded config and 0 rule(s)");
+
+        std::fs::write(
+            &rules_path,
+            "[[rule]]\nname = \"bad\"\n[rule.match]\nheader = \"From\"\nregex = \"x\"\n[rule.actions]\nmove_to = \"/\"\n",
+        )
+        .unwrap();
+        let (config, loaded, message) = reload_from_disk(&config_path, &rules_path);
+        assert!(config.is_some());
+        assert!(loaded.is_none());
+        assert!(
+            message.starts_with(
+                "Reloaded config; rules kept (1 invalid rule(s): Rule 'bad' (line 1): move_to"
+            ),
+            "{}",
+            message
+        );
+
+        std::fs::write(&config_path, "[mail\n").unwrap();
+        let (config, _, message) = reload_from_disk(&config_path, &rules_path);
+        assert!(config.is_none());
+        assert!(
+            message.starts_with("Reload failed: config kept ("),
+            "{}",
+            message
+        );
+        assert!(!message.contains('\n'));
+    }
+}
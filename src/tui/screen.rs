@@ -1,4 +1,5 @@
-use crate::config::Theme;
+use crate::config::{ColorMode, Theme};
+use std::borrow::Cow;
 use std::io::{self, BufWriter, Stdout, Write};
 use std::os::unix::io::AsRawFd;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -9,6 +10,13 @@ extern "C" fn handle_sigwinch(_: libc::c_int) {
     RESIZE_REQUESTED.store(true, Ordering::Relaxed);
 }
 
+/// Whether the controlling terminal is gone, telling a real hangup apart
+/// from a `kill -HUP` reload request.
+pub fn terminal_hung_up() -> bool {
+    let mut termios: libc::termios = unsafe { std::mem::zeroed() };
+    unsafe { libc::tcgetattr(io::stdin().as_raw_fd(), &mut termios) == -1 }
+}
+
 pub struct Terminal {
     original_termios: libc::termios,
     out: BufWriter<Stdout>,
@@ -46,7 +54,7 @@ impl Terminal {
         // Setup SIGWINCH handler
         unsafe {
             let mut sa: libc::sigaction = std::mem::zeroed();
-            sa.sa_sigaction = handle_sigwinch as libc::sighandler_t;
+            sa.sa_sigaction = handle_sigwinch as *const () as libc::sighandler_t;
             libc::sigemptyset(&mut sa.sa_mask);
             sa.sa_flags = 0;
             libc::sigaction(libc::SIGWINCH, &sa, std::ptr::null_mut());
@@ -58,11 +66,19 @@ impl Terminal {
         // Enter alternate screen buffer, hide cursor
         write!(out, "\x1b[?1049h\x1b[?25l")?;
         // Apply base theme colors to the entire screen
-        if let Some((r, g, b)) = theme.bg {
-            write!(out, "\x1b[48;2;{};{};{}m", r, g, b)?;
+        if let Some(rgb) = theme.bg {
+            write!(
+                out,
+                "{}",
+                color_sgr(theme.color_mode.unwrap_or_default(), BG, rgb)
+            )?;
         }
-        if let Some((r, g, b)) = theme.fg {
-            write!(out, "\x1b[38;2;{};{};{}m", r, g, b)?;
+        if let Some(rgb) = theme.fg {
+            write!(
+                out,
+                "{}",
+                color_sgr(theme.color_mode.unwrap_or_default(), FG, rgb)
+            )?;
         }
         if mouse {
             // Enable X10 mouse tracking + SGR extended coordinates
@@ -138,11 +154,11 @@ impl Terminal {
         write!(self.out, "\x1b[0m")?;
         self.in_selection = false;
         // Re-apply base theme colors so the theme persists after resets
-        if let Some((r, g, b)) = self.theme.bg {
-            write!(self.out, "\x1b[48;2;{};{};{}m", r, g, b)?;
+        if let Some(rgb) = self.theme.bg {
+            self.write_color(BG, rgb)?;
         }
-        if let Some((r, g, b)) = self.theme.fg {
-            write!(self.out, "\x1b[38;2;{};{};{}m", r, g, b)?;
+        if let Some(rgb) = self.theme.fg {
+            self.write_color(FG, rgb)?;
         }
         Ok(())
     }
@@ -152,11 +168,11 @@ impl Terminal {
     pub fn set_selection(&mut self) -> io::Result<()> {
         self.in_selection = true;
         if self.theme.selection_bg.is_some() || self.theme.selection_fg.is_some() {
-            if let Some((r, g, b)) = self.theme.selection_bg {
-                write!(self.out, "\x1b[48;2;{};{};{}m", r, g, b)?;
+            if let Some(rgb) = self.theme.selection_bg {
+                self.write_color(BG, rgb)?;
             }
-            if let Some((r, g, b)) = self.theme.selection_fg {
-                write!(self.out, "\x1b[38;2;{};{};{}m", r, g, b)?;
+            if let Some(rgb) = self.theme.selection_fg {
+                self.write_color(FG, rgb)?;
             }
             Ok(())
         } else {
@@ -168,11 +184,11 @@ impl Terminal {
     /// Falls back to reverse video if no theme colors are set.
     pub fn set_status(&mut self) -> io::Result<()> {
         if self.theme.status_bg.is_some() || self.theme.status_fg.is_some() {
-            if let Some((r, g, b)) = self.theme.status_bg {
-                write!(self.out, "\x1b[48;2;{};{};{}m", r, g, b)?;
+            if let Some(rgb) = self.theme.status_bg {
+                self.write_color(BG, rgb)?;
             }
-            if let Some((r, g, b)) = self.theme.status_fg {
-                write!(self.out, "\x1b[38;2;{};{};{}m", r, g, b)?;
+            if let Some(rgb) = self.theme.status_fg {
+                self.write_color(FG, rgb)?;
             }
             Ok(())
         } else {
@@ -183,12 +199,27 @@ impl Terminal {
     /// Apply header colors (bold + header_fg if set).
     pub fn set_header(&mut self) -> io::Result<()> {
         write!(self.out, "\x1b[1m")?;
-        if let Some((r, g, b)) = self.theme.header_fg {
-            write!(self.out, "\x1b[38;2;{};{};{}m", r, g, b)?;
+        if let Some(rgb) = self.theme.header_fg {
+            self.write_color(FG, rgb)?;
         }
         Ok(())
     }
 
+    /// Write `label` in `color` inside a header line, then restore the header
+    /// style. Returns the columns used.
+    pub fn write_header_label(
+        &mut self,
+        label: &str,
+        color: (u8, u8, u8),
+        max_width: u16,
+    ) -> io::Result<u16> {
+        self.set_fg(color)?;
+        self.write_truncated(label, max_width)?;
+        self.reset_attr()?;
+        self.set_header()?;
+        Ok((label.chars().count() as u16).min(max_width))
+    }
+
     /// Apply bold text colors (for unread items).
     /// Uses bold_fg if set, otherwise plain bold.
     /// When inside a selection, only adds bold without changing fg,
@@ -196,13 +227,48 @@ impl Terminal {
     pub fn set_bold_text(&mut self) -> io::Result<()> {
         write!(self.out, "\x1b[1m")?;
         if !self.in_selection {
-            if let Some((r, g, b)) = self.theme.bold_fg {
-                write!(self.out, "\x1b[38;2;{};{};{}m", r, g, b)?;
+            if let Some(rgb) = self.theme.bold_fg {
+                self.write_color(FG, rgb)?;
             }
         }
         Ok(())
     }
 
+    /// Switch to another theme (e.g. the new account's), re-applying the
+    /// base colors; the next full render repaints with it.
+    pub fn set_theme(&mut self, theme: Theme) -> io::Result<()> {
+        self.theme = theme;
+        self.reset_attr()
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    /// Set the foreground color, unless inside a selection (where
+    /// selection_fg keeps priority for contrast).
+    pub fn set_fg(&mut self, rgb: (u8, u8, u8)) -> io::Result<()> {
+        if !self.in_selection {
+            self.write_color(FG, rgb)?;
+        }
+        Ok(())
+    }
+
+    /// Like [`Terminal::write_truncated`] for text carrying its own color
+    /// escapes (rendered HTML), downsampled to the theme's color mode.
+    pub fn write_colored(&mut self, s: &str, max_width: u16) -> io::Result<()> {
+        let s = downsample(self.theme.color_mode.unwrap_or_default(), s);
+        self.write_truncated(&s, max_width)
+    }
+
+    fn write_color(&mut self, layer: u8, rgb: (u8, u8, u8)) -> io::Result<()> {
+        write!(
+            self.out,
+            "{}",
+            color_sgr(self.theme.color_mode.unwrap_or_default(), layer, rgb)
+        )
+    }
+
     pub fn flush(&mut self) -> io::Result<()> {
         self.out.flush()
     }
@@ -241,21 +307,160 @@ impl Drop for Terminal {
     }
 }
 
-fn get_window_size() -> io::Result<(u16, u16)> {
-    #[repr(C)]
-    struct WinSize {
-        ws_row: u16,
-        ws_col: u16,
-        ws_xpixel: u16,
-        ws_ypixel: u16,
+const FG: u8 = 38;
+const BG: u8 = 48;
+
+/// xterm's default 16-color palette.
+const ANSI16: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// SGR escape selecting `rgb` as the foreground (`layer` 38) or background
+/// (48) color, approximated for 256- and 16-color terminals.
+fn color_sgr(mode: ColorMode, layer: u8, (r, g, b): (u8, u8, u8)) -> String {
+    match mode {
+        ColorMode::TrueColor => format!("\x1b[{};2;{};{};{}m", layer, r, g, b),
+        ColorMode::Ansi256 => format!("\x1b[{};5;{}m", layer, ansi256_index((r, g, b))),
+        ColorMode::Ansi16 => {
+            let dist = |&(pr, pg, pb): &(u8, u8, u8)| {
+                let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+                d(r, pr) + d(g, pg) + d(b, pb)
+            };
+            let idx = (0..16).min_by_key(|&i| dist(&ANSI16[i])).unwrap_or(7) as u8;
+            // 30-37/40-47 for the normal colors, 90-97/100-107 for bright.
+            let base = if layer == FG { 30 } else { 40 };
+            let code = if idx < 8 {
+                base + idx
+            } else {
+                base + 60 + idx - 8
+            };
+            format!("\x1b[{}m", code)
+        }
+    }
+}
+
+/// Nearest entry of the xterm 256-color palette: the 24-step gray ramp for
+/// grays, otherwise the 6x6x6 color cube.
+fn ansi256_index((r, g, b): (u8, u8, u8)) -> u8 {
+    if r == g && g == b {
+        return match r {
+            0..=7 => 16,
+            239..=255 => 231,
+            v => 232 + (v - 8) / 10,
+        };
+    }
+    // Cube levels are 0, 95, 135, 175, 215, 255.
+    let level = |v: u8| match v {
+        0..=47 => 0,
+        48..=114 => 1,
+        v => (v - 35) / 40,
+    };
+    16 + 36 * level(r) + 6 * level(g) + level(b)
+}
+
+/// Rewrite the `38;2;R;G;B` / `48;2;R;G;B` escapes in `s` for `mode`.
+fn downsample(mode: ColorMode, s: &str) -> Cow<'_, str> {
+    if mode == ColorMode::TrueColor || !s.contains(";2;") {
+        return Cow::Borrowed(s);
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find("\x1b[") {
+        out.push_str(&rest[..start]);
+        let seq = &rest[start + 2..];
+        let parsed = seq.find('m').and_then(|end| {
+            let params: Vec<&str> = seq[..end].split(';').collect();
+            match params.as_slice() {
+                [layer @ ("38" | "48"), "2", r, g, b] => {
+                    let rgb = (r.parse().ok()?, g.parse().ok()?, b.parse().ok()?);
+                    let layer = if *layer == "38" { FG } else { BG };
+                    Some((color_sgr(mode, layer, rgb), end))
+                }
+                _ => None,
+            }
+        });
+        match parsed {
+            Some((sgr, end)) => {
+                out.push_str(&sgr);
+                rest = &seq[end + 1..];
+            }
+            None => {
+                out.push_str("\x1b[");
+                rest = seq;
+            }
+        }
     }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+#[repr(C)]
+struct WinSize {
+    ws_row: u16,
+    ws_col: u16,
+    ws_xpixel: u16,
+    ws_ypixel: u16,
+}
 
+fn window_size() -> io::Result<WinSize> {
     let mut ws: WinSize = unsafe { std::mem::zeroed() };
     let fd = io::stdout().as_raw_fd();
 
     if unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut ws) } == -1 {
         return Err(io::Error::last_os_error());
     }
+    Ok(ws)
+}
+
+fn get_window_size() -> io::Result<(u16, u16)> {
+    window_size().map(|ws| (ws.ws_row, ws.ws_col))
+}
 
-    Ok((ws.ws_row, ws.ws_col))
+/// Height of a character cell in pixels, when the terminal reports its
+/// pixel size (many do not).
+pub fn cell_pixel_height() -> Option<u16> {
+    let ws = window_size().ok()?;
+    (ws.ws_row > 0 && ws.ws_ypixel > 0).then(|| ws.ws_ypixel / ws.ws_row)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn downsamples_truecolor_escapes() {
+        let html = "\x1b[1m\x1b[38;2;255;0;0mred\x1b[39m \x1b[48;2;128;128;128mgray\x1b[49m";
+        assert_eq!(downsample(ColorMode::TrueColor, html), html);
+        assert_eq!(
+            downsample(ColorMode::Ansi256, html),
+            "\x1b[1m\x1b[38;5;196mred\x1b[39m \x1b[48;5;244mgray\x1b[49m"
+        );
+        assert_eq!(
+            downsample(ColorMode::Ansi16, html),
+            "\x1b[1m\x1b[91mred\x1b[39m \x1b[100mgray\x1b[49m"
+        );
+        assert_eq!(
+            downsample(ColorMode::Ansi16, "a;2;b \x1b[2m"),
+            "a;2;b \x1b[2m"
+        );
+        assert_eq!(
+            color_sgr(ColorMode::Ansi256, FG, (0x26, 0x8b, 0xd2)),
+            "\x1b[38;5;32m"
+        );
+    }
 }
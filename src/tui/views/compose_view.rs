@@ -0,0 +1,597 @@
+//! Built-in draft editor (`[ui].internal_editor`): edits the same draft text
+//! `$EDITOR` would get and sends it through the backend on Ctrl-S, or stores
+//! it in the Drafts mailbox when abandoned.
+
+use crate::backend::{BackendCommand, BackendResponse};
+use crate::compose::{self, ComposeDraft, DraftAttachment, ParsedDraft};
+use crate::config::ComposeFormat;
+use crate::drafts::DraftStore;
+use crate::jmap::types::OutgoingAttachment;
+use crate::tui::input::Key;
+use crate::tui::screen::Terminal;
+use crate::tui::views::{ReloadedSettings, View, ViewAction};
+use std::io;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+const TAB_WIDTH: usize = 4;
+/// Minimum time between autosaves of a draft being edited.
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(2);
+
+pub struct ComposeView {
+    cmd_tx: mpsc::Sender<BackendCommand>,
+    lines: Vec<String>,
+    /// Cursor line, and char (not byte) column within it.
+    row: usize,
+    col: usize,
+    scroll: usize,
+    /// In-memory attachments of the draft, e.g. a forwarded message.
+    attachments: Vec<DraftAttachment>,
+    format: ComposeFormat,
+    flowed: bool,
+    /// `[ui].quote_prefix`, recognized as a quote when encoding flowed.
+    quote_prefix: String,
+    /// `[mail].drafts_folder`, for "save to Drafts".
+    drafts_folder: String,
+    modified: bool,
+    sending: bool,
+    /// Waiting for the backend to store the draft in `drafts_folder`.
+    saving_draft: bool,
+    /// Escape was pressed on a modified draft; a second one discards it.
+    confirm_discard: bool,
+    /// Set once the backend confirms the send or the save to Drafts, so the
+    /// view closes.
+    done: bool,
+    status_message: Option<String>,
+    /// Store and id of the saved copy, kept up to date while editing.
+    saved: Option<(DraftStore, String)>,
+    /// Whether the draft was resumed from the store rather than just created.
+    resumed: bool,
+    /// Edits not yet written to the saved copy.
+    unsaved: bool,
+    last_save: Instant,
+}
+
+impl ComposeView {
+    pub fn new(
+        cmd_tx: mpsc::Sender<BackendCommand>,
+        draft: ComposeDraft,
+        format: ComposeFormat,
+        flowed: bool,
+        quote_prefix: String,
+        drafts_folder: String,
+    ) -> Self {
+        let mut lines: Vec<String> = draft.body.lines().map(str::to_string).collect();
+        if lines.is_empty() {
+            lines.push(String::new());
+        }
+        // Start on the first empty header (usually To:) when there is one.
+        let row = lines
+            .iter()
+            .take_while(|l| !l.is_empty() && !l.starts_with("--"))
+            .position(|l| l.ends_with(": ") || l.ends_with(':'))
+            .unwrap_or(0);
+        let col = lines[row].chars().count();
+        ComposeView {
+            cmd_tx,
+            lines,
+            row,
+            col,
+            scroll: 0,
+            attachments: draft.attachments,
+            format,
+            flowed,
+            quote_prefix,
+            drafts_folder,
+            modified: false,
+            sending: false,
+            saving_draft: false,
+            confirm_discard: false,
+            done: false,
+            status_message: None,
+            saved: None,
+            resumed: false,
+            unsaved: false,
+            last_save: Instant::now(),
+        }
+    }
+
+    /// Autosave edits to the saved draft `id`, and remove it once sent.
+    pub fn with_saved_draft(mut self, store: DraftStore, id: String, resumed: bool) -> Self {
+        self.saved = Some((store, id));
+        self.resumed = resumed;
+        self
+    }
+
+    fn touch(&mut self) {
+        self.modified = true;
+        self.unsaved = true;
+    }
+
+    /// Write pending edits to the saved draft, at most every
+    /// `AUTOSAVE_INTERVAL` unless `force` is set.
+    fn autosave(&mut self, force: bool) {
+        if !self.unsaved || (!force && self.last_save.elapsed() < AUTOSAVE_INTERVAL) {
+            return;
+        }
+        if let Some((store, id)) = &self.saved {
+            if let Err(e) = store.update(id, &self.text()) {
+                self.status_message = Some(format!("Autosave failed: {}", e));
+                return;
+            }
+        }
+        self.unsaved = false;
+        self.last_save = Instant::now();
+    }
+
+    fn remove_saved(&mut self) {
+        if let Some((store, id)) = self.saved.take() {
+            if let Err(e) = store.remove(&id) {
+                crate::log_warn!("[Compose] failed to remove draft {}: {}", id, e);
+            }
+        }
+    }
+
+    pub fn text(&self) -> String {
+        let mut text = self.lines.join("\n");
+        text.push('\n');
+        text
+    }
+
+    fn byte_col(&self) -> usize {
+        let line = &self.lines[self.row];
+        line.char_indices()
+            .nth(self.col)
+            .map(|(i, _)| i)
+            .unwrap_or(line.len())
+    }
+
+    fn line_chars(&self) -> usize {
+        self.lines[self.row].chars().count()
+    }
+
+    fn insert(&mut self, c: char) {
+        let at = self.byte_col();
+        self.lines[self.row].insert(at, c);
+        self.col += 1;
+        self.touch();
+    }
+
+    fn newline(&mut self) {
+        let at = self.byte_col();
+        let rest = self.lines[self.row].split_off(at);
+        self.lines.insert(self.row + 1, rest);
+        self.row += 1;
+        self.col = 0;
+        self.touch();
+    }
+
+    fn backspace(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+            let at = self.byte_col();
+            self.lines[self.row].remove(at);
+            self.touch();
+        } else if self.row > 0 {
+            let line = self.lines.remove(self.row);
+            self.row -= 1;
+            self.col = self.line_chars();
+            self.lines[self.row].push_str(&line);
+            self.touch();
+        }
+    }
+
+    fn delete(&mut self) {
+        if self.col < self.line_chars() {
+            let at = self.byte_col();
+            self.lines[self.row].remove(at);
+            self.touch();
+        } else if self.row + 1 < self.lines.len() {
+            let next = self.lines.remove(self.row + 1);
+            self.lines[self.row].push_str(&next);
+            self.touch();
+        }
+    }
+
+    fn move_vertical(&mut self, down: bool, count: usize) {
+        self.row = if down {
+            (self.row + count).min(self.lines.len() - 1)
+        } else {
+            self.row.saturating_sub(count)
+        };
+        self.col = self.col.min(self.line_chars());
+    }
+
+    fn adjust_scroll(&mut self, text_rows: usize) {
+        let text_rows = text_rows.max(1);
+        if self.row < self.scroll {
+            self.scroll = self.row;
+        } else if self.row >= self.scroll + text_rows {
+            self.scroll = self.row + 1 - text_rows;
+        }
+    }
+
+    /// The edited draft plus every attachment: `Attach:` files and the
+    /// in-memory ones. `parse` is `compose::parse_draft` for sending, or the
+    /// lenient `parse_unfinished_draft` for saving to Drafts.
+    fn outgoing(
+        &self,
+        parse: fn(&str) -> Result<ParsedDraft, String>,
+    ) -> Result<(ParsedDraft, Vec<OutgoingAttachment>), String> {
+        let mut draft = parse(&self.text())?;
+        draft.format.get_or_insert(self.format);
+        draft.flowed = self.flowed;
+        draft.quote_prefix = self.quote_prefix.clone();
+        let mut attachments = compose::load_attachments(&draft.attachments)?;
+        attachments.extend(self.attachments.iter().map(|a| OutgoingAttachment {
+            name: a.filename.clone(),
+            content_type: a.content_type.clone(),
+            data: a.data.clone(),
+        }));
+        Ok((draft, attachments))
+    }
+
+    /// Parse the draft and hand it to the backend, reporting parse and
+    /// attachment errors in the status bar.
+    fn send(&mut self) {
+        self.autosave(true);
+        let result = self
+            .outgoing(compose::parse_draft)
+            .and_then(|(draft, attachments)| {
+                self.cmd_tx
+                    .send(BackendCommand::SendEmail { draft, attachments })
+                    .map_err(|e| e.to_string())
+            });
+        match result {
+            Ok(()) => {
+                self.sending = true;
+                self.status_message = Some("Sending...".to_string());
+            }
+            Err(e) => self.status_message = Some(format!("Cannot send: {}", e)),
+        }
+    }
+
+    /// Store the draft in the account's Drafts mailbox instead of sending it.
+    fn save_to_drafts(&mut self) {
+        self.autosave(true);
+        let result =
+            self.outgoing(compose::parse_unfinished_draft)
+                .and_then(|(draft, attachments)| {
+                    self.cmd_tx
+                        .send(BackendCommand::SaveDraft {
+                            draft,
+                            attachments,
+                            folder: self.drafts_folder.clone(),
+                        })
+                        .map_err(|e| e.to_string())
+                });
+        match result {
+            Ok(()) => {
+                self.saving_draft = true;
+                self.status_message = Some(format!("Saving to {}...", self.drafts_folder));
+            }
+            Err(e) => self.status_message = Some(format!("Cannot save draft: {}", e)),
+        }
+    }
+}
+
+impl View for ComposeView {
+    fn render(&self, term: &mut Terminal) -> io::Result<()> {
+        term.clear()?;
+        let text_rows = (term.rows as usize).saturating_sub(1);
+        let cols = term.cols as usize;
+
+        for (i, line) in self
+            .lines
+            .iter()
+            .enumerate()
+            .skip(self.scroll)
+            .take(text_rows)
+        {
+            term.move_to(1 + (i - self.scroll) as u16, 1)?;
+            if i != self.row {
+                term.write_truncated(line, term.cols)?;
+                continue;
+            }
+            // Scroll the cursor line sideways so the cursor stays on screen,
+            // and draw the cursor cell in reverse video.
+            let chars: Vec<char> = line.chars().collect();
+            let start = (self.col + 1).saturating_sub(cols.max(1));
+            let before: String = chars[start..self.col].iter().collect();
+            let at = chars.get(self.col).copied().unwrap_or(' ');
+            let after: String = chars
+                .iter()
+                .skip(self.col + 1)
+                .take(cols.saturating_sub(self.col - start + 1))
+                .collect();
+            term.write_str(&before)?;
+            term.set_reverse()?;
+            term.write_str(&at.to_string())?;
+            term.reset_attr()?;
+            term.write_str(&after)?;
+        }
+
+        term.move_to(term.rows, 1)?;
+        term.set_status()?;
+        let attachments = match self.attachments.len() {
+            0 => String::new(),
+            1 => format!(" | attached: {}", self.attachments[0].filename),
+            n => format!(" | {} attachments", n),
+        };
+        let base = format!(
+            " Compose{} | line {}/{}{} | C-s:send Esc:discard",
+            if self.modified { " [+]" } else { "" },
+            self.row + 1,
+            self.lines.len(),
+            attachments
+        );
+        let status = match (&self.status_message, self.confirm_discard) {
+            (_, true) => {
+                " Discard this draft? Esc:discard s:save for later d:save to Drafts folder, any other key keeps editing"
+                    .to_string()
+            }
+            (Some(msg), _) => format!(" {} |{}", msg, base),
+            (None, _) => base,
+        };
+        term.write_truncated(&status, term.cols)?;
+        let remaining = cols.saturating_sub(status.len());
+        for _ in 0..remaining {
+            term.write_str(" ")?;
+        }
+        term.reset_attr()?;
+
+        term.flush()
+    }
+
+    fn wants_mouse(&self) -> bool {
+        false
+    }
+
+    fn handle_key(&mut self, key: Key, term_rows: u16) -> ViewAction {
+        if self.sending || self.saving_draft {
+            return ViewAction::Continue;
+        }
+        if self.confirm_discard {
+            self.confirm_discard = false;
+            match key {
+                Key::Escape => {
+                    self.remove_saved();
+                    return ViewAction::Pop;
+                }
+                Key::Char('s') => {
+                    self.autosave(true);
+                    return ViewAction::Pop;
+                }
+                Key::Char('d') => {
+                    self.save_to_drafts();
+                    return ViewAction::Continue;
+                }
+                _ => return ViewAction::Continue,
+            }
+        }
+        self.status_message = None;
+        let text_rows = (term_rows as usize).saturating_sub(1);
+        match key {
+            Key::Escape => {
+                if !self.modified {
+                    // An untouched new draft is not worth keeping.
+                    if !self.resumed {
+                        self.remove_saved();
+                    }
+                    return ViewAction::Pop;
+                }
+                self.confirm_discard = true;
+            }
+            Key::Ctrl('s') => self.send(),
+            Key::Char(c) => self.insert(c),
+            Key::Tab => {
+                for _ in 0..TAB_WIDTH - self.col % TAB_WIDTH {
+                    self.insert(' ');
+                }
+            }
+            Key::Enter => self.newline(),
+            Key::Backspace | Key::Ctrl('h') => self.backspace(),
+            Key::Delete | Key::Ctrl('d') => self.delete(),
+            Key::Left | Key::Ctrl('b') => {
+                if self.col > 0 {
+                    self.col -= 1;
+                } else if self.row > 0 {
+                    self.row -= 1;
+                    self.col = self.line_chars();
+                }
+            }
+            Key::Right | Key::Ctrl('f') => {
+                if self.col < self.line_chars() {
+                    self.col += 1;
+                } else if self.row + 1 < self.lines.len() {
+                    self.row += 1;
+                    self.col = 0;
+                }
+            }
+            Key::Up | Key::Ctrl('p') => self.move_vertical(false, 1),
+            Key::Down | Key::Ctrl('n') => self.move_vertical(true, 1),
+            Key::PageUp => self.move_vertical(false, text_rows.max(1)),
+            Key::PageDown => self.move_vertical(true, text_rows.max(1)),
+            Key::Home | Key::Ctrl('a') => self.col = 0,
+            Key::End | Key::Ctrl('e') => self.col = self.line_chars(),
+            _ => {}
+        }
+        self.adjust_scroll(text_rows);
+        self.autosave(false);
+        ViewAction::Continue
+    }
+
+    fn on_response(&mut self, response: &BackendResponse) -> bool {
+        match response {
+            BackendResponse::EmailSent { result } if self.sending => {
+                self.sending = false;
+                match result {
+                    Ok(_) => {
+                        self.done = true;
+                        self.remove_saved();
+                    }
+                    Err(e) => self.status_message = Some(format!("Send failed: {}", e)),
+                }
+                true
+            }
+            BackendResponse::DraftSaved { result } if self.saving_draft => {
+                self.saving_draft = false;
+                match result {
+                    Ok(_) => {
+                        self.done = true;
+                        self.remove_saved();
+                    }
+                    Err(e) => self.status_message = Some(format!("Save to Drafts failed: {}", e)),
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn take_pending_action(&mut self) -> Option<ViewAction> {
+        if self.done {
+            self.done = false;
+            return Some(ViewAction::Pop);
+        }
+        None
+    }
+
+    fn on_reload(&mut self, _settings: Option<&ReloadedSettings>, message: Option<&str>) {
+        if let Some(message) = message {
+            self.status_message = Some(message.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compose(body: &str) -> (ComposeView, mpsc::Receiver<BackendCommand>) {
+        let (cmd_tx, cmd_rx) = mpsc::channel();
+        let draft = ComposeDraft::text(body.to_string());
+        let view = ComposeView::new(
+            cmd_tx,
+            draft,
+            ComposeFormat::Plain,
+            false,
+            String::new(),
+            "Drafts".to_string(),
+        );
+        (view, cmd_rx)
+    }
+
+    fn type_str(view: &mut ComposeView, text: &str) {
+        for c in text.chars() {
+            let key = if c == '\n' { Key::Enter } else { Key::Char(c) };
+            view.handle_key(key, 24);
+        }
+    }
+
+    #[test]
+    fn edits_and_sends_the_draft() {
+        let (mut view, cmd_rx) =
+            compose("From: me@example.com\nTo: \nSubject: \n--text follows this line--\n");
+        assert_eq!((view.row, view.col), (1, 4), "starts on the empty To:");
+
+        type_str(&mut view, "ann@example.com");
+        view.handle_key(Key::Down, 24);
+        view.handle_key(Key::End, 24);
+        type_str(&mut view, "Hi thre");
+        view.handle_key(Key::Backspace, 24);
+        view.handle_key(Key::Backspace, 24);
+        type_str(&mut view, "ere");
+        view.handle_key(Key::Down, 24);
+        view.handle_key(Key::End, 24);
+        type_str(&mut view, "\nLine one\nLine two");
+        view.handle_key(Key::Home, 24);
+        view.handle_key(Key::Backspace, 24);
+        assert_eq!(view.lines[4], "Line oneLine two");
+        view.handle_key(Key::Enter, 24);
+        assert!(view.modified);
+
+        view.handle_key(Key::Ctrl('s'), 24);
+        assert!(view.sending);
+        match cmd_rx.try_recv() {
+            Ok(BackendCommand::SendEmail { draft, attachments }) => {
+                assert_eq!(draft.to, "ann@example.com");
+                assert_eq!(draft.subject, "Hi there");
+                assert_eq!(draft.body.trim_end(), "Line one\nLine two");
+                assert!(attachments.is_empty());
+            }
+            _ => panic!("expected SendEmail"),
+        }
+
+        view.on_response(&BackendResponse::EmailSent {
+            result: Ok("sent-1".to_string()),
+        });
+        assert!(matches!(view.take_pending_action(), Some(ViewAction::Pop)));
+    }
+
+    #[test]
+    fn saves_an_abandoned_draft_to_the_drafts_folder() {
+        let (mut view, cmd_rx) =
+            compose("From: me@example.com\nTo: \nSubject: \n--text follows this line--\n");
+        type_str(&mut view, "x");
+        view.handle_key(Key::Escape, 24);
+        view.handle_key(Key::Char('d'), 24);
+        match cmd_rx.try_recv() {
+            Ok(BackendCommand::SaveDraft { draft, folder, .. }) => {
+                assert_eq!(draft.to, "x");
+                assert_eq!(folder, "Drafts");
+            }
+            _ => panic!("expected SaveDraft"),
+        }
+        view.on_response(&BackendResponse::DraftSaved {
+            result: Ok("draft-1".to_string()),
+        });
+        assert!(matches!(view.take_pending_action(), Some(ViewAction::Pop)));
+    }
+
+    #[test]
+    fn autosaves_edits_and_removes_the_draft_once_sent() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = DraftStore::new(dir.path().to_path_buf());
+        let text = "From: me@example.com\nTo: \nSubject: \n--text follows this line--\n";
+        let saved = store.create(&ComposeDraft::text(text.to_string())).unwrap();
+        let (view, _cmd_rx) = compose(text);
+        let mut view = view.with_saved_draft(store.clone(), saved.id.clone(), false);
+
+        type_str(&mut view, "ann@example.com");
+        view.handle_key(Key::Ctrl('s'), 24);
+        assert_eq!(store.list()[0].to, "ann@example.com");
+
+        view.on_response(&BackendResponse::EmailSent {
+            result: Err("rejected".to_string()),
+        });
+        assert_eq!(store.list().len(), 1, "kept after a failed send");
+        view.handle_key(Key::Ctrl('s'), 24);
+        view.on_response(&BackendResponse::EmailSent {
+            result: Ok("sent-1".to_string()),
+        });
+        assert!(store.list().is_empty());
+    }
+
+    #[test]
+    fn escape_asks_before_discarding_edits() {
+        let (mut view, _cmd_rx) = compose("To: \n\nbody\n");
+        assert!(matches!(view.handle_key(Key::Escape, 24), ViewAction::Pop));
+
+        let (mut view, _cmd_rx) = compose("To: \n\nbody\n");
+        type_str(&mut view, "x");
+        assert!(matches!(
+            view.handle_key(Key::Escape, 24),
+            ViewAction::Continue
+        ));
+        assert!(matches!(
+            view.handle_key(Key::Char('n'), 24),
+            ViewAction::Continue
+        ));
+        assert!(matches!(
+            view.handle_key(Key::Escape, 24),
+            ViewAction::Continue
+        ));
+        assert!(matches!(view.handle_key(Key::Escape, 24), ViewAction::Pop));
+    }
+}
@@ -0,0 +1,183 @@
+use crate::backend::BackendResponse;
+use crate::drafts::{DraftStore, SavedDraft};
+use crate::tui::input::Key;
+use crate::tui::screen::Terminal;
+use crate::tui::views::{ReloadedSettings, View, ViewAction};
+use std::io;
+
+/// Saved drafts, newest first; Enter resumes one in the configured composer.
+pub struct DraftsView {
+    store: DraftStore,
+    drafts: Vec<SavedDraft>,
+    cursor: usize,
+    scroll: usize,
+    delete_confirm: bool,
+    status_message: Option<String>,
+}
+
+impl DraftsView {
+    pub fn new(store: DraftStore) -> Self {
+        let drafts = store.list();
+        DraftsView {
+            store,
+            drafts,
+            cursor: 0,
+            scroll: 0,
+            delete_confirm: false,
+            status_message: None,
+        }
+    }
+
+    fn reload(&mut self) {
+        self.drafts = self.store.list();
+        self.cursor = self.cursor.min(self.drafts.len().saturating_sub(1));
+    }
+
+    fn delete_selected(&mut self) {
+        let Some(draft) = self.drafts.get(self.cursor) else {
+            return;
+        };
+        self.status_message = Some(match self.store.remove(&draft.id) {
+            Ok(_) => "Draft deleted".to_string(),
+            Err(e) => format!("Delete failed: {}", e),
+        });
+        self.reload();
+    }
+}
+
+impl View for DraftsView {
+    fn render(&self, term: &mut Terminal) -> io::Result<()> {
+        term.clear()?;
+
+        term.move_to(1, 1)?;
+        term.set_header()?;
+        term.write_truncated(&format!("Saved drafts ({})", self.drafts.len()), term.cols)?;
+        term.reset_attr()?;
+
+        let visible_rows = (term.rows as usize).saturating_sub(3);
+        if self.drafts.is_empty() {
+            term.move_to(3, 1)?;
+            term.write_truncated("No saved drafts.", term.cols)?;
+        }
+        for (i, draft) in self
+            .drafts
+            .iter()
+            .enumerate()
+            .skip(self.scroll)
+            .take(visible_rows)
+        {
+            term.move_to(3 + (i - self.scroll) as u16, 1)?;
+            let subject = if draft.subject.is_empty() {
+                "(no subject)"
+            } else {
+                &draft.subject
+            };
+            let to = if draft.to.is_empty() {
+                "(no recipient)"
+            } else {
+                &draft.to
+            };
+            let line = format!(
+                "{} | {} | {}{}",
+                crate::datefmt::format_local(draft.saved_at as i64, "%Y-%m-%d %H:%M"),
+                to,
+                subject,
+                if draft.attachments.is_empty() {
+                    ""
+                } else {
+                    " [att]"
+                }
+            );
+            if i == self.cursor {
+                term.set_selection()?;
+                term.write_truncated(&line, term.cols)?;
+                term.reset_attr()?;
+            } else {
+                term.write_truncated(&line, term.cols)?;
+            }
+        }
+
+        term.move_to(term.rows, 1)?;
+        term.set_status()?;
+        let keys = if self.delete_confirm {
+            " Delete draft? | y:delete n/Esc:cancel".to_string()
+        } else {
+            format!(
+                " Drafts {}/{} | RET:resume d:delete g:refresh q:back",
+                (self.cursor + 1).min(self.drafts.len()),
+                self.drafts.len()
+            )
+        };
+        let status = match &self.status_message {
+            Some(msg) => format!(" {} |{}", msg, keys),
+            None => keys,
+        };
+        term.write_truncated(&status, term.cols)?;
+        let remaining = (term.cols as usize).saturating_sub(status.len());
+        for _ in 0..remaining {
+            term.write_str(" ")?;
+        }
+        term.reset_attr()?;
+
+        term.flush()
+    }
+
+    fn handle_key(&mut self, key: Key, term_rows: u16) -> ViewAction {
+        if self.delete_confirm {
+            self.delete_confirm = false;
+            if matches!(key, Key::Char('y') | Key::Char('Y')) {
+                self.delete_selected();
+            }
+            return ViewAction::Continue;
+        }
+        self.status_message = None;
+
+        let visible_rows = (term_rows as usize).saturating_sub(3).max(1);
+        match key {
+            Key::Char('q') | Key::Escape => return ViewAction::Pop,
+            Key::Char('n') | Key::Char('j') | Key::Down | Key::ScrollDown
+                if self.cursor + 1 < self.drafts.len() =>
+            {
+                self.cursor += 1;
+            }
+            Key::Char('p') | Key::Char('k') | Key::Up | Key::ScrollUp => {
+                self.cursor = self.cursor.saturating_sub(1);
+            }
+            Key::Home => self.cursor = 0,
+            Key::End => self.cursor = self.drafts.len().saturating_sub(1),
+            Key::Enter => {
+                if let Some(draft) = self.drafts.get(self.cursor) {
+                    return ViewAction::ResumeDraft(draft.id.clone());
+                }
+            }
+            Key::Char('d') if !self.drafts.is_empty() => self.delete_confirm = true,
+            Key::Char('g') => self.reload(),
+            _ => {}
+        }
+        if self.cursor < self.scroll {
+            self.scroll = self.cursor;
+        } else if self.cursor >= self.scroll + visible_rows {
+            self.scroll = self.cursor + 1 - visible_rows;
+        }
+        ViewAction::Continue
+    }
+
+    fn wants_mouse(&self) -> bool {
+        false
+    }
+
+    fn on_response(&mut self, _response: &BackendResponse) -> bool {
+        false
+    }
+
+    fn on_reveal(&mut self) -> bool {
+        self.reload();
+        true
+    }
+
+    fn on_reload(&mut self, _settings: Option<&ReloadedSettings>, message: Option<&str>) {
+        if let Some(message) = message {
+            self.status_message = Some(message.to_string());
+        }
+    }
+}
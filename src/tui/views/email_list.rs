@@ -1,18 +1,30 @@
 use crate::backend::{BackendCommand, BackendResponse, EmailMutationAction, RulesDryRunResult};
-use crate::compose;
+use crate::cache;
+use crate::compose::{self, SharedIdentities};
+use crate::config::{SortField, SortOrder};
+use crate::datefmt::{self, DateFormat};
 use crate::jmap::types::{Email, Mailbox};
+use crate::keybindings::Keymap;
 use crate::rules;
+use crate::snooze;
+use crate::tui::graphics::ImageProtocol;
 use crate::tui::input::Key;
 use crate::tui::screen::Terminal;
-use crate::tui::views::email_view::{EmailNavEntry, EmailView};
+use crate::tui::views::email_view::{
+    EmailNavEntry, EmailView, SharedBodyChoice, DEFAULT_RENDER_WIDTH,
+};
 use crate::tui::views::help::HelpView;
 use crate::tui::views::rules_preview::RulesPreviewView;
 use crate::tui::views::thread_view::ThreadView;
-use crate::tui::views::{format_system_time, View, ViewAction};
-use std::collections::{HashMap, HashSet};
+use crate::tui::views::{
+    filter_mailboxes, format_system_time, move_picker_key, move_picker_status, MovePick,
+    ReloadedSettings, View, ViewAction, ViewOptions,
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io;
+use std::path::PathBuf;
 use std::sync::mpsc;
-use std::time::SystemTime;
+use std::time::{Duration, Instant, SystemTime};
 
 enum PendingWriteOp {
     Flag {
@@ -29,6 +41,60 @@ enum PendingWriteOp {
     },
 }
 
+/// The most recent archive/delete/move, kept so `Z` can put it back.
+struct LastMove {
+    /// The moved rows, in ascending index order.
+    emails: Vec<RemovedRow>,
+    toast: String,
+}
+
+/// A row taken out of the list by `remove_indices`.
+struct RemovedRow {
+    /// The op_id of the command sent for it.
+    op_id: u64,
+    from_index: usize,
+    email: Email,
+}
+
+/// The kinds of move `Z` can undo.
+#[derive(Clone, Debug, PartialEq)]
+enum MoveKind {
+    Archive,
+    Delete,
+    /// A move to the named folder (`m` or a quick-move key).
+    MoveTo(String),
+    ReportSpam,
+    ReportNotSpam,
+}
+
+impl MoveKind {
+    /// Name of the action in progress and failure messages.
+    fn label(&self) -> String {
+        match self {
+            MoveKind::Archive => "Archive".to_string(),
+            MoveKind::Delete => "Delete".to_string(),
+            MoveKind::MoveTo(folder) => format!("Move to {}", folder),
+            MoveKind::ReportSpam => "Report spam".to_string(),
+            MoveKind::ReportNotSpam => "Report not-spam".to_string(),
+        }
+    }
+
+    /// What happened, for the undo toast.
+    fn done(&self) -> String {
+        match self {
+            MoveKind::Archive => "Archived".to_string(),
+            MoveKind::Delete => "Deleted".to_string(),
+            MoveKind::MoveTo(folder) => format!("Moved to {}", folder),
+            MoveKind::ReportSpam => "Reported as spam".to_string(),
+            MoveKind::ReportNotSpam => "Reported as not spam".to_string(),
+        }
+    }
+}
+
+/// How far down the listed emails (in percent) the cursor gets before the
+/// next page is prefetched.
+const PREFETCH_AT_PERCENT: usize = 80;
+
 #[derive(Clone)]
 pub struct CachedEmailListState {
     pub emails: Vec<Email>,
@@ -42,6 +108,9 @@ pub struct CachedEmailListState {
 pub struct EmailListView {
     cmd_tx: mpsc::Sender<BackendCommand>,
     reply_from_address: String,
+    identities: SharedIdentities,
+    signature: compose::SignatureOptions,
+    quote: compose::QuoteStyle,
     mailbox_id: String,
     mailbox_name: String,
     page_size: u32,
@@ -53,6 +122,9 @@ pub struct EmailListView {
     last_loaded_count: u32,
     loading: bool,
     loading_more: bool,
+    /// A next-page query sent ahead of the cursor reaching the end is in
+    /// flight; `loading_more` is only set once the user is waiting for it.
+    prefetching: bool,
     error: Option<String>,
     pending_click: bool,
     pending_reply_request: Option<(String, bool)>,
@@ -61,9 +133,23 @@ pub struct EmailListView {
     mailboxes: Vec<Mailbox>,
     move_mode: bool,
     move_cursor: usize,
+    /// Typed move-picker filter; `move_cursor` indexes the matching mailboxes.
+    move_filter: String,
     search_mode: bool,
     search_input: String,
+    /// The account's recent searches, oldest first (Up/Down at the prompt).
+    search_history: Vec<String>,
+    /// Entry shown while browsing `search_history`; None while typing.
+    history_pos: Option<usize>,
+    snooze_mode: bool,
+    snooze_input: String,
     active_search: Option<String>,
+    /// Received-date range (`w`), as `YYYY-MM-DD` days; sent to the server
+    /// as `after`/`before` bounds on every query.
+    date_mode: bool,
+    date_input: String,
+    received_after: Option<String>,
+    received_before: Option<String>,
     status_message: Option<String>,
     next_write_op_id: u64,
     pending_write_ops: HashMap<u64, PendingWriteOp>,
@@ -71,34 +157,95 @@ pub struct EmailListView {
     /// On-demand spam verdicts keyed by email id (from the `S` key), used to tag
     /// rows in the list. Not persisted; populated as the user scores messages.
     spam_verdicts: HashMap<String, String>,
+    /// Email ids marked with Space for bulk actions. When empty, actions
+    /// apply to the cursor item.
+    marked: HashSet<String>,
+    /// Client-side filter (`U`): only unread emails are listed. The cursor
+    /// row stays visible until the cursor moves off it, so reading a message
+    /// doesn't yank it out from under the cursor.
+    show_unread_only: bool,
+    /// Thread-collapsed display (`C`): each thread with several loaded
+    /// messages is listed once at its newest message. Threads in
+    /// `expanded_threads` list their other messages right below it.
+    collapse_threads: bool,
+    expanded_threads: HashSet<String>,
+    /// For each loaded email, the index of its thread's newest loaded
+    /// message. Rebuilt by `reindex_threads` whenever `emails` changes.
+    thread_heads: Vec<usize>,
+    /// Every loaded message of each multi-message thread, keyed by the
+    /// thread's head index, in list order.
+    thread_members: HashMap<usize, Vec<usize>>,
     scroll_offset: usize,
     archive_folder: String,
     deleted_folder: String,
+    spam_folder: String,
+    snooze_folder: String,
+    quick_move: BTreeMap<char, String>,
+    keymap: Keymap,
+    /// Ask before permanently destroying mail (`[ui].confirm_destructive`).
+    confirm_destructive: bool,
+    /// The email `D` destroys once the user answers y.
+    confirm_expire: Option<String>,
+    /// Split layout (`v`): the lower half of the screen previews the cursor
+    /// message. `preview` is None while its body is loading.
+    preview_pane: bool,
+    preview_email_id: Option<String>,
+    preview: Option<Result<Vec<String>, String>>,
+    /// Column width HTML previews are laid out at.
+    render_width: usize,
+    /// Client-side order (`o` cycles the field, `O` flips direction). Loaded
+    /// pages are merged and re-sorted as they arrive.
+    sort: SortOrder,
+    date_format: DateFormat,
+    last_move: Option<LastMove>,
+    /// `Undo` commands sent whose `Undone` reply has not arrived yet.
+    pending_undos: usize,
+    /// Thread whose `K` mute toggle awaits a `ThreadMuted` reply.
+    pending_mute: Option<String>,
     browser: Option<String>,
+    download_dir: Option<PathBuf>,
+    image_protocol: Option<ImageProtocol>,
+    block_remote_content: bool,
+    /// Passed to opened messages for their `Authentication-Results` summary.
+    authserv_ids: Vec<String>,
     last_refreshed: Option<SystemTime>,
+    /// `[ui].sync_all_mailboxes`: idle syncs also refetch every mailbox's
+    /// counts, at most once per this many seconds.
+    mailbox_sync_secs: Option<u64>,
+    /// Last time mailbox counts were fetched or the user refreshed by hand.
+    last_counts_refresh: Option<Instant>,
+    /// Per-message body choice handed to every opened message.
+    body_choice: SharedBodyChoice,
+    /// Account name and `[account.NAME].color`, shown ahead of the header.
+    account_label: Option<(String, (u8, u8, u8))>,
+    /// A saved search over every mailbox: pages come from `SearchAllEmails`
+    /// and `mailbox_id` names no real mailbox.
+    cross_mailbox: bool,
 }
 
 impl EmailListView {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         cmd_tx: mpsc::Sender<BackendCommand>,
+        options: &ViewOptions,
         reply_from_address: String,
+        identities: SharedIdentities,
+        signature: compose::SignatureOptions,
         mailbox_id: String,
         mailbox_name: String,
-        page_size: u32,
-        scrolloff: usize,
         mailboxes: Vec<Mailbox>,
-        archive_folder: String,
-        deleted_folder: String,
-        browser: Option<String>,
+        body_choice: SharedBodyChoice,
     ) -> Self {
         EmailListView {
             cmd_tx,
             reply_from_address,
+            identities,
+            signature,
+            quote: options.quote.clone(),
             mailbox_id,
             mailbox_name,
-            page_size,
-            scrolloff,
+            page_size: options.page_size,
+            scrolloff: options.scrolloff,
             emails: Vec::new(),
             cursor: 0,
             total: None,
@@ -106,6 +253,7 @@ impl EmailListView {
             last_loaded_count: 0,
             loading: true,
             loading_more: false,
+            prefetching: false,
             error: None,
             pending_click: false,
             pending_reply_request: None,
@@ -114,22 +262,94 @@ impl EmailListView {
             mailboxes,
             move_mode: false,
             move_cursor: 0,
+            move_filter: String::new(),
             search_mode: false,
             search_input: String::new(),
+            search_history: Vec::new(),
+            history_pos: None,
+            snooze_mode: false,
+            snooze_input: String::new(),
             active_search: None,
+            date_mode: false,
+            date_input: String::new(),
+            received_after: None,
+            received_before: None,
             status_message: None,
             next_write_op_id: 1,
             pending_write_ops: HashMap::new(),
             thread_counts: HashMap::new(),
             spam_verdicts: HashMap::new(),
+            marked: HashSet::new(),
+            show_unread_only: false,
+            collapse_threads: false,
+            expanded_threads: HashSet::new(),
+            thread_heads: Vec::new(),
+            thread_members: HashMap::new(),
             scroll_offset: 0,
-            archive_folder,
-            deleted_folder,
-            browser,
+            archive_folder: options.archive_folder.clone(),
+            deleted_folder: options.deleted_folder.clone(),
+            spam_folder: options.spam_folder.clone(),
+            snooze_folder: options.snooze_folder.clone(),
+            quick_move: options.quick_move.clone(),
+            keymap: options.keymap.clone(),
+            confirm_destructive: options.confirm_destructive,
+            confirm_expire: None,
+            preview_pane: options.preview_pane,
+            preview_email_id: None,
+            preview: None,
+            render_width: DEFAULT_RENDER_WIDTH,
+            sort: options.sort,
+            date_format: options.date_format.clone(),
+            last_move: None,
+            pending_undos: 0,
+            pending_mute: None,
+            browser: options.browser.clone(),
+            download_dir: options.download_dir.clone(),
+            image_protocol: options.image_protocol,
+            block_remote_content: options.block_remote_content,
+            authserv_ids: options.authserv_ids.clone(),
             last_refreshed: None,
+            mailbox_sync_secs: options.mailbox_sync_secs(),
+            last_counts_refresh: None,
+            body_choice,
+            account_label: None,
+            cross_mailbox: false,
         }
     }
 
+    /// Open with `query` as the active search (a saved search), over every
+    /// mailbox when `cross_mailbox` is set.
+    pub fn with_search(mut self, query: String, cross_mailbox: bool) -> Self {
+        self.active_search = Some(query);
+        self.cross_mailbox = cross_mailbox;
+        self
+    }
+
+    /// Show `name` in `color` at the start of the header.
+    pub fn with_account_label(mut self, name: String, color: (u8, u8, u8)) -> Self {
+        self.account_label = Some((name, color));
+        self
+    }
+
+    /// The active received-date range as typed at the `w` prompt.
+    fn date_range_label(&self) -> Option<String> {
+        let parts: Vec<String> = [
+            ("after", &self.received_after),
+            ("before", &self.received_before),
+        ]
+        .into_iter()
+        .filter_map(|(name, day)| day.as_ref().map(|d| format!("{}:{}", name, d)))
+        .collect();
+        (!parts.is_empty()).then(|| parts.join(" "))
+    }
+
+    /// Whether a search or date range narrows the server query.
+    fn has_query_filter(&self) -> bool {
+        self.active_search.is_some()
+            || self.received_after.is_some()
+            || self.received_before.is_some()
+    }
+
     pub fn apply_cached_state(&mut self, state: &CachedEmailListState) {
         self.emails = state.emails.clone();
         self.total = state.total;
@@ -145,22 +365,63 @@ impl EmailListView {
         if self.cursor >= self.emails.len() && !self.emails.is_empty() {
             self.cursor = self.emails.len() - 1;
         }
+        self.apply_sort();
+        self.sync_preview();
     }
 
-    fn request_refresh(&mut self, origin: &str) {
+    pub fn request_refresh(&mut self, origin: &str) {
+        self.last_move = None;
         self.next_query_position = 0;
         self.last_loaded_count = 0;
         self.loading = true;
         self.loading_more = false;
+        self.prefetching = false;
         self.scroll_offset = 0;
-        let _ = self.cmd_tx.send(BackendCommand::QueryEmails {
-            origin: origin.to_string(),
-            mailbox_id: self.mailbox_id.clone(),
-            page_size: self.page_size,
-            position: 0,
-            search_query: self.active_search.clone(),
-            received_after: None,
-            received_before: None,
+        let _ = self.send_query(origin, 0);
+    }
+
+    /// Ask for the page at `position` with the active search and date range.
+    fn send_query(&self, origin: &str, position: u32) -> Result<(), String> {
+        let received_after = self.received_after.as_deref().map(utc_midnight);
+        let received_before = self.received_before.as_deref().map(utc_midnight);
+        let cmd = if self.cross_mailbox {
+            BackendCommand::SearchAllEmails {
+                page_size: self.page_size,
+                position,
+                search_query: self.active_search.clone(),
+                received_after,
+                received_before,
+            }
+        } else {
+            BackendCommand::QueryEmails {
+                origin: origin.to_string(),
+                mailbox_id: self.mailbox_id.clone(),
+                page_size: self.page_size,
+                position,
+                search_query: self.active_search.clone(),
+                received_after,
+                received_before,
+            }
+        };
+        self.cmd_tx.send(cmd).map_err(|e| e.to_string())
+    }
+
+    /// Refetch all mailboxes so the mailbox list's unread badges stay
+    /// current while this folder is open. Skipped when counts were refreshed
+    /// within the sync interval.
+    fn sync_mailbox_counts(&mut self) {
+        let Some(secs) = self.mailbox_sync_secs else {
+            return;
+        };
+        if self
+            .last_counts_refresh
+            .is_some_and(|t| t.elapsed() < Duration::from_secs(secs))
+        {
+            return;
+        }
+        self.last_counts_refresh = Some(Instant::now());
+        let _ = self.cmd_tx.send(BackendCommand::FetchMailboxes {
+            origin: "email_list.idle_sync".to_string(),
         });
     }
 
@@ -183,15 +444,11 @@ impl EmailListView {
 
         self.loading = true;
         self.loading_more = true;
-        match self.cmd_tx.send(BackendCommand::QueryEmails {
-            origin: "email_list.load_more".to_string(),
-            mailbox_id: self.mailbox_id.clone(),
-            page_size: self.page_size,
-            position: self.next_query_position,
-            search_query: self.active_search.clone(),
-            received_after: None,
-            received_before: None,
-        }) {
+        if self.prefetching {
+            // The next page is already on its way.
+            return true;
+        }
+        match self.send_query("email_list.load_more", self.next_query_position) {
             Ok(()) => true,
             Err(e) => {
                 self.loading = false;
@@ -202,6 +459,111 @@ impl EmailListView {
         }
     }
 
+    /// Fetch the next page in the background once the cursor is
+    /// `PREFETCH_AT_PERCENT` of the way down the listed emails, so it has
+    /// usually arrived by the time the cursor reaches the end.
+    fn maybe_prefetch(&mut self) {
+        if self.prefetching || !self.can_load_more() {
+            return;
+        }
+        let visible = self.visible_indices();
+        let Some(row) = visible.iter().position(|&i| i == self.cursor) else {
+            return;
+        };
+        if (row + 1) * 100 < visible.len() * PREFETCH_AT_PERCENT {
+            return;
+        }
+        if self
+            .send_query("email_list.prefetch", self.next_query_position)
+            .is_ok()
+        {
+            self.prefetching = true;
+        }
+    }
+
+    /// Merge a page of query results (a mailbox query or, for a
+    /// cross-mailbox saved search, `SearchAllEmails`) into the list.
+    fn apply_page(
+        &mut self,
+        emails: &Result<Vec<Email>, String>,
+        total: Option<u32>,
+        position: u32,
+        loaded: u32,
+        thread_counts: &HashMap<String, (usize, usize)>,
+    ) -> bool {
+        if position > 0 && self.loading && !self.loading_more {
+            // A prefetched page overtaken by a refresh of the list.
+            return true;
+        }
+        self.loading = false;
+        self.loading_more = false;
+        self.prefetching = false;
+        self.total = total;
+        self.last_loaded_count = loaded;
+        self.next_query_position = position.saturating_add(loaded);
+        match emails {
+            Ok(emails) => {
+                self.last_refreshed = Some(SystemTime::now());
+                if position == 0 {
+                    // Collect IDs of emails with in-flight move/destroy
+                    // ops so we can filter them out of incoming data.
+                    // These emails were optimistically removed and must
+                    // stay hidden until the backend confirms or rejects.
+                    let inflight_move_ids: HashSet<&str> = self
+                        .pending_write_ops
+                        .values()
+                        .filter_map(|op| match op {
+                            PendingWriteOp::Move { email, .. } => Some(email.id.as_str()),
+                            _ => None,
+                        })
+                        .collect();
+                    if inflight_move_ids.is_empty() {
+                        self.emails = emails.clone();
+                        self.pending_write_ops.clear();
+                    } else {
+                        self.emails = emails
+                            .iter()
+                            .filter(|e| !inflight_move_ids.contains(e.id.as_str()))
+                            .cloned()
+                            .collect();
+                        // Only clear flag/seen ops — move ops are still
+                        // in flight and must be kept for rollback.
+                        self.pending_write_ops
+                            .retain(|_, op| matches!(op, PendingWriteOp::Move { .. }));
+                    }
+                    self.thread_counts = thread_counts.clone();
+                    let loaded_ids: HashSet<&str> =
+                        self.emails.iter().map(|e| e.id.as_str()).collect();
+                    self.marked.retain(|id| loaded_ids.contains(id.as_str()));
+                } else {
+                    self.thread_counts
+                        .extend(thread_counts.iter().map(|(k, v)| (k.clone(), *v)));
+                    let mut existing_ids: HashSet<String> =
+                        self.emails.iter().map(|e| e.id.clone()).collect();
+                    for email in emails {
+                        if existing_ids.insert(email.id.clone()) {
+                            self.emails.push(email.clone());
+                        }
+                    }
+                }
+                self.error = None;
+                if self.cursor >= self.emails.len() && !self.emails.is_empty() {
+                    self.cursor = self.emails.len() - 1;
+                }
+                self.apply_sort();
+                self.sync_preview();
+            }
+            Err(e) => {
+                if position == 0 {
+                    self.error = Some(format!("Failed to fetch emails: {}", e));
+                } else {
+                    self.status_message = Some(format!("Load more failed: {}", e));
+                }
+            }
+        }
+        true
+    }
+
     fn is_unread(email: &Email) -> bool {
         !email.keywords.contains_key("$seen")
     }
@@ -224,12 +586,17 @@ impl EmailListView {
         width: u16,
         thread_counts: Option<(usize, usize)>,
         spam: &str,
+        marked: bool,
+        thread_member: bool,
+        date: &str,
     ) -> String {
+        let mark = if marked { "*" } else { " " };
         let unread = if Self::is_unread(email) { "N" } else { " " };
         let flagged = if Self::is_flagged(email) { "F" } else { " " };
 
         // Fixed 8-char column for thread indicator [read/total]
         let thread_col = match thread_counts {
+            _ if thread_member => "  +-".to_string(),
             Some((unread_count, total)) if total > 1 => {
                 let read_count = total - unread_count;
                 format!("[{}/{}]", read_count, total)
@@ -251,15 +618,9 @@ impl EmailListView {
 
         let subject = email.subject.as_deref().unwrap_or("(no subject)");
 
-        let date = email
-            .received_at
-            .as_deref()
-            .map(|d| if d.len() >= 10 { &d[..10] } else { d })
-            .unwrap_or("");
-
         let w = width as usize;
-        // " NFS" (5) + thread_display (8) + date (10) + " " (1) + from + " " (1) + subject
-        let prefix_len = 5 + 8 + 10 + 1;
+        // "*NFS" (5) + thread_display (8) + date + " " (1) + from + " " (1) + subject
+        let prefix_len = 5 + 8 + date.chars().count() + 1;
         let from_width = 20.min(w.saturating_sub(prefix_len + 1));
         let subj_width = w.saturating_sub(prefix_len + from_width + 1);
 
@@ -267,7 +628,8 @@ impl EmailListView {
         let subj_display = truncate(subject, subj_width);
 
         format!(
-            " {}{}{}{}{} {:from_w$} {}",
+            "{}{}{}{}{}{} {:from_w$} {}",
+            mark,
             unread,
             flagged,
             spam,
@@ -323,16 +685,27 @@ impl EmailListView {
                 self.set_email_seen_state(&email_id, old_seen)
             }
             PendingWriteOp::Move { email, from_index } => {
-                let insert_at = from_index.min(self.emails.len());
-                self.emails.insert(insert_at, *email);
-                self.cursor = insert_at;
-                if let Some(ref mut total) = self.total {
-                    *total = total.saturating_add(1);
+                // An undo may already have put it back.
+                if self.emails.iter().any(|e| e.id == email.id) {
+                    return;
                 }
+                self.reinsert_email(from_index, *email);
             }
         }
     }
 
+    /// Put a removed row back and select it. Other rows may have moved since
+    /// `from_index` was recorded, so the list is re-sorted to place it.
+    fn reinsert_email(&mut self, from_index: usize, email: Email) {
+        let insert_at = from_index.min(self.emails.len());
+        self.emails.insert(insert_at, email);
+        self.cursor = insert_at;
+        self.apply_sort();
+        if let Some(ref mut total) = self.total {
+            *total = total.saturating_add(1);
+        }
+    }
+
     fn open_selected(&mut self) -> Option<ViewAction> {
         let email = self.emails.get(self.cursor)?;
         let thread_total = self
@@ -351,14 +724,23 @@ impl EmailListView {
             let view = EmailView::new_thread(
                 self.cmd_tx.clone(),
                 self.reply_from_address.clone(),
+                self.identities.clone(),
+                self.signature.clone(),
+                self.quote.clone(),
                 thread_id,
                 subject,
                 can_expire_now,
                 self.mailboxes.clone(),
                 self.archive_folder.clone(),
                 self.deleted_folder.clone(),
+                self.spam_folder.clone(),
                 self.browser.clone(),
-            );
+                self.download_dir.clone(),
+                self.image_protocol,
+                self.block_remote_content,
+                self.body_choice.clone(),
+            )
+            .with_authserv_ids(self.authserv_ids.clone());
             Some(ViewAction::Push(Box::new(view)))
         } else {
             self.open_single_email()
@@ -379,7 +761,7 @@ impl EmailListView {
                 .subject
                 .clone()
                 .unwrap_or_else(|| "(no subject)".to_string());
-            let filter_mailbox_id = if cross_folder {
+            let filter_mailbox_id = if cross_folder || self.cross_mailbox {
                 None
             } else {
                 Some(self.mailbox_id.clone())
@@ -387,16 +769,25 @@ impl EmailListView {
             let view = ThreadView::new(
                 self.cmd_tx.clone(),
                 self.reply_from_address.clone(),
+                self.identities.clone(),
+                self.signature.clone(),
+                self.quote.clone(),
                 thread_id,
                 subject,
                 self.scrolloff,
                 self.mailboxes.clone(),
                 self.archive_folder.clone(),
                 self.deleted_folder.clone(),
+                self.spam_folder.clone(),
                 can_expire_now,
                 filter_mailbox_id,
                 self.browser.clone(),
-            );
+                self.download_dir.clone(),
+                self.image_protocol,
+                self.block_remote_content,
+                self.body_choice.clone(),
+            )
+            .with_authserv_ids(self.authserv_ids.clone());
             Some(ViewAction::Push(Box::new(view)))
         } else {
             self.open_single_email()
@@ -418,6 +809,9 @@ impl EmailListView {
         let view = EmailView::new(
             self.cmd_tx.clone(),
             self.reply_from_address.clone(),
+            self.identities.clone(),
+            self.signature.clone(),
+            self.quote.clone(),
             email_id.clone(),
             nav_entries,
             self.cursor,
@@ -425,8 +819,14 @@ impl EmailListView {
             self.mailboxes.clone(),
             self.archive_folder.clone(),
             self.deleted_folder.clone(),
+            self.spam_folder.clone(),
             self.browser.clone(),
-        );
+            self.download_dir.clone(),
+            self.image_protocol,
+            self.block_remote_content,
+            self.body_choice.clone(),
+        )
+        .with_authserv_ids(self.authserv_ids.clone());
         let _ = self.cmd_tx.send(BackendCommand::GetEmail {
             id: email_id.clone(),
         });
@@ -468,25 +868,324 @@ impl EmailListView {
         if max_items == 0 {
             return;
         }
-        let max_offset = self.emails.len().saturating_sub(max_items);
+        let visible = self.visible_indices();
+        let cursor = self.visible_position(&visible);
+        let max_offset = visible.len().saturating_sub(max_items);
         let margin = self.scrolloff.min(max_items.saturating_sub(1));
         let min_cursor = self.scroll_offset.saturating_add(margin);
         let max_cursor = self
             .scroll_offset
             .saturating_add(max_items.saturating_sub(margin + 1));
 
-        if self.cursor < min_cursor {
-            self.scroll_offset = self.cursor.saturating_sub(margin);
-        } else if self.cursor > max_cursor {
-            self.scroll_offset = self
-                .cursor
-                .saturating_add(margin + 1)
-                .saturating_sub(max_items);
+        if cursor < min_cursor {
+            self.scroll_offset = cursor.saturating_sub(margin);
+        } else if cursor > max_cursor {
+            self.scroll_offset = cursor.saturating_add(margin + 1).saturating_sub(max_items);
         }
         self.scroll_offset = self.scroll_offset.min(max_offset);
     }
 
+    /// Rows available to the message list; the preview pane takes the lower
+    /// half of the screen when open.
+    fn list_rows(&self, term_rows: u16) -> usize {
+        let rows = (term_rows as usize).saturating_sub(4);
+        if self.preview_pane {
+            rows / 2
+        } else {
+            rows
+        }
+    }
+
+    /// Scroll offset to render with, keeping the cursor on screen even when
+    /// the list area shrank (resize or preview pane) since the last key.
+    fn render_scroll_offset(&self, visible: &[usize], max_items: usize) -> usize {
+        let pos = self.visible_position(visible);
+        if pos < self.scroll_offset {
+            pos
+        } else if max_items > 0 && pos >= self.scroll_offset + max_items {
+            pos + 1 - max_items
+        } else {
+            self.scroll_offset
+        }
+    }
+
+    /// Request the cursor message's body for the preview pane when the
+    /// cursor moved to a different message.
+    fn sync_preview(&mut self) {
+        if !self.preview_pane {
+            return;
+        }
+        let Some(id) = self.emails.get(self.cursor).map(|e| e.id.clone()) else {
+            self.preview_email_id = None;
+            self.preview = None;
+            return;
+        };
+        if self.preview_email_id.as_deref() == Some(id.as_str()) {
+            return;
+        }
+        self.preview_email_id = Some(id.clone());
+        self.preview = None;
+        let _ = self.cmd_tx.send(BackendCommand::GetEmail { id });
+    }
+
+    /// Draw the preview pane from `start_row` down to just above the status
+    /// bar: a separator, the message's sender and subject, then its body.
+    fn render_preview(&self, term: &mut Terminal, start_row: u16) -> io::Result<()> {
+        term.move_to(start_row, 1)?;
+        term.write_str(&"-".repeat(term.cols as usize))?;
+        let Some(email) = self.emails.get(self.cursor) else {
+            return Ok(());
+        };
+        let from = email
+            .from
+            .as_ref()
+            .and_then(|addrs| addrs.first())
+            .map(|a| a.to_string())
+            .unwrap_or_else(|| "(unknown)".to_string());
+        let subject = email.subject.as_deref().unwrap_or("(no subject)");
+        let mut lines = vec![format!("From: {}  Subject: {}", from, subject)];
+        match &self.preview {
+            None => lines.push("Loading...".to_string()),
+            Some(Err(e)) => lines.push(format!("Failed to load message: {}", e)),
+            Some(Ok(body)) => lines.extend(body.iter().cloned()),
+        }
+        let body_rows = term.rows.saturating_sub(start_row + 1) as usize;
+        for (i, line) in lines.iter().take(body_rows).enumerate() {
+            term.move_to(start_row + 1 + i as u16, 1)?;
+            if i == 0 {
+                term.set_header()?;
+            }
+            term.write_truncated(line, term.cols)?;
+            term.reset_attr()?;
+        }
+        Ok(())
+    }
+
+    fn toggle_preview_pane(&mut self, term_rows: u16) {
+        self.preview_pane = !self.preview_pane;
+        self.preview_email_id = None;
+        self.preview = None;
+        self.adjust_scroll(self.list_rows(term_rows));
+    }
+
+    fn is_visible(&self, index: usize) -> bool {
+        !self.show_unread_only || index == self.cursor || Self::is_unread(&self.emails[index])
+    }
+
+    /// Indices into `emails` of the rows currently listed, in display order.
+    fn visible_indices(&self) -> Vec<usize> {
+        self.display_order()
+            .into_iter()
+            .filter(|&i| self.is_visible(i))
+            .collect()
+    }
+
+    /// Group the loaded emails by thread in one pass, so listing rows and
+    /// moving the cursor don't rescan the list for every row.
+    fn reindex_threads(&mut self) {
+        let mut first: HashMap<&str, usize> = HashMap::new();
+        let mut members: HashMap<usize, Vec<usize>> = HashMap::new();
+        self.thread_heads = (0..self.emails.len()).collect();
+        for (i, email) in self.emails.iter().enumerate() {
+            let Some(thread_id) = email.thread_id.as_deref() else {
+                continue;
+            };
+            let head = *first.entry(thread_id).or_insert(i);
+            self.thread_heads[i] = head;
+            members.entry(head).or_default().push(i);
+        }
+        members.retain(|_, group| group.len() > 1);
+        self.thread_members = members;
+    }
+
+    /// Order in which `emails` are listed. With threads collapsed, a thread
+    /// shows up once at its newest message, followed by its other messages
+    /// when expanded. A group holding the cursor on a hidden message is shown
+    /// expanded so the cursor row is never hidden.
+    fn display_order(&self) -> Vec<usize> {
+        if !self.collapse_threads {
+            return (0..self.emails.len()).collect();
+        }
+        let mut order = Vec::with_capacity(self.emails.len());
+        for i in 0..self.emails.len() {
+            if self.thread_head(i) != i {
+                continue;
+            }
+            order.push(i);
+            let Some(group) = self.thread_members.get(&i) else {
+                continue;
+            };
+            let expanded = self.emails[i]
+                .thread_id
+                .as_deref()
+                .is_some_and(|t| self.expanded_threads.contains(t));
+            if expanded || (self.cursor != i && self.thread_head(self.cursor) == i) {
+                order.extend(group.iter().skip(1).copied());
+            }
+        }
+        order
+    }
+
+    /// Index of the newest loaded message in the thread of `emails[index]`.
+    fn thread_head(&self, index: usize) -> usize {
+        self.thread_heads.get(index).copied().unwrap_or(index)
+    }
+
+    /// True for a non-head message listed under its expanded thread.
+    fn is_thread_member_row(&self, index: usize) -> bool {
+        self.collapse_threads && self.thread_head(index) != index
+    }
+
+    /// True when the cursor row heads a collapsed thread with hidden messages.
+    fn cursor_on_collapsed_thread(&self) -> bool {
+        let Some(thread_id) = self
+            .emails
+            .get(self.cursor)
+            .and_then(|e| e.thread_id.as_deref())
+        else {
+            return false;
+        };
+        self.collapse_threads
+            && !self.expanded_threads.contains(thread_id)
+            && self.thread_members.contains_key(&self.cursor)
+    }
+
+    fn toggle_collapse_threads(&mut self, max_items: usize) {
+        self.collapse_threads = !self.collapse_threads;
+        self.expanded_threads.clear();
+        if self.collapse_threads {
+            self.cursor = self.thread_head(self.cursor);
+        }
+        self.scroll_offset = 0;
+        self.adjust_scroll(max_items);
+        self.status_message = Some(if self.collapse_threads {
+            "Threads collapsed".to_string()
+        } else {
+            "Threads expanded".to_string()
+        });
+    }
+
+    /// Expand or collapse the thread under the cursor. Collapsing moves the
+    /// cursor to the thread's newest message.
+    fn toggle_thread_expanded(&mut self, max_items: usize) {
+        if !self.collapse_threads {
+            return;
+        }
+        let Some(thread_id) = self
+            .emails
+            .get(self.cursor)
+            .and_then(|e| e.thread_id.clone())
+        else {
+            return;
+        };
+        let head = self.thread_head(self.cursor);
+        let expanded = self.expanded_threads.contains(&thread_id) || head != self.cursor;
+        if expanded {
+            self.expanded_threads.remove(&thread_id);
+            self.cursor = head;
+        } else {
+            self.expanded_threads.insert(thread_id);
+        }
+        self.adjust_scroll(max_items);
+    }
+
+    /// Position of the cursor within `visible`.
+    fn visible_position(&self, visible: &[usize]) -> usize {
+        visible.iter().position(|&i| i == self.cursor).unwrap_or(0)
+    }
+
+    /// Move the cursor to the next (or previous) listed email. Returns false
+    /// when already at that end of the list.
+    fn step_cursor(&mut self, forward: bool, max_items: usize) -> bool {
+        let visible = self.visible_indices();
+        let next = match visible.iter().position(|&i| i == self.cursor) {
+            Some(pos) if forward => visible.get(pos + 1).copied(),
+            Some(pos) => pos.checked_sub(1).map(|p| visible[p]),
+            None => visible.first().copied(),
+        };
+        match next {
+            Some(i) => {
+                self.cursor = i;
+                self.adjust_scroll(max_items);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Move the cursor `page` listed rows forward or back, clamped to the ends.
+    fn page_cursor(&mut self, forward: bool, page: usize, max_items: usize) {
+        let visible = self.visible_indices();
+        if visible.is_empty() {
+            return;
+        }
+        let pos = self.visible_position(&visible);
+        let target = if forward {
+            pos.saturating_add(page).min(visible.len() - 1)
+        } else {
+            pos.saturating_sub(page)
+        };
+        self.cursor = visible[target];
+        self.adjust_scroll(max_items);
+    }
+
+    /// Sort the loaded emails by `self.sort`, keeping the cursor on the same
+    /// message. The sort is stable, so ties keep the server's order.
+    fn apply_sort(&mut self) {
+        let cursor_id = self.emails.get(self.cursor).map(|e| e.id.clone());
+        let sort = self.sort;
+        let from_key = |e: &Email| {
+            e.from
+                .as_ref()
+                .and_then(|addrs| addrs.first())
+                .and_then(|a| a.name.as_deref().or(a.email.as_deref()))
+                .unwrap_or("")
+                .to_lowercase()
+        };
+        let subject_key = |e: &Email| e.subject.as_deref().unwrap_or("").to_lowercase();
+        self.emails.sort_by(|a, b| {
+            let ord = match sort.field {
+                SortField::Date => a.received_at.cmp(&b.received_at),
+                SortField::From => from_key(a).cmp(&from_key(b)),
+                SortField::Subject => subject_key(a).cmp(&subject_key(b)),
+            };
+            if sort.descending {
+                ord.reverse()
+            } else {
+                ord
+            }
+        });
+        if let Some(id) = cursor_id {
+            if let Some(index) = self.emails.iter().position(|e| e.id == id) {
+                self.cursor = index;
+            }
+        }
+        self.reindex_threads();
+    }
+
+    fn set_sort(&mut self, sort: SortOrder, max_items: usize) {
+        self.sort = sort;
+        self.apply_sort();
+        self.adjust_scroll(max_items);
+        self.status_message = Some(format!("Sort: {}", sort.label()));
+    }
+
+    fn toggle_unread_only(&mut self, max_items: usize) {
+        self.show_unread_only = !self.show_unread_only;
+        self.scroll_offset = 0;
+        self.adjust_scroll(max_items);
+        self.status_message = Some(if self.show_unread_only {
+            "Showing unread only".to_string()
+        } else {
+            "Showing all messages".to_string()
+        });
+    }
+
     fn is_in_deleted_folder(&self) -> bool {
+        // A cross-mailbox saved search may share the folder's name.
+        if self.cross_mailbox {
+            return false;
+        }
         if self.mailbox_name.eq_ignore_ascii_case(&self.deleted_folder) {
             return true;
         }
@@ -523,76 +1222,459 @@ impl EmailListView {
         }
     }
 
-    fn move_selected_to_folder(&mut self, folder: &str, action_label: &str) {
-        let Some(target_id) = rules::resolve_mailbox_id(folder, &self.mailboxes) else {
+    fn in_spam_folder(&self) -> bool {
+        rules::resolve_mailbox_id(&self.spam_folder, &self.mailboxes)
+            .is_some_and(|id| id == self.mailbox_id)
+    }
+
+    /// `!`: report the selected/marked emails as spam, or as not spam when
+    /// viewing the spam folder. Each is one `ReportSpam` mutation that sets
+    /// `$junk`/`$notjunk` and moves it to the spam folder (or the inbox).
+    fn report_spam(&mut self) {
+        let is_spam = !self.in_spam_folder();
+        let (folder, kind) = if is_spam {
+            (self.spam_folder.clone(), MoveKind::ReportSpam)
+        } else {
+            ("inbox".to_string(), MoveKind::ReportNotSpam)
+        };
+        let label = kind.label();
+        let Some(target_id) = rules::resolve_mailbox_id(&folder, &self.mailboxes) else {
             self.status_message = Some(format!(
                 "{} failed: could not resolve folder '{}'",
-                action_label, folder
+                label, folder
             ));
             return;
         };
-
-        let Some(email) = self.emails.get(self.cursor).cloned() else {
-            return;
-        };
-        let op_id = self.next_op_id();
-        let from_index = self.cursor;
-        self.pending_write_ops.insert(
-            op_id,
-            PendingWriteOp::Move {
-                email: Box::new(email.clone()),
-                from_index,
-            },
-        );
-
-        // Always move only this single email (not the whole thread) so that
-        // archive/delete/move in the email list only affect the current folder.
-        let send_result = self.cmd_tx.send(BackendCommand::MoveEmail {
+        let indices = self.target_indices();
+        let moved = self.remove_indices(indices, &label, |op_id, id| BackendCommand::ReportSpam {
             op_id,
-            id: email.id.clone(),
-            to_mailbox_id: target_id,
+            ids: vec![id],
+            spam: is_spam,
+            to_mailbox_id: target_id.clone(),
         });
+        self.remember_move(moved, &kind);
+    }
 
-        self.emails.remove(from_index);
-        if self.cursor >= self.emails.len() && self.cursor > 0 {
-            self.cursor -= 1;
-        }
-        if let Some(ref mut total) = self.total {
-            *total = total.saturating_sub(1);
+    /// Indices of the emails a bulk-capable action applies to: every marked
+    /// email in list order, or the cursor item when nothing is marked.
+    fn target_indices(&self) -> Vec<usize> {
+        if self.marked.is_empty() {
+            if self.cursor < self.emails.len() {
+                vec![self.cursor]
+            } else {
+                Vec::new()
+            }
+        } else {
+            self.emails
+                .iter()
+                .enumerate()
+                .filter(|(_, e)| self.marked.contains(&e.id))
+                .map(|(i, _)| i)
+                .collect()
         }
-        if let Err(e) = send_result {
-            self.record_send_failure(
-                op_id,
-                PendingWriteOp::Move {
-                    email: Box::new(email),
-                    from_index,
-                },
-                action_label,
-                e.to_string(),
-            );
+    }
+
+    /// Move the cursor to the next (or previous) unread email, stopping at the
+    /// ends of the list. Searching forward past the last loaded email asks for
+    /// another page so a repeat press can continue into it.
+    fn jump_to_unread(&mut self, forward: bool, max_items: usize) {
+        let found = if forward {
+            ((self.cursor + 1)..self.emails.len()).find(|&i| Self::is_unread(&self.emails[i]))
+        } else {
+            (0..self.cursor.min(self.emails.len()))
+                .rev()
+                .find(|&i| Self::is_unread(&self.emails[i]))
+        };
+        match found {
+            Some(i) => {
+                self.cursor = i;
+                self.adjust_scroll(max_items);
+            }
+            None if forward && self.request_load_more() => {
+                self.status_message = Some("No unread below; loading more...".to_string());
+            }
+            None => {
+                let direction = if forward { "below" } else { "above" };
+                self.status_message = Some(format!("No unread messages {}", direction));
+            }
         }
     }
 
-    fn expire_selected_now(&mut self) {
-        let Some(email) = self.emails.get(self.cursor).cloned() else {
+    fn toggle_mark_selected(&mut self, max_items: usize) {
+        let Some(email) = self.emails.get(self.cursor) else {
             return;
         };
-        let op_id = self.next_op_id();
-        let from_index = self.cursor;
-        self.pending_write_ops.insert(
+        if !self.marked.remove(&email.id) {
+            self.marked.insert(email.id.clone());
+        }
+        self.step_cursor(true, max_items);
+    }
+
+    fn move_selected_to_folder(&mut self, folder: &str, action_label: &str) {
+        let indices = self.emails.get(self.cursor).map(|_| vec![self.cursor]);
+        self.move_indices_to_folder(indices.unwrap_or_default(), folder, action_label);
+    }
+
+    fn move_targets_to_folder(&mut self, folder: &str, kind: MoveKind) {
+        let indices = self.target_indices();
+        let moved = self.move_indices_to_folder(indices, folder, &kind.label());
+        self.remember_move(moved, &kind);
+    }
+
+    fn move_indices_to_folder(
+        &mut self,
+        indices: Vec<usize>,
+        folder: &str,
+        action_label: &str,
+    ) -> Vec<RemovedRow> {
+        let Some(target_id) = rules::resolve_mailbox_id(folder, &self.mailboxes) else {
+            self.status_message = Some(format!(
+                "{} failed: could not resolve folder '{}'",
+                action_label, folder
+            ));
+            return Vec::new();
+        };
+        self.move_indices(indices, target_id, action_label)
+    }
+
+    fn move_indices(
+        &mut self,
+        indices: Vec<usize>,
+        target_id: String,
+        action_label: &str,
+    ) -> Vec<RemovedRow> {
+        // Always move only these emails (not whole threads) so that
+        // archive/delete/move in the email list only affect the current folder.
+        self.remove_indices(indices, action_label, |op_id, id| {
+            BackendCommand::MoveEmail {
+                op_id,
+                id,
+                to_mailbox_id: target_id.clone(),
+            }
+        })
+    }
+
+    /// Keep the emails just moved out of this mailbox so `Z` can undo it.
+    fn remember_move(&mut self, moved: Vec<RemovedRow>, kind: &MoveKind) {
+        if moved.is_empty() {
+            return;
+        }
+        let done = kind.done();
+        let toast = if moved.len() == 1 {
+            format!("{} - Z to undo", done)
+        } else {
+            format!("{} {} messages - Z to undo", done, moved.len())
+        };
+        self.last_move = Some(LastMove {
+            emails: moved,
+            toast,
+        });
+    }
+
+    /// Undo through the backend's persisted undo log. Rows from the last
+    /// archive/delete/move in this list are put back right away and their own
+    /// undo entries reverted; otherwise the newest entry is, and the change
+    /// shows up when the list refreshes.
+    fn undo_last_change(&mut self) {
+        let requests = match self.last_move.take() {
+            Some(last) => {
+                let mut requests = Vec::new();
+                for row in last.emails {
+                    // Still listed means the move failed and was never logged.
+                    if self.emails.iter().any(|e| e.id == row.email.id) {
+                        continue;
+                    }
+                    requests.push(BackendCommand::Undo {
+                        move_op: Some((row.op_id, row.email.id.clone())),
+                    });
+                    self.reinsert_email(row.from_index, row.email);
+                }
+                requests
+            }
+            None => vec![BackendCommand::Undo { move_op: None }],
+        };
+        if requests.is_empty() {
+            return;
+        }
+        for request in requests {
+            if let Err(e) = self.cmd_tx.send(request) {
+                self.status_message = Some(format!("Undo failed: {}", e));
+                return;
+            }
+            self.pending_undos += 1;
+        }
+        self.status_message = Some("Undoing...".to_string());
+    }
+
+    /// Optimistically remove the emails at `indices` (ascending) and send one
+    /// command per message. Each gets its own pending op recording its
+    /// original index; failures roll back in ascending order, so a fully
+    /// failed batch is restored to its original positions.
+    fn remove_indices(
+        &mut self,
+        indices: Vec<usize>,
+        action_label: &str,
+        command: impl Fn(u64, String) -> BackendCommand,
+    ) -> Vec<RemovedRow> {
+        if indices.is_empty() {
+            return Vec::new();
+        }
+        let mut failures = Vec::new();
+        let mut op_ids = Vec::with_capacity(indices.len());
+        for &from_index in &indices {
+            let email = self.emails[from_index].clone();
+            let op_id = self.next_op_id();
+            op_ids.push(op_id);
+            self.pending_write_ops.insert(
+                op_id,
+                PendingWriteOp::Move {
+                    email: Box::new(email.clone()),
+                    from_index,
+                },
+            );
+            if let Err(e) = self.cmd_tx.send(command(op_id, email.id.clone())) {
+                failures.push((op_id, email, from_index, e.to_string()));
+            }
+        }
+
+        let mut removed = Vec::with_capacity(indices.len());
+        for (&from_index, &op_id) in indices.iter().zip(&op_ids).rev() {
+            let email = self.emails.remove(from_index);
+            self.marked.remove(&email.id);
+            removed.push(RemovedRow {
+                op_id,
+                from_index,
+                email,
+            });
+        }
+        removed.reverse();
+        self.reindex_threads();
+        self.cursor = indices[0].min(self.emails.len().saturating_sub(1));
+        if self.collapse_threads {
+            self.cursor = self.thread_head(self.cursor);
+        }
+        if let Some(ref mut total) = self.total {
+            *total = total.saturating_sub(indices.len() as u32);
+        }
+        if indices.len() > 1 {
+            self.status_message = Some(format!("{}: {} messages", action_label, indices.len()));
+        }
+        removed.retain(|row| !failures.iter().any(|(_, f, _, _)| f.id == row.email.id));
+        for (op_id, email, from_index, err) in failures {
+            self.record_send_failure(
+                op_id,
+                PendingWriteOp::Move {
+                    email: Box::new(email),
+                    from_index,
+                },
+                action_label,
+                err,
+            );
+        }
+        removed
+    }
+
+    /// Toggle `$flagged` on the target emails. With several marked, flag them
+    /// all unless every one is already flagged, in which case unflag them.
+    fn toggle_flag_targets(&mut self) {
+        let indices = self.target_indices();
+        if indices.is_empty() {
+            return;
+        }
+        let new_flagged = !indices.iter().all(|&i| Self::is_flagged(&self.emails[i]));
+        let mut updated = 0;
+        for i in indices {
+            let email_id = self.emails[i].id.clone();
+            let old_flagged = Self::is_flagged(&self.emails[i]);
+            if old_flagged == new_flagged {
+                continue;
+            }
+            let op_id = self.next_op_id();
+            self.pending_write_ops.insert(
+                op_id,
+                PendingWriteOp::Flag {
+                    email_id: email_id.clone(),
+                    old_flagged,
+                },
+            );
+            self.set_email_flag_state(&email_id, new_flagged);
+            if let Err(e) = self.cmd_tx.send(BackendCommand::SetEmailFlagged {
+                op_id,
+                id: email_id.clone(),
+                flagged: new_flagged,
+            }) {
+                self.record_send_failure(
+                    op_id,
+                    PendingWriteOp::Flag {
+                        email_id,
+                        old_flagged,
+                    },
+                    "Flag update",
+                    e.to_string(),
+                );
+            } else {
+                updated += 1;
+            }
+        }
+        if !self.marked.is_empty() {
+            self.marked.clear();
+            let verb = if new_flagged { "Flagged" } else { "Unflagged" };
+            self.status_message = Some(format!("{} {} messages", verb, updated));
+        }
+    }
+
+    /// Toggle `$seen` on the target emails. With several marked, mark them all
+    /// read unless every one is already read, in which case mark them unread.
+    /// Returns the new seen state, or None when there was nothing to update.
+    fn toggle_seen_targets(&mut self) -> Option<bool> {
+        let indices = self.target_indices();
+        if indices.is_empty() {
+            return None;
+        }
+        let new_seen = indices.iter().any(|&i| Self::is_unread(&self.emails[i]));
+        let mut updated = 0;
+        for i in indices {
+            if self.set_seen_at(i, new_seen) {
+                updated += 1;
+            }
+        }
+        if !self.marked.is_empty() {
+            self.marked.clear();
+            let state = if new_seen { "read" } else { "unread" };
+            self.status_message = Some(format!("Marked {} messages {}", updated, state));
+        }
+        Some(new_seen)
+    }
+
+    /// Set `$seen` on the email at `index` and send the update. Returns true
+    /// when an update was sent.
+    fn set_seen_at(&mut self, index: usize, new_seen: bool) -> bool {
+        let email_id = self.emails[index].id.clone();
+        let old_seen = !Self::is_unread(&self.emails[index]);
+        if old_seen == new_seen {
+            return false;
+        }
+        let op_id = self.next_op_id();
+        self.pending_write_ops.insert(
+            op_id,
+            PendingWriteOp::Seen {
+                email_id: email_id.clone(),
+                old_seen,
+            },
+        );
+        self.set_email_seen_state(&email_id, new_seen);
+        let send_result = if new_seen {
+            self.cmd_tx.send(BackendCommand::MarkEmailRead {
+                op_id,
+                id: email_id.clone(),
+            })
+        } else {
+            self.cmd_tx.send(BackendCommand::MarkEmailUnread {
+                op_id,
+                id: email_id.clone(),
+            })
+        };
+        if let Err(e) = send_result {
+            self.record_send_failure(
+                op_id,
+                PendingWriteOp::Seen { email_id, old_seen },
+                "Read state update",
+                e.to_string(),
+            );
+            return false;
+        }
+        true
+    }
+
+    /// `K`: mute or unmute the cursor row's thread; the reply arrives as
+    /// `ThreadMuted`.
+    fn toggle_mute_selected(&mut self) {
+        let Some(thread_id) = self
+            .emails
+            .get(self.cursor)
+            .and_then(|e| e.thread_id.clone())
+        else {
+            return;
+        };
+        if let Err(e) = self.cmd_tx.send(BackendCommand::SetThreadMuted {
+            thread_id: thread_id.clone(),
+            muted: None,
+        }) {
+            self.status_message = Some(format!("Mute failed to send: {}", e));
+        } else {
+            self.pending_mute = Some(thread_id);
+        }
+    }
+
+    /// Mark every loaded message in the cursor row's thread read. Rows that
+    /// are not part of a multi-message thread are marked read on their own.
+    fn mark_thread_read_selected(&mut self) {
+        let Some(email) = self.emails.get(self.cursor) else {
+            return;
+        };
+        let thread_id = match (&email.thread_id, self.get_thread_counts(email)) {
+            (Some(thread_id), Some((_, total))) if total > 1 => thread_id.clone(),
+            _ => {
+                self.set_seen_at(self.cursor, true);
+                return;
+            }
+        };
+        let email_ids: Vec<String> = self
+            .emails
+            .iter()
+            .filter(|e| e.thread_id.as_deref() == Some(thread_id.as_str()) && Self::is_unread(e))
+            .map(|e| e.id.clone())
+            .collect();
+        if email_ids.is_empty() {
+            self.status_message = Some("Thread already read".to_string());
+            return;
+        }
+        for id in &email_ids {
+            self.set_email_seen_state(id, true);
+        }
+        if let Some((unread, _)) = self.thread_counts.get_mut(&thread_id) {
+            *unread = unread.saturating_sub(email_ids.len());
+        }
+        let count = email_ids.len();
+        if let Err(e) = self.cmd_tx.send(BackendCommand::MarkThreadRead {
+            thread_id,
+            email_ids,
+        }) {
+            self.status_message = Some(format!("Mark thread read failed: {}", e));
+            self.request_refresh("email_list.mark_thread_read_failed");
+            return;
+        }
+        self.status_message = Some(format!("Marked {} messages in thread read", count));
+    }
+
+    fn snooze_selected(&mut self, input: &str) {
+        let wake_at = match snooze::resolve_wake_time(input, snooze::now_secs()) {
+            Ok(wake_at) => wake_at,
+            Err(e) => {
+                self.status_message = Some(format!("Snooze failed: {}", e));
+                return;
+            }
+        };
+        let Some(email) = self.emails.get(self.cursor).cloned() else {
+            return;
+        };
+        let op_id = self.next_op_id();
+        let from_index = self.cursor;
+        self.pending_write_ops.insert(
             op_id,
             PendingWriteOp::Move {
                 email: Box::new(email.clone()),
                 from_index,
             },
         );
-        // Always destroy only this single email (not the whole thread) so that
-        // expire in the email list only affects the current folder.
-        let send_result = self.cmd_tx.send(BackendCommand::DestroyEmail {
+        let send_result = self.cmd_tx.send(BackendCommand::SnoozeEmail {
             op_id,
             id: email.id.clone(),
+            folder: self.snooze_folder.clone(),
+            wake_at,
         });
         self.emails.remove(from_index);
+        self.reindex_threads();
         if self.cursor >= self.emails.len() && self.cursor > 0 {
             self.cursor -= 1;
         }
@@ -606,11 +1688,65 @@ impl EmailListView {
                     email: Box::new(email),
                     from_index,
                 },
-                "Expire",
+                "Snooze",
                 e.to_string(),
             );
+        } else {
+            self.status_message = Some(format!("Snoozed for {}", input.trim()));
+        }
+    }
+
+    /// Permanently destroy the email with `id`, if it is still listed.
+    fn expire_email_now(&mut self, id: &str) {
+        let Some(index) = self.emails.iter().position(|e| e.id == id) else {
+            return;
+        };
+        // Always destroy only this single email (not the whole thread) so
+        // that expire in the email list only affects the current folder.
+        self.remove_indices(vec![index], "Expire", |op_id, id| {
+            BackendCommand::DestroyEmail { op_id, id }
+        });
+        // Destroyed mail cannot be moved back.
+        self.last_move = None;
+    }
+}
+
+/// Parse `after:YYYY-MM-DD before:YYYY-MM-DD` (either bound optional, empty
+/// input clears both) into the days to filter on.
+fn parse_date_range(input: &str) -> Result<(Option<String>, Option<String>), String> {
+    let mut after = None;
+    let mut before = None;
+    for token in input.split_whitespace() {
+        let (slot, day) = if let Some(day) = token.strip_prefix("after:") {
+            (&mut after, day)
+        } else if let Some(day) = token.strip_prefix("before:") {
+            (&mut before, day)
+        } else {
+            return Err(format!(
+                "expected after:DATE or before:DATE, got '{}'",
+                token
+            ));
+        };
+        let valid = day.len() == 10
+            && day.as_bytes()[4] == b'-'
+            && day.as_bytes()[7] == b'-'
+            && datefmt::parse_utc_timestamp(&format!("{}T00:00:00Z", day)).is_some();
+        if !valid {
+            return Err(format!("invalid date '{}' (use YYYY-MM-DD)", day));
+        }
+        *slot = Some(day.to_string());
+    }
+    if let (Some(a), Some(b)) = (&after, &before) {
+        if a >= b {
+            return Err(format!("{} is not before {}", a, b));
         }
     }
+    Ok((after, before))
+}
+
+/// A `YYYY-MM-DD` day as the JMAP `UTCDate` of its start.
+fn utc_midnight(day: &str) -> String {
+    format!("{}T00:00:00Z", day)
 }
 
 fn truncate(s: &str, max: usize) -> String {
@@ -634,6 +1770,12 @@ impl View for EmailListView {
         // Header
         term.move_to(1, 1)?;
         term.set_header()?;
+        let mut used = 0;
+        if let Some((name, color)) = &self.account_label {
+            used = term.write_header_label(&format!("[{}]", name), *color, term.cols)?;
+            term.write_truncated(" ", term.cols - used)?;
+            used = (used + 1).min(term.cols);
+        }
         let header = {
             let base = if let Some(ref query) = self.active_search {
                 match self.total {
@@ -649,13 +1791,32 @@ impl View for EmailListView {
                     None => self.mailbox_name.clone(),
                 }
             };
+            let base = match self.date_range_label() {
+                Some(range) => format!("{} [{}]", base, range),
+                None => base,
+            };
+            let base = if self.show_unread_only {
+                format!("{} [unread only]", base)
+            } else {
+                base
+            };
+            let base = if self.collapse_threads {
+                format!("{} [threads]", base)
+            } else {
+                base
+            };
+            let base = if self.sort != SortOrder::default() {
+                format!("{} [sort: {}]", base, self.sort.label())
+            } else {
+                base
+            };
             if let Some(ts) = self.last_refreshed {
                 format!("{} (refreshed {})", base, format_system_time(ts))
             } else {
                 base
             }
         };
-        term.write_truncated(&header, term.cols)?;
+        term.write_truncated(&header, term.cols - used)?;
         term.reset_attr()?;
 
         // Separator
@@ -677,9 +1838,8 @@ impl View for EmailListView {
                 0
             };
 
-            for (i, mailbox) in self
-                .mailboxes
-                .iter()
+            for (i, mailbox) in filter_mailboxes(&self.mailboxes, &self.move_filter)
+                .into_iter()
                 .skip(scroll_offset)
                 .enumerate()
                 .take(max_items)
@@ -707,22 +1867,33 @@ impl View for EmailListView {
             term.move_to(3, 1)?;
             term.write_truncated("No messages.", term.cols)?;
         } else {
-            let max_items = (term.rows as usize).saturating_sub(4);
+            let max_items = self.list_rows(term.rows);
+            let visible = self.visible_indices();
+            let scroll_offset = self.render_scroll_offset(&visible, max_items);
+            let now = snooze::now_secs();
 
-            for (i, email) in self
-                .emails
+            for (i, &display_idx) in visible
                 .iter()
-                .skip(self.scroll_offset)
+                .skip(scroll_offset)
                 .enumerate()
                 .take(max_items)
             {
                 let row = 3 + i as u16;
                 term.move_to(row, 1)?;
 
-                let display_idx = self.scroll_offset + i;
+                let email = &self.emails[display_idx];
                 let thread_counts = self.get_thread_counts(email);
-                let line =
-                    Self::format_email(email, term.cols, thread_counts, self.spam_marker(email));
+                let line = Self::format_email(
+                    email,
+                    term.cols,
+                    thread_counts,
+                    self.spam_marker(email),
+                    self.marked.contains(&email.id),
+                    self.is_thread_member_row(display_idx),
+                    &self
+                        .date_format
+                        .format(email.received_at.as_deref(), now as i64),
+                );
 
                 if display_idx == self.cursor {
                     term.set_selection()?;
@@ -732,22 +1903,48 @@ impl View for EmailListView {
                 } else if Self::is_unread(email) {
                     term.set_bold_text()?;
                 }
+                let from = email
+                    .from
+                    .as_ref()
+                    .and_then(|addrs| addrs.first())
+                    .map(|a| a.to_string())
+                    .unwrap_or_default();
+                if let Some(color) = term
+                    .theme()
+                    .row_fg(&from, |k| email.keywords.contains_key(k))
+                {
+                    term.set_fg(color)?;
+                }
 
                 term.write_truncated(&line, term.cols)?;
                 term.reset_attr()?;
             }
+
+            if self.preview_pane {
+                self.render_preview(term, 3 + max_items as u16)?;
+            }
         }
 
         // Status bar
         term.move_to(term.rows, 1)?;
         term.set_status()?;
-        let base_status = if self.search_mode {
-            format!(" Search: {}_", self.search_input)
-        } else if self.move_mode {
+        let base_status = if self.confirm_expire.is_some() {
+            " Permanently destroy 1 message? This cannot be undone. y:destroy n/Esc:cancel"
+                .to_string()
+        } else if self.search_mode {
+            format!(" Search (Up/Down: history): {}_", self.search_input)
+        } else if self.date_mode {
             format!(
-                " {}/{} | n/p:navigate RET:move Esc:cancel",
-                self.move_cursor + 1,
-                self.mailboxes.len()
+                " Received (after:YYYY-MM-DD before:YYYY-MM-DD, empty clears): {}_",
+                self.date_input
+            )
+        } else if self.snooze_mode {
+            format!(" Snooze for (e.g. 4h, 3d, 1w): {}_", self.snooze_input)
+        } else if self.move_mode {
+            move_picker_status(
+                self.move_cursor,
+                filter_mailboxes(&self.mailboxes, &self.move_filter).len(),
+                &self.move_filter,
             )
         } else if self.loading {
             if self.loading_more {
@@ -756,9 +1953,16 @@ impl View for EmailListView {
                 " Loading... | q:back".to_string()
             }
         } else if self.emails.is_empty() {
-            " q:back g:refresh s:search".to_string()
+            format!(
+                " q:back g:refresh s:search w:dates{}",
+                if self.has_query_filter() {
+                    " Esc:clear-search"
+                } else {
+                    ""
+                }
+            )
         } else {
-            let search_hint = if self.active_search.is_some() {
+            let search_hint = if self.has_query_filter() {
                 " Esc:clear-search"
             } else {
                 ""
@@ -769,10 +1973,31 @@ impl View for EmailListView {
             } else {
                 ""
             };
+            let selected_hint = if !self.marked.is_empty() {
+                format!("{} selected | ", self.marked.len())
+            } else if let Some(ref last) = self.last_move {
+                format!("{} | ", last.toast)
+            } else {
+                String::new()
+            };
+            let position = if self.show_unread_only {
+                let visible = self.visible_indices();
+                format!(
+                    "{}/{} unread",
+                    self.visible_position(&visible) + 1,
+                    visible.len()
+                )
+            } else {
+                format!(
+                    "{}/{}",
+                    self.cursor + 1,
+                    self.total.unwrap_or(self.emails.len() as u32)
+                )
+            };
             format!(
-                " {}/{} | q:back n/p:nav RET:read g:refresh r:reply R:reply-all e:dry-run E:run-rules a:archive d:delete{} J:spam H:ham S:score f:flag u:unread m:move s:search{}{}",
-                self.cursor + 1,
-                self.total.unwrap_or(self.emails.len() as u32),
+                " {} | {}q:back n/p:nav N/P:unread U:unread-only C:threads o/O:sort v:preview RET:read g:refresh r:reply R:reply-all e:dry-run E:run-rules a:archive z:snooze d:delete{} J:spam H:ham S:score f:flag u:unread M:thread-read m:move SPC:mark s:search w:dates{}{}",
+                position,
+                selected_hint,
                 expire_hint,
                 search_hint,
                 load_more_hint
@@ -804,6 +2029,10 @@ impl View for EmailListView {
                         self.active_search = None;
                     } else {
                         self.active_search = Some(self.search_input.clone());
+                        cache::push_search_history(&mut self.search_history, &self.search_input);
+                        let _ = self.cmd_tx.send(BackendCommand::RecordSearch {
+                            query: self.search_input.clone(),
+                        });
                     }
                     self.search_input.clear();
                     self.request_refresh("email_list.search_submit");
@@ -812,10 +2041,31 @@ impl View for EmailListView {
                     self.search_mode = false;
                     self.search_input.clear();
                 }
+                Key::Up if !self.search_history.is_empty() => {
+                    let pos = match self.history_pos {
+                        Some(pos) => pos.saturating_sub(1),
+                        None => self.search_history.len() - 1,
+                    };
+                    self.history_pos = Some(pos);
+                    self.search_input = self.search_history[pos].clone();
+                }
+                Key::Down => match self.history_pos {
+                    Some(pos) if pos + 1 < self.search_history.len() => {
+                        self.history_pos = Some(pos + 1);
+                        self.search_input = self.search_history[pos + 1].clone();
+                    }
+                    Some(_) => {
+                        self.history_pos = None;
+                        self.search_input.clear();
+                    }
+                    None => {}
+                },
                 Key::Backspace => {
+                    self.history_pos = None;
                     self.search_input.pop();
                 }
                 Key::Char(c) => {
+                    self.history_pos = None;
                     self.search_input.push(c);
                 }
                 _ => {}
@@ -823,131 +2073,186 @@ impl View for EmailListView {
             return ViewAction::Continue;
         }
 
-        // Move mode: mailbox picker
-        if self.move_mode {
+        // Date-range mode: capture `after:`/`before:` bounds
+        if self.date_mode {
             match key {
-                Key::Escape | Key::Char('q') => {
-                    self.move_mode = false;
-                }
-                Key::Char('n') | Key::Char('j') | Key::Down => {
-                    if !self.mailboxes.is_empty() && self.move_cursor + 1 < self.mailboxes.len() {
-                        self.move_cursor += 1;
+                Key::Enter => {
+                    self.date_mode = false;
+                    let input = std::mem::take(&mut self.date_input);
+                    match parse_date_range(&input) {
+                        Ok((after, before)) => {
+                            self.received_after = after;
+                            self.received_before = before;
+                            self.request_refresh("email_list.date_filter_submit");
+                        }
+                        Err(e) => self.status_message = Some(format!("Date filter: {}", e)),
                     }
                 }
-                Key::Char('p') | Key::Char('k') | Key::Up => {
-                    if self.move_cursor > 0 {
-                        self.move_cursor -= 1;
-                    }
+                Key::Escape => {
+                    self.date_mode = false;
+                    self.date_input.clear();
+                }
+                Key::Backspace => {
+                    self.date_input.pop();
+                }
+                Key::Char(c) => {
+                    self.date_input.push(c);
                 }
+                _ => {}
+            }
+            return ViewAction::Continue;
+        }
+
+        // Snooze mode: capture a relative duration
+        if self.snooze_mode {
+            match key {
                 Key::Enter => {
-                    if let Some(target_id) =
-                        self.mailboxes.get(self.move_cursor).map(|m| m.id.clone())
-                    {
-                        if let Some(email) = self.emails.get(self.cursor).cloned() {
-                            let op_id = self.next_op_id();
-                            let from_index = self.cursor;
-                            self.pending_write_ops.insert(
-                                op_id,
-                                PendingWriteOp::Move {
-                                    email: Box::new(email.clone()),
-                                    from_index,
-                                },
-                            );
-                            let send_result = self.cmd_tx.send(BackendCommand::MoveEmail {
-                                op_id,
-                                id: email.id.clone(),
-                                to_mailbox_id: target_id,
-                            });
-                            self.emails.remove(from_index);
-                            if self.cursor >= self.emails.len() && self.cursor > 0 {
-                                self.cursor -= 1;
-                            }
-                            if let Some(ref mut total) = self.total {
-                                *total = total.saturating_sub(1);
-                            }
-                            if let Err(e) = send_result {
-                                self.record_send_failure(
-                                    op_id,
-                                    PendingWriteOp::Move {
-                                        email: Box::new(email),
-                                        from_index,
-                                    },
-                                    "Move",
-                                    e.to_string(),
-                                );
-                            }
-                        }
-                        self.move_mode = false;
+                    self.snooze_mode = false;
+                    let input = std::mem::take(&mut self.snooze_input);
+                    if !input.trim().is_empty() {
+                        self.snooze_selected(&input);
                     }
                 }
-                Key::ScrollUp => {
-                    if self.move_cursor > 0 {
-                        self.move_cursor -= 1;
-                    }
+                Key::Escape => {
+                    self.snooze_mode = false;
+                    self.snooze_input.clear();
                 }
-                Key::ScrollDown => {
-                    if !self.mailboxes.is_empty() && self.move_cursor + 1 < self.mailboxes.len() {
-                        self.move_cursor += 1;
-                    }
+                Key::Backspace => {
+                    self.snooze_input.pop();
+                }
+                Key::Char(c) => {
+                    self.snooze_input.push(c);
                 }
                 _ => {}
             }
             return ViewAction::Continue;
         }
 
-        // Normal mode
-        let max_items = (term_rows as usize).saturating_sub(4);
+        // Expire confirmation: only 'y' destroys
+        if let Some(id) = self.confirm_expire.take() {
+            if matches!(key, Key::Char('y') | Key::Char('Y')) {
+                self.expire_email_now(&id);
+            } else {
+                self.status_message = Some("Expire cancelled".to_string());
+            }
+            return ViewAction::Continue;
+        }
+
+        // Move mode: mailbox picker
+        if self.move_mode {
+            let picked = match move_picker_key(
+                key,
+                &self.mailboxes,
+                &mut self.move_cursor,
+                &mut self.move_filter,
+            ) {
+                MovePick::Continue => return ViewAction::Continue,
+                MovePick::Cancel => None,
+                MovePick::Chosen(mailbox) => Some((mailbox.id.clone(), mailbox.name.clone())),
+            };
+            self.move_mode = false;
+            if let Some((target_id, name)) = picked {
+                let indices = self.target_indices();
+                let kind = MoveKind::MoveTo(name);
+                let moved = self.move_indices(indices, target_id, &kind.label());
+                self.remember_move(moved, &kind);
+            }
+            return ViewAction::Continue;
+        }
+
+        // Normal mode: apply [keybindings] remapping, then match default keys
+        let key = match key {
+            Key::Char(c) => match self.keymap.translate(c) {
+                Some(c) => Key::Char(c),
+                None => return ViewAction::Continue,
+            },
+            other => other,
+        };
+        let max_items = self.list_rows(term_rows);
         let page = max_items;
         match key {
             Key::Char('q') => ViewAction::Pop,
             Key::Char('n') | Key::Char('j') | Key::Down => {
-                if !self.emails.is_empty() && self.cursor + 1 < self.emails.len() {
-                    self.cursor += 1;
-                    self.adjust_scroll(max_items);
-                } else {
+                if !self.step_cursor(true, max_items) {
                     self.request_load_more();
                 }
                 ViewAction::Continue
             }
             Key::Char('p') | Key::Char('k') | Key::Up => {
-                if self.cursor > 0 {
-                    self.cursor -= 1;
-                    self.adjust_scroll(max_items);
-                }
+                self.step_cursor(false, max_items);
                 ViewAction::Continue
             }
             Key::PageDown => {
                 if !self.emails.is_empty() {
-                    self.cursor = (self.cursor + page).min(self.emails.len() - 1);
-                    self.adjust_scroll(max_items);
-                    if self.cursor + 1 >= self.emails.len() {
+                    self.page_cursor(true, page, max_items);
+                    if self.visible_indices().last() == Some(&self.cursor) {
                         self.request_load_more();
                     }
                 }
                 ViewAction::Continue
             }
             Key::PageUp => {
-                self.cursor = self.cursor.saturating_sub(page);
-                self.adjust_scroll(max_items);
+                self.page_cursor(false, page, max_items);
                 ViewAction::Continue
             }
             Key::Home => {
-                self.cursor = 0;
-                self.adjust_scroll(max_items);
+                self.page_cursor(false, usize::MAX, max_items);
                 ViewAction::Continue
             }
             Key::End => {
                 if !self.emails.is_empty() {
-                    self.cursor = self.emails.len() - 1;
-                    self.adjust_scroll(max_items);
+                    self.page_cursor(true, usize::MAX, max_items);
                     self.request_load_more();
                 }
                 ViewAction::Continue
             }
+            Key::Char('U') => {
+                self.toggle_unread_only(max_items);
+                ViewAction::Continue
+            }
+            Key::Char('Z') => {
+                self.undo_last_change();
+                self.adjust_scroll(max_items);
+                ViewAction::Continue
+            }
+            Key::Char('o') => {
+                let field = self.sort.field.next();
+                let sort = SortOrder {
+                    field,
+                    descending: field.default_descending(),
+                };
+                self.set_sort(sort, max_items);
+                ViewAction::Continue
+            }
+            Key::Char('O') => {
+                let sort = SortOrder {
+                    descending: !self.sort.descending,
+                    ..self.sort
+                };
+                self.set_sort(sort, max_items);
+                ViewAction::Continue
+            }
+            Key::Char('v') => {
+                self.toggle_preview_pane(term_rows);
+                ViewAction::Continue
+            }
+            Key::Char('C') => {
+                self.toggle_collapse_threads(max_items);
+                ViewAction::Continue
+            }
+            Key::Tab => {
+                self.toggle_thread_expanded(max_items);
+                ViewAction::Continue
+            }
+            Key::Enter if self.cursor_on_collapsed_thread() => {
+                self.toggle_thread_expanded(max_items);
+                ViewAction::Continue
+            }
             Key::Enter => self.open_selected().unwrap_or(ViewAction::Continue),
             Key::Char('t') => self.open_thread_list(false).unwrap_or(ViewAction::Continue),
             Key::Char('T') => self.open_thread_list(true).unwrap_or(ViewAction::Continue),
             Key::Char('g') => {
+                self.last_counts_refresh = Some(Instant::now());
                 self.request_refresh("email_list.key_g");
                 ViewAction::Continue
             }
@@ -979,6 +2284,11 @@ impl View for EmailListView {
                 }
                 ViewAction::Continue
             }
+            Key::Char('e') | Key::Char('E') if self.cross_mailbox => {
+                self.status_message =
+                    Some("Rules run per mailbox; open a mailbox to run them".to_string());
+                ViewAction::Continue
+            }
             Key::Char('e') => {
                 let mailbox_id = self.mailbox_id.clone();
                 let mailbox_name = self.mailbox_name.clone();
@@ -986,6 +2296,7 @@ impl View for EmailListView {
                     origin: "email_list.key_e_dry_run".to_string(),
                     mailbox_id,
                     mailbox_name: mailbox_name.clone(),
+                    progress: false,
                 }) {
                     self.status_message = Some(format!("Rules dry-run failed to send: {}", e));
                 } else {
@@ -1001,6 +2312,7 @@ impl View for EmailListView {
                     origin: "email_list.key_E_run_rules".to_string(),
                     mailbox_id,
                     mailbox_name: mailbox_name.clone(),
+                    progress: false,
                 }) {
                     self.status_message = Some(format!("Run rules failed to send: {}", e));
                 } else {
@@ -1009,117 +2321,88 @@ impl View for EmailListView {
                 ViewAction::Continue
             }
             Key::Char('f') => {
-                if let Some(email) = self.emails.get(self.cursor) {
-                    let email_id = email.id.clone();
-                    let old_flagged = email.keywords.contains_key("$flagged");
-                    let new_flagged = !old_flagged;
-                    let op_id = self.next_op_id();
-                    self.pending_write_ops.insert(
-                        op_id,
-                        PendingWriteOp::Flag {
-                            email_id: email_id.clone(),
-                            old_flagged,
-                        },
-                    );
-                    self.set_email_flag_state(&email_id, new_flagged);
-                    if let Err(e) = self.cmd_tx.send(BackendCommand::SetEmailFlagged {
-                        op_id,
-                        id: email_id.clone(),
-                        flagged: new_flagged,
-                    }) {
-                        self.record_send_failure(
-                            op_id,
-                            PendingWriteOp::Flag {
-                                email_id,
-                                old_flagged,
-                            },
-                            "Flag update",
-                            e.to_string(),
-                        );
-                    }
-                }
+                self.toggle_flag_targets();
+                ViewAction::Continue
+            }
+            Key::Char('M') => {
+                self.mark_thread_read_selected();
+                ViewAction::Continue
+            }
+            Key::Char('K') => {
+                self.toggle_mute_selected();
                 ViewAction::Continue
             }
             Key::Char('u') => {
-                if let Some(email) = self.emails.get(self.cursor) {
-                    let email_id = email.id.clone();
-                    let old_seen = email.keywords.contains_key("$seen");
-                    let new_seen = !old_seen;
-                    let marking_read = new_seen;
-                    let op_id = self.next_op_id();
-                    self.pending_write_ops.insert(
-                        op_id,
-                        PendingWriteOp::Seen {
-                            email_id: email_id.clone(),
-                            old_seen,
-                        },
-                    );
-                    self.set_email_seen_state(&email_id, new_seen);
-                    let send_result = if new_seen {
-                        self.cmd_tx.send(BackendCommand::MarkEmailRead {
-                            op_id,
-                            id: email_id.clone(),
-                        })
-                    } else {
-                        self.cmd_tx.send(BackendCommand::MarkEmailUnread {
-                            op_id,
-                            id: email_id.clone(),
-                        })
-                    };
-                    if let Err(e) = send_result {
-                        self.record_send_failure(
-                            op_id,
-                            PendingWriteOp::Seen { email_id, old_seen },
-                            "Read state update",
-                            e.to_string(),
-                        );
+                let bulk = !self.marked.is_empty();
+                // When marking a single email as read, advance to the next unread email
+                if self.toggle_seen_targets() == Some(true) && !bulk {
+                    // Scan down (older items) first
+                    let mut found = false;
+                    for i in (self.cursor + 1)..self.emails.len() {
+                        if Self::is_unread(&self.emails[i]) {
+                            self.cursor = i;
+                            found = true;
+                            break;
+                        }
                     }
-                    // When marking as read, advance to the next unread email
-                    if marking_read {
-                        // Scan down (older items) first
-                        let mut found = false;
-                        for i in (self.cursor + 1)..self.emails.len() {
+                    // If none found below, scan up (newer items)
+                    if !found {
+                        for i in (0..self.cursor).rev() {
                             if Self::is_unread(&self.emails[i]) {
                                 self.cursor = i;
-                                found = true;
                                 break;
                             }
                         }
-                        // If none found below, scan up (newer items)
-                        if !found {
-                            for i in (0..self.cursor).rev() {
-                                if Self::is_unread(&self.emails[i]) {
-                                    self.cursor = i;
-                                    break;
-                                }
-                            }
-                        }
-                        self.adjust_scroll(max_items);
                     }
+                    self.adjust_scroll(max_items);
                 }
                 ViewAction::Continue
             }
+            Key::Char('N') => {
+                self.jump_to_unread(true, max_items);
+                ViewAction::Continue
+            }
+            Key::Char('P') => {
+                self.jump_to_unread(false, max_items);
+                ViewAction::Continue
+            }
+            Key::Char(' ') => {
+                self.toggle_mark_selected(max_items);
+                ViewAction::Continue
+            }
             Key::Char('m') => {
                 if !self.emails.is_empty() && !self.mailboxes.is_empty() {
                     self.move_mode = true;
                     self.move_cursor = 0;
+                    self.move_filter.clear();
                 }
                 ViewAction::Continue
             }
             Key::Char('a') => {
                 let target = self.archive_folder.clone();
-                self.move_selected_to_folder(&target, "Archive");
+                self.move_targets_to_folder(&target, MoveKind::Archive);
                 ViewAction::Continue
             }
             Key::Char('d') => {
                 let target = self.deleted_folder.clone();
-                self.move_selected_to_folder(&target, "Delete");
+                self.move_targets_to_folder(&target, MoveKind::Delete);
+                ViewAction::Continue
+            }
+            Key::Char('z') => {
+                if !self.emails.is_empty() {
+                    self.snooze_mode = true;
+                    self.snooze_input.clear();
+                }
                 ViewAction::Continue
             }
             Key::Char('J') => {
                 self.mark_selected_spam(true);
                 ViewAction::Continue
             }
+            Key::Char('!') => {
+                self.report_spam();
+                ViewAction::Continue
+            }
             Key::Char('H') => {
                 self.mark_selected_spam(false);
                 ViewAction::Continue
@@ -1136,7 +2419,13 @@ impl View for EmailListView {
             }
             Key::Char('D') => {
                 if self.is_in_deleted_folder() {
-                    self.expire_selected_now();
+                    if let Some(id) = self.emails.get(self.cursor).map(|e| e.id.clone()) {
+                        if self.confirm_destructive {
+                            self.confirm_expire = Some(id);
+                        } else {
+                            self.expire_email_now(&id);
+                        }
+                    }
                 } else {
                     self.status_message =
                         Some("Expire is only available in the deleted folder".to_string());
@@ -1146,6 +2435,22 @@ impl View for EmailListView {
             Key::Char('s') => {
                 self.search_mode = true;
                 self.search_input.clear();
+                self.history_pos = None;
+                ViewAction::Continue
+            }
+            Key::Char('L') => {
+                match self.search_history.last() {
+                    Some(query) => {
+                        self.active_search = Some(query.clone());
+                        self.request_refresh("email_list.search_last");
+                    }
+                    None => self.status_message = Some("No previous search".to_string()),
+                }
+                ViewAction::Continue
+            }
+            Key::Char('w') => {
+                self.date_mode = true;
+                self.date_input = self.date_range_label().unwrap_or_default();
                 ViewAction::Continue
             }
             Key::Char('l') => {
@@ -1153,48 +2458,68 @@ impl View for EmailListView {
                 ViewAction::Continue
             }
             Key::Escape => {
-                if self.active_search.is_some() {
+                if !self.marked.is_empty() {
+                    self.marked.clear();
+                } else if self.has_query_filter() {
                     self.active_search = None;
+                    self.received_after = None;
+                    self.received_before = None;
                     self.request_refresh("email_list.clear_search_escape");
                 }
                 ViewAction::Continue
             }
             Key::Char('c') => {
-                let draft = compose::build_compose_draft(&self.reply_from_address);
+                let draft = compose::build_compose_draft(
+                    &self.reply_from_address,
+                    self.identities.borrow().for_compose(),
+                    &self.signature,
+                );
                 ViewAction::Compose(draft.into())
             }
-            Key::Char('?') => ViewAction::Push(Box::new(HelpView::new())),
+            Key::Char('i') => {
+                self.status_message = Some(self.identities.borrow_mut().cycle());
+                ViewAction::Continue
+            }
+            Key::Char('?') => ViewAction::Push(Box::new(
+                HelpView::with_quick_moves(&self.quick_move).with_keymap(&self.keymap),
+            )),
             Key::ScrollUp => {
-                if self.cursor > 0 {
-                    self.cursor -= 1;
-                    self.adjust_scroll(max_items);
-                }
+                self.step_cursor(false, max_items);
                 ViewAction::Continue
             }
             Key::ScrollDown => {
-                if !self.emails.is_empty() && self.cursor + 1 < self.emails.len() {
-                    self.cursor += 1;
-                    self.adjust_scroll(max_items);
-                } else {
+                if !self.step_cursor(true, max_items) {
                     self.request_load_more();
                 }
                 ViewAction::Continue
             }
             Key::MouseClick { row, col: _ } => {
-                if row >= 3 && !self.emails.is_empty() {
+                if row >= 3 && ((row - 3) as usize) < max_items && !self.emails.is_empty() {
+                    self.adjust_scroll(max_items);
                     let clicked = self.scroll_offset + (row - 3) as usize;
-                    if clicked < self.emails.len() {
-                        self.cursor = clicked;
+                    if let Some(&index) = self.visible_indices().get(clicked) {
+                        self.cursor = index;
                         self.pending_click = true;
                         return ViewAction::Continue;
                     }
                 }
                 ViewAction::Continue
             }
+            // Configured quick-move keys; built-in bindings above take precedence.
+            Key::Char(c) if self.quick_move.contains_key(&c) => {
+                let folder = self.quick_move[&c].clone();
+                self.move_targets_to_folder(&folder, MoveKind::MoveTo(folder.clone()));
+                ViewAction::Continue
+            }
             _ => ViewAction::Continue,
         }
     }
 
+    fn after_key(&mut self) {
+        self.maybe_prefetch();
+        self.sync_preview();
+    }
+
     fn take_pending_action(&mut self) -> Option<ViewAction> {
         if let Some(draft) = self.pending_compose.take() {
             return Some(ViewAction::Compose(draft.into()));
@@ -1214,6 +2539,10 @@ impl View for EmailListView {
 
     fn on_response(&mut self, response: &BackendResponse) -> bool {
         match response {
+            BackendResponse::SearchHistory(history) => {
+                self.search_history = history.clone();
+                false
+            }
             BackendResponse::Emails {
                 mailbox_id,
                 emails,
@@ -1222,67 +2551,15 @@ impl View for EmailListView {
                 loaded,
                 thread_counts,
             } if *mailbox_id == self.mailbox_id => {
-                self.loading = false;
-                self.loading_more = false;
-                self.total = *total;
-                self.last_loaded_count = *loaded;
-                self.next_query_position = position.saturating_add(*loaded);
-                match emails {
-                    Ok(emails) => {
-                        self.last_refreshed = Some(SystemTime::now());
-                        if *position == 0 {
-                            // Collect IDs of emails with in-flight move/destroy
-                            // ops so we can filter them out of incoming data.
-                            // These emails were optimistically removed and must
-                            // stay hidden until the backend confirms or rejects.
-                            let inflight_move_ids: HashSet<&str> = self
-                                .pending_write_ops
-                                .values()
-                                .filter_map(|op| match op {
-                                    PendingWriteOp::Move { email, .. } => Some(email.id.as_str()),
-                                    _ => None,
-                                })
-                                .collect();
-                            if inflight_move_ids.is_empty() {
-                                self.emails = emails.clone();
-                                self.pending_write_ops.clear();
-                            } else {
-                                self.emails = emails
-                                    .iter()
-                                    .filter(|e| !inflight_move_ids.contains(e.id.as_str()))
-                                    .cloned()
-                                    .collect();
-                                // Only clear flag/seen ops — move ops are still
-                                // in flight and must be kept for rollback.
-                                self.pending_write_ops
-                                    .retain(|_, op| matches!(op, PendingWriteOp::Move { .. }));
-                            }
-                            self.thread_counts = thread_counts.clone();
-                        } else {
-                            self.thread_counts
-                                .extend(thread_counts.iter().map(|(k, v)| (k.clone(), *v)));
-                            let mut existing_ids: HashSet<String> =
-                                self.emails.iter().map(|e| e.id.clone()).collect();
-                            for email in emails {
-                                if existing_ids.insert(email.id.clone()) {
-                                    self.emails.push(email.clone());
-                                }
-                            }
-                        }
-                        self.error = None;
-                        if self.cursor >= self.emails.len() && !self.emails.is_empty() {
-                            self.cursor = self.emails.len() - 1;
-                        }
-                    }
-                    Err(e) => {
-                        if *position == 0 {
-                            self.error = Some(format!("Failed to fetch emails: {}", e));
-                        } else {
-                            self.status_message = Some(format!("Load more failed: {}", e));
-                        }
-                    }
-                }
-                true
+                self.apply_page(emails, *total, *position, *loaded, thread_counts)
+            }
+            BackendResponse::SearchResults {
+                emails,
+                total,
+                position,
+            } if self.cross_mailbox => {
+                let loaded = emails.as_ref().map_or(0, |e| e.len() as u32);
+                self.apply_page(emails, *total, *position, loaded, &HashMap::new())
             }
             BackendResponse::EmailMutation {
                 op_id,
@@ -1305,6 +2582,7 @@ impl View for EmailListView {
                                 EmailMutationAction::MarkRead => "Mark read",
                                 EmailMutationAction::MarkUnread => "Mark unread",
                                 EmailMutationAction::SetFlagged(_) => "Flag update",
+                                EmailMutationAction::SetKeyword(_) => "Keyword update",
                                 EmailMutationAction::Move => "Move",
                                 EmailMutationAction::Destroy => "Expire",
                             };
@@ -1316,40 +2594,79 @@ impl View for EmailListView {
                     false
                 }
             }
-            BackendResponse::EmailForReply { id, result } => {
+            BackendResponse::EmailForReply { id, result }
                 if self
                     .pending_reply_request
                     .as_ref()
                     .map(|(pending_id, _)| pending_id.as_str())
-                    == Some(id.as_str())
-                {
-                    let (_, reply_all) = self.pending_reply_request.take().unwrap();
-                    match result.as_ref() {
-                        Ok(email) => {
-                            let draft = compose::build_reply_draft(
-                                email,
-                                reply_all,
-                                &self.reply_from_address,
-                            );
-                            self.pending_compose = Some(draft);
-                        }
-                        Err(e) => {
-                            let action = if reply_all { "Reply all" } else { "Reply" };
-                            self.status_message = Some(format!("{} failed: {}", action, e));
-                        }
+                    == Some(id.as_str()) =>
+            {
+                let (_, reply_all) = self.pending_reply_request.take().unwrap();
+                match result.as_ref() {
+                    Ok(email) => {
+                        let draft = compose::build_reply_draft(
+                            email,
+                            reply_all,
+                            &self.reply_from_address,
+                            self.identities.borrow().for_response(Some(email)),
+                            &self.signature,
+                            &self.quote,
+                        );
+                        self.pending_compose = Some(draft);
                     }
-                    true
-                } else {
-                    false
+                    Err(e) => {
+                        let action = if reply_all { "Reply all" } else { "Reply" };
+                        self.status_message = Some(format!("{} failed: {}", action, e));
+                    }
+                }
+                true
+            }
+            BackendResponse::EmailBody { id, result }
+                if self.preview_email_id.as_deref() == Some(id.as_str()) =>
+            {
+                self.preview = Some(match result.as_ref() {
+                    Ok(email) => Ok(EmailView::extract_body(
+                        email,
+                        false,
+                        self.render_width,
+                        self.block_remote_content,
+                    )
+                    .0
+                    .lines()
+                    .map(|l| l.to_string())
+                    .collect()),
+                    Err(e) => Err(e.clone()),
+                });
+                true
+            }
+            BackendResponse::Undone { result } if self.pending_undos > 0 => {
+                self.pending_undos -= 1;
+                self.status_message = Some(match result {
+                    Ok(description) => format!("Undone: {}", description),
+                    Err(e) => format!("Undo failed: {}", e),
+                });
+                if self.pending_undos == 0 {
+                    self.request_refresh("email_list.undone");
                 }
+                true
+            }
+            BackendResponse::ThreadMuted { thread_id, result }
+                if self.pending_mute.as_deref() == Some(thread_id) =>
+            {
+                self.pending_mute = None;
+                self.status_message = Some(match result {
+                    Ok(true) => "Thread muted: new replies will be archived".to_string(),
+                    Ok(false) => "Thread unmuted".to_string(),
+                    Err(e) => format!("Mute failed: {}", e),
+                });
+                true
             }
             BackendResponse::ThreadMarkedRead { result, .. } => {
-                if result.is_ok() {
-                    self.request_refresh("email_list.thread_marked_read");
-                    true
-                } else {
-                    false
+                if let Err(e) = result {
+                    self.status_message = Some(format!("Mark thread read failed: {}", e));
                 }
+                self.request_refresh("email_list.thread_marked_read");
+                true
             }
             BackendResponse::RulesDryRun {
                 mailbox_id,
@@ -1389,6 +2706,12 @@ impl View for EmailListView {
                 }
                 true
             }
+            BackendResponse::MailboxEmptied { mailbox_id, .. }
+                if *mailbox_id == self.mailbox_id && !self.cross_mailbox =>
+            {
+                self.request_refresh("email_list.mailbox_emptied");
+                true
+            }
             BackendResponse::MessageTrained { spam, result, .. } => {
                 let label = if *spam { "spam" } else { "not-spam" };
                 self.status_message = Some(match result {
@@ -1410,23 +2733,56 @@ impl View for EmailListView {
                 }
                 true
             }
+            BackendResponse::Mailboxes(Ok(_)) => {
+                self.last_counts_refresh = Some(Instant::now());
+                false
+            }
             _ => false,
         }
     }
 
     fn trigger_idle_sync(&mut self) -> bool {
-        if self.loading || self.move_mode || self.search_mode {
+        if self.loading
+            || self.prefetching
+            || self.move_mode
+            || self.search_mode
+            || self.date_mode
+            || self.snooze_mode
+            || self.confirm_expire.is_some()
+        {
             return false;
         }
         self.request_refresh("email_list.idle_sync");
+        self.sync_mailbox_counts();
         true
     }
+
+    fn on_render_width(&mut self, width: usize) -> bool {
+        self.render_width = width;
+        false
+    }
+
+    fn on_reload(&mut self, settings: Option<&ReloadedSettings>, message: Option<&str>) {
+        if let Some(settings) = settings {
+            self.archive_folder = settings.archive_folder.clone();
+            self.deleted_folder = settings.deleted_folder.clone();
+            self.spam_folder = settings.spam_folder.clone();
+            self.snooze_folder = settings.snooze_folder.clone();
+            self.mailbox_sync_secs = settings.mailbox_sync_secs();
+        }
+        if let Some(message) = message {
+            self.status_message = Some(message.to_string());
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::IdentityConfig;
     use crate::jmap::types::{Email, Mailbox};
+    use crate::tui::views::email_view::BodyChoice;
+    use crate::tui::views::test_view_options;
 
     fn make_email(id: &str, thread_id: &str) -> Email {
         Email {
@@ -1481,6 +2837,24 @@ mod tests {
                 unread_emails: 0,
                 sort_order: 0,
             },
+            Mailbox {
+                id: "mbox-junk".to_string(),
+                name: "Junk".to_string(),
+                parent_id: None,
+                role: Some("junk".to_string()),
+                total_emails: 0,
+                unread_emails: 0,
+                sort_order: 0,
+            },
+            Mailbox {
+                id: "mbox-snoozed".to_string(),
+                name: "Snoozed".to_string(),
+                parent_id: None,
+                role: None,
+                total_emails: 0,
+                unread_emails: 0,
+                sort_order: 0,
+            },
         ]
     }
 
@@ -1489,17 +2863,21 @@ mod tests {
     ) -> (EmailListView, mpsc::Receiver<BackendCommand>) {
         let (cmd_tx, cmd_rx) = mpsc::channel();
         let mailboxes = make_mailboxes();
+        let options = ViewOptions {
+            scrolloff,
+            quick_move: BTreeMap::from([('x', "Archive".to_string()), ('a', "Trash".to_string())]),
+            ..test_view_options()
+        };
         let mut view = EmailListView::new(
             cmd_tx,
+            &options,
             "me@example.com".to_string(),
+            SharedIdentities::default(),
+            compose::SignatureOptions::default(),
             "mbox-inbox".to_string(),
             "Inbox".to_string(),
-            50,
-            scrolloff,
             mailboxes,
-            "Archive".to_string(),
-            "Trash".to_string(),
-            None,
+            BodyChoice::shared(false),
         );
         view.loading = false;
 
@@ -1512,6 +2890,7 @@ mod tests {
         // Mark thread-A as having 2 emails (so it's a multi-email thread)
         view.thread_counts.insert("thread-A".to_string(), (0, 2));
         view.thread_counts.insert("thread-B".to_string(), (0, 1));
+        view.reindex_threads();
 
         (view, cmd_rx)
     }
@@ -1520,6 +2899,48 @@ mod tests {
         make_view_with_scrolloff(1)
     }
 
+    /// Handle `key` the way the view stack does, including `after_key`.
+    fn press(view: &mut EmailListView, key: Key, term_rows: u16) -> ViewAction {
+        let action = view.handle_key(key, term_rows);
+        view.after_key();
+        action
+    }
+
+    #[test]
+    fn identity_pick_is_shared_with_other_views() {
+        let identities = compose::IdentityChoice::shared(
+            vec![
+                IdentityConfig {
+                    name: "Me".to_string(),
+                    email: "me@example.com".to_string(),
+                    signature: None,
+                },
+                IdentityConfig {
+                    name: "Sales".to_string(),
+                    email: "sales@example.com".to_string(),
+                    signature: None,
+                },
+            ],
+            false,
+        );
+        let (mut first, _rx1) = make_view();
+        let (mut second, _rx2) = make_view();
+        first.identities = identities.clone();
+        second.identities = identities;
+
+        first.handle_key(Key::Char('i'), 24);
+        assert_eq!(
+            first.status_message.as_deref(),
+            Some("Composing as Sales <sales@example.com>")
+        );
+        match second.handle_key(Key::Char('c'), 24) {
+            ViewAction::Compose(draft) => {
+                assert!(draft.body.starts_with("From: Sales <sales@example.com>\n"))
+            }
+            _ => panic!("expected a compose draft"),
+        }
+    }
+
     #[test]
     fn archive_sends_move_email_not_move_thread() {
         let (mut view, cmd_rx) = make_view();
@@ -1549,119 +2970,682 @@ mod tests {
     }
 
     #[test]
-    fn delete_sends_move_email_not_move_thread() {
+    fn marked_archive_moves_all_marked_emails() {
         let (mut view, cmd_rx) = make_view();
         view.cursor = 0;
+        view.handle_key(Key::Char(' '), 24);
+        view.handle_key(Key::Char('n'), 24);
+        view.handle_key(Key::Char(' '), 24);
+        assert_eq!(view.marked.len(), 2);
+        assert!(view.marked.contains("email-1"));
+        assert!(view.marked.contains("email-3"));
 
-        view.handle_key(Key::Char('d'), 24);
+        view.handle_key(Key::Char('a'), 24);
 
-        let mut found_move_email = false;
+        let mut moved = Vec::new();
         while let Ok(cmd) = cmd_rx.try_recv() {
-            match cmd {
-                BackendCommand::MoveEmail {
-                    id, to_mailbox_id, ..
-                } => {
-                    assert_eq!(id, "email-1");
-                    assert_eq!(to_mailbox_id, "mbox-trash");
-                    found_move_email = true;
-                }
-                BackendCommand::MoveThread { .. } => {
-                    panic!("delete should send MoveEmail, not MoveThread");
-                }
-                _ => {}
+            if let BackendCommand::MoveEmail { id, .. } = cmd {
+                moved.push(id);
             }
         }
-        assert!(found_move_email, "expected MoveEmail command for delete");
+        assert_eq!(moved, vec!["email-1", "email-3"]);
+        assert_eq!(view.emails.len(), 1);
+        assert_eq!(view.emails[0].id, "email-2");
+        assert_eq!(view.total, Some(1));
+        assert!(view.marked.is_empty());
     }
 
     #[test]
-    fn move_mode_sends_move_email_not_move_thread() {
+    fn failed_batch_move_rolls_back_to_original_positions() {
         let (mut view, cmd_rx) = make_view();
-        view.cursor = 0;
-
-        // Enter move mode
-        view.handle_key(Key::Char('m'), 24);
-        assert!(view.move_mode);
-
-        // Select the second mailbox (Archive) and confirm
-        view.handle_key(Key::Char('n'), 24);
-        view.handle_key(Key::Enter, 24);
+        view.marked.insert("email-1".to_string());
+        view.marked.insert("email-3".to_string());
+        view.handle_key(Key::Char('d'), 24);
 
-        let mut found_move_email = false;
+        let mut op_ids = Vec::new();
         while let Ok(cmd) = cmd_rx.try_recv() {
-            match cmd {
-                BackendCommand::MoveEmail {
-                    id, to_mailbox_id, ..
-                } => {
-                    assert_eq!(id, "email-1");
-                    assert_eq!(to_mailbox_id, "mbox-archive");
-                    found_move_email = true;
-                }
-                BackendCommand::MoveThread { .. } => {
-                    panic!("move should send MoveEmail, not MoveThread");
-                }
-                _ => {}
+            if let BackendCommand::MoveEmail { op_id, id, .. } = cmd {
+                op_ids.push((op_id, id));
             }
         }
-        assert!(found_move_email, "expected MoveEmail command for move");
+        for (op_id, id) in op_ids {
+            view.on_response(&BackendResponse::EmailMutation {
+                op_id,
+                id,
+                action: EmailMutationAction::Move,
+                result: Err("server error".to_string()),
+            });
+        }
+        let ids: Vec<&str> = view.emails.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["email-1", "email-2", "email-3"]);
+        assert_eq!(view.total, Some(3));
     }
 
     #[test]
-    fn expire_sends_destroy_email_not_destroy_thread() {
-        let (cmd_tx, cmd_rx) = mpsc::channel();
-        let mailboxes = make_mailboxes();
-        let mut view = EmailListView::new(
-            cmd_tx,
-            "me@example.com".to_string(),
-            "mbox-trash".to_string(),
-            "Trash".to_string(),
-            50,
-            1,
-            mailboxes,
-            "Archive".to_string(),
-            "Trash".to_string(),
-            None,
-        );
-        view.loading = false;
-
-        let e1 = make_email("email-1", "thread-A");
-        view.emails = vec![e1];
-        view.total = Some(1);
-        view.thread_counts.insert("thread-A".to_string(), (0, 3));
+    fn failed_move_lands_in_sorted_position_after_resort() {
+        let (mut view, cmd_rx) = make_view();
+        let by_subject = |descending| SortOrder {
+            field: SortField::Subject,
+            descending,
+        };
+        view.set_sort(by_subject(false), 24);
         view.cursor = 0;
+        view.handle_key(Key::Char('d'), 24);
+        // The recorded index (0) is stale once the order flips.
+        view.set_sort(by_subject(true), 24);
 
-        view.handle_key(Key::Char('D'), 24);
-
-        let mut found_destroy_email = false;
+        let mut op_ids = Vec::new();
         while let Ok(cmd) = cmd_rx.try_recv() {
-            match cmd {
-                BackendCommand::DestroyEmail { id, .. } => {
-                    assert_eq!(id, "email-1");
-                    found_destroy_email = true;
-                }
-                BackendCommand::DestroyThread { .. } => {
-                    panic!("expire should send DestroyEmail, not DestroyThread");
-                }
-                _ => {}
+            if let BackendCommand::MoveEmail { op_id, id, .. } = cmd {
+                op_ids.push((op_id, id));
             }
         }
-        assert!(
-            found_destroy_email,
-            "expected DestroyEmail command for expire"
-        );
+        assert_eq!(op_ids.len(), 1);
+        let (op_id, id) = op_ids.remove(0);
+        view.on_response(&BackendResponse::EmailMutation {
+            op_id,
+            id,
+            action: EmailMutationAction::Move,
+            result: Err("server error".to_string()),
+        });
+        let ids: Vec<&str> = view.emails.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["email-3", "email-2", "email-1"]);
+        assert_eq!(view.emails[view.cursor].id, "email-1");
     }
 
     #[test]
-    fn status_bar_shows_server_total_not_loaded_count() {
-        let (mut view, _cmd_rx) = make_view();
-        // Simulate: server says 150 total but only 3 loaded
-        view.total = Some(150);
-        assert_eq!(view.emails.len(), 3);
+    fn marked_flag_flags_all_marked_emails() {
+        let (mut view, cmd_rx) = make_view();
+        view.emails[0].keywords.insert("$flagged".to_string(), true);
+        view.marked.insert("email-1".to_string());
+        view.marked.insert("email-2".to_string());
+        view.handle_key(Key::Char('f'), 24);
 
-        // Render and capture status bar content
-        // We can't easily render, so test the logic directly:
-        // The status format uses self.total.unwrap_or(self.emails.len() as u32)
-        let displayed_total = view.total.unwrap_or(view.emails.len() as u32);
+        let mut flagged = Vec::new();
+        while let Ok(cmd) = cmd_rx.try_recv() {
+            if let BackendCommand::SetEmailFlagged { id, flagged: f, .. } = cmd {
+                flagged.push((id, f));
+            }
+        }
+        // email-1 was already flagged, so only email-2 needs an update.
+        assert_eq!(flagged, vec![("email-2".to_string(), true)]);
+        assert!(EmailListView::is_flagged(&view.emails[1]));
+        assert!(view.marked.is_empty());
+    }
+
+    #[test]
+    fn quick_move_key_moves_to_configured_folder() {
+        let (mut view, cmd_rx) = make_view();
+        view.cursor = 2;
+        view.handle_key(Key::Char('x'), 24);
+
+        let mut moved = Vec::new();
+        while let Ok(cmd) = cmd_rx.try_recv() {
+            if let BackendCommand::MoveEmail {
+                id, to_mailbox_id, ..
+            } = cmd
+            {
+                moved.push((id, to_mailbox_id));
+            }
+        }
+        assert_eq!(
+            moved,
+            vec![("email-3".to_string(), "mbox-archive".to_string())]
+        );
+    }
+
+    #[test]
+    fn report_spam_sends_one_report_per_email() {
+        let (mut view, cmd_rx) = make_view();
+        view.cursor = 2;
+        view.handle_key(Key::Char('!'), 24);
+
+        let reports: Vec<_> = cmd_rx
+            .try_iter()
+            .map(|cmd| match cmd {
+                BackendCommand::ReportSpam {
+                    ids,
+                    spam,
+                    to_mailbox_id,
+                    ..
+                } => (ids, spam, to_mailbox_id),
+                _ => panic!("expected only ReportSpam"),
+            })
+            .collect();
+        assert_eq!(
+            reports,
+            vec![(vec!["email-3".to_string()], true, "mbox-junk".to_string())]
+        );
+        assert!(view.emails.iter().all(|e| e.id != "email-3"));
+    }
+
+    #[test]
+    fn quick_move_does_not_override_builtin_keys() {
+        let (mut view, cmd_rx) = make_view();
+        view.cursor = 0;
+        // 'a' is mapped to Trash in the test config but stays "archive".
+        view.handle_key(Key::Char('a'), 24);
+        while let Ok(cmd) = cmd_rx.try_recv() {
+            if let BackendCommand::MoveEmail { to_mailbox_id, .. } = cmd {
+                assert_eq!(to_mailbox_id, "mbox-archive");
+            }
+        }
+    }
+
+    #[test]
+    fn undo_toast_names_the_kind_of_move() {
+        let (mut view, _cmd_rx) = make_view();
+        view.handle_key(Key::Char('a'), 24);
+        assert_eq!(
+            view.last_move.as_ref().map(|m| m.toast.as_str()),
+            Some("Archived - Z to undo")
+        );
+        view.handle_key(Key::Char('x'), 24);
+        assert_eq!(
+            view.last_move.as_ref().map(|m| m.toast.as_str()),
+            Some("Moved to Archive - Z to undo")
+        );
+    }
+
+    #[test]
+    fn undo_moves_archived_email_back_and_reinserts_row() {
+        let (mut view, cmd_rx) = make_view();
+        view.cursor = 1;
+        view.handle_key(Key::Char('a'), 24);
+        assert_eq!(view.emails.len(), 2);
+        assert!(view.last_move.is_some());
+        while cmd_rx.try_recv().is_ok() {}
+
+        view.handle_key(Key::Char('Z'), 24);
+        let ids: Vec<&str> = view.emails.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["email-1", "email-2", "email-3"]);
+        assert_eq!(view.cursor, 1);
+        assert_eq!(view.total, Some(3));
+        assert!(view.last_move.is_none());
+
+        let mut undos = 0;
+        while let Ok(cmd) = cmd_rx.try_recv() {
+            if let BackendCommand::Undo {
+                move_op: Some((_, id)),
+            } = cmd
+            {
+                assert_eq!(id, "email-2");
+                undos += 1;
+            }
+        }
+        assert_eq!(undos, 1, "expected one Undo for the archived email");
+        assert_eq!(view.pending_undos, 1);
+    }
+
+    #[test]
+    fn undo_without_local_move_uses_undo_log_and_refreshes() {
+        let (mut view, cmd_rx) = make_view();
+        view.handle_key(Key::Char('Z'), 24);
+        assert!(matches!(
+            cmd_rx.try_recv(),
+            Ok(BackendCommand::Undo { move_op: None })
+        ));
+
+        view.on_response(&BackendResponse::Undone {
+            result: Ok("flag".to_string()),
+        });
+        assert_eq!(view.status_message.as_deref(), Some("Undone: flag"));
+        assert_eq!(view.pending_undos, 0);
+        assert!(matches!(
+            cmd_rx.try_recv(),
+            Ok(BackendCommand::QueryEmails { .. })
+        ));
+
+        // Replies to undos sent elsewhere (e.g. the CLI) are not ours.
+        assert!(!view.on_response(&BackendResponse::Undone {
+            result: Err("nothing to undo".to_string()),
+        }));
+    }
+
+    #[test]
+    fn idle_sync_refreshes_mailbox_counts_unless_recently_refreshed() {
+        let fetches = |rx: &mpsc::Receiver<BackendCommand>| {
+            rx.try_iter()
+                .filter(|cmd| matches!(cmd, BackendCommand::FetchMailboxes { .. }))
+                .count()
+        };
+
+        let (mut view, cmd_rx) = make_view();
+        assert!(view.trigger_idle_sync());
+        assert_eq!(fetches(&cmd_rx), 0, "off unless sync_all_mailboxes");
+
+        let (mut view, cmd_rx) = make_view();
+        view.mailbox_sync_secs = Some(60);
+        assert!(view.trigger_idle_sync());
+        assert_eq!(fetches(&cmd_rx), 1);
+        view.loading = false;
+        assert!(view.trigger_idle_sync());
+        assert_eq!(fetches(&cmd_rx), 0, "throttled within the interval");
+
+        let (mut view, cmd_rx) = make_view();
+        view.mailbox_sync_secs = Some(60);
+        view.handle_key(Key::Char('g'), 24);
+        view.loading = false;
+        assert!(view.trigger_idle_sync());
+        assert_eq!(fetches(&cmd_rx), 0, "manual refresh counts as recent");
+    }
+
+    #[test]
+    fn refresh_clears_undo_state() {
+        let (mut view, _cmd_rx) = make_view();
+        view.handle_key(Key::Char('d'), 24);
+        assert!(view.last_move.is_some());
+        view.handle_key(Key::Char('g'), 24);
+        assert!(view.last_move.is_none());
+    }
+
+    #[test]
+    fn rebound_archive_key_archives_and_default_key_is_unbound() {
+        let (mut view, cmd_rx) = make_view();
+        view.keymap =
+            Keymap::from_overrides(BTreeMap::from([("archive".to_string(), 'y')])).unwrap();
+        view.cursor = 0;
+
+        view.handle_key(Key::Char('a'), 24);
+        assert_eq!(view.emails.len(), 3, "default key should no longer archive");
+
+        view.handle_key(Key::Char('y'), 24);
+        let mut archived = Vec::new();
+        while let Ok(cmd) = cmd_rx.try_recv() {
+            if let BackendCommand::MoveEmail {
+                id, to_mailbox_id, ..
+            } = cmd
+            {
+                archived.push((id, to_mailbox_id));
+            }
+        }
+        assert_eq!(
+            archived,
+            vec![("email-1".to_string(), "mbox-archive".to_string())]
+        );
+    }
+
+    #[test]
+    fn delete_sends_move_email_not_move_thread() {
+        let (mut view, cmd_rx) = make_view();
+        view.cursor = 0;
+
+        view.handle_key(Key::Char('d'), 24);
+
+        let mut found_move_email = false;
+        while let Ok(cmd) = cmd_rx.try_recv() {
+            match cmd {
+                BackendCommand::MoveEmail {
+                    id, to_mailbox_id, ..
+                } => {
+                    assert_eq!(id, "email-1");
+                    assert_eq!(to_mailbox_id, "mbox-trash");
+                    found_move_email = true;
+                }
+                BackendCommand::MoveThread { .. } => {
+                    panic!("delete should send MoveEmail, not MoveThread");
+                }
+                _ => {}
+            }
+        }
+        assert!(found_move_email, "expected MoveEmail command for delete");
+    }
+
+    #[test]
+    fn snooze_prompt_sends_snooze_email() {
+        let (mut view, cmd_rx) = make_view();
+        view.cursor = 0;
+
+        view.handle_key(Key::Char('z'), 24);
+        assert!(view.snooze_mode);
+        for c in "3d".chars() {
+            view.handle_key(Key::Char(c), 24);
+        }
+        view.handle_key(Key::Enter, 24);
+        assert!(!view.snooze_mode);
+
+        let mut found = false;
+        while let Ok(cmd) = cmd_rx.try_recv() {
+            if let BackendCommand::SnoozeEmail {
+                id,
+                folder,
+                wake_at,
+                ..
+            } = cmd
+            {
+                assert_eq!(id, "email-1");
+                assert_eq!(folder, "Snoozed");
+                assert!(wake_at > snooze::now_secs() + 2 * 24 * 60 * 60);
+                found = true;
+            }
+        }
+        assert!(found, "expected SnoozeEmail command");
+        assert_eq!(view.emails.len(), 2);
+    }
+
+    #[test]
+    fn snooze_rejects_invalid_duration() {
+        let (mut view, cmd_rx) = make_view();
+        view.handle_key(Key::Char('z'), 24);
+        view.handle_key(Key::Char('x'), 24);
+        view.handle_key(Key::Enter, 24);
+
+        while let Ok(cmd) = cmd_rx.try_recv() {
+            assert!(!matches!(cmd, BackendCommand::SnoozeEmail { .. }));
+        }
+        assert_eq!(view.emails.len(), 3);
+        assert!(view
+            .status_message
+            .as_deref()
+            .unwrap_or("")
+            .starts_with("Snooze failed"));
+    }
+
+    #[test]
+    fn search_history_recalls_and_reruns_last_search() {
+        let (mut view, cmd_rx) = make_view();
+        view.on_response(&BackendResponse::SearchHistory(vec![
+            "from:ann".to_string(),
+            "invoice".to_string(),
+        ]));
+
+        view.handle_key(Key::Char('s'), 24);
+        view.handle_key(Key::Up, 24);
+        assert_eq!(view.search_input, "invoice");
+        view.handle_key(Key::Up, 24);
+        view.handle_key(Key::Up, 24);
+        assert_eq!(view.search_input, "from:ann");
+        view.handle_key(Key::Down, 24);
+        assert_eq!(view.search_input, "invoice");
+        view.handle_key(Key::Down, 24);
+        assert_eq!(view.search_input, "");
+        view.handle_key(Key::Up, 24);
+        view.handle_key(Key::Up, 24);
+        view.handle_key(Key::Enter, 24);
+        assert_eq!(view.active_search.as_deref(), Some("from:ann"));
+        assert_eq!(view.search_history, ["from:ann", "invoice", "from:ann"]);
+        assert!(matches!(
+            cmd_rx.try_recv(),
+            Ok(BackendCommand::RecordSearch { query }) if query == "from:ann"
+        ));
+
+        view.active_search = None;
+        view.handle_key(Key::Char('L'), 24);
+        assert_eq!(view.active_search.as_deref(), Some("from:ann"));
+    }
+
+    #[test]
+    fn cross_mailbox_search_named_like_trash_does_not_expire() {
+        let (view, _cmd_rx) = make_view();
+        let mut view = view.with_search("invoice".to_string(), true);
+        view.mailbox_name = view.deleted_folder.clone();
+        assert!(!view.is_in_deleted_folder());
+    }
+
+    #[test]
+    fn cross_mailbox_saved_search_pages_through_search_all() {
+        let (view, cmd_rx) = make_view();
+        let mut view = view.with_search("invoice".to_string(), true);
+        view.request_refresh("test");
+        match cmd_rx.try_recv() {
+            Ok(BackendCommand::SearchAllEmails {
+                search_query,
+                position,
+                ..
+            }) => {
+                assert_eq!(search_query.as_deref(), Some("invoice"));
+                assert_eq!(position, 0);
+            }
+            _ => panic!("expected SearchAllEmails"),
+        }
+
+        // Mailbox pages for the same id are not this view's results.
+        assert!(!view.on_response(&BackendResponse::Emails {
+            mailbox_id: "other".to_string(),
+            emails: Ok(Vec::new()),
+            total: Some(0),
+            position: 0,
+            loaded: 0,
+            thread_counts: HashMap::new(),
+        }));
+        assert!(view.on_response(&BackendResponse::SearchResults {
+            emails: Ok(vec![make_email("hit-1", "thread-X")]),
+            total: Some(1),
+            position: 0,
+        }));
+        assert!(!view.loading);
+        assert_eq!(view.emails.len(), 1);
+        assert_eq!(view.emails[0].id, "hit-1");
+        assert_eq!(view.total, Some(1));
+    }
+
+    #[test]
+    fn parse_date_range_accepts_either_bound() {
+        assert_eq!(
+            parse_date_range("after:2025-01-01 before:2025-02-01"),
+            Ok((
+                Some("2025-01-01".to_string()),
+                Some("2025-02-01".to_string())
+            ))
+        );
+        assert_eq!(
+            parse_date_range(" before:2025-02-01 "),
+            Ok((None, Some("2025-02-01".to_string())))
+        );
+        assert_eq!(parse_date_range(""), Ok((None, None)));
+        assert!(parse_date_range("since:2025-01-01").is_err());
+        assert!(parse_date_range("after:2025-13-01").is_err());
+        assert!(parse_date_range("after:25-1-1").is_err());
+        assert!(parse_date_range("after:2025-02-01 before:2025-01-01").is_err());
+    }
+
+    #[test]
+    fn date_filter_bounds_queries_until_escape() {
+        let (mut view, cmd_rx) = make_view();
+        view.handle_key(Key::Char('w'), 24);
+        assert!(view.date_mode);
+        for c in "after:2025-01-01 before:2025-02-01".chars() {
+            view.handle_key(Key::Char(c), 24);
+        }
+        view.handle_key(Key::Enter, 24);
+        assert!(!view.date_mode);
+        match cmd_rx.try_recv() {
+            Ok(BackendCommand::QueryEmails {
+                received_after,
+                received_before,
+                ..
+            }) => {
+                assert_eq!(received_after.as_deref(), Some("2025-01-01T00:00:00Z"));
+                assert_eq!(received_before.as_deref(), Some("2025-02-01T00:00:00Z"));
+            }
+            _ => panic!("expected QueryEmails"),
+        }
+        assert_eq!(
+            view.date_range_label().as_deref(),
+            Some("after:2025-01-01 before:2025-02-01")
+        );
+
+        view.handle_key(Key::Escape, 24);
+        match cmd_rx.try_recv() {
+            Ok(BackendCommand::QueryEmails {
+                received_after,
+                received_before,
+                ..
+            }) => assert!(received_after.is_none() && received_before.is_none()),
+            _ => panic!("expected QueryEmails"),
+        }
+        assert!(view.date_range_label().is_none());
+    }
+
+    #[test]
+    fn move_mode_typing_filters_mailboxes() {
+        let (mut view, cmd_rx) = make_view();
+        view.cursor = 0;
+        view.handle_key(Key::Char('m'), 24);
+
+        // Arrows and Ctrl-n/Ctrl-p navigate; every letter filters, even
+        // the ones that navigate elsewhere
+        view.handle_key(Key::Down, 24);
+        view.handle_key(Key::Ctrl('n'), 24);
+        view.handle_key(Key::Ctrl('p'), 24);
+        assert_eq!(view.move_cursor, 1);
+        view.handle_key(Key::Char('j'), 24);
+        assert_eq!(view.move_filter, "j");
+        assert_eq!(
+            filter_mailboxes(&view.mailboxes, &view.move_filter)
+                .iter()
+                .map(|m| m.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Junk"]
+        );
+        view.handle_key(Key::Backspace, 24);
+        view.handle_key(Key::Char('T'), 24);
+        view.handle_key(Key::Char('r'), 24);
+        assert_eq!(view.move_filter, "Tr");
+        assert_eq!(view.move_cursor, 0);
+        assert_eq!(
+            filter_mailboxes(&view.mailboxes, &view.move_filter)
+                .iter()
+                .map(|m| m.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Trash"]
+        );
+
+        // Escape clears the filter before it cancels the picker
+        view.handle_key(Key::Escape, 24);
+        assert!(view.move_mode);
+        assert!(view.move_filter.is_empty());
+
+        for c in "snoo".chars() {
+            view.handle_key(Key::Char(c), 24);
+        }
+        view.handle_key(Key::Enter, 24);
+        assert!(!view.move_mode);
+        let moved_to: Vec<String> = cmd_rx
+            .try_iter()
+            .filter_map(|cmd| match cmd {
+                BackendCommand::MoveEmail { to_mailbox_id, .. } => Some(to_mailbox_id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(moved_to, vec!["mbox-snoozed".to_string()]);
+    }
+
+    #[test]
+    fn move_mode_sends_move_email_not_move_thread() {
+        let (mut view, cmd_rx) = make_view();
+        view.cursor = 0;
+
+        // Enter move mode
+        view.handle_key(Key::Char('m'), 24);
+        assert!(view.move_mode);
+
+        // Select the second mailbox (Archive) and confirm
+        view.handle_key(Key::Down, 24);
+        view.handle_key(Key::Enter, 24);
+
+        let mut found_move_email = false;
+        while let Ok(cmd) = cmd_rx.try_recv() {
+            match cmd {
+                BackendCommand::MoveEmail {
+                    id, to_mailbox_id, ..
+                } => {
+                    assert_eq!(id, "email-1");
+                    assert_eq!(to_mailbox_id, "mbox-archive");
+                    found_move_email = true;
+                }
+                BackendCommand::MoveThread { .. } => {
+                    panic!("move should send MoveEmail, not MoveThread");
+                }
+                _ => {}
+            }
+        }
+        assert!(found_move_email, "expected MoveEmail command for move");
+    }
+
+    #[test]
+    fn expire_sends_destroy_email_not_destroy_thread() {
+        let (cmd_tx, cmd_rx) = mpsc::channel();
+        let mailboxes = make_mailboxes();
+        let mut view = EmailListView::new(
+            cmd_tx,
+            &test_view_options(),
+            "me@example.com".to_string(),
+            SharedIdentities::default(),
+            compose::SignatureOptions::default(),
+            "mbox-trash".to_string(),
+            "Trash".to_string(),
+            mailboxes,
+            BodyChoice::shared(false),
+        );
+        view.loading = false;
+
+        let e1 = make_email("email-1", "thread-A");
+        view.emails = vec![e1];
+        view.total = Some(1);
+        view.thread_counts.insert("thread-A".to_string(), (0, 3));
+        view.cursor = 0;
+
+        view.handle_key(Key::Char('D'), 24);
+
+        let mut found_destroy_email = false;
+        while let Ok(cmd) = cmd_rx.try_recv() {
+            match cmd {
+                BackendCommand::DestroyEmail { id, .. } => {
+                    assert_eq!(id, "email-1");
+                    found_destroy_email = true;
+                }
+                BackendCommand::DestroyThread { .. } => {
+                    panic!("expire should send DestroyEmail, not DestroyThread");
+                }
+                _ => {}
+            }
+        }
+        assert!(
+            found_destroy_email,
+            "expected DestroyEmail command for expire"
+        );
+    }
+
+    #[test]
+    fn expire_waits_for_confirmation_when_enabled() {
+        let (mut view, cmd_rx) = make_view();
+        view.mailbox_id = "mbox-trash".to_string();
+        view.mailbox_name = "Trash".to_string();
+        view.confirm_destructive = true;
+        // Marks do not widen expire beyond the selected message.
+        view.marked.insert("email-3".to_string());
+
+        view.handle_key(Key::Char('D'), 24);
+        assert_eq!(view.confirm_expire.as_deref(), Some("email-1"));
+        view.handle_key(Key::Char('n'), 24);
+        assert_eq!(view.confirm_expire, None);
+        assert_eq!(view.emails.len(), 3);
+        assert!(cmd_rx.try_recv().is_err(), "cancel should not destroy");
+
+        view.handle_key(Key::Char('D'), 24);
+        view.handle_key(Key::Char('y'), 24);
+        let mut destroyed = Vec::new();
+        while let Ok(cmd) = cmd_rx.try_recv() {
+            if let BackendCommand::DestroyEmail { id, .. } = cmd {
+                destroyed.push(id);
+            }
+        }
+        assert_eq!(destroyed, vec!["email-1"]);
+        assert_eq!(view.emails.len(), 2);
+    }
+
+    #[test]
+    fn status_bar_shows_server_total_not_loaded_count() {
+        let (mut view, _cmd_rx) = make_view();
+        // Simulate: server says 150 total but only 3 loaded
+        view.total = Some(150);
+        assert_eq!(view.emails.len(), 3);
+
+        // Render and capture status bar content
+        // We can't easily render, so test the logic directly:
+        // The status format uses self.total.unwrap_or(self.emails.len() as u32)
+        let displayed_total = view.total.unwrap_or(view.emails.len() as u32);
         assert_eq!(
             displayed_total, 150,
             "Y should be server total (150), not loaded count (3)"
@@ -1712,6 +3696,150 @@ mod tests {
         email
     }
 
+    #[test]
+    fn unread_only_filter_skips_read_emails() {
+        let (mut view, cmd_rx) = make_view();
+        view.emails = vec![
+            make_email_with_seen("e1", false),
+            make_email_with_seen("e2", true),
+            make_email_with_seen("e3", false),
+            make_email_with_seen("e4", true),
+        ];
+        view.cursor = 0;
+        view.handle_key(Key::Char('U'), 24);
+        assert_eq!(view.visible_indices(), vec![0, 2]);
+
+        view.handle_key(Key::Char('j'), 24);
+        assert_eq!(view.cursor, 2);
+        view.handle_key(Key::Char('k'), 24);
+        assert_eq!(view.cursor, 0);
+        view.handle_key(Key::End, 24);
+        assert_eq!(view.cursor, 2);
+
+        // Filtering is view-local: no re-query is sent.
+        while let Ok(cmd) = cmd_rx.try_recv() {
+            assert!(
+                !matches!(cmd, BackendCommand::QueryEmails { .. }),
+                "toggling the filter should not query the backend"
+            );
+        }
+
+        view.handle_key(Key::Char('U'), 24);
+        assert_eq!(view.visible_indices(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn unread_only_keeps_read_cursor_row_until_cursor_moves() {
+        let (mut view, _cmd_rx) = make_view();
+        view.emails = vec![
+            make_email_with_seen("e1", false),
+            make_email_with_seen("e2", true),
+            make_email_with_seen("e3", false),
+        ];
+        view.cursor = 1;
+        view.handle_key(Key::Char('U'), 24);
+        assert_eq!(view.visible_indices(), vec![0, 1, 2]);
+        view.handle_key(Key::Char('j'), 24);
+        assert_eq!(view.cursor, 2);
+        assert_eq!(view.visible_indices(), vec![0, 2]);
+    }
+
+    #[test]
+    fn next_and_prev_unread_jump_over_read_emails() {
+        let (mut view, _cmd_rx) = make_view();
+        view.emails = vec![
+            make_email_with_seen("e1", false),
+            make_email_with_seen("e2", true),
+            make_email_with_seen("e3", true),
+            make_email_with_seen("e4", false),
+        ];
+        view.cursor = 0;
+
+        view.handle_key(Key::Char('N'), 24);
+        assert_eq!(view.cursor, 3);
+        view.handle_key(Key::Char('N'), 24);
+        assert_eq!(view.cursor, 3, "should stop at the last unread");
+        view.handle_key(Key::Char('P'), 24);
+        assert_eq!(view.cursor, 0);
+        view.handle_key(Key::Char('P'), 24);
+        assert_eq!(view.cursor, 0, "should stop at the first unread");
+    }
+
+    #[test]
+    fn next_unread_loads_more_when_none_loaded_below() {
+        let (mut view, cmd_rx) = make_view();
+        view.emails = vec![
+            make_email_with_seen("e1", true),
+            make_email_with_seen("e2", true),
+        ];
+        view.total = Some(10);
+        view.next_query_position = 2;
+        view.last_loaded_count = 2;
+        view.cursor = 0;
+
+        view.handle_key(Key::Char('N'), 24);
+        assert_eq!(view.cursor, 0);
+        let mut load_more = false;
+        while let Ok(cmd) = cmd_rx.try_recv() {
+            if let BackendCommand::QueryEmails { position, .. } = cmd {
+                assert_eq!(position, 2);
+                load_more = true;
+            }
+        }
+        assert!(load_more, "expected a load-more query");
+    }
+
+    #[test]
+    fn prefetches_next_page_near_the_end_once() {
+        let (mut view, cmd_rx) = make_view();
+        view.emails = (0..10)
+            .map(|i| make_email_with_seen(&format!("e{}", i), true))
+            .collect();
+        view.total = Some(20);
+        view.next_query_position = 10;
+        view.last_loaded_count = 10;
+        view.cursor = 0;
+        let queries = |cmd_rx: &mpsc::Receiver<BackendCommand>| -> Vec<(String, u32)> {
+            cmd_rx
+                .try_iter()
+                .filter_map(|cmd| match cmd {
+                    BackendCommand::QueryEmails {
+                        origin, position, ..
+                    } => Some((origin, position)),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        for _ in 0..6 {
+            press(&mut view, Key::Char('j'), 40);
+        }
+        assert!(queries(&cmd_rx).is_empty(), "cursor at row 7 of 10");
+        press(&mut view, Key::Char('j'), 40);
+        assert_eq!(queries(&cmd_rx), [("email_list.prefetch".to_string(), 10)]);
+        assert!(!view.loading, "prefetch should not block the list");
+
+        // Reaching the end waits on the prefetch instead of asking again.
+        press(&mut view, Key::Char('j'), 40);
+        press(&mut view, Key::Char('j'), 40);
+        press(&mut view, Key::Char('j'), 40);
+        assert!(queries(&cmd_rx).is_empty());
+        assert!(view.loading_more);
+
+        view.on_response(&BackendResponse::Emails {
+            mailbox_id: "mbox-inbox".to_string(),
+            emails: Ok((10..20)
+                .map(|i| make_email_with_seen(&format!("e{}", i), true))
+                .collect()),
+            total: Some(20),
+            position: 10,
+            loaded: 10,
+            thread_counts: HashMap::new(),
+        });
+        assert_eq!(view.emails.len(), 20);
+        assert!(!view.loading && !view.prefetching);
+    }
+
     #[test]
     fn mark_read_advances_to_next_unread_below() {
         let (mut view, _cmd_rx) = make_view();
@@ -1779,6 +3907,189 @@ mod tests {
         assert_eq!(view.cursor, 0, "marking unread should not move cursor");
     }
 
+    #[test]
+    fn mark_thread_read_marks_all_loaded_thread_members() {
+        let (mut view, cmd_rx) = make_view();
+        view.cursor = 0;
+        view.handle_key(Key::Char('M'), 24);
+
+        let mut thread_ids = None;
+        while let Ok(cmd) = cmd_rx.try_recv() {
+            if let BackendCommand::MarkThreadRead {
+                thread_id,
+                email_ids,
+            } = cmd
+            {
+                assert_eq!(thread_id, "thread-A");
+                thread_ids = Some(email_ids);
+            }
+        }
+        assert_eq!(
+            thread_ids,
+            Some(vec!["email-1".to_string(), "email-2".to_string()])
+        );
+        assert!(!EmailListView::is_unread(&view.emails[0]));
+        assert!(!EmailListView::is_unread(&view.emails[1]));
+        assert!(EmailListView::is_unread(&view.emails[2]));
+    }
+
+    #[test]
+    fn mark_thread_read_on_single_message_marks_that_email() {
+        let (mut view, cmd_rx) = make_view();
+        view.cursor = 2;
+        view.handle_key(Key::Char('M'), 24);
+
+        let mut read_ids = Vec::new();
+        while let Ok(cmd) = cmd_rx.try_recv() {
+            match cmd {
+                BackendCommand::MarkEmailRead { id, .. } => read_ids.push(id),
+                BackendCommand::MarkThreadRead { .. } => {
+                    panic!("single-message row should not send MarkThreadRead")
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(read_ids, vec!["email-3".to_string()]);
+    }
+
+    #[test]
+    fn collapsed_threads_list_one_row_and_expand_inline() {
+        let (mut view, _cmd_rx) = make_view();
+        view.emails.push(make_email("email-4", "thread-A"));
+        view.reindex_threads();
+        view.thread_counts.insert("thread-A".to_string(), (0, 3));
+        view.cursor = 0;
+
+        view.handle_key(Key::Char('C'), 24);
+        assert_eq!(view.visible_indices(), vec![0, 2]);
+
+        view.handle_key(Key::Enter, 24);
+        assert_eq!(view.visible_indices(), vec![0, 1, 3, 2]);
+        view.handle_key(Key::Char('n'), 24);
+        assert_eq!(view.cursor, 1);
+        assert!(view.is_thread_member_row(1));
+
+        view.handle_key(Key::Tab, 24);
+        assert_eq!(
+            view.cursor, 0,
+            "collapsing moves to the thread's newest message"
+        );
+        assert_eq!(view.visible_indices(), vec![0, 2]);
+        view.handle_key(Key::Char('n'), 24);
+        assert_eq!(view.cursor, 2);
+    }
+
+    #[test]
+    fn archive_on_collapsed_thread_moves_only_latest_message() {
+        let (mut view, cmd_rx) = make_view();
+        view.cursor = 0;
+        view.handle_key(Key::Char('C'), 24);
+        view.handle_key(Key::Char('a'), 24);
+
+        let mut moved = Vec::new();
+        while let Ok(cmd) = cmd_rx.try_recv() {
+            if let BackendCommand::MoveEmail { id, .. } = cmd {
+                moved.push(id);
+            }
+        }
+        assert_eq!(moved, vec!["email-1".to_string()]);
+        assert_eq!(view.emails[view.cursor].id, "email-2");
+        assert!(!view.is_thread_member_row(view.cursor));
+    }
+
+    #[test]
+    fn preview_pane_fetches_cursor_email_as_cursor_moves() {
+        let (mut view, cmd_rx) = make_view();
+        view.cursor = 0;
+        press(&mut view, Key::Char('v'), 24);
+        press(&mut view, Key::Char('n'), 24);
+        press(&mut view, Key::Char('v'), 24);
+        press(&mut view, Key::Char('n'), 24);
+
+        let requested: Vec<String> = cmd_rx
+            .try_iter()
+            .filter_map(|cmd| match cmd {
+                BackendCommand::GetEmail { id } => Some(id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            requested,
+            vec!["email-1".to_string(), "email-2".to_string()]
+        );
+
+        press(&mut view, Key::Char('v'), 24);
+        let mut email = make_email("email-3", "thread-B");
+        email.preview = Some("first line\nsecond line".to_string());
+        view.on_response(&BackendResponse::EmailBody {
+            id: "email-3".to_string(),
+            result: Box::new(Ok(email)),
+        });
+        assert_eq!(
+            view.preview,
+            Some(Ok(vec![
+                "first line".to_string(),
+                "second line".to_string()
+            ]))
+        );
+    }
+
+    fn make_email_from(id: &str, from: &str, received_at: &str) -> Email {
+        let mut email = make_email(id, id);
+        email.from = Some(vec![crate::jmap::types::EmailAddress {
+            name: Some(from.to_string()),
+            email: None,
+        }]);
+        email.received_at = Some(received_at.to_string());
+        email
+    }
+
+    #[test]
+    fn sort_keys_reorder_and_keep_cursor_on_same_email() {
+        let (mut view, _cmd_rx) = make_view();
+        view.emails = vec![
+            make_email_from("e1", "carol", "2025-01-03"),
+            make_email_from("e2", "alice", "2025-01-02"),
+            make_email_from("e3", "bob", "2025-01-01"),
+        ];
+        view.cursor = 0;
+
+        view.handle_key(Key::Char('o'), 24);
+        let ids: Vec<&str> = view.emails.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["e2", "e3", "e1"]);
+        assert_eq!(view.emails[view.cursor].id, "e1");
+
+        view.handle_key(Key::Char('O'), 24);
+        let ids: Vec<&str> = view.emails.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["e1", "e3", "e2"]);
+    }
+
+    #[test]
+    fn load_more_merges_pages_into_sort_order() {
+        let (mut view, _cmd_rx) = make_view();
+        view.sort = SortOrder {
+            field: SortField::From,
+            descending: false,
+        };
+        view.emails = vec![
+            make_email_from("e1", "bob", "2025-01-03"),
+            make_email_from("e2", "dave", "2025-01-02"),
+        ];
+        view.on_response(&BackendResponse::Emails {
+            mailbox_id: "mbox-inbox".to_string(),
+            emails: Ok(vec![
+                make_email_from("e3", "alice", "2025-01-01"),
+                make_email_from("e4", "carol", "2024-12-31"),
+            ]),
+            total: Some(4),
+            position: 2,
+            loaded: 2,
+            thread_counts: HashMap::new(),
+        });
+        let ids: Vec<&str> = view.emails.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["e3", "e1", "e4", "e2"]);
+    }
+
     #[test]
     fn scrolloff_keeps_context_when_scrolling_down() {
         let (mut view, _cmd_rx) = make_view_with_scrolloff(3);
@@ -1,14 +1,21 @@
-use crate::backend::{BackendCommand, BackendResponse, EmailMutationAction};
-use crate::compose;
+use crate::backend::{self, BackendCommand, BackendResponse, EmailMutationAction};
+use crate::compose::{self, SharedIdentities};
 use crate::jmap::types::{Email, Mailbox};
 use crate::rules;
+use crate::tui::graphics::{self, EncodedImage, ImageProtocol};
 use crate::tui::input::Key;
 use crate::tui::screen::Terminal;
 use crate::tui::views::help::HelpView;
-use crate::tui::views::{View, ViewAction};
+use crate::tui::views::{
+    filter_mailboxes, format_size, move_picker_key, move_picker_status, MovePick, ReloadedSettings,
+    View, ViewAction,
+};
 use regex::Regex;
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::io;
+use std::path::PathBuf;
+use std::rc::Rc;
 use std::sync::mpsc;
 
 #[derive(Clone, Copy, PartialEq)]
@@ -16,18 +23,241 @@ enum LineKind {
     Header,
     Separator,
     Body,
+    /// An entry of the attachment list (`[theme].attachment_fg`).
+    Attachment,
+    /// An entry of the `Links:` list (`[theme].link_fg`).
+    Link,
+    /// `Authentication: SPF: pass, DKIM: ...` summary atop the full headers.
+    AuthResults(AuthSummary),
+    /// A block of `graphics::IMAGE_ROWS` lines reserved for an inline image;
+    /// the first holds its `[img: cid:...]` placeholder.
+    Image,
 }
 
-fn format_size(bytes: u64) -> String {
-    if bytes < 1024 {
-        format!("{} B", bytes)
-    } else if bytes < 1024 * 1024 {
-        format!("{:.1} KB", bytes as f64 / 1024.0)
-    } else {
-        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+/// Verdict colors used when the theme sets no `auth_pass_fg`/`auth_fail_fg`.
+const AUTH_PASS_FG: (u8, u8, u8) = (0x85, 0x99, 0x00);
+const AUTH_FAIL_FG: (u8, u8, u8) = (0xdc, 0x32, 0x2f);
+
+/// A result keyword of an `Authentication-Results` method (RFC 8601).
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum AuthVerdict {
+    Pass,
+    Fail,
+    SoftFail,
+    Neutral,
+    None,
+    Policy,
+    TempError,
+    PermError,
+    Unknown,
+}
+
+impl AuthVerdict {
+    fn parse(result: &str) -> Self {
+        match result.to_ascii_lowercase().as_str() {
+            "pass" => AuthVerdict::Pass,
+            "fail" => AuthVerdict::Fail,
+            "softfail" => AuthVerdict::SoftFail,
+            "neutral" => AuthVerdict::Neutral,
+            "none" => AuthVerdict::None,
+            "policy" => AuthVerdict::Policy,
+            "temperror" => AuthVerdict::TempError,
+            "permerror" => AuthVerdict::PermError,
+            _ => AuthVerdict::Unknown,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            AuthVerdict::Pass => "pass",
+            AuthVerdict::Fail => "fail",
+            AuthVerdict::SoftFail => "softfail",
+            AuthVerdict::Neutral => "neutral",
+            AuthVerdict::None => "none",
+            AuthVerdict::Policy => "policy",
+            AuthVerdict::TempError => "temperror",
+            AuthVerdict::PermError => "permerror",
+            AuthVerdict::Unknown => "unknown",
+        }
+    }
+
+    fn is_failure(self) -> bool {
+        matches!(
+            self,
+            AuthVerdict::Fail | AuthVerdict::SoftFail | AuthVerdict::PermError
+        )
     }
 }
 
+/// SPF, DKIM and DMARC verdicts of one `Authentication-Results` header.
+/// `trusted` is set when its authserv-id belongs to the account; the
+/// verdicts of any other header may have been written by the sender.
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct AuthSummary {
+    trusted: bool,
+    spf: Option<AuthVerdict>,
+    dkim: Option<AuthVerdict>,
+    dmarc: Option<AuthVerdict>,
+}
+
+impl AuthSummary {
+    /// `(label, verdict)` for each method the header reported.
+    fn verdicts(&self) -> impl Iterator<Item = (&'static str, AuthVerdict)> {
+        [
+            ("SPF", self.spf),
+            ("DKIM", self.dkim),
+            ("DMARC", self.dmarc),
+        ]
+        .into_iter()
+        .filter_map(|(label, verdict)| Some((label, verdict?)))
+    }
+}
+
+/// Whether `authserv_id` is one of `trusted` or a subdomain of one.
+fn authserv_id_trusted(authserv_id: &str, trusted: &[String]) -> bool {
+    let id = authserv_id.to_ascii_lowercase();
+    trusted.iter().any(|t| {
+        id == *t
+            || id
+                .strip_suffix(t.as_str())
+                .is_some_and(|sub| sub.ends_with('.'))
+    })
+}
+
+/// Every `Authentication-Results` header value, topmost first.
+fn auth_results_headers(raw_headers: &str) -> Vec<String> {
+    let mut values: Vec<String> = Vec::new();
+    let mut in_value = false;
+    for line in raw_headers.lines() {
+        let line = line.trim_end_matches('\r');
+        if line.is_empty() {
+            break;
+        }
+        if line.starts_with([' ', '\t']) {
+            if in_value {
+                if let Some(v) = values.last_mut() {
+                    v.push(' ');
+                    v.push_str(line.trim());
+                }
+            }
+            continue;
+        }
+        in_value = false;
+        if let Some((name, rest)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("authentication-results") {
+                values.push(rest.trim().to_string());
+                in_value = true;
+            }
+        }
+    }
+    values
+}
+
+/// SPF, DKIM and DMARC results from the topmost `Authentication-Results`
+/// header whose authserv-id is in `trusted_ids`, else from the topmost
+/// header (marked untrusted), with that header's authserv-id. With several
+/// DKIM signatures a passing one wins.
+fn auth_results(raw_headers: &str, trusted_ids: &[String]) -> Option<(AuthSummary, String)> {
+    let headers = auth_results_headers(raw_headers);
+    let value = headers
+        .iter()
+        .find(|v| authserv_id_trusted(authserv_id(v), trusted_ids))
+        .or(headers.first())?;
+    let id = authserv_id(value);
+    let verdict = |method: &str| {
+        // The first `;`-separated field is the authserv-id.
+        let results: Vec<AuthVerdict> = value
+            .split(';')
+            .skip(1)
+            .filter_map(|res| {
+                let (name, result) = res.split_whitespace().next()?.split_once('=')?;
+                let name = name.split('/').next().unwrap_or(name);
+                name.eq_ignore_ascii_case(method)
+                    .then(|| AuthVerdict::parse(result))
+            })
+            .collect();
+        results
+            .iter()
+            .find(|r| **r == AuthVerdict::Pass)
+            .or(results.first())
+            .copied()
+    };
+    let summary = AuthSummary {
+        trusted: authserv_id_trusted(id, trusted_ids),
+        spf: verdict("spf"),
+        dkim: verdict("dkim"),
+        dmarc: verdict("dmarc"),
+    };
+    summary
+        .verdicts()
+        .next()
+        .is_some()
+        .then(|| (summary, id.to_string()))
+}
+
+/// The authserv-id of an `Authentication-Results` value, without any
+/// trailing version number.
+fn authserv_id(value: &str) -> &str {
+    value
+        .split(';')
+        .next()
+        .and_then(|id| id.split_whitespace().next())
+        .unwrap_or("")
+}
+
+/// Display name of the first From address, else the address itself.
+fn sender_name(email: &Email) -> &str {
+    email
+        .from
+        .as_ref()
+        .and_then(|from| from.first())
+        .and_then(|a| {
+            a.name
+                .as_deref()
+                .filter(|n| !n.is_empty())
+                .or(a.email.as_deref())
+        })
+        .unwrap_or("(unknown sender)")
+}
+
+/// The content id of an inline image block's first line.
+fn image_anchor_cid(line: &str) -> Option<&str> {
+    line.strip_prefix("[img: cid:")?.strip_suffix(']')
+}
+
+/// Move every `[img: cid:...]` placeholder that has a drawable image onto
+/// its own line, followed by enough blank lines to hold the image.
+fn insert_image_blocks(
+    lines: Vec<String>,
+    kinds: Vec<LineKind>,
+    images: &HashMap<String, Option<EncodedImage>>,
+) -> (Vec<String>, Vec<LineKind>) {
+    let mut out_lines = Vec::with_capacity(lines.len());
+    let mut out_kinds = Vec::with_capacity(kinds.len());
+    for (i, mut line) in lines.into_iter().enumerate() {
+        let kind = kinds.get(i).copied().unwrap_or(LineKind::Body);
+        let mut blocks = Vec::new();
+        for (cid, image) in images {
+            let placeholder = format!(" \x1b[2m[img: cid:{}]\x1b[22m", cid);
+            if image.is_some() && line.contains(&placeholder) {
+                line = line.replace(&placeholder, "");
+                blocks.push(cid.clone());
+            }
+        }
+        if blocks.is_empty() || !line.trim().is_empty() {
+            out_lines.push(line);
+            out_kinds.push(kind);
+        }
+        blocks.sort();
+        for cid in blocks {
+            out_lines.push(format!("[img: cid:{}]", cid));
+            out_lines.extend(std::iter::repeat_n(String::new(), graphics::IMAGE_ROWS - 1));
+            out_kinds.extend(std::iter::repeat_n(LineKind::Image, graphics::IMAGE_ROWS));
+        }
+    }
+    (out_lines, out_kinds)
+}
+
 /// Word-wrap a line at `max_width` characters, preferring to break at spaces.
 fn wrap_line(s: &str, max_width: usize) -> Vec<&str> {
     if max_width == 0 || s.is_empty() {
@@ -72,32 +302,78 @@ fn wrap_line(s: &str, max_width: usize) -> Vec<&str> {
     result
 }
 
-/// Heuristic check: does this text look like HTML rather than plain text?
-/// Checks for common HTML structural tags anywhere in the content.
-fn looks_like_html(text: &str) -> bool {
-    // Check a reasonable prefix to avoid scanning huge bodies.
-    // Walk back to the nearest char boundary so multi-byte UTF-8 doesn't panic.
-    let mut end = 2000.min(text.len());
-    while end > 0 && !text.is_char_boundary(end) {
-        end -= 1;
-    }
-    let sample = &text[..end];
-    let lower = sample.to_ascii_lowercase();
-    lower.contains("<!doctype")
-        || lower.contains("<html")
-        || lower.contains("<head")
-        || lower.contains("<body")
-        || lower.contains("<style")
-        || lower.contains("<table")
-        || lower.contains("<div")
+/// Replace each run of two or more quoted (`>`) body lines with a single
+/// `[N quoted lines]` placeholder.
+fn collapse_quoted(lines: Vec<String>, kinds: Vec<LineKind>) -> (Vec<String>, Vec<LineKind>) {
+    let is_quote =
+        |line: &str, kind: LineKind| kind == LineKind::Body && line.trim_start().starts_with('>');
+    let mut out_lines = Vec::with_capacity(lines.len());
+    let mut out_kinds = Vec::with_capacity(kinds.len());
+    let mut i = 0;
+    while i < lines.len() {
+        let kind = kinds.get(i).copied().unwrap_or(LineKind::Body);
+        let mut end = i;
+        while end < lines.len()
+            && is_quote(
+                &lines[end],
+                kinds.get(end).copied().unwrap_or(LineKind::Body),
+            )
+        {
+            end += 1;
+        }
+        if end - i >= 2 {
+            out_lines.push(format!("[{} quoted lines]", end - i));
+            out_kinds.push(LineKind::Body);
+            i = end;
+        } else {
+            out_lines.push(lines[i].clone());
+            out_kinds.push(kind);
+            i += 1;
+        }
+    }
+    (out_lines, out_kinds)
+}
+
+/// HTML layout width until the view stack reports the terminal's.
+pub(crate) const DEFAULT_RENDER_WIDTH: usize = 80;
+
+fn is_remote_url(url: &str) -> bool {
+    let lower = url.to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://") || lower.starts_with("//")
+}
+
+/// Remote URLs an HTML body would load on display: `src=` and
+/// `background=` attributes and CSS `url(...)`, deduplicated in order.
+/// Tracking pixels usually have no alt text, so html2text never reports
+/// them; scan the markup instead.
+fn remote_resources(html: &str) -> Vec<String> {
+    let lower = html.to_ascii_lowercase();
+    let mut urls: Vec<String> = Vec::new();
+    for pattern in ["src=", "background=", "url("] {
+        for (pos, _) in lower.match_indices(pattern) {
+            let rest = html[pos + pattern.len()..].trim_start();
+            let (rest, end_chars): (&str, &[char]) = match rest.chars().next() {
+                Some('"') => (&rest[1..], &['"']),
+                Some('\'') => (&rest[1..], &['\'']),
+                _ => (rest, &[' ', '>', ')', '\t', '\n', '\r']),
+            };
+            let url = rest[..rest.find(end_chars).unwrap_or(rest.len())].trim();
+            if is_remote_url(url) && !urls.iter().any(|u| u == url) {
+                urls.push(url.to_string());
+            }
+        }
+    }
+    urls
 }
 
 /// Convert HTML to terminal-formatted text with ANSI escape codes for
 /// bold, underline, color, etc. using html2text's rich rendering mode.
-fn html_to_terminal(html: &str) -> String {
+/// With `block_remote`, remote images get no `[img: url]` note; the view
+/// shows a single banner for them instead.
+fn html_to_terminal(html: &str, width: usize, block_remote: bool) -> String {
     use html2text::render::RichAnnotation;
 
-    html2text::from_read_coloured(html.as_bytes(), 80, |annotations, text| {
+    html2text::from_read_coloured(html.as_bytes(), width, |annotations, text| {
         let mut prefix = String::new();
         let mut suffix = String::new();
         for ann in annotations {
@@ -122,6 +398,7 @@ fn html_to_terminal(html: &str) -> String {
                     // Show link URL after text in dim
                     suffix.push_str(&format!(" \x1b[2m[{}]\x1b[22m", url));
                 }
+                RichAnnotation::Image(src) if block_remote && is_remote_url(src) => {}
                 RichAnnotation::Image(src) => {
                     suffix.push_str(&format!(" \x1b[2m[img: {}]\x1b[22m", src));
                 }
@@ -161,15 +438,59 @@ enum PendingWriteOp {
     Seen { old_seen: bool },
 }
 
+/// What picking an attachment number does: `A` saves it to the download
+/// directory, `O` opens a temporary copy.
+#[derive(Clone, Copy, PartialEq)]
+enum AttachmentPick {
+    Download,
+    Open,
+}
+
 #[derive(Clone)]
 pub struct EmailNavEntry {
     pub id: String,
     pub unread: bool,
 }
 
+/// Which body each message opens on: the account's `prefer_html` unless
+/// `h` flipped it for that message.
+#[derive(Debug, Default)]
+pub struct BodyChoice {
+    prefer_html: bool,
+    toggled: HashSet<String>,
+}
+
+/// One `BodyChoice` per account, shared by all of its views so a message
+/// reopened later still shows the body picked for it.
+pub type SharedBodyChoice = Rc<RefCell<BodyChoice>>;
+
+impl BodyChoice {
+    pub fn shared(prefer_html: bool) -> SharedBodyChoice {
+        Rc::new(RefCell::new(BodyChoice {
+            prefer_html,
+            toggled: HashSet::new(),
+        }))
+    }
+
+    pub fn prefers_html(&self, email_id: &str) -> bool {
+        self.prefer_html != self.toggled.contains(email_id)
+    }
+
+    /// Flip the body shown for `email_id`; returns whether it is now HTML.
+    pub fn toggle(&mut self, email_id: &str) -> bool {
+        if !self.toggled.remove(email_id) {
+            self.toggled.insert(email_id.to_string());
+        }
+        self.prefers_html(email_id)
+    }
+}
+
 pub struct EmailView {
     cmd_tx: mpsc::Sender<BackendCommand>,
     reply_from_address: String,
+    identities: SharedIdentities,
+    signature: compose::SignatureOptions,
+    quote: compose::QuoteStyle,
     can_expire_now: bool,
     email_id: String,
     email: Option<Email>,
@@ -180,28 +501,74 @@ pub struct EmailView {
     error: Option<String>,
     pending_reply_all: Option<bool>,
     pending_forward: bool,
+    /// Message a pending reply or forward was requested for; in a thread,
+    /// the one at the top of the screen rather than the newest.
+    reply_target: Option<String>,
     pending_compose: Option<compose::ComposeDraft>,
     status_message: Option<String>,
     next_write_op_id: u64,
     pending_write_ops: HashMap<u64, PendingWriteOp>,
-    attachment_picking: bool,
+    attachment_picking: Option<AttachmentPick>,
+    /// Attachment name awaiting `AttachmentReadyToOpen`.
+    pending_open: Option<String>,
+    /// `A` then `a`: waiting for `AttachmentsDownloaded`.
+    pending_download_all: bool,
     show_all_headers: bool,
+    /// `T`: fold runs of quoted reply text into placeholders.
+    collapse_quotes: bool,
     raw_headers_cache: HashMap<String, String>,
     raw_headers_loading: bool,
+    /// Raw-source save requests in flight: email id -> file number within a
+    /// thread save (None when saving a single message).
+    pending_source_saves: HashMap<String, Option<usize>>,
     thread_id: Option<String>,
     thread_emails: Vec<Email>,
     mailboxes: Vec<Mailbox>,
     archive_folder: String,
     deleted_folder: String,
+    spam_folder: String,
     move_mode: bool,
     move_cursor: usize,
-    prefer_html: bool,
+    /// Typed move-picker filter; `move_cursor` indexes the matching mailboxes.
+    move_filter: String,
+    /// Plain-text vs HTML body choice per message, shared with the list
+    /// that opened this view.
+    body_choice: SharedBodyChoice,
+    /// Column width HTML bodies are laid out at (see `View::on_render_width`).
+    render_width: usize,
     browser: Option<String>,
+    download_dir: Option<PathBuf>,
+    image_protocol: Option<ImageProtocol>,
+    /// `[ui].block_remote_content`: leave remote images out of HTML bodies.
+    block_remote_content: bool,
+    /// `B`: list the blocked remote URLs under their banner.
+    show_blocked: bool,
+    /// Authserv-ids whose `Authentication-Results` the summary trusts.
+    authserv_ids: Vec<String>,
+    /// `C` in a thread: fold messages read before opening it to one line.
+    collapse_read: bool,
+    /// Inline images by content id; `None` when one could not be drawn.
+    images: HashMap<String, Option<EncodedImage>>,
+    /// `FetchInlineImage` requests in flight: blob id -> content id.
+    pending_images: HashMap<String, String>,
+    /// Kitty image ids whose data the terminal already holds.
+    transmitted_images: RefCell<HashSet<u32>>,
     urls: Vec<String>,
     url_picking: bool,
     url_cursor: usize,
+    /// `o` prompt: typed link number, for messages with more than nine links.
+    link_number_mode: bool,
+    link_number_input: String,
     nav_entries: Vec<EmailNavEntry>,
     nav_cursor: usize,
+    search_mode: bool,
+    search_input: String,
+    /// Active body search (case-insensitive) and the indices into `lines`
+    /// that contain it; `n`/`N` cycle through these while a search is active.
+    search_query: Option<String>,
+    search_matches: Vec<usize>,
+    /// Thread whose `K` mute toggle awaits a `ThreadMuted` reply.
+    pending_mute: Option<String>,
 }
 
 impl EmailView {
@@ -209,6 +576,9 @@ impl EmailView {
     pub fn new(
         cmd_tx: mpsc::Sender<BackendCommand>,
         reply_from_address: String,
+        identities: SharedIdentities,
+        signature: compose::SignatureOptions,
+        quote: compose::QuoteStyle,
         email_id: String,
         nav_entries: Vec<EmailNavEntry>,
         nav_cursor: usize,
@@ -216,11 +586,19 @@ impl EmailView {
         mailboxes: Vec<Mailbox>,
         archive_folder: String,
         deleted_folder: String,
+        spam_folder: String,
         browser: Option<String>,
+        download_dir: Option<PathBuf>,
+        image_protocol: Option<ImageProtocol>,
+        block_remote_content: bool,
+        body_choice: SharedBodyChoice,
     ) -> Self {
         EmailView {
             cmd_tx,
             reply_from_address,
+            identities,
+            signature,
+            quote,
             can_expire_now,
             email_id,
             email: None,
@@ -231,28 +609,52 @@ impl EmailView {
             error: None,
             pending_reply_all: None,
             pending_forward: false,
+            reply_target: None,
             pending_compose: None,
             status_message: None,
             next_write_op_id: 1,
             pending_write_ops: HashMap::new(),
-            attachment_picking: false,
+            attachment_picking: None,
+            pending_open: None,
+            pending_download_all: false,
             show_all_headers: false,
+            collapse_quotes: false,
             raw_headers_cache: HashMap::new(),
             raw_headers_loading: false,
+            pending_source_saves: HashMap::new(),
             thread_id: None,
             thread_emails: Vec::new(),
             mailboxes,
             archive_folder,
             deleted_folder,
+            spam_folder,
             move_mode: false,
             move_cursor: 0,
-            prefer_html: false,
+            move_filter: String::new(),
+            body_choice,
+            render_width: DEFAULT_RENDER_WIDTH,
             browser,
+            download_dir,
+            image_protocol,
+            block_remote_content,
+            show_blocked: false,
+            authserv_ids: Vec::new(),
+            collapse_read: false,
+            images: HashMap::new(),
+            pending_images: HashMap::new(),
+            transmitted_images: RefCell::new(HashSet::new()),
             urls: Vec::new(),
             url_picking: false,
             url_cursor: 0,
+            link_number_mode: false,
+            link_number_input: String::new(),
             nav_entries,
             nav_cursor,
+            search_mode: false,
+            search_input: String::new(),
+            search_query: None,
+            search_matches: Vec::new(),
+            pending_mute: None,
         }
     }
 
@@ -260,13 +662,21 @@ impl EmailView {
     pub fn new_thread(
         cmd_tx: mpsc::Sender<BackendCommand>,
         reply_from_address: String,
+        identities: SharedIdentities,
+        signature: compose::SignatureOptions,
+        quote: compose::QuoteStyle,
         thread_id: String,
         _subject: String,
         can_expire_now: bool,
         mailboxes: Vec<Mailbox>,
         archive_folder: String,
         deleted_folder: String,
+        spam_folder: String,
         browser: Option<String>,
+        download_dir: Option<PathBuf>,
+        image_protocol: Option<ImageProtocol>,
+        block_remote_content: bool,
+        body_choice: SharedBodyChoice,
     ) -> Self {
         let _ = cmd_tx.send(BackendCommand::QueryThreadEmails {
             thread_id: thread_id.clone(),
@@ -274,6 +684,9 @@ impl EmailView {
         EmailView {
             cmd_tx,
             reply_from_address,
+            identities,
+            signature,
+            quote,
             can_expire_now,
             email_id: String::new(),
             email: None,
@@ -284,31 +697,62 @@ impl EmailView {
             error: None,
             pending_reply_all: None,
             pending_forward: false,
+            reply_target: None,
             pending_compose: None,
             status_message: None,
             next_write_op_id: 1,
             pending_write_ops: HashMap::new(),
-            attachment_picking: false,
+            attachment_picking: None,
+            pending_open: None,
+            pending_download_all: false,
             show_all_headers: false,
+            collapse_quotes: false,
             raw_headers_cache: HashMap::new(),
             raw_headers_loading: false,
+            pending_source_saves: HashMap::new(),
             thread_id: Some(thread_id),
             thread_emails: Vec::new(),
             mailboxes,
             archive_folder,
             deleted_folder,
+            spam_folder,
             move_mode: false,
             move_cursor: 0,
-            prefer_html: false,
+            move_filter: String::new(),
+            body_choice,
+            render_width: DEFAULT_RENDER_WIDTH,
             browser,
+            download_dir,
+            image_protocol,
+            block_remote_content,
+            show_blocked: false,
+            authserv_ids: Vec::new(),
+            collapse_read: false,
+            images: HashMap::new(),
+            pending_images: HashMap::new(),
+            transmitted_images: RefCell::new(HashSet::new()),
             urls: Vec::new(),
             url_picking: false,
             url_cursor: 0,
+            link_number_mode: false,
+            link_number_input: String::new(),
             nav_entries: Vec::new(),
             nav_cursor: 0,
+            search_mode: false,
+            search_input: String::new(),
+            search_query: None,
+            search_matches: Vec::new(),
+            pending_mute: None,
         }
     }
 
+    /// Trust `Authentication-Results` headers from these authserv-ids (and
+    /// their subdomains) in the `v` summary.
+    pub fn with_authserv_ids(mut self, ids: Vec<String>) -> Self {
+        self.authserv_ids = ids;
+        self
+    }
+
     fn set_nav_unread(&mut self, id: &str, unread: bool) {
         if let Some(entry) = self.nav_entries.iter_mut().find(|e| e.id == id) {
             entry.unread = unread;
@@ -363,10 +807,27 @@ impl EmailView {
     fn render_headers(
         email: &Email,
         raw_headers: Option<&str>,
+        authserv_ids: &[String],
         lines: &mut Vec<String>,
         kinds: &mut Vec<LineKind>,
     ) {
         if let Some(raw) = raw_headers {
+            if let Some((summary, id)) = auth_results(raw, authserv_ids) {
+                let verdicts: Vec<String> = summary
+                    .verdicts()
+                    .map(|(label, verdict)| format!("{}: {}", label, verdict.as_str()))
+                    .collect();
+                lines.push(if summary.trusted {
+                    format!("Authentication: {}", verdicts.join(", "))
+                } else {
+                    format!(
+                        "Authentication (unverified, from {}): {}",
+                        id,
+                        verdicts.join(", ")
+                    )
+                });
+                kinds.push(LineKind::AuthResults(summary));
+            }
             for line in raw.lines() {
                 lines.push(line.to_string());
                 kinds.push(LineKind::Header);
@@ -401,15 +862,20 @@ impl EmailView {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn render_email(
         email: &Email,
         raw_headers: Option<&str>,
+        authserv_ids: &[String],
         prefer_html: bool,
+        width: usize,
+        block_remote: bool,
+        show_blocked: bool,
     ) -> (Vec<String>, Vec<LineKind>, Vec<String>) {
         let mut lines = Vec::new();
         let mut kinds = Vec::new();
 
-        Self::render_headers(email, raw_headers, &mut lines, &mut kinds);
+        Self::render_headers(email, raw_headers, authserv_ids, &mut lines, &mut kinds);
 
         // Attachments
         if let Some(ref attachments) = email.attachments {
@@ -417,15 +883,18 @@ impl EmailView {
                 lines.push(String::new());
                 kinds.push(LineKind::Body);
                 lines.push(format!("Attachments ({})", attachments.len()));
-                kinds.push(LineKind::Body);
+                kinds.push(LineKind::Attachment);
                 for (i, att) in attachments.iter().enumerate() {
                     let name = att.name.as_deref().unwrap_or("unnamed");
                     let size = att.size.map(format_size).unwrap_or_default();
                     let type_str = att.r#type.as_deref().unwrap_or("application/octet-stream");
                     lines.push(format!("  [{}] {} ({}, {})", i + 1, name, type_str, size));
-                    kinds.push(LineKind::Body);
+                    kinds.push(LineKind::Attachment);
                 }
-                lines.push("  Press 'A' then 1-9 to download/open".to_string());
+                lines.push(
+                    "  Press 'A' then 1-9 to download/open, 'O' then 1-9 to open a temporary copy"
+                        .to_string(),
+                );
                 kinds.push(LineKind::Body);
             }
         }
@@ -435,7 +904,8 @@ impl EmailView {
         kinds.push(LineKind::Body);
 
         // Body
-        let body_text = Self::extract_body(email, prefer_html);
+        let (body_text, blocked) = Self::extract_body(email, prefer_html, width, block_remote);
+        Self::render_blocked(&blocked, show_blocked, &mut lines, &mut kinds);
         for line in body_text.lines() {
             lines.push(line.to_string());
             kinds.push(LineKind::Body);
@@ -450,17 +920,67 @@ impl EmailView {
             kinds.push(LineKind::Header);
             for (i, url) in urls.iter().enumerate() {
                 lines.push(format!("  [{}] {}", i + 1, url));
-                kinds.push(LineKind::Body);
+                kinds.push(LineKind::Link);
             }
         }
 
         (lines, kinds, urls)
     }
 
+    /// One-line notice of blocked remote resources, followed by their URLs
+    /// once `B` has revealed them.
+    fn render_blocked(
+        blocked: &[String],
+        show_blocked: bool,
+        lines: &mut Vec<String>,
+        kinds: &mut Vec<LineKind>,
+    ) {
+        if blocked.is_empty() {
+            return;
+        }
+        lines.push(format!(
+            "\u{26a0} {} remote resource{} blocked (B: {})",
+            blocked.len(),
+            if blocked.len() == 1 { "" } else { "s" },
+            if show_blocked { "hide" } else { "show" }
+        ));
+        kinds.push(LineKind::Header);
+        if show_blocked {
+            for url in blocked {
+                lines.push(format!("  {}", url));
+                kinds.push(LineKind::Link);
+            }
+        }
+        lines.push(String::new());
+        kinds.push(LineKind::Body);
+    }
+
+    /// One-line stand-in for a read thread message: sender, date and the
+    /// first line of the body.
+    fn collapsed_summary(email: &Email, body_text: &str) -> String {
+        let from = sender_name(email);
+        let date = email.received_at.as_deref().unwrap_or("");
+        let first_line = body_text
+            .lines()
+            .map(str::trim)
+            .find(|l| !l.is_empty())
+            .unwrap_or("");
+        format!(
+            "\u{25b8} {} \u{00b7} {} \u{00b7} {}",
+            from, date, first_line
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn render_thread_emails(
         emails: &[Email],
         raw_headers_cache: &HashMap<String, String>,
-        prefer_html: bool,
+        authserv_ids: &[String],
+        body_choice: &BodyChoice,
+        width: usize,
+        block_remote: bool,
+        show_blocked: bool,
+        collapse_read: bool,
     ) -> (Vec<String>, Vec<LineKind>, Vec<String>) {
         let mut lines = Vec::new();
         let mut kinds = Vec::new();
@@ -474,11 +994,30 @@ impl EmailView {
                 lines.push(String::new());
                 kinds.push(LineKind::Body);
             }
+            // The newest message stays open so a fully read thread still
+            // shows where it ended.
+            if collapse_read && email.keywords.contains_key("$seen") && i + 1 < emails.len() {
+                let (body_text, _) = Self::extract_body(
+                    email,
+                    body_choice.prefers_html(&email.id),
+                    width,
+                    block_remote,
+                );
+                lines.push(Self::collapsed_summary(email, &body_text));
+                kinds.push(LineKind::Header);
+                continue;
+            }
             let raw = raw_headers_cache.get(&email.id).map(|s| s.as_str());
-            Self::render_headers(email, raw, &mut lines, &mut kinds);
+            Self::render_headers(email, raw, authserv_ids, &mut lines, &mut kinds);
             lines.push(String::new());
             kinds.push(LineKind::Body);
-            let body_text = Self::extract_body(email, prefer_html);
+            let (body_text, blocked) = Self::extract_body(
+                email,
+                body_choice.prefers_html(&email.id),
+                width,
+                block_remote,
+            );
+            Self::render_blocked(&blocked, show_blocked, &mut lines, &mut kinds);
             for line in body_text.lines() {
                 lines.push(line.to_string());
                 kinds.push(LineKind::Body);
@@ -497,19 +1036,34 @@ impl EmailView {
             kinds.push(LineKind::Header);
             for (i, url) in all_urls.iter().enumerate() {
                 lines.push(format!("  [{}] {}", i + 1, url));
-                kinds.push(LineKind::Body);
+                kinds.push(LineKind::Link);
             }
         }
         (lines, kinds, all_urls)
     }
 
-    fn extract_body(email: &Email, prefer_html: bool) -> String {
+    /// The body text to show, plus the remote resources its HTML would have
+    /// loaded when `block_remote` kept them out.
+    pub(crate) fn extract_body(
+        email: &Email,
+        prefer_html: bool,
+        width: usize,
+        block_remote: bool,
+    ) -> (String, Vec<String>) {
+        let html = |html: &str| {
+            let blocked = if block_remote {
+                remote_resources(html)
+            } else {
+                Vec::new()
+            };
+            (html_to_terminal(html, width, block_remote), blocked)
+        };
         if prefer_html {
             // When user explicitly requests HTML rendering
             if let Some(ref html_body) = email.html_body {
                 for part in html_body {
                     if let Some(value) = email.body_values.get(&part.part_id) {
-                        return html_to_terminal(&value.value);
+                        return html(&value.value);
                     }
                 }
             }
@@ -519,16 +1073,16 @@ impl EmailView {
         if let Some(ref text_body) = email.text_body {
             for part in text_body {
                 if let Some(value) = email.body_values.get(&part.part_id) {
-                    if looks_like_html(&value.value)
+                    if crate::body::looks_like_html(&value.value)
                         || part
                             .r#type
                             .as_deref()
                             .map(|t| t.eq_ignore_ascii_case("text/html"))
                             .unwrap_or(false)
                     {
-                        return html_to_terminal(&value.value);
+                        return html(&value.value);
                     }
-                    return value.value.clone();
+                    return (value.value.clone(), Vec::new());
                 }
             }
         }
@@ -536,20 +1090,34 @@ impl EmailView {
         if let Some(ref html_body) = email.html_body {
             for part in html_body {
                 if let Some(value) = email.body_values.get(&part.part_id) {
-                    return html_to_terminal(&value.value);
+                    return html(&value.value);
                 }
             }
         }
 
-        email.preview.as_deref().unwrap_or("(no body)").to_string()
+        (
+            email.preview.as_deref().unwrap_or("(no body)").to_string(),
+            Vec::new(),
+        )
+    }
+
+    /// The message `r`/`R`/`f`/`F` act on: in a thread, the one at the top
+    /// of the screen, so a reply threads under it rather than the newest.
+    fn reply_target_id(&self) -> String {
+        if self.thread_id.is_some() {
+            if let Some(email) = self.thread_emails.get(self.current_message()) {
+                return email.id.clone();
+            }
+        }
+        self.email_id.clone()
     }
 
     fn request_reply(&mut self, reply_all: bool) {
         self.pending_reply_all = Some(reply_all);
+        let id = self.reply_target_id();
+        self.reply_target = Some(id.clone());
         // Fetch the email with reply headers (messageId, references, replyTo, sentAt)
-        let _ = self.cmd_tx.send(BackendCommand::GetEmailForReply {
-            id: self.email_id.clone(),
-        });
+        let _ = self.cmd_tx.send(BackendCommand::GetEmailForReply { id });
     }
 
     fn next_op_id(&mut self) -> u64 {
@@ -578,7 +1146,39 @@ impl EmailView {
         }
     }
 
-    fn download_attachment(&mut self, index: usize) {
+    /// Request the full raw source of the current message (or every message
+    /// in thread mode) so it can be written to the download dir as `.eml`.
+    fn request_source_save(&mut self) {
+        let targets: Vec<(String, Option<usize>)> = if self.thread_id.is_some() {
+            self.thread_emails
+                .iter()
+                .enumerate()
+                .map(|(i, e)| (e.id.clone(), Some(i + 1)))
+                .collect()
+        } else if self.email.is_some() {
+            vec![(self.email_id.clone(), None)]
+        } else {
+            Vec::new()
+        };
+        if targets.is_empty() {
+            self.status_message = Some("Nothing to save".to_string());
+            return;
+        }
+        let count = targets.len();
+        for (id, number) in targets {
+            let _ = self
+                .cmd_tx
+                .send(BackendCommand::GetEmailRaw { id: id.clone() });
+            self.pending_source_saves.insert(id, number);
+        }
+        self.status_message = Some(if count == 1 {
+            "Saving message source...".to_string()
+        } else {
+            format!("Saving {} messages...", count)
+        });
+    }
+
+    fn download_attachment(&mut self, index: usize, pick: AttachmentPick) {
         let attachment = self
             .email
             .as_ref()
@@ -594,11 +1194,23 @@ impl EmailView {
                     .unwrap_or("application/octet-stream")
                     .to_string();
                 self.status_message = Some(format!("Downloading {}...", name));
-                let _ = self.cmd_tx.send(BackendCommand::DownloadAttachment {
-                    blob_id: blob_id.clone(),
-                    name,
-                    content_type,
-                });
+                let cmd = match pick {
+                    AttachmentPick::Download => BackendCommand::DownloadAttachment {
+                        blob_id: blob_id.clone(),
+                        name,
+                        content_type,
+                        dir: backend::download_dir(self.download_dir.as_deref()),
+                    },
+                    AttachmentPick::Open => {
+                        self.pending_open = Some(name.clone());
+                        BackendCommand::OpenAttachment {
+                            blob_id: blob_id.clone(),
+                            name,
+                            content_type,
+                        }
+                    }
+                };
+                let _ = self.cmd_tx.send(cmd);
             } else {
                 self.status_message = Some("Attachment has no blob ID".to_string());
             }
@@ -607,6 +1219,28 @@ impl EmailView {
         }
     }
 
+    /// Save every attachment of the displayed email (skipping unnamed
+    /// inline images) without opening them.
+    fn download_all_attachments(&mut self) {
+        let Some(email) = self.email.as_ref() else {
+            return;
+        };
+        let attachments = backend::attachment_downloads(email, false);
+        if attachments.is_empty() {
+            self.status_message = Some("No attachments to save".to_string());
+            return;
+        }
+        self.status_message = Some(format!("Downloading {} attachments...", attachments.len()));
+        if let Err(e) = self.cmd_tx.send(BackendCommand::DownloadAttachments {
+            attachments,
+            dir: backend::download_dir(self.download_dir.as_deref()),
+        }) {
+            self.status_message = Some(format!("Download failed: {}", e));
+        } else {
+            self.pending_download_all = true;
+        }
+    }
+
     fn attachment_count(&self) -> usize {
         self.email
             .as_ref()
@@ -615,28 +1249,68 @@ impl EmailView {
             .unwrap_or(0)
     }
 
+    /// The configured browser, else `$BROWSER`, else the platform opener.
+    fn browser_command(&self) -> String {
+        self.browser
+            .clone()
+            .or_else(|| std::env::var("BROWSER").ok())
+            .unwrap_or_else(|| {
+                if cfg!(target_os = "macos") {
+                    "open".to_string()
+                } else {
+                    "xdg-open".to_string()
+                }
+            })
+    }
+
+    /// Build a shell command running `browser` on `target`. The target is
+    /// shell-escaped with single quotes and substituted for `{url}` when the
+    /// command contains it, otherwise appended.
+    fn browser_shell_command(browser: &str, target: &str) -> String {
+        let escaped = format!("'{}'", target.replace('\'', "'\\''"));
+        if browser.contains("{url}") {
+            browser.replace("{url}", &escaped)
+        } else {
+            format!("{} {}", browser, escaped)
+        }
+    }
+
+    /// Launch the opener on a temporary attachment copy. The copy stays until
+    /// tmc exits, since the opener may return before the viewer reads it.
+    fn open_downloaded_attachment(&mut self, name: &str, path: &std::path::Path) {
+        let opener = self.browser_command();
+        let shell_cmd = Self::browser_shell_command(&opener, &path.display().to_string());
+        let cleanup_dir = path.parent().map(|p| p.to_path_buf());
+        crate::log_info!("[Attachment] running: {}", shell_cmd);
+        match std::process::Command::new("sh")
+            .arg("-c")
+            .arg(&shell_cmd)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn()
+        {
+            Ok(mut child) => {
+                self.status_message = Some(format!("Opening {}...", name));
+                std::thread::spawn(move || {
+                    let _ = child.wait();
+                });
+            }
+            Err(e) => {
+                crate::log_error!("[Attachment] failed to run '{}': {}", shell_cmd, e);
+                self.status_message = Some(format!("Failed to open {}: {}", name, e));
+                if let Some(dir) = cleanup_dir {
+                    let _ = std::fs::remove_dir_all(dir);
+                }
+            }
+        }
+    }
+
     fn open_url(&mut self, index: usize) {
         if let Some(url) = self.urls.get(index) {
-            let browser = self
-                .browser
-                .clone()
-                .or_else(|| std::env::var("BROWSER").ok())
-                .unwrap_or_else(|| {
-                    if cfg!(target_os = "macos") {
-                        "open".to_string()
-                    } else {
-                        "xdg-open".to_string()
-                    }
-                });
+            let browser = self.browser_command();
             // Use shell to support complex browser commands with arguments.
-            // The URL is shell-escaped with single quotes.
-            let escaped_url = format!("'{}'", url.replace('\'', "'\\''"));
-            // If the browser command contains {url}, substitute it; otherwise append.
-            let shell_cmd = if browser.contains("{url}") {
-                browser.replace("{url}", &escaped_url)
-            } else {
-                format!("{} {}", browser, escaped_url)
-            };
+            let shell_cmd = Self::browser_shell_command(&browser, url);
             crate::log_info!("[Browser] running: {}", shell_cmd);
             match std::process::Command::new("sh")
                 .arg("-c")
@@ -668,24 +1342,220 @@ impl EmailView {
                 // Empty map = use structured headers
                 &HashMap::new()
             };
-            let (lines, kinds, urls) =
-                Self::render_thread_emails(&self.thread_emails, cache, self.prefer_html);
-            self.lines = lines;
-            self.line_kinds = kinds;
-            self.urls = urls;
+            let rendered = Self::render_thread_emails(
+                &self.thread_emails,
+                cache,
+                &self.authserv_ids,
+                &self.body_choice.borrow(),
+                self.render_width,
+                self.block_remote_content,
+                self.show_blocked,
+                self.collapse_read,
+            );
+            self.set_rendered(rendered);
         } else if let Some(ref email) = self.email {
             let raw = if self.show_all_headers {
                 self.raw_headers_cache.get(&email.id).map(|s| s.as_str())
             } else {
                 None
             };
-            let (lines, kinds, urls) = Self::render_email(email, raw, self.prefer_html);
-            self.lines = lines;
-            self.line_kinds = kinds;
-            self.urls = urls;
+            let rendered = Self::render_email(
+                email,
+                raw,
+                &self.authserv_ids,
+                self.body_choice.borrow().prefers_html(&email.id),
+                self.render_width,
+                self.block_remote_content,
+                self.show_blocked,
+            );
+            self.set_rendered(rendered);
+        }
+    }
+
+    /// Installs freshly rendered lines, folding quoted runs when enabled.
+    fn set_rendered(&mut self, (lines, kinds, urls): (Vec<String>, Vec<LineKind>, Vec<String>)) {
+        let (lines, kinds) = if self.collapse_quotes {
+            collapse_quoted(lines, kinds)
+        } else {
+            (lines, kinds)
+        };
+        let (lines, kinds) = if self.images.is_empty() {
+            (lines, kinds)
+        } else {
+            insert_image_blocks(lines, kinds, &self.images)
+        };
+        self.lines = lines;
+        self.line_kinds = kinds;
+        self.urls = urls;
+        self.refresh_search_matches();
+        self.request_inline_images();
+    }
+
+    /// Fetch the inline images the rendered body shows placeholders for,
+    /// when `[ui].inline_images` found a graphics-capable terminal.
+    fn request_inline_images(&mut self) {
+        let Some(protocol) = self.image_protocol else {
+            return;
+        };
+        if self.thread_id.is_some() {
+            return;
+        }
+        let Some(ref email) = self.email else {
+            return;
+        };
+        let parts = email
+            .attachments
+            .iter()
+            .chain(email.html_body.iter())
+            .flatten();
+        for part in parts {
+            let (Some(cid), Some(blob_id), Some(content_type)) =
+                (&part.cid, &part.blob_id, &part.r#type)
+            else {
+                continue;
+            };
+            let cid = cid.trim_matches(['<', '>']).to_string();
+            let placeholder = format!("[img: cid:{}]", cid);
+            if !content_type.starts_with("image/")
+                || part.size.unwrap_or(0) > graphics::MAX_IMAGE_BYTES as u64
+                || self.images.contains_key(&cid)
+                || self.pending_images.contains_key(blob_id)
+                || !self.lines.iter().any(|l| l.contains(&placeholder))
+            {
+                continue;
+            }
+            let _ = self.cmd_tx.send(BackendCommand::FetchInlineImage {
+                blob_id: blob_id.clone(),
+                content_type: content_type.clone(),
+                protocol,
+            });
+            self.pending_images.insert(blob_id.clone(), cid);
         }
     }
 
+    fn refresh_search_matches(&mut self) {
+        self.search_matches = match self.search_query {
+            Some(ref query) => {
+                let needle = query.to_lowercase();
+                self.lines
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, line)| line.to_lowercase().contains(&needle))
+                    .map(|(i, _)| i)
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+    }
+
+    fn submit_search(&mut self, query: String) {
+        if query.is_empty() {
+            self.search_query = None;
+            self.search_matches.clear();
+            return;
+        }
+        self.search_query = Some(query);
+        self.refresh_search_matches();
+        match self.search_matches.first() {
+            Some(&first) => {
+                self.scroll = first;
+                self.status_message = Some(format!("Match 1/{}", self.search_matches.len()));
+            }
+            None => {
+                self.status_message = Some(format!(
+                    "Not found: {}",
+                    self.search_query.as_deref().unwrap_or_default()
+                ));
+            }
+        }
+    }
+
+    /// Line index of each thread message's first header line; message
+    /// boundaries are the separator bars `render_thread_emails` draws.
+    fn message_starts(&self) -> Vec<usize> {
+        let mut starts = vec![0];
+        for (i, kind) in self.line_kinds.iter().enumerate() {
+            if *kind == LineKind::Separator {
+                let header = self.line_kinds[i..]
+                    .iter()
+                    .position(|k| matches!(k, LineKind::Header | LineKind::AuthResults(_)))
+                    .map_or(i + 1, |offset| i + offset);
+                starts.push(header);
+            }
+        }
+        starts
+    }
+
+    /// Index of the thread message at the top of the screen.
+    fn current_message(&self) -> usize {
+        self.message_starts()
+            .iter()
+            .rposition(|&start| start <= self.scroll)
+            .unwrap_or(0)
+    }
+
+    fn jump_to_message(&mut self, index: usize) {
+        let starts = self.message_starts();
+        if let Some(&start) = starts.get(index) {
+            self.scroll = start.min(self.lines.len().saturating_sub(1));
+            self.status_message = Some(format!("Message {}/{}", index + 1, starts.len()));
+        }
+    }
+
+    /// `]`/`[`: next message, or back to the start of this (then the
+    /// previous) message.
+    fn step_message(&mut self, forward: bool) {
+        let starts = self.message_starts();
+        let current = self.current_message();
+        if forward {
+            if current + 1 < starts.len() {
+                self.jump_to_message(current + 1);
+            } else {
+                self.status_message = Some("Last message in thread".to_string());
+            }
+        } else if self.scroll > starts[current] {
+            self.jump_to_message(current);
+        } else if current > 0 {
+            self.jump_to_message(current - 1);
+        } else {
+            self.status_message = Some("First message in thread".to_string());
+        }
+    }
+
+    /// `U`: the first message that was unread when the thread opened.
+    fn jump_to_first_unread(&mut self) {
+        match self
+            .thread_emails
+            .iter()
+            .position(|e| !e.keywords.contains_key("$seen"))
+        {
+            Some(index) => self.jump_to_message(index),
+            None => self.status_message = Some("No unread messages in thread".to_string()),
+        }
+    }
+
+    /// Scroll to the next (or previous) match relative to the top visible
+    /// line, wrapping around the ends of the message.
+    fn jump_to_match(&mut self, forward: bool) {
+        if self.search_matches.is_empty() {
+            self.status_message = Some("No matches".to_string());
+            return;
+        }
+        let idx = if forward {
+            self.search_matches
+                .iter()
+                .position(|&line| line > self.scroll)
+                .unwrap_or(0)
+        } else {
+            self.search_matches
+                .iter()
+                .rposition(|&line| line < self.scroll)
+                .unwrap_or(self.search_matches.len() - 1)
+        };
+        self.scroll = self.search_matches[idx];
+        self.status_message = Some(format!("Match {}/{}", idx + 1, self.search_matches.len()));
+    }
+
     fn rollback_pending_write(&mut self, op: PendingWriteOp) {
         match op {
             PendingWriteOp::Flag { old_flagged } => self.set_flagged(old_flagged),
@@ -755,6 +1625,57 @@ impl EmailView {
         }
     }
 
+    /// `!`: report the displayed message (or thread) as spam, or as not spam
+    /// when it is in the spam folder, as one `ReportSpam` mutation. Reporting
+    /// a thread as not spam only touches its messages in the spam folder.
+    fn report_spam(&mut self) -> ViewAction {
+        let spam_id = rules::resolve_mailbox_id(&self.spam_folder, &self.mailboxes);
+        let in_spam = |e: &Email| {
+            spam_id
+                .as_ref()
+                .is_some_and(|id| e.mailbox_ids.contains_key(id))
+        };
+        let shown: Vec<&Email> = if self.thread_id.is_some() {
+            self.thread_emails.iter().collect()
+        } else {
+            self.email.iter().collect()
+        };
+        let is_spam = !shown.iter().any(|e| in_spam(e));
+        let mut ids: Vec<String> = shown
+            .iter()
+            .filter(|e| is_spam || in_spam(e))
+            .map(|e| e.id.clone())
+            .collect();
+        if ids.is_empty() && !self.email_id.is_empty() {
+            ids.push(self.email_id.clone());
+        }
+        let (folder, action_label) = if is_spam {
+            (self.spam_folder.clone(), "Report spam")
+        } else {
+            ("inbox".to_string(), "Report not-spam")
+        };
+        let Some(to_mailbox_id) = rules::resolve_mailbox_id(&folder, &self.mailboxes) else {
+            self.status_message = Some(format!(
+                "{} failed: could not resolve folder '{}'",
+                action_label, folder
+            ));
+            return ViewAction::Continue;
+        };
+        let op_id = self.next_op_id();
+        match self.cmd_tx.send(BackendCommand::ReportSpam {
+            op_id,
+            ids,
+            spam: is_spam,
+            to_mailbox_id,
+        }) {
+            Ok(()) => ViewAction::Pop,
+            Err(e) => {
+                self.status_message = Some(format!("{} failed: {}", action_label, e));
+                ViewAction::Continue
+            }
+        }
+    }
+
     /// True if the displayed message currently lives in a mailbox with `role`.
     fn email_in_role(&self, role: &str) -> bool {
         let Some(ref email) = self.email else {
@@ -824,8 +1745,20 @@ impl View for EmailView {
         false
     }
 
+    fn on_render_width(&mut self, width: usize) -> bool {
+        if width == self.render_width {
+            return false;
+        }
+        self.render_width = width;
+        self.rerender_lines();
+        !self.lines.is_empty()
+    }
+
     fn render(&self, term: &mut Terminal) -> io::Result<()> {
         term.clear()?;
+        if self.image_protocol == Some(ImageProtocol::Kitty) && !self.images.is_empty() {
+            term.write_str(graphics::KITTY_CLEAR_PLACEMENTS)?;
+        }
 
         if self.loading {
             term.move_to(1, 1)?;
@@ -926,9 +1859,8 @@ impl View for EmailView {
                 0
             };
 
-            for (i, mailbox) in self
-                .mailboxes
-                .iter()
+            for (i, mailbox) in filter_mailboxes(&self.mailboxes, &self.move_filter)
+                .into_iter()
                 .skip(scroll_offset)
                 .enumerate()
                 .take(max_items)
@@ -950,10 +1882,10 @@ impl View for EmailView {
             // Status bar
             term.move_to(term.rows, 1)?;
             term.set_status()?;
-            let status = format!(
-                " {}/{} | n/p:navigate RET:move Esc:cancel",
-                self.move_cursor + 1,
-                self.mailboxes.len()
+            let status = move_picker_status(
+                self.move_cursor,
+                filter_mailboxes(&self.mailboxes, &self.move_filter).len(),
+                &self.move_filter,
             );
             term.write_truncated(&status, term.cols)?;
             let remaining = (term.cols as usize).saturating_sub(status.len());
@@ -975,6 +1907,7 @@ impl View for EmailView {
             }
 
             let abs_idx = self.scroll + i;
+            let is_match = self.search_matches.binary_search(&abs_idx).is_ok();
             let kind = self
                 .line_kinds
                 .get(abs_idx)
@@ -986,6 +1919,9 @@ impl View for EmailView {
                     let row = 1 + row_idx as u16;
                     term.move_to(row, 1)?;
                     term.set_header()?;
+                    if is_match {
+                        term.set_selection()?;
+                    }
                     term.write_truncated(line, term.cols)?;
                     term.reset_attr()?;
                     row_idx += 1;
@@ -1000,7 +1936,69 @@ impl View for EmailView {
                     term.reset_attr()?;
                     row_idx += 1;
                 }
-                LineKind::Body => {
+                LineKind::AuthResults(summary) if summary.trusted => {
+                    let row = 1 + row_idx as u16;
+                    term.move_to(row, 1)?;
+                    let theme = term.theme();
+                    let pass_fg = theme.auth_pass_fg.unwrap_or(AUTH_PASS_FG);
+                    let fail_fg = theme.auth_fail_fg.unwrap_or(AUTH_FAIL_FG);
+                    term.set_header()?;
+                    term.write_str("Authentication:")?;
+                    term.reset_attr()?;
+                    for (i, (label, verdict)) in summary.verdicts().enumerate() {
+                        term.write_str(if i == 0 { " " } else { ", " })?;
+                        term.write_str(label)?;
+                        term.write_str(": ")?;
+                        if verdict == AuthVerdict::Pass {
+                            term.set_fg(pass_fg)?;
+                        } else if verdict.is_failure() {
+                            term.set_fg(fail_fg)?;
+                        }
+                        term.write_str(verdict.as_str())?;
+                        term.reset_attr()?;
+                    }
+                    row_idx += 1;
+                }
+                // Verdicts from a server the account does not trust are
+                // shown uncolored, labeled unverified.
+                LineKind::AuthResults(_) => {
+                    let row = 1 + row_idx as u16;
+                    term.move_to(row, 1)?;
+                    term.set_header()?;
+                    term.write_truncated(line, term.cols)?;
+                    term.reset_attr()?;
+                    row_idx += 1;
+                }
+                LineKind::Image => {
+                    let row = 1 + row_idx as u16;
+                    term.move_to(row, 1)?;
+                    let image = image_anchor_cid(line)
+                        .and_then(|cid| self.images.get(cid))
+                        .and_then(Option::as_ref);
+                    if let Some(image) = image {
+                        // Only draw blocks that fit whole; a sixel image
+                        // running off the bottom would scroll the screen.
+                        if row_idx + graphics::IMAGE_ROWS <= visible_rows {
+                            if let EncodedImage::Kitty { id, .. } = image {
+                                if self.transmitted_images.borrow_mut().insert(*id) {
+                                    term.write_str(&graphics::transmit(image))?;
+                                }
+                            }
+                            term.write_str(&graphics::place(image))?;
+                        } else {
+                            term.write_colored(&format!("\x1b[2m{}\x1b[22m", line), term.cols)?;
+                        }
+                    }
+                    row_idx += 1;
+                }
+                LineKind::Body | LineKind::Attachment | LineKind::Link => {
+                    let theme = term.theme();
+                    let color = match kind {
+                        LineKind::Attachment => theme.attachment_fg,
+                        LineKind::Link => theme.link_fg,
+                        _ if line.trim_start().starts_with('>') => theme.quote_fg,
+                        _ => None,
+                    };
                     if line.is_empty() {
                         let row = 1 + row_idx as u16;
                         term.move_to(row, 1)?;
@@ -1012,7 +2010,16 @@ impl View for EmailView {
                             }
                             let row = 1 + row_idx as u16;
                             term.move_to(row, 1)?;
-                            term.write_truncated(segment, term.cols)?;
+                            if let Some(fg) = color {
+                                term.set_fg(fg)?;
+                            }
+                            if is_match {
+                                term.set_selection()?;
+                            }
+                            term.write_colored(segment, term.cols)?;
+                            if is_match || color.is_some() {
+                                term.reset_attr()?;
+                            }
                             row_idx += 1;
                         }
                     }
@@ -1024,19 +2031,37 @@ impl View for EmailView {
         term.move_to(term.rows, 1)?;
         term.set_status()?;
         let total_lines = self.lines.len();
-        let base_status = if self.url_picking {
+        let base_status = if self.link_number_mode {
+            format!(
+                " Open link [1-{}]: {}_ | RET:open Esc:cancel",
+                self.urls.len(),
+                self.link_number_input
+            )
+        } else if self.search_mode {
+            format!(" Find: {}_", self.search_input)
+        } else if self.url_picking {
             format!(
                 " line {}/{} | Open URL [1-{}] n/p:navigate RET:open or any key to cancel",
                 self.scroll + 1,
                 total_lines,
                 self.urls.len()
             )
-        } else if self.attachment_picking {
+        } else if let Some(pick) = self.attachment_picking {
             format!(
-                " line {}/{} | Pick attachment [1-{}] or any key to cancel",
+                " line {}/{} | {} attachment [1-{}]{} or any key to cancel",
                 self.scroll + 1,
                 total_lines,
-                self.attachment_count()
+                if pick == AttachmentPick::Open {
+                    "Open"
+                } else {
+                    "Pick"
+                },
+                self.attachment_count(),
+                if pick == AttachmentPick::Download {
+                    " a:all"
+                } else {
+                    ""
+                }
             )
         } else if self.pending_reply_all.is_some() || self.pending_forward {
             format!(
@@ -1046,20 +2071,37 @@ impl View for EmailView {
             )
         } else {
             let att_hint = if self.attachment_count() > 0 {
-                " A:attach"
+                " A/O:attach"
             } else {
                 ""
             };
             let expire_hint = if self.can_expire_now { " D:expire" } else { "" };
             let url_hint = if !self.urls.is_empty() {
-                " b:links"
+                " b/o:links"
             } else {
                 ""
             };
+            let search_hint = if self.search_query.is_some() {
+                " n/N:match Esc:clear-find"
+            } else {
+                " /:find"
+            };
+            let current = self.current_message();
+            let thread_pos = match self.thread_emails.get(current) {
+                Some(target) if self.thread_id.is_some() => format!(
+                    " | target msg {}/{} ({}) [/]:msg U:unread C:fold-read",
+                    current + 1,
+                    self.thread_emails.len(),
+                    sender_name(target)
+                ),
+                _ => String::new(),
+            };
             format!(
-                " line {}/{} | q:back n/p:unread j/k:scroll r:reply R:reply-all F:forward h:html{}{}{} a:archive d:delete m:move J:spam H:ham S:score ?:help",
+                " line {}/{}{} | q:back n/p:unread j/k:scroll{} r:reply R:reply-all F:forward h:html s:save{}{}{} a:archive d:delete m:move J:spam H:ham S:score ?:help",
                 self.scroll + 1,
                 total_lines,
+                thread_pos,
+                search_hint,
                 att_hint,
                 expire_hint,
                 url_hint
@@ -1082,11 +2124,12 @@ impl View for EmailView {
 
     fn handle_key(&mut self, key: Key, term_rows: u16) -> ViewAction {
         // Attachment picking mode: waiting for digit
-        if self.attachment_picking {
-            self.attachment_picking = false;
+        if let Some(pick) = self.attachment_picking.take() {
             if let Key::Char(c @ '1'..='9') = key {
                 let index = (c as usize) - ('1' as usize);
-                self.download_attachment(index);
+                self.download_attachment(index, pick);
+            } else if key == Key::Char('a') && pick == AttachmentPick::Download {
+                self.download_all_attachments();
             } else {
                 self.status_message = Some("Cancelled".to_string());
             }
@@ -1125,27 +2168,67 @@ impl View for EmailView {
 
         // Move mode: mailbox picker
         if self.move_mode {
+            let picked = match move_picker_key(
+                key,
+                &self.mailboxes,
+                &mut self.move_cursor,
+                &mut self.move_filter,
+            ) {
+                MovePick::Continue => return ViewAction::Continue,
+                MovePick::Cancel => None,
+                MovePick::Chosen(mailbox) => Some(mailbox.id.clone()),
+            };
+            self.move_mode = false;
+            return match picked {
+                Some(target_id) => self.move_to_mailbox_id(target_id),
+                None => ViewAction::Continue,
+            };
+        }
+
+        // Link number prompt: digits until Enter
+        if self.link_number_mode {
             match key {
-                Key::Escape | Key::Char('q') => {
-                    self.move_mode = false;
-                }
-                Key::Char('n') | Key::Char('j') | Key::Down | Key::ScrollDown => {
-                    if !self.mailboxes.is_empty() && self.move_cursor + 1 < self.mailboxes.len() {
-                        self.move_cursor += 1;
+                Key::Enter => {
+                    self.link_number_mode = false;
+                    let input = std::mem::take(&mut self.link_number_input);
+                    match input.parse::<usize>() {
+                        Ok(n) if n >= 1 => self.open_url(n - 1),
+                        _ => self.status_message = Some("Invalid URL number".to_string()),
                     }
                 }
-                Key::Char('p') | Key::Char('k') | Key::Up | Key::ScrollUp => {
-                    if self.move_cursor > 0 {
-                        self.move_cursor -= 1;
-                    }
+                Key::Escape => {
+                    self.link_number_mode = false;
+                    self.link_number_input.clear();
+                    self.status_message = Some("Cancelled".to_string());
+                }
+                Key::Backspace => {
+                    self.link_number_input.pop();
                 }
+                Key::Char(c) if c.is_ascii_digit() => {
+                    self.link_number_input.push(c);
+                }
+                _ => {}
+            }
+            return ViewAction::Continue;
+        }
+
+        // Search mode: capture the find query
+        if self.search_mode {
+            match key {
                 Key::Enter => {
-                    if let Some(target_id) =
-                        self.mailboxes.get(self.move_cursor).map(|m| m.id.clone())
-                    {
-                        self.move_mode = false;
-                        return self.move_to_mailbox_id(target_id);
-                    }
+                    self.search_mode = false;
+                    let query = std::mem::take(&mut self.search_input);
+                    self.submit_search(query);
+                }
+                Key::Escape => {
+                    self.search_mode = false;
+                    self.search_input.clear();
+                }
+                Key::Backspace => {
+                    self.search_input.pop();
+                }
+                Key::Char(c) => {
+                    self.search_input.push(c);
                 }
                 _ => {}
             }
@@ -1155,6 +2238,50 @@ impl View for EmailView {
         let page = (term_rows as usize).saturating_sub(1);
         match key {
             Key::Char('q') => ViewAction::Pop,
+            Key::Char('/') => {
+                self.search_mode = true;
+                self.search_input.clear();
+                ViewAction::Continue
+            }
+            Key::Char('n') if self.search_query.is_some() => {
+                self.jump_to_match(true);
+                ViewAction::Continue
+            }
+            Key::Char('N') if self.search_query.is_some() => {
+                self.jump_to_match(false);
+                ViewAction::Continue
+            }
+            Key::Escape => {
+                if self.search_query.take().is_some() {
+                    self.search_matches.clear();
+                    self.status_message = None;
+                }
+                ViewAction::Continue
+            }
+            Key::Char(']') if self.thread_id.is_some() => {
+                self.step_message(true);
+                ViewAction::Continue
+            }
+            Key::Char('[') if self.thread_id.is_some() => {
+                self.step_message(false);
+                ViewAction::Continue
+            }
+            Key::Char('U') if self.thread_id.is_some() => {
+                self.jump_to_first_unread();
+                ViewAction::Continue
+            }
+            Key::Char('C') if self.thread_id.is_some() => {
+                let current = self.current_message();
+                self.collapse_read = !self.collapse_read;
+                self.rerender_lines();
+                self.scroll = self.message_starts().get(current).copied().unwrap_or(0);
+                self.status_message = Some(if self.collapse_read {
+                    "Read messages collapsed".to_string()
+                } else {
+                    "All messages expanded".to_string()
+                });
+                ViewAction::Continue
+            }
             Key::Char('n') => {
                 if !self.navigate_unread(true) && self.scroll + 1 < self.lines.len() {
                     self.scroll += 1;
@@ -1206,18 +2333,18 @@ impl View for EmailView {
             Key::Char('F') => {
                 // Forward as attachment: fetch the full raw message and embed it
                 // as a message/rfc822 part so the HTML part is preserved.
-                let _ = self.cmd_tx.send(BackendCommand::GetEmailRaw {
-                    id: self.email_id.clone(),
-                });
+                let id = self.reply_target_id();
+                self.reply_target = Some(id.clone());
+                let _ = self.cmd_tx.send(BackendCommand::GetEmailRaw { id });
                 self.status_message = Some("Preparing forward (attachment)...".to_string());
                 ViewAction::Continue
             }
             Key::Char('f') => {
                 // Forward as inline quoted text.
                 self.pending_forward = true;
-                let _ = self.cmd_tx.send(BackendCommand::GetEmailForReply {
-                    id: self.email_id.clone(),
-                });
+                let id = self.reply_target_id();
+                self.reply_target = Some(id.clone());
+                let _ = self.cmd_tx.send(BackendCommand::GetEmailForReply { id });
                 ViewAction::Continue
             }
             Key::Char('*') => {
@@ -1279,6 +2406,7 @@ impl View for EmailView {
                 self.move_to_folder(&target, "Delete")
             }
             Key::Char('J') => self.mark_spam(true),
+            Key::Char('!') => self.report_spam(),
             Key::Char('H') => self.mark_spam(false),
             Key::Char('S') => {
                 let _ = self.cmd_tx.send(BackendCommand::ClassifyMessage {
@@ -1292,23 +2420,41 @@ impl View for EmailView {
                 if !self.mailboxes.is_empty() {
                     self.move_mode = true;
                     self.move_cursor = 0;
+                    self.move_filter.clear();
                 }
                 ViewAction::Continue
             }
-            Key::Char('A') => {
+            Key::Char(c @ ('A' | 'O')) => {
+                let (pick, verb) = if c == 'O' {
+                    (AttachmentPick::Open, "Open")
+                } else {
+                    (AttachmentPick::Download, "Download")
+                };
                 let count = self.attachment_count();
                 if count == 0 {
                     self.status_message = Some("No attachments".to_string());
                 } else if count == 1 {
-                    self.download_attachment(0);
+                    self.download_attachment(0, pick);
                 } else {
-                    self.attachment_picking = true;
-                    self.status_message = Some(format!("Download attachment [1-{}]:", count));
+                    self.attachment_picking = Some(pick);
+                    self.status_message = Some(if pick == AttachmentPick::Download {
+                        format!("{} attachment [1-{}] or a for all:", verb, count)
+                    } else {
+                        format!("{} attachment [1-{}]:", verb, count)
+                    });
                 }
                 ViewAction::Continue
             }
+            Key::Char('s') => {
+                self.request_source_save();
+                ViewAction::Continue
+            }
             Key::Char('c') => {
-                let draft = compose::build_compose_draft(&self.reply_from_address);
+                let draft = compose::build_compose_draft(
+                    &self.reply_from_address,
+                    self.identities.borrow().for_compose(),
+                    &self.signature,
+                );
                 ViewAction::Compose(draft.into())
             }
             Key::Char('v') => {
@@ -1343,9 +2489,43 @@ impl View for EmailView {
                 }
                 ViewAction::Continue
             }
+            Key::Char('K') => {
+                let thread_id = self
+                    .thread_id
+                    .clone()
+                    .or_else(|| self.email.as_ref().and_then(|e| e.thread_id.clone()));
+                if let Some(thread_id) = thread_id {
+                    if let Err(e) = self.cmd_tx.send(BackendCommand::SetThreadMuted {
+                        thread_id: thread_id.clone(),
+                        muted: None,
+                    }) {
+                        self.status_message = Some(format!("Mute failed to send: {}", e));
+                    } else {
+                        self.pending_mute = Some(thread_id);
+                    }
+                }
+                ViewAction::Continue
+            }
+            Key::Char('T') => {
+                self.collapse_quotes = !self.collapse_quotes;
+                self.status_message = Some(if self.collapse_quotes {
+                    "Quoted text collapsed".to_string()
+                } else {
+                    "Quoted text expanded".to_string()
+                });
+                self.rerender_lines();
+                self.scroll = self.scroll.min(self.lines.len().saturating_sub(1));
+                ViewAction::Continue
+            }
             Key::Char('h') => {
-                self.prefer_html = !self.prefer_html;
-                self.status_message = Some(if self.prefer_html {
+                let email_id = if self.thread_id.is_some() && !self.thread_emails.is_empty() {
+                    let index = self.current_message().min(self.thread_emails.len() - 1);
+                    self.thread_emails[index].id.clone()
+                } else {
+                    self.email_id.clone()
+                };
+                let html = self.body_choice.borrow_mut().toggle(&email_id);
+                self.status_message = Some(if html {
                     "Showing HTML body".to_string()
                 } else {
                     "Showing plain text body".to_string()
@@ -1353,6 +2533,19 @@ impl View for EmailView {
                 self.rerender_lines();
                 ViewAction::Continue
             }
+            Key::Char('B') => {
+                if !self.block_remote_content {
+                    self.status_message = Some(
+                        "Remote content is not blocked ([ui].block_remote_content)".to_string(),
+                    );
+                } else if !self.lines.iter().any(|l| l.starts_with('\u{26a0}')) {
+                    self.status_message = Some("No remote resources blocked".to_string());
+                } else {
+                    self.show_blocked = !self.show_blocked;
+                    self.rerender_lines();
+                }
+                ViewAction::Continue
+            }
             Key::Char('b') => {
                 if self.urls.is_empty() {
                     self.status_message = Some("No URLs in this message".to_string());
@@ -1368,6 +2561,15 @@ impl View for EmailView {
                 }
                 ViewAction::Continue
             }
+            Key::Char('o') => {
+                if self.urls.is_empty() {
+                    self.status_message = Some("No URLs in this message".to_string());
+                } else {
+                    self.link_number_mode = true;
+                    self.link_number_input.clear();
+                }
+                ViewAction::Continue
+            }
             Key::Char(c @ '1'..='9') => {
                 let index = (c as usize) - ('1' as usize);
                 if index < self.urls.len() {
@@ -1411,14 +2613,17 @@ impl View for EmailView {
                         } else {
                             &empty
                         };
-                        let (lines, kinds, urls) = Self::render_thread_emails(
+                        let rendered = Self::render_thread_emails(
                             &self.thread_emails,
                             cache,
-                            self.prefer_html,
+                            &self.authserv_ids,
+                            &self.body_choice.borrow(),
+                            self.render_width,
+                            self.block_remote_content,
+                            self.show_blocked,
+                            self.collapse_read,
                         );
-                        self.lines = lines;
-                        self.line_kinds = kinds;
-                        self.urls = urls;
+                        self.set_rendered(rendered);
                         self.error = None;
                         // Mark all unread thread emails as read
                         let unread_ids: Vec<String> = emails
@@ -1456,11 +2661,21 @@ impl View for EmailView {
                         } else {
                             None
                         };
-                        let (lines, kinds, urls) = Self::render_email(email, raw, self.prefer_html);
-                        self.lines = lines;
-                        self.line_kinds = kinds;
-                        self.urls = urls;
+                        if self.email.as_ref().is_none_or(|e| e.id != email.id) {
+                            self.images.clear();
+                            self.pending_images.clear();
+                        }
                         self.email = Some(email.clone());
+                        let rendered = Self::render_email(
+                            email,
+                            raw,
+                            &self.authserv_ids,
+                            self.body_choice.borrow().prefers_html(&email.id),
+                            self.render_width,
+                            self.block_remote_content,
+                            self.show_blocked,
+                        );
+                        self.set_rendered(rendered);
                         self.error = None;
                         self.pending_write_ops.clear();
                     }
@@ -1470,22 +2685,49 @@ impl View for EmailView {
                 }
                 true
             }
-            BackendResponse::EmailForReply { id, result } if *id == self.email_id => {
+            BackendResponse::InlineImage { blob_id, result }
+                if self.pending_images.contains_key(blob_id) =>
+            {
+                let Some(cid) = self.pending_images.remove(blob_id) else {
+                    return false;
+                };
+                let image = result.clone().ok().flatten();
+                let drawable = image.is_some();
+                self.images.insert(cid, image);
+                if drawable {
+                    self.rerender_lines();
+                }
+                drawable
+            }
+            BackendResponse::EmailForReply { id, result }
+                if self.reply_target.as_deref() == Some(id.as_str()) =>
+            {
+                self.reply_target = None;
                 let reply_all = self.pending_reply_all.take();
                 let is_forward = self.pending_forward;
                 self.pending_forward = false;
                 match result.as_ref() {
                     Ok(email) => {
-                        self.email = Some(email.clone());
+                        if *id == self.email_id {
+                            self.email = Some(email.clone());
+                        }
                         if is_forward {
-                            let draft =
-                                compose::build_forward_draft(email, &self.reply_from_address);
+                            let draft = compose::build_forward_draft(
+                                email,
+                                &self.reply_from_address,
+                                self.identities.borrow().for_response(Some(email)),
+                                &self.signature,
+                                &self.quote,
+                            );
                             self.pending_compose = Some(draft.into());
                         } else if let Some(reply_all) = reply_all {
                             let draft = compose::build_reply_draft(
                                 email,
                                 reply_all,
                                 &self.reply_from_address,
+                                self.identities.borrow().for_response(Some(email)),
+                                &self.signature,
+                                &self.quote,
                             );
                             self.pending_compose = Some(draft.into());
                         }
@@ -1496,13 +2738,41 @@ impl View for EmailView {
                 }
                 true
             }
-            BackendResponse::EmailRaw { id, result } if *id == self.email_id => {
+            BackendResponse::EmailRaw { id, result }
+                if self.pending_source_saves.contains_key(id) =>
+            {
+                let number = self.pending_source_saves.remove(id).flatten();
+                self.status_message = Some(match result {
+                    Ok(raw) => match save_message_source(
+                        &backend::download_dir(self.download_dir.as_deref()),
+                        id,
+                        raw,
+                        number,
+                    ) {
+                        Ok(path) => format!("Saved: {}", path.display()),
+                        Err(e) => format!("Save failed: {}", e),
+                    },
+                    Err(e) => format!("Save failed: {}", e),
+                });
+                true
+            }
+            BackendResponse::EmailRaw { id, result }
+                if self.reply_target.as_deref() == Some(id.as_str()) =>
+            {
+                self.reply_target = None;
                 match result {
                     Ok(raw) => {
+                        let email = self
+                            .thread_emails
+                            .iter()
+                            .find(|e| e.id == *id)
+                            .or(self.email.as_ref());
                         let draft = compose::build_forward_attachment_draft(
-                            self.email.as_ref(),
-                            raw.clone().into_bytes(),
+                            email,
+                            raw.clone(),
                             &self.reply_from_address,
+                            self.identities.borrow().for_response(email),
+                            &self.signature,
                         );
                         self.pending_compose = Some(draft);
                         self.status_message = None;
@@ -1526,6 +2796,7 @@ impl View for EmailView {
                             EmailMutationAction::MarkRead => "Mark read",
                             EmailMutationAction::MarkUnread => "Mark unread",
                             EmailMutationAction::SetFlagged(_) => "Flag update",
+                            EmailMutationAction::SetKeyword(_) => "Keyword update",
                             EmailMutationAction::Move => "Move",
                             EmailMutationAction::Destroy => "Expire",
                         };
@@ -1568,6 +2839,35 @@ impl View for EmailView {
                 }
                 true
             }
+            BackendResponse::AttachmentsDownloaded { saved, failed }
+                if self.pending_download_all =>
+            {
+                self.pending_download_all = false;
+                let dir = saved
+                    .first()
+                    .and_then(|p| p.parent())
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_default();
+                self.status_message = Some(match failed.first() {
+                    None => format!("Saved {} attachments to {}", saved.len(), dir),
+                    Some(first) => {
+                        format!("Saved {}, {} failed ({})", saved.len(), failed.len(), first)
+                    }
+                });
+                true
+            }
+            BackendResponse::AttachmentReadyToOpen { name, result }
+                if self.pending_open.as_ref() == Some(name) =>
+            {
+                self.pending_open = None;
+                match result {
+                    Ok(path) => self.open_downloaded_attachment(name, path),
+                    Err(e) => {
+                        self.status_message = Some(format!("Download failed: {}", e));
+                    }
+                }
+                true
+            }
             BackendResponse::EmailRawHeaders { id, result } => {
                 match result {
                     Ok(headers) => {
@@ -1594,6 +2894,17 @@ impl View for EmailView {
                 }
                 true
             }
+            BackendResponse::ThreadMuted { thread_id, result }
+                if self.pending_mute.as_deref() == Some(thread_id) =>
+            {
+                self.pending_mute = None;
+                self.status_message = Some(match result {
+                    Ok(true) => "Thread muted: new replies will be archived".to_string(),
+                    Ok(false) => "Thread unmuted".to_string(),
+                    Err(e) => format!("Mute failed: {}", e),
+                });
+                true
+            }
             BackendResponse::MessageClassified { id, result } if *id == self.email_id => {
                 self.status_message = Some(match result {
                     Ok((score, verdict)) => {
@@ -1610,4 +2921,621 @@ impl View for EmailView {
     fn take_pending_action(&mut self) -> Option<ViewAction> {
         self.pending_compose.take().map(ViewAction::Compose)
     }
+
+    fn on_reload(&mut self, settings: Option<&ReloadedSettings>, message: Option<&str>) {
+        if let Some(settings) = settings {
+            self.archive_folder = settings.archive_folder.clone();
+            self.deleted_folder = settings.deleted_folder.clone();
+            self.spam_folder = settings.spam_folder.clone();
+        }
+        if let Some(message) = message {
+            self.status_message = Some(message.to_string());
+        }
+    }
+}
+
+/// Write a raw message to the download dir as `{message-id}.eml`, prefixed with
+/// a two-digit `number` when saving a whole thread.
+fn save_message_source(
+    dir: &std::path::Path,
+    id: &str,
+    raw: &[u8],
+    number: Option<usize>,
+) -> Result<std::path::PathBuf, String> {
+    let stem = backend::safe_file_name(
+        &message_id_from_raw(&String::from_utf8_lossy(raw)).unwrap_or_else(|| id.to_string()),
+    );
+    let file_name = match number {
+        Some(n) => format!("{:02}-{}.eml", n, stem),
+        None => format!("{}.eml", stem),
+    };
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create download dir: {}", e))?;
+    let path = dir.join(file_name);
+    std::fs::write(&path, raw).map_err(|e| format!("Failed to write file: {}", e))?;
+    Ok(path)
+}
+
+/// Extract the Message-ID header value (without angle brackets) from a raw message.
+fn message_id_from_raw(raw: &str) -> Option<String> {
+    for line in raw.lines() {
+        let line = line.trim_end_matches('\r');
+        if line.is_empty() {
+            break;
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        if name.eq_ignore_ascii_case("message-id") {
+            let value = value.trim().trim_start_matches('<').trim_end_matches('>');
+            if !value.is_empty() {
+                return Some(value.to_string());
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn thread_email(id: &str, seen: bool, body_lines: usize) -> Email {
+        let mut keywords = serde_json::Map::new();
+        if seen {
+            keywords.insert("$seen".to_string(), true.into());
+        }
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "threadId": "thread-1",
+            "subject": format!("Subject {}", id),
+            "keywords": keywords,
+            "mailboxIds": {},
+            "textBody": [{"partId": "1"}],
+            "bodyValues": {"1": {"value": vec!["line"; body_lines].join("\n")}},
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn collapse_quoted_folds_runs_of_two_or_more_body_quotes() {
+        let lines: Vec<String> = [
+            "> header-looking line",
+            "Hi,",
+            "> single quote",
+            "reply",
+            "> a",
+            "  > b",
+            ">> c",
+            "",
+            "> tail 1",
+            "> tail 2",
+        ]
+        .iter()
+        .map(|l| l.to_string())
+        .collect();
+        let mut kinds = vec![LineKind::Body; lines.len()];
+        kinds[0] = LineKind::Header;
+        let (lines, kinds) = collapse_quoted(lines, kinds);
+        assert_eq!(
+            lines,
+            vec![
+                "> header-looking line",
+                "Hi,",
+                "> single quote",
+                "reply",
+                "[3 quoted lines]",
+                "",
+                "[2 quoted lines]",
+            ]
+        );
+        assert!(kinds[0] == LineKind::Header);
+        assert!(kinds[1..].iter().all(|k| *k == LineKind::Body));
+    }
+
+    #[test]
+    fn image_blocks_replace_drawable_placeholders() {
+        let placeholder = |cid: &str| format!(" \x1b[2m[img: cid:{}]\x1b[22m", cid);
+        let lines = vec![
+            "Intro".to_string(),
+            format!("See{}", placeholder("chart")),
+            placeholder("logo"),
+            format!("Missing{}", placeholder("gone")),
+        ];
+        let kinds = vec![LineKind::Body; lines.len()];
+        let images = HashMap::from([
+            (
+                "chart".to_string(),
+                Some(EncodedImage::Sixel("sixel".to_string())),
+            ),
+            (
+                "logo".to_string(),
+                Some(EncodedImage::Sixel("sixel".to_string())),
+            ),
+            ("gone".to_string(), None),
+        ]);
+        let (lines, kinds) = insert_image_blocks(lines, kinds, &images);
+
+        let rows = graphics::IMAGE_ROWS;
+        assert_eq!(lines.len(), 3 + 2 * rows);
+        assert_eq!(lines[0], "Intro");
+        assert_eq!(lines[1], "See");
+        assert_eq!(lines[2], "[img: cid:chart]");
+        assert!(lines[3..2 + rows].iter().all(|l| l.is_empty()));
+        // A line holding only the placeholder gives way to the block.
+        assert_eq!(lines[2 + rows], "[img: cid:logo]");
+        assert_eq!(
+            lines.last(),
+            Some(&format!("Missing{}", placeholder("gone")))
+        );
+        assert_eq!(image_anchor_cid(&lines[2 + rows]), Some("logo"));
+
+        assert!(kinds[..2].iter().all(|k| *k == LineKind::Body));
+        assert!(kinds[2..2 + 2 * rows].iter().all(|k| *k == LineKind::Image));
+        assert!(kinds.last() == Some(&LineKind::Body));
+    }
+
+    #[test]
+    fn thread_keys_jump_between_messages_and_to_first_unread() {
+        let (tx, rx) = mpsc::channel();
+        let mut view = EmailView::new_thread(
+            tx,
+            "me@example.com".to_string(),
+            SharedIdentities::default(),
+            compose::SignatureOptions::default(),
+            compose::QuoteStyle::default(),
+            "thread-1".to_string(),
+            String::new(),
+            false,
+            Vec::new(),
+            "Archive".to_string(),
+            "Trash".to_string(),
+            "Junk".to_string(),
+            None,
+            None,
+            None,
+            true,
+            BodyChoice::shared(false),
+        );
+        view.on_response(&BackendResponse::ThreadEmails {
+            thread_id: "thread-1".to_string(),
+            emails: Ok(vec![
+                thread_email("a", true, 30),
+                thread_email("b", false, 30),
+                thread_email("c", false, 30),
+            ]),
+        });
+        let starts = view.message_starts();
+        assert_eq!(starts.len(), 3);
+        assert!(view.lines[starts[1]].starts_with("Subject: Subject b"));
+
+        view.handle_key(Key::Char(']'), 24);
+        assert_eq!(view.scroll, starts[1]);
+        assert_eq!(view.current_message(), 1);
+        view.handle_key(Key::Char(']'), 24);
+        view.handle_key(Key::Char(']'), 24);
+        assert_eq!(view.scroll, starts[2]);
+        assert_eq!(
+            view.status_message.as_deref(),
+            Some("Last message in thread")
+        );
+
+        view.handle_key(Key::Char('j'), 24);
+        view.handle_key(Key::Char('['), 24);
+        assert_eq!(view.scroll, starts[2]);
+        view.handle_key(Key::Char('['), 24);
+        assert_eq!(view.scroll, starts[1]);
+
+        view.scroll = 0;
+        view.handle_key(Key::Char('U'), 24);
+        assert_eq!(view.scroll, starts[1]);
+        assert_eq!(view.status_message.as_deref(), Some("Message 2/3"));
+
+        // C folds the read first message to one line and keeps the place.
+        let expanded_len = view.lines.len();
+        view.handle_key(Key::Char('C'), 24);
+        let starts = view.message_starts();
+        assert_eq!(starts.len(), 3);
+        assert!(view.lines[0].starts_with("\u{25b8} "));
+        assert!(view.lines[0].ends_with(" \u{00b7} line"));
+        assert_eq!(view.scroll, starts[1]);
+        assert!(view.lines.len() < expanded_len);
+        view.handle_key(Key::Char('C'), 24);
+        assert_eq!(view.lines.len(), expanded_len);
+
+        // Replies go to the message on screen, not the newest one.
+        view.handle_key(Key::Char('r'), 24);
+        let reply_ids: Vec<String> = rx
+            .try_iter()
+            .filter_map(|cmd| match cmd {
+                BackendCommand::GetEmailForReply { id } => Some(id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(reply_ids, vec!["b".to_string()]);
+        assert_eq!(view.reply_target.as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn thread_not_spam_report_leaves_messages_outside_junk() {
+        let mailbox = |id: &str, role: &str| -> Mailbox {
+            serde_json::from_value(serde_json::json!({
+                "id": id,
+                "name": id,
+                "role": role,
+            }))
+            .unwrap()
+        };
+        let (tx, rx) = mpsc::channel();
+        let mut view = EmailView::new_thread(
+            tx,
+            "me@example.com".to_string(),
+            SharedIdentities::default(),
+            compose::SignatureOptions::default(),
+            compose::QuoteStyle::default(),
+            "thread-1".to_string(),
+            String::new(),
+            false,
+            vec![
+                mailbox("inbox", "inbox"),
+                mailbox("junk", "junk"),
+                mailbox("sent", "sent"),
+            ],
+            "Archive".to_string(),
+            "Trash".to_string(),
+            "junk".to_string(),
+            None,
+            None,
+            None,
+            true,
+            BodyChoice::shared(false),
+        );
+        let in_mailbox = |id: &str, mailbox_id: &str| {
+            let mut email = thread_email(id, true, 3);
+            email.mailbox_ids.insert(mailbox_id.to_string(), true);
+            email
+        };
+        view.on_response(&BackendResponse::ThreadEmails {
+            thread_id: "thread-1".to_string(),
+            emails: Ok(vec![in_mailbox("a", "sent"), in_mailbox("b", "junk")]),
+        });
+
+        assert!(matches!(
+            view.handle_key(Key::Char('!'), 24),
+            ViewAction::Pop
+        ));
+        let reports: Vec<_> = rx
+            .try_iter()
+            .filter_map(|cmd| match cmd {
+                BackendCommand::ReportSpam {
+                    ids,
+                    spam,
+                    to_mailbox_id,
+                    ..
+                } => Some((ids, spam, to_mailbox_id)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            reports,
+            vec![(vec!["b".to_string()], false, "inbox".to_string())]
+        );
+    }
+
+    #[test]
+    fn h_switches_the_body_of_one_message_and_remembers_it() {
+        let email = |id: &str| -> Email {
+            serde_json::from_value(serde_json::json!({
+                "id": id,
+                "threadId": "thread-1",
+                "subject": format!("Subject {}", id),
+                "mailboxIds": {},
+                "textBody": [{"partId": "1"}],
+                "htmlBody": [{"partId": "2"}],
+                "bodyValues": {
+                    "1": {"value": format!("plain {}", id)},
+                    "2": {"value": format!("<p>rich {}</p>", id)},
+                },
+            }))
+            .unwrap()
+        };
+        let has_line = |view: &EmailView, text: &str| view.lines.iter().any(|l| l == text);
+        let body_choice = BodyChoice::shared(false);
+
+        let (tx, _rx) = mpsc::channel();
+        let mut view = EmailView::new_thread(
+            tx,
+            "me@example.com".to_string(),
+            SharedIdentities::default(),
+            compose::SignatureOptions::default(),
+            compose::QuoteStyle::default(),
+            "thread-1".to_string(),
+            String::new(),
+            false,
+            Vec::new(),
+            "Archive".to_string(),
+            "Trash".to_string(),
+            "Junk".to_string(),
+            None,
+            None,
+            None,
+            true,
+            body_choice.clone(),
+        );
+        view.on_response(&BackendResponse::ThreadEmails {
+            thread_id: "thread-1".to_string(),
+            emails: Ok(vec![email("a"), email("b")]),
+        });
+        view.handle_key(Key::Char('h'), 24);
+        assert_eq!(view.status_message.as_deref(), Some("Showing HTML body"));
+        assert!(has_line(&view, "rich a"));
+        assert!(has_line(&view, "plain b"));
+
+        // Reopening the message keeps its body; other messages are untouched.
+        let open = |id: &str| {
+            let (tx, _rx) = mpsc::channel();
+            let mut view = EmailView::new(
+                tx,
+                "me@example.com".to_string(),
+                SharedIdentities::default(),
+                compose::SignatureOptions::default(),
+                compose::QuoteStyle::default(),
+                id.to_string(),
+                Vec::new(),
+                0,
+                false,
+                Vec::new(),
+                "Archive".to_string(),
+                "Trash".to_string(),
+                "Junk".to_string(),
+                None,
+                None,
+                None,
+                true,
+                body_choice.clone(),
+            );
+            view.on_response(&BackendResponse::EmailBody {
+                id: id.to_string(),
+                result: Box::new(Ok(email(id))),
+            });
+            view
+        };
+        let mut a = open("a");
+        assert!(has_line(&a, "rich a"));
+        assert!(has_line(&open("b"), "plain b"));
+        a.handle_key(Key::Char('h'), 24);
+        assert!(has_line(&a, "plain a"));
+        assert!(!body_choice.borrow().prefers_html("a"));
+    }
+
+    #[test]
+    fn s_saves_every_thread_message_as_eml() {
+        let dir = tempfile::tempdir().unwrap();
+        let (tx, rx) = mpsc::channel();
+        let mut view = EmailView::new_thread(
+            tx,
+            "me@example.com".to_string(),
+            SharedIdentities::default(),
+            compose::SignatureOptions::default(),
+            compose::QuoteStyle::default(),
+            "thread-1".to_string(),
+            String::new(),
+            false,
+            Vec::new(),
+            "Archive".to_string(),
+            "Trash".to_string(),
+            "Junk".to_string(),
+            None,
+            Some(dir.path().to_path_buf()),
+            None,
+            true,
+            BodyChoice::shared(false),
+        );
+        view.handle_key(Key::Char('s'), 24);
+        assert_eq!(view.status_message.as_deref(), Some("Nothing to save"));
+
+        view.on_response(&BackendResponse::ThreadEmails {
+            thread_id: "thread-1".to_string(),
+            emails: Ok(vec![thread_email("a", true, 1), thread_email("b", true, 1)]),
+        });
+        view.handle_key(Key::Char('s'), 24);
+        let requested: Vec<String> = rx
+            .try_iter()
+            .filter_map(|cmd| match cmd {
+                BackendCommand::GetEmailRaw { id } => Some(id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(requested, vec!["a".to_string(), "b".to_string()]);
+
+        view.on_response(&BackendResponse::EmailRaw {
+            id: "a".to_string(),
+            result: Ok(b"Message-ID: <a/1@example.com>\r\n\r\nbody a\r\n".to_vec()),
+        });
+        view.on_response(&BackendResponse::EmailRaw {
+            id: "b".to_string(),
+            result: Ok(b"Subject: no id\r\n\r\nbody b\r\n".to_vec()),
+        });
+        let saved = dir.path().join("02-b.eml");
+        assert_eq!(
+            view.status_message,
+            Some(format!("Saved: {}", saved.display()))
+        );
+        assert_eq!(
+            std::fs::read_to_string(saved).unwrap(),
+            "Subject: no id\r\n\r\nbody b\r\n"
+        );
+        let first = std::fs::read_dir(dir.path())
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .find(|name| name.starts_with("01-"))
+            .unwrap();
+        assert!(
+            first.ends_with(".eml") && first.contains("example.com"),
+            "{}",
+            first
+        );
+    }
+
+    #[test]
+    fn find_steps_through_matches_and_wraps() {
+        let (tx, _rx) = mpsc::channel();
+        let mut view = EmailView::new_thread(
+            tx,
+            "me@example.com".to_string(),
+            SharedIdentities::default(),
+            compose::SignatureOptions::default(),
+            compose::QuoteStyle::default(),
+            "thread-1".to_string(),
+            String::new(),
+            false,
+            Vec::new(),
+            "Archive".to_string(),
+            "Trash".to_string(),
+            "Junk".to_string(),
+            None,
+            None,
+            None,
+            true,
+            BodyChoice::shared(false),
+        );
+        // No thread loaded and no search yet: N must not touch the view.
+        view.handle_key(Key::Char('N'), 24);
+        assert_eq!(view.scroll, 0);
+        assert_eq!(view.status_message, None);
+
+        view.on_response(&BackendResponse::ThreadEmails {
+            thread_id: "thread-1".to_string(),
+            emails: Ok(vec![thread_email("a", true, 3), thread_email("b", true, 3)]),
+        });
+        let find = |view: &mut EmailView, query: &str| {
+            view.handle_key(Key::Char('/'), 24);
+            for c in query.chars() {
+                view.handle_key(Key::Char(c), 24);
+            }
+            view.handle_key(Key::Enter, 24);
+        };
+
+        find(&mut view, "SUBJECT");
+        let matches = view.search_matches.clone();
+        assert_eq!(matches.len(), 2);
+        assert_eq!(view.scroll, matches[0]);
+        assert_eq!(view.status_message.as_deref(), Some("Match 1/2"));
+        view.handle_key(Key::Char('n'), 24);
+        assert_eq!(view.scroll, matches[1]);
+        view.handle_key(Key::Char('n'), 24);
+        assert_eq!(view.scroll, matches[0]);
+        view.handle_key(Key::Char('N'), 24);
+        assert_eq!(view.scroll, matches[1]);
+        assert_eq!(view.status_message.as_deref(), Some("Match 2/2"));
+
+        find(&mut view, "absent");
+        assert_eq!(view.status_message.as_deref(), Some("Not found: absent"));
+        view.handle_key(Key::Char('N'), 24);
+        assert_eq!(view.status_message.as_deref(), Some("No matches"));
+
+        view.handle_key(Key::Escape, 24);
+        assert!(view.search_query.is_none());
+    }
+
+    #[test]
+    fn message_id_from_raw_stops_at_the_body() {
+        assert_eq!(
+            message_id_from_raw("From: a\r\nMessage-Id:  <x@y>\r\n\r\n").as_deref(),
+            Some("x@y")
+        );
+        assert_eq!(
+            message_id_from_raw("From: a\r\n\r\nMessage-ID: <x@y>\r\n"),
+            None
+        );
+        assert_eq!(message_id_from_raw("Message-ID: <>\r\n"), None);
+    }
+
+    #[test]
+    fn auth_results_summarize_the_topmost_trusted_header() {
+        let raw = "Received: from mx.example.com\r\n\
+Authentication-Results: forged.example; spf=pass; dkim=pass; dmarc=pass\r\n\
+Authentication-Results: mx1.example.com;\r\n\
+\tspf=pass smtp.mailfrom=example.org;\r\n\
+\tdkim=fail header.d=other.example; dkim=pass header.d=example.org;\r\n\
+\tdmarc=FAIL (p=reject) header.from=example.org\r\n\
+Authentication-Results: mx.example.com; spf=fail\r\n\
+Subject: hi\r\n";
+        let trusted = vec!["example.com".to_string()];
+        let (summary, id) = auth_results(raw, &trusted).unwrap();
+        assert_eq!(id, "mx1.example.com");
+        assert_eq!(
+            summary,
+            AuthSummary {
+                trusted: true,
+                spf: Some(AuthVerdict::Pass),
+                dkim: Some(AuthVerdict::Pass),
+                dmarc: Some(AuthVerdict::Fail),
+            }
+        );
+        assert!(auth_results("Subject: hi\r\n", &trusted).is_none());
+        assert!(
+            auth_results("Authentication-Results: mx.example.com; none\r\n", &trusted).is_none()
+        );
+    }
+
+    #[test]
+    fn auth_results_from_unknown_servers_are_unverified() {
+        let raw = "Authentication-Results: forged.example; spf=pass; dkim=pass\r\n\
+Authentication-Results: notexample.com; dmarc=pass\r\n\
+Subject: hi\r\n";
+        let trusted = vec!["example.com".to_string()];
+        let (summary, id) = auth_results(raw, &trusted).unwrap();
+        assert_eq!(id, "forged.example");
+        assert!(!summary.trusted);
+        assert_eq!(summary.spf, Some(AuthVerdict::Pass));
+
+        let email = thread_email("a", true, 1);
+        let mut lines = Vec::new();
+        let mut kinds = Vec::new();
+        EmailView::render_headers(&email, Some(raw), &trusted, &mut lines, &mut kinds);
+        assert_eq!(
+            lines[0],
+            "Authentication (unverified, from forged.example): SPF: pass, DKIM: pass"
+        );
+        assert!(kinds[0] == LineKind::AuthResults(summary));
+    }
+
+    #[test]
+    fn remote_resources_finds_pixels_backgrounds_and_css() {
+        let html = r#"<html><body background="https://t.example/bg.png">
+            <p style="background: url(http://t.example/css.gif)">Hi</p>
+            <img src='https://t.example/open.gif' width=1 height=1>
+            <img SRC=https://t.example/open.gif>
+            <img src="cid:logo@example">
+            <a href="https://example.com/">link</a>
+            </body></html>"#;
+        assert_eq!(
+            remote_resources(html),
+            vec![
+                "https://t.example/open.gif",
+                "https://t.example/bg.png",
+                "http://t.example/css.gif",
+            ]
+        );
+    }
+
+    #[test]
+    fn blocked_remote_images_collapse_into_a_banner() {
+        let html = r#"<p>Hello</p><img src="https://t.example/p.gif" alt="pixel">"#;
+        let shown = html_to_terminal(html, 80, false);
+        assert!(shown.contains("[img: https://t.example/p.gif]"));
+        let blocked = html_to_terminal(html, 80, true);
+        assert!(!blocked.contains("t.example"));
+
+        let urls = remote_resources(html);
+        let (mut lines, mut kinds) = (Vec::new(), Vec::new());
+        EmailView::render_blocked(&urls, false, &mut lines, &mut kinds);
+        assert_eq!(lines[0], "\u{26a0} 1 remote resource blocked (B: show)");
+        assert_eq!(lines.len(), 2);
+        let (mut lines, mut kinds) = (Vec::new(), Vec::new());
+        EmailView::render_blocked(&urls, true, &mut lines, &mut kinds);
+        assert_eq!(lines[1], "  https://t.example/p.gif");
+        assert!(kinds[1] == LineKind::Link);
+    }
 }
@@ -1,7 +1,9 @@
 use crate::backend::BackendResponse;
+use crate::keybindings::Keymap;
 use crate::tui::input::Key;
 use crate::tui::screen::Terminal;
 use crate::tui::views::{View, ViewAction};
+use std::collections::BTreeMap;
 use std::io;
 
 pub struct HelpView {
@@ -25,13 +27,18 @@ impl HelpView {
             "  q           Quit".to_string(),
             "  n/j/Down    Next mailbox".to_string(),
             "  p/k/Up      Previous mailbox".to_string(),
-            "  Enter       Open mailbox".to_string(),
+            "  Enter       Open mailbox or [search] saved search".to_string(),
+            "  /           Jump to folder (type to filter, Enter opens first match)".to_string(),
             "  g           Refresh".to_string(),
+            "  G           Refresh unread counts for all accounts".to_string(),
             "  +           Create folder".to_string(),
             "  d           Delete selected folder".to_string(),
             "  u           Mark all mail in selected folder read".to_string(),
+            "  E           Empty the selected Trash/Junk folder (asks first)".to_string(),
             "  x           Preview retention expiry list".to_string(),
-            "  X           Expire retained mail now".to_string(),
+            "  X           Expire retained mail now (asks first)".to_string(),
+            "  r           Saved drafts (resume or delete)".to_string(),
+            "  i           Cycle sender identity for new drafts".to_string(),
             "  PgDn        Page down".to_string(),
             "  PgUp        Page up".to_string(),
             "  Home        Jump to top".to_string(),
@@ -42,7 +49,16 @@ impl HelpView {
             "  q           Back to mailbox list".to_string(),
             "  n/j/Down    Next email".to_string(),
             "  p/k/Up      Previous email".to_string(),
-            "  Enter       Open email / thread reading view".to_string(),
+            "  N           Jump to next unread email".to_string(),
+            "  P           Jump to previous unread email".to_string(),
+            "  U           Toggle showing unread emails only".to_string(),
+            "  C           Toggle collapsing threads into one row".to_string(),
+            "  v           Toggle the message preview pane".to_string(),
+            "  o           Cycle sort field (date, from, subject)".to_string(),
+            "  O           Reverse sort direction".to_string(),
+            "  Tab         Expand/collapse the thread under the cursor".to_string(),
+            "  Enter       Open email / thread reading view (expands a collapsed thread)"
+                .to_string(),
             "  t           Open thread list view (same folder)".to_string(),
             "  T           Open thread list view (all folders)".to_string(),
             "  g           Refresh".to_string(),
@@ -51,17 +67,28 @@ impl HelpView {
             "  e           Dry-run rules on loaded messages".to_string(),
             "  E           Run rules on loaded messages".to_string(),
             "  a           Archive selected email/thread".to_string(),
+            "  z           Snooze selected email for a duration (e.g. 4h, 3d, 1w)".to_string(),
             "  d           Move selected email/thread to deleted folder".to_string(),
-            "  D           Expire selected email/thread now (deleted folder only)".to_string(),
+            "  D           Expire selected/marked email now (deleted folder only; asks first)"
+                .to_string(),
             "  J           Mark spam: train classifier and move to Junk".to_string(),
+            "  !           Report spam ($junk, move to spam folder); in Junk: not spam".to_string(),
             "  H           Mark not-spam (ham): train classifier and move to Inbox".to_string(),
             "  S           Score selected message and tag it (S=spam, ?=unsure)".to_string(),
             "  f           Toggle flagged".to_string(),
             "  u           Toggle read/unread".to_string(),
-            "  m           Move to folder".to_string(),
-            "  s           Search in mailbox".to_string(),
+            "  M           Mark the whole thread read".to_string(),
+            "  K           Mute/unmute thread (new replies are archived)".to_string(),
+            "  m           Move to folder (type to filter, Up/Down or C-n/C-p to pick)".to_string(),
+            "  Z           Undo the last move, flag or read/unread change".to_string(),
+            "  Space       Mark/unmark email for bulk a/d/m/f/u".to_string(),
+            "  s           Search in mailbox (Up/Down recall recent searches)".to_string(),
+            "  L           Re-run the last search".to_string(),
+            "  w           Filter by received date (after:YYYY-MM-DD before:YYYY-MM-DD)"
+                .to_string(),
             "  l           Load more messages".to_string(),
-            "  Escape      Clear search".to_string(),
+            "  i           Cycle sender identity for new drafts".to_string(),
+            "  Escape      Clear marks, then search and date filter".to_string(),
             "  PgDn        Page down".to_string(),
             "  PgUp        Page up".to_string(),
             "  Home        Jump to top".to_string(),
@@ -95,24 +122,89 @@ impl HelpView {
             "  PgUp        Page up".to_string(),
             "  Home        Jump to top".to_string(),
             "  End         Jump to bottom".to_string(),
+            "  ]/[         Next/previous message (thread reading view)".to_string(),
+            "  U           First unread message (thread reading view)".to_string(),
+            "  C           Collapse/expand read messages (thread reading view)".to_string(),
+            "              (in a thread, r/R/f/F act on the message at the top)".to_string(),
             "  r           Reply".to_string(),
             "  R           Reply all".to_string(),
             "  F           Forward as attachment (preserves HTML)".to_string(),
             "  f           Forward as inline quoted text".to_string(),
-            "  A           Download/open attachment".to_string(),
+            "  A           Download/open attachment (then a: save all)".to_string(),
+            "  O           Open a temporary copy of an attachment".to_string(),
             "  h           Toggle HTML vs plain text body".to_string(),
-            "  v           Toggle raw headers (DKIM, Received, etc)".to_string(),
+            "  b           Browse links (1-9 opens directly)".to_string(),
+            "  B           Show/hide blocked remote resource URLs".to_string(),
+            "  o           Open link by number from the Links list".to_string(),
+            "  v           Toggle raw headers with SPF/DKIM/DMARC summary".to_string(),
+            "  T           Collapse/expand quoted reply text".to_string(),
+            "  K           Mute/unmute thread (new replies are archived)".to_string(),
+            "  s           Save message source (.eml) to downloads".to_string(),
+            "  /           Find text in message".to_string(),
+            "  n/N         Next/previous match (while a find is active)".to_string(),
+            "  Escape      Clear find".to_string(),
             "  *           Toggle flagged".to_string(),
             "  u           Toggle read/unread".to_string(),
             "  J           Mark spam: train classifier and move to Junk".to_string(),
+            "  !           Report spam ($junk, move to spam folder); in Junk: not spam".to_string(),
             "  H           Mark not-spam (ham): train classifier and move to Inbox".to_string(),
             "  S           Show this message's spam score and verdict".to_string(),
             "  D           Expire now (deleted folder only)".to_string(),
             String::new(),
+            "Saved Drafts (r in mailbox list)".to_string(),
+            "--------------------------------".to_string(),
+            "  Enter       Resume draft in the editor".to_string(),
+            "  d           Delete draft (asks first)".to_string(),
+            "  g           Refresh".to_string(),
+            "  q/Escape    Back".to_string(),
+            String::new(),
+            "Compose ([ui].internal_editor)".to_string(),
+            "------------------------------".to_string(),
+            "  C-s         Send".to_string(),
+            "  Escape      Discard (press twice if modified; Esc then s saves for later)"
+                .to_string(),
+            "  Esc d       Save to the Drafts folder ([mail].drafts_folder) and close".to_string(),
+            "  Arrows      Move cursor (also C-b/C-f/C-p/C-n)".to_string(),
+            "  Home/End    Start/end of line (also C-a/C-e)".to_string(),
+            "  PgUp/PgDn   Page up/down".to_string(),
+            "  Delete      Delete character under cursor (also C-d)".to_string(),
+            String::new(),
         ];
 
         HelpView { lines, scroll: 0 }
     }
+
+    /// Help with the configured `[mail].quick_move` shortcuts listed after
+    /// the built-in bindings.
+    pub fn with_quick_moves(quick_move: &BTreeMap<char, String>) -> Self {
+        let mut view = Self::new();
+        if !quick_move.is_empty() {
+            view.lines
+                .push("Quick Move (email list, from config)".to_string());
+            view.lines
+                .push("------------------------------------".to_string());
+            for (key, folder) in quick_move {
+                view.lines.push(format!("  {:<12}Move to {}", key, folder));
+            }
+            view.lines.push(String::new());
+        }
+        view
+    }
+
+    /// Append the email-list keys rebound in `[keybindings]`.
+    pub fn with_keymap(mut self, keymap: &Keymap) -> Self {
+        if !keymap.overrides().is_empty() {
+            self.lines
+                .push("Rebound Keys (email list, from config)".to_string());
+            self.lines
+                .push("--------------------------------------".to_string());
+            for (action, key) in keymap.overrides() {
+                self.lines.push(format!("  {:<12}{}", key, action));
+            }
+            self.lines.push(String::new());
+        }
+        self
+    }
 }
 
 impl View for HelpView {
@@ -1,91 +1,157 @@
-use crate::backend::{BackendCommand, BackendResponse, RetentionCandidate};
-use crate::compose;
-use crate::config::RetentionPolicyConfig;
-use crate::jmap::types::Mailbox;
+use crate::backend::{self, BackendCommand, BackendResponse, RetentionCandidate};
+use crate::cache::SessionState;
+use crate::compose::{self, SharedIdentities};
+use crate::config::SavedSearchConfig;
+use crate::drafts::DraftStore;
+use crate::jmap::types::{Email, Mailbox, Quota};
+use crate::rules;
 use crate::tui::input::Key;
 use crate::tui::screen::Terminal;
+use crate::tui::views::drafts_view::DraftsView;
 use crate::tui::views::email_list::{CachedEmailListState, EmailListView};
+use crate::tui::views::email_view::{BodyChoice, SharedBodyChoice};
 use crate::tui::views::help::HelpView;
 use crate::tui::views::retention_preview::RetentionPreviewView;
-use crate::tui::views::{format_system_time, View, ViewAction};
+use crate::tui::views::{
+    filter_mailboxes, format_size, format_system_time, ReloadedSettings, View, ViewAction,
+    ViewOptions,
+};
 use std::collections::{HashMap, HashSet};
 use std::io;
 use std::sync::mpsc;
 use std::time::SystemTime;
 
+/// Background refreshes re-read the storage quota at most this often.
+const QUOTA_REFRESH_SECS: u64 = 15 * 60;
+
 pub struct MailboxListView {
     cmd_tx: mpsc::Sender<BackendCommand>,
     from_address: String,
     reply_from_address: Option<String>,
-    browser: Option<String>,
-    page_size: u32,
-    scrolloff: usize,
+    identities: SharedIdentities,
+    signature: compose::SignatureOptions,
+    options: ViewOptions,
     mailboxes: Vec<Mailbox>,
     cursor: usize,
     loading: bool,
     error: Option<String>,
     account_names: Vec<String>,
     current_account: String,
+    /// `[account.NAME].color` of the current account.
+    account_color: Option<(u8, u8, u8)>,
     pending_click: bool,
-    archive_folder: String,
-    deleted_folder: String,
-    retention_policies: Vec<RetentionPolicyConfig>,
     status_message: Option<String>,
     pending_retention_preview: Option<Vec<RetentionCandidate>>,
     create_mode: bool,
+    /// `/` quick-jump: typed substring narrowing the list, and the cursor
+    /// within the matches.
+    filter_mode: bool,
+    filter_input: String,
+    filter_cursor: usize,
     create_input: String,
     delete_confirm_mode: bool,
+    /// `E`: waiting for the fresh list of the Trash/Junk folder's messages,
+    /// then for a y/n answer before destroying exactly those.
+    empty_counting: bool,
+    empty_confirm: Option<EmptyConfirm>,
+    /// `X` with confirmation on: waiting for the preview that lists the
+    /// messages, then for a y/n answer before destroying exactly those.
+    expire_counting: bool,
+    expire_confirm: Option<Vec<RetentionCandidate>>,
     last_refreshed: Option<SystemTime>,
-    sync_interval_secs: Option<u64>,
     email_cache: HashMap<String, CachedEmailListState>,
+    /// Body choice shared by every message opened from this account, so a
+    /// message toggled with `h` keeps its body when reopened.
+    body_choice: SharedBodyChoice,
+    /// Every mailbox from the last fetch; `mailboxes` is the displayed subset
+    /// when empty folders are hidden. Move targets come from here.
+    all_mailboxes: Vec<Mailbox>,
+    /// Unread inbox email ids already seen, so a notification names mail
+    /// that arrived since the previous sync.
+    known_inbox_unread: HashSet<String>,
+    inbox_unread_count: Option<u32>,
+    /// New inbox mail counted by the last mailbox fetch, announced once the
+    /// inbox page naming the newest message arrives.
+    pending_new_mail: Option<u32>,
+    /// Account storage (used, limit) in bytes, when the server reports quotas.
+    storage_quota: Option<(u64, u64)>,
+    /// When quota was last requested; background refreshes only ask again
+    /// after `QUOTA_REFRESH_SECS`.
+    quota_requested: Option<SystemTime>,
+    /// Inbox unread counts of the other accounts; `None` when the last
+    /// lookup failed.
+    account_unread: HashMap<String, Option<u32>>,
+    /// Saved session, applied when the first mailbox list arrives.
+    pending_session: Option<SessionState>,
+    pending_restore_open: bool,
 }
 
 impl MailboxListView {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         cmd_tx: mpsc::Sender<BackendCommand>,
+        options: ViewOptions,
         from_address: String,
         reply_from_address: Option<String>,
-        browser: Option<String>,
-        page_size: u32,
-        scrolloff: usize,
+        identities: SharedIdentities,
+        signature: compose::SignatureOptions,
         account_names: Vec<String>,
         current_account: String,
-        archive_folder: String,
-        deleted_folder: String,
-        retention_policies: Vec<RetentionPolicyConfig>,
-        sync_interval_secs: Option<u64>,
+        account_color: Option<(u8, u8, u8)>,
+        prefer_html: bool,
     ) -> Self {
+        if options.restore_session {
+            let _ = cmd_tx.send(BackendCommand::LoadSession);
+        }
         MailboxListView {
             cmd_tx,
             from_address,
             reply_from_address,
-            browser,
-            page_size,
-            scrolloff,
+            identities,
+            signature,
+            options,
             mailboxes: Vec::new(),
             cursor: 0,
             loading: true,
             error: None,
             account_names,
             current_account,
+            account_color,
             pending_click: false,
-            archive_folder,
-            deleted_folder,
-            retention_policies,
             status_message: None,
             pending_retention_preview: None,
             create_mode: false,
+            filter_mode: false,
+            filter_input: String::new(),
+            filter_cursor: 0,
             create_input: String::new(),
             delete_confirm_mode: false,
+            empty_counting: false,
+            empty_confirm: None,
+            expire_counting: false,
+            expire_confirm: None,
             last_refreshed: None,
-            sync_interval_secs,
             email_cache: HashMap::new(),
+            body_choice: BodyChoice::shared(prefer_html),
+            all_mailboxes: Vec::new(),
+            known_inbox_unread: HashSet::new(),
+            inbox_unread_count: None,
+            pending_new_mail: None,
+            storage_quota: None,
+            quota_requested: None,
+            account_unread: HashMap::new(),
+            pending_session: None,
+            pending_restore_open: false,
         }
     }
 
+    /// Mailboxes plus saved searches.
+    fn row_count(&self) -> usize {
+        self.mailboxes.len() + self.options.saved_searches.len()
+    }
+
     fn is_cached_emails_fresh(&self, mailbox_id: &str) -> bool {
-        let Some(sync_interval_secs) = self.sync_interval_secs else {
+        let Some(sync_interval_secs) = self.options.sync_interval_secs else {
             return false;
         };
         let Some(cached) = self.email_cache.get(mailbox_id) else {
@@ -97,11 +163,143 @@ impl MailboxListView {
         }
     }
 
+    fn inbox(mailboxes: &[Mailbox]) -> Option<&Mailbox> {
+        mailboxes
+            .iter()
+            .find(|m| m.role.as_deref() == Some("inbox"))
+    }
+
+    /// Note when the inbox unread count went up since the last mailbox
+    /// fetch and query the inbox so the notification can name the newest
+    /// message. Each sync sends at most one notification.
+    fn note_inbox_unread_count(&mut self, mailboxes: &[Mailbox]) {
+        if self.options.notify_command.is_none() {
+            return;
+        }
+        let Some(inbox) = Self::inbox(mailboxes) else {
+            return;
+        };
+        let unread = inbox.unread_emails;
+        if let Some(previous) = self.inbox_unread_count {
+            if unread > previous {
+                let pending = self.pending_new_mail.get_or_insert(0);
+                *pending = pending.saturating_add(unread - previous);
+                let _ = self.cmd_tx.send(BackendCommand::QueryEmails {
+                    origin: "mailbox_list.new_mail".to_string(),
+                    mailbox_id: inbox.id.clone(),
+                    page_size: self.options.page_size,
+                    position: 0,
+                    search_query: None,
+                    received_after: None,
+                    received_before: None,
+                });
+            }
+        }
+        self.inbox_unread_count = Some(unread);
+    }
+
+    /// The (summary, body) to announce for new inbox mail once the first
+    /// inbox page arrives after a count increase. Every inbox page records
+    /// the unread ids it lists, so the newest message not seen before is
+    /// the one named.
+    fn new_mail_notification(
+        &mut self,
+        mailbox_id: &str,
+        emails: &Result<Vec<Email>, String>,
+    ) -> Option<(String, String)> {
+        if Self::inbox(&self.all_mailboxes).map(|m| m.id.as_str()) != Some(mailbox_id) {
+            return None;
+        }
+        let emails = match emails {
+            Ok(emails) => emails,
+            Err(_) => {
+                let count = self.pending_new_mail.take()?;
+                return Some((new_messages_summary(count), self.current_account.clone()));
+            }
+        };
+        let unread: Vec<&Email> = emails
+            .iter()
+            .filter(|e| !e.keywords.contains_key("$seen"))
+            .collect();
+        let new: Vec<&Email> = unread
+            .iter()
+            .copied()
+            .filter(|e| self.known_inbox_unread.insert(e.id.clone()))
+            .collect();
+        let count = self.pending_new_mail.take()?;
+        let Some(newest) = new.first().or(unread.first()) else {
+            return Some((new_messages_summary(count), self.current_account.clone()));
+        };
+        let from = newest
+            .from
+            .as_ref()
+            .and_then(|addrs| addrs.first())
+            .map(|a| a.to_string())
+            .unwrap_or_else(|| "(unknown)".to_string());
+        let subject = newest.subject.as_deref().unwrap_or("(no subject)");
+        let summary = if count == 1 {
+            format!("New mail from {}", from)
+        } else {
+            format!("{} new messages, latest from {}", count, from)
+        };
+        Some((summary, subject.to_string()))
+    }
+
+    fn empty_mailbox(&mut self, confirm: EmptyConfirm) {
+        let name = confirm.mailbox_name.clone();
+        if let Err(e) = self.cmd_tx.send(BackendCommand::EmptyMailbox {
+            mailbox_id: confirm.mailbox_id,
+            mailbox_name: confirm.mailbox_name,
+            ids: confirm.ids,
+        }) {
+            self.status_message = Some(format!("Empty folder failed to send: {}", e));
+        } else {
+            self.status_message = Some(format!("Emptying folder '{}'...", name));
+        }
+    }
+
+    fn execute_retention_expiry(&mut self) {
+        let _ = self.cmd_tx.send(BackendCommand::ExecuteRetentionExpiry {
+            policies: self.options.retention_policies.clone(),
+        });
+        self.status_message = Some("Expiring retained mail...".to_string());
+    }
+
     fn request_refresh(&mut self, origin: &str) {
         self.loading = true;
         let _ = self.cmd_tx.send(BackendCommand::FetchMailboxes {
             origin: origin.to_string(),
         });
+        let quota_stale = self.quota_requested.is_none_or(|at| {
+            SystemTime::now()
+                .duration_since(at)
+                .is_ok_and(|age| age.as_secs() >= QUOTA_REFRESH_SECS)
+        });
+        if quota_stale {
+            self.quota_requested = Some(SystemTime::now());
+            let _ = self.cmd_tx.send(BackendCommand::GetQuota);
+        }
+    }
+
+    /// `work: 3 | *home: 12` for every account, the active one starred;
+    /// `?` for counts not known yet or that failed to load.
+    fn account_summary(&self) -> String {
+        self.account_names
+            .iter()
+            .map(|name| {
+                let (marker, count) = if *name == self.current_account {
+                    (
+                        "*",
+                        Self::inbox(&self.all_mailboxes).map(|m| m.unread_emails),
+                    )
+                } else {
+                    ("", self.account_unread.get(name).copied().flatten())
+                };
+                let count = count.map_or_else(|| "?".to_string(), |c| c.to_string());
+                format!("{}{}: {}", marker, name, count)
+            })
+            .collect::<Vec<_>>()
+            .join(" | ")
     }
 
     fn next_account_name(&self) -> Option<String> {
@@ -141,56 +339,187 @@ impl MailboxListView {
         });
     }
 
-    fn format_mailbox(m: &Mailbox) -> String {
-        if m.unread_emails > 0 {
-            format!("{} ({}/{})", m.name, m.unread_emails, m.total_emails)
-        } else if m.total_emails > 0 {
-            format!("{} ({})", m.name, m.total_emails)
-        } else {
-            m.name.clone()
-        }
+    fn format_mailbox(format: &str, m: &Mailbox) -> String {
+        // Name last, so braces inside a folder name are left alone.
+        format
+            .replace("{unread}", &m.unread_emails.to_string())
+            .replace("{total}", &m.total_emails.to_string())
+            .replace("{name}", &m.name)
     }
 
-    fn scroll_offset_for(&self, max_items: usize) -> usize {
-        if max_items == 0 || self.mailboxes.is_empty() {
+    fn scroll_offset_for(&self, cursor: usize, len: usize, max_items: usize) -> usize {
+        if max_items == 0 || len == 0 {
             return 0;
         }
-        let max_offset = self.mailboxes.len().saturating_sub(max_items);
-        let margin = self.scrolloff.min(max_items.saturating_sub(1));
-        let lower_bound = margin;
+        let max_offset = len.saturating_sub(max_items);
+        let margin = self.options.scrolloff.min(max_items.saturating_sub(1));
         let upper_bound = max_items.saturating_sub(margin + 1);
 
-        if self.cursor < lower_bound {
-            0
-        } else if self.cursor <= upper_bound {
+        if cursor <= upper_bound {
             0
         } else {
-            (self.cursor - upper_bound).min(max_offset)
+            (cursor - upper_bound).min(max_offset)
+        }
+    }
+
+    fn clear_filter(&mut self) {
+        self.filter_mode = false;
+        self.filter_input.clear();
+        self.filter_cursor = 0;
+    }
+
+    /// Open the highlighted filter match, leaving the cursor on it in the
+    /// full list for when the user comes back.
+    fn open_filter_match(&mut self) -> ViewAction {
+        let target = filter_mailboxes(&self.mailboxes, &self.filter_input)
+            .get(self.filter_cursor)
+            .map(|m| m.id.clone());
+        self.clear_filter();
+        let Some(idx) = target.and_then(|id| self.mailboxes.iter().position(|m| m.id == id)) else {
+            return ViewAction::Continue;
+        };
+        self.cursor = idx;
+        self.open_at_cursor("mailbox_list.open_filter")
+            .unwrap_or(ViewAction::Continue)
+    }
+
+    fn row_at_cursor(&self) -> Option<Row<'_>> {
+        match self.mailboxes.get(self.cursor) {
+            Some(mailbox) => Some(Row::Mailbox(mailbox)),
+            None => self
+                .options
+                .saved_searches
+                .get(self.cursor.checked_sub(self.mailboxes.len())?)
+                .map(Row::SavedSearch),
+        }
+    }
+
+    /// The mailbox under the cursor for a folder action (`d`, `u`, `E`). On a
+    /// saved-search row the status bar says so and there is none.
+    fn folder_at_cursor(&mut self) -> Option<Mailbox> {
+        match self.row_at_cursor()? {
+            Row::Mailbox(mailbox) => Some(mailbox.clone()),
+            Row::SavedSearch(search) => {
+                self.status_message =
+                    Some(format!("'{}' is a saved search, not a folder", search.name));
+                None
+            }
+        }
+    }
+
+    /// Push the email list for the mailbox under the cursor, remembering it
+    /// as the session's open mailbox.
+    fn open_at_cursor(&mut self, origin: &str) -> Option<ViewAction> {
+        let mailbox = match self.row_at_cursor()? {
+            Row::Mailbox(mailbox) => mailbox.clone(),
+            Row::SavedSearch(search) => {
+                let search = search.clone();
+                return Some(self.open_saved_search(&search));
+            }
+        };
+        let view = self.build_email_list_view(&mailbox);
+        self.maybe_query_on_open(&mailbox, origin);
+        self.save_session(Some(mailbox.id));
+        Some(ViewAction::Push(Box::new(view)))
+    }
+
+    /// Push an email list running a saved search: in its mailbox, or across
+    /// all mailboxes when it names none.
+    fn open_saved_search(&mut self, search: &SavedSearchConfig) -> ViewAction {
+        let (mailbox, cross_mailbox) = match &search.mailbox {
+            Some(name) => {
+                let found = rules::resolve_mailbox_id(name, &self.all_mailboxes)
+                    .and_then(|id| self.all_mailboxes.iter().find(|m| m.id == id));
+                let Some(mailbox) = found else {
+                    self.status_message = Some(format!(
+                        "Saved search '{}': no mailbox '{}'",
+                        search.name, name
+                    ));
+                    return ViewAction::Continue;
+                };
+                (mailbox.clone(), false)
+            }
+            None => (
+                Mailbox {
+                    id: format!("saved-search:{}", search.name),
+                    name: search.name.clone(),
+                    parent_id: None,
+                    role: None,
+                    total_emails: 0,
+                    unread_emails: 0,
+                    sort_order: 0,
+                },
+                true,
+            ),
+        };
+        let mut view = self
+            .new_email_list_view(&mailbox)
+            .with_search(search.query.clone(), cross_mailbox);
+        view.request_refresh("mailbox_list.open_saved_search");
+        ViewAction::Push(Box::new(view))
+    }
+
+    fn save_session(&self, last_mailbox_id: Option<String>) {
+        if !self.options.restore_session {
+            return;
+        }
+        let _ = self.cmd_tx.send(BackendCommand::SaveSession {
+            state: SessionState {
+                last_mailbox_id,
+                cursor: self.cursor,
+            },
+        });
+    }
+
+    /// Apply a saved session once mailboxes are known: put the cursor back
+    /// and reopen the mailbox that was open, falling back to the inbox when
+    /// it no longer exists.
+    fn restore(&mut self, state: SessionState) {
+        self.cursor = state.cursor.min(self.row_count().saturating_sub(1));
+        let Some(id) = state.last_mailbox_id else {
+            return;
+        };
+        let target = self.mailboxes.iter().position(|m| m.id == id).or_else(|| {
+            self.mailboxes
+                .iter()
+                .position(|m| m.role.as_deref() == Some("inbox"))
+        });
+        if let Some(idx) = target {
+            self.cursor = idx;
+            self.pending_restore_open = true;
         }
     }
 
     fn build_email_list_view(&self, mailbox: &Mailbox) -> EmailListView {
+        let mut view = self.new_email_list_view(mailbox);
+        // Always hydrate from any cached snapshot we have, even if stale.
+        // Freshness only controls whether we skip a background refresh.
+        if let Some(cached) = self.email_cache.get(&mailbox.id) {
+            view.apply_cached_state(cached);
+        }
+        view
+    }
+
+    fn new_email_list_view(&self, mailbox: &Mailbox) -> EmailListView {
         let reply_from = self
             .reply_from_address
             .clone()
             .unwrap_or_else(|| self.from_address.clone());
         let mut view = EmailListView::new(
             self.cmd_tx.clone(),
+            &self.options,
             reply_from,
+            self.identities.clone(),
+            self.signature.clone(),
             mailbox.id.clone(),
             mailbox.name.clone(),
-            self.page_size,
-            self.scrolloff,
-            self.mailboxes.clone(),
-            self.archive_folder.clone(),
-            self.deleted_folder.clone(),
-            self.browser.clone(),
+            self.all_mailboxes.clone(),
+            self.body_choice.clone(),
         );
-        // Always hydrate from any cached snapshot we have, even if stale.
-        // Freshness only controls whether we skip a background refresh.
-        if let Some(cached) = self.email_cache.get(&mailbox.id) {
-            view.apply_cached_state(cached);
+        if let Some(color) = self.account_color {
+            view = view.with_account_label(self.current_account.clone(), color);
         }
+        let _ = self.cmd_tx.send(BackendCommand::LoadSearchHistory);
         view
     }
 
@@ -202,7 +531,7 @@ impl MailboxListView {
         let _ = self.cmd_tx.send(BackendCommand::QueryEmails {
             origin: origin.to_string(),
             mailbox_id: mailbox.id.clone(),
-            page_size: self.page_size,
+            page_size: self.options.page_size,
             position: 0,
             search_query: None,
             received_after: None,
@@ -211,6 +540,41 @@ impl MailboxListView {
     }
 }
 
+/// The messages an `E` confirmation would destroy.
+struct EmptyConfirm {
+    mailbox_id: String,
+    mailbox_name: String,
+    ids: Vec<String>,
+}
+
+/// A row of the mailbox list: a real mailbox, or one of the
+/// `[[saved_search]]` entries listed below them.
+enum Row<'a> {
+    Mailbox(&'a Mailbox),
+    SavedSearch(&'a SavedSearchConfig),
+}
+
+/// A saved-search row: `[search] name  (query in Mailbox)`.
+fn format_saved_search(search: &SavedSearchConfig) -> String {
+    match &search.mailbox {
+        Some(mailbox) => format!(
+            "[search] {}  ({} in {})",
+            search.name, search.query, mailbox
+        ),
+        None => format!("[search] {}  ({})", search.name, search.query),
+    }
+}
+
+/// Pick the account-wide storage quota: the first `octets` quota scoped to
+/// the account, else any `octets` quota.
+fn account_storage_quota(quotas: &[Quota]) -> Option<(u64, u64)> {
+    let octets = || quotas.iter().filter(|q| q.resource_type == "octets");
+    octets()
+        .find(|q| q.scope.as_deref().is_none_or(|s| s == "account"))
+        .or_else(|| octets().next())
+        .map(|q| (q.used, q.hard_limit))
+}
+
 impl View for MailboxListView {
     fn render(&self, term: &mut Terminal) -> io::Result<()> {
         term.clear()?;
@@ -218,25 +582,42 @@ impl View for MailboxListView {
         // Header
         term.move_to(1, 1)?;
         term.set_header()?;
-        let header = {
-            let title = if self.account_names.len() > 1 {
-                format!("tmc - {}", self.current_account)
-            } else {
-                "tmc - Timmy's Mail Console".to_string()
-            };
-            if let Some(ts) = self.last_refreshed {
-                format!("{} (refreshed {})", title, format_system_time(ts))
-            } else {
-                title
+        let (title, label) = match self.account_color {
+            Some(color) => ("tmc - ".to_string(), Some(color)),
+            None if self.account_names.len() > 1 => {
+                (format!("tmc - {}", self.current_account), None)
             }
+            None => ("tmc - Timmy's Mail Console".to_string(), None),
         };
-        term.write_truncated(&header, term.cols)?;
+        let mut rest = String::new();
+        if let Some((used, limit)) = self.storage_quota {
+            rest.push_str(&format!(
+                " [{} / {}]",
+                format_size(used),
+                format_size(limit)
+            ));
+        }
+        if let Some(ts) = self.last_refreshed {
+            rest.push_str(&format!(" (refreshed {})", format_system_time(ts)));
+        }
+        term.write_truncated(&title, term.cols)?;
+        let mut used = (title.chars().count() as u16).min(term.cols);
+        if let Some(color) = label {
+            used += term.write_header_label(&self.current_account, color, term.cols - used)?;
+        }
+        term.write_truncated(&rest, term.cols - used)?;
         term.reset_attr()?;
 
-        // Separator
+        // Separator, carrying the unread summary when there are several accounts
         term.move_to(2, 1)?;
-        let sep = "-".repeat(term.cols as usize);
-        term.write_str(&sep)?;
+        let sep = if self.account_names.len() > 1 {
+            let summary = format!("-- Unread: {} ", self.account_summary());
+            let pad = (term.cols as usize).saturating_sub(summary.chars().count());
+            format!("{}{}", summary, "-".repeat(pad))
+        } else {
+            "-".repeat(term.cols as usize)
+        };
+        term.write_truncated(&sep, term.cols)?;
 
         if self.create_mode {
             term.move_to(3, 1)?;
@@ -259,6 +640,35 @@ impl View for MailboxListView {
             term.reset_attr()?;
             term.move_to(4, 1)?;
             term.write_truncated("Press y to confirm, n or Esc to cancel.", term.cols)?;
+        } else if let Some(confirm) = &self.empty_confirm {
+            term.move_to(3, 1)?;
+            term.set_header()?;
+            let prompt = format!(
+                "Permanently destroy all {} message(s) in '{}'? (y/N)",
+                confirm.ids.len(),
+                confirm.mailbox_name
+            );
+            term.write_truncated(&prompt, term.cols)?;
+            term.reset_attr()?;
+            term.move_to(4, 1)?;
+            term.write_truncated(
+                "This cannot be undone. Press y to confirm, n or Esc to cancel.",
+                term.cols,
+            )?;
+        } else if let Some(candidates) = &self.expire_confirm {
+            term.move_to(3, 1)?;
+            term.set_header()?;
+            let prompt = format!(
+                "Permanently destroy {} message(s) under the retention policies? (y/N)",
+                candidates.len()
+            );
+            term.write_truncated(&prompt, term.cols)?;
+            term.reset_attr()?;
+            term.move_to(4, 1)?;
+            term.write_truncated(
+                "This cannot be undone. Press y to confirm, n or Esc to cancel.",
+                term.cols,
+            )?;
         } else if self.loading && self.mailboxes.is_empty() {
             term.move_to(3, 1)?;
             term.write_truncated("Loading mailboxes...", term.cols)?;
@@ -270,11 +680,41 @@ impl View for MailboxListView {
             term.write_truncated("No mailboxes found.", term.cols)?;
         } else {
             let max_items = (term.rows as usize).saturating_sub(4);
-            let scroll_offset = self.scroll_offset_for(max_items);
+            let (visible, cursor) = if self.filter_mode {
+                (
+                    filter_mailboxes(&self.mailboxes, &self.filter_input),
+                    self.filter_cursor,
+                )
+            } else {
+                (self.mailboxes.iter().collect(), self.cursor)
+            };
+            // (line, bold) per row; saved searches follow the mailboxes
+            // outside of quick-jump.
+            let mut rows: Vec<(String, bool)> = visible
+                .into_iter()
+                .map(|m| {
+                    (
+                        Self::format_mailbox(&self.options.mailbox_format, m),
+                        m.unread_emails > 0,
+                    )
+                })
+                .collect();
+            if !self.filter_mode {
+                rows.extend(
+                    self.options
+                        .saved_searches
+                        .iter()
+                        .map(|s| (format_saved_search(s), false)),
+                );
+            }
+            let scroll_offset = self.scroll_offset_for(cursor, rows.len(), max_items);
 
-            for (i, mailbox) in self
-                .mailboxes
-                .iter()
+            if rows.is_empty() {
+                term.move_to(3, 1)?;
+                term.write_truncated("No matching folders.", term.cols)?;
+            }
+            for (i, (line, bold)) in rows
+                .into_iter()
                 .skip(scroll_offset)
                 .enumerate()
                 .take(max_items)
@@ -283,14 +723,13 @@ impl View for MailboxListView {
                 term.move_to(row, 1)?;
 
                 let display_idx = scroll_offset + i;
-                let line = Self::format_mailbox(mailbox);
 
-                if display_idx == self.cursor {
+                if display_idx == cursor {
                     term.set_selection()?;
-                    if mailbox.unread_emails > 0 {
+                    if bold {
                         term.set_bold_text()?;
                     }
-                } else if mailbox.unread_emails > 0 {
+                } else if bold {
                     term.set_bold_text()?;
                 }
 
@@ -311,6 +750,18 @@ impl View for MailboxListView {
             " New folder name | Enter:create Esc:cancel".to_string()
         } else if self.delete_confirm_mode {
             " Confirm delete | y:delete n/Esc:cancel".to_string()
+        } else if self.empty_confirm.is_some() {
+            " Confirm empty | y:destroy n/Esc:cancel".to_string()
+        } else if self.expire_confirm.is_some() {
+            " Confirm expire | y:destroy n/Esc:cancel".to_string()
+        } else if self.filter_mode {
+            let matches = filter_mailboxes(&self.mailboxes, &self.filter_input).len();
+            format!(
+                " Jump to: {}_ ({} match{}) | Up/Down:navigate RET:open Esc:cancel",
+                self.filter_input,
+                matches,
+                if matches == 1 { "" } else { "es" }
+            )
         } else if self.loading {
             format!(
                 " Loading... | q:quit g:refresh c:compose +:new-folder d:delete-folder u:read-all x:preview-expire X:expire{}",
@@ -323,9 +774,9 @@ impl View for MailboxListView {
             )
         } else {
             format!(
-                " {}/{} | q:quit n/p:navigate RET:open g:refresh c:compose +:new-folder d:delete-folder u:read-all x:preview-expire X:expire ?:help{}",
+                " {}/{} | q:quit n/p:navigate RET:open g:refresh c:compose r:drafts +:new-folder d:delete-folder u:read-all E:empty x:preview-expire X:expire ?:help{}",
                 self.cursor + 1,
-                self.mailboxes.len(),
+                self.row_count(),
                 account_hint,
             )
         };
@@ -380,7 +831,7 @@ impl View for MailboxListView {
         if self.delete_confirm_mode {
             match key {
                 Key::Char('y') | Key::Char('Y') => {
-                    if let Some(mailbox) = self.mailboxes.get(self.cursor) {
+                    if let Some(mailbox) = self.folder_at_cursor() {
                         if let Err(e) = self.cmd_tx.send(BackendCommand::DeleteMailbox {
                             id: mailbox.id.clone(),
                             name: mailbox.name.clone(),
@@ -402,11 +853,77 @@ impl View for MailboxListView {
             return ViewAction::Continue;
         }
 
+        if let Some(confirm) = self.empty_confirm.take() {
+            if matches!(key, Key::Char('y') | Key::Char('Y')) {
+                self.empty_mailbox(confirm);
+            } else {
+                self.status_message = Some("Empty cancelled".to_string());
+            }
+            return ViewAction::Continue;
+        }
+
+        if let Some(candidates) = self.expire_confirm.take() {
+            if matches!(key, Key::Char('y') | Key::Char('Y')) {
+                let _ = self
+                    .cmd_tx
+                    .send(BackendCommand::ExpireRetentionCandidates { candidates });
+                self.status_message = Some("Expiring retained mail...".to_string());
+            } else {
+                self.status_message = Some("Expire cancelled".to_string());
+            }
+            return ViewAction::Continue;
+        }
+
+        if self.filter_mode {
+            let matches = filter_mailboxes(&self.mailboxes, &self.filter_input).len();
+            match key {
+                Key::Escape => self.clear_filter(),
+                Key::Enter => return self.open_filter_match(),
+                Key::Backspace => {
+                    self.filter_input.pop();
+                    self.filter_cursor = 0;
+                }
+                Key::Down | Key::Ctrl('n') | Key::ScrollDown
+                    if self.filter_cursor + 1 < matches =>
+                {
+                    self.filter_cursor += 1;
+                }
+                Key::Up | Key::Ctrl('p') | Key::ScrollUp if self.filter_cursor > 0 => {
+                    self.filter_cursor -= 1;
+                }
+                Key::MouseClick { row, col: _ } if row >= 3 => {
+                    let max_items = (term_rows as usize).saturating_sub(4);
+                    let scroll_offset =
+                        self.scroll_offset_for(self.filter_cursor, matches, max_items);
+                    let clicked = scroll_offset + (row - 3) as usize;
+                    if clicked < matches {
+                        self.filter_cursor = clicked;
+                        return self.open_filter_match();
+                    }
+                }
+                Key::Char(c) => {
+                    self.filter_input.push(c);
+                    self.filter_cursor = 0;
+                }
+                _ => {}
+            }
+            return ViewAction::Continue;
+        }
+
         let page = (term_rows as usize).saturating_sub(4);
         match key {
-            Key::Char('q') => ViewAction::Quit,
+            Key::Char('q') => {
+                self.save_session(None);
+                ViewAction::Quit
+            }
+            Key::Char('/') => {
+                self.filter_mode = true;
+                self.filter_input.clear();
+                self.filter_cursor = 0;
+                ViewAction::Continue
+            }
             Key::Char('n') | Key::Char('j') | Key::Down => {
-                if !self.mailboxes.is_empty() && self.cursor + 1 < self.mailboxes.len() {
+                if self.cursor + 1 < self.row_count() {
                     self.cursor += 1;
                 }
                 ViewAction::Continue
@@ -419,7 +936,7 @@ impl View for MailboxListView {
             }
             Key::PageDown => {
                 if !self.mailboxes.is_empty() {
-                    self.cursor = (self.cursor + page).min(self.mailboxes.len() - 1);
+                    self.cursor = (self.cursor + page).min(self.row_count() - 1);
                 }
                 ViewAction::Continue
             }
@@ -433,20 +950,16 @@ impl View for MailboxListView {
             }
             Key::End => {
                 if !self.mailboxes.is_empty() {
-                    self.cursor = self.mailboxes.len() - 1;
+                    self.cursor = self.row_count() - 1;
                 }
                 ViewAction::Continue
             }
-            Key::Enter => {
-                if let Some(mailbox) = self.mailboxes.get(self.cursor) {
-                    let view = self.build_email_list_view(mailbox);
-                    self.maybe_query_on_open(mailbox, "mailbox_list.open_enter");
-                    ViewAction::Push(Box::new(view))
-                } else {
-                    ViewAction::Continue
-                }
-            }
+            Key::Enter => self
+                .open_at_cursor("mailbox_list.open_enter")
+                .unwrap_or(ViewAction::Continue),
             Key::Char('g') => {
+                // An explicit refresh also re-reads the storage quota.
+                self.quota_requested = None;
                 self.request_refresh("mailbox_list.key_g");
                 ViewAction::Continue
             }
@@ -456,13 +969,13 @@ impl View for MailboxListView {
                 ViewAction::Continue
             }
             Key::Char('d') => {
-                if !self.mailboxes.is_empty() {
+                if self.folder_at_cursor().is_some() {
                     self.delete_confirm_mode = true;
                 }
                 ViewAction::Continue
             }
             Key::Char('u') => {
-                if let Some(mailbox) = self.mailboxes.get(self.cursor) {
+                if let Some(mailbox) = self.folder_at_cursor() {
                     if mailbox.unread_emails == 0 {
                         self.status_message =
                             Some(format!("Folder '{}' already read", mailbox.name));
@@ -479,18 +992,50 @@ impl View for MailboxListView {
                 }
                 ViewAction::Continue
             }
+            Key::Char('E') => {
+                if let Some(mailbox) = self.folder_at_cursor() {
+                    if !backend::is_emptiable_mailbox(
+                        &mailbox.id,
+                        &self.mailboxes,
+                        &self.options.deleted_folder,
+                        &self.options.spam_folder,
+                    ) {
+                        self.status_message =
+                            Some("Only the Trash or Junk folder can be emptied".to_string());
+                    } else if self
+                        .cmd_tx
+                        .send(BackendCommand::PreviewEmptyMailbox {
+                            mailbox_id: mailbox.id.clone(),
+                            mailbox_name: mailbox.name.clone(),
+                        })
+                        .is_ok()
+                    {
+                        self.empty_counting = true;
+                        self.status_message =
+                            Some(format!("Counting messages in '{}'...", mailbox.name));
+                    }
+                }
+                ViewAction::Continue
+            }
             Key::Char('x') => {
                 let _ = self.cmd_tx.send(BackendCommand::PreviewRetentionExpiry {
-                    policies: self.retention_policies.clone(),
+                    policies: self.options.retention_policies.clone(),
                 });
                 self.status_message = Some("Building retention preview...".to_string());
                 ViewAction::Continue
             }
             Key::Char('X') => {
-                let _ = self.cmd_tx.send(BackendCommand::ExecuteRetentionExpiry {
-                    policies: self.retention_policies.clone(),
-                });
-                self.status_message = Some("Expiring retained mail...".to_string());
+                if self.options.confirm_destructive {
+                    // Count what would be destroyed first; the prompt opens
+                    // when the preview arrives.
+                    let _ = self.cmd_tx.send(BackendCommand::PreviewRetentionExpiry {
+                        policies: self.options.retention_policies.clone(),
+                    });
+                    self.expire_counting = true;
+                    self.status_message = Some("Counting retained mail...".to_string());
+                } else {
+                    self.execute_retention_expiry();
+                }
                 ViewAction::Continue
             }
             Key::Char('c') => {
@@ -498,11 +1043,28 @@ impl View for MailboxListView {
                     .reply_from_address
                     .as_deref()
                     .unwrap_or(&self.from_address);
-                let draft = compose::build_compose_draft(from);
+                let draft = compose::build_compose_draft(
+                    from,
+                    self.identities.borrow().for_compose(),
+                    &self.signature,
+                );
                 ViewAction::Compose(draft.into())
             }
+            Key::Char('r') => ViewAction::Push(Box::new(DraftsView::new(DraftStore::for_account(
+                &self.current_account,
+            )))),
+            Key::Char('i') => {
+                self.status_message = Some(self.identities.borrow_mut().cycle());
+                ViewAction::Continue
+            }
+            Key::Char('G') if self.account_names.len() > 1 => {
+                self.status_message = Some("Refreshing all accounts...".to_string());
+                self.request_refresh("mailbox_list.refresh_all_accounts");
+                ViewAction::RefreshAccounts
+            }
             Key::Char('a') => {
                 if let Some(next) = self.next_account_name() {
+                    self.save_session(None);
                     ViewAction::SwitchAccount(next)
                 } else {
                     ViewAction::Continue
@@ -516,7 +1078,7 @@ impl View for MailboxListView {
                 ViewAction::Continue
             }
             Key::ScrollDown => {
-                if !self.mailboxes.is_empty() && self.cursor + 1 < self.mailboxes.len() {
+                if self.cursor + 1 < self.row_count() {
                     self.cursor += 1;
                 }
                 ViewAction::Continue
@@ -524,9 +1086,10 @@ impl View for MailboxListView {
             Key::MouseClick { row, col: _ } => {
                 if row >= 3 && !self.mailboxes.is_empty() {
                     let max_items = (term_rows as usize).saturating_sub(4);
-                    let scroll_offset = self.scroll_offset_for(max_items);
+                    let scroll_offset =
+                        self.scroll_offset_for(self.cursor, self.row_count(), max_items);
                     let clicked = scroll_offset + (row - 3) as usize;
-                    if clicked < self.mailboxes.len() {
+                    if clicked < self.row_count() {
                         self.cursor = clicked;
                         self.pending_click = true;
                         return ViewAction::Continue;
@@ -541,10 +1104,14 @@ impl View for MailboxListView {
     fn take_pending_action(&mut self) -> Option<ViewAction> {
         if self.pending_click {
             self.pending_click = false;
-            if let Some(mailbox) = self.mailboxes.get(self.cursor) {
-                let view = self.build_email_list_view(mailbox);
-                self.maybe_query_on_open(mailbox, "mailbox_list.open_click");
-                return Some(ViewAction::Push(Box::new(view)));
+            if let Some(action) = self.open_at_cursor("mailbox_list.open_click") {
+                return Some(action);
+            }
+        }
+        if self.pending_restore_open {
+            self.pending_restore_open = false;
+            if let Some(action) = self.open_at_cursor("mailbox_list.restore_session") {
+                return Some(action);
             }
         }
         if let Some(candidates) = self.pending_retention_preview.take() {
@@ -561,13 +1128,30 @@ impl View for MailboxListView {
                 self.loading = false;
                 match result {
                     Ok(mailboxes) => {
+                        self.note_inbox_unread_count(mailboxes);
                         let mut mailboxes = mailboxes.clone();
                         Self::sort_mailboxes(&mut mailboxes);
-                        self.mailboxes = mailboxes;
+                        self.mailboxes = if self.options.hide_empty_mailboxes {
+                            mailboxes
+                                .iter()
+                                .filter(|m| {
+                                    m.total_emails > 0 || m.role.as_deref() == Some("inbox")
+                                })
+                                .cloned()
+                                .collect()
+                        } else {
+                            mailboxes.clone()
+                        };
+                        self.all_mailboxes = mailboxes;
+                        if !self.mailboxes.is_empty() {
+                            if let Some(state) = self.pending_session.take() {
+                                self.restore(state);
+                            }
+                        }
                         self.error = None;
                         self.last_refreshed = Some(SystemTime::now());
-                        if self.cursor >= self.mailboxes.len() && !self.mailboxes.is_empty() {
-                            self.cursor = self.mailboxes.len() - 1;
+                        if self.cursor >= self.row_count() && !self.mailboxes.is_empty() {
+                            self.cursor = self.row_count() - 1;
                         }
                     }
                     Err(e) => {
@@ -576,6 +1160,25 @@ impl View for MailboxListView {
                 }
                 true
             }
+            BackendResponse::Session(state) => {
+                self.pending_session = state.clone();
+                false
+            }
+            BackendResponse::AccountUnread { account, result } => {
+                self.account_unread
+                    .insert(account.clone(), result.as_ref().ok().copied());
+                if let Err(e) = result {
+                    self.status_message = Some(format!("Account '{}': {}", account, e));
+                }
+                true
+            }
+            BackendResponse::Quota { result } => {
+                // Quota is informational; keep the last known value on error.
+                if let Ok(quotas) = result {
+                    self.storage_quota = quotas.as_deref().and_then(account_storage_quota);
+                }
+                true
+            }
             BackendResponse::Emails {
                 mailbox_id,
                 emails,
@@ -584,6 +1187,13 @@ impl View for MailboxListView {
                 loaded,
                 thread_counts,
             } => {
+                if *position == 0 {
+                    if let Some((summary, body)) = self.new_mail_notification(mailbox_id, emails) {
+                        if let Some(command) = self.options.notify_command.as_deref() {
+                            send_notification(command, &summary, &body);
+                        }
+                    }
+                }
                 if let Ok(emails) = emails {
                     let now = SystemTime::now();
                     let entry = self
@@ -621,6 +1231,21 @@ impl View for MailboxListView {
                 }
                 false
             }
+            BackendResponse::RetentionPreview { result } if self.expire_counting => {
+                self.expire_counting = false;
+                match result {
+                    Ok(preview) if preview.candidates.is_empty() => {
+                        self.status_message = Some("No messages eligible for expiry".to_string());
+                    }
+                    Ok(preview) => {
+                        self.expire_confirm = Some(preview.candidates.clone());
+                    }
+                    Err(e) => {
+                        self.status_message = Some(format!("Retention preview failed: {}", e));
+                    }
+                }
+                true
+            }
             BackendResponse::RetentionPreview { result } => {
                 match result {
                     Ok(preview) => {
@@ -689,6 +1314,57 @@ impl View for MailboxListView {
                 false
             }
             BackendResponse::ThreadMarkedRead { .. } => false,
+            BackendResponse::EmptyMailboxPreview {
+                mailbox_id,
+                mailbox_name,
+                result,
+            } if self.empty_counting => {
+                self.empty_counting = false;
+                match result {
+                    Ok(ids) if ids.is_empty() => {
+                        self.status_message =
+                            Some(format!("Folder '{}' is already empty", mailbox_name));
+                    }
+                    Ok(ids) => {
+                        self.status_message = None;
+                        self.empty_confirm = Some(EmptyConfirm {
+                            mailbox_id: mailbox_id.clone(),
+                            mailbox_name: mailbox_name.clone(),
+                            ids: ids.clone(),
+                        });
+                    }
+                    Err(e) => {
+                        self.status_message =
+                            Some(format!("Empty failed for '{}': {}", mailbox_name, e));
+                    }
+                }
+                true
+            }
+            BackendResponse::MailboxEmptied {
+                mailbox_id,
+                mailbox_name,
+                destroyed,
+                result,
+            } => {
+                match result {
+                    Ok(()) => {
+                        self.status_message = Some(format!(
+                            "Destroyed {} message(s) in '{}'",
+                            destroyed, mailbox_name
+                        ));
+                        self.email_cache.remove(mailbox_id);
+                        self.request_refresh(&format!(
+                            "mailbox_list.mailbox_emptied:{}",
+                            mailbox_id
+                        ));
+                    }
+                    Err(e) => {
+                        self.status_message =
+                            Some(format!("Empty failed for '{}': {}", mailbox_name, e));
+                    }
+                }
+                true
+            }
             BackendResponse::MailboxMarkedRead {
                 mailbox_id,
                 mailbox_name,
@@ -725,7 +1401,15 @@ impl View for MailboxListView {
     }
 
     fn trigger_idle_sync(&mut self) -> bool {
-        if self.loading || self.create_mode || self.delete_confirm_mode {
+        if self.loading
+            || self.create_mode
+            || self.filter_mode
+            || self.delete_confirm_mode
+            || self.empty_counting
+            || self.empty_confirm.is_some()
+            || self.expire_counting
+            || self.expire_confirm.is_some()
+        {
             return false;
         }
         self.request_refresh("mailbox_list.idle_sync");
@@ -741,6 +1425,374 @@ impl View for MailboxListView {
             entry.last_refreshed = SystemTime::UNIX_EPOCH;
         }
         self.request_refresh("mailbox_list.reveal");
+        self.save_session(None);
         true
     }
+
+    fn on_reload(&mut self, settings: Option<&ReloadedSettings>, message: Option<&str>) {
+        if let Some(settings) = settings {
+            self.options.apply_reload(settings);
+        }
+        if let Some(message) = message {
+            self.status_message = Some(message.to_string());
+        }
+    }
+}
+
+fn new_messages_summary(count: u32) -> String {
+    if count == 1 {
+        "1 new message".to_string()
+    } else {
+        format!("{} new messages", count)
+    }
+}
+
+/// Run the configured notification command with the summary and body
+/// appended as shell-quoted arguments. A background thread waits for the
+/// command so it does not linger as a zombie.
+fn send_notification(
+    command: &str,
+    summary: &str,
+    body: &str,
+) -> Option<std::thread::JoinHandle<()>> {
+    let quote = |s: &str| format!("'{}'", s.replace('\'', "'\\''"));
+    let shell_cmd = format!("{} {} {}", command, quote(summary), quote(body));
+    crate::log_info!("[Notify] running: {}", shell_cmd);
+    match std::process::Command::new("sh")
+        .arg("-c")
+        .arg(&shell_cmd)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+    {
+        Ok(mut child) => Some(std::thread::spawn(move || {
+            let _ = child.wait();
+        })),
+        Err(e) => {
+            crate::log_error!("[Notify] failed to run '{}': {}", shell_cmd, e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tui::views::test_view_options;
+
+    fn make_view() -> (MailboxListView, mpsc::Receiver<BackendCommand>) {
+        let (cmd_tx, cmd_rx) = mpsc::channel();
+        let options = ViewOptions {
+            spam_folder: "Junk".to_string(),
+            confirm_destructive: true,
+            sync_interval_secs: Some(60),
+            ..test_view_options()
+        };
+        let view = MailboxListView::new(
+            cmd_tx,
+            options,
+            "me@example.com".to_string(),
+            None,
+            SharedIdentities::default(),
+            compose::SignatureOptions::default(),
+            vec!["test".to_string()],
+            "test".to_string(),
+            None,
+            false,
+        );
+        (view, cmd_rx)
+    }
+
+    fn quota_requests(cmd_rx: &mpsc::Receiver<BackendCommand>) -> usize {
+        cmd_rx
+            .try_iter()
+            .filter(|cmd| matches!(cmd, BackendCommand::GetQuota))
+            .count()
+    }
+
+    #[test]
+    fn quota_is_not_requested_on_every_sync() {
+        let (mut view, cmd_rx) = make_view();
+        view.loading = false;
+        assert!(view.trigger_idle_sync());
+        assert_eq!(quota_requests(&cmd_rx), 1);
+        view.loading = false;
+        assert!(view.trigger_idle_sync());
+        assert_eq!(quota_requests(&cmd_rx), 0);
+
+        view.loading = false;
+        view.handle_key(Key::Char('g'), 24);
+        assert_eq!(quota_requests(&cmd_rx), 1);
+    }
+
+    #[test]
+    fn quota_without_server_support_shows_nothing() {
+        let (mut view, _cmd_rx) = make_view();
+        let quotas: Vec<Quota> = serde_json::from_value(serde_json::json!([
+            {"id": "q", "resourceType": "octets", "used": 512, "hardLimit": 1024}
+        ]))
+        .unwrap();
+        view.on_response(&BackendResponse::Quota {
+            result: Ok(Some(quotas)),
+        });
+        assert_eq!(view.storage_quota, Some((512, 1024)));
+
+        view.on_response(&BackendResponse::Quota {
+            result: Err("timeout".to_string()),
+        });
+        assert_eq!(view.storage_quota, Some((512, 1024)));
+
+        view.on_response(&BackendResponse::Quota { result: Ok(None) });
+        assert_eq!(view.storage_quota, None);
+    }
+
+    fn inbox_with_unread(unread: u32) -> Mailbox {
+        serde_json::from_value(serde_json::json!({
+            "id": "inbox",
+            "name": "INBOX",
+            "parentId": null,
+            "role": "inbox",
+            "totalEmails": 10,
+            "unreadEmails": unread,
+            "sortOrder": 0
+        }))
+        .unwrap()
+    }
+
+    fn unread_email(id: &str, from: &str) -> Email {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "threadId": id,
+            "mailboxIds": {"inbox": true},
+            "from": [{"email": from}],
+            "subject": format!("subject {}", id),
+            "receivedAt": "2026-01-01T00:00:00Z",
+            "keywords": {}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn new_mail_is_announced_once_per_sync() {
+        let (mut view, cmd_rx) = make_view();
+        view.options.notify_command = Some("true".to_string());
+        view.on_response(&BackendResponse::Mailboxes(Ok(vec![inbox_with_unread(1)])));
+        let first_page = Ok(vec![unread_email("e1", "old@example.com")]);
+        assert_eq!(view.new_mail_notification("inbox", &first_page), None);
+        assert!(!cmd_rx
+            .try_iter()
+            .any(|cmd| matches!(cmd, BackendCommand::QueryEmails { .. })));
+
+        // Two new messages: the count change asks for the inbox page.
+        view.on_response(&BackendResponse::Mailboxes(Ok(vec![inbox_with_unread(3)])));
+        assert!(cmd_rx.try_iter().any(|cmd| matches!(
+            cmd,
+            BackendCommand::QueryEmails { ref mailbox_id, .. } if mailbox_id == "inbox"
+        )));
+        let page = Ok(vec![
+            unread_email("e3", "new@example.com"),
+            unread_email("e2", "new@example.com"),
+            unread_email("e1", "old@example.com"),
+        ]);
+        assert_eq!(
+            view.new_mail_notification("inbox", &page),
+            Some((
+                "2 new messages, latest from new@example.com".to_string(),
+                "subject e3".to_string()
+            ))
+        );
+        // The email list syncing the same page does not announce it again.
+        assert_eq!(view.new_mail_notification("inbox", &page), None);
+    }
+
+    #[test]
+    fn notification_command_is_reaped() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("ran");
+        let command = format!("touch '{}'; :", marker.display());
+        let waiter = send_notification(&command, "summary", "body").unwrap();
+        waiter.join().unwrap();
+        assert!(marker.exists());
+    }
+
+    fn candidate(id: &str) -> RetentionCandidate {
+        RetentionCandidate {
+            id: id.to_string(),
+            mailbox: "Trash".to_string(),
+            policy: "trash".to_string(),
+            received_at: "2020-01-01T00:00:00Z".to_string(),
+            from: "a@example.com".to_string(),
+            subject: "old".to_string(),
+        }
+    }
+
+    #[test]
+    fn expire_destroys_only_the_confirmed_candidates() {
+        let (mut view, cmd_rx) = make_view();
+        view.loading = false;
+        view.handle_key(Key::Char('X'), 24);
+        assert!(cmd_rx
+            .try_iter()
+            .any(|cmd| matches!(cmd, BackendCommand::PreviewRetentionExpiry { .. })));
+
+        view.on_response(&BackendResponse::RetentionPreview {
+            result: Ok(backend::RetentionPreviewResult {
+                candidates: vec![candidate("e1"), candidate("e2")],
+            }),
+        });
+        view.handle_key(Key::Char('y'), 24);
+        let ids: Vec<String> = cmd_rx
+            .try_iter()
+            .find_map(|cmd| match cmd {
+                BackendCommand::ExpireRetentionCandidates { candidates } => {
+                    Some(candidates.into_iter().map(|c| c.id).collect())
+                }
+                BackendCommand::ExecuteRetentionExpiry { .. } => {
+                    panic!("confirmed expiry must not re-run the policies")
+                }
+                _ => None,
+            })
+            .expect("expected the confirmed candidates to be expired");
+        assert_eq!(ids, vec!["e1", "e2"]);
+    }
+
+    fn mailbox(id: &str, name: &str) -> Mailbox {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": name,
+            "parentId": null,
+            "role": null,
+            "totalEmails": 0,
+            "unreadEmails": 0,
+            "sortOrder": 0
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn restored_session_reopens_its_mailbox_or_falls_back_to_inbox() {
+        let mailboxes = || {
+            let mut inbox = mailbox("inbox", "INBOX");
+            inbox.role = Some("inbox".to_string());
+            vec![
+                mailbox("archive", "Archive"),
+                inbox,
+                mailbox("lists", "Lists"),
+            ]
+        };
+        let restore = |last_mailbox_id: &str| {
+            let (mut view, _cmd_rx) = make_view();
+            view.on_response(&BackendResponse::Session(Some(SessionState {
+                last_mailbox_id: Some(last_mailbox_id.to_string()),
+                cursor: 0,
+            })));
+            view.on_response(&BackendResponse::Mailboxes(Ok(mailboxes())));
+            let opened = matches!(view.take_pending_action(), Some(ViewAction::Push(_)));
+            (view.mailboxes[view.cursor].id.clone(), opened)
+        };
+
+        assert_eq!(restore("lists"), ("lists".to_string(), true));
+        // The saved mailbox was deleted since: open the inbox instead.
+        assert_eq!(restore("gone"), ("inbox".to_string(), true));
+    }
+
+    #[test]
+    fn click_opens_a_quick_jump_match() {
+        let (mut view, _cmd_rx) = make_view();
+        view.loading = false;
+        view.mailboxes = vec![
+            mailbox("inbox", "INBOX"),
+            mailbox("archive", "Archive"),
+            mailbox("lists", "Lists"),
+            mailbox("later", "Later"),
+        ];
+        view.handle_key(Key::Char('/'), 24);
+        view.handle_key(Key::Char('l'), 24);
+        // Rows 3 and 4 show the matches Lists and Later.
+        assert!(matches!(
+            view.handle_key(Key::MouseClick { row: 4, col: 1 }, 24),
+            ViewAction::Push(_)
+        ));
+        assert!(!view.filter_mode);
+        assert_eq!(view.mailboxes[view.cursor].id, "later");
+
+        // A click below the matches keeps filtering.
+        view.handle_key(Key::Char('/'), 24);
+        view.handle_key(Key::Char('z'), 24);
+        assert!(matches!(
+            view.handle_key(Key::MouseClick { row: 3, col: 1 }, 24),
+            ViewAction::Continue
+        ));
+        assert!(view.filter_mode);
+    }
+
+    #[test]
+    fn saved_search_rows_take_no_folder_actions() {
+        let (mut view, cmd_rx) = make_view();
+        view.options.saved_searches = vec![SavedSearchConfig {
+            name: "Trash".to_string(),
+            query: "from:boss".to_string(),
+            mailbox: None,
+        }];
+        view.loading = false;
+        view.mailboxes = vec![mailbox("inbox", "INBOX")];
+        view.cursor = 1;
+
+        for key in ['d', 'u', 'E'] {
+            view.status_message = None;
+            view.handle_key(Key::Char(key), 24);
+            assert_eq!(
+                view.status_message.as_deref(),
+                Some("'Trash' is a saved search, not a folder")
+            );
+        }
+        assert!(!view.delete_confirm_mode);
+        assert_eq!(cmd_rx.try_iter().count(), 0);
+
+        assert!(matches!(
+            view.handle_key(Key::Enter, 24),
+            ViewAction::Push(_)
+        ));
+        let cmds: Vec<BackendCommand> = cmd_rx.try_iter().collect();
+        assert!(cmds
+            .iter()
+            .any(|cmd| matches!(cmd, BackendCommand::SearchAllEmails { .. })));
+        assert!(!cmds
+            .iter()
+            .any(|cmd| matches!(cmd, BackendCommand::QueryEmails { .. })));
+    }
+
+    #[test]
+    fn empty_confirms_the_fresh_count_and_destroys_only_those_messages() {
+        let (mut view, cmd_rx) = make_view();
+        let mut trash = mailbox("trash", "Trash");
+        trash.role = Some("trash".to_string());
+        // The list's count is stale; the prompt must not use it.
+        trash.total_emails = 0;
+        view.loading = false;
+        view.mailboxes = vec![trash];
+
+        view.handle_key(Key::Char('E'), 24);
+        assert!(cmd_rx.try_iter().any(|cmd| matches!(
+            cmd,
+            BackendCommand::PreviewEmptyMailbox { mailbox_id, .. } if mailbox_id == "trash"
+        )));
+        view.on_response(&BackendResponse::EmptyMailboxPreview {
+            mailbox_id: "trash".to_string(),
+            mailbox_name: "Trash".to_string(),
+            result: Ok(vec!["e1".to_string(), "e2".to_string()]),
+        });
+        assert_eq!(view.empty_confirm.as_ref().map(|c| c.ids.len()), Some(2));
+
+        view.handle_key(Key::Char('y'), 24);
+        let ids = cmd_rx
+            .try_iter()
+            .find_map(|cmd| match cmd {
+                BackendCommand::EmptyMailbox { ids, .. } => Some(ids),
+                _ => None,
+            })
+            .expect("expected the confirmed messages to be destroyed");
+        assert_eq!(ids, vec!["e1", "e2"]);
+    }
 }
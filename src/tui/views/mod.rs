@@ -1,3 +1,5 @@
+pub mod compose_view;
+pub mod drafts_view;
 pub mod email_list;
 pub mod email_view;
 pub mod help;
@@ -6,23 +8,249 @@ pub mod retention_preview;
 pub mod rules_preview;
 pub mod thread_view;
 
+use super::graphics::ImageProtocol;
 use super::input::Key;
 use super::screen::Terminal;
 use crate::backend::BackendResponse;
+use crate::compose::QuoteStyle;
+use crate::config::{AccountConfig, Config, RetentionPolicyConfig, SavedSearchConfig, SortOrder};
+use crate::datefmt::DateFormat;
+use crate::jmap::types::Mailbox;
+use crate::keybindings::Keymap;
+use std::collections::BTreeMap;
 use std::io;
+use std::path::PathBuf;
 use std::time::SystemTime;
 
 pub fn format_system_time(time: SystemTime) -> String {
     let duration = time
         .duration_since(SystemTime::UNIX_EPOCH)
         .unwrap_or_default();
-    let secs = duration.as_secs();
-    let timestamp = secs as libc::time_t;
-    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
-    unsafe {
-        libc::localtime_r(&timestamp, &mut tm);
+    crate::datefmt::format_local(duration.as_secs() as i64, "%H:%M:%S")
+}
+
+pub fn format_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else if bytes < 1024 * 1024 * 1024 {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("{:.1} GB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+    }
+}
+
+/// Mailboxes whose name contains `filter` (case-insensitive), for the
+/// move picker's type-to-filter.
+pub fn filter_mailboxes<'a>(mailboxes: &'a [Mailbox], filter: &str) -> Vec<&'a Mailbox> {
+    let needle = filter.to_lowercase();
+    mailboxes
+        .iter()
+        .filter(|m| m.name.to_lowercase().contains(&needle))
+        .collect()
+}
+
+/// Status bar text for the move picker.
+pub fn move_picker_status(cursor: usize, matches: usize, filter: &str) -> String {
+    if filter.is_empty() {
+        format!(
+            " {}/{} | Up/Down:navigate type:filter RET:move Esc:cancel",
+            (cursor + 1).min(matches),
+            matches
+        )
+    } else {
+        format!(
+            " Filter: {}_ ({} match{}) | Up/Down:navigate RET:move Esc:clear",
+            filter,
+            matches,
+            if matches == 1 { "" } else { "es" }
+        )
+    }
+}
+
+/// Outcome of a key pressed in the move picker.
+pub enum MovePick<'a> {
+    Continue,
+    Cancel,
+    Chosen(&'a Mailbox),
+}
+
+/// Key handling for the move picker shared by the list and message views.
+/// Every letter extends the filter; Up/Down or Ctrl-n/Ctrl-p move the
+/// selection, and Escape clears the filter before it cancels.
+pub fn move_picker_key<'a>(
+    key: Key,
+    mailboxes: &'a [Mailbox],
+    cursor: &mut usize,
+    filter: &mut String,
+) -> MovePick<'a> {
+    let matches = filter_mailboxes(mailboxes, filter);
+    match key {
+        Key::Escape if !filter.is_empty() => {
+            filter.clear();
+            *cursor = 0;
+        }
+        Key::Escape => return MovePick::Cancel,
+        Key::Down | Key::Ctrl('n') | Key::ScrollDown if *cursor + 1 < matches.len() => {
+            *cursor += 1;
+        }
+        Key::Up | Key::Ctrl('p') | Key::ScrollUp if *cursor > 0 => {
+            *cursor -= 1;
+        }
+        Key::Backspace => {
+            filter.pop();
+            *cursor = 0;
+        }
+        Key::Char(c) => {
+            filter.push(c);
+            *cursor = 0;
+        }
+        Key::Enter => {
+            if let Some(mailbox) = matches.get(*cursor) {
+                return MovePick::Chosen(mailbox);
+            }
+        }
+        _ => {}
+    }
+    MovePick::Continue
+}
+
+/// Config settings re-read on SIGHUP that views keep their own copies of.
+#[derive(Clone, Debug)]
+pub struct ReloadedSettings {
+    pub archive_folder: String,
+    pub deleted_folder: String,
+    pub spam_folder: String,
+    pub snooze_folder: String,
+    pub sync_interval_secs: Option<u64>,
+    pub sync_all_mailboxes: bool,
+}
+
+impl ReloadedSettings {
+    /// Idle-sync interval for a single mailbox's list, as the mailbox list
+    /// passes it on when opening one.
+    pub fn mailbox_sync_secs(&self) -> Option<u64> {
+        self.sync_interval_secs.filter(|_| self.sync_all_mailboxes)
+    }
+}
+
+/// `[ui]`/`[mail]` settings the mailbox and email lists are built with.
+/// The mailbox list keeps one and hands it to each email list it opens.
+#[derive(Clone, Debug)]
+pub struct ViewOptions {
+    pub page_size: u32,
+    pub scrolloff: usize,
+    pub browser: Option<String>,
+    pub download_dir: Option<PathBuf>,
+    pub image_protocol: Option<ImageProtocol>,
+    pub block_remote_content: bool,
+    pub archive_folder: String,
+    pub deleted_folder: String,
+    pub spam_folder: String,
+    pub snooze_folder: String,
+    pub quick_move: BTreeMap<char, String>,
+    pub keymap: Keymap,
+    pub confirm_destructive: bool,
+    pub preview_pane: bool,
+    pub sort: SortOrder,
+    pub date_format: DateFormat,
+    pub retention_policies: Vec<RetentionPolicyConfig>,
+    pub saved_searches: Vec<SavedSearchConfig>,
+    pub sync_interval_secs: Option<u64>,
+    pub sync_all_mailboxes: bool,
+    pub notify_command: Option<String>,
+    pub mailbox_format: String,
+    pub hide_empty_mailboxes: bool,
+    pub restore_session: bool,
+    /// How replies quote and forwards introduce the original message.
+    pub quote: QuoteStyle,
+    /// The active account's `AccountConfig::trusted_authserv_ids`.
+    pub authserv_ids: Vec<String>,
+}
+
+impl ViewOptions {
+    pub fn from_config(config: &Config, image_protocol: Option<ImageProtocol>) -> Self {
+        ViewOptions {
+            page_size: config.ui.page_size,
+            scrolloff: config.ui.scrolloff,
+            browser: config.ui.browser.clone(),
+            download_dir: config.ui.download_dir.clone(),
+            image_protocol,
+            block_remote_content: config.ui.block_remote_content,
+            archive_folder: config.mail.archive_folder.clone(),
+            deleted_folder: config.mail.deleted_folder.clone(),
+            spam_folder: config.mail.spam_folder.clone(),
+            snooze_folder: config.mail.snooze_folder.clone(),
+            quick_move: config.mail.quick_move.clone(),
+            keymap: config.keybindings.clone(),
+            confirm_destructive: config.ui.confirm_destructive,
+            preview_pane: config.ui.preview_pane,
+            sort: config.ui.sort,
+            date_format: config.ui.date_format.clone(),
+            retention_policies: config.mail.retention_policies.clone(),
+            saved_searches: config.saved_searches.clone(),
+            sync_interval_secs: config.ui.sync_interval_secs,
+            sync_all_mailboxes: config.ui.sync_all_mailboxes,
+            notify_command: config.ui.notify_command.clone(),
+            mailbox_format: config.ui.mailbox_format.clone(),
+            hide_empty_mailboxes: config.ui.hide_empty_mailboxes,
+            restore_session: config.ui.restore_session,
+            quote: QuoteStyle::from_config(&config.ui),
+            authserv_ids: config
+                .accounts
+                .first()
+                .map(AccountConfig::trusted_authserv_ids)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Take the settings a SIGHUP re-read.
+    pub fn apply_reload(&mut self, settings: &ReloadedSettings) {
+        self.archive_folder = settings.archive_folder.clone();
+        self.deleted_folder = settings.deleted_folder.clone();
+        self.spam_folder = settings.spam_folder.clone();
+        self.snooze_folder = settings.snooze_folder.clone();
+        self.sync_interval_secs = settings.sync_interval_secs;
+        self.sync_all_mailboxes = settings.sync_all_mailboxes;
+    }
+
+    /// Idle-sync interval for a single mailbox's list.
+    pub fn mailbox_sync_secs(&self) -> Option<u64> {
+        self.sync_interval_secs.filter(|_| self.sync_all_mailboxes)
+    }
+}
+
+#[cfg(test)]
+pub fn test_view_options() -> ViewOptions {
+    ViewOptions {
+        page_size: 50,
+        scrolloff: 1,
+        browser: None,
+        download_dir: None,
+        image_protocol: None,
+        block_remote_content: true,
+        archive_folder: "Archive".to_string(),
+        deleted_folder: "Trash".to_string(),
+        spam_folder: "junk".to_string(),
+        snooze_folder: "Snoozed".to_string(),
+        quick_move: BTreeMap::new(),
+        keymap: Keymap::default(),
+        confirm_destructive: false,
+        preview_pane: false,
+        sort: SortOrder::default(),
+        date_format: DateFormat::Iso,
+        retention_policies: Vec::new(),
+        saved_searches: Vec::new(),
+        sync_interval_secs: None,
+        sync_all_mailboxes: false,
+        notify_command: None,
+        mailbox_format: "{name}".to_string(),
+        hide_empty_mailboxes: false,
+        restore_session: false,
+        quote: QuoteStyle::default(),
+        authserv_ids: Vec::new(),
     }
-    format!("{:02}:{:02}:{:02}", tm.tm_hour, tm.tm_min, tm.tm_sec)
 }
 
 pub enum ViewAction {
@@ -31,12 +259,19 @@ pub enum ViewAction {
     Pop,
     Quit,
     Compose(crate::compose::ComposeDraft),
+    /// Reopen a saved draft (by `crate::drafts` id) in the composer.
+    ResumeDraft(String),
     SwitchAccount(String),
+    /// Re-fetch unread counts for every configured account.
+    RefreshAccounts,
 }
 
 pub trait View {
     fn render(&self, term: &mut Terminal) -> io::Result<()>;
     fn handle_key(&mut self, key: Key, term_rows: u16) -> ViewAction;
+    /// Called after every `handle_key`, for work that follows the cursor
+    /// wherever the key moved it.
+    fn after_key(&mut self) {}
     /// Whether this view wants terminal mouse tracking enabled.
     fn wants_mouse(&self) -> bool {
         true
@@ -60,16 +295,29 @@ pub trait View {
     fn on_reveal(&mut self) -> bool {
         false
     }
+    /// Called with the column width HTML bodies should be laid out at when
+    /// this view is pushed and after every resize. Returns true if it
+    /// changed state and should re-render.
+    fn on_render_width(&mut self, _width: usize) -> bool {
+        false
+    }
+    /// Called after config and rules were re-read on SIGHUP, with the new
+    /// settings (`None` if the config failed to parse) and, for the top
+    /// view only, a message for the status bar.
+    fn on_reload(&mut self, _settings: Option<&ReloadedSettings>, _message: Option<&str>) {}
 }
 
 pub struct ViewStack {
     views: Vec<Box<dyn View>>,
+    render_width: usize,
 }
 
 impl ViewStack {
-    pub fn new(initial: Box<dyn View>) -> Self {
+    pub fn new(mut initial: Box<dyn View>, render_width: usize) -> Self {
+        initial.on_render_width(render_width);
         ViewStack {
             views: vec![initial],
+            render_width,
         }
     }
 
@@ -81,9 +329,11 @@ impl ViewStack {
     }
 
     pub fn handle_key(&mut self, key: Key, term_rows: u16) -> Option<ViewAction> {
-        self.views
-            .last_mut()
-            .map(|view| view.handle_key(key, term_rows))
+        self.views.last_mut().map(|view| {
+            let action = view.handle_key(key, term_rows);
+            view.after_key();
+            action
+        })
     }
 
     /// Route a backend response to all views (top-most can trigger re-render).
@@ -106,10 +356,32 @@ impl ViewStack {
         self.views.last().map(|v| v.as_ref())
     }
 
-    pub fn push(&mut self, view: Box<dyn View>) {
+    pub fn push(&mut self, mut view: Box<dyn View>) {
+        view.on_render_width(self.render_width);
         self.views.push(view);
     }
 
+    /// Pass a new HTML layout width to every view (see
+    /// [`View::on_render_width`]).
+    pub fn set_render_width(&mut self, width: usize) {
+        if width == self.render_width {
+            return;
+        }
+        self.render_width = width;
+        for view in &mut self.views {
+            view.on_render_width(width);
+        }
+    }
+
+    /// Pass reloaded settings to every view; only the top one shows
+    /// `message` (see [`View::on_reload`]).
+    pub fn reload(&mut self, settings: Option<&ReloadedSettings>, message: &str) {
+        let top = self.views.len().saturating_sub(1);
+        for (idx, view) in self.views.iter_mut().enumerate() {
+            view.on_reload(settings, (idx == top).then_some(message));
+        }
+    }
+
     pub fn pop(&mut self) -> bool {
         if self.views.len() > 1 {
             self.views.pop();
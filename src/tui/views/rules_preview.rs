@@ -6,23 +6,63 @@ use crate::tui::views::{View, ViewAction};
 use std::io;
 
 pub struct RulesPreviewView {
+    mailbox_name: String,
+    preview: RulesDryRunResult,
+    /// List every scanned message, not just those with rule actions.
+    show_all: bool,
     lines: Vec<String>,
     scroll: usize,
 }
 
 impl RulesPreviewView {
     pub fn new(mailbox_name: String, preview: RulesDryRunResult) -> Self {
+        let mut view = RulesPreviewView {
+            mailbox_name,
+            preview,
+            show_all: false,
+            lines: Vec::new(),
+            scroll: 0,
+        };
+        view.build_lines();
+        view
+    }
+
+    fn build_lines(&mut self) {
+        let preview = &self.preview;
+        let unmatched = preview.scanned_emails.iter().filter(|e| !e.matched).count();
         let mut lines = Vec::new();
         lines.push(format!(
-            "Rules dry-run for '{}' (scanned: {}, matches: {}, actions: {})",
-            mailbox_name, preview.scanned, preview.matched_rules, preview.actions
+            "Rules dry-run for '{}' (scanned: {}, matches: {}, actions: {}, no rule: {})",
+            self.mailbox_name, preview.scanned, preview.matched_rules, preview.actions, unmatched
         ));
         lines.push(String::new());
 
-        if preview.entries.is_empty() {
+        if self.show_all {
+            for email in &preview.scanned_emails {
+                lines.push(format!(
+                    "{} | {} | {}",
+                    email.received_at, email.from, email.subject
+                ));
+                let mut applied = false;
+                for entry in preview.entries.iter().filter(|e| e.id == email.id) {
+                    applied = true;
+                    lines.push(format!(
+                        "  rule={} actions={}",
+                        entry.rule_name,
+                        entry.actions.join(", ")
+                    ));
+                }
+                if !email.matched {
+                    lines.push("  no rule matched".to_string());
+                } else if !applied {
+                    lines.push("  matched, nothing left to do".to_string());
+                }
+                lines.push(String::new());
+            }
+        } else if preview.entries.is_empty() {
             lines.push("No rule actions would be applied.".to_string());
         } else {
-            for entry in preview.entries {
+            for entry in &preview.entries {
                 lines.push(format!(
                     "{} | {} | {}",
                     entry.received_at, entry.from, entry.subject
@@ -36,7 +76,8 @@ impl RulesPreviewView {
             }
         }
 
-        RulesPreviewView { lines, scroll: 0 }
+        self.lines = lines;
+        self.scroll = 0;
     }
 }
 
@@ -66,9 +107,15 @@ impl View for RulesPreviewView {
         term.move_to(term.rows, 1)?;
         term.set_status()?;
         let status = format!(
-            " Rules dry-run | line {}/{} | q/Esc/Enter:close n/p:scroll",
+            " Rules dry-run ({}) | line {}/{} | q/Esc/Enter:close n/p:scroll a:{}",
+            if self.show_all { "all" } else { "matched" },
             self.scroll + 1,
-            self.lines.len()
+            self.lines.len(),
+            if self.show_all {
+                "show matched"
+            } else {
+                "show all"
+            }
         );
         term.write_truncated(&status, term.cols)?;
         let remaining = (term.cols as usize).saturating_sub(status.len());
@@ -84,6 +131,11 @@ impl View for RulesPreviewView {
         let page = (term_rows as usize).saturating_sub(1);
         match key {
             Key::Char('q') | Key::Escape | Key::Enter => ViewAction::Pop,
+            Key::Char('a') => {
+                self.show_all = !self.show_all;
+                self.build_lines();
+                ViewAction::Continue
+            }
             Key::Char('n') | Key::Char('j') | Key::Down => {
                 if self.scroll + 1 < self.lines.len() {
                     self.scroll += 1;
@@ -132,3 +184,53 @@ impl View for RulesPreviewView {
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{RulesDryRunEntry, RulesScannedEmail};
+
+    fn scanned(id: &str, matched: bool) -> RulesScannedEmail {
+        RulesScannedEmail {
+            id: id.to_string(),
+            received_at: "2025-01-01".to_string(),
+            from: "a@example.com".to_string(),
+            subject: format!("Subject {}", id),
+            matched,
+        }
+    }
+
+    #[test]
+    fn a_toggles_unmatched_messages() {
+        let preview = RulesDryRunResult {
+            scanned: 3,
+            matched_rules: 1,
+            actions: 1,
+            entries: vec![RulesDryRunEntry {
+                id: "e1".to_string(),
+                received_at: "2025-01-01".to_string(),
+                from: "a@example.com".to_string(),
+                subject: "Subject e1".to_string(),
+                rule_name: "archive".to_string(),
+                actions: vec!["move Archive".to_string()],
+            }],
+            scanned_emails: vec![
+                scanned("e1", true),
+                scanned("e2", true),
+                scanned("e3", false),
+            ],
+        };
+        let mut view = RulesPreviewView::new("INBOX".to_string(), preview);
+        assert!(view.lines[0].ends_with("no rule: 1)"));
+        assert!(!view.lines.iter().any(|l| l.contains("Subject e3")));
+
+        view.handle_key(Key::Char('a'), 24);
+        let text = view.lines.join("\n");
+        assert!(text.contains("Subject e1\n  rule=archive actions=move Archive"));
+        assert!(text.contains("Subject e2\n  matched, nothing left to do"));
+        assert!(text.contains("Subject e3\n  no rule matched"));
+
+        view.handle_key(Key::Char('a'), 24);
+        assert!(!view.lines.iter().any(|l| l.contains("Subject e3")));
+    }
+}
@@ -1,14 +1,16 @@
 use crate::backend::{BackendCommand, BackendResponse, EmailMutationAction};
-use crate::compose;
+use crate::compose::{self, SharedIdentities};
 use crate::jmap::types::{Email, Mailbox};
 use crate::rules;
+use crate::tui::graphics::ImageProtocol;
 use crate::tui::input::Key;
 use crate::tui::screen::Terminal;
-use crate::tui::views::email_view::{EmailNavEntry, EmailView};
+use crate::tui::views::email_view::{EmailNavEntry, EmailView, SharedBodyChoice};
 use crate::tui::views::help::HelpView;
-use crate::tui::views::{View, ViewAction};
+use crate::tui::views::{ReloadedSettings, View, ViewAction};
 use std::collections::HashMap;
 use std::io;
+use std::path::PathBuf;
 use std::sync::mpsc;
 
 enum PendingWriteOp {
@@ -29,6 +31,9 @@ enum PendingWriteOp {
 pub struct ThreadView {
     cmd_tx: mpsc::Sender<BackendCommand>,
     reply_from_address: String,
+    identities: SharedIdentities,
+    signature: compose::SignatureOptions,
+    quote: compose::QuoteStyle,
     thread_id: String,
     subject: String,
     scrolloff: usize,
@@ -44,11 +49,18 @@ pub struct ThreadView {
     mailboxes: Vec<Mailbox>,
     archive_folder: String,
     deleted_folder: String,
+    spam_folder: String,
     can_expire_now: bool,
     /// If set, only show emails in this mailbox (same-folder mode).
     /// If None, show all emails across folders (cross-folder mode).
     filter_mailbox_id: Option<String>,
     browser: Option<String>,
+    download_dir: Option<PathBuf>,
+    image_protocol: Option<ImageProtocol>,
+    block_remote_content: bool,
+    body_choice: SharedBodyChoice,
+    /// Passed to opened messages for their `Authentication-Results` summary.
+    authserv_ids: Vec<String>,
 }
 
 impl ThreadView {
@@ -56,15 +68,23 @@ impl ThreadView {
     pub fn new(
         cmd_tx: mpsc::Sender<BackendCommand>,
         reply_from_address: String,
+        identities: SharedIdentities,
+        signature: compose::SignatureOptions,
+        quote: compose::QuoteStyle,
         thread_id: String,
         subject: String,
         scrolloff: usize,
         mailboxes: Vec<Mailbox>,
         archive_folder: String,
         deleted_folder: String,
+        spam_folder: String,
         can_expire_now: bool,
         filter_mailbox_id: Option<String>,
         browser: Option<String>,
+        download_dir: Option<PathBuf>,
+        image_protocol: Option<ImageProtocol>,
+        block_remote_content: bool,
+        body_choice: SharedBodyChoice,
     ) -> Self {
         let _ = cmd_tx.send(BackendCommand::QueryThreadEmails {
             thread_id: thread_id.clone(),
@@ -72,6 +92,9 @@ impl ThreadView {
         ThreadView {
             cmd_tx,
             reply_from_address,
+            identities,
+            signature,
+            quote,
             thread_id,
             subject,
             scrolloff,
@@ -87,12 +110,25 @@ impl ThreadView {
             mailboxes,
             archive_folder,
             deleted_folder,
+            spam_folder,
             can_expire_now,
             filter_mailbox_id,
             browser,
+            download_dir,
+            image_protocol,
+            block_remote_content,
+            body_choice,
+            authserv_ids: Vec::new(),
         }
     }
 
+    /// Trust `Authentication-Results` headers from these authserv-ids in
+    /// the messages this view opens.
+    pub fn with_authserv_ids(mut self, ids: Vec<String>) -> Self {
+        self.authserv_ids = ids;
+        self
+    }
+
     fn is_unread(email: &Email) -> bool {
         !email.keywords.contains_key("$seen")
     }
@@ -263,6 +299,9 @@ impl ThreadView {
         let view = EmailView::new(
             self.cmd_tx.clone(),
             self.reply_from_address.clone(),
+            self.identities.clone(),
+            self.signature.clone(),
+            self.quote.clone(),
             email_id.clone(),
             nav_entries,
             self.cursor,
@@ -270,8 +309,14 @@ impl ThreadView {
             self.mailboxes.clone(),
             self.archive_folder.clone(),
             self.deleted_folder.clone(),
+            self.spam_folder.clone(),
             self.browser.clone(),
-        );
+            self.download_dir.clone(),
+            self.image_protocol,
+            self.block_remote_content,
+            self.body_choice.clone(),
+        )
+        .with_authserv_ids(self.authserv_ids.clone());
         let _ = self.cmd_tx.send(BackendCommand::GetEmail {
             id: email_id.clone(),
         });
@@ -638,7 +683,11 @@ impl View for ThreadView {
                 ViewAction::Continue
             }
             Key::Char('c') => {
-                let draft = compose::build_compose_draft(&self.reply_from_address);
+                let draft = compose::build_compose_draft(
+                    &self.reply_from_address,
+                    self.identities.borrow().for_compose(),
+                    &self.signature,
+                );
                 ViewAction::Compose(draft.into())
             }
             Key::Char('?') => ViewAction::Push(Box::new(HelpView::new())),
@@ -720,6 +769,7 @@ impl View for ThreadView {
                                 EmailMutationAction::MarkRead => "Mark read",
                                 EmailMutationAction::MarkUnread => "Mark unread",
                                 EmailMutationAction::SetFlagged(_) => "Flag update",
+                                EmailMutationAction::SetKeyword(_) => "Keyword update",
                                 EmailMutationAction::Move => "Move",
                                 EmailMutationAction::Destroy => "Expire",
                             };
@@ -734,4 +784,15 @@ impl View for ThreadView {
             _ => false,
         }
     }
+
+    fn on_reload(&mut self, settings: Option<&ReloadedSettings>, message: Option<&str>) {
+        if let Some(settings) = settings {
+            self.archive_folder = settings.archive_folder.clone();
+            self.deleted_folder = settings.deleted_folder.clone();
+            self.spam_folder = settings.spam_folder.clone();
+        }
+        if let Some(message) = message {
+            self.status_message = Some(message.to_string());
+        }
+    }
 }
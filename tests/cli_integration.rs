@@ -1,6 +1,6 @@
 mod mock_jmap;
 
-use mock_jmap::MockJmapServer;
+use mock_jmap::{MockJmapServer, EXPIRED_TOKEN, LATIN1_EMAIL_ID};
 use serde_json::{json, Value};
 use std::io::{BufRead, BufReader, Write};
 use std::path::{Path, PathBuf};
@@ -10,7 +10,7 @@ struct CliHarness {
     child: Child,
     stdin: std::process::ChildStdin,
     reader: BufReader<std::process::ChildStdout>,
-    _server: MockJmapServer,
+    server: MockJmapServer,
     _config_dir: tempfile::TempDir,
 }
 
@@ -20,11 +20,15 @@ impl CliHarness {
     }
 
     fn start_with_mail_config(mail_config: &str) -> Self {
-        Self::start_with_opts(mail_config, false, None, None)
+        Self::start_with_opts(mail_config, false, None, None, None)
     }
 
     fn start_with_data_home(data_home: PathBuf) -> Self {
-        Self::start_with_opts("", false, None, Some(data_home))
+        Self::start_with_opts("", false, None, Some(data_home), None)
+    }
+
+    fn start_with_rules(rules: &str, cache_home: &Path) -> Self {
+        Self::start_with_opts("", false, Some(cache_home.to_path_buf()), None, Some(rules))
     }
 
     fn start_with_opts(
@@ -32,6 +36,7 @@ impl CliHarness {
         offline: bool,
         cache_home: Option<PathBuf>,
         data_home: Option<PathBuf>,
+        rules: Option<&str>,
     ) -> Self {
         let server = MockJmapServer::start();
         let config_dir = tempfile::tempdir().expect("create temp dir");
@@ -47,9 +52,12 @@ password_command = "echo test"
 {}
 "#,
             server.url(),
-            mail_config
+            mail_config.replace("@SERVER@", &server.url())
         );
         std::fs::write(&config_path, config_content).expect("write config");
+        if let Some(rules) = rules {
+            std::fs::write(config_dir.path().join("rules.toml"), rules).expect("write rules");
+        }
 
         let tmc_bin = env!("CARGO_BIN_EXE_tmc");
         let mut command = Command::new(tmc_bin);
@@ -80,7 +88,7 @@ password_command = "echo test"
             child,
             stdin,
             reader,
-            _server: server,
+            server,
             _config_dir: config_dir,
         }
     }
@@ -89,7 +97,23 @@ password_command = "echo test"
         let line = serde_json::to_string(&cmd).expect("serialize command");
         writeln!(self.stdin, "{}", line).expect("write to stdin");
         self.stdin.flush().expect("flush stdin");
+        self.read()
+    }
 
+    /// Send a command with `emit_progress` and collect the progress lines
+    /// that precede its response.
+    fn send_with_progress(&mut self, mut cmd: Value) -> (Vec<Value>, Value) {
+        cmd["emit_progress"] = json!(true);
+        let mut progress = Vec::new();
+        let mut resp = self.send(cmd);
+        while let Some(p) = resp.get("progress") {
+            progress.push(p.clone());
+            resp = self.read();
+        }
+        (progress, resp)
+    }
+
+    fn read(&mut self) -> Value {
         let mut response_line = String::new();
         self.reader
             .read_line(&mut response_line)
@@ -138,7 +162,7 @@ fn test_connect_and_list_mailboxes() {
     assert_eq!(resp["ok"], true, "list_mailboxes failed: {}", resp);
 
     let mailboxes = resp["mailboxes"].as_array().expect("mailboxes array");
-    assert_eq!(mailboxes.len(), 3);
+    assert_eq!(mailboxes.len(), 4);
 
     let names: Vec<&str> = mailboxes
         .iter()
@@ -147,6 +171,249 @@ fn test_connect_and_list_mailboxes() {
     assert!(names.contains(&"INBOX"));
     assert!(names.contains(&"Archive"));
     assert!(names.contains(&"Trash"));
+    assert!(names.contains(&"Sent"));
+}
+
+#[test]
+fn test_connect_reuses_pooled_backends() {
+    let dir = tempfile::tempdir().unwrap();
+    let log = dir.path().join("password-calls");
+    let mut h = CliHarness::start_with_mail_config(&format!(
+        r#"
+[account.other]
+well_known_url = "@SERVER@/.well-known/jmap"
+username = "other@example.com"
+password_command = "echo >> {}; echo test"
+"#,
+        log.display()
+    ));
+    let calls = || {
+        std::fs::read_to_string(&log)
+            .unwrap_or_default()
+            .lines()
+            .count()
+    };
+
+    let resp = h.send(json!({"command": "connect", "account": "other"}));
+    assert_eq!(resp["reused"], false, "connect failed: {}", resp);
+    let resp = h.send(json!({"command": "connect", "account": "test"}));
+    assert_eq!(resp["reused"], false, "connect failed: {}", resp);
+    assert_eq!(calls(), 1);
+
+    let resp = h.send(json!({"command": "connect", "account": "other"}));
+    assert_eq!(resp["reused"], true, "reconnect failed: {}", resp);
+    assert_eq!(calls(), 1);
+    assert_eq!(h.send(json!({"command": "list_mailboxes"}))["ok"], true);
+    let status = h.send(json!({"command": "status"}));
+    assert_eq!(status["account"], "other");
+    assert_eq!(status["pooled"], json!(["test"]));
+
+    let resp = h.send(json!({"command": "disconnect"}));
+    assert_eq!(resp["account"], "other");
+    let status = h.send(json!({"command": "status"}));
+    assert_eq!(status["connected"], false);
+    assert_eq!(status["pooled"], json!(["test"]));
+    assert_eq!(
+        h.send(json!({"command": "disconnect", "account": "other"}))["ok"],
+        false
+    );
+
+    let resp = h.send(json!({"command": "connect", "account": "other"}));
+    assert_eq!(resp["reused"], false, "connect failed: {}", resp);
+    assert_eq!(calls(), 2);
+
+    // A fresh connect replaces the running backend.
+    let resp = h.send(json!({"command": "connect", "account": "other", "fresh": true}));
+    assert_eq!(resp["reused"], false, "connect failed: {}", resp);
+    assert_eq!(calls(), 3);
+    assert_eq!(h.send(json!({"command": "list_mailboxes"}))["ok"], true);
+    let status = h.send(json!({"command": "status"}));
+    assert_eq!(status["pooled"], json!(["test"]));
+}
+
+#[test]
+fn test_unread_counts() {
+    let mut h = CliHarness::start();
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+
+    let resp = h.send(json!({"command": "unread_counts"}));
+    assert_eq!(resp["ok"], true, "unread_counts failed: {}", resp);
+    assert_eq!(
+        resp["mailboxes"].as_array().expect("mailboxes array").len(),
+        4
+    );
+
+    let resp = h.send(json!({"command": "unread_counts", "only_unread": true}));
+    assert_eq!(resp["ok"], true, "unread_counts failed: {}", resp);
+    assert_eq!(
+        resp["mailboxes"],
+        json!([{"mailbox_id": "mbox-inbox", "name": "INBOX", "unread": 2, "total": 4}])
+    );
+}
+
+#[test]
+fn test_mailbox_refresh_uses_mailbox_changes() {
+    let mut h = CliHarness::start();
+    h.server.use_live_counts();
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+    let inbox_unread = |resp: &Value| {
+        resp["mailboxes"]
+            .as_array()
+            .expect("mailboxes array")
+            .iter()
+            .find(|m| m["name"] == "INBOX")
+            .map(|m| m["unread_emails"].clone())
+    };
+
+    let resp = h.send(json!({"command": "list_mailboxes"}));
+    assert_eq!(resp["ok"], true, "list_mailboxes failed: {}", resp);
+    assert_eq!(inbox_unread(&resp), Some(json!(2)));
+    h.server.take_method_calls();
+
+    // Nothing changed: only Mailbox/changes goes out.
+    let resp = h.send(json!({"command": "list_mailboxes"}));
+    assert_eq!(inbox_unread(&resp), Some(json!(2)));
+    assert_eq!(h.server.take_method_calls(), ["Mailbox/changes"]);
+
+    // Only the mailbox reported as updated is fetched again.
+    h.server.set_read("email-001", false);
+    let resp = h.send(json!({"command": "list_mailboxes"}));
+    assert_eq!(inbox_unread(&resp), Some(json!(3)));
+    assert_eq!(resp["mailboxes"].as_array().unwrap().len(), 4);
+    let calls = h.server.take_calls();
+    let names: Vec<&str> = calls.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["Mailbox/changes", "Mailbox/get"]);
+    assert_eq!(calls[1].1["ids"], json!(["mbox-inbox"]));
+}
+
+#[test]
+fn test_query_emails_sync_uses_email_changes() {
+    let mut h = CliHarness::start();
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+    let query = json!({"command": "query_emails", "mailbox_id": "mbox-inbox", "sync": true});
+    let unread = |resp: &Value| -> Vec<String> {
+        resp["emails"]
+            .as_array()
+            .expect("emails array")
+            .iter()
+            .filter(|e| e["is_read"] == false)
+            .filter_map(|e| e["id"].as_str().map(str::to_string))
+            .collect()
+    };
+
+    let resp = h.send(query.clone());
+    assert_eq!(resp["ok"], true, "query_emails failed: {}", resp);
+    assert_eq!(resp["loaded"], 4);
+    h.server.take_method_calls();
+
+    // Nothing changed: the page comes back without a query.
+    let resp = h.send(query.clone());
+    assert_eq!(resp["loaded"], 4);
+    assert_eq!(h.server.take_method_calls(), ["Email/changes"]);
+
+    // Only the updated and the created email are fetched.
+    h.server.set_read("email-001", false);
+    h.server
+        .deliver_reply("email-002", "email-002-reply", "2099-01-01T00:00:00Z");
+    let resp = h.send(query.clone());
+    assert_eq!(resp["ok"], true, "query_emails failed: {}", resp);
+    assert_eq!(resp["loaded"], 5);
+    assert_eq!(resp["emails"][0]["id"], "email-002-reply");
+    let mut ids = unread(&resp);
+    ids.sort();
+    assert_eq!(
+        ids,
+        ["email-001", "email-002", "email-002-reply", "email-003"]
+    );
+    let calls = h.server.take_calls();
+    let names: Vec<&str> = calls.iter().map(|(name, _)| name.as_str()).collect();
+    // Thread counts for the page follow.
+    assert_eq!(names[..3], ["Email/changes", "Email/query", "Email/get"]);
+    let mut fetched: Vec<&str> = calls[2].1["ids"]
+        .as_array()
+        .expect("fetched ids")
+        .iter()
+        .filter_map(|id| id.as_str())
+        .collect();
+    fetched.sort();
+    assert_eq!(fetched, ["email-001", "email-002-reply"]);
+}
+
+#[test]
+fn test_complete_address_merges_contacts_and_seen_mail() {
+    let cache_home = tempfile::tempdir().unwrap();
+    let runs = cache_home.path().join("contacts-runs");
+    let mut h = CliHarness::start_with_opts(
+        &format!(
+            "[contacts]\ncommand = \"echo run >> {}; printf 'Andrea Alvarez <andrea@example.org>\\\\nAlice <alice@example.com>'\"",
+            runs.display()
+        ),
+        false,
+        Some(cache_home.path().to_path_buf()),
+        None,
+        None,
+    );
+
+    // Address book entries work before connecting.
+    let resp = h.send(json!({"command": "complete_address", "prefix": "alv"}));
+    assert_eq!(resp["ok"], true, "complete_address failed: {}", resp);
+    assert_eq!(
+        resp["contacts"][0]["display"],
+        "Andrea Alvarez <andrea@example.org>"
+    );
+
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+    let resp = h.send(json!({"command": "query_emails", "mailbox_id": "mbox-inbox"}));
+    assert_eq!(resp["ok"], true, "query_emails failed: {}", resp);
+
+    let resp = h.send(json!({"command": "complete_address", "prefix": "a"}));
+    assert_eq!(resp["ok"], true, "complete_address failed: {}", resp);
+    assert_eq!(
+        resp["contacts"],
+        json!([
+            {"name": "Alice", "email": "alice@example.com", "display": "Alice <alice@example.com>", "count": 1, "contact": true},
+            {"name": "Andrea Alvarez", "email": "andrea@example.org", "display": "Andrea Alvarez <andrea@example.org>", "count": 0, "contact": true},
+        ])
+    );
+
+    let resp = h.send(json!({"command": "compose_draft"}));
+    let recent: Vec<&str> = resp["recent"]
+        .as_array()
+        .expect("recent array")
+        .iter()
+        .filter_map(|v| v.as_str())
+        .collect();
+    assert!(
+        recent.contains(&"Bob <bob@example.com>"),
+        "recent: {:?}",
+        recent
+    );
+    assert!(
+        !recent.iter().any(|a| a.contains("test@example.com")),
+        "own address listed: {:?}",
+        recent
+    );
+    let draft = resp["draft"].as_str().unwrap();
+    let recent_line = draft
+        .lines()
+        .find(|l| l.starts_with("Recent: "))
+        .expect("Recent: line in the draft");
+    assert!(recent_line.contains("Bob <bob@example.com>"), "{}", draft);
+
+    // The contacts command ran once for both completions.
+    assert_eq!(std::fs::read_to_string(&runs).unwrap(), "run\n");
 }
 
 #[test]
@@ -170,9 +437,91 @@ fn test_query_and_get_email() {
     assert_eq!(resp["ok"], true, "get_email failed: {}", resp);
     assert_eq!(resp["id"], "email-001");
     assert_eq!(resp["subject"], "Hello World");
+    assert_eq!(resp["received_at"], "2025-01-15T10:30:00Z");
+    assert_eq!(resp["received_epoch"], 1_736_937_000);
+    assert_eq!(resp["received_date"], "2025-01-15");
     assert!(resp["body"].as_str().unwrap().contains("body of email 001"));
 }
 
+#[test]
+fn test_query_emails_cursor_pagination() {
+    let mut h = CliHarness::start();
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+
+    let first = h.send(json!({"command": "query_emails", "mailbox_id": "mbox-inbox", "limit": 3}));
+    assert_eq!(first["emails"].as_array().unwrap().len(), 3);
+    let cursor = first["next_cursor"]
+        .as_str()
+        .expect("next_cursor")
+        .to_string();
+
+    let second = h.send(json!({"command": "query_emails", "cursor": cursor, "limit": 3}));
+    assert_eq!(second["ok"], true, "cursor query failed: {}", second);
+    assert_eq!(second["position"], 3);
+    assert_eq!(second["emails"].as_array().unwrap().len(), 1);
+    assert!(second["next_cursor"].is_null());
+
+    let ids = |resp: &Value| -> Vec<String> {
+        resp["emails"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["id"].as_str().unwrap().to_string())
+            .collect()
+    };
+    assert!(ids(&second).iter().all(|id| !ids(&first).contains(id)));
+
+    let resp =
+        h.send(json!({"command": "query_emails", "cursor": cursor, "mailbox_id": "mbox-archive"}));
+    assert!(
+        resp["error"].as_str().unwrap().contains("mbox-inbox"),
+        "{}",
+        resp
+    );
+    let resp = h.send(json!({"command": "query_emails", "cursor": cursor, "search": "hello"}));
+    assert!(
+        resp["error"].as_str().unwrap().contains("search"),
+        "{}",
+        resp
+    );
+    let resp = h.send(json!({"command": "query_emails", "cursor": cursor, "position": 0}));
+    assert_eq!(resp["ok"], false);
+    let resp = h.send(json!({"command": "query_emails", "cursor": "not a cursor"}));
+    assert_eq!(resp["error"], "invalid 'cursor'");
+}
+
+#[test]
+fn test_get_emails_batch() {
+    let mut h = CliHarness::start();
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+
+    let resp = h.send(json!({
+        "command": "get_emails",
+        "ids": ["email-003", "email-001", "email-missing"],
+        "max_body_chars": 4
+    }));
+    assert_eq!(resp["ok"], true, "get_emails failed: {}", resp);
+    let emails = resp["emails"].as_array().expect("emails array");
+    let ids: Vec<&str> = emails.iter().map(|e| e["id"].as_str().unwrap()).collect();
+    assert_eq!(ids, vec!["email-003", "email-001"]);
+    assert_eq!(emails[1]["body"], "This");
+    assert_eq!(emails[1]["body_truncated"], true);
+    assert_eq!(resp["missing"], json!(["email-missing"]));
+
+    let resp = h.send(json!({"command": "get_emails", "ids": ["email-002"], "headers_only": true}));
+    assert_eq!(resp["ok"], true, "get_emails failed: {}", resp);
+    assert!(resp["emails"][0].get("body").is_none());
+
+    let resp = h.send(json!({"command": "get_emails"}));
+    assert_eq!(resp["ok"], false);
+}
+
 #[test]
 fn test_archive_and_delete_work_without_preloading_mailboxes() {
     let mut h = CliHarness::start();
@@ -195,6 +544,194 @@ fn test_archive_and_delete_work_without_preloading_mailboxes() {
     assert_eq!(m3[0], "mbox-trash");
 }
 
+#[test]
+fn test_empty_mailbox_destroys_trash_only() {
+    let mut h = CliHarness::start();
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+    for id in ["email-002", "email-003"] {
+        let resp = h.send(json!({"command": "delete_email", "id": id}));
+        assert_eq!(resp["ok"], true, "delete failed: {}", resp);
+    }
+
+    let resp = h.send(json!({"command": "empty_mailbox", "mailbox_id": "mbox-inbox"}));
+    assert_eq!(resp["ok"], false, "inbox must not be emptied: {}", resp);
+
+    h.server.take_calls();
+    let resp = h.send(json!({"command": "empty_mailbox", "mailbox_id": "mbox-trash"}));
+    assert_eq!(resp["ok"], true, "empty_mailbox failed: {}", resp);
+    assert_eq!(resp["destroyed"], 2);
+    let mut destroyed: Vec<Value> = h
+        .server
+        .take_calls()
+        .into_iter()
+        .filter(|(name, _)| name == "Email/set")
+        .flat_map(|(_, args)| args["destroy"].as_array().cloned().unwrap_or_default())
+        .collect();
+    destroyed.sort_by_key(|id| id.to_string());
+    assert_eq!(destroyed, vec![json!("email-002"), json!("email-003")]);
+
+    let resp = h.send(json!({"command": "query_emails", "mailbox_id": "mbox-trash"}));
+    assert_eq!(resp["emails"].as_array().map(|a| a.len()), Some(0));
+
+    let resp = h.send(json!({"command": "empty_mailbox", "mailbox_id": "mbox-trash"}));
+    assert_eq!(resp["ok"], true, "empty_mailbox failed: {}", resp);
+    assert_eq!(resp["destroyed"], 0);
+}
+
+#[test]
+fn test_retention_preview_and_execute() {
+    let mut h = CliHarness::start();
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+    let resp = h.send(json!({"command": "retention_preview"}));
+    assert_eq!(resp["ok"], false, "no policies configured: {}", resp);
+
+    let resp = h.send(json!({"command": "delete_email", "id": "email-003"}));
+    assert_eq!(resp["ok"], true, "delete failed: {}", resp);
+
+    let policies = json!([{"name": "trash", "folder": "Trash", "days": 30}]);
+    let resp = h.send(json!({"command": "retention_preview", "policies": policies}));
+    assert_eq!(resp["ok"], true, "retention_preview failed: {}", resp);
+    assert_eq!(resp["count"], 1);
+    assert_eq!(resp["candidates"][0]["id"], "email-003");
+    assert_eq!(resp["candidates"][0]["policy"], "trash");
+
+    let resp = h.send(json!({"command": "retention_execute", "policies": policies}));
+    assert_eq!(resp["ok"], true, "retention_execute failed: {}", resp);
+    assert_eq!(resp["deleted"], 1);
+
+    let resp = h.send(json!({"command": "retention_preview", "policies": policies}));
+    assert_eq!(resp["count"], 0);
+
+    let resp = h.send(json!({
+        "command": "retention_preview",
+        "policies": [{"folder": "Trash", "days": 0}],
+    }));
+    assert_eq!(resp["ok"], false);
+}
+
+#[test]
+fn test_thread_move_commands() {
+    let mut h = CliHarness::start();
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+
+    let archive_resp = h.send(json!({"command": "archive_thread", "thread_id": "thread-002"}));
+    assert_eq!(
+        archive_resp["ok"], true,
+        "archive_thread failed: {}",
+        archive_resp
+    );
+    assert_eq!(archive_resp["id"], "thread-002");
+
+    let delete_resp = h.send(json!({"command": "delete_thread", "thread_id": "thread-003"}));
+    assert_eq!(
+        delete_resp["ok"], true,
+        "delete_thread failed: {}",
+        delete_resp
+    );
+
+    let move_resp = h.send(json!({
+        "command": "move_thread",
+        "thread_id": "thread-004",
+        "to_mailbox_id": "mbox-archive"
+    }));
+    assert_eq!(move_resp["ok"], true, "move_thread failed: {}", move_resp);
+
+    let mailbox_of = |h: &mut CliHarness, id: &str| {
+        let e = h.send(json!({"command": "get_email", "id": id, "headers_only": true}));
+        e["mailbox_ids"][0].as_str().unwrap_or_default().to_string()
+    };
+    assert_eq!(mailbox_of(&mut h, "email-001"), "mbox-inbox");
+    assert_eq!(mailbox_of(&mut h, "email-002"), "mbox-archive");
+    assert_eq!(mailbox_of(&mut h, "email-003"), "mbox-trash");
+    assert_eq!(mailbox_of(&mut h, "email-004"), "mbox-archive");
+
+    let missing = h.send(json!({"command": "move_thread", "thread_id": "thread-001"}));
+    assert_eq!(missing["ok"], false);
+}
+
+#[test]
+fn test_search_all_spans_mailboxes() {
+    let mut h = CliHarness::start();
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+
+    let resp = h.send(json!({"command": "search_all", "search": "example.com"}));
+    assert_eq!(resp["ok"], true, "search_all failed: {}", resp);
+    let found: Vec<(String, String)> = resp["emails"]
+        .as_array()
+        .expect("emails array")
+        .iter()
+        .map(|e| {
+            (
+                e["id"].as_str().unwrap_or_default().to_string(),
+                e["mailbox_ids"][0].as_str().unwrap_or_default().to_string(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("email-002".to_string(), "mbox-inbox".to_string()),
+            ("email-005".to_string(), "mbox-archive".to_string()),
+            ("email-001".to_string(), "mbox-inbox".to_string()),
+        ]
+    );
+
+    let dated = h.send(json!({
+        "command": "search_all",
+        "received_after": "2025-12-01T00:00:00Z",
+        "limit": 2,
+        "position": 1,
+        "headers_only": true
+    }));
+    assert_eq!(dated["ok"], true, "search_all failed: {}", dated);
+    let ids: Vec<&str> = dated["emails"]
+        .as_array()
+        .expect("emails array")
+        .iter()
+        .filter_map(|e| e["id"].as_str())
+        .collect();
+    assert_eq!(ids, vec!["email-003", "email-002"]);
+    assert_eq!(dated["position"], 1);
+}
+
+#[test]
+fn test_watch_polls_for_mailbox_changes() {
+    let mut h = CliHarness::start();
+    h.server.use_live_counts();
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+
+    // The reply comes once the watcher has recorded its baseline.
+    let resp = h.send(json!({"command": "watch", "interval_secs": 1}));
+    assert_eq!(resp["ok"], true, "watch failed: {}", resp);
+    assert_eq!(resp["interval_secs"], 1);
+    h.server.set_read("email-001", false);
+
+    let event = h.read();
+    assert_eq!(event["event"], "mailbox_changed");
+    assert_eq!(event["mailbox_id"], "mbox-inbox");
+    assert_eq!(event["total_emails"], 4);
+    assert_eq!(event["unread_emails"], 3);
+
+    // Commands still work while watching.
+    let resp = h.send(json!({"command": "list_mailboxes"}));
+    assert_eq!(resp["ok"], true, "list_mailboxes failed: {}", resp);
+}
+
 #[test]
 fn test_mailbox_id_overrides_and_bulk_commands() {
     let mut h = CliHarness::start_with_mail_config(
@@ -330,45 +867,386 @@ fn test_mark_read_unread() {
 }
 
 #[test]
-fn test_offline_queue_replay_on_reconnect() {
+fn test_set_keyword_projects_to_cache_and_undoes() {
     let cache_dir = tempfile::tempdir().expect("create cache dir");
+    let mut h =
+        CliHarness::start_with_opts("", false, Some(cache_dir.path().to_path_buf()), None, None);
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+    assert_eq!(
+        h.send(json!({"command": "query_emails", "mailbox_id": "mbox-inbox"}))["ok"],
+        true
+    );
 
-    // Prime cache from an online session.
-    {
-        let mut online =
-            CliHarness::start_with_opts("", false, Some(cache_dir.path().to_path_buf()), None);
-        assert_eq!(
-            online.send(json!({"command": "connect", "account": "test"}))["ok"],
-            true
-        );
-        assert_eq!(
-            online.send(json!({"command": "list_mailboxes"}))["ok"],
-            true
-        );
-        assert_eq!(
-            online.send(json!({
-                "command": "query_emails",
-                "mailbox_id": "mbox-inbox",
-                "limit": 50
-            }))["ok"],
-            true
-        );
-    }
+    let resp = h.send(json!({
+        "command": "set_keyword",
+        "id": "email-002",
+        "keyword": "$label1",
+        "value": true
+    }));
+    assert_eq!(resp["ok"], true, "set_keyword failed: {}", resp);
+    assert_eq!(resp["action"], "SetKeyword(true)");
+    let email = h.send(json!({"command": "get_email", "id": "email-002", "headers_only": true}));
+    assert_eq!(email["keywords"], json!(["$label1"]));
+
+    let resp = h.send(json!({"command": "undo"}));
+    assert_eq!(resp["ok"], true, "undo set_keyword failed: {}", resp);
+    assert_eq!(resp["undone"], "set $label1");
+    let email = h.send(json!({"command": "get_email", "id": "email-002", "headers_only": true}));
+    assert_eq!(email["keywords"], json!([]));
+
+    let resp = h.send(json!({"command": "set_keyword", "id": "email-002", "keyword": "$junk"}));
+    assert_eq!(resp["ok"], false);
+}
 
-    // Queue writes offline; they should succeed and update local cache projection.
-    {
-        let mut offline =
-            CliHarness::start_with_opts("", true, Some(cache_dir.path().to_path_buf()), None);
-        assert_eq!(
-            offline.send(json!({"command": "connect", "account": "test"}))["ok"],
-            true
-        );
+#[test]
+fn test_mark_spam_and_not_spam_are_one_change_each() {
+    let cache_dir = tempfile::tempdir().expect("create cache dir");
+    let mut h =
+        CliHarness::start_with_opts("", false, Some(cache_dir.path().to_path_buf()), None, None);
+    h.server.add_mailbox("mbox-junk", "Junk", Some("junk"));
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+    assert_eq!(
+        h.send(json!({"command": "query_emails", "mailbox_id": "mbox-inbox"}))["ok"],
+        true
+    );
+    h.server.take_calls();
+
+    let resp = h.send(json!({"command": "mark_spam", "id": "email-002"}));
+    assert_eq!(resp["ok"], true, "mark_spam failed: {}", resp);
+    assert_eq!(resp["mailbox_id"], "mbox-junk");
+    assert_eq!(resp["keywords"], json!({"$junk": true, "$notjunk": false}));
+    let sets: Vec<Value> = h
+        .server
+        .take_calls()
+        .into_iter()
+        .filter(|(name, _)| name == "Email/set")
+        .map(|(_, args)| args["update"].clone())
+        .collect();
+    assert_eq!(
+        sets,
+        vec![json!({"email-002": {
+            "keywords/$junk": true,
+            "keywords/$notjunk": null,
+            "mailboxIds": {"mbox-junk": true}
+        }})]
+    );
+    let email = h.send(json!({"command": "get_email", "id": "email-002", "headers_only": true}));
+    assert_eq!(email["keywords"], json!(["$junk"]));
+    assert_eq!(email["mailbox_ids"], json!(["mbox-junk"]));
 
-        let mark = offline.send(json!({"command": "mark_read", "id": "email-002"}));
-        assert_eq!(mark["ok"], true, "offline mark_read failed: {}", mark);
+    let resp = h.send(json!({"command": "undo"}));
+    assert_eq!(resp["ok"], true, "undo mark_spam failed: {}", resp);
+    assert_eq!(resp["undone"], "report spam");
+    let email = h.send(json!({"command": "get_email", "id": "email-002", "headers_only": true}));
+    assert_eq!(email["keywords"], json!([]));
+    assert_eq!(email["mailbox_ids"], json!(["mbox-inbox"]));
 
-        let archive = offline.send(json!({"command": "archive", "id": "email-001"}));
-        assert_eq!(archive["ok"], true, "offline archive failed: {}", archive);
+    assert_eq!(
+        h.send(json!({"command": "mark_spam", "id": "email-002"}))["ok"],
+        true
+    );
+    let resp = h.send(json!({"command": "mark_not_spam", "id": "email-002"}));
+    assert_eq!(resp["ok"], true, "mark_not_spam failed: {}", resp);
+    assert_eq!(resp["mailbox_id"], "mbox-inbox");
+    assert_eq!(resp["keywords"], json!({"$junk": false, "$notjunk": true}));
+    let email = h.send(json!({"command": "get_email", "id": "email-002", "headers_only": true}));
+    assert_eq!(email["keywords"], json!(["$notjunk"]));
+    assert_eq!(email["mailbox_ids"], json!(["mbox-inbox"]));
+
+    let resp = h.send(json!({"command": "mark_spam"}));
+    assert_eq!(resp["ok"], false);
+}
+
+#[test]
+fn test_undo_reverses_recent_mutations() {
+    let cache_dir = tempfile::tempdir().expect("create cache dir");
+    let mut h =
+        CliHarness::start_with_opts("", false, Some(cache_dir.path().to_path_buf()), None, None);
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+    // Undo needs the original mailbox and flags, which come from the cache.
+    assert_eq!(
+        h.send(json!({"command": "query_emails", "mailbox_id": "mbox-inbox"}))["ok"],
+        true
+    );
+
+    assert_eq!(
+        h.send(json!({"command": "archive", "id": "email-001"}))["ok"],
+        true
+    );
+    assert_eq!(
+        h.send(json!({"command": "flag", "id": "email-002"}))["ok"],
+        true
+    );
+    assert_eq!(
+        h.send(json!({"command": "destroy", "ids": ["email-003"]}))["ok"],
+        true
+    );
+
+    let resp = h.send(json!({"command": "undo"}));
+    assert_eq!(resp["ok"], false);
+    assert!(
+        resp["error"]
+            .as_str()
+            .unwrap_or_default()
+            .contains("cannot be undone"),
+        "unexpected undo error: {}",
+        resp
+    );
+
+    let resp = h.send(json!({"command": "undo"}));
+    assert_eq!(resp["ok"], true, "undo flag failed: {}", resp);
+    assert_eq!(resp["undone"], "flag");
+    let email = h.send(json!({"command": "get_email", "id": "email-002", "headers_only": true}));
+    assert_eq!(email["is_flagged"], false);
+
+    let resp = h.send(json!({"command": "undo"}));
+    assert_eq!(resp["ok"], true, "undo move failed: {}", resp);
+    assert_eq!(resp["undone"], "move");
+    let email = h.send(json!({"command": "get_email", "id": "email-001", "headers_only": true}));
+    assert_eq!(email["mailbox_ids"][0], "mbox-inbox");
+
+    let resp = h.send(json!({"command": "undo"}));
+    assert_eq!(resp["ok"], false);
+    assert_eq!(resp["error"], "nothing to undo");
+}
+
+#[test]
+fn test_vacation_get_and_set() {
+    let mut h = CliHarness::start();
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+
+    let resp = h.send(json!({"command": "get_vacation"}));
+    assert_eq!(resp["ok"], true, "get_vacation failed: {}", resp);
+    assert_eq!(resp["enabled"], false);
+
+    let resp = h.send(json!({
+        "command": "set_vacation",
+        "enabled": true,
+        "subject": "Away",
+        "text_body": "Back on Monday.",
+        "to_date": "2025-07-14T00:00:00Z"
+    }));
+    assert_eq!(resp["ok"], true, "set_vacation failed: {}", resp);
+    assert_eq!(resp["enabled"], true);
+    assert_eq!(resp["subject"], "Away");
+
+    let resp = h.send(json!({"command": "get_vacation"}));
+    assert_eq!(resp["text_body"], "Back on Monday.");
+    assert_eq!(resp["to_date"], "2025-07-14T00:00:00Z");
+    assert_eq!(resp["from_date"], Value::Null);
+
+    let resp = h.send(json!({"command": "set_vacation", "subject": "Away"}));
+    assert_eq!(resp["ok"], false);
+}
+
+#[test]
+fn test_vacation_requires_the_capability() {
+    let mut h = CliHarness::start();
+    h.server
+        .drop_capability("urn:ietf:params:jmap:vacationresponse");
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+
+    let resp = h.send(json!({"command": "get_vacation"}));
+    assert_eq!(resp["ok"], false);
+    let error = resp["error"].as_str().unwrap();
+    assert!(
+        error.contains("missing urn:ietf:params:jmap:vacationresponse capability"),
+        "{}",
+        error
+    );
+    let resp = h.send(json!({"command": "set_vacation", "enabled": false}));
+    assert_eq!(resp["ok"], false);
+    assert!(h
+        .server
+        .take_method_calls()
+        .iter()
+        .all(|m| !m.starts_with("VacationResponse/")));
+}
+
+#[test]
+fn test_get_quota_reports_usage() {
+    let mut h = CliHarness::start();
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+
+    let resp = h.send(json!({"command": "get_quota"}));
+    assert_eq!(resp["ok"], true, "get_quota failed: {}", resp);
+    let quotas = resp["quota"].as_array().expect("quota array");
+    assert_eq!(quotas.len(), 2);
+    let storage = &quotas[0];
+    assert_eq!(storage["id"], "quota-mail");
+    assert_eq!(storage["name"], "Mail storage");
+    assert_eq!(storage["resource_type"], "octets");
+    assert_eq!(storage["limit"], 1_073_741_824u64);
+    assert!(storage["used"].as_u64().expect("used") > 0);
+    assert_eq!(quotas[1]["name"], Value::Null);
+}
+
+#[test]
+fn test_get_quota_is_null_without_server_support() {
+    let mut h = CliHarness::start();
+    h.server.drop_capability("urn:ietf:params:jmap:quota");
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+
+    let resp = h.send(json!({"command": "get_quota"}));
+    assert_eq!(resp["ok"], true, "get_quota failed: {}", resp);
+    assert_eq!(resp["quota"], Value::Null);
+    assert!(!h
+        .server
+        .take_method_calls()
+        .contains(&"Quota/get".to_string()));
+}
+
+#[test]
+fn test_oauth2_refreshes_rejected_token() {
+    let mut h = CliHarness::start_with_mail_config(&format!(
+        r#"
+[account.oauth]
+well_known_url = "@SERVER@/.well-known/jmap"
+username = "test@example.com"
+password_command = "echo {}"
+auth = "oauth2"
+token_refresh_command = "echo fresh-token"
+"#,
+        EXPIRED_TOKEN
+    ));
+
+    let resp = h.send(json!({"command": "connect", "account": "oauth"}));
+    assert_eq!(resp["ok"], true, "oauth2 connect failed: {}", resp);
+    let resp = h.send(json!({"command": "list_mailboxes"}));
+    assert_eq!(resp["ok"], true, "list_mailboxes failed: {}", resp);
+}
+
+#[test]
+fn test_oauth2_without_refresh_command_fails_on_401() {
+    let mut h = CliHarness::start_with_mail_config(&format!(
+        r#"
+[account.oauth]
+well_known_url = "@SERVER@/.well-known/jmap"
+username = "test@example.com"
+password_command = "echo {}"
+auth = "oauth2"
+"#,
+        EXPIRED_TOKEN
+    ));
+
+    let resp = h.send(json!({"command": "connect", "account": "oauth"}));
+    assert_eq!(resp["ok"], false);
+    assert!(
+        resp["error"].as_str().unwrap_or("").contains("401"),
+        "unexpected error: {}",
+        resp
+    );
+}
+
+#[test]
+fn test_transient_api_errors_are_retried() {
+    let mut h = CliHarness::start();
+    let resp = h.send(json!({"command": "connect", "account": "test"}));
+    assert_eq!(resp["ok"], true, "connect failed: {}", resp);
+
+    h.server.fail_next_api_calls(2);
+    let resp = h.send(json!({"command": "list_mailboxes"}));
+    assert_eq!(resp["ok"], true, "list_mailboxes not retried: {}", resp);
+}
+
+#[test]
+fn test_max_retries_zero_surfaces_transient_error() {
+    let mut h = CliHarness::start_with_mail_config("[ui]\nmax_retries = 0");
+    let resp = h.send(json!({"command": "connect", "account": "test"}));
+    assert_eq!(resp["ok"], true, "connect failed: {}", resp);
+
+    h.server.fail_next_api_calls(1);
+    let resp = h.send(json!({"command": "list_mailboxes"}));
+    assert_eq!(resp["ok"], false);
+    assert!(
+        resp["error"].as_str().unwrap_or("").contains("503"),
+        "unexpected error: {}",
+        resp
+    );
+}
+
+#[test]
+fn test_request_timeout_reports_timed_out() {
+    let mut h = CliHarness::start_with_mail_config("[ui]\nrequest_timeout_secs = 1");
+    let resp = h.send(json!({"command": "connect", "account": "test"}));
+    assert_eq!(resp["ok"], true, "connect failed: {}", resp);
+
+    h.server.stall_next_api_call();
+    let resp = h.send(json!({"command": "list_mailboxes"}));
+    assert_eq!(resp["ok"], false);
+    assert!(
+        resp["error"].as_str().unwrap_or("").contains("timed out"),
+        "unexpected error: {}",
+        resp
+    );
+}
+
+#[test]
+fn test_offline_queue_replay_on_reconnect() {
+    let cache_dir = tempfile::tempdir().expect("create cache dir");
+
+    // Prime cache from an online session.
+    {
+        let mut online = CliHarness::start_with_opts(
+            "",
+            false,
+            Some(cache_dir.path().to_path_buf()),
+            None,
+            None,
+        );
+        assert_eq!(
+            online.send(json!({"command": "connect", "account": "test"}))["ok"],
+            true
+        );
+        assert_eq!(
+            online.send(json!({"command": "list_mailboxes"}))["ok"],
+            true
+        );
+        assert_eq!(
+            online.send(json!({
+                "command": "query_emails",
+                "mailbox_id": "mbox-inbox",
+                "limit": 50
+            }))["ok"],
+            true
+        );
+    }
+
+    // Queue writes offline; they should succeed and update local cache projection.
+    {
+        let mut offline =
+            CliHarness::start_with_opts("", true, Some(cache_dir.path().to_path_buf()), None, None);
+        assert_eq!(
+            offline.send(json!({"command": "connect", "account": "test"}))["ok"],
+            true
+        );
+
+        let mark = offline.send(json!({"command": "mark_read", "id": "email-002"}));
+        assert_eq!(mark["ok"], true, "offline mark_read failed: {}", mark);
+
+        let archive = offline.send(json!({"command": "archive", "id": "email-001"}));
+        assert_eq!(archive["ok"], true, "offline archive failed: {}", archive);
 
         let inbox = offline.send(json!({
             "command": "query_emails",
@@ -387,8 +1265,13 @@ fn test_offline_queue_replay_on_reconnect() {
 
     // Reconnect online with the same cache; queued ops should replay to server.
     {
-        let mut online =
-            CliHarness::start_with_opts("", false, Some(cache_dir.path().to_path_buf()), None);
+        let mut online = CliHarness::start_with_opts(
+            "",
+            false,
+            Some(cache_dir.path().to_path_buf()),
+            None,
+            None,
+        );
         assert_eq!(
             online.send(json!({"command": "connect", "account": "test"}))["ok"],
             true
@@ -406,6 +1289,135 @@ fn test_offline_queue_replay_on_reconnect() {
     }
 }
 
+#[test]
+fn test_offline_total_counts_cached_messages() {
+    let cache_dir = tempfile::tempdir().expect("create cache dir");
+    {
+        let mut online = CliHarness::start_with_opts(
+            "",
+            false,
+            Some(cache_dir.path().to_path_buf()),
+            None,
+            None,
+        );
+        assert_eq!(
+            online.send(json!({"command": "connect", "account": "test"}))["ok"],
+            true
+        );
+        let resp = online.send(json!({
+            "command": "query_emails",
+            "mailbox_id": "mbox-inbox",
+            "limit": 2
+        }));
+        assert_eq!(resp["ok"], true, "query failed: {}", resp);
+    }
+
+    let mut offline =
+        CliHarness::start_with_opts("", true, Some(cache_dir.path().to_path_buf()), None, None);
+    assert_eq!(
+        offline.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+    // Only the first page was cached; paging must not promise the rest.
+    let resp = offline.send(json!({
+        "command": "query_emails",
+        "mailbox_id": "mbox-inbox",
+        "limit": 50
+    }));
+    assert_eq!(resp["ok"], true, "offline query failed: {}", resp);
+    assert_eq!(resp["emails"].as_array().map(Vec::len), Some(2));
+    assert_eq!(resp["total"], 2);
+}
+
+#[test]
+fn test_offline_search_and_paging_use_cached_mail() {
+    let cache_dir = tempfile::tempdir().expect("create cache dir");
+    {
+        let mut online = CliHarness::start_with_opts(
+            "",
+            false,
+            Some(cache_dir.path().to_path_buf()),
+            None,
+            None,
+        );
+        assert_eq!(
+            online.send(json!({"command": "connect", "account": "test"}))["ok"],
+            true
+        );
+        assert_eq!(
+            online.send(json!({"command": "list_mailboxes"}))["ok"],
+            true
+        );
+        assert_eq!(
+            online.send(json!({
+                "command": "query_emails",
+                "mailbox_id": "mbox-inbox",
+                "limit": 50
+            }))["ok"],
+            true
+        );
+    }
+
+    let mut offline =
+        CliHarness::start_with_opts("", true, Some(cache_dir.path().to_path_buf()), None, None);
+    assert_eq!(
+        offline.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+
+    let ids = |resp: &Value| -> Vec<String> {
+        resp["emails"]
+            .as_array()
+            .expect("emails array")
+            .iter()
+            .filter_map(|e| e["id"].as_str().map(str::to_string))
+            .collect()
+    };
+
+    let resp = offline.send(json!({
+        "command": "query_emails",
+        "mailbox_id": "mbox-inbox",
+        "search": "FLIGHT receipt",
+        "limit": 50
+    }));
+    assert_eq!(resp["ok"], true, "offline search failed: {}", resp);
+    assert_eq!(ids(&resp), vec!["email-004"]);
+
+    let resp = offline.send(json!({
+        "command": "query_emails",
+        "mailbox_id": "mbox-archive",
+        "search": "flight",
+        "limit": 50
+    }));
+    assert_eq!(resp["ok"], true, "offline search failed: {}", resp);
+    assert!(ids(&resp).is_empty());
+
+    let resp = offline.send(json!({"command": "search_all", "search": "bob@", "limit": 50}));
+    assert_eq!(resp["ok"], true, "offline search_all failed: {}", resp);
+    assert_eq!(ids(&resp), vec!["email-002"]);
+
+    let first = offline.send(json!({
+        "command": "query_emails",
+        "mailbox_id": "mbox-inbox",
+        "limit": 3
+    }));
+    assert_eq!(first["ok"], true, "offline first page failed: {}", first);
+    assert_eq!(ids(&first).len(), 3);
+    assert_eq!(first["total"], 4);
+
+    let second = offline.send(json!({
+        "command": "query_emails",
+        "mailbox_id": "mbox-inbox",
+        "limit": 3,
+        "position": 3
+    }));
+    assert_eq!(second["ok"], true, "offline second page failed: {}", second);
+    assert_eq!(second["position"], 3);
+    let rest = ids(&second);
+    assert_eq!(rest.len(), 1);
+    assert!(!ids(&first).contains(&rest[0]));
+}
+
 #[test]
 fn test_download_attachment() {
     let mut h = CliHarness::start();
@@ -450,9 +1462,74 @@ fn test_download_attachment() {
 }
 
 #[test]
-fn test_train_spam_and_ham() {
-    let data_dir = tempfile::tempdir().expect("create data dir");
-    let mut h = CliHarness::start_with_data_home(data_dir.path().to_path_buf());
+fn test_download_all_attachments() {
+    let dir = tempfile::tempdir().expect("create download dir");
+    let mut h = CliHarness::start();
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+
+    let resp = h.send(json!({
+        "command": "download_all_attachments",
+        "id": "email-001",
+        "dir": dir.path().to_str().unwrap(),
+    }));
+    assert_eq!(
+        resp["ok"], true,
+        "download_all_attachments failed: {}",
+        resp
+    );
+    let paths = resp["paths"].as_array().expect("paths array");
+    assert_eq!(paths.len(), 1);
+    let path = Path::new(paths[0].as_str().unwrap());
+    assert_eq!(path, dir.path().join("test-document.pdf"));
+    assert!(std::fs::read_to_string(path)
+        .unwrap()
+        .contains("blob-att-001"));
+
+    let resp = h.send(json!({"command": "download_all_attachments", "id": "email-002"}));
+    assert_eq!(resp["ok"], true);
+    assert_eq!(resp["paths"].as_array().map(|a| a.len()), Some(0));
+}
+
+#[test]
+fn test_download_attachment_dir_override_and_config() {
+    let configured = tempfile::tempdir().expect("create configured dir");
+    let mut h = CliHarness::start_with_mail_config(&format!(
+        "[ui]\ndownload_dir = \"{}\"",
+        configured.path().display()
+    ));
+    let resp = h.send(json!({"command": "connect", "account": "test"}));
+    assert_eq!(resp["ok"], true, "connect failed: {}", resp);
+
+    let resp = h.send(json!({
+        "command": "download_attachment",
+        "blob_id": "blob-att-001",
+        "name": "..secret.pdf"
+    }));
+    assert_eq!(resp["ok"], true, "download_attachment failed: {}", resp);
+    let path = Path::new(resp["path"].as_str().expect("path string")).to_path_buf();
+    assert_eq!(path, configured.path().join("secret.pdf"));
+    assert!(path.exists());
+
+    let override_dir = tempfile::tempdir().expect("create override dir");
+    let resp = h.send(json!({
+        "command": "download_attachment",
+        "blob_id": "blob-att-001",
+        "name": "test-document.pdf",
+        "dir": override_dir.path().join("nested").display().to_string()
+    }));
+    assert_eq!(resp["ok"], true, "download_attachment failed: {}", resp);
+    let path = Path::new(resp["path"].as_str().expect("path string")).to_path_buf();
+    assert_eq!(path, override_dir.path().join("nested/test-document.pdf"));
+    assert!(path.exists());
+}
+
+#[test]
+fn test_train_spam_and_ham() {
+    let data_dir = tempfile::tempdir().expect("create data dir");
+    let mut h = CliHarness::start_with_data_home(data_dir.path().to_path_buf());
 
     let resp = h.send(json!({"command": "connect", "account": "test"}));
     assert_eq!(resp["ok"], true, "connect failed: {}", resp);
@@ -493,3 +1570,711 @@ fn test_train_missing_id_errors() {
     assert_eq!(resp["ok"], false);
     assert!(resp["error"].as_str().unwrap_or("").contains("id"));
 }
+
+#[test]
+fn test_send_email() {
+    let mut h = CliHarness::start();
+    let resp = h.send(json!({"command": "connect", "account": "test"}));
+    assert_eq!(resp["ok"], true, "connect failed: {}", resp);
+
+    let draft = "From: test@example.com\nTo: friend@example.com\nCc: \nSubject: Lunch?\n--text follows this line--\n\nAre you free at noon?\n";
+    let resp = h.send(json!({"command": "send_email", "draft": draft}));
+    assert_eq!(resp["ok"], true, "send_email failed: {}", resp);
+    let id = resp["id"].as_str().expect("sent email id").to_string();
+
+    let resp = h.send(json!({"command": "get_email", "id": id}));
+    assert_eq!(resp["ok"], true, "get_email failed: {}", resp);
+    assert_eq!(resp["subject"], "Lunch?");
+    assert_eq!(resp["mailbox_ids"][0], "mbox-sent");
+    assert!(resp["body"]
+        .as_str()
+        .unwrap_or("")
+        .contains("Are you free at noon?"));
+}
+
+#[test]
+fn test_send_email_with_attachments() {
+    // Sent ids are per mock server, so keep this test's cache private.
+    let cache_dir = tempfile::tempdir().expect("create cache dir");
+    let mut h =
+        CliHarness::start_with_opts("", false, Some(cache_dir.path().to_path_buf()), None, None);
+    let resp = h.send(json!({"command": "connect", "account": "test"}));
+    assert_eq!(resp["ok"], true, "connect failed: {}", resp);
+
+    let dir = tempfile::tempdir().expect("create attachment dir");
+    let notes = dir.path().join("notes.txt");
+    let agenda = dir.path().join("agenda.pdf");
+    std::fs::write(&notes, "remember the milk").expect("write notes");
+    std::fs::write(&agenda, "%PDF-1.4 fake").expect("write agenda");
+
+    let draft = format!(
+        "From: test@example.com\nTo: Friend <friend@example.com>\nSubject: Files\nAttach: [{:?}, {:?}]\n--text follows this line--\n\nSee attached.\n",
+        notes.display(),
+        agenda.display()
+    );
+    let resp = h.send(json!({"command": "send_email", "draft": draft}));
+    assert_eq!(resp["ok"], true, "send_email failed: {}", resp);
+    let id = resp["id"].as_str().expect("sent email id").to_string();
+
+    let resp = h.send(json!({"command": "get_email", "id": id}));
+    assert_eq!(resp["ok"], true, "get_email failed: {}", resp);
+    assert_eq!(resp["subject"], "Files");
+    assert_eq!(resp["mailbox_ids"][0], "mbox-sent");
+    let attachments = resp["attachments"].as_array().expect("attachments");
+    assert_eq!(attachments.len(), 2);
+    assert_eq!(attachments[0]["name"], "notes.txt");
+    assert_eq!(attachments[0]["type"], "text/plain");
+    assert_eq!(attachments[1]["name"], "agenda.pdf");
+    assert_eq!(attachments[1]["type"], "application/pdf");
+
+    let missing = dir.path().join("missing.doc");
+    let draft = format!(
+        "From: test@example.com\nTo: friend@example.com\nSubject: Oops\nAttach: {}\n--text follows this line--\n\nHi\n",
+        missing.display()
+    );
+    let resp = h.send(json!({"command": "send_email", "draft": draft}));
+    assert_eq!(resp["ok"], false);
+    assert!(resp["error"].as_str().unwrap().contains("missing.doc"));
+}
+
+#[test]
+fn test_drafts_are_saved_until_sent() {
+    let cache_dir = tempfile::tempdir().expect("create cache dir");
+    let mut h = CliHarness::start_with_opts(
+        r#"
+[account.other]
+well_known_url = "@SERVER@/.well-known/jmap"
+username = "other@example.com"
+password_command = "echo test"
+"#,
+        false,
+        Some(cache_dir.path().to_path_buf()),
+        None,
+        None,
+    );
+    let resp = h.send(json!({"command": "connect", "account": "test"}));
+    assert_eq!(resp["ok"], true, "connect failed: {}", resp);
+
+    // Generating drafts saves nothing on its own.
+    let created = h.send(json!({"command": "compose_draft"}));
+    assert_eq!(created["ok"], true, "compose_draft failed: {}", created);
+    assert!(created["draft_id"].is_null());
+    let reply = h.send(json!({"command": "reply_draft", "id": "email-001"}));
+    assert_eq!(reply["ok"], true, "reply_draft failed: {}", reply);
+    assert!(reply["draft_id"].is_null());
+    let resp = h.send(json!({"command": "list_drafts"}));
+    assert_eq!(resp["drafts"], json!([]));
+
+    let resp = h.send(json!({"command": "save_draft", "draft": created["draft"], "local": true}));
+    assert_eq!(resp["ok"], true, "save_draft failed: {}", resp);
+    let draft_id = resp["draft_id"].as_str().expect("draft_id").to_string();
+    assert!(cache_dir
+        .path()
+        .join("tmc/drafts/test")
+        .join(format!("{}.eml", draft_id))
+        .exists());
+
+    let resp = h.send(json!({"command": "list_drafts"}));
+    assert_eq!(resp["ok"], true, "list_drafts failed: {}", resp);
+    let drafts = resp["drafts"].as_array().expect("drafts");
+    assert_eq!(drafts.len(), 1);
+    assert_eq!(drafts[0]["id"], draft_id.as_str());
+    assert_eq!(drafts[0]["to"], "");
+
+    let resp = h.send(json!({"command": "resume_draft", "id": draft_id}));
+    assert_eq!(resp["ok"], true, "resume_draft failed: {}", resp);
+    assert_eq!(resp["draft"], created["draft"]);
+
+    let resp = h.send(json!({"command": "resume_draft", "id": "../../config"}));
+    assert_eq!(resp["ok"], false);
+
+    // Saved drafts belong to the account they were written from.
+    let resp = h.send(json!({"command": "connect", "account": "other"}));
+    assert_eq!(resp["ok"], true, "connect failed: {}", resp);
+    let resp = h.send(json!({"command": "list_drafts"}));
+    assert_eq!(resp["drafts"], json!([]));
+    let resp = h.send(json!({"command": "resume_draft", "id": draft_id}));
+    assert_eq!(resp["ok"], false);
+    let resp = h.send(json!({"command": "connect", "account": "test"}));
+    assert_eq!(resp["ok"], true, "connect failed: {}", resp);
+
+    let text = created["draft"]
+        .as_str()
+        .expect("draft text")
+        .replacen("To: ", "To: friend@example.com", 1)
+        .replacen("Subject: ", "Subject: Saved", 1);
+    let resp = h.send(json!({
+        "command": "save_draft",
+        "draft": text,
+        "draft_id": draft_id,
+        "local": true
+    }));
+    assert_eq!(resp["draft_id"], draft_id.as_str());
+    let resp = h.send(json!({"command": "list_drafts"}));
+    assert_eq!(resp["drafts"][0]["to"], "friend@example.com");
+
+    let resp = h.send(json!({"command": "send_email", "draft": text, "draft_id": draft_id}));
+    assert_eq!(resp["ok"], true, "send_email failed: {}", resp);
+
+    let resp = h.send(json!({"command": "list_drafts"}));
+    assert_eq!(resp["drafts"].as_array().expect("drafts").len(), 0);
+
+    let scratch =
+        h.send(json!({"command": "save_draft", "draft": created["draft"], "local": true}));
+    let resp = h.send(json!({"command": "delete_draft", "id": scratch["draft_id"]}));
+    assert_eq!(resp["ok"], true, "delete_draft failed: {}", resp);
+    let resp = h.send(json!({"command": "delete_draft", "id": scratch["draft_id"]}));
+    assert_eq!(resp["ok"], false);
+}
+
+#[test]
+fn test_save_draft_stores_in_drafts_folder() {
+    let cache_dir = tempfile::tempdir().expect("create cache dir");
+    let mut h =
+        CliHarness::start_with_opts("", false, Some(cache_dir.path().to_path_buf()), None, None);
+    h.server
+        .add_mailbox("mbox-drafts", "Drafts", Some("drafts"));
+    let resp = h.send(json!({"command": "connect", "account": "test"}));
+    assert_eq!(resp["ok"], true, "connect failed: {}", resp);
+
+    let created = h.send(json!({"command": "compose_draft"}));
+    let text = created["draft"].as_str().expect("draft text").replacen(
+        "Subject: ",
+        "Subject: Unfinished",
+        1,
+    );
+    let local = h.send(json!({"command": "save_draft", "draft": text, "local": true}));
+    assert_eq!(local["ok"], true, "save_draft failed: {}", local);
+    let resp = h.send(json!({
+        "command": "save_draft",
+        "draft": text,
+        "draft_id": local["draft_id"],
+    }));
+    assert_eq!(resp["ok"], true, "save_draft failed: {}", resp);
+    assert_eq!(resp["folder"], "Drafts");
+    let id = resp["id"].as_str().expect("draft email id").to_string();
+
+    let resp = h.send(json!({"command": "get_email", "id": id}));
+    assert_eq!(resp["ok"], true, "get_email failed: {}", resp);
+    assert_eq!(resp["subject"], "Unfinished");
+    assert_eq!(resp["mailbox_ids"][0], "mbox-drafts");
+    let keywords = resp["keywords"].as_array().expect("keywords");
+    assert!(
+        keywords.contains(&json!("$draft")),
+        "keywords: {:?}",
+        keywords
+    );
+
+    let resp = h.send(json!({"command": "list_drafts"}));
+    assert_eq!(resp["drafts"].as_array().expect("drafts").len(), 0);
+}
+
+#[test]
+fn test_send_email_rejects_draft_without_recipients() {
+    let mut h = CliHarness::start();
+    let resp = h.send(json!({"command": "connect", "account": "test"}));
+    assert_eq!(resp["ok"], true, "connect failed: {}", resp);
+
+    let draft = h.send(json!({"command": "compose_draft"}));
+    let resp = h.send(json!({"command": "send_email", "draft": draft["draft"]}));
+    assert_eq!(resp["ok"], false);
+    assert!(resp["error"].as_str().unwrap().contains("no recipients"));
+}
+
+#[test]
+fn test_list_identities_and_reply_from_matching_identity() {
+    let mut h = CliHarness::start_with_mail_config(
+        r#"
+[[account.test.identities]]
+name = "Primary"
+email = "primary@example.com"
+
+[[account.test.identities]]
+name = "Test User"
+email = "test@example.com"
+"#,
+    );
+    let resp = h.send(json!({"command": "list_identities"}));
+    assert_eq!(resp["ok"], false);
+
+    let resp = h.send(json!({"command": "connect", "account": "test"}));
+    assert_eq!(resp["ok"], true, "connect failed: {}", resp);
+
+    let resp = h.send(json!({"command": "list_identities"}));
+    assert_eq!(resp["ok"], true, "list_identities failed: {}", resp);
+    let identities = resp["identities"].as_array().expect("identities array");
+    assert_eq!(identities.len(), 2);
+    assert_eq!(identities[0]["from"], "Primary <primary@example.com>");
+
+    // Mock emails are addressed to test@example.com, so replies use that identity.
+    let resp = h.send(json!({"command": "reply_draft", "id": "email-002"}));
+    assert_eq!(resp["ok"], true, "reply_draft failed: {}", resp);
+    assert!(resp["draft"]
+        .as_str()
+        .unwrap()
+        .starts_with("From: Test User <test@example.com>\n"));
+
+    let resp = h.send(json!({"command": "compose_draft"}));
+    assert!(resp["draft"]
+        .as_str()
+        .unwrap()
+        .starts_with("From: Primary <primary@example.com>\n"));
+}
+
+#[test]
+fn test_snooze_creates_the_folder_and_moves_the_email() {
+    let cache_home = tempfile::tempdir().expect("cache dir");
+    let mut h =
+        CliHarness::start_with_opts("", false, Some(cache_home.path().to_path_buf()), None, None);
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+
+    let bad = h.send(json!({"command": "snooze", "id": "email-002", "until": "soon"}));
+    assert_eq!(bad["ok"], false);
+
+    let resp = h.send(json!({"command": "snooze", "id": "email-002", "until": "3d"}));
+    assert_eq!(resp["ok"], true, "snooze failed: {}", resp);
+    assert!(resp["wake_at"].as_u64().is_some());
+
+    let mailboxes = h.send(json!({"command": "list_mailboxes"}));
+    let snoozed = mailboxes["mailboxes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|m| m["name"] == "Snoozed")
+        .unwrap_or_else(|| panic!("snooze folder not created: {}", mailboxes));
+    let e2 = h.send(json!({"command": "get_email", "id": "email-002", "headers_only": true}));
+    assert_eq!(e2["mailbox_ids"][0], snoozed["id"]);
+
+    // A second snooze reuses the folder.
+    let resp = h.send(json!({"command": "snooze", "id": "email-003", "until": "1h"}));
+    assert_eq!(resp["ok"], true, "snooze failed: {}", resp);
+    let e3 = h.send(json!({"command": "get_email", "id": "email-003", "headers_only": true}));
+    assert_eq!(e3["mailbox_ids"][0], snoozed["id"]);
+}
+
+#[test]
+fn test_muted_thread_is_archived_on_sync() {
+    let data_home = tempfile::tempdir().expect("data dir");
+    let mut h = CliHarness::start_with_data_home(data_home.path().to_path_buf());
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+
+    assert_eq!(h.send(json!({"command": "mute_thread"}))["ok"], false);
+    let resp = h.send(json!({"command": "mute_thread", "thread_id": "thread-002"}));
+    assert_eq!(resp["ok"], true, "mute_thread failed: {}", resp);
+    assert_eq!(resp["muted"], true);
+    assert!(data_home.path().join("tmc/muted/test.json").exists());
+
+    // Only a reply arriving after the mute is archived; the message that
+    // was already in the thread stays in the inbox.
+    h.server
+        .deliver_reply("email-002", "email-002-reply", "2099-01-01T00:00:00Z");
+    let inbox = h.send(json!({"command": "query_emails", "mailbox_id": "mbox-inbox", "limit": 50}));
+    assert_eq!(inbox["ok"], true, "query_emails failed: {}", inbox);
+    let ids: Vec<&str> = inbox["emails"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|e| e["id"].as_str())
+        .collect();
+    assert!(
+        ids.contains(&"email-002"),
+        "existing email moved: {:?}",
+        ids
+    );
+    assert!(
+        !ids.contains(&"email-002-reply"),
+        "muted reply listed: {:?}",
+        ids
+    );
+    let reply =
+        h.send(json!({"command": "get_email", "id": "email-002-reply", "headers_only": true}));
+    assert_eq!(reply["mailbox_ids"][0], "mbox-archive");
+
+    let resp = h.send(json!({"command": "unmute_thread", "thread_id": "thread-002"}));
+    assert_eq!(resp["ok"], true, "unmute_thread failed: {}", resp);
+    assert_eq!(resp["muted"], false);
+}
+
+#[test]
+fn test_mute_thread_works_offline() {
+    let cache_home = tempfile::tempdir().expect("cache dir");
+    let data_home = tempfile::tempdir().expect("data dir");
+    let mut h = CliHarness::start_with_opts(
+        "",
+        true,
+        Some(cache_home.path().to_path_buf()),
+        Some(data_home.path().to_path_buf()),
+        None,
+    );
+    let resp = h.send(json!({"command": "connect", "account": "test"}));
+    assert_eq!(resp["ok"], true, "offline connect failed: {}", resp);
+
+    let resp = h.send(json!({"command": "mute_thread", "thread_id": "thread-002"}));
+    assert_eq!(resp["ok"], true, "offline mute_thread failed: {}", resp);
+    assert_eq!(resp["muted"], true);
+    assert!(data_home.path().join("tmc/muted/test.json").exists());
+}
+
+#[test]
+fn test_dry_run_and_run_rules() {
+    let cache_home = tempfile::tempdir().expect("cache dir");
+    let mut h = CliHarness::start_with_rules(
+        r#"
+[[rule]]
+name = "file receipts"
+[rule.match]
+header = "Subject"
+regex = "Receipt"
+[rule.actions]
+move_to = "Archive"
+"#,
+        cache_home.path(),
+    );
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+
+    let missing = h.send(json!({"command": "dry_run_rules"}));
+    assert_eq!(missing["ok"], false);
+
+    let preview = h.send(json!({"command": "dry_run_rules", "mailbox_id": "mbox-inbox"}));
+    assert_eq!(preview["ok"], true, "dry_run_rules failed: {}", preview);
+    assert_eq!(preview["mailbox_name"], "INBOX");
+    assert_eq!(preview["matched_rules"], 1);
+    let entries = preview["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["id"], "email-004");
+    assert_eq!(entries[0]["rule"], "file receipts");
+    assert_eq!(entries[0]["actions"][0], "move_to=Archive");
+
+    let e4 = h.send(json!({"command": "get_email", "id": "email-004", "headers_only": true}));
+    assert_eq!(e4["mailbox_ids"][0], "mbox-inbox");
+
+    let run = h.send(json!({"command": "run_rules", "mailbox_id": "mbox-inbox"}));
+    assert_eq!(run["ok"], true, "run_rules failed: {}", run);
+    assert_eq!(run["actions"], 1);
+
+    let e4 = h.send(json!({"command": "get_email", "id": "email-004", "headers_only": true}));
+    assert_eq!(e4["mailbox_ids"][0], "mbox-archive");
+}
+
+#[test]
+fn test_manual_rule_runs_only_use_rules_scoped_to_the_mailbox() {
+    let cache_home = tempfile::tempdir().expect("cache dir");
+    let mut h = CliHarness::start_with_rules(
+        r#"
+[[rule]]
+name = "trash archived receipts"
+mailbox = "Archive"
+[rule.match]
+header = "Subject"
+regex = "Receipt"
+[rule.actions]
+move_to = "Trash"
+
+[[rule]]
+name = "flag inbox receipts"
+mailbox = "INBOX"
+[rule.match]
+header = "Subject"
+regex = "Receipt"
+[rule.actions]
+flag = true
+"#,
+        cache_home.path(),
+    );
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+
+    let preview = h.send(json!({"command": "dry_run_rules", "mailbox_id": "mbox-inbox"}));
+    assert_eq!(preview["ok"], true, "dry_run_rules failed: {}", preview);
+    let entries = preview["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 1, "unexpected entries: {}", preview);
+    assert_eq!(entries[0]["id"], "email-004");
+    assert_eq!(entries[0]["rule"], "flag inbox receipts");
+
+    let run = h.send(json!({"command": "run_rules", "mailbox_id": "mbox-inbox"}));
+    assert_eq!(run["ok"], true, "run_rules failed: {}", run);
+    assert_eq!(run["actions"], 1);
+    let e4 = h.send(json!({"command": "get_email", "id": "email-004", "headers_only": true}));
+    assert_eq!(e4["mailbox_ids"][0], "mbox-inbox");
+    assert!(
+        e4["keywords"]
+            .as_array()
+            .unwrap()
+            .contains(&json!("$flagged")),
+        "email-004 not flagged: {}",
+        e4
+    );
+
+    let plan = h.send(json!({
+        "command": "triage_suggest",
+        "mailbox_id": "mbox-inbox",
+        "limit": 50
+    }));
+    assert_eq!(plan["ok"], true, "triage_suggest failed: {}", plan);
+    assert!(
+        !plan.to_string().contains("trash archived receipts"),
+        "Archive-only rule used for INBOX triage: {}",
+        plan
+    );
+}
+
+#[test]
+fn test_long_scans_emit_progress() {
+    let cache_home = tempfile::tempdir().expect("cache dir");
+    let mut h = CliHarness::start_with_rules(
+        r#"
+[[rule]]
+name = "file receipts"
+[rule.match]
+header = "Subject"
+regex = "Receipt"
+[rule.actions]
+move_to = "Archive"
+"#,
+        cache_home.path(),
+    );
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+
+    let (progress, plan) = h.send_with_progress(json!({
+        "command": "triage_suggest",
+        "mailbox_id": "mbox-inbox",
+        "limit": 50
+    }));
+    assert_eq!(plan["ok"], true, "triage_suggest failed: {}", plan);
+    assert_eq!(progress, vec![json!({"scanned": 4, "total": 4})]);
+    let planned: usize = ["archive", "trash", "keep"]
+        .iter()
+        .map(|k| plan[k].as_array().unwrap().len())
+        .sum();
+    assert_eq!(planned, 4);
+
+    let (progress, preview) =
+        h.send_with_progress(json!({"command": "dry_run_rules", "mailbox_id": "mbox-inbox"}));
+    assert_eq!(
+        preview["matched_rules"], 1,
+        "dry_run_rules failed: {}",
+        preview
+    );
+    assert_eq!(progress, vec![json!({"scanned": 4, "total": 4})]);
+
+    let (progress, run) =
+        h.send_with_progress(json!({"command": "run_rules", "mailbox_id": "mbox-inbox"}));
+    assert_eq!(run["actions"], 1, "run_rules failed: {}", run);
+    assert_eq!(progress, vec![json!({"scanned": 4, "total": 4})]);
+}
+
+#[test]
+fn test_test_rules_explains_one_email() {
+    let cache_home = tempfile::tempdir().expect("cache dir");
+    let mut h = CliHarness::start_with_rules(
+        r#"
+[[rule]]
+name = "receipts from alice"
+[rule.match]
+all = [
+    { header = "Subject", regex = "receipt", case_sensitive = false },
+    { header = "From", regex = "alice@" },
+]
+[rule.actions]
+flag = true
+
+[[rule]]
+name = "file receipts"
+[rule.match]
+header = "Subject"
+regex = "Receipt"
+[rule.actions]
+move_to = "Archive"
+"#,
+        cache_home.path(),
+    );
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+
+    let missing = h.send(json!({"command": "test_rules"}));
+    assert_eq!(missing["ok"], false);
+
+    let resp = h.send(json!({"command": "test_rules", "id": "email-004"}));
+    assert_eq!(resp["ok"], true, "test_rules failed: {}", resp);
+    assert_eq!(
+        resp["matched"],
+        json!([{"rule": "file receipts", "actions": ["move_to=Archive"]}])
+    );
+    let rules = resp["rules"].as_array().unwrap();
+    assert_eq!(rules.len(), 2);
+    assert_eq!(rules[0]["matched"], false);
+    let passed: Vec<bool> = rules[0]["conditions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c["passed"].as_bool().unwrap())
+        .collect();
+    assert_eq!(passed, [false, true, false]);
+    assert_eq!(rules[0]["conditions"][2]["depth"], 1);
+    assert_eq!(rules[1]["matched"], true);
+
+    // Nothing was applied.
+    let e4 = h.send(json!({"command": "get_email", "id": "email-004", "headers_only": true}));
+    assert_eq!(e4["mailbox_ids"][0], "mbox-inbox");
+
+    let unknown = h.send(json!({"command": "test_rules", "id": "nope"}));
+    assert_eq!(unknown["ok"], false);
+}
+
+#[test]
+fn test_run_rules_forward_to() {
+    let cache_home = tempfile::tempdir().expect("cache dir");
+    let mut h = CliHarness::start_with_rules(
+        r#"
+[[rule]]
+name = "receipts to accountant"
+[rule.match]
+header = "Subject"
+regex = "Receipt"
+[rule.actions]
+forward_to = "books@example.net"
+"#,
+        cache_home.path(),
+    );
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+
+    let preview = h.send(json!({"command": "dry_run_rules", "mailbox_id": "mbox-inbox"}));
+    assert_eq!(preview["ok"], true, "dry_run_rules failed: {}", preview);
+    assert_eq!(
+        preview["entries"][0]["actions"][0],
+        "forward_to=books@example.net"
+    );
+
+    let run = h.send(json!({"command": "run_rules", "mailbox_id": "mbox-inbox"}));
+    assert_eq!(run["ok"], true, "run_rules failed: {}", run);
+    assert_eq!(run["actions"], 1);
+
+    // The original is marked $forwarded, so a second run sends nothing.
+    let run = h.send(json!({"command": "run_rules", "mailbox_id": "mbox-inbox"}));
+    assert_eq!(run["ok"], true, "run_rules failed: {}", run);
+    assert_eq!(run["actions"], 0, "forwarded again: {}", run);
+
+    let sent = h.send(json!({"command": "query_emails", "mailbox_id": "mbox-sent"}));
+    assert_eq!(sent["ok"], true, "query_emails failed: {}", sent);
+    let emails = sent["emails"].as_array().unwrap();
+    assert_eq!(emails.len(), 1, "expected one forward: {}", sent);
+    let id = emails[0]["id"].as_str().unwrap().to_string();
+    let fwd = h.send(json!({"command": "get_email", "id": id}));
+    assert!(fwd["subject"].as_str().unwrap().starts_with("Fwd: "));
+    assert!(fwd["body"]
+        .as_str()
+        .unwrap_or("")
+        .contains("Forwarded message"));
+}
+
+#[test]
+fn test_get_raw_returns_headers_and_body() {
+    let mut h = CliHarness::start();
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+
+    let resp = h.send(json!({"command": "get_raw", "id": "email-002"}));
+    assert_eq!(resp["ok"], true, "get_raw failed: {}", resp);
+    assert_eq!(resp["id"], "email-002");
+    let raw = resp["raw"].as_str().unwrap();
+    assert!(raw.contains("Subject: raw message for email-002"));
+    assert!(raw.contains("raw body of email-002"));
+    assert_eq!(resp["bytes"], raw.len());
+    assert_eq!(resp["encoding"], "utf-8");
+
+    let missing = h.send(json!({"command": "get_raw"}));
+    assert_eq!(missing["ok"], false);
+}
+
+#[test]
+fn test_get_raw_base64_for_non_utf8_source() {
+    use base64::Engine;
+
+    let mut h = CliHarness::start();
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+
+    let resp = h.send(json!({"command": "get_raw", "id": LATIN1_EMAIL_ID}));
+    assert_eq!(resp["ok"], true, "get_raw failed: {}", resp);
+    assert_eq!(resp["encoding"], "base64");
+    let raw = base64::engine::general_purpose::STANDARD
+        .decode(resp["raw"].as_str().unwrap())
+        .expect("valid base64");
+    assert_eq!(resp["bytes"], raw.len());
+    assert!(raw
+        .windows(b"Caf\xe9 cr\xe8me\r\n".len())
+        .any(|w| w == b"Caf\xe9 cr\xe8me\r\n"));
+}
+
+#[test]
+fn test_export_eml() {
+    let mut h = CliHarness::start();
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+
+    let dir = tempfile::tempdir().expect("temp dir");
+    let target = dir.path().join("saved").join("message.eml");
+    let resp = h.send(json!({
+        "command": "export_eml",
+        "id": "email-002",
+        "path": target.to_string_lossy(),
+    }));
+    assert_eq!(resp["ok"], true, "export_eml failed: {}", resp);
+    assert_eq!(resp["path"], target.to_string_lossy().as_ref());
+
+    let contents = std::fs::read_to_string(&target).expect("read exported file");
+    assert!(contents.contains("Subject: raw message for email-002"));
+    assert!(contents.contains("raw body of email-002"));
+
+    let missing = h.send(json!({"command": "export_eml"}));
+    assert_eq!(missing["ok"], false);
+}
+
+#[test]
+fn test_export_eml_keeps_non_utf8_bytes() {
+    let mut h = CliHarness::start();
+    assert_eq!(
+        h.send(json!({"command": "connect", "account": "test"}))["ok"],
+        true
+    );
+
+    let dir = tempfile::tempdir().expect("temp dir");
+    let target = dir.path().join("latin1.eml");
+    let resp = h.send(json!({
+        "command": "export_eml",
+        "id": LATIN1_EMAIL_ID,
+        "path": target.to_string_lossy(),
+    }));
+    assert_eq!(resp["ok"], true, "export_eml failed: {}", resp);
+
+    let contents = std::fs::read(&target).expect("read exported file");
+    assert_eq!(resp["bytes"], contents.len());
+    assert!(contents
+        .windows(b"Caf\xe9 cr\xe8me\r\n".len())
+        .any(|w| w == b"Caf\xe9 cr\xe8me\r\n"));
+}
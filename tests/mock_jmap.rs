@@ -6,6 +6,12 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 
+/// Bearer token the mock always rejects with 401, to exercise token refresh.
+pub const EXPIRED_TOKEN: &str = "expired-token";
+
+/// Email whose raw source ends in a Latin-1 line that is not valid UTF-8.
+pub const LATIN1_EMAIL_ID: &str = "email-003";
+
 #[derive(Clone)]
 struct EmailRecord {
     id: String,
@@ -17,6 +23,10 @@ struct EmailRecord {
     received_at: String,
     mailbox_id: String,
     is_read: bool,
+    /// Has the `$draft` keyword (saved with `Email/set` as a draft).
+    draft: bool,
+    /// Other keywords set with `Email/set`, e.g. `$forwarded`.
+    keywords: Vec<String>,
     attachments: Vec<Value>,
 }
 
@@ -26,6 +36,12 @@ impl EmailRecord {
         if self.is_read {
             keywords.insert("$seen".to_string(), json!(true));
         }
+        if self.draft {
+            keywords.insert("$draft".to_string(), json!(true));
+        }
+        for keyword in &self.keywords {
+            keywords.insert(keyword.clone(), json!(true));
+        }
 
         json!({
             "id": self.id,
@@ -50,8 +66,38 @@ impl EmailRecord {
     }
 }
 
+/// Email and mailbox objects by id, as returned by `*/get`.
+struct Snapshot {
+    emails: HashMap<String, Value>,
+    mailboxes: HashMap<String, Value>,
+}
+
 struct MockState {
     emails: HashMap<String, EmailRecord>,
+    uploads: HashMap<String, String>,
+    vacation: Value,
+    /// API calls still to be answered with 503 Service Unavailable.
+    failing_api_calls: u32,
+    /// Leave the next API call unanswered.
+    stall_next_api_call: bool,
+    /// Connections of stalled API calls, held open until the server drops.
+    stalled: Vec<std::net::TcpStream>,
+    /// Bumped on every change; `*/changes` diffs the live state against the
+    /// snapshot taken when `sinceState` was current.
+    change_seq: u64,
+    /// Emails and mailboxes as they were at each earlier `change_seq`.
+    history: HashMap<u64, Snapshot>,
+    /// Method name and arguments of every API call, in order.
+    method_log: Vec<(String, Value)>,
+    /// Mailboxes a test added to the seed ones, as (id, name, role).
+    added_mailboxes: Vec<(String, String, Option<String>)>,
+    /// Mailboxes created with `Mailbox/set`, as (id, name).
+    created_mailboxes: Vec<(String, String)>,
+    /// Capabilities left out of the session resource.
+    dropped_capabilities: Vec<String>,
+    /// Derive mailbox counts from the current emails instead of reporting
+    /// the fixed seed counts.
+    live_counts: bool,
 }
 
 impl MockState {
@@ -69,6 +115,8 @@ impl MockState {
                 received_at: "2025-01-15T10:30:00Z".to_string(),
                 mailbox_id: "mbox-inbox".to_string(),
                 is_read: true,
+                draft: false,
+                keywords: Vec::new(),
                 attachments: vec![json!({
                     "partId": "2",
                     "blobId": "blob-att-001",
@@ -87,6 +135,8 @@ impl MockState {
                 received_at: "2025-12-06T11:00:00Z".to_string(),
                 mailbox_id: "mbox-inbox".to_string(),
                 is_read: false,
+                draft: false,
+                keywords: Vec::new(),
                 attachments: vec![],
             },
             EmailRecord {
@@ -99,6 +149,8 @@ impl MockState {
                 received_at: "2025-12-20T09:00:00Z".to_string(),
                 mailbox_id: "mbox-inbox".to_string(),
                 is_read: false,
+                draft: false,
+                keywords: Vec::new(),
                 attachments: vec![],
             },
             EmailRecord {
@@ -111,6 +163,8 @@ impl MockState {
                 received_at: "2025-12-22T08:00:00Z".to_string(),
                 mailbox_id: "mbox-inbox".to_string(),
                 is_read: true,
+                draft: false,
+                keywords: Vec::new(),
                 attachments: vec![],
             },
             EmailRecord {
@@ -123,6 +177,8 @@ impl MockState {
                 received_at: "2025-11-01T08:00:00Z".to_string(),
                 mailbox_id: "mbox-archive".to_string(),
                 is_read: true,
+                draft: false,
+                keywords: Vec::new(),
                 attachments: vec![],
             },
         ];
@@ -131,11 +187,248 @@ impl MockState {
             emails.insert(e.id.clone(), e);
         }
 
-        Self { emails }
+        Self {
+            emails,
+            uploads: HashMap::new(),
+            failing_api_calls: 0,
+            stall_next_api_call: false,
+            stalled: Vec::new(),
+            change_seq: 1,
+            history: HashMap::new(),
+            method_log: Vec::new(),
+            added_mailboxes: Vec::new(),
+            created_mailboxes: Vec::new(),
+            dropped_capabilities: Vec::new(),
+            live_counts: false,
+            vacation: json!({
+                "id": "singleton",
+                "isEnabled": false,
+                "fromDate": null,
+                "toDate": null,
+                "subject": null,
+                "textBody": null,
+                "htmlBody": null
+            }),
+        }
+    }
+
+    /// Mailboxes with the seed counts, or counts derived from the current
+    /// emails once `live_counts` is set.
+    fn mailbox_list(&self) -> Vec<Value> {
+        let seeded = [
+            ("mbox-inbox", "INBOX", Some("inbox"), 1, (4, 2)),
+            ("mbox-archive", "Archive", Some("archive"), 3, (1, 0)),
+            ("mbox-trash", "Trash", Some("trash"), 4, (0, 0)),
+            ("mbox-sent", "Sent", Some("sent"), 5, (0, 0)),
+        ];
+        let added = self
+            .added_mailboxes
+            .iter()
+            .map(|(id, name, role)| (id.as_str(), name.as_str(), role.as_deref(), 6, (0, 0)));
+        let created = self
+            .created_mailboxes
+            .iter()
+            .map(|(id, name)| (id.as_str(), name.as_str(), None, 10, (0, 0)));
+        seeded
+            .into_iter()
+            .chain(added)
+            .chain(created)
+            .map(|(id, name, role, sort_order, (total, unread))| {
+                let (total, unread) = if self.live_counts {
+                    let in_mailbox = self.emails.values().filter(|e| e.mailbox_id == id);
+                    (
+                        in_mailbox.clone().count(),
+                        in_mailbox.filter(|e| !e.is_read).count(),
+                    )
+                } else {
+                    (total, unread)
+                };
+                json!({
+                    "id": id,
+                    "name": name,
+                    "role": role,
+                    "totalEmails": total,
+                    "unreadEmails": unread,
+                    "sortOrder": sort_order
+                })
+            })
+            .collect()
+    }
+
+    fn state(&self, prefix: &str) -> String {
+        format!("{}-{:03}", prefix, self.change_seq)
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            emails: self
+                .emails
+                .values()
+                .map(|e| (e.id.clone(), e.to_jmap_email()))
+                .collect(),
+            mailboxes: self
+                .mailbox_list()
+                .into_iter()
+                .map(|m| (m["id"].as_str().unwrap_or("").to_string(), m))
+                .collect(),
+        }
+    }
+
+    /// Record the current state before a change and move to the next one.
+    fn bump(&mut self) {
+        let snapshot = self.snapshot();
+        self.history.insert(self.change_seq, snapshot);
+        self.change_seq += 1;
+    }
+
+    /// `Email/changes` / `Mailbox/changes`: the ids created, updated or
+    /// destroyed since `sinceState`, or `cannotCalculateChanges` for a state
+    /// the mock never issued.
+    fn changes(&self, method: &str, prefix: &str, args: &Value, call_id: &str) -> Value {
+        let current = self.state(prefix);
+        let since = args
+            .get("sinceState")
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        let seq = since
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.strip_prefix('-'))
+            .and_then(|n| n.parse::<u64>().ok());
+        let live = self.snapshot();
+        let (before, after) = match seq {
+            Some(seq) if seq == self.change_seq => (&live, &live),
+            Some(seq) => match self.history.get(&seq) {
+                Some(before) => (before, &live),
+                None => return json!(["error", {"type": "cannotCalculateChanges"}, call_id]),
+            },
+            None => return json!(["error", {"type": "cannotCalculateChanges"}, call_id]),
+        };
+        let (before, after) = if prefix == "estate" {
+            (&before.emails, &after.emails)
+        } else {
+            (&before.mailboxes, &after.mailboxes)
+        };
+        let mut created: Vec<&String> = after
+            .keys()
+            .filter(|id| !before.contains_key(*id))
+            .collect();
+        let mut updated: Vec<&String> = after
+            .iter()
+            .filter(|(id, value)| before.get(*id).is_some_and(|old| old != *value))
+            .map(|(id, _)| id)
+            .collect();
+        let mut destroyed: Vec<&String> = before
+            .keys()
+            .filter(|id| !after.contains_key(*id))
+            .collect();
+        created.sort();
+        updated.sort();
+        destroyed.sort();
+        json!([
+            method,
+            {
+                "accountId": "account-001",
+                "oldState": since,
+                "newState": current,
+                "hasMoreChanges": false,
+                "created": created,
+                "updated": updated,
+                "destroyed": destroyed
+            },
+            call_id
+        ])
+    }
+
+    /// `Mailbox/set` create: new top-level mailboxes with no role.
+    fn apply_mailbox_set(&mut self, args: &Value) -> Value {
+        self.bump();
+        let mut created = serde_json::Map::new();
+        if let Some(create) = args.get("create").and_then(|v| v.as_object()) {
+            for (creation_id, spec) in create {
+                let id = format!("mbox-created-{}", self.created_mailboxes.len() + 1);
+                let name = spec["name"].as_str().unwrap_or("").to_string();
+                self.created_mailboxes.push((id.clone(), name));
+                created.insert(creation_id.clone(), json!({"id": id}));
+            }
+        }
+        json!({
+            "accountId": "account-001",
+            "oldState": "state-001",
+            "newState": "state-002",
+            "created": created
+        })
+    }
+
+    fn store_upload(&mut self, body: String) -> String {
+        let blob_id = format!("blob-upload-{:03}", self.uploads.len() + 1);
+        self.uploads.insert(blob_id.clone(), body);
+        blob_id
+    }
+
+    /// Import uploaded messages as new emails, parsing just enough of the
+    /// headers (From/Subject) to make them visible to Email/get.
+    fn apply_email_import(&mut self, args: &Value) -> Value {
+        self.bump();
+        let mut created = serde_json::Map::new();
+        let mut not_created = serde_json::Map::new();
+
+        if let Some(emails) = args.get("emails").and_then(|v| v.as_object()) {
+            for (creation_id, spec) in emails {
+                let blob_id = spec.get("blobId").and_then(|v| v.as_str()).unwrap_or("");
+                let Some(raw) = self.uploads.get(blob_id).cloned() else {
+                    not_created.insert(creation_id.clone(), json!({"type": "blobNotFound"}));
+                    continue;
+                };
+                let mailbox_id = spec
+                    .get("mailboxIds")
+                    .and_then(|v| v.as_object())
+                    .and_then(|m| m.keys().next().cloned())
+                    .unwrap_or_default();
+                let header = |name: &str| {
+                    raw.lines()
+                        .take_while(|l| !l.is_empty())
+                        .find_map(|l| {
+                            l.strip_prefix(name)
+                                .and_then(|rest| rest.strip_prefix(':'))
+                                .map(|v| v.trim().to_string())
+                        })
+                        .unwrap_or_default()
+                };
+                let id = format!("email-sent-{:03}", self.emails.len() + 1);
+                let record = EmailRecord {
+                    id: id.clone(),
+                    thread_id: format!("thread-{}", id),
+                    from_name: None,
+                    from_email: header("From"),
+                    subject: header("Subject"),
+                    body: raw.split("\r\n\r\n").nth(1).unwrap_or("").to_string(),
+                    received_at: "2026-01-01T00:00:00Z".to_string(),
+                    mailbox_id,
+                    is_read: true,
+                    draft: false,
+                    keywords: Vec::new(),
+                    attachments: vec![],
+                };
+                self.emails.insert(id.clone(), record);
+                created.insert(
+                    creation_id.clone(),
+                    json!({"id": id, "blobId": blob_id, "threadId": format!("thread-{}", id)}),
+                );
+            }
+        }
+
+        json!({
+            "accountId": "account-001",
+            "oldState": "estate-001",
+            "newState": "estate-002",
+            "created": created,
+            "notCreated": not_created
+        })
     }
 
     fn query_email_ids(&self, filter: &Value, limit: usize, position: usize) -> Vec<String> {
         let mut in_mailbox: Option<String> = None;
+        let mut in_thread: Option<String> = None;
         let mut text: Option<String> = None;
         let mut after: Option<String> = None;
         let mut before: Option<String> = None;
@@ -143,6 +436,7 @@ impl MockState {
         fn parse_filter(
             f: &Value,
             in_mailbox: &mut Option<String>,
+            in_thread: &mut Option<String>,
             text: &mut Option<String>,
             after: &mut Option<String>,
             before: &mut Option<String>,
@@ -151,6 +445,9 @@ impl MockState {
                 if let Some(v) = obj.get("inMailbox").and_then(|v| v.as_str()) {
                     *in_mailbox = Some(v.to_string());
                 }
+                if let Some(v) = obj.get("inThread").and_then(|v| v.as_str()) {
+                    *in_thread = Some(v.to_string());
+                }
                 if let Some(v) = obj.get("text").and_then(|v| v.as_str()) {
                     *text = Some(v.to_ascii_lowercase());
                 }
@@ -163,14 +460,21 @@ impl MockState {
                 if obj.get("operator").and_then(|v| v.as_str()) == Some("AND") {
                     if let Some(conditions) = obj.get("conditions").and_then(|v| v.as_array()) {
                         for c in conditions {
-                            parse_filter(c, in_mailbox, text, after, before);
+                            parse_filter(c, in_mailbox, in_thread, text, after, before);
                         }
                     }
                 }
             }
         }
 
-        parse_filter(filter, &mut in_mailbox, &mut text, &mut after, &mut before);
+        parse_filter(
+            filter,
+            &mut in_mailbox,
+            &mut in_thread,
+            &mut text,
+            &mut after,
+            &mut before,
+        );
 
         let mut emails: Vec<&EmailRecord> = self
             .emails
@@ -181,6 +485,11 @@ impl MockState {
                         return false;
                     }
                 }
+                if let Some(ref thread) = in_thread {
+                    if &e.thread_id != thread {
+                        return false;
+                    }
+                }
                 if let Some(ref q) = text {
                     let hay = format!(
                         "{} {} {}",
@@ -217,6 +526,9 @@ impl MockState {
     }
 
     fn apply_email_set(&mut self, args: &Value) -> Value {
+        self.bump();
+        let mut created = serde_json::Map::new();
+        let mut not_created = serde_json::Map::new();
         let mut updated = serde_json::Map::new();
         let mut not_updated = serde_json::Map::new();
         let mut not_destroyed = serde_json::Map::new();
@@ -228,8 +540,19 @@ impl MockState {
                     continue;
                 };
 
-                if let Some(seen) = patch.get("keywords/$seen") {
-                    email.is_read = seen.as_bool().unwrap_or(false);
+                for (path, value) in patch.as_object().into_iter().flatten() {
+                    let Some(keyword) = path.strip_prefix("keywords/") else {
+                        continue;
+                    };
+                    let set = value.as_bool().unwrap_or(false);
+                    if keyword == "$seen" {
+                        email.is_read = set;
+                    } else {
+                        email.keywords.retain(|k| k != keyword);
+                        if set {
+                            email.keywords.push(keyword.to_string());
+                        }
+                    }
                 }
                 if let Some(mailbox_ids) = patch.get("mailboxIds").and_then(|v| v.as_object()) {
                     if let Some((target, _)) = mailbox_ids
@@ -243,6 +566,62 @@ impl MockState {
             }
         }
 
+        // Creation only needs to cover what the send and save-draft paths
+        // produce: a text body plus attachments that reference uploaded blobs.
+        if let Some(create) = args.get("create").and_then(|v| v.as_object()) {
+            for (creation_id, spec) in create {
+                let mut attachments = Vec::new();
+                for (n, att) in spec["attachments"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .enumerate()
+                {
+                    let blob_id = att["blobId"].as_str().unwrap_or("");
+                    let Some(data) = self.uploads.get(blob_id) else {
+                        break;
+                    };
+                    attachments.push(json!({
+                        "partId": (n + 2).to_string(),
+                        "blobId": blob_id,
+                        "type": att["type"],
+                        "name": att["name"],
+                        "size": data.len()
+                    }));
+                }
+                if attachments.len() != spec["attachments"].as_array().map_or(0, |a| a.len()) {
+                    not_created.insert(creation_id.clone(), json!({"type": "blobNotFound"}));
+                    continue;
+                }
+                let id = format!("email-sent-{:03}", self.emails.len() + 1);
+                let record = EmailRecord {
+                    id: id.clone(),
+                    thread_id: format!("thread-{}", id),
+                    from_name: spec["from"][0]["name"].as_str().map(|s| s.to_string()),
+                    from_email: spec["from"][0]["email"].as_str().unwrap_or("").to_string(),
+                    subject: spec["subject"].as_str().unwrap_or("").to_string(),
+                    body: spec["bodyValues"]["body"]["value"]
+                        .as_str()
+                        .unwrap_or("")
+                        .to_string(),
+                    received_at: "2026-01-01T00:00:00Z".to_string(),
+                    mailbox_id: spec["mailboxIds"]
+                        .as_object()
+                        .and_then(|m| m.keys().next().cloned())
+                        .unwrap_or_default(),
+                    is_read: true,
+                    draft: spec["keywords"]["$draft"].as_bool().unwrap_or(false),
+                    keywords: Vec::new(),
+                    attachments,
+                };
+                self.emails.insert(id.clone(), record);
+                created.insert(
+                    creation_id.clone(),
+                    json!({"id": id, "threadId": format!("thread-{}", id)}),
+                );
+            }
+        }
+
         if let Some(destroy) = args.get("destroy").and_then(|v| v.as_array()) {
             for idv in destroy {
                 let Some(id) = idv.as_str() else {
@@ -258,6 +637,12 @@ impl MockState {
         resp.insert("accountId".to_string(), json!("account-001"));
         resp.insert("oldState".to_string(), json!("estate-001"));
         resp.insert("newState".to_string(), json!("estate-002"));
+        if !created.is_empty() {
+            resp.insert("created".to_string(), Value::Object(created));
+        }
+        if !not_created.is_empty() {
+            resp.insert("notCreated".to_string(), Value::Object(not_created));
+        }
         if !updated.is_empty() {
             resp.insert("updated".to_string(), Value::Object(updated));
         }
@@ -273,6 +658,7 @@ impl MockState {
 
 pub struct MockJmapServer {
     port: u16,
+    state: Arc<Mutex<MockState>>,
     shutdown: Arc<AtomicBool>,
     handle: Option<thread::JoinHandle<()>>,
 }
@@ -289,12 +675,14 @@ impl MockJmapServer {
             .set_nonblocking(true)
             .expect("set_nonblocking on listener");
 
+        let state_clone = state.clone();
         let handle = thread::spawn(move || {
-            Self::serve(listener, shutdown_clone, state, port);
+            Self::serve(listener, shutdown_clone, state_clone, port);
         });
 
         MockJmapServer {
             port,
+            state,
             shutdown,
             handle: Some(handle),
         }
@@ -304,6 +692,77 @@ impl MockJmapServer {
         format!("http://127.0.0.1:{}", self.port)
     }
 
+    /// Answer the next `count` API calls with 503 and `Retry-After: 0`.
+    pub fn fail_next_api_calls(&self, count: u32) {
+        self.state.lock().expect("state lock").failing_api_calls = count;
+    }
+
+    /// Never answer the next API call, so the client's request times out.
+    pub fn stall_next_api_call(&self) {
+        self.state.lock().expect("state lock").stall_next_api_call = true;
+    }
+
+    /// Report mailbox counts derived from the current emails, so changes
+    /// made with `set_read` show up in `Mailbox/get`.
+    pub fn use_live_counts(&self) {
+        self.state.lock().expect("state lock").live_counts = true;
+    }
+
+    /// Change an email's read state behind the client's back, as another
+    /// client would.
+    pub fn set_read(&self, id: &str, read: bool) {
+        let mut guard = self.state.lock().expect("state lock");
+        guard.bump();
+        if let Some(email) = guard.emails.get_mut(id) {
+            email.is_read = read;
+        }
+    }
+
+    /// Add an unread inbox copy of `like` as a new message `id` received at
+    /// `received_at`, as a reply arriving in its thread would.
+    pub fn deliver_reply(&self, like: &str, id: &str, received_at: &str) {
+        let mut guard = self.state.lock().expect("state lock");
+        guard.bump();
+        let mut email = guard.emails.get(like).expect("email to reply to").clone();
+        email.id = id.to_string();
+        email.received_at = received_at.to_string();
+        email.mailbox_id = "mbox-inbox".to_string();
+        email.is_read = false;
+        guard.emails.insert(id.to_string(), email);
+    }
+
+    /// Add an empty mailbox to the seed ones, e.g. a Drafts folder.
+    pub fn add_mailbox(&self, id: &str, name: &str, role: Option<&str>) {
+        let mut guard = self.state.lock().expect("state lock");
+        guard.bump();
+        guard
+            .added_mailboxes
+            .push((id.to_string(), name.to_string(), role.map(str::to_string)));
+    }
+
+    /// Leave `capability` out of the session, as a server without it would.
+    pub fn drop_capability(&self, capability: &str) {
+        self.state
+            .lock()
+            .expect("state lock")
+            .dropped_capabilities
+            .push(capability.to_string());
+    }
+
+    /// Method names of the API calls made since the last call to this.
+    pub fn take_method_calls(&self) -> Vec<String> {
+        self.take_calls()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    /// Method names and arguments of the API calls made since the last call
+    /// to this or `take_method_calls`.
+    pub fn take_calls(&self) -> Vec<(String, Value)> {
+        std::mem::take(&mut self.state.lock().expect("state lock").method_log)
+    }
+
     fn serve(
         listener: TcpListener,
         shutdown: Arc<AtomicBool>,
@@ -342,6 +801,7 @@ impl MockJmapServer {
         }
 
         let mut content_length: usize = 0;
+        let mut authorization = String::new();
         loop {
             let mut header = String::new();
             if reader.read_line(&mut header).is_err() {
@@ -361,6 +821,11 @@ impl MockJmapServer {
                     content_length = len;
                 }
             }
+            if let Some((name, val)) = trimmed.split_once(':') {
+                if name.eq_ignore_ascii_case("authorization") {
+                    authorization = val.trim().to_string();
+                }
+            }
         }
 
         let body = if content_length > 0 {
@@ -380,36 +845,78 @@ impl MockJmapServer {
         let method = parts[0];
         let path = parts[1];
 
-        let (status, response_body, content_type) =
-            if method == "GET" && path.contains("/.well-known/jmap") {
-                let (s, b) = Self::handle_session(port);
-                (s, b, "application/json")
-            } else if method == "POST" && path.contains("/api") {
-                let (s, b) = Self::handle_api(&body, state);
-                (s, b, "application/json")
-            } else if method == "GET" && path.starts_with("/download/") {
-                let (s, b) = Self::handle_download(path);
-                (s, b, "application/octet-stream")
-            } else {
-                (
-                    "404 Not Found".to_string(),
-                    json!({"error": "not found"}).to_string(),
-                    "application/json",
-                )
+        if method == "POST" && path.contains("/api") {
+            let mut guard = state.lock().expect("state lock");
+            if std::mem::take(&mut guard.stall_next_api_call) {
+                guard.stalled.push(stream);
+                return;
+            }
+        }
+        let fail_api = method == "POST" && path.contains("/api") && {
+            let mut guard = state.lock().expect("state lock");
+            let fail = guard.failing_api_calls > 0;
+            guard.failing_api_calls = guard.failing_api_calls.saturating_sub(1);
+            fail
+        };
+        let (status, response_body, content_type) = if fail_api {
+            (
+                "503 Service Unavailable".to_string(),
+                json!({"error": "try again"}).to_string().into_bytes(),
+                "application/json",
+            )
+        } else if authorization == format!("Bearer {}", EXPIRED_TOKEN) {
+            (
+                "401 Unauthorized".to_string(),
+                json!({"error": "token expired"}).to_string().into_bytes(),
+                "application/json",
+            )
+        } else if method == "GET" && path.contains("/.well-known/jmap") {
+            let (s, b) = Self::handle_session(port, state);
+            (s, b.into_bytes(), "application/json")
+        } else if method == "POST" && path.contains("/api") {
+            let (s, b) = Self::handle_api(&body, state);
+            (s, b.into_bytes(), "application/json")
+        } else if method == "POST" && path.starts_with("/upload/") {
+            let blob_id = {
+                let mut guard = state.lock().expect("state lock");
+                guard.store_upload(body.clone())
             };
+            let resp = json!({
+                "accountId": "account-001",
+                "blobId": blob_id,
+                "type": "message/rfc822",
+                "size": body.len()
+            });
+            (
+                "201 Created".to_string(),
+                resp.to_string().into_bytes(),
+                "application/json",
+            )
+        } else if method == "GET" && path.starts_with("/download/") {
+            let (s, b) = Self::handle_download(path);
+            (s, b, "application/octet-stream")
+        } else {
+            (
+                "404 Not Found".to_string(),
+                json!({"error": "not found"}).to_string().into_bytes(),
+                "application/json",
+            )
+        };
 
-        let response = format!(
-            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        let mut response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n",
             status,
             content_type,
             response_body.len(),
-            response_body
-        );
-        let _ = stream.write_all(response.as_bytes());
+            if fail_api { "Retry-After: 0\r\n" } else { "" },
+        )
+        .into_bytes();
+        response.extend_from_slice(&response_body);
+        let _ = stream.write_all(&response);
         let _ = stream.flush();
     }
 
-    fn handle_download(path: &str) -> (String, String) {
+    fn handle_download(path: &str) -> (String, Vec<u8>) {
         // Path format: /download/{accountId}/{blobId}/{name}?type={type}
         let path_no_query = path.split('?').next().unwrap_or(path);
         let segments: Vec<&str> = path_no_query.split('/').collect();
@@ -417,31 +924,41 @@ impl MockJmapServer {
         let blob_id = segments.get(3).copied().unwrap_or("");
         if blob_id == "blob-att-001" {
             let fake_pdf_content = b"%PDF-1.4 fake test content for blob-att-001";
-            (
-                "200 OK".to_string(),
-                String::from_utf8_lossy(fake_pdf_content).to_string(),
-            )
+            ("200 OK".to_string(), fake_pdf_content.to_vec())
         } else if let Some(email_id) = blob_id.strip_prefix("blob-raw-") {
             // Synthesize a raw RFC822 message for the spam classifier to tokenize.
-            let raw = format!(
+            let mut raw = format!(
                 "From: sender-{id}@example.com\r\n\
                  To: test@example.com\r\n\
                  Subject: raw message for {id}\r\n\
                  \r\n\
                  This is the raw body of {id} with words to tokenize.\r\n",
                 id = email_id
-            );
+            )
+            .into_bytes();
+            if email_id == LATIN1_EMAIL_ID {
+                // An 8-bit ISO-8859-1 line, which is not valid UTF-8.
+                raw.extend_from_slice(b"Caf\xe9 cr\xe8me\r\n");
+            }
             ("200 OK".to_string(), raw)
         } else {
-            ("404 Not Found".to_string(), "blob not found".to_string())
+            ("404 Not Found".to_string(), b"blob not found".to_vec())
         }
     }
 
-    fn handle_session(port: u16) -> (String, String) {
-        let session = json!({
+    fn handle_session(port: u16, state: &Arc<Mutex<MockState>>) -> (String, String) {
+        let mut session = json!({
             "username": "test@example.com",
             "apiUrl": format!("http://127.0.0.1:{}/api", port),
             "downloadUrl": format!("http://127.0.0.1:{}/download/{{accountId}}/{{blobId}}/{{name}}?type={{type}}", port),
+            "uploadUrl": format!("http://127.0.0.1:{}/upload/{{accountId}}/", port),
+            "capabilities": {
+                "urn:ietf:params:jmap:core": {},
+                "urn:ietf:params:jmap:mail": {},
+                "urn:ietf:params:jmap:submission": {},
+                "urn:ietf:params:jmap:vacationresponse": {},
+                "urn:ietf:params:jmap:quota": {}
+            },
             "primaryAccounts": {
                 "urn:ietf:params:jmap:mail": "account-001"
             },
@@ -453,6 +970,16 @@ impl MockJmapServer {
                 }
             }
         });
+        let dropped = state
+            .lock()
+            .expect("state lock")
+            .dropped_capabilities
+            .clone();
+        if let Some(capabilities) = session["capabilities"].as_object_mut() {
+            for capability in dropped {
+                capabilities.remove(&capability);
+            }
+        }
         ("200 OK".to_string(), session.to_string())
     }
 
@@ -487,43 +1014,49 @@ impl MockJmapServer {
             let method_name = arr[0].as_str().unwrap_or("");
             let args = &arr[1];
             let call_id = arr[2].as_str().unwrap_or("0");
+            state
+                .lock()
+                .expect("state lock")
+                .method_log
+                .push((method_name.to_string(), args.clone()));
 
             let response = match method_name {
-                "Mailbox/get" => json!([
-                    "Mailbox/get",
-                    {
-                        "accountId": "account-001",
-                        "state": "state-001",
-                        "list": [
-                            {
-                                "id": "mbox-inbox",
-                                "name": "INBOX",
-                                "role": "inbox",
-                                "totalEmails": 4,
-                                "unreadEmails": 2,
-                                "sortOrder": 1
-                            },
-                            {
-                                "id": "mbox-archive",
-                                "name": "Archive",
-                                "role": "archive",
-                                "totalEmails": 1,
-                                "unreadEmails": 0,
-                                "sortOrder": 3
-                            },
-                            {
-                                "id": "mbox-trash",
-                                "name": "Trash",
-                                "role": "trash",
-                                "totalEmails": 0,
-                                "unreadEmails": 0,
-                                "sortOrder": 4
-                            }
-                        ],
-                        "notFound": []
-                    },
-                    call_id
-                ]),
+                "Mailbox/get" => {
+                    let (list, mailbox_state) = {
+                        let guard = state.lock().expect("state lock");
+                        let ids: Option<Vec<&str>> = args
+                            .get("ids")
+                            .and_then(|v| v.as_array())
+                            .map(|ids| ids.iter().filter_map(|id| id.as_str()).collect());
+                        let list: Vec<Value> = guard
+                            .mailbox_list()
+                            .into_iter()
+                            .filter(|m| {
+                                ids.as_ref()
+                                    .is_none_or(|ids| ids.contains(&m["id"].as_str().unwrap_or("")))
+                            })
+                            .collect();
+                        (list, guard.state("state"))
+                    };
+                    json!([
+                        "Mailbox/get",
+                        {
+                            "accountId": "account-001",
+                            "state": mailbox_state,
+                            "list": list,
+                            "notFound": []
+                        },
+                        call_id
+                    ])
+                }
+                "Mailbox/changes" => {
+                    let guard = state.lock().expect("state lock");
+                    guard.changes("Mailbox/changes", "state", args, call_id)
+                }
+                "Email/changes" => {
+                    let guard = state.lock().expect("state lock");
+                    guard.changes("Email/changes", "estate", args, call_id)
+                }
                 "Email/query" => {
                     let filter = args.get("filter").cloned().unwrap_or_else(|| json!({}));
                     let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(50) as usize;
@@ -552,9 +1085,9 @@ impl MockJmapServer {
                         .map(|arr| arr.iter().filter_map(|v| v.as_str()).collect::<Vec<_>>())
                         .unwrap_or_default();
 
-                    let list = {
+                    let (list, email_state) = {
                         let guard = state.lock().expect("state lock");
-                        if requested_ids.is_empty() {
+                        let list = if requested_ids.is_empty() {
                             guard
                                 .emails
                                 .values()
@@ -567,20 +1100,28 @@ impl MockJmapServer {
                                     guard.emails.get(id).map(EmailRecord::to_jmap_email)
                                 })
                                 .collect::<Vec<_>>()
-                        }
+                        };
+                        (list, guard.state("estate"))
                     };
 
                     json!([
                         "Email/get",
                         {
                             "accountId": "account-001",
-                            "state": "estate-001",
+                            "state": email_state,
                             "list": list,
                             "notFound": []
                         },
                         call_id
                     ])
                 }
+                "Mailbox/set" => {
+                    let payload = {
+                        let mut guard = state.lock().expect("state lock");
+                        guard.apply_mailbox_set(args)
+                    };
+                    json!(["Mailbox/set", payload, call_id])
+                }
                 "Email/set" => {
                     let payload = {
                         let mut guard = state.lock().expect("state lock");
@@ -588,6 +1129,125 @@ impl MockJmapServer {
                     };
                     json!(["Email/set", payload, call_id])
                 }
+                "Email/import" => {
+                    let payload = {
+                        let mut guard = state.lock().expect("state lock");
+                        guard.apply_email_import(args)
+                    };
+                    json!(["Email/import", payload, call_id])
+                }
+                "Quota/get" => {
+                    let used: usize = {
+                        let guard = state.lock().expect("state lock");
+                        guard.emails.values().map(|e| e.body.len()).sum()
+                    };
+                    json!([
+                        "Quota/get",
+                        {
+                            "accountId": "account-001",
+                            "state": "qstate-001",
+                            "list": [
+                                {
+                                    "id": "quota-mail",
+                                    "name": "Mail storage",
+                                    "resourceType": "octets",
+                                    "scope": "account",
+                                    "used": used,
+                                    "hardLimit": 1_073_741_824u64,
+                                    "types": ["Mail"]
+                                },
+                                {
+                                    "id": "quota-count",
+                                    "resourceType": "count",
+                                    "scope": "account",
+                                    "used": 0,
+                                    "hardLimit": 100_000,
+                                    "types": ["Mail"]
+                                }
+                            ],
+                            "notFound": []
+                        },
+                        call_id
+                    ])
+                }
+                "VacationResponse/get" => {
+                    let vacation = {
+                        let guard = state.lock().expect("state lock");
+                        guard.vacation.clone()
+                    };
+                    json!([
+                        "VacationResponse/get",
+                        {
+                            "accountId": "account-001",
+                            "state": "vstate-001",
+                            "list": [vacation],
+                            "notFound": []
+                        },
+                        call_id
+                    ])
+                }
+                "VacationResponse/set" => {
+                    let patch = args
+                        .get("update")
+                        .and_then(|u| u.get("singleton"))
+                        .and_then(|p| p.as_object())
+                        .cloned()
+                        .unwrap_or_default();
+                    {
+                        let mut guard = state.lock().expect("state lock");
+                        for (key, value) in patch {
+                            guard.vacation[key] = value;
+                        }
+                    }
+                    json!([
+                        "VacationResponse/set",
+                        {
+                            "accountId": "account-001",
+                            "oldState": "vstate-001",
+                            "newState": "vstate-002",
+                            "updated": {"singleton": null}
+                        },
+                        call_id
+                    ])
+                }
+                "Identity/get" => json!([
+                    "Identity/get",
+                    {
+                        "accountId": "account-001",
+                        "state": "istate-001",
+                        "list": [
+                            {
+                                "id": "identity-001",
+                                "name": "Test User",
+                                "email": "test@example.com"
+                            }
+                        ],
+                        "notFound": []
+                    },
+                    call_id
+                ]),
+                "EmailSubmission/set" => {
+                    let created = args
+                        .get("create")
+                        .and_then(|v| v.as_object())
+                        .map(|create| {
+                            create
+                                .keys()
+                                .map(|k| (k.clone(), json!({"id": format!("sub-{}", k)})))
+                                .collect::<serde_json::Map<_, _>>()
+                        })
+                        .unwrap_or_default();
+                    json!([
+                        "EmailSubmission/set",
+                        {
+                            "accountId": "account-001",
+                            "oldState": "sstate-001",
+                            "newState": "sstate-002",
+                            "created": created
+                        },
+                        call_id
+                    ])
+                }
                 "Thread/get" => json!([
                     "Thread/get",
                     {